@@ -2,7 +2,7 @@
 //!
 //! 定义所有命令行接口的结构和枚举
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 /// CLI 版本信息
@@ -22,12 +22,21 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub verbose: bool,
 
+    /// 额外加载的配置文件，覆盖用户和项目配置
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// 覆盖配置项，优先级最高 (可重复，如 --set executor.max_concurrent=4)
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    pub overrides: Vec<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
 /// 子命令枚举
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// 执行命令
     Run {
@@ -70,6 +79,18 @@ pub enum Commands {
         #[command(subcommand)]
         action: ConfigAction,
     },
+
+    /// Model Context Protocol 服务
+    Mcp {
+        #[command(subcommand)]
+        action: McpAction,
+    },
+
+    /// LLM 工具调用策略：确认请求和审计日志
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
 }
 
 /// Schedule 子命令
@@ -93,6 +114,33 @@ pub enum ScheduleAction {
         /// 创建系统级任务调度器 (Windows schtasks / macOS launchd / Linux cron)
         #[arg(short, long)]
         system: bool,
+        /// 错过运行的处理策略 (skip, run_once, run_all:<N>)
+        #[arg(long)]
+        misfire: Option<String>,
+        /// 错过运行的宽限期 (秒)
+        #[arg(long)]
+        grace: Option<u64>,
+        /// 失败时最多尝试次数 (含首次运行)
+        #[arg(long)]
+        max_attempts: Option<u32>,
+        /// 重试间隔 (fixed:<秒>, exponential:<初始秒>[:<最大秒>[:<倍数>]])
+        #[arg(long)]
+        backoff: Option<String>,
+        /// 需要重试的失败类型 (exit, timeout, error, all，逗号分隔)
+        #[arg(long)]
+        retry_on: Option<String>,
+        /// 依赖的任务 (格式: <任务 ID>[:<窗口秒数>]，可重复)
+        #[arg(long = "depends-on")]
+        depends_on: Vec<String>,
+        /// 上一次运行未结束时的处理策略 (allow, skip, queue, replace)
+        #[arg(long)]
+        overlap: Option<String>,
+        /// 任务标签，用于按标签限制并发 (可重复)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// 解释 Cron 表达式所用的 IANA 时区 (如 Asia/Shanghai)，默认使用 scheduler.timezone 配置
+        #[arg(long)]
+        timezone: Option<String>,
     },
     /// 列出所有定时任务
     List {
@@ -149,6 +197,9 @@ pub enum ScheduleAction {
         /// 日志级别 (debug, info, warn, error)
         #[arg(short, long)]
         level: Option<String>,
+        /// 持续输出新日志，直到运行结束
+        #[arg(short, long)]
+        follow: bool,
     },
     /// 获取任务简报
     Status {
@@ -171,6 +222,39 @@ pub enum ScheduleAction {
         /// 新 Cron 表达式
         #[arg(short, long)]
         cron: Option<String>,
+        /// 错过运行的处理策略 (skip, run_once, run_all:<N>)
+        #[arg(long)]
+        misfire: Option<String>,
+        /// 错过运行的宽限期 (秒)
+        #[arg(long)]
+        grace: Option<u64>,
+        /// 失败时最多尝试次数 (含首次运行)
+        #[arg(long)]
+        max_attempts: Option<u32>,
+        /// 重试间隔 (fixed:<秒>, exponential:<初始秒>[:<最大秒>[:<倍数>]])
+        #[arg(long)]
+        backoff: Option<String>,
+        /// 需要重试的失败类型 (exit, timeout, error, all，逗号分隔)
+        #[arg(long)]
+        retry_on: Option<String>,
+        /// 依赖的任务，替换现有依赖 (格式: <任务 ID>[:<窗口秒数>]，可重复)
+        #[arg(long = "depends-on")]
+        depends_on: Vec<String>,
+        /// 清除所有依赖
+        #[arg(long, conflicts_with = "depends_on")]
+        clear_depends_on: bool,
+        /// 上一次运行未结束时的处理策略 (allow, skip, queue, replace)
+        #[arg(long)]
+        overlap: Option<String>,
+        /// 任务标签，替换现有标签 (可重复)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// 清除所有标签
+        #[arg(long, conflicts_with = "tags")]
+        clear_tags: bool,
+        /// 新的 IANA 时区，设为 UTC 时按 UTC 调度
+        #[arg(long)]
+        timezone: Option<String>,
     },
     /// 销毁任务
     Destroy {
@@ -182,6 +266,91 @@ pub enum ScheduleAction {
         /// 任务 ID
         id: String,
     },
+    /// 按保留策略清理运行记录和日志，未指定的限制使用配置中的保留策略
+    Prune {
+        /// 清理结束超过指定天数的运行，0 表示不限制
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+        /// 每个任务保留的最近运行数，0 表示不限制
+        #[arg(long, value_name = "N")]
+        keep: Option<usize>,
+        /// 运行记录和日志的总大小上限 (MB)，0 表示不限制
+        #[arg(long, value_name = "MB")]
+        max_size: Option<u64>,
+        /// 只显示将清理的内容，不实际删除
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 导出任务到迁移包 (JSON Lines)
+    Export {
+        /// 输出文件，默认输出到标准输出
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// 只导出指定任务 (可重复)
+        #[arg(long = "task", value_name = "ID")]
+        tasks: Vec<String>,
+        /// 同时导出运行实例和日志
+        #[arg(long)]
+        history: bool,
+    },
+    /// 从迁移包导入任务
+    Import {
+        /// 迁移包文件，`-` 表示标准输入
+        file: std::path::PathBuf,
+        /// 与现有任务 ID 或名称相同时的处理方式 (skip, overwrite, rename)
+        #[arg(long, default_value = "skip")]
+        on_conflict: String,
+        /// 为导入的任务、运行实例和日志生成新 ID
+        #[arg(long)]
+        remap_ids: bool,
+        /// 不导入运行历史
+        #[arg(long)]
+        no_history: bool,
+        /// 重新创建系统级任务，否则系统级任务作为内置任务导入
+        #[arg(short, long)]
+        system: bool,
+        /// 只显示导入计划，不实际导入
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 按任务清单 (TOML / YAML) 新建、更新和删除任务
+    Apply {
+        /// 清单文件，`.yaml` / `.yml` 按 YAML 解析，其余按 TOML 解析
+        #[arg(short, long)]
+        file: std::path::PathBuf,
+        /// 删除清单中没有的任务 (系统级任务除外)
+        #[arg(long)]
+        prune: bool,
+        /// 跳过确认
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// 显示任务清单与现有任务的差异
+    Diff {
+        /// 清单文件
+        #[arg(short, long)]
+        file: std::path::PathBuf,
+        /// 同时显示将被删除的任务
+        #[arg(long)]
+        prune: bool,
+    },
+    /// 校验任务清单
+    Validate {
+        /// 清单文件
+        #[arg(short, long)]
+        file: std::path::PathBuf,
+    },
+    /// 预览 Cron 表达式接下来的触发时间
+    Preview {
+        /// Cron 表达式 (5/6/7 字段或 @daily 等宏)
+        expression: String,
+        /// 显示的次数
+        #[arg(short = 'n', long, default_value = "10")]
+        count: usize,
+        /// 解释 Cron 表达式所用的 IANA 时区，默认使用 scheduler.timezone 配置
+        #[arg(long)]
+        timezone: Option<String>,
+    },
     /// 清空所有定时任务
     Clear {
         /// 同时清空系统级任务
@@ -191,6 +360,11 @@ pub enum ScheduleAction {
         #[arg(short, long)]
         force: bool,
     },
+    /// 工作流管理
+    Workflow {
+        #[command(subcommand)]
+        action: WorkflowAction,
+    },
     /// 守护进程管理
     Daemon {
         #[command(subcommand)]
@@ -198,6 +372,36 @@ pub enum ScheduleAction {
     },
 }
 
+/// Workflow 子命令
+#[derive(Subcommand, Debug)]
+pub enum WorkflowAction {
+    /// 从 JSON 定义文件添加工作流
+    Add {
+        /// 工作流定义文件 (name, description, cron, steps)
+        file: std::path::PathBuf,
+    },
+    /// 列出所有工作流
+    List,
+    /// 手动运行工作流，等待所有步骤结束
+    Run {
+        /// 工作流 ID
+        id: String,
+        /// 传给每个步骤的参数 (格式: key=value)
+        #[arg(short, long)]
+        user: Vec<String>,
+    },
+    /// 查看工作流简报和最近运行的步骤状态
+    Status {
+        /// 工作流 ID
+        id: String,
+    },
+    /// 删除工作流
+    Remove {
+        /// 工作流 ID
+        id: String,
+    },
+}
+
 /// Daemon 子命令
 #[derive(Subcommand, Debug, Clone)]
 pub enum DaemonAction {
@@ -267,21 +471,113 @@ pub enum VoiceAction {
 }
 
 /// Config 子命令
+///
+/// 配置按 `~/.config/sker/config.toml`、项目目录的 `.sker.toml`、`--config` 文件、
+/// `SKER_*` 环境变量、`--set` 的顺序合并，后者覆盖前者
 #[derive(Subcommand, Debug)]
 pub enum ConfigAction {
-    /// 显示当前配置
+    /// 显示当前生效的配置
     Show,
-    /// 设置配置项
+    /// 查看配置项的生效值
+    Get {
+        /// 配置键，如 executor.max_concurrent
+        key: String,
+    },
+    /// 设置配置项并写入配置文件
     Set {
         /// 配置键
         key: String,
-        /// 配置值
+        /// 配置值，非字符串项按 TOML 解析 (如 true、4、["a", "b"])
         value: String,
+        /// 写入项目配置文件而不是用户配置文件
+        #[arg(long)]
+        project: bool,
+    },
+    /// 从配置文件中删除配置项
+    Unset {
+        /// 配置键
+        key: String,
+        /// 修改项目配置文件而不是用户配置文件
+        #[arg(long)]
+        project: bool,
+    },
+    /// 用编辑器打开配置文件，保存后校验
+    Edit {
+        /// 编辑项目配置文件而不是用户配置文件
+        #[arg(long)]
+        project: bool,
+    },
+    /// 显示各层配置的位置
+    Path,
+}
+
+/// Mcp 子命令
+#[derive(Subcommand, Debug)]
+pub enum McpAction {
+    /// 以 MCP 服务端提供工具，默认使用 stdio 传输
+    Serve {
+        /// 改用 streamable HTTP 传输并监听该地址 (如 127.0.0.1:8765)，非本机地址需要设置 mcp.token
+        #[arg(long)]
+        http: Option<String>,
+        /// HTTP 端点路径
+        #[arg(long, default_value = "/mcp", requires = "http")]
+        path: String,
+        /// 只提供指定的工具组 (逗号分隔)，默认提供全部
+        #[arg(long, value_enum, value_delimiter = ',')]
+        tools: Vec<ToolGroup>,
+    },
+}
+
+/// 可通过 MCP 提供的工具组
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToolGroup {
+    /// 定时任务
+    Scheduler,
+    /// 命令执行
+    Command,
+    /// 电源管理
+    Power,
+    /// 语音合成与识别
+    Voice,
+    /// 文件监控
+    Filesystem,
+}
+
+/// Policy 子命令
+#[derive(Subcommand, Debug)]
+pub enum PolicyAction {
+    /// 列出等待确认的工具调用
+    Pending,
+    /// 批准一个工具调用，调用方重试后执行
+    Approve {
+        /// 确认请求 ID
+        id: String,
+    },
+    /// 拒绝一个工具调用
+    Reject {
+        /// 确认请求 ID
+        id: String,
+    },
+    /// 查看最近的审计记录
+    Audit {
+        /// 显示条数
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: usize,
+    },
+    /// 检查策略对一次调用的决定 (不执行)
+    Check {
+        /// 工具名称
+        tool: String,
+        /// JSON 格式的参数
+        #[arg(long, default_value = "{}")]
+        args: String,
     },
 }
 
 /// 初始化日志系统
-pub fn init_logging(verbose: bool) {
+///
+/// 日志写到标准错误，标准输出留给命令输出 (如 MCP 的 stdio 传输)
+pub fn init_logging(verbose: bool, colors: bool) {
     let filter_level = if verbose { "debug" } else { "info" };
 
     tracing_subscriber::fmt()
@@ -289,6 +585,8 @@ pub fn init_logging(verbose: bool) {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(filter_level)),
         )
+        .with_writer(std::io::stderr)
+        .with_ansi(colors)
         .with_target(false)
         .with_thread_ids(false)
         .init();
@@ -334,11 +632,11 @@ mod tests {
     fn test_schedule_add_parsing() {
         let cli = Cli::try_parse_from([
             "cli", "schedule", "add", "* * * * *", "echo hello", "-d", "Test task", "-t",
-            "My Title", "--content", "echo test",
+            "My Title", "--content", "echo test", "--timezone", "Asia/Shanghai",
         ]);
         assert!(cli.is_ok());
         if let Commands::Schedule {
-            action: ScheduleAction::Add { cron, command, title, description, content, system: _ },
+            action: ScheduleAction::Add { cron, command, title, description, content, timezone, .. },
         } = cli.unwrap().command
         {
             assert_eq!(cron, "* * * * *");
@@ -346,6 +644,7 @@ mod tests {
             assert_eq!(description, Some("Test task".to_string()));
             assert_eq!(title, Some("My Title".to_string()));
             assert_eq!(content, Some("echo test".to_string()));
+            assert_eq!(timezone.as_deref(), Some("Asia/Shanghai"));
         } else {
             panic!("Expected Schedule Add command");
         }
@@ -384,16 +683,33 @@ mod tests {
         let cli = Cli::try_parse_from(["cli", "config", "set", "cli.prompt", "$"]);
         assert!(cli.is_ok());
         if let Commands::Config {
-            action: ConfigAction::Set { key, value },
+            action: ConfigAction::Set { key, value, project },
         } = cli.unwrap().command
         {
             assert_eq!(key, "cli.prompt");
             assert_eq!(value, "$");
+            assert!(!project);
         } else {
             panic!("Expected Config Set command");
         }
     }
 
+    #[test]
+    fn test_config_overrides_parsing() {
+        let cli = Cli::try_parse_from([
+            "cli", "schedule", "list", "--config", "ci.toml", "--set", "executor.max_concurrent=2", "--set", "voice.enabled=true",
+        ])
+        .unwrap();
+        assert_eq!(cli.config, Some(PathBuf::from("ci.toml")));
+        assert_eq!(cli.overrides, vec!["executor.max_concurrent=2", "voice.enabled=true"]);
+
+        let cli = Cli::try_parse_from(["cli", "config", "unset", "executor.shell", "--project"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Config { action: ConfigAction::Unset { ref key, project: true } } if key == "executor.shell"
+        ));
+    }
+
     #[test]
     fn test_schedule_run_with_run_id() {
         // 测试 --run-id 参数 (系统任务调用格式)
@@ -515,4 +831,175 @@ mod tests {
             panic!("Expected Schedule Clear command");
         }
     }
+
+    #[test]
+    fn test_schedule_prune_parsing() {
+        let cli = Cli::try_parse_from(["cli", "schedule", "prune", "--keep", "10", "--dry-run"]).unwrap();
+        if let Commands::Schedule {
+            action: ScheduleAction::Prune { older_than, keep, max_size, dry_run },
+        } = cli.command
+        {
+            assert_eq!(older_than, None);
+            assert_eq!(keep, Some(10));
+            assert_eq!(max_size, None);
+            assert!(dry_run);
+        } else {
+            panic!("Expected Schedule Prune command");
+        }
+    }
+
+    #[test]
+    fn test_schedule_export_import_parsing() {
+        let cli = Cli::try_parse_from(["cli", "schedule", "export", "-o", "tasks.jsonl", "--history"]).unwrap();
+        if let Commands::Schedule {
+            action: ScheduleAction::Export { output, tasks, history },
+        } = cli.command
+        {
+            assert_eq!(output, Some(std::path::PathBuf::from("tasks.jsonl")));
+            assert!(tasks.is_empty());
+            assert!(history);
+        } else {
+            panic!("Expected Schedule Export command");
+        }
+
+        let cli = Cli::try_parse_from([
+            "cli", "schedule", "import", "tasks.jsonl", "--on-conflict", "rename", "--remap-ids", "--dry-run",
+        ])
+        .unwrap();
+        if let Commands::Schedule {
+            action: ScheduleAction::Import { file, on_conflict, remap_ids, no_history, system, dry_run },
+        } = cli.command
+        {
+            assert_eq!(file, std::path::PathBuf::from("tasks.jsonl"));
+            assert_eq!(on_conflict, "rename");
+            assert!(remap_ids && dry_run);
+            assert!(!no_history && !system);
+        } else {
+            panic!("Expected Schedule Import command");
+        }
+    }
+
+    #[test]
+    fn test_schedule_manifest_parsing() {
+        let cli = Cli::try_parse_from(["cli", "schedule", "apply", "-f", "tasks.toml", "--prune", "-y"]).unwrap();
+        if let Commands::Schedule {
+            action: ScheduleAction::Apply { file, prune, yes },
+        } = cli.command
+        {
+            assert_eq!(file, std::path::PathBuf::from("tasks.toml"));
+            assert!(prune && yes);
+        } else {
+            panic!("Expected Schedule Apply command");
+        }
+
+        let cli = Cli::try_parse_from(["cli", "schedule", "diff", "--file", "tasks.yaml"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Schedule { action: ScheduleAction::Diff { prune: false, .. } }
+        ));
+        assert!(Cli::try_parse_from(["cli", "schedule", "validate"]).is_err());
+    }
+
+    #[test]
+    fn test_schedule_preview_parsing() {
+        let cli = Cli::try_parse_from(["sker", "schedule", "preview", "0 9 * * MON-FRI", "-n", "5"]).unwrap();
+        if let Commands::Schedule {
+            action: ScheduleAction::Preview { expression, count, timezone },
+        } = cli.command
+        {
+            assert_eq!(expression, "0 9 * * MON-FRI");
+            assert_eq!(count, 5);
+            assert_eq!(timezone, None);
+        } else {
+            panic!("Expected Schedule Preview command");
+        }
+
+        let cli = Cli::try_parse_from(["sker", "schedule", "preview", "@daily", "--timezone", "Asia/Shanghai"]).unwrap();
+        if let Commands::Schedule {
+            action: ScheduleAction::Preview { count, timezone, .. },
+        } = cli.command
+        {
+            assert_eq!(count, 10);
+            assert_eq!(timezone.as_deref(), Some("Asia/Shanghai"));
+        } else {
+            panic!("Expected Schedule Preview command");
+        }
+    }
+
+    #[test]
+    fn test_schedule_workflow_run_parsing() {
+        // 测试工作流运行命令
+        let cli = Cli::try_parse_from([
+            "cli", "schedule", "workflow", "run", "550e8400-e29b-41d4-a716-446655440000",
+            "-u", "date=2024-01-01",
+        ]);
+        assert!(cli.is_ok());
+        if let Commands::Schedule {
+            action: ScheduleAction::Workflow {
+                action: WorkflowAction::Run { id, user },
+            },
+        } = cli.unwrap().command
+        {
+            assert_eq!(id, "550e8400-e29b-41d4-a716-446655440000");
+            assert_eq!(user, vec!["date=2024-01-01"]);
+        } else {
+            panic!("Expected Schedule Workflow Run command");
+        }
+    }
+
+    #[test]
+    fn test_policy_parsing() {
+        let cli = Cli::try_parse_from(["cli", "policy", "approve", "0123abcd"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Policy { action: PolicyAction::Approve { ref id } } if id == "0123abcd"
+        ));
+
+        let cli = Cli::try_parse_from(["cli", "policy", "check", "add_task", "--args", r#"{"content":"ls"}"#]).unwrap();
+        if let Commands::Policy {
+            action: PolicyAction::Check { tool, args },
+        } = cli.command
+        {
+            assert_eq!(tool, "add_task");
+            assert_eq!(args, r#"{"content":"ls"}"#);
+        } else {
+            panic!("Expected Policy Check command");
+        }
+
+        let cli = Cli::try_parse_from(["cli", "policy", "audit"]).unwrap();
+        assert!(matches!(cli.command, Commands::Policy { action: PolicyAction::Audit { limit: 20 } }));
+    }
+
+    #[test]
+    fn test_mcp_serve_parsing() {
+        let cli = Cli::try_parse_from(["cli", "mcp", "serve"]).unwrap();
+        if let Commands::Mcp {
+            action: McpAction::Serve { http, path, tools },
+        } = cli.command
+        {
+            assert!(http.is_none());
+            assert_eq!(path, "/mcp");
+            assert!(tools.is_empty());
+        } else {
+            panic!("Expected Mcp Serve command");
+        }
+
+        let cli = Cli::try_parse_from(["cli", "mcp", "serve", "--http", "127.0.0.1:8765"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Mcp { action: McpAction::Serve { http: Some(_), .. } }
+        ));
+        assert!(Cli::try_parse_from(["cli", "mcp", "serve", "--path", "/x"]).is_err());
+
+        let cli = Cli::try_parse_from(["cli", "mcp", "serve", "--tools", "scheduler,filesystem"]).unwrap();
+        if let Commands::Mcp {
+            action: McpAction::Serve { tools, .. },
+        } = cli.command
+        {
+            assert_eq!(tools, vec![ToolGroup::Scheduler, ToolGroup::Filesystem]);
+        } else {
+            panic!("Expected Mcp Serve command");
+        }
+        assert!(Cli::try_parse_from(["cli", "mcp", "serve", "--tools", "browser"]).is_err());
+    }
 }
@@ -1,12 +1,103 @@
 //! Config 命令实现
 
-use config::AppConfig;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use config::{ConfigFile, ConfigLoader, PROJECT_FILE_NAME};
 use crate::cli::ConfigAction;
 
-pub fn execute_config(action: ConfigAction) -> anyhow::Result<()> {
+/// 按命令行参数创建配置加载器，`overrides` 为 `KEY=VALUE` 形式
+pub fn config_loader(file: Option<PathBuf>, overrides: &[String]) -> anyhow::Result<ConfigLoader> {
+    let mut loader = ConfigLoader::new();
+    if let Some(file) = file {
+        loader = loader.with_file(file);
+    }
+    for item in overrides {
+        let (key, value) = item
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("--set 需要 KEY=VALUE 形式: {}", item))?;
+        loader = loader.with_override(key.trim(), value);
+    }
+    Ok(loader)
+}
+
+/// `set`/`unset`/`edit` 修改的配置文件
+fn target_file(loader: &ConfigLoader, project: bool) -> anyhow::Result<PathBuf> {
+    if project {
+        return match loader.project_file() {
+            Some(path) => Ok(path.to_path_buf()),
+            None => Ok(std::env::current_dir()?.join(PROJECT_FILE_NAME)),
+        };
+    }
+    loader
+        .user_file()
+        .map(|path| path.to_path_buf())
+        .ok_or_else(|| anyhow::anyhow!("无法确定用户主目录"))
+}
+
+fn print_value(value: &toml::Value) -> anyhow::Result<()> {
+    match value {
+        toml::Value::String(text) => println!("{}", text),
+        toml::Value::Table(table) => print!("{}", toml::to_string_pretty(table)?),
+        other => println!("{}", other),
+    }
+    Ok(())
+}
+
+/// 打开编辑器，校验失败时可以重新编辑或放弃修改
+fn edit_file(path: &PathBuf) -> anyhow::Result<()> {
+    let original = std::fs::read_to_string(path).ok();
+    if original.is_none() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, "# sker 配置，可用的键见 `sker config show`\n")?;
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad".to_string() } else { "vi".to_string() });
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().ok_or_else(|| anyhow::anyhow!("编辑器为空"))?;
+    let args: Vec<&str> = parts.collect();
+
+    loop {
+        let status = std::process::Command::new(program)
+            .args(&args)
+            .arg(path)
+            .status()
+            .map_err(|e| anyhow::anyhow!("无法启动编辑器 {}: {}", editor, e))?;
+        if !status.success() {
+            anyhow::bail!("编辑器异常退出: {}", status);
+        }
+
+        let error = match ConfigFile::load(path.clone()) {
+            Ok(_) => {
+                println!("✅ 配置已保存: {}", path.display());
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+        println!("❌ 配置无效: {}", error);
+        print!("重新编辑? [Y/n] ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        let read = io::stdin().lock().read_line(&mut answer)?;
+        if read == 0 || answer.trim().eq_ignore_ascii_case("n") {
+            match &original {
+                Some(text) => std::fs::write(path, text)?,
+                None => std::fs::remove_file(path)?,
+            }
+            println!("已放弃修改");
+            return Ok(());
+        }
+    }
+}
+
+pub fn execute_config(action: ConfigAction, loader: &ConfigLoader) -> anyhow::Result<()> {
     match action {
         ConfigAction::Show => {
-            let config = AppConfig::default();
+            let config = loader.load()?;
             println!("当前配置:");
             println!("{:-<40}", "");
             println!("CLI 配置:");
@@ -18,23 +109,121 @@ pub fn execute_config(action: ConfigAction) -> anyhow::Result<()> {
             println!("  默认超时: {} 秒", config.executor.default_timeout_secs);
             println!("  Shell: {}", config.executor.shell);
             println!("  最大并发数: {}", config.executor.max_concurrent);
+            let mut tag_limits: Vec<_> = config.executor.tag_limits.iter().collect();
+            tag_limits.sort();
+            for (tag, limit) in tag_limits {
+                println!("  标签 {} 并发数: {}", tag, limit);
+            }
             println!();
             println!("调度器配置:");
             println!("  最大任务数: {}", config.scheduler.max_tasks);
-            println!("  持久化路径: {:?}", config.scheduler.persistence_path);
+            println!("  默认时区: {}", config.scheduler.timezone);
+            let retention = &config.scheduler.retention;
+            println!("  运行记录保留天数: {}", retention.max_age_days);
+            println!("  每个任务保留运行数: {}", retention.max_runs_per_task);
+            println!("  运行记录总大小上限: {} MB", retention.max_size_mb);
+            println!("  自动清理间隔: {} 分钟", retention.interval_minutes);
             println!();
             println!("存储配置:");
             println!("  后端: {:?}", config.storage.backend);
-            println!("  路径: {:?}", config.storage.path);
+            println!("  数据目录: {:?}", config.storage.path);
             println!();
             println!("语音配置:");
             println!("  启用: {}", config.voice.enabled);
             println!("  语言: {}", config.voice.language);
             println!("  模型: {}", config.voice.model);
+            println!();
+            println!("守护进程配置:");
+            println!("  日志级别: {}", config.daemon.log_level);
+            println!("  日志轮转大小: {} MB", config.daemon.log_max_size_mb);
+            println!("  保留轮转日志数: {}", config.daemon.log_keep);
+            println!();
+            println!("通知配置:");
+            let mut sinks: Vec<&str> = config.notifications.sinks.keys().map(String::as_str).collect();
+            sinks.sort();
+            let sinks = sinks.join(", ");
+            println!("  发送目标: {}", if sinks.is_empty() { "无" } else { &sinks });
+            println!("  路由规则: {} 条", config.notifications.routes.len());
+            println!();
+            println!("工具调用策略:");
+            println!("  启用: {}", config.policy.enabled);
+            println!("  默认: {}", config.policy.default);
+            println!("  演练模式: {}", config.policy.dry_run);
+            println!("  审计日志: {:?}", config.policy.audit_log);
+            for rule in &config.policy.rules {
+                let mut arguments: Vec<String> = rule
+                    .arguments
+                    .iter()
+                    .map(|(name, patterns)| format!("{}=[{}]", name, patterns.join("|")))
+                    .collect();
+                arguments.sort();
+                println!("  规则: {} {} {}", rule.effect, rule.tool, arguments.join(" "));
+            }
+            println!();
+            println!("MCP 配置:");
+            println!("  访问令牌: {}", if config.mcp.token.is_empty() { "未设置" } else { "已设置" });
+        }
+        ConfigAction::Get { key } => match loader.load()?.get(&key)? {
+            Some(value) => print_value(&value)?,
+            None => println!("{} 未设置", key),
+        },
+        ConfigAction::Set { key, value, project } => {
+            let path = target_file(loader, project)?;
+            let mut file = ConfigFile::load(path)?;
+            file.set(&key, &value)?;
+            file.save()?;
+            println!("✅ 已写入 {}: {}", file.path().display(), key);
+
+            // 项目配置文件可能是刚创建的
+            let loader = match project {
+                true => loader.clone().with_project_file(Some(file.path().to_path_buf())),
+                false => loader.clone(),
+            };
+            let effective = loader.load()?.get(&key)?;
+            if effective.as_ref() != file.get(&key) {
+                if let Some(effective) = effective {
+                    println!("注意: 该项被更高优先级的配置覆盖，生效值为 {}", effective);
+                }
+            }
+        }
+        ConfigAction::Unset { key, project } => {
+            let path = target_file(loader, project)?;
+            let mut file = ConfigFile::load(path)?;
+            if file.unset(&key)? {
+                file.save()?;
+                println!("✅ 已从 {} 删除: {}", file.path().display(), key);
+            } else {
+                println!("{} 中没有设置 {}", file.path().display(), key);
+            }
+        }
+        ConfigAction::Edit { project } => {
+            edit_file(&target_file(loader, project)?)?;
         }
-        ConfigAction::Set { key, value } => {
-            println!("设置配置项: {} = {}", key, value);
-            println!("注意: 配置持久化功能正在开发中");
+        ConfigAction::Path => {
+            let describe = |path: &std::path::Path| {
+                if path.exists() {
+                    path.display().to_string()
+                } else {
+                    format!("{} (不存在)", path.display())
+                }
+            };
+            match loader.user_file() {
+                Some(path) => println!("用户配置: {}", describe(path)),
+                None => println!("用户配置: 无法确定用户主目录"),
+            }
+            match loader.project_file() {
+                Some(path) => println!("项目配置: {}", describe(path)),
+                None => println!("项目配置: 未找到 {} (--project 时在当前目录创建)", PROJECT_FILE_NAME),
+            }
+            for path in loader.files() {
+                println!("附加配置: {}", describe(path));
+            }
+            for (name, key) in loader.env_overrides() {
+                println!("环境变量: {} -> {}", name, key);
+            }
+            for (key, value) in loader.overrides() {
+                println!("命令行覆盖: {} = {}", key, value);
+            }
         }
     }
     Ok(())
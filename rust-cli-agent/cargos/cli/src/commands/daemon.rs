@@ -2,10 +2,30 @@
 //!
 //! 提供定时任务守护进程的管理功能：start / stop / restart / kill / status
 
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "windows")]
 use std::process::Command;
 use std::fs;
 use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use filesystem::{FileSystemEvent, FileSystemService};
+use task_scheduler::{ConcurrencyLimits, HookManager, PersistentCronTaskScheduler, TaskScheduler, Tz};
+
+use config::{AppConfig, ConfigLoader, DaemonConfig, RetentionConfig};
+
+use crate::commands::schedule::{
+    concurrency_limits, default_timezone, get_scheduler_data_dir, notification_hooks, open_scheduler, retention_policy,
+};
+use crate::log_rotation::{RotatingLog, RotatingLogWriter};
 
 /// 守护进程管理器
 pub struct DaemonManager {
@@ -15,6 +35,10 @@ pub struct DaemonManager {
     pid_file: PathBuf,
     /// 日志文件路径
     log_file: PathBuf,
+    /// 控制套接字路径
+    socket_file: PathBuf,
+    /// 配置状态文件路径
+    config_status_file: PathBuf,
 }
 
 impl DaemonManager {
@@ -25,6 +49,8 @@ impl DaemonManager {
         Self {
             pid_file: data_dir.join("daemon.pid"),
             log_file: data_dir.join("daemon.log"),
+            socket_file: data_dir.join("daemon.sock"),
+            config_status_file: data_dir.join("daemon.config.json"),
             data_dir,
         }
     }
@@ -39,6 +65,28 @@ impl DaemonManager {
         &self.log_file
     }
 
+    /// 获取控制套接字路径
+    pub fn socket_file(&self) -> &PathBuf {
+        &self.socket_file
+    }
+
+    /// 获取配置状态文件路径
+    pub fn config_status_file(&self) -> &PathBuf {
+        &self.config_status_file
+    }
+
+    /// 读取工作进程写入的配置状态
+    pub fn read_config_status(&self) -> Option<ConfigStatus> {
+        let content = fs::read_to_string(&self.config_status_file).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// 写入配置状态
+    pub fn write_config_status(&self, status: &ConfigStatus) -> io::Result<()> {
+        let content = serde_json::to_string_pretty(status)?;
+        fs::write(&self.config_status_file, content)
+    }
+
     /// 读取 PID
     pub fn read_pid(&self) -> io::Result<u32> {
         let content = fs::read_to_string(&self.pid_file)?;
@@ -227,7 +275,9 @@ if ($process -ne $null) {{
             0 => {
                 // 子进程
                 // 创建新的会话
-                libc::setsid();
+                unsafe {
+                    libc::setsid();
+                }
 
                 // 重定向标准输入/输出/错误
                 let devnull = std::fs::OpenOptions::new()
@@ -388,190 +438,526 @@ impl std::fmt::Display for DaemonStatus {
     }
 }
 
-/// 运行守护进程工作循环
-pub async fn run_daemon_worker() -> anyhow::Result<()> {
-    use tokio::time::{sleep, Duration};
-    use chrono::Utc;
-    use cron::Schedule;
-    use std::str::FromStr;
+/// 守护进程与存储重新同步任务的间隔
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
 
-    // 尝试初始化 tracing（如果已经初始化则忽略错误）
-    let daemon_manager = DaemonManager::new();
-    let log_file = daemon_manager.log_file();
+/// 配置文件变化后等待的时间，合并编辑器保存时的多次写入
+const CONFIG_DEBOUNCE: Duration = Duration::from_millis(500);
 
-    // 确保日志目录存在
-    if let Some(parent) = log_file.parent() {
-        fs::create_dir_all(parent)?;
-    }
+/// 守护进程日志级别的重载句柄
+type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
 
-    // 创建日志文件写入器
-    let log_file_clone = log_file.clone();
-    let file_writer = move || {
-        let file = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_file_clone)
-            .expect("无法打开日志文件");
-        Box::new(file) as Box<dyn std::io::Write + Send>
-    };
+/// 守护进程日志，级别和轮转设置可在运行中替换
+struct DaemonLogging {
+    filter: LogFilterHandle,
+    file: Arc<RotatingLog>,
+}
 
-    // 尝试初始化 tracing（如果已经初始化则忽略错误）
-    let _ = tracing_subscriber::fmt()
-        .with_writer(file_writer)
-        .with_ansi(false)
-        .with_target(false)
-        .with_thread_ids(false)
-        .with_file(false)
-        .with_line_number(false)
-        .try_init();
+/// 守护进程的配置状态，由工作进程写入，供 `daemon status` 查看
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigStatus {
+    /// 监控中的配置文件
+    pub files: Vec<PathBuf>,
+    /// 当前配置的生效时间
+    pub applied_at: Option<DateTime<Utc>>,
+    /// 最近一次加载失败的原因，配置修正后清除
+    pub error: Option<String>,
+    /// 最近一次加载失败的时间
+    pub failed_at: Option<DateTime<Utc>>,
+    /// 已修改但需要重启守护进程才能生效的配置项
+    pub pending_restart: Vec<String>,
+}
 
-    tracing::info!("守护进程工作进程启动");
+/// 可在运行中替换的守护进程设置
+///
+/// 先完整校验再统一应用，配置有误时不会只生效一部分。
+/// 守护进程不使用语音引擎，`voice` 配置由 `sker voice` 和 `sker mcp serve` 在启动时读取，不在此重新加载
+struct DaemonSettings {
+    limits: ConcurrencyLimits,
+    max_tasks: usize,
+    timezone: Tz,
+    hooks: Option<HookManager>,
+    log_filter: EnvFilter,
+    log_max_bytes: u64,
+    log_keep: usize,
+}
 
-    // 写入 PID
-    let pid = std::process::id();
-    daemon_manager.write_pid(pid)?;
-    tracing::info!("守护进程 PID: {}", pid);
+impl DaemonSettings {
+    /// 按配置构建设置，不产生任何副作用
+    fn prepare(config: &AppConfig) -> anyhow::Result<Self> {
+        let log_filter = EnvFilter::try_new(&config.daemon.log_level)
+            .map_err(|e| anyhow::anyhow!("daemon.log_level 无效: {}", e))?;
+        Ok(Self {
+            limits: concurrency_limits(&config.executor),
+            max_tasks: config.scheduler.max_tasks,
+            timezone: default_timezone(&config.scheduler)?,
+            hooks: notification_hooks(&config.notifications)?,
+            log_filter,
+            log_max_bytes: config.daemon.log_max_size_mb.saturating_mul(1024 * 1024),
+            log_keep: config.daemon.log_keep,
+        })
+    }
 
-    // 获取可执行文件路径
-    let exe_path = std::env::current_exe()?;
-    tracing::info!("可执行文件: {:?}", exe_path);
+    /// 应用设置，previous 为上次生效的配置，首次应用时为 None
+    async fn apply(self, scheduler: &PersistentCronTaskScheduler, logging: &DaemonLogging, previous: Option<&AppConfig>) {
+        if let Err(e) = logging.filter.reload(self.log_filter) {
+            tracing::warn!("更新日志级别失败: {}", e);
+        }
+        logging.file.set_limits(self.log_max_bytes, self.log_keep);
+        // 并发上限未变时不触碰，正在运行的任务占用的名额保持不变
+        if previous.is_none_or(|config| concurrency_limits(&config.executor) != self.limits) {
+            scheduler.set_concurrency_limits(&self.limits);
+        }
+        scheduler.set_max_tasks(self.max_tasks);
+        scheduler.set_default_timezone(self.timezone);
+        // 没有路由规则时清空回调，停止发送通知
+        scheduler.set_hooks(self.hooks.unwrap_or_default()).await;
+    }
+}
 
-    // 创建关闭信号
-    let (shutdown_tx, mut shutdown_rx) = tokio::sync::broadcast::channel::<()>(1);
+/// 修改后需要重启守护进程才能生效的配置项
+fn restart_required(started: &AppConfig, config: &AppConfig) -> Vec<String> {
+    let mut keys = Vec::new();
+    if started.storage.backend != config.storage.backend {
+        keys.push("storage.backend".to_string());
+    }
+    if started.storage.path != config.storage.path {
+        keys.push("storage.path".to_string());
+    }
+    if started.executor.shell != config.executor.shell {
+        keys.push("executor.shell".to_string());
+    }
+    keys
+}
 
-    // 设置 Ctrl+C 处理
-    let shutdown_tx_clone = shutdown_tx.clone();
-    tokio::spawn(async move {
-        tokio::signal::ctrl_c().await.ok();
-        let _ = shutdown_tx_clone.send(());
-    });
+fn same_config(a: &AppConfig, b: &AppConfig) -> bool {
+    matches!((toml::Value::try_from(a), toml::Value::try_from(b)), (Ok(a), Ok(b)) if a == b)
+}
 
-    tracing::info!("开始任务轮询循环");
+/// 守护进程运行中的配置
+struct LiveConfig<'a> {
+    loader: &'a ConfigLoader,
+    daemon_manager: &'a DaemonManager,
+    logging: DaemonLogging,
+    /// 启动时的配置，用于判断哪些修改需要重启
+    started: AppConfig,
+    /// 当前生效的配置
+    current: AppConfig,
+    status: ConfigStatus,
+}
 
-    // 主循环：每分钟检查一次需要执行的任务
-    loop {
-        // 检查关闭信号
-        if shutdown_rx.try_recv().is_ok() {
-            tracing::info!("收到关闭信号，守护进程退出");
-            break;
-        }
-
-        // 通过调用 sker schedule list --format json 获取任务列表
-        // 设置环境变量禁用子命令的日志输出
-        let tasks_result = Command::new(&exe_path)
-            .args(["schedule", "list", "--format", "json"])
-            .env("RUST_LOG", "off")
-            .env("NO_COLOR", "1")
-            .output();
+impl<'a> LiveConfig<'a> {
+    fn new(
+        loader: &'a ConfigLoader,
+        daemon_manager: &'a DaemonManager,
+        logging: DaemonLogging,
+        config: AppConfig,
+    ) -> Self {
+        Self {
+            loader,
+            daemon_manager,
+            logging,
+            started: config.clone(),
+            current: config,
+            status: ConfigStatus::default(),
+        }
+    }
 
-        let tasks_json = match tasks_result {
-            Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout).to_string(),
-            Ok(o) => {
-                tracing::error!("获取任务列表失败: {}", String::from_utf8_lossy(&o.stderr));
-                sleep(Duration::from_secs(60)).await;
-                continue;
-            }
+    /// 应用启动时的配置
+    ///
+    /// 通知或日志配置有误时不影响调度，这两项使用默认值并等待修正
+    async fn apply_initial(&mut self, scheduler: &PersistentCronTaskScheduler) -> anyhow::Result<()> {
+        let settings = match DaemonSettings::prepare(&self.current) {
+            Ok(settings) => settings,
             Err(e) => {
-                tracing::error!("执行命令失败: {}", e);
-                sleep(Duration::from_secs(60)).await;
-                continue;
+                tracing::error!("配置无效，通知和日志级别使用默认值: {:#}", e);
+                self.fail(&e);
+                let mut fallback = self.current.clone();
+                fallback.notifications = Default::default();
+                fallback.daemon = Default::default();
+                DaemonSettings::prepare(&fallback)?
             }
         };
+        let routes = if settings.hooks.is_some() { self.current.notifications.routes.len() } else { 0 };
+        settings.apply(scheduler, &self.logging, None).await;
+        tracing::info!("最大并发数: {}", self.current.executor.max_concurrent);
+        if routes > 0 {
+            tracing::info!("已启用 {} 条通知规则", routes);
+        }
+        self.status.applied_at = Some(Utc::now());
+        self.save_status();
+        Ok(())
+    }
 
-        // 解析任务列表
-        let tasks: Vec<serde_json::Value> = match serde_json::from_str(&tasks_json) {
-            Ok(t) => t,
+    /// 重新加载配置，校验通过后整体替换，否则继续使用当前配置
+    async fn reload(&mut self, scheduler: &PersistentCronTaskScheduler) {
+        let prepared = self
+            .loader
+            .load()
+            .map_err(anyhow::Error::from)
+            .and_then(|config| DaemonSettings::prepare(&config).map(|settings| (config, settings)));
+        let (config, settings) = match prepared {
+            Ok(prepared) => prepared,
             Err(e) => {
-                tracing::error!("解析任务列表失败: {} (内容: {})", e, &tasks_json[..tasks_json.len().min(200)]);
-                sleep(Duration::from_secs(60)).await;
-                continue;
+                tracing::error!("配置修改无效，继续使用上次有效的配置: {:#}", e);
+                self.fail(&e);
+                return;
             }
         };
+        if same_config(&config, &self.current) && self.status.error.is_none() {
+            return;
+        }
 
-        tracing::debug!("当前任务数量: {}", tasks.len());
+        settings.apply(scheduler, &self.logging, Some(&self.current)).await;
+        if config.voice != self.current.voice {
+            tracing::info!("voice 配置不由守护进程使用，下次运行 sker voice 或 sker mcp serve 时生效");
+        }
+        self.status.pending_restart = restart_required(&self.started, &config);
+        if !self.status.pending_restart.is_empty() {
+            tracing::warn!("以下配置需要重启守护进程才能生效: {}", self.status.pending_restart.join(", "));
+        }
+        tracing::info!(
+            "配置已重新加载: 最大并发数 {}，最大任务数 {}，通知规则 {} 条，日志级别 {}",
+            config.executor.max_concurrent,
+            config.scheduler.max_tasks,
+            config.notifications.routes.len(),
+            config.daemon.log_level
+        );
+        self.current = config;
+        self.status.applied_at = Some(Utc::now());
+        self.status.error = None;
+        self.status.failed_at = None;
+        self.save_status();
+    }
 
-        let now = Utc::now();
+    fn fail(&mut self, error: &anyhow::Error) {
+        self.status.error = Some(format!("{:#}", error));
+        self.status.failed_at = Some(Utc::now());
+        self.save_status();
+    }
 
-        // 检查每个任务是否需要执行
-        for task in &tasks {
-            // 跳过系统任务
-            if task.get("is_system").and_then(|v| v.as_bool()).unwrap_or(false) {
-                continue;
-            }
+    fn save_status(&self) {
+        if let Err(e) = self.daemon_manager.write_config_status(&self.status) {
+            tracing::warn!("写入配置状态失败: {}", e);
+        }
+    }
 
-            // 跳过禁用的任务
-            if !task.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true) {
-                continue;
+    /// 监控配置文件所在的目录
+    ///
+    /// 编辑器常以重命名替换文件，只监控文件本身会在保存后失效。
+    /// 用户配置目录不存在时先创建，以便之后新建的配置文件也能生效
+    async fn watch(&mut self, watcher: &mut FileSystemService) -> HashSet<PathBuf> {
+        let user_file = self.loader.user_file().map(Path::to_path_buf);
+        if let Some(dir) = user_file.as_deref().and_then(Path::parent) {
+            if let Err(e) = fs::create_dir_all(dir) {
+                tracing::warn!("无法创建配置目录 {:?}: {}", dir, e);
             }
+        }
 
-            // 跳过暂停的任务
-            let status = task.get("status").and_then(|v| v.as_str()).unwrap_or("");
-            if status == "Paused" {
+        let mut watched_dirs = HashSet::new();
+        let mut files = HashSet::new();
+        let candidates = user_file
+            .into_iter()
+            .chain(self.loader.project_file().map(Path::to_path_buf))
+            .chain(self.loader.files().iter().cloned());
+        for file in candidates {
+            let (Some(dir), Some(name)) = (file.parent(), file.file_name()) else {
                 continue;
-            }
-
-            // 获取任务 ID 和 cron 表达式
-            let task_id = match task.get("id").and_then(|v| v.as_str()) {
-                Some(id) => id.to_string(),
-                None => continue,
             };
-
-            let cron_expr = match task.get("cron_expression").and_then(|v| v.as_str()) {
-                Some(expr) => expr,
-                None => continue,
-            };
-
-            // 解析 cron 表达式
-            let schedule: Schedule = match Schedule::from_str(cron_expr) {
-                Ok(s) => s,
+            let dir = match dir.canonicalize() {
+                Ok(dir) => dir,
                 Err(e) => {
-                    tracing::error!("任务 {} 的 cron 表达式无效: {}", task_id, e);
+                    tracing::warn!("无法监控配置文件 {:?}: {}", file, e);
                     continue;
                 }
             };
+            if watched_dirs.insert(dir.clone()) {
+                if let Err(e) = watcher.watch_shallow(dir.clone()).await {
+                    tracing::warn!("无法监控配置目录 {:?}: {}", dir, e);
+                    continue;
+                }
+            }
+            files.insert(dir.join(name));
+        }
 
-            // 获取下次执行时间
-            let next_run = schedule.upcoming(Utc).next();
-
-            if let Some(next_time) = next_run {
-                // 如果下次执行时间在当前时间之前或刚好到达，则执行任务
-                if next_time <= now {
-                    let title = task.get("title").and_then(|v| v.as_str()).unwrap_or("unknown");
-                    tracing::info!("执行任务: {} ({})", title, task_id);
-
-                    // 通过调用 sker schedule run 命令执行任务
-                    let exe = exe_path.clone();
-                    let tid = task_id.clone();
-                    let run_result = Command::new(&exe)
-                        .args(["schedule", "run", &tid])
-                        .output();
-
-                    match run_result {
-                        Ok(o) if o.status.success() => {
-                            tracing::info!("任务 {} 执行完成", task_id);
-                        }
-                        Ok(o) => {
-                            tracing::error!("任务 {} 执行失败: {}", task_id, String::from_utf8_lossy(&o.stderr));
-                        }
-                        Err(e) => {
-                            tracing::error!("任务 {} 执行失败: {}", task_id, e);
+        let mut watched: Vec<PathBuf> = files.iter().cloned().collect();
+        watched.sort();
+        for file in &watched {
+            tracing::info!("监控配置文件: {:?}", file);
+        }
+        self.status.files = watched;
+        self.save_status();
+        files
+    }
+}
+
+/// 初始化写入日志文件的 tracing，日志按大小轮转
+fn init_daemon_logging(log_file: &Path) -> io::Result<DaemonLogging> {
+    // 确保日志目录存在
+    if let Some(parent) = log_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // 加载配置前使用默认的轮转设置
+    let defaults = DaemonConfig::default();
+    let file = Arc::new(RotatingLog::new(
+        log_file,
+        defaults.log_max_size_mb * 1024 * 1024,
+        defaults.log_keep,
+    ));
+    let writer = file.clone();
+    let file_writer = move || RotatingLogWriter(writer.clone());
+
+    // 加载配置前先按 info 级别记录，配置加载后替换
+    let (filter, handle) = reload::Layer::new(EnvFilter::new("info"));
+    // 尝试初始化 tracing（如果已经初始化则忽略错误）
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(file_writer)
+                .with_ansi(false)
+                .with_target(false)
+                .with_thread_ids(false)
+                .with_file(false)
+                .with_line_number(false),
+        )
+        .try_init();
+    Ok(DaemonLogging { filter: handle, file })
+}
+
+/// 按各任务的策略处理错过的运行
+async fn catch_up_missed_runs(scheduler: &PersistentCronTaskScheduler) {
+    match scheduler.catch_up_missed_runs().await {
+        Ok(0) => {}
+        Ok(runs) => tracing::info!("补跑错过的运行: {} 次", runs),
+        Err(e) => tracing::error!("处理错过的运行失败: {}", e),
+    }
+}
+
+/// 按保留策略清理运行记录和日志
+async fn compact_history(scheduler: &PersistentCronTaskScheduler, retention: &RetentionConfig) {
+    let policy = retention_policy(retention);
+    if policy.is_unlimited() {
+        return;
+    }
+    match scheduler.prune_history(policy, false).await {
+        Ok(report) if report.runs > 0 => tracing::info!(
+            "已清理 {} 个任务的 {} 次运行、{} 条日志，约 {} KB",
+            report.tasks,
+            report.runs,
+            report.logs,
+            report.bytes / 1024
+        ),
+        Ok(_) => {}
+        Err(e) => tracing::error!("清理运行记录失败: {}", e),
+    }
+}
+
+/// 下一次后台清理的时间，间隔为 0 时不自动清理
+fn next_compaction(retention: &RetentionConfig) -> Option<tokio::time::Instant> {
+    (retention.interval_minutes > 0)
+        .then(|| tokio::time::Instant::now() + Duration::from_secs(retention.interval_minutes.saturating_mul(60)))
+}
+
+/// 运行守护进程工作循环
+///
+/// 在进程内托管持久化调度器，由 cron 作业按秒触发任务，
+/// 并定期从存储同步其他 CLI 调用新增、修改或删除的任务。
+/// 配置文件修改后自动重新加载，无需重启。启动时和之后每隔一段时间按保留策略清理运行记录
+pub async fn run_daemon_worker(loader: &ConfigLoader) -> anyhow::Result<()> {
+    let daemon_manager = DaemonManager::new();
+    let logging = init_daemon_logging(daemon_manager.log_file())?;
+
+    tracing::info!("守护进程工作进程启动");
+
+    let config = match loader.load() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("配置无效，守护进程无法启动: {}", e);
+            return Err(e.into());
+        }
+    };
+
+    // 写入 PID
+    let pid = std::process::id();
+    daemon_manager.write_pid(pid)?;
+    tracing::info!("守护进程 PID: {}", pid);
+
+    let data_dir = get_scheduler_data_dir(&config.storage);
+    let scheduler = Arc::new(open_scheduler(&config).await?);
+    let mut daemon_config = LiveConfig::new(loader, &daemon_manager, logging, config);
+    daemon_config.apply_initial(&scheduler).await?;
+    let tasks = scheduler.list_tasks().await?;
+    tracing::info!("已从 {:?} 加载 {} 个任务", data_dir, tasks.len());
+
+    scheduler.start().await?;
+    tracing::info!("调度器已启动");
+    // 守护进程停止期间错过的运行
+    catch_up_missed_runs(&scheduler).await;
+    compact_history(&scheduler, &daemon_config.current.scheduler.retention).await;
+    let mut compact_at = next_compaction(&daemon_config.current.scheduler.retention);
+
+    let control = match serve_control_socket(&daemon_manager, scheduler.clone()) {
+        Ok(control) => control,
+        Err(e) => {
+            tracing::error!("控制套接字无法启动，守护进程退出: {}", e);
+            let _ = scheduler.stop().await;
+            daemon_manager.remove_pid_file()?;
+            return Err(e.into());
+        }
+    };
+
+    let mut watcher = FileSystemService::new();
+    let config_files = daemon_config.watch(&mut watcher).await;
+    let mut config_events = watcher.subscribe();
+    let mut watching = !config_files.is_empty();
+    let mut reload_config_at: Option<tokio::time::Instant> = None;
+
+    let mut reload = tokio::time::interval(RELOAD_INTERVAL);
+    reload.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // 第一次 tick 立即完成，任务刚加载过，跳过
+    reload.tick().await;
+
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => {
+                tracing::info!("收到关闭信号，守护进程退出");
+                break;
+            }
+            _ = reload.tick() => {
+                match scheduler.reload_tasks().await {
+                    Ok(summary) if !summary.is_empty() => tracing::info!(
+                        "任务已同步: 新增 {}，修改 {}，删除 {}",
+                        summary.added,
+                        summary.updated,
+                        summary.removed
+                    ),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("同步任务失败: {}", e),
+                }
+                // 机器休眠唤醒后补上休眠期间错过的运行
+                catch_up_missed_runs(&scheduler).await;
+            }
+            event = config_events.recv(), if watching => {
+                match event {
+                    Ok(FileSystemEvent::Created { path })
+                    | Ok(FileSystemEvent::Modified { path })
+                    | Ok(FileSystemEvent::Deleted { path }) => {
+                        if config_files.contains(&path) {
+                            reload_config_at = Some(tokio::time::Instant::now() + CONFIG_DEBOUNCE);
                         }
                     }
+                    // 丢失的事件中可能有配置文件的修改
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        reload_config_at = Some(tokio::time::Instant::now() + CONFIG_DEBOUNCE);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        tracing::warn!("配置文件监控已停止，修改配置后需要重启守护进程");
+                        watching = false;
+                    }
+                }
+            }
+            _ = tokio::time::sleep_until(reload_config_at.unwrap_or_else(tokio::time::Instant::now)), if reload_config_at.is_some() => {
+                reload_config_at = None;
+                let interval = daemon_config.current.scheduler.retention.interval_minutes;
+                daemon_config.reload(&scheduler).await;
+                if daemon_config.current.scheduler.retention.interval_minutes != interval {
+                    compact_at = next_compaction(&daemon_config.current.scheduler.retention);
                 }
             }
+            _ = tokio::time::sleep_until(compact_at.unwrap_or_else(tokio::time::Instant::now)), if compact_at.is_some() => {
+                compact_history(&scheduler, &daemon_config.current.scheduler.retention).await;
+                compact_at = next_compaction(&daemon_config.current.scheduler.retention);
+            }
         }
+    }
 
-        // 等待一分钟
-        sleep(Duration::from_secs(60)).await;
+    if let Some(control) = control {
+        control.abort();
+        let _ = fs::remove_file(daemon_manager.socket_file());
+    }
+    if let Err(e) = scheduler.stop().await {
+        tracing::error!("停止调度器失败: {}", e);
     }
 
-    // 清理 PID 文件
+    // 清理 PID 文件和配置状态
     daemon_manager.remove_pid_file()?;
+    let _ = fs::remove_file(daemon_manager.config_status_file());
 
     tracing::info!("守护进程工作进程退出");
     Ok(())
 }
 
+/// 在控制套接字上提供调度器接口
+#[cfg(unix)]
+fn serve_control_socket(
+    daemon_manager: &DaemonManager,
+    scheduler: Arc<PersistentCronTaskScheduler>,
+) -> io::Result<Option<tokio::task::JoinHandle<()>>> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let socket_file = daemon_manager.socket_file();
+    // 清理上次异常退出留下的套接字文件；属于其他用户的文件可能是伪造的套接字，拒绝启动
+    if let Ok(metadata) = fs::symlink_metadata(socket_file) {
+        let uid = unsafe { libc::geteuid() };
+        if metadata.uid() != uid {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Control socket {:?} is owned by uid {}, not {}", socket_file, metadata.uid(), uid),
+            ));
+        }
+        fs::remove_file(socket_file)?;
+    }
+    let listener = tokio::net::UnixListener::bind(socket_file)?;
+    // 控制套接字可以创建和运行任意命令，只允许当前用户连接
+    fs::set_permissions(socket_file, fs::Permissions::from_mode(0o600))?;
+    tracing::info!("控制套接字: {:?} (协议版本 {})", socket_file, task_scheduler::PROTOCOL_VERSION);
+
+    let scheduler: Arc<dyn TaskScheduler> = scheduler;
+    Ok(Some(tokio::spawn(async move {
+        if let Err(e) = task_scheduler::ipc::serve(listener, scheduler).await {
+            tracing::error!("控制套接字停止服务: {}", e);
+        }
+    })))
+}
+
+/// 非 Unix 平台没有控制套接字，CLI 直接访问存储
+#[cfg(not(unix))]
+fn serve_control_socket(
+    _daemon_manager: &DaemonManager,
+    _scheduler: Arc<PersistentCronTaskScheduler>,
+) -> io::Result<Option<tokio::task::JoinHandle<()>>> {
+    Ok(None)
+}
+
+/// 等待 Ctrl+C 或 SIGTERM (`daemon stop` 发送)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                tracing::warn!("无法监听 SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
 /// 显示守护进程状态
 pub fn print_status(daemon_manager: &DaemonManager) {
     let status = daemon_manager.status();
@@ -581,11 +967,35 @@ pub fn print_status(daemon_manager: &DaemonManager) {
         DaemonStatus::Running { .. } => {
             println!("PID 文件: {}", daemon_manager.pid_file().display());
             println!("日志文件: {}", daemon_manager.log_file().display());
+            #[cfg(unix)]
+            println!("控制套接字: {}", daemon_manager.socket_file().display());
+            if let Some(config) = daemon_manager.read_config_status() {
+                print_config_status(&config);
+            }
         }
         _ => {}
     }
 }
 
+/// 显示守护进程的配置状态
+fn print_config_status(status: &ConfigStatus) {
+    let local = |time: &DateTime<Utc>| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string();
+    for file in &status.files {
+        println!("配置文件: {}", file.display());
+    }
+    if let Some(applied_at) = &status.applied_at {
+        println!("配置生效时间: {}", local(applied_at));
+    }
+    if let Some(error) = &status.error {
+        let failed_at = status.failed_at.as_ref().map(local).unwrap_or_default();
+        println!("⚠️  配置无效 ({})，仍在使用上次有效的配置:", failed_at);
+        println!("  {}", error);
+    }
+    if !status.pending_restart.is_empty() {
+        println!("需要重启才能生效: {}", status.pending_restart.join(", "));
+    }
+}
+
 /// 显示守护进程日志
 pub fn print_logs(daemon_manager: &DaemonManager, lines: usize) -> io::Result<()> {
     let log_file = daemon_manager.log_file();
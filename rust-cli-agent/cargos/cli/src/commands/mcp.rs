@@ -0,0 +1,167 @@
+//! Mcp 命令实现
+
+use std::sync::Arc;
+
+use command_executor::{CommandToolAdapter, CommandToolLimits, LocalCommandExecutor};
+use config::{AppConfig, VoiceConfig};
+use filesystem::FileSystemToolAdapter;
+use power_management::{LocalPowerManager, PowerToolAdapter};
+use task_scheduler::{McpServer, SchedulerToolAdapter};
+#[cfg(unix)]
+use task_scheduler::DaemonClient;
+use tools::{ToolProvider, ToolRegistry};
+
+use crate::cli::{McpAction, ToolGroup};
+#[cfg(unix)]
+use crate::commands::daemon::DaemonManager;
+use crate::commands::policy::guard_tools;
+use crate::commands::schedule::{notification_hooks, open_scheduler};
+
+/// `initialize` 中返回的使用说明
+const INSTRUCTIONS: &str = "sker 本机工具：管理定时任务和工作流，执行命令，查询电源能力、设置唤醒和休眠 (需确认)，语音合成与音频转录，监控文件变化";
+
+/// 未指定 `--tools` 时提供的工具组
+const ALL_TOOL_GROUPS: &[ToolGroup] = &[
+    ToolGroup::Scheduler,
+    ToolGroup::Command,
+    ToolGroup::Power,
+    ToolGroup::Voice,
+    ToolGroup::Filesystem,
+];
+
+pub async fn execute_mcp(action: McpAction, config: &AppConfig) -> anyhow::Result<()> {
+    match action {
+        McpAction::Serve { http, path, tools } => {
+            let registry = tool_registry(&tools, config).await?;
+            tracing::info!("MCP 服务提供 {} 个工具", registry.len());
+            let provider = guard_tools(Arc::new(registry), &config.policy)?;
+            let mut server = McpServer::new(provider).with_instructions(INSTRUCTIONS);
+            match http {
+                Some(address) => {
+                    let listener = std::net::TcpListener::bind(&address)
+                        .map_err(|e| anyhow::anyhow!("无法监听 {}: {}", address, e))?;
+                    // 工具可以执行命令，监听其他地址时必须用令牌认证每个请求
+                    if config.mcp.token.is_empty() {
+                        if !listener.local_addr()?.ip().is_loopback() {
+                            anyhow::bail!(
+                                "{} 不是本机地址，请先设置访问令牌 (mcp.token 或 SKER_MCP_TOKEN)",
+                                address
+                            );
+                        }
+                    } else {
+                        server = server.with_bearer_token(config.mcp.token.clone());
+                    }
+                    let server = Arc::new(server);
+                    // 标准输出不承载协议消息，提示信息写到标准错误与 stdio 模式保持一致
+                    eprintln!("MCP 服务已启动: http://{}{}", listener.local_addr()?, path);
+                    server
+                        .serve_http(listener, path, async {
+                            let _ = tokio::signal::ctrl_c().await;
+                        })
+                        .await?;
+                }
+                None => {
+                    tracing::info!("MCP 服务使用 stdio 传输");
+                    Arc::new(server).serve_stdio(tokio::io::stdin(), tokio::io::stdout()).await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 按工具组注册工具
+///
+/// 未指定工具组时，当前环境不可用的工具组 (如缺少语音引擎) 只记录警告并跳过
+async fn tool_registry(groups: &[ToolGroup], config: &AppConfig) -> anyhow::Result<ToolRegistry> {
+    let explicit = !groups.is_empty();
+    let groups = if explicit { groups } else { ALL_TOOL_GROUPS };
+
+    let mut registry = ToolRegistry::new();
+    for group in groups {
+        let provider: Arc<dyn ToolProvider> = match group {
+            ToolGroup::Scheduler => scheduler_tools(config).await?,
+            ToolGroup::Command => {
+                let max_timeout_secs = CommandToolLimits::default().max_timeout_secs;
+                // 工具调用必须有超时，配置为 0 (不限制) 时使用上限
+                let default_timeout_secs = match config.executor.default_timeout_secs {
+                    0 => max_timeout_secs,
+                    secs => secs,
+                };
+                let limits = CommandToolLimits {
+                    default_timeout_secs,
+                    max_timeout_secs: default_timeout_secs.max(max_timeout_secs),
+                    ..Default::default()
+                };
+                let executor = LocalCommandExecutor::new().with_shell(config.executor.shell.clone());
+                Arc::new(CommandToolAdapter::new(Arc::new(executor)).with_limits(limits))
+            }
+            ToolGroup::Power => Arc::new(PowerToolAdapter::new(Arc::new(LocalPowerManager::new()))),
+            ToolGroup::Voice if !explicit && !config.voice.enabled => {
+                tracing::info!("语音未启用 (voice.enabled)，已跳过语音工具");
+                continue;
+            }
+            ToolGroup::Voice => match voice_tools(&config.voice) {
+                Ok(provider) => provider,
+                Err(e) if !explicit => {
+                    tracing::warn!("语音工具不可用，已跳过: {}", e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            },
+            ToolGroup::Filesystem => Arc::new(FileSystemToolAdapter::new()),
+        };
+        registry
+            .register(provider)
+            .map_err(|e| anyhow::anyhow!("注册 {:?} 工具失败: {}", group, e))?;
+    }
+    Ok(registry)
+}
+
+/// 平台语音合成加 whisper.cpp 语音识别
+fn voice_tools(config: &VoiceConfig) -> anyhow::Result<Arc<dyn ToolProvider>> {
+    use voice_assistant::{STTOptions, VoiceAssistantService, VoiceToolAdapter, WhisperSTT};
+
+    #[cfg(windows)]
+    let tts = voice_assistant::tts::WindowsTTS::new();
+    #[cfg(target_os = "linux")]
+    let tts = voice_assistant::tts::LinuxTTS::new();
+    #[cfg(target_os = "macos")]
+    let tts = voice_assistant::tts::MacOSTTS::new();
+    #[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+    let tts: voice_assistant::VoiceResult<voice_assistant::tts::MockTTS> = Err(voice_assistant::VoiceError::NotAvailable);
+
+    let tts = tts.map_err(|e| anyhow::anyhow!("无法创建语音合成引擎: {}", e))?;
+    let stt = WhisperSTT::new().with_model_size(&config.model);
+    let defaults = STTOptions {
+        language: Some(config.language.clone()),
+        model: Some(config.model.clone()),
+    };
+    Ok(Arc::new(
+        VoiceToolAdapter::new(VoiceAssistantService::new(tts, stt)).with_stt_defaults(defaults),
+    ))
+}
+
+/// 守护进程运行时操作守护进程中的调度器，否则直接打开存储
+async fn scheduler_tools(config: &AppConfig) -> anyhow::Result<Arc<dyn ToolProvider>> {
+    #[cfg(unix)]
+    {
+        let socket_file = DaemonManager::new().socket_file().clone();
+        if socket_file.exists() {
+            match DaemonClient::connect(&socket_file).await {
+                Ok(client) => {
+                    tracing::info!("通过守护进程提供定时任务工具: {:?}", socket_file);
+                    return Ok(Arc::new(SchedulerToolAdapter::new(Arc::new(client))));
+                }
+                Err(e) => tracing::warn!("无法连接守护进程，直接访问存储: {}", e),
+            }
+        }
+    }
+
+    tracing::warn!("守护进程未运行，直接访问存储；任务按计划运行需要启动守护进程");
+    let scheduler = open_scheduler(config).await?;
+    if let Some(hooks) = notification_hooks(&config.notifications)? {
+        scheduler.set_hooks(hooks).await;
+    }
+    Ok(Arc::new(SchedulerToolAdapter::new(Arc::new(scheduler))))
+}
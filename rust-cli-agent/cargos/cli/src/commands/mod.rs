@@ -4,6 +4,8 @@
 
 pub mod config;
 pub mod daemon;
+pub mod mcp;
+pub mod policy;
 pub mod power;
 pub mod run;
 pub mod schedule;
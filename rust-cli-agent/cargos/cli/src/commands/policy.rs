@@ -0,0 +1,181 @@
+//! Policy 命令实现
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use config::PolicyConfig;
+use tools::{ApprovalStore, AuditLog, CallToolRequest, PolicyGuard, PolicyRule, ToolPolicy, ToolProvider};
+
+use crate::cli::PolicyAction;
+use crate::commands::schedule::expand_home;
+
+/// 确认请求的存放目录
+fn approvals_dir() -> PathBuf {
+    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.join(".sker").join("policy").join("approvals")
+}
+
+/// 按配置创建工具调用策略
+pub fn tool_policy(config: &PolicyConfig) -> anyhow::Result<ToolPolicy> {
+    let default = config
+        .default
+        .parse()
+        .map_err(|e| anyhow::anyhow!("policy.default 无效: {}", e))?;
+    let mut policy = ToolPolicy::new(default).with_dry_run(config.dry_run);
+    for (index, rule) in config.rules.iter().enumerate() {
+        let effect = rule
+            .effect
+            .parse()
+            .map_err(|e| anyhow::anyhow!("policy.rules[{}].effect 无效: {}", index, e))?;
+        let mut policy_rule = PolicyRule::new(rule.tool.clone(), effect);
+        for (name, patterns) in &rule.arguments {
+            policy_rule = policy_rule.with_argument(name.clone(), patterns.clone());
+        }
+        if let Some(reason) = &rule.reason {
+            policy_rule = policy_rule.with_reason(reason.clone());
+        }
+        policy = policy.with_rule(policy_rule);
+    }
+    Ok(policy)
+}
+
+fn approval_store(config: &PolicyConfig) -> ApprovalStore {
+    ApprovalStore::new(approvals_dir()).with_ttl(chrono::Duration::hours(config.approval_ttl_hours as i64))
+}
+
+fn audit_log(config: &PolicyConfig) -> AuditLog {
+    AuditLog::new(expand_home(&config.audit_log))
+}
+
+/// 按配置用策略包装工具，策略未启用时原样返回
+pub fn guard_tools(provider: Arc<dyn ToolProvider>, config: &PolicyConfig) -> anyhow::Result<Arc<dyn ToolProvider>> {
+    if !config.enabled {
+        tracing::warn!("工具调用策略未启用，所有调用将直接执行");
+        return Ok(provider);
+    }
+    let guard = PolicyGuard::new(provider, tool_policy(config)?)
+        .with_approvals(approval_store(config))
+        .with_audit(audit_log(config));
+    Ok(Arc::new(guard))
+}
+
+pub fn execute_policy(action: PolicyAction, config: &PolicyConfig) -> anyhow::Result<()> {
+    match action {
+        PolicyAction::Pending => {
+            let pending: Vec<_> = approval_store(config)
+                .list()?
+                .into_iter()
+                .filter(|a| a.status == tools::ApprovalStatus::Pending)
+                .collect();
+            if pending.is_empty() {
+                println!("没有等待确认的工具调用");
+            }
+            for approval in pending {
+                println!("{}  {}", approval.id, approval.tool);
+                println!("  参数: {}", approval.arguments);
+                println!("  原因: {}", approval.reason);
+                println!("  请求时间: {}", approval.requested_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"));
+            }
+        }
+        PolicyAction::Approve { id } => {
+            let approval = approval_store(config).approve(&id)?;
+            println!("已批准 {} ({})，调用方重试后执行", approval.id, approval.tool);
+        }
+        PolicyAction::Reject { id } => {
+            let approval = approval_store(config).reject(&id)?;
+            println!("已拒绝 {} ({})", approval.id, approval.tool);
+        }
+        PolicyAction::Audit { limit } => {
+            let log = audit_log(config);
+            let records = log.read_recent(limit)?;
+            if records.is_empty() {
+                println!("审计日志为空: {:?}", log.path());
+            }
+            for record in records {
+                let outcome = serde_json::to_value(record.outcome)?;
+                println!(
+                    "{}  {:<20} {:<8} {}{}",
+                    record.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+                    record.tool,
+                    record.effect,
+                    outcome.as_str().unwrap_or_default(),
+                    if record.dry_run { " (演练)" } else { "" }
+                );
+                println!("  参数: {}", record.arguments);
+                if let Some(rule) = &record.rule {
+                    println!("  规则: {}", rule);
+                }
+                if let Some(id) = &record.approval_id {
+                    println!("  确认请求: {}", id);
+                }
+                if let Some(error) = &record.error {
+                    println!("  错误: {}", error);
+                }
+            }
+        }
+        PolicyAction::Check { tool, args } => {
+            let arguments: serde_json::Value =
+                serde_json::from_str(&args).map_err(|e| anyhow::anyhow!("参数不是有效的 JSON: {}", e))?;
+            let policy = tool_policy(config)?;
+            let decision = policy.evaluate(&CallToolRequest { name: tool.clone(), arguments });
+            println!("工具: {}", tool);
+            println!("决定: {}", decision.effect);
+            println!("规则: {}", decision.rule.as_deref().unwrap_or("(默认)"));
+            if let Some(reason) = &decision.reason {
+                println!("原因: {}", reason);
+            }
+            if !config.enabled {
+                println!("注意: 策略未启用，实际调用不受限制");
+            } else if policy.is_dry_run() {
+                println!("注意: 演练模式，放行的调用不会执行");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use config::AppConfig;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tools::{CallToolResponse, Tool};
+
+    struct CountingTools {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ToolProvider for CountingTools {
+        fn tools(&self) -> Vec<Tool> {
+            Vec::new()
+        }
+
+        async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            CallToolResponse::text(request.name)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_policy_confirms_scheduled_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let tools = Arc::new(CountingTools { calls: AtomicUsize::new(0) });
+        let guard = PolicyGuard::new(tools.clone(), tool_policy(&AppConfig::default().policy).unwrap())
+            .with_approvals(ApprovalStore::new(dir.path().to_path_buf()));
+
+        let calls = [
+            ("add_task", json!({"title": "x", "name": "x", "cron": "* * * * *", "content": "rm -rf ~"})),
+            ("update_task", json!({"id": "x", "content": "rm -rf ~"})),
+            ("add_workflow", json!({"name": "x", "cron": "* * * * *", "steps": []})),
+        ];
+        for (name, arguments) in calls {
+            let response = guard.call(CallToolRequest { name: name.to_string(), arguments }).await;
+            assert!(response.error.unwrap().starts_with("Confirmation required"), "{}", name);
+        }
+        assert_eq!(tools.calls.load(Ordering::SeqCst), 0);
+        assert!(guard.call(CallToolRequest { name: "list_tasks".to_string(), arguments: json!({}) }).await.success);
+    }
+}
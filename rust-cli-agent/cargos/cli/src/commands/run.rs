@@ -1,13 +1,13 @@
 //! Run 命令实现
 
 use std::path::PathBuf;
-use std::sync::Arc;
 use uuid::Uuid;
 
+use config::ExecutorConfig;
 use command_executor::{
-    Command, CommandExecutor, ExecutionEnvironment, ExecutionStatus, LocalCommandExecutor,
+    Command, CommandEvent, CommandExecutor, ExecutionEnvironment, ExecutionStatus,
+    LocalCommandExecutor,
 };
-use task_scheduler::{TaskExecutionResult, SchedulerError};
 
 pub async fn execute_run(
     program: String,
@@ -15,16 +15,18 @@ pub async fn execute_run(
     work_dir: Option<PathBuf>,
     timeout: Option<u64>,
     shell: bool,
+    config: &ExecutorConfig,
 ) -> anyhow::Result<()> {
     tracing::info!("执行命令: {} {:?}", program, args);
 
-    let executor = LocalCommandExecutor::new();
+    let executor = LocalCommandExecutor::new().with_shell(config.shell.clone());
 
     let mut env = ExecutionEnvironment::default();
     if let Some(dir) = work_dir {
         env.working_dir = Some(dir);
     }
-    env.timeout_secs = timeout;
+    // 0 表示不限制
+    env.timeout_secs = timeout.or(Some(config.default_timeout_secs)).filter(|secs| *secs > 0);
     env.use_shell = shell;
 
     let command = Command {
@@ -35,14 +37,29 @@ pub async fn execute_run(
         status: ExecutionStatus::Pending,
     };
 
-    let result = executor.execute(command).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+    let mut handle = executor
+        .execute_streaming(command)
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
 
-    if !result.stdout.is_empty() {
-        println!("{}", result.stdout.trim());
-    }
-    if !result.stderr.is_empty() {
-        eprintln!("{}", result.stderr.trim());
+    // 输出边产生边打印；子进程不在终端前台进程组，Ctrl-C 需转为取消
+    let mut result = None;
+    let mut interrupted = false;
+    loop {
+        tokio::select! {
+            event = handle.next_event() => match event {
+                Some(CommandEvent::Stdout(line)) => println!("{}", line),
+                Some(CommandEvent::Stderr(line)) => eprintln!("{}", line),
+                Some(CommandEvent::Exited(r)) => result = Some(r),
+                None => break,
+            },
+            _ = tokio::signal::ctrl_c(), if !interrupted => {
+                interrupted = true;
+                let _ = executor.cancel(handle.id()).await;
+            }
+        }
     }
+    let result = result.ok_or_else(|| anyhow::anyhow!("命令未返回退出状态"))?;
 
     tracing::info!("命令执行完成: 退出码={}, 耗时={}ms", result.exit_code, result.duration_ms);
 
@@ -52,29 +69,3 @@ pub async fn execute_run(
 
     Ok(())
 }
-
-pub fn create_executor(
-    cmd: String,
-) -> Arc<dyn Fn(uuid::Uuid, std::collections::HashMap<String, String>) -> Result<TaskExecutionResult, SchedulerError> + Send + Sync> {
-    Arc::new(move |_task_id: uuid::Uuid, _context: std::collections::HashMap<String, String>| {
-        let result = std::process::Command::new("sh")
-            .arg("-c")
-            .arg(&cmd)
-            .output();
-
-        match result {
-            Ok(output) => Ok(TaskExecutionResult {
-                task_id: uuid::Uuid::new_v4(),
-                run_instance_id: None,
-                started_at: chrono::Utc::now(),
-                completed_at: Some(chrono::Utc::now()),
-                success: output.status.success(),
-                error: if output.status.success() { None } else { Some(String::from_utf8_lossy(&output.stderr).to_string()) },
-                stdout: Some(String::from_utf8_lossy(&output.stdout).to_string()),
-                stderr: Some(String::from_utf8_lossy(&output.stderr).to_string()),
-                exit_code: output.status.code(),
-            }),
-            Err(e) => Err(SchedulerError::ExecutionError(e.to_string())),
-        }
-    })
-}
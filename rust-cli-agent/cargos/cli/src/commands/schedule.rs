@@ -1,21 +1,160 @@
 //! Schedule 命令实现
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use std::process::Command;
 use uuid::Uuid;
 
-use crate::cli::{ScheduleAction, DaemonAction};
-use crate::output::{print_instance_info, print_task_briefing, print_task_info, print_task_info_full, sanitize_task_name};
+use crate::cli::{ScheduleAction, DaemonAction, WorkflowAction};
+use crate::output::{
+    describe_cron, print_import_report, print_instance_info, print_manifest_plan, print_prune_report, print_schedule_preview, print_task_briefing, print_task_info, print_task_info_full, print_workflow_briefing,
+    print_workflow_run, sanitize_task_name,
+};
+use config::{AppConfig, ExecutorConfig, NotificationConfig, NotificationSinkConfig, RetentionConfig, SchedulerConfig, StorageConfig};
 use task_scheduler::{
-    PersistentCronTaskScheduler, ScheduledTask, TaskLog, LogLevel, TaskUpdateRequest,
-    TaskScheduler, SystemTaskManager,
+    open_scheduler_storage, ActionRegistry, ExportOptions, ImportOptions, ScheduleBundle, TaskManifest, RetentionPolicy, ConcurrencyLimits, DesktopSink, FileSink, HookManager, NotificationRoute, NotificationRouter, NotificationSink,
+    SmtpSettings, SmtpSink, TaskOutcome, WebhookSink, MisfireConfig, PersistentCronTaskScheduler, RetryPolicy, ScheduledTask, TaskDependency, TaskLog, LogLevel,
+    TaskUpdateRequest, TaskScheduler, SystemTaskManager, TaskAction, TaskStatus, Workflow, ZonedSchedule, parse_timezone, Tz,
 };
-use crate::commands::run::create_executor;
-use crate::commands::daemon::{DaemonManager, run_daemon_worker, print_status, print_logs};
+#[cfg(unix)]
+use task_scheduler::DaemonClient;
+use crate::commands::daemon::{DaemonManager, print_status, print_logs};
+
+/// 调度器数据目录
+pub fn get_scheduler_data_dir(config: &StorageConfig) -> PathBuf {
+    expand_home(&config.path)
+}
+
+/// 按配置打开持久化调度器，通知回调由调用方按需设置
+pub async fn open_scheduler(config: &AppConfig) -> anyhow::Result<PersistentCronTaskScheduler> {
+    let storage_config = StorageConfig {
+        path: get_scheduler_data_dir(&config.storage),
+        ..config.storage.clone()
+    };
+    let storage = open_scheduler_storage(&storage_config)
+        .map_err(|e| anyhow::anyhow!("无法打开 {:?} 存储: {}", config.storage.backend, e))?;
+    let registry = ActionRegistry::with_shell(config.executor.shell.clone());
+    let scheduler = PersistentCronTaskScheduler::with_storage(storage, registry).await?;
+    scheduler.set_concurrency_limits(&concurrency_limits(&config.executor));
+    scheduler.set_max_tasks(config.scheduler.max_tasks);
+    scheduler.set_default_timezone(default_timezone(&config.scheduler)?);
+    Ok(scheduler)
+}
+
+/// 调度器配置中新建任务的默认时区
+pub fn default_timezone(config: &SchedulerConfig) -> anyhow::Result<Tz> {
+    parse_timezone(&config.timezone).map_err(|e| anyhow::anyhow!("scheduler.timezone 无效: {}", e))
+}
+
+/// 执行器配置中的并发上限
+pub fn concurrency_limits(config: &ExecutorConfig) -> ConcurrencyLimits {
+    ConcurrencyLimits {
+        max_concurrent: config.max_concurrent,
+        per_tag: config.tag_limits.clone(),
+    }
+}
+
+/// 保留配置对应的保留策略，为 0 的项不限制
+pub fn retention_policy(config: &RetentionConfig) -> RetentionPolicy {
+    let mut policy = RetentionPolicy::default();
+    if config.max_age_days > 0 {
+        policy = policy.with_max_age(chrono::Duration::days(config.max_age_days.min(i32::MAX as u64) as i64));
+    }
+    if config.max_runs_per_task > 0 {
+        policy = policy.with_max_runs_per_task(config.max_runs_per_task);
+    }
+    if config.max_size_mb > 0 {
+        policy = policy.with_max_total_bytes(config.max_size_mb.saturating_mul(1024 * 1024));
+    }
+    policy
+}
+
+/// 展开路径开头的 `~`
+pub(crate) fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
 
-pub fn get_scheduler_data_dir() -> PathBuf {
-    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-    home.join(".sker").join("scheduler")
+/// 按配置创建通知发送目标
+fn notification_sink(config: &NotificationSinkConfig) -> anyhow::Result<Arc<dyn NotificationSink>> {
+    let sink: Arc<dyn NotificationSink> = match config {
+        NotificationSinkConfig::Webhook { url, method, headers, body, timeout_secs } => {
+            let mut sink = WebhookSink::new(url.clone())
+                .with_method(method)?
+                .with_timeout(Duration::from_secs(*timeout_secs));
+            for (name, value) in headers {
+                sink = sink.with_header(name.clone(), value.clone());
+            }
+            if let Some(body) = body {
+                sink = sink.with_body_template(body)?;
+            }
+            Arc::new(sink)
+        }
+        NotificationSinkConfig::Smtp { host, port, from, to, username, password, subject, body } => {
+            let mut settings = SmtpSettings::new(host.clone(), *port, from.clone(), to.clone());
+            settings.username = username.clone();
+            settings.password = password.clone();
+            let mut sink = SmtpSink::new(settings)?;
+            if let Some(subject) = subject {
+                sink = sink.with_subject_template(subject.clone());
+            }
+            if let Some(body) = body {
+                sink = sink.with_body_template(body.clone());
+            }
+            Arc::new(sink)
+        }
+        NotificationSinkConfig::Desktop { title, body } => {
+            let mut sink = DesktopSink::new();
+            if let Some(title) = title {
+                sink = sink.with_title_template(title.clone());
+            }
+            if let Some(body) = body {
+                sink = sink.with_body_template(body.clone());
+            }
+            Arc::new(sink)
+        }
+        NotificationSinkConfig::File { path, template } => {
+            let mut sink = FileSink::new(expand_home(path));
+            if let Some(template) = template {
+                sink = sink.with_template(template.clone());
+            }
+            Arc::new(sink)
+        }
+    };
+    Ok(sink)
+}
+
+/// 按通知配置创建回调，没有路由规则时返回 None
+pub fn notification_hooks(config: &NotificationConfig) -> anyhow::Result<Option<HookManager>> {
+    if config.routes.is_empty() {
+        return Ok(None);
+    }
+    let mut router = NotificationRouter::new();
+    for (name, sink) in &config.sinks {
+        let sink = notification_sink(sink).map_err(|e| anyhow::anyhow!("通知目标 {} 配置无效: {}", name, e))?;
+        router = router.with_sink(name.clone(), sink);
+    }
+    for route in &config.routes {
+        let on = route
+            .on
+            .iter()
+            .map(|o| o.parse::<TaskOutcome>())
+            .collect::<Result<Vec<_>, _>>()?;
+        router = router.with_route(NotificationRoute {
+            tasks: route.tasks.clone(),
+            tags: route.tags.clone(),
+            on,
+            sinks: route.sinks.clone(),
+        });
+    }
+    router.validate()?;
+
+    let mut hooks = HookManager::new();
+    Arc::new(router).register(&mut hooks);
+    Ok(Some(hooks))
 }
 
 /// 列出系统级任务
@@ -58,48 +197,224 @@ fn list_system_tasks() -> anyhow::Result<Vec<(String, String, String)>> {
     Ok(tasks)
 }
 
-pub async fn execute_schedule(action: ScheduleAction) -> anyhow::Result<()> {
+pub async fn execute_schedule(action: ScheduleAction, config: &AppConfig) -> anyhow::Result<()> {
     match action {
         ScheduleAction::Daemon { action: daemon_action } => {
             // Daemon action 不需要访问数据库
             execute_daemon(daemon_action).await
         }
+        ScheduleAction::Preview { expression, count, timezone } => {
+            // 预览只计算触发时间，不需要访问数据库
+            preview_schedule(&expression, count, timezone.as_deref(), &config.scheduler)
+        }
         other => {
+            // 守护进程运行时通过控制套接字操作守护进程中的调度器
+            #[cfg(unix)]
+            {
+                let socket_file = DaemonManager::new().socket_file().clone();
+                if socket_file.exists() {
+                    match DaemonClient::connect(&socket_file).await {
+                        Ok(client) => {
+                            tracing::debug!("通过守护进程执行: {:?}", socket_file);
+                            return execute_schedule_with_scheduler(other, &client, config).await;
+                        }
+                        Err(e) => tracing::debug!("无法连接守护进程，直接访问存储: {}", e),
+                    }
+                }
+            }
+
             // 其他 action 需要访问数据库
-            let data_dir = get_scheduler_data_dir();
-            let scheduler: PersistentCronTaskScheduler = PersistentCronTaskScheduler::new(data_dir).await?;
-            execute_schedule_with_scheduler(other, scheduler).await
+            let scheduler = open_scheduler(config).await?;
+            if let Some(hooks) = notification_hooks(&config.notifications)? {
+                scheduler.set_hooks(hooks).await;
+            }
+            execute_schedule_with_scheduler(other, &scheduler, config).await
+        }
+    }
+}
+
+/// 预览 cron 表达式接下来的触发时间，未指定时区时使用 scheduler.timezone 配置
+fn preview_schedule(expression: &str, count: usize, timezone: Option<&str>, config: &SchedulerConfig) -> anyhow::Result<()> {
+    let timezone = match timezone {
+        Some(name) => parse_timezone(name)?,
+        None => default_timezone(config)?,
+    };
+    let schedule = ZonedSchedule::parse(expression, Some(timezone.name()))?;
+    let runs: Vec<_> = schedule.after(chrono::Utc::now()).take(count).collect();
+    print_schedule_preview(&schedule, &runs);
+    Ok(())
+}
+
+/// 执行工作流子命令
+async fn execute_workflow(action: WorkflowAction, scheduler: &dyn TaskScheduler) -> anyhow::Result<()> {
+    match action {
+        WorkflowAction::Add { file } => {
+            let definition = std::fs::read_to_string(&file)
+                .map_err(|e| anyhow::anyhow!("无法读取工作流定义 {}: {}", file.display(), e))?;
+            let workflow: Workflow = serde_json::from_str(&definition)
+                .map_err(|e| anyhow::anyhow!("工作流定义格式错误: {}", e))?;
+            let workflow = scheduler.add_workflow(workflow).await?;
+            println!("✅ 工作流已添加:");
+            println!("  ID: {}", workflow.id);
+            println!("  名称: {}", workflow.name);
+            println!("  步骤: {}", workflow.steps.len());
+        }
+        WorkflowAction::List => {
+            let workflows = scheduler.list_workflows().await?;
+            if workflows.is_empty() {
+                println!("没有工作流");
+                return Ok(());
+            }
+            println!("工作流列表:");
+            println!("{:-<80}", "");
+            for workflow in workflows {
+                println!("  ID: {}", workflow.id);
+                println!("  名称: {}", workflow.name);
+                match &workflow.cron_expression {
+                    Some(cron_expression) => println!("  Cron: {}", describe_cron(cron_expression, workflow.timezone.as_deref())),
+                    None => println!("  Cron: 手动运行"),
+                }
+                let steps: Vec<&str> = workflow.steps.iter().map(|s| s.name.as_str()).collect();
+                println!("  步骤: {}", steps.join(", "));
+                println!("{:-<80}", "");
+            }
+        }
+        WorkflowAction::Run { id, user } => {
+            let workflow_id = Uuid::parse_str(&id)?;
+            let run = scheduler.run_workflow(workflow_id, parse_user_params(user)).await?;
+            println!("工作流运行结束:");
+            print_workflow_run(&run);
+        }
+        WorkflowAction::Status { id } => {
+            let workflow_id = Uuid::parse_str(&id)?;
+            let briefing = scheduler.get_workflow_briefing(workflow_id).await?;
+            print_workflow_briefing(&briefing);
+        }
+        WorkflowAction::Remove { id } => {
+            let workflow_id = Uuid::parse_str(&id)?;
+            scheduler.remove_workflow(workflow_id).await?;
+            println!("✅ 工作流已删除: {}", id);
+        }
+    }
+    Ok(())
+}
+
+/// 跟随日志时的轮询间隔
+const FOLLOW_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// 持续输出运行实例的日志，直到实例结束
+async fn follow_logs(
+    scheduler: &dyn TaskScheduler,
+    instance_id: Uuid,
+    level: Option<LogLevel>,
+) -> anyhow::Result<()> {
+    let mut printed = 0;
+    loop {
+        // 先读取状态再读取日志，实例结束前的日志不会漏掉
+        let instance = scheduler.get_run_instance(instance_id).await?;
+        let logs = scheduler.get_instance_logs(instance_id, level.clone()).await?;
+        for log in logs.iter().skip(printed) {
+            println!("[{}] {} - {}", log.timestamp.format("%Y-%m-%d %H:%M:%S"), log.level, log.message);
         }
+        printed = printed.max(logs.len());
+
+        if instance.status != TaskStatus::Running {
+            println!("运行实例已结束: {:?}", instance.status);
+            return Ok(());
+        }
+        tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+    }
+}
+
+/// 为新建任务设置错过运行、失败重试、依赖和并发等策略
+async fn apply_policies(
+    scheduler: &dyn TaskScheduler,
+    task: ScheduledTask,
+    policies: TaskUpdateRequest,
+) -> anyhow::Result<ScheduledTask> {
+    if policies.is_empty() {
+        return Ok(task);
+    }
+    let request = TaskUpdateRequest { id: task.id, ..policies };
+    Ok(scheduler.update_task(request).await?)
+}
+
+/// 解析 `--depends-on` 参数，未指定时返回 None
+fn parse_dependencies(depends_on: &[String]) -> anyhow::Result<Option<Vec<TaskDependency>>> {
+    if depends_on.is_empty() {
+        return Ok(None);
     }
+    let deps = depends_on
+        .iter()
+        .map(|d| d.parse::<TaskDependency>())
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(deps))
+}
+
+/// 解析 `key=value` 形式的用户参数
+fn parse_user_params(user: Vec<String>) -> std::collections::HashMap<String, String> {
+    user.iter()
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// 提示用户确认，输入 y 或 yes 时返回 true
+fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    use std::io::{self, Write};
+    print!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+    Ok(input == "y" || input == "yes")
 }
 
 async fn execute_schedule_with_scheduler(
     action: ScheduleAction,
-    scheduler: PersistentCronTaskScheduler,
+    scheduler: &dyn TaskScheduler,
+    config: &AppConfig,
 ) -> anyhow::Result<()> {
     match action {
-        ScheduleAction::Daemon { .. } => {
-            // Daemon 已经在 execute_schedule 中处理，不应该到达这里
-            unreachable!("Daemon action should be handled in execute_schedule")
+        ScheduleAction::Daemon { .. } | ScheduleAction::Preview { .. } => {
+            // Daemon 和 Preview 已经在 execute_schedule 中处理，不应该到达这里
+            unreachable!("Daemon and preview actions should be handled in execute_schedule")
         }
-        ScheduleAction::Add { cron, command, title, description, content, system } => {
+        ScheduleAction::Add {
+            cron, command, title, description, content, system, misfire, grace, max_attempts, backoff, retry_on,
+            depends_on, overlap, tags, timezone,
+        } => {
+            // 时区在创建任务前校验，避免留下未设置时区的任务
+            if let Some(timezone) = &timezone {
+                parse_timezone(timezone)?;
+            }
+            let policies = TaskUpdateRequest {
+                misfire: MisfireConfig::default().with_overrides(misfire.as_deref(), grace)?,
+                retry: RetryPolicy::default().with_overrides(max_attempts, backoff.as_deref(), retry_on.as_deref())?,
+                depends_on: parse_dependencies(&depends_on)?,
+                overlap: overlap.as_deref().map(str::parse).transpose()?,
+                tags: (!tags.is_empty()).then_some(tags),
+                timezone,
+                ..Default::default()
+            };
             if system {
                 // 创建系统级任务 (先保存到 storage，再创建系统任务)
                 tracing::info!("添加系统级定时任务: {} -> {}", cron, command);
 
                 // 1. 先保存到 storage 获取 id，设置 is_system = true
-                let executor = create_executor(command.clone());
                 let task_title = title.unwrap_or_else(|| command.clone());
                 let task_name = sanitize_task_name(&command);
-                let task = scheduler.add_task_with_system(
+                let task = scheduler.add_task_with_action(
                     task_title.clone(),
                     task_name,
                     description,
                     content,
                     cron.clone(),
-                    executor,
+                    TaskAction::shell(command.clone()),
                     true  // is_system = true
                 ).await?;
+                let task = apply_policies(scheduler, task, policies).await?;
 
                 // 2. 创建系统任务
                 let system_manager = SystemTaskManager::new()
@@ -123,10 +438,18 @@ async fn execute_schedule_with_scheduler(
             } else {
                 // 使用内置调度器
                 tracing::info!("添加定时任务: {} -> {}", cron, command);
-                let executor = create_executor(command.clone());
                 let task_title = title.unwrap_or_else(|| command.clone());
                 let task_name = sanitize_task_name(&command);
-                let task = scheduler.add_task_full(task_title, task_name, description, content, cron, executor).await?;
+                let task = scheduler.add_task_with_action(
+                    task_title,
+                    task_name,
+                    description,
+                    content,
+                    cron,
+                    TaskAction::shell(command.clone()),
+                    false,
+                ).await?;
+                let task = apply_policies(scheduler, task, policies).await?;
                 println!("✅ 任务已添加:");
                 print_task_info(&task);
             }
@@ -240,12 +563,7 @@ async fn execute_schedule_with_scheduler(
             match scheduler.get_task(task_id).await {
                 Ok(task) => {
                     // 任务存在，执行任务
-                    let mut params: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-                    for param in user {
-                        if let Some((key, value)) = param.split_once('=') {
-                            params.insert(key.to_string(), value.to_string());
-                        }
-                    }
+                    let params = parse_user_params(user);
                     let instance = scheduler.run_task(task_id, params).await?;
                     println!("✅ 任务已开始运行:");
                     print_instance_info(&instance);
@@ -275,7 +593,7 @@ async fn execute_schedule_with_scheduler(
             scheduler.stop_task(instance_id).await?;
             println!("✅ 任务已停止: {}", run_id);
         }
-        ScheduleAction::Log { run_id, level } => {
+        ScheduleAction::Log { run_id, level, follow } => {
             let instance_id = Uuid::parse_str(&run_id)?;
             let log_level = level.as_ref().and_then(|l: &String| match l.to_lowercase().as_str() {
                 "debug" => Some(LogLevel::Debug),
@@ -284,6 +602,9 @@ async fn execute_schedule_with_scheduler(
                 "error" => Some(LogLevel::Error),
                 _ => None,
             });
+            if follow {
+                return follow_logs(scheduler, instance_id, log_level).await;
+            }
             let logs: Vec<TaskLog> = scheduler.get_instance_logs(instance_id, log_level).await?;
             if logs.is_empty() {
                 println!("没有日志");
@@ -299,8 +620,16 @@ async fn execute_schedule_with_scheduler(
             let briefing = scheduler.get_task_briefing(task_id).await?;
             print_task_briefing(&briefing);
         }
-        ScheduleAction::Update { id, title, description, content, cron } => {
+        ScheduleAction::Update {
+            id, title, description, content, cron, misfire, grace, max_attempts, backoff, retry_on,
+            depends_on, clear_depends_on, overlap, tags, clear_tags, timezone,
+        } => {
             let task_id = Uuid::parse_str(&id)?;
+            let current = scheduler.get_task(task_id).await?;
+            let misfire = current.misfire.with_overrides(misfire.as_deref(), grace)?;
+            let retry = current
+                .retry
+                .with_overrides(max_attempts, backoff.as_deref(), retry_on.as_deref())?;
             let request = TaskUpdateRequest {
                 id: task_id,
                 title,
@@ -308,11 +637,29 @@ async fn execute_schedule_with_scheduler(
                 content,
                 cron_expression: cron,
                 enabled: None,
+                misfire,
+                retry,
+                depends_on: if clear_depends_on {
+                    Some(Vec::new())
+                } else {
+                    parse_dependencies(&depends_on)?
+                },
+                overlap: overlap.as_deref().map(str::parse).transpose()?,
+                tags: if clear_tags {
+                    Some(Vec::new())
+                } else {
+                    (!tags.is_empty()).then_some(tags)
+                },
+                action: None,
+                timezone,
             };
             let task = scheduler.update_task(request).await?;
             println!("✅ 任务已更新:");
             print_task_info(&task);
         }
+        ScheduleAction::Workflow { action } => {
+            execute_workflow(action, scheduler).await?;
+        }
         ScheduleAction::Destroy { id } => {
             let task_id = Uuid::parse_str(&id)?;
             scheduler.remove_task(task_id).await?;
@@ -324,6 +671,119 @@ async fn execute_schedule_with_scheduler(
             println!("任务详情:");
             print_task_info_full(&task);
         }
+        ScheduleAction::Prune { older_than, keep, max_size, dry_run } => {
+            let defaults = &config.scheduler.retention;
+            let policy = retention_policy(&RetentionConfig {
+                max_age_days: older_than.unwrap_or(defaults.max_age_days),
+                max_runs_per_task: keep.unwrap_or(defaults.max_runs_per_task),
+                max_size_mb: max_size.unwrap_or(defaults.max_size_mb),
+                ..defaults.clone()
+            });
+            if policy.is_unlimited() {
+                println!("保留策略没有任何限制，无需清理");
+                return Ok(());
+            }
+            let report = scheduler.prune_history(policy, dry_run).await?;
+            print_prune_report(&report);
+        }
+        ScheduleAction::Export { output, tasks, history } => {
+            let task_ids = tasks.iter().map(|id| Uuid::parse_str(id)).collect::<Result<Vec<_>, _>>()?;
+            let mut options = ExportOptions::default().with_tasks(task_ids);
+            if history {
+                options = options.with_history();
+            }
+            let bundle = scheduler.export_bundle(options).await?;
+            match output {
+                Some(path) => {
+                    let file = std::fs::File::create(&path)
+                        .map_err(|e| anyhow::anyhow!("无法创建迁移包 {}: {}", path.display(), e))?;
+                    bundle.write_to(std::io::BufWriter::new(file))?;
+                    println!(
+                        "✅ 已导出 {} 个任务、{} 次运行、{} 条日志到 {}",
+                        bundle.tasks.len(),
+                        bundle.run_instances.len(),
+                        bundle.logs.len(),
+                        path.display()
+                    );
+                }
+                None => bundle.write_to(std::io::stdout().lock())?,
+            }
+        }
+        ScheduleAction::Import { file, on_conflict, remap_ids, no_history, system, dry_run } => {
+            let bundle = if file.as_os_str() == "-" {
+                ScheduleBundle::read_from(std::io::stdin().lock())?
+            } else {
+                let reader = std::fs::File::open(&file)
+                    .map_err(|e| anyhow::anyhow!("无法读取迁移包 {}: {}", file.display(), e))?;
+                ScheduleBundle::read_from(std::io::BufReader::new(reader))?
+            };
+            let options = ImportOptions {
+                conflict: on_conflict.parse()?,
+                remap_ids,
+                include_history: !no_history,
+                recreate_system: system,
+                dry_run,
+            };
+            let report = scheduler.import_bundle(bundle, options).await?;
+            print_import_report(&report);
+
+            // 系统级任务在本机重新创建，失败时保留为内置任务记录
+            let system_tasks: Vec<Uuid> = report.imported().filter(|t| t.is_system).map(|t| t.target_id).collect();
+            if !dry_run && !system_tasks.is_empty() {
+                let system_manager = SystemTaskManager::new()
+                    .map_err(|e| anyhow::anyhow!("Failed to create system task manager: {}", e))?;
+                for task_id in system_tasks {
+                    let task = scheduler.get_task(task_id).await?;
+                    match system_manager.create_system_task(&task).await {
+                        Ok(()) => println!("✅ 系统级任务已创建: Sker_{}", task_id),
+                        Err(e) => {
+                            tracing::error!("创建系统任务失败: {}", e);
+                            println!("⚠️  系统级任务 {} 创建失败: {}", task_id, e);
+                        }
+                    }
+                }
+            }
+        }
+        ScheduleAction::Apply { file, prune, yes } => {
+            let manifest = TaskManifest::load(&file)?;
+            let plan = manifest.plan(&scheduler.list_tasks().await?, prune)?;
+            if plan.is_empty() {
+                println!("任务与清单一致，无需变更");
+                return Ok(());
+            }
+            print_manifest_plan(&plan);
+            if !yes && !confirm("确认应用以上变更? [y/N]: ")? {
+                println!("操作已取消");
+                return Ok(());
+            }
+            plan.apply(scheduler).await?;
+            let summary = plan.summary();
+            println!(
+                "✅ 已应用清单: 新建 {} 个, 更新 {} 个, 删除 {} 个",
+                summary.created, summary.updated, summary.deleted
+            );
+        }
+        ScheduleAction::Diff { file, prune } => {
+            let manifest = TaskManifest::load(&file)?;
+            let plan = manifest.plan(&scheduler.list_tasks().await?, prune)?;
+            if plan.is_empty() {
+                println!("任务与清单一致");
+            } else {
+                print_manifest_plan(&plan);
+            }
+        }
+        ScheduleAction::Validate { file } => {
+            let manifest = TaskManifest::load(&file)?;
+            match manifest.validate() {
+                Ok(specs) => println!("✅ 清单有效，共 {} 个任务", specs.len()),
+                Err(errors) => {
+                    for error in &errors {
+                        println!("❌ {}", error);
+                    }
+                    anyhow::bail!("清单中有 {} 处错误", errors.len());
+                }
+            }
+        }
         ScheduleAction::Clear { system, force } => {
             tracing::info!("清空所有定时任务 (system: {}, force: {})", system, force);
 
@@ -363,19 +823,9 @@ async fn execute_schedule_with_scheduler(
             println!("{:-<80}", "");
 
             // 如果没有 --force 标志，需要用户确认
-            if !force {
-                use std::io::{self, Write};
-                print!("确认清空以上所有任务? [y/N]: ");
-                io::stdout().flush()?;
-
-                let mut input = String::new();
-                io::stdin().read_line(&mut input)?;
-                let input = input.trim().to_lowercase();
-
-                if input != "y" && input != "yes" {
-                    println!("操作已取消");
-                    return Ok(());
-                }
+            if !force && !confirm("确认清空以上所有任务? [y/N]: ")? {
+                println!("操作已取消");
+                return Ok(());
             }
 
             // 清空内置任务
@@ -423,7 +873,7 @@ async fn execute_schedule_with_scheduler(
 }
 
 /// 执行守护进程命令
-async fn execute_daemon(action: DaemonAction) -> anyhow::Result<()> {
+pub async fn execute_daemon(action: DaemonAction) -> anyhow::Result<()> {
     let daemon_manager = DaemonManager::new();
 
     match action {
@@ -485,10 +935,7 @@ async fn execute_daemon(action: DaemonAction) -> anyhow::Result<()> {
         DaemonAction::Logs { lines } => {
             print_logs(&daemon_manager, lines)?;
         }
-        DaemonAction::Worker => {
-            // 运行守护进程工作循环
-            run_daemon_worker().await?;
-        }
+        DaemonAction::Worker => unreachable!("守护进程工作进程已在加载配置前处理"),
     }
 
     Ok(())
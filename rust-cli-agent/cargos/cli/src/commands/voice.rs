@@ -1,8 +1,11 @@
 //! Voice 命令实现
 
+use config::VoiceConfig;
+use voice_assistant::{STTBackend, STTOptions, STTResult, WhisperSTT};
+
 use crate::cli::VoiceAction;
 
-pub async fn execute_voice(action: VoiceAction) -> anyhow::Result<()> {
+pub async fn execute_voice(action: VoiceAction, config: &VoiceConfig) -> anyhow::Result<()> {
     match action {
         VoiceAction::Speak { text } => {
             tracing::info!("TTS: {}", text);
@@ -18,14 +21,27 @@ pub async fn execute_voice(action: VoiceAction) -> anyhow::Result<()> {
             if audio.is_none() && url.is_none() {
                 return Err(anyhow::anyhow!("Please provide either --audio or --url"));
             }
-            if let Some(file_path) = audio {
+            let options = STTOptions {
+                language: Some(language.unwrap_or_else(|| config.language.clone())),
+                model: Some(config.model.clone()),
+            };
+            let result = if let Some(file_path) = audio {
                 tracing::info!("STT from file: {}", file_path);
-                // STT 功能需要 whisper 模型，暂时省略
-                println!("STT from file: {} (需要 whisper 模型)", file_path);
-            } else if let Some(audio_url) = url {
+                let data = tokio::fs::read(&file_path)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("无法读取音频文件 {}: {}", file_path, e))?;
+                WhisperSTT::new()
+                    .with_model_size(&config.model)
+                    .transcribe(&data, Some(options))
+                    .await
+            } else {
+                let audio_url = url.unwrap_or_default();
                 tracing::info!("STT from URL: {}", audio_url);
-                println!("STT from URL: {} (需要 whisper 模型)", audio_url);
-            }
+                voice_assistant::transcribe_url(&audio_url, Some(options)).await
+            };
+            let STTResult { text, language, .. } =
+                result.map_err(|e| anyhow::anyhow!("STT failed (需要 whisper-cli 和 {} 模型): {}", config.model, e))?;
+            println!("[{}] {}", language, text.trim());
         }
     }
     Ok(())
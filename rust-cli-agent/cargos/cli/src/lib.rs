@@ -26,12 +26,12 @@ pub use command_executor::{
     LocalCommandExecutor,
 };
 pub use config::{
-    AppConfig, CliConfig, ExecutorConfig, SchedulerConfig, StorageBackend, StorageConfig,
+    AppConfig, CliConfig, ConfigLoader, ExecutorConfig, SchedulerConfig, StorageBackend, StorageConfig,
     VoiceConfig,
 };
 pub use power_management::{PowerError, PowerManagementService, PowerState};
 pub use task_scheduler::{
-    AsyncTaskExecutor, CronTaskScheduler, PersistentCronTaskScheduler, ScheduledTask,
+    AsyncTaskExecutor, TaskExecutor, TaskRunContext, TaskAction, ActionRegistry, CronTaskScheduler, PersistentCronTaskScheduler, ScheduledTask,
     SchedulerError, TaskExecutionResult, TaskScheduler, TaskStatus, TaskBriefing, TaskRunInstance,
     LogLevel,
 };
@@ -0,0 +1,152 @@
+//! 守护进程日志轮转
+//!
+//! 日志文件超过大小上限后，当前内容以 gzip 压缩为 `<文件名>.1.gz`，
+//! 已有的压缩日志依次后移，超出保留数量的被删除。日志文件原地截断，
+//! 其他以追加方式打开该文件的写入方 (如重定向的标准输出) 不受影响
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+/// 按大小轮转的日志文件
+pub struct RotatingLog {
+    path: PathBuf,
+    /// 轮转的大小上限，0 表示不轮转
+    max_bytes: AtomicU64,
+    /// 保留的压缩日志数量
+    keep: AtomicUsize,
+    file: Mutex<Option<File>>,
+}
+
+impl RotatingLog {
+    pub fn new(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes: AtomicU64::new(max_bytes),
+            keep: AtomicUsize::new(keep),
+            file: Mutex::new(None),
+        }
+    }
+
+    /// 更新大小上限和保留数量，下一次写入时生效
+    pub fn set_limits(&self, max_bytes: u64, keep: usize) {
+        self.max_bytes.store(max_bytes, Ordering::Relaxed);
+        self.keep.store(keep, Ordering::Relaxed);
+    }
+
+    /// 第 n 个压缩日志的路径
+    fn archive_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}.gz", n));
+        self.path.with_file_name(name)
+    }
+
+    fn open(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// 压缩当前日志并截断
+    fn rotate(&self, file: &File) -> io::Result<()> {
+        let keep = self.keep.load(Ordering::Relaxed);
+        if keep > 0 {
+            // 超出保留数量的压缩日志被删除，其余依次后移
+            let _ = fs::remove_file(self.archive_path(keep));
+            for n in (1..keep).rev() {
+                let from = self.archive_path(n);
+                if from.exists() {
+                    fs::rename(&from, self.archive_path(n + 1))?;
+                }
+            }
+            let mut encoder = GzEncoder::new(File::create(self.archive_path(1))?, Compression::default());
+            io::copy(&mut File::open(&self.path)?, &mut encoder)?;
+            encoder.finish()?;
+        }
+        file.set_len(0)
+    }
+
+    /// 写入一条日志，写入前超出大小上限则先轮转
+    fn append(&self, buf: &[u8]) -> io::Result<()> {
+        let mut guard = self.file.lock().map_err(|e| io::Error::other(e.to_string()))?;
+        let file = match guard.as_mut() {
+            Some(file) => file,
+            None => guard.insert(Self::open(&self.path)?),
+        };
+
+        let max_bytes = self.max_bytes.load(Ordering::Relaxed);
+        let size = file.metadata()?.len();
+        if max_bytes > 0 && size > 0 && size + buf.len() as u64 > max_bytes {
+            // 轮转失败时继续写入当前文件，不丢失日志
+            if let Err(e) = self.rotate(file) {
+                eprintln!("日志轮转失败 {:?}: {}", self.path, e);
+            }
+        }
+        file.write_all(buf)
+    }
+}
+
+/// 写入 [`RotatingLog`] 的句柄，供 tracing 的 `with_writer` 使用
+#[derive(Clone)]
+pub struct RotatingLogWriter(pub Arc<RotatingLog>);
+
+impl Write for RotatingLogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.append(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    fn read_archive(path: &Path) -> String {
+        let mut content = String::new();
+        GzDecoder::new(File::open(path).unwrap()).read_to_string(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn test_rotates_and_keeps_archives() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("daemon.log");
+        let mut writer = RotatingLogWriter(Arc::new(RotatingLog::new(&path, 10, 2)));
+
+        for line in ["first-line\n", "second-line\n", "third-line\n", "fourth-line\n"] {
+            writer.write_all(line.as_bytes()).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth-line\n");
+        assert_eq!(read_archive(&temp.path().join("daemon.log.1.gz")), "third-line\n");
+        assert_eq!(read_archive(&temp.path().join("daemon.log.2.gz")), "second-line\n");
+        assert!(!temp.path().join("daemon.log.3.gz").exists());
+    }
+
+    #[test]
+    fn test_limits_apply_on_next_write() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("daemon.log");
+        let log = Arc::new(RotatingLog::new(&path, 0, 1));
+        let mut writer = RotatingLogWriter(log.clone());
+
+        writer.write_all(b"first-line\n").unwrap();
+        writer.write_all(b"second-line\n").unwrap();
+        assert!(!temp.path().join("daemon.log.1.gz").exists());
+
+        log.set_limits(10, 0);
+        writer.write_all(b"third-line\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third-line\n");
+        assert!(!temp.path().join("daemon.log.1.gz").exists());
+    }
+}
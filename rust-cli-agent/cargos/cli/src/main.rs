@@ -4,38 +4,68 @@
 
 mod cli;
 mod commands;
+mod log_rotation;
 mod output;
 
 use clap::Parser;
-use cli::{Cli, Commands, init_logging};
+use cli::{Cli, Commands, DaemonAction, ScheduleAction, init_logging};
 use commands::{
-    config::execute_config,
+    config::{config_loader, execute_config},
+    daemon::run_daemon_worker,
+    mcp::execute_mcp,
+    policy::execute_policy,
     power::execute_power,
     run::execute_run,
-    schedule::execute_schedule,
+    schedule::{execute_daemon, execute_schedule},
     voice::execute_voice,
 };
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    init_logging(cli.verbose);
+    let loader = config_loader(cli.config, &cli.overrides)?;
 
-    match cli.command {
+    // config 子命令不要求现有配置有效，以便修复错误的配置
+    let command = match cli.command {
+        Commands::Config { action } => {
+            init_logging(cli.verbose, true);
+            return execute_config(action, &loader);
+        }
+        // 守护进程管理不依赖配置，配置无效时也能查看状态和停止守护进程；
+        // 工作进程自行加载配置和初始化日志，以便修改配置后热重载
+        Commands::Schedule { action: ScheduleAction::Daemon { action } } => {
+            return match action {
+                DaemonAction::Worker => run_daemon_worker(&loader).await,
+                action => {
+                    init_logging(cli.verbose, true);
+                    execute_daemon(action).await
+                }
+            };
+        }
+        command => command,
+    };
+    let config = loader.load()?;
+    init_logging(cli.verbose || config.cli.verbose, config.cli.colors);
+
+    match command {
         Commands::Run { program, args, work_dir, timeout, shell } => {
-            execute_run(program, args, work_dir, timeout, shell).await?;
+            execute_run(program, args, work_dir, timeout, shell, &config.executor).await?;
         }
         Commands::Schedule { action } => {
-            execute_schedule(action).await?;
+            execute_schedule(action, &config).await?;
         }
         Commands::Power { action } => {
             execute_power(action).await?;
         }
         Commands::Voice { action } => {
-            execute_voice(action).await?;
+            execute_voice(action, &config.voice).await?;
         }
-        Commands::Config { action } => {
-            execute_config(action)?;
+        Commands::Config { .. } => unreachable!("config 子命令已在加载配置前处理"),
+        Commands::Mcp { action } => {
+            execute_mcp(action, &config).await?;
+        }
+        Commands::Policy { action } => {
+            execute_policy(action, &config.policy)?;
         }
     }
 
@@ -1,6 +1,9 @@
 //! 输出辅助模块
 
-use task_scheduler::{ScheduledTask, TaskBriefing, TaskRunInstance};
+use chrono::{DateTime, Datelike, Utc};
+use task_scheduler::{
+    parse_timezone, ChangeKind, ImportAction, ImportReport, ManifestPlan, PruneReport, RetryPolicy, ScheduledTask, TaskBriefing, TaskDependency, TaskRunInstance, WorkflowBriefing, WorkflowRun, ZonedSchedule,
+};
 
 pub fn sanitize_task_name(name: &str) -> String {
     name.chars()
@@ -8,6 +11,36 @@ pub fn sanitize_task_name(name: &str) -> String {
         .collect()
 }
 
+/// 任务或工作流的 cron 表达式，带时区时附上时区
+pub fn describe_cron(cron_expression: &str, timezone: Option<&str>) -> String {
+    match timezone {
+        Some(timezone) => format!("{} ({})", cron_expression, timezone),
+        None => cron_expression.to_string(),
+    }
+}
+
+/// 计划时间，带时区的任务按当地时间显示
+fn format_scheduled(at: &DateTime<Utc>, timezone: Option<&str>) -> String {
+    match timezone.and_then(|name| parse_timezone(name).ok()) {
+        Some(tz) => format!("{} {}", at.with_timezone(&tz).format("%Y-%m-%d %H:%M:%S"), tz.name()),
+        None => at.format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+/// 预览中的一次触发时间，附上星期；带时区时同时显示 UTC 时间
+fn format_preview_run(at: &DateTime<Utc>, timezone: Option<&str>) -> String {
+    const WEEKDAYS: [&str; 7] = ["周日", "周一", "周二", "周三", "周四", "周五", "周六"];
+    let local = match timezone.and_then(|name| parse_timezone(name).ok()) {
+        Some(tz) => at.with_timezone(&tz).weekday(),
+        None => at.weekday(),
+    };
+    let line = format!("{} {}", format_scheduled(at, timezone), WEEKDAYS[local.num_days_from_sunday() as usize]);
+    match timezone {
+        Some(_) => format!("{} ({} UTC)", line, at.format("%Y-%m-%d %H:%M:%S")),
+        None => line,
+    }
+}
+
 pub fn print_task_info(task: &ScheduledTask) {
     println!("  ID: {}", task.id);
     println!("  标题: {}", task.title);
@@ -15,7 +48,7 @@ pub fn print_task_info(task: &ScheduledTask) {
     if let Some(ref desc) = task.description {
         println!("  描述: {}", desc);
     }
-    println!("  Cron: {}", task.cron_expression);
+    println!("  Cron: {}", describe_cron(&task.cron_expression, task.timezone.as_deref()));
     println!("  状态: {}", task.status);
     println!("  启用: {}", task.enabled);
     println!("  系统任务: {}", if task.is_system { "是" } else { "否" });
@@ -24,7 +57,7 @@ pub fn print_task_info(task: &ScheduledTask) {
         println!("  上次运行: {}", last_run.format("%Y-%m-%d %H:%M:%S"));
     }
     if let Some(ref next_run) = task.next_run {
-        println!("  下次运行: {}", next_run.format("%Y-%m-%d %H:%M:%S"));
+        println!("  下次运行: {}", format_scheduled(next_run, task.timezone.as_deref()));
     }
     println!("  运行次数: {}", task.run_count);
 }
@@ -39,16 +72,23 @@ pub fn print_task_info_full(task: &ScheduledTask) {
     if let Some(ref content) = task.content {
         println!("内容: {}", content);
     }
-    println!("Cron: {}", task.cron_expression);
+    println!("Cron: {}", describe_cron(&task.cron_expression, task.timezone.as_deref()));
     println!("状态: {}", task.status);
     println!("启用: {}", task.enabled);
     println!("系统任务: {}", if task.is_system { "是" } else { "否" });
+    println!("错过运行: {} (宽限 {} 秒)", task.misfire.policy, task.misfire.grace_secs);
+    print_retry_policy(&task.retry);
+    print_dependencies(&task.depends_on);
+    println!("重叠策略: {}", task.overlap);
+    if !task.tags.is_empty() {
+        println!("标签: {}", task.tags.join(", "));
+    }
     println!("创建时间: {}", task.created_at.format("%Y-%m-%d %H:%M:%S"));
     if let Some(ref last_run) = task.last_run {
         println!("上次运行: {}", last_run.format("%Y-%m-%d %H:%M:%S"));
     }
     if let Some(ref next_run) = task.next_run {
-        println!("下次运行: {}", next_run.format("%Y-%m-%d %H:%M:%S"));
+        println!("下次运行: {}", format_scheduled(next_run, task.timezone.as_deref()));
     }
     println!("运行次数: {}", task.run_count);
 }
@@ -57,6 +97,9 @@ pub fn print_instance_info(instance: &TaskRunInstance) {
     println!("  实例ID: {}", instance.id);
     println!("  任务ID: {}", instance.task_id);
     println!("  状态: {}", instance.status);
+    if let Some(logical_run_id) = instance.logical_run_id {
+        println!("  尝试: 第 {} 次 (首次运行 {})", instance.attempt, logical_run_id);
+    }
     println!("  开始时间: {}", instance.started_at.format("%Y-%m-%d %H:%M:%S"));
     if let Some(ref completed) = instance.completed_at {
         println!("  结束时间: {}", completed.format("%Y-%m-%d %H:%M:%S"));
@@ -69,6 +112,27 @@ pub fn print_instance_info(instance: &TaskRunInstance) {
     }
 }
 
+fn print_dependencies(depends_on: &[TaskDependency]) {
+    if depends_on.is_empty() {
+        return;
+    }
+    println!("依赖任务:");
+    for dep in depends_on {
+        println!("  {} (窗口 {} 秒)", dep.task_id, dep.within_secs);
+    }
+}
+
+fn print_retry_policy(retry: &RetryPolicy) {
+    if retry.is_enabled() {
+        println!(
+            "失败重试: 最多 {} 次, 间隔 {}, 条件 {}",
+            retry.max_attempts, retry.backoff, retry.retry_on
+        );
+    } else {
+        println!("失败重试: 不重试");
+    }
+}
+
 pub fn print_task_briefing(briefing: &TaskBriefing) {
     println!("═══════════════════════════════════════");
     println!("  任务简报");
@@ -80,20 +144,214 @@ pub fn print_task_briefing(briefing: &TaskBriefing) {
         println!("描述: {}", desc);
     }
     println!("状态: {}", briefing.status);
-    println!("Cron: {}", briefing.cron_expression);
+    println!("Cron: {}", describe_cron(&briefing.cron_expression, briefing.timezone.as_deref()));
     println!("系统任务: {}", if briefing.is_system { "是" } else { "否" });
     println!("创建时间: {}", briefing.created_at.format("%Y-%m-%d %H:%M:%S"));
     if let Some(ref last_run) = briefing.last_run {
         println!("上次运行: {}", last_run.format("%Y-%m-%d %H:%M:%S"));
     }
     if let Some(ref next_run) = briefing.next_run {
-        println!("下次运行: {}", next_run.format("%Y-%m-%d %H:%M:%S"));
+        println!("下次运行: {}", format_scheduled(next_run, briefing.timezone.as_deref()));
     }
     println!("运行次数: {}", briefing.run_count);
     println!("启用: {}", briefing.enabled);
+    println!("错过运行: {} (宽限 {} 秒)", briefing.misfire.policy, briefing.misfire.grace_secs);
+    println!("过期运行: {}", briefing.expired_count);
+    print_retry_policy(&briefing.retry);
+    if briefing.latest_attempts > 0 {
+        println!("最近一次运行尝试: {}/{}", briefing.latest_attempts, briefing.retry.max_attempts);
+    }
+    println!("重叠策略: {}", briefing.overlap);
+    if !briefing.tags.is_empty() {
+        println!("标签: {}", briefing.tags.join(", "));
+    }
+    print_dependencies(&briefing.depends_on);
+    if briefing.skipped_count > 0 {
+        println!("跳过的运行: {}", briefing.skipped_count);
+    }
+    if let Some(ref pruned) = briefing.pruned {
+        print!(
+            "已清理的运行: {} (成功 {}, 失败 {}, 过期 {}, 跳过 {})",
+            pruned.runs, pruned.succeeded, pruned.failed, pruned.expired, pruned.skipped
+        );
+        match (pruned.first_started_at, pruned.last_started_at) {
+            (Some(first), Some(last)) => println!(
+                ", {} 至 {}",
+                first.format("%Y-%m-%d %H:%M:%S"),
+                last.format("%Y-%m-%d %H:%M:%S")
+            ),
+            _ => println!(),
+        }
+    }
+    if !briefing.recent_instances.is_empty() {
+        println!("最近运行:");
+        for instance in &briefing.recent_instances {
+            println!(
+                "  {} {:<9} {} (第 {} 次尝试)",
+                instance.started_at.format("%Y-%m-%d %H:%M:%S"),
+                instance.status.to_string(),
+                instance.id,
+                instance.attempt
+            );
+        }
+    }
     println!("═══════════════════════════════════════");
 }
 
+pub fn print_prune_report(report: &PruneReport) {
+    if report.runs == 0 {
+        println!("没有需要清理的运行记录");
+        return;
+    }
+    let verb = if report.dry_run { "将清理" } else { "✅ 已清理" };
+    println!(
+        "{} {} 个任务的 {} 次运行、{} 条日志，约 {:.1} KB",
+        verb,
+        report.tasks,
+        report.runs,
+        report.logs,
+        report.bytes as f64 / 1024.0
+    );
+    if report.dry_run {
+        println!("(演练模式，未删除任何记录)");
+    }
+}
+
+pub fn print_import_report(report: &ImportReport) {
+    if report.tasks.is_empty() {
+        println!("迁移包中没有任务");
+        return;
+    }
+    println!("{}", if report.dry_run { "导入计划:" } else { "导入结果:" });
+    for task in &report.tasks {
+        let action = match task.action {
+            ImportAction::Create => "新建",
+            ImportAction::Overwrite => "覆盖",
+            ImportAction::Rename => "改名新建",
+            ImportAction::Skip => "跳过 (已存在)",
+        };
+        print!("  {} {} -> {} ({})", action, task.source_id, task.target_id, task.name);
+        if task.is_system {
+            print!(" [系统任务]");
+        }
+        println!();
+    }
+    let imported = report.imported().count();
+    let verb = if report.dry_run { "将导入" } else { "✅ 已导入" };
+    println!(
+        "{} {} 个任务 (跳过 {} 个)、{} 次运行、{} 条日志",
+        verb,
+        imported,
+        report.tasks.len() - imported,
+        report.run_instances,
+        report.logs
+    );
+    if report.dry_run {
+        println!("(演练模式，未写入任何记录)");
+    }
+}
+
+pub fn print_manifest_plan(plan: &ManifestPlan) {
+    for change in &plan.changes {
+        if change.kind == ChangeKind::Unchanged {
+            continue;
+        }
+        match change.task_id {
+            Some(id) => println!("  {} {} ({})", change.kind, change.name, id),
+            None => println!("  {} {}", change.kind, change.name),
+        }
+        for field in &change.fields {
+            match &field.from {
+                Some(from) => println!("      {}: {:?} -> {:?}", field.field, from, field.to),
+                None => println!("      {}: {:?}", field.field, field.to),
+            }
+        }
+    }
+    let summary = plan.summary();
+    println!(
+        "计划: 新建 {} 个, 更新 {} 个, 删除 {} 个, 未变化 {} 个",
+        summary.created, summary.updated, summary.deleted, summary.unchanged
+    );
+}
+
+pub fn print_workflow_run(run: &WorkflowRun) {
+    println!("  运行ID: {}", run.id);
+    println!("  工作流: {} ({})", run.workflow_name, run.workflow_id);
+    println!("  状态: {}", run.status);
+    println!("  开始时间: {}", run.started_at.format("%Y-%m-%d %H:%M:%S"));
+    if let Some(ref completed) = run.completed_at {
+        println!("  结束时间: {}", completed.format("%Y-%m-%d %H:%M:%S"));
+    }
+    println!("  步骤:");
+    for step in &run.steps {
+        print!("    {:<16} {:<9}", step.name, step.status.to_string());
+        if let Some(instance_id) = step.run_instance_id {
+            print!(" {}", instance_id);
+        }
+        if step.attempts > 1 {
+            print!(" (尝试 {} 次)", step.attempts);
+        }
+        println!();
+        if let Some(ref error) = step.error {
+            println!("      错误: {}", error);
+        }
+    }
+}
+
+pub fn print_workflow_briefing(briefing: &WorkflowBriefing) {
+    let workflow = &briefing.workflow;
+    println!("═══════════════════════════════════════");
+    println!("  工作流简报");
+    println!("═══════════════════════════════════════");
+    println!("ID: {}", workflow.id);
+    println!("名称: {}", workflow.name);
+    if let Some(ref desc) = workflow.description {
+        println!("描述: {}", desc);
+    }
+    match &workflow.cron_expression {
+        Some(cron_expression) => println!("Cron: {}", describe_cron(cron_expression, workflow.timezone.as_deref())),
+        None => println!("Cron: 手动运行"),
+    }
+    println!("运行次数: {}", briefing.run_count);
+    println!("步骤:");
+    for step in &workflow.steps {
+        if step.depends_on.is_empty() {
+            println!("  {} -> 任务 {}", step.name, step.task_id);
+        } else {
+            println!("  {} -> 任务 {} (依赖 {})", step.name, step.task_id, step.depends_on.join(", "));
+        }
+    }
+    if let Some(run) = briefing.latest_run() {
+        println!("最近一次运行:");
+        print_workflow_run(run);
+    }
+    if briefing.recent_runs.len() > 1 {
+        println!("最近运行:");
+        for run in &briefing.recent_runs {
+            println!(
+                "  {} {:<9} {}",
+                run.started_at.format("%Y-%m-%d %H:%M:%S"),
+                run.status.to_string(),
+                run.id
+            );
+        }
+    }
+    println!("═══════════════════════════════════════");
+}
+
+pub fn print_schedule_preview(schedule: &ZonedSchedule, runs: &[DateTime<Utc>]) {
+    let timezone = (!schedule.is_utc()).then(|| schedule.timezone().name());
+    println!("Cron: {}", describe_cron(schedule.schedule().source(), timezone.or(Some("UTC"))));
+    if runs.is_empty() {
+        println!("该表达式之后不会再触发");
+        return;
+    }
+    println!("接下来 {} 次触发:", runs.len());
+    for (index, at) in runs.iter().enumerate() {
+        println!("  {:>2}. {}", index + 1, format_preview_run(at, timezone));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,4 +361,22 @@ mod tests {
         assert_eq!(sanitize_task_name("hello-world"), "hello_world");
         assert_eq!(sanitize_task_name("test@123!"), "test_123_");
     }
+
+    #[test]
+    fn test_format_scheduled_in_timezone() {
+        let at = "2024-01-02T01:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(format_scheduled(&at, None), "2024-01-02 01:00:00");
+        assert_eq!(format_scheduled(&at, Some("Asia/Shanghai")), "2024-01-02 09:00:00 Asia/Shanghai");
+        assert_eq!(describe_cron("0 0 9 * * *", Some("Asia/Shanghai")), "0 0 9 * * * (Asia/Shanghai)");
+    }
+
+    #[test]
+    fn test_format_preview_run() {
+        let at = "2024-01-05T23:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(format_preview_run(&at, None), "2024-01-05 23:30:00 周五");
+        assert_eq!(
+            format_preview_run(&at, Some("Asia/Shanghai")),
+            "2024-01-06 07:30:00 Asia/Shanghai 周六 (2024-01-05 23:30:00 UTC)"
+        );
+    }
 }
@@ -0,0 +1,133 @@
+//! 进程取消
+//!
+//! 按 `CommandId` 登记运行中的命令；取消时先向整个进程组发送 SIGTERM，
+//! 宽限期后仍未退出则发送 SIGKILL
+
+use std::collections::HashMap;
+use std::process::ExitStatus;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Child;
+use tokio::sync::{Mutex, Notify};
+
+use crate::CommandId;
+
+/// SIGTERM 与 SIGKILL 之间的默认宽限期
+pub const DEFAULT_KILL_GRACE: Duration = Duration::from_secs(5);
+
+/// 运行中命令的取消登记表
+#[derive(Clone, Default)]
+pub(crate) struct CancelRegistry {
+    commands: Arc<Mutex<HashMap<CommandId, Arc<Notify>>>>,
+}
+
+impl CancelRegistry {
+    /// 登记命令，返回取消信号
+    pub(crate) async fn register(&self, id: CommandId) -> Arc<Notify> {
+        let signal = Arc::new(Notify::new());
+        self.commands.lock().await.insert(id, signal.clone());
+        signal
+    }
+
+    pub(crate) async fn unregister(&self, id: CommandId) {
+        self.commands.lock().await.remove(&id);
+    }
+
+    /// 请求取消，命令不在运行时返回 false
+    pub(crate) async fn cancel(&self, id: CommandId) -> bool {
+        match self.commands.lock().await.get(&id) {
+            Some(signal) => {
+                signal.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 终止子进程及其所在进程组
+///
+/// 子进程以自身 PID 作为进程组 ID 启动，因此信号会送达它派生的所有进程
+pub(crate) async fn terminate(child: &mut Child, grace: Duration) -> std::io::Result<ExitStatus> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let pgid = pid as libc::pid_t;
+        signal_group(pgid, libc::SIGTERM);
+        let status = match tokio::time::timeout(grace, child.wait()).await {
+            Ok(status) => status,
+            Err(_) => {
+                signal_group(pgid, libc::SIGKILL);
+                child.wait().await
+            }
+        };
+        // 组长已退出但组内仍有残留进程
+        if group_alive(pgid) {
+            signal_group(pgid, libc::SIGKILL);
+        }
+        return status;
+    }
+
+    child.start_kill()?;
+    child.wait().await
+}
+
+/// 按进程组 ID 终止由其他进程启动的命令
+///
+/// 用于跨进程停止：先发送 SIGTERM，宽限期内组内进程未全部退出则发送 SIGKILL。
+/// 进程组不存在时返回 false
+pub async fn terminate_process_group(pgid: u32, grace: Duration) -> bool {
+    #[cfg(unix)]
+    {
+        let pgid = pgid as libc::pid_t;
+        if !group_alive(pgid) {
+            return false;
+        }
+        signal_group(pgid, libc::SIGTERM);
+        let deadline = tokio::time::Instant::now() + grace;
+        while group_alive(pgid) && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        if group_alive(pgid) {
+            signal_group(pgid, libc::SIGKILL);
+        }
+        true
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = grace;
+        tokio::process::Command::new("taskkill")
+            .args(["/PID", &pgid.to_string(), "/T", "/F"])
+            .output()
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(unix)]
+fn signal_group(pgid: libc::pid_t, signal: libc::c_int) {
+    // SAFETY: kill 只向给定进程组发送信号，不涉及内存访问
+    unsafe {
+        libc::kill(-pgid, signal);
+    }
+}
+
+#[cfg(unix)]
+fn group_alive(pgid: libc::pid_t) -> bool {
+    // SAFETY: 信号 0 只做存在性检查
+    unsafe { libc::kill(-pgid, 0) == 0 }
+}
+
+/// 进程被信号终止时返回信号编号
+pub(crate) fn exit_signal(status: &ExitStatus) -> Option<i32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = status;
+        None
+    }
+}
@@ -1,13 +1,25 @@
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Command as TokioCommand;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
+mod cancel;
+pub mod llm;
+
+pub use cancel::{terminate_process_group, DEFAULT_KILL_GRACE};
+pub use llm::{CommandToolAdapter, CommandToolLimits};
+
 pub type CommandId = Uuid;
 
+/// 进程被终止后等待读完剩余输出的时限，脱离进程组的派生进程可能一直占用管道
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
 #[derive(Debug, Clone)]
 pub enum ExecutionStatus {
     Pending,
@@ -15,6 +27,7 @@ pub enum ExecutionStatus {
     Completed,
     Failed,
     Timeout,
+    Cancelled,
 }
 
 #[derive(Debug, Clone)]
@@ -53,16 +66,80 @@ pub struct CommandResult {
     pub exit_code: i32,
     pub duration_ms: u64,
     pub success: bool,
+    /// 进程被信号终止时的信号编号
+    pub signal: Option<i32>,
+    /// 是否因超时被终止
+    pub timed_out: bool,
+}
+
+/// 命令执行过程中产生的事件
+///
+/// 输出按行推送，进程结束后以 `Exited` 携带最终结果收尾
+#[derive(Debug, Clone)]
+pub enum CommandEvent {
+    Stdout(String),
+    Stderr(String),
+    Exited(CommandResult),
+}
+
+/// 流式执行句柄
+///
+/// 由 `CommandExecutor::execute_streaming` 返回，逐条产出输出事件
+pub struct CommandHandle {
+    id: CommandId,
+    pid: Option<u32>,
+    events: mpsc::UnboundedReceiver<CommandEvent>,
+}
+
+impl CommandHandle {
+    /// 由命令 ID、进程 ID 和事件通道创建句柄
+    pub fn new(id: CommandId, pid: Option<u32>, events: mpsc::UnboundedReceiver<CommandEvent>) -> Self {
+        Self { id, pid, events }
+    }
+
+    pub fn id(&self) -> CommandId {
+        self.id
+    }
+
+    /// 子进程 ID，Unix 下同时也是其进程组 ID
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// 获取下一个事件，`Exited` 之后返回 None
+    pub async fn next_event(&mut self) -> Option<CommandEvent> {
+        self.events.recv().await
+    }
+
+    /// 丢弃中间输出，等待最终结果
+    pub async fn wait(mut self) -> Result<CommandResult, Box<dyn std::error::Error + Send + Sync>> {
+        while let Some(event) = self.next_event().await {
+            if let CommandEvent::Exited(result) = event {
+                return Ok(result);
+            }
+        }
+        Err(format!("Command {} ended without exit status", self.id).into())
+    }
 }
 
 #[async_trait]
 pub trait CommandExecutor: Send + Sync {
     async fn execute(&self, command: Command) -> Result<CommandResult, Box<dyn std::error::Error + Send + Sync>>;
+    /// 启动命令并返回流式句柄，stdout/stderr 在产生时即按行推送
+    async fn execute_streaming(&self, command: Command) -> Result<CommandHandle, Box<dyn std::error::Error + Send + Sync>>;
     async fn execute_batch(&self, commands: Vec<Command>) -> Vec<CommandResult>;
     async fn is_available(&self, program: &str) -> bool;
+    /// 取消运行中的命令，终止其整个进程组
+    async fn cancel(&self, id: CommandId) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 }
 
-pub struct LocalCommandExecutor;
+#[derive(Clone)]
+pub struct LocalCommandExecutor {
+    running: cancel::CancelRegistry,
+    kill_grace: std::time::Duration,
+    /// shell 模式使用的 shell，None 时使用平台默认
+    shell: Option<String>,
+}
 
 impl Default for LocalCommandExecutor {
     fn default() -> Self {
@@ -72,82 +149,118 @@ impl Default for LocalCommandExecutor {
 
 impl LocalCommandExecutor {
     pub fn new() -> Self {
-        Self
+        Self {
+            running: cancel::CancelRegistry::default(),
+            kill_grace: DEFAULT_KILL_GRACE,
+            shell: None,
+        }
+    }
+
+    /// 设置 SIGTERM 之后等待进程退出的宽限期
+    pub fn with_kill_grace(mut self, grace: std::time::Duration) -> Self {
+        self.kill_grace = grace;
+        self
+    }
+
+    /// 设置 shell 模式使用的 shell，如 `bash`、`pwsh`
+    pub fn with_shell(mut self, shell: impl Into<String>) -> Self {
+        self.shell = Some(shell.into());
+        self
     }
 }
 
+/// 子进程的结束方式
+enum Outcome {
+    Exited(std::io::Result<std::process::ExitStatus>),
+    TimedOut,
+    Cancelled,
+}
+
 #[async_trait]
 impl CommandExecutor for LocalCommandExecutor {
-    async fn execute(&self, mut cmd: Command) -> Result<CommandResult, Box<dyn std::error::Error + Send + Sync>> {
+    async fn execute(&self, cmd: Command) -> Result<CommandResult, Box<dyn std::error::Error + Send + Sync>> {
+        self.execute_streaming(cmd).await?.wait().await
+    }
+
+    async fn execute_streaming(&self, mut cmd: Command) -> Result<CommandHandle, Box<dyn std::error::Error + Send + Sync>> {
         let start = Instant::now();
         cmd.status = ExecutionStatus::Running;
 
-        let program = cmd.program.clone();
-        let args = cmd.args.clone();
         let timeout = cmd.environment.timeout_secs;
-        let use_shell = cmd.environment.use_shell;
-
-        let mut tokio_cmd = if use_shell {
-            let shell_cmd = if cfg!(windows) { "cmd" } else { "sh" };
-            let mut full_args = vec!["/c".to_string()];
-            full_args.extend(args);
-            let mut cmd = TokioCommand::new(shell_cmd);
-            cmd.args(&full_args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-            cmd
-        } else {
-            let mut cmd = TokioCommand::new(&program);
-            cmd.args(&args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
-            cmd
-        };
+        let mut tokio_cmd = build_process(&cmd, self.shell.as_deref());
+        tokio_cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        // 子进程自成进程组，取消时可以连同其派生进程一起终止
+        #[cfg(unix)]
+        tokio_cmd.process_group(0);
 
-        if let Some(dir) = &cmd.environment.working_dir {
-            tokio_cmd.current_dir(dir);
-        }
+        let mut child = tokio_cmd.spawn().map_err(|e| e.to_string())?;
+        let pid = child.id();
+        let (tx, rx) = mpsc::unbounded_channel();
 
-        for (key, value) in &cmd.environment.env_vars {
-            tokio_cmd.env(key, value);
-        }
+        let stdout_reader = child
+            .stdout
+            .take()
+            .map(|out| OutputReader::spawn(out, tx.clone(), CommandEvent::Stdout));
+        let stderr_reader = child
+            .stderr
+            .take()
+            .map(|err| OutputReader::spawn(err, tx.clone(), CommandEvent::Stderr));
 
-        let handle = tokio_cmd.spawn().map_err(|e| e.to_string())?;
+        let command_id = cmd.id;
+        let cancel_signal = self.running.register(command_id).await;
+        let running = self.running.clone();
+        let kill_grace = self.kill_grace;
+        tokio::spawn(async move {
+            let deadline = async {
+                match timeout {
+                    Some(secs) => tokio::time::sleep(tokio::time::Duration::from_secs(secs)).await,
+                    None => std::future::pending().await,
+                }
+            };
+            let outcome = tokio::select! {
+                status = child.wait() => Outcome::Exited(status),
+                _ = deadline => Outcome::TimedOut,
+                _ = cancel_signal.notified() => Outcome::Cancelled,
+            };
 
-        let output_result = if let Some(timeout_secs) = timeout {
-            let timeout_duration = tokio::time::Duration::from_secs(timeout_secs);
-            tokio::time::timeout(timeout_duration, handle.wait_with_output()).await
-        } else {
-            Ok(handle.wait_with_output().await)
-        };
+            let timed_out = matches!(outcome, Outcome::TimedOut);
+            let (status, notice) = match outcome {
+                Outcome::Exited(status) => (status, None),
+                Outcome::TimedOut => (cancel::terminate(&mut child, kill_grace).await, Some("Command timeout")),
+                Outcome::Cancelled => (cancel::terminate(&mut child, kill_grace).await, Some("Command cancelled")),
+            };
+            running.unregister(command_id).await;
 
-        let output = match output_result {
-            Ok(Ok(o)) => o,
-            Ok(Err(e)) => return Err(e.to_string().into()),
-            Err(_) => {
-                // Timeout occurred - note: process may still be running
-                return Ok(CommandResult {
-                    command_id: cmd.id,
-                    stdout: String::new(),
-                    stderr: "Command timeout".to_string(),
-                    exit_code: -1,
-                    duration_ms: timeout.unwrap_or(0) * 1000,
-                    success: false,
-                });
+            // 进程退出后读完剩余输出；被终止时只等待一小段时间
+            let drain = notice.map(|_| OUTPUT_DRAIN_TIMEOUT);
+            let stdout = join_reader(stdout_reader, drain).await;
+            let mut stderr = join_reader(stderr_reader, drain).await;
+            if let Some(notice) = notice {
+                let _ = tx.send(CommandEvent::Stderr(notice.to_string()));
+                // 提示单独成行，不与子进程最后一行未换行的输出相连
+                if !stderr.is_empty() && !stderr.ends_with('\n') {
+                    stderr.push('\n');
+                }
+                stderr.push_str(notice);
             }
-        };
 
-        let duration = start.elapsed().as_millis() as u64;
-        let exit_code = output.status.code().unwrap_or(-1);
+            let status = status.ok();
+            let exit_code = status.and_then(|s| s.code()).unwrap_or(-1);
+            let result = CommandResult {
+                command_id,
+                stdout,
+                stderr,
+                exit_code,
+                duration_ms: start.elapsed().as_millis() as u64,
+                success: notice.is_none() && exit_code == 0,
+                signal: status.as_ref().and_then(cancel::exit_signal),
+                timed_out,
+            };
 
-        Ok(CommandResult {
-            command_id: cmd.id,
-            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-            exit_code,
-            duration_ms: duration,
-            success: exit_code == 0,
-        })
+            let _ = tx.send(CommandEvent::Exited(result));
+        });
+
+        Ok(CommandHandle::new(command_id, pid, rx))
     }
 
     async fn execute_batch(&self, commands: Vec<Command>) -> Vec<CommandResult> {
@@ -164,6 +277,8 @@ impl CommandExecutor for LocalCommandExecutor {
                         exit_code: -1,
                         duration_ms: 0,
                         success: false,
+                        signal: None,
+                        timed_out: false,
                     });
                 }
             }
@@ -179,6 +294,129 @@ impl CommandExecutor for LocalCommandExecutor {
             .map(|o| o.status.success())
             .unwrap_or(false)
     }
+
+    async fn cancel(&self, id: CommandId) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if self.running.cancel(id).await {
+            Ok(())
+        } else {
+            Err(format!("Command {} is not running", id).into())
+        }
+    }
+}
+
+/// 构建子进程命令
+///
+/// shell 模式下将程序和参数拼成一行交给 shell，默认为 `sh -c` (Windows 下为 `cmd /C`)
+fn build_process(cmd: &Command, shell: Option<&str>) -> TokioCommand {
+    let mut process = if cmd.environment.use_shell {
+        let line = std::iter::once(cmd.program.as_str())
+            .chain(cmd.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let shell = shell.unwrap_or(if cfg!(windows) { "cmd" } else { "sh" });
+        let mut process = TokioCommand::new(shell);
+        process.arg(shell_flag(shell)).arg(line);
+        process
+    } else {
+        let mut process = TokioCommand::new(&cmd.program);
+        process.args(&cmd.args);
+        process
+    };
+
+    if let Some(dir) = &cmd.environment.working_dir {
+        process.current_dir(dir);
+    }
+    for (key, value) in &cmd.environment.env_vars {
+        process.env(key, value);
+    }
+    process
+}
+
+/// shell 执行一行命令的参数
+fn shell_flag(shell: &str) -> &'static str {
+    let name = Path::new(shell)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match name.as_str() {
+        "cmd" => "/C",
+        "powershell" | "pwsh" => "-Command",
+        _ => "-c",
+    }
+}
+
+/// 读取一路输出的任务及其已读到的内容
+struct OutputReader {
+    task: tokio::task::JoinHandle<()>,
+    collected: Arc<Mutex<Vec<u8>>>,
+}
+
+impl OutputReader {
+    fn spawn<R>(reader: R, tx: mpsc::UnboundedSender<CommandEvent>, wrap: fn(String) -> CommandEvent) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let collected = Arc::new(Mutex::new(Vec::new()));
+        let task = tokio::spawn(forward_lines(reader, tx, wrap, collected.clone()));
+        Self { task, collected }
+    }
+}
+
+/// 按行读取输出并推送事件，同时累计完整输出
+///
+/// 读到的内容立即累计，读取被放弃时未换行的部分也不会丢失
+async fn forward_lines<R>(
+    reader: R,
+    tx: mpsc::UnboundedSender<CommandEvent>,
+    wrap: fn(String) -> CommandEvent,
+    collected: Arc<Mutex<Vec<u8>>>,
+) where
+    R: AsyncRead + Unpin,
+{
+    let send = |line: &[u8]| {
+        let line = String::from_utf8_lossy(line);
+        let _ = tx.send(wrap(line.trim_end_matches(['\r', '\n']).to_string()));
+    };
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        let chunk = match reader.fill_buf().await {
+            Ok([]) | Err(_) => break,
+            Ok(chunk) => chunk,
+        };
+        collected.lock().expect("output lock poisoned").extend_from_slice(chunk);
+        let len = chunk.len();
+        let mut rest = chunk;
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            line.extend_from_slice(&rest[..=end]);
+            send(&line);
+            line.clear();
+            rest = &rest[end + 1..];
+        }
+        line.extend_from_slice(rest);
+        reader.consume(len);
+    }
+    if !line.is_empty() {
+        send(&line);
+    }
+}
+
+/// 等待读取结束并返回输出
+///
+/// 给出 `limit` 时最多等待这么久，到期后放弃读取 (关闭管道的读端)，返回已读到的部分
+async fn join_reader(reader: Option<OutputReader>, limit: Option<Duration>) -> String {
+    let Some(OutputReader { mut task, collected }) = reader else {
+        return String::new();
+    };
+    let finished = match limit {
+        Some(limit) => tokio::time::timeout(limit, &mut task).await.is_ok(),
+        None => (&mut task).await.is_ok(),
+    };
+    if !finished {
+        task.abort();
+    }
+    let output = collected.lock().expect("output lock poisoned");
+    String::from_utf8_lossy(&output).into_owned()
 }
 
 #[cfg(test)]
@@ -217,6 +455,99 @@ mod tests {
         // should timeout
     }
 
+    /// 进程存在且不是僵尸进程
+    #[cfg(target_os = "linux")]
+    fn process_alive(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| !stat.contains(") Z"))
+            .unwrap_or(false)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_timeout_kills_process_group() {
+        let executor = LocalCommandExecutor::new();
+        let mut cmd = create_test_command();
+        // 后台子进程与 shell 同组，超时后应一并终止
+        cmd.program = "printf partial >&2; sleep 30 & echo $!; wait".to_string();
+        cmd.args.clear();
+        cmd.environment.use_shell = true;
+        cmd.environment.timeout_secs = Some(1);
+
+        let start = Instant::now();
+        let result = executor.execute(cmd).await.unwrap();
+        assert!(start.elapsed().as_secs() < 10);
+        assert!(!result.success);
+        assert_eq!(result.signal, Some(libc::SIGTERM));
+        assert!(result.stderr.ends_with("partial\nCommand timeout"));
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+        assert!(!process_alive(result.stdout.trim()));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancel_escalates_to_sigkill() {
+        let executor = LocalCommandExecutor::new().with_kill_grace(std::time::Duration::from_millis(300));
+        let mut cmd = create_test_command();
+        cmd.program = "trap '' TERM; printf partial >&2; echo ready; sleep 30".to_string();
+        cmd.args.clear();
+        cmd.environment.use_shell = true;
+
+        let mut handle = executor.execute_streaming(cmd).await.unwrap();
+        let id = handle.id();
+        // 等待 trap 生效后再取消
+        assert!(matches!(handle.next_event().await, Some(CommandEvent::Stdout(_))));
+        executor.cancel(id).await.unwrap();
+
+        let result = handle.wait().await.unwrap();
+        assert_eq!(result.signal, Some(libc::SIGKILL));
+        assert!(result.stderr.ends_with("partial\nCommand cancelled"));
+        assert!(executor.cancel(id).await.is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_timeout_with_detached_grandchild() {
+        let executor = LocalCommandExecutor::new();
+        let mut cmd = create_test_command();
+        // setsid 的派生进程离开了进程组，终止后仍持有输出管道
+        cmd.program = "printf partial >&2; setsid sleep 30 & echo $!; wait".to_string();
+        cmd.args.clear();
+        cmd.environment.use_shell = true;
+        cmd.environment.timeout_secs = Some(1);
+
+        let start = Instant::now();
+        let result = executor.execute(cmd).await.unwrap();
+        assert!(start.elapsed().as_secs() < 5);
+        assert!(result.timed_out);
+        assert!(result.stderr.ends_with("partial\nCommand timeout"));
+
+        let pid: i32 = result.stdout.trim().parse().unwrap();
+        assert!(process_alive(&pid.to_string()));
+        unsafe {
+            libc::kill(pid, libc::SIGKILL);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_terminate_process_group_by_pid() {
+        let executor = LocalCommandExecutor::new();
+        let mut cmd = create_test_command();
+        cmd.program = "sleep 30".to_string();
+        cmd.args.clear();
+        cmd.environment.use_shell = true;
+
+        let handle = executor.execute_streaming(cmd).await.unwrap();
+        let pid = handle.pid().unwrap();
+        assert!(terminate_process_group(pid, std::time::Duration::from_secs(1)).await);
+
+        let result = handle.wait().await.unwrap();
+        assert_eq!(result.signal, Some(libc::SIGTERM));
+        assert!(!terminate_process_group(pid, std::time::Duration::from_secs(1)).await);
+    }
+
     #[tokio::test]
     async fn test_execute_batch() {
         let executor = LocalCommandExecutor::new();
@@ -225,6 +556,58 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_execute_streaming_lines() {
+        let executor = LocalCommandExecutor::new();
+        let mut cmd = create_test_command();
+        cmd.program = "echo one; echo two >&2; echo three".to_string();
+        cmd.args.clear();
+        cmd.environment.use_shell = true;
+
+        let mut handle = executor.execute_streaming(cmd).await.unwrap();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut exit = None;
+        while let Some(event) = handle.next_event().await {
+            match event {
+                CommandEvent::Stdout(line) => stdout.push(line),
+                CommandEvent::Stderr(line) => stderr.push(line),
+                CommandEvent::Exited(result) => exit = Some(result),
+            }
+        }
+
+        assert_eq!(stdout, vec!["one", "three"]);
+        assert_eq!(stderr, vec!["two"]);
+        let result = exit.unwrap();
+        assert!(result.success);
+        assert_eq!(result.stdout, "one\nthree\n");
+    }
+
+    #[tokio::test]
+    async fn test_shell_mode_keeps_program() {
+        let executor = LocalCommandExecutor::new();
+        let mut cmd = create_test_command();
+        cmd.environment.use_shell = true;
+        let result = executor.execute(cmd).await.unwrap();
+        assert_eq!(result.stdout.trim(), "hello");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_configured_shell() {
+        let executor = LocalCommandExecutor::new().with_shell("/bin/bash");
+        let mut cmd = create_test_command();
+        cmd.program = "echo $BASH_VERSION".to_string();
+        cmd.args.clear();
+        cmd.environment.use_shell = true;
+        let result = executor.execute(cmd).await.unwrap();
+        assert!(!result.stdout.trim().is_empty());
+
+        assert_eq!(shell_flag("cmd.exe"), "/C");
+        assert_eq!(shell_flag("pwsh"), "-Command");
+        assert_eq!(shell_flag("zsh"), "-c");
+    }
+
     #[tokio::test]
     async fn test_is_available() {
         let executor = LocalCommandExecutor::new();
@@ -0,0 +1,251 @@
+//! LLM 工具适配器
+//!
+//! 通过 `run_command` 工具执行命令，超时和输出大小受 [`CommandToolLimits`] 限制
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tools::{Arguments, CallToolRequest, CallToolResponse, Tool, ToolError, ToolProvider, ToolResult};
+use uuid::Uuid;
+
+use crate::{Command, CommandExecutor, ExecutionEnvironment, ExecutionStatus};
+
+/// 工具执行命令的限制
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandToolLimits {
+    /// 未指定超时时使用的超时(秒)
+    pub default_timeout_secs: u64,
+    /// 允许的最大超时(秒)
+    pub max_timeout_secs: u64,
+    /// stdout 和 stderr 各自保留的最大字节数，超出时保留末尾
+    pub max_output_bytes: usize,
+}
+
+impl Default for CommandToolLimits {
+    fn default() -> Self {
+        Self {
+            default_timeout_secs: 30,
+            max_timeout_secs: 300,
+            max_output_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// 命令执行工具适配器
+pub struct CommandToolAdapter<E: CommandExecutor> {
+    executor: Arc<E>,
+    limits: CommandToolLimits,
+}
+
+impl<E: CommandExecutor> CommandToolAdapter<E> {
+    pub fn new(executor: Arc<E>) -> Self {
+        Self {
+            executor,
+            limits: CommandToolLimits::default(),
+        }
+    }
+
+    /// 设置执行限制
+    pub fn with_limits(mut self, limits: CommandToolLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// 获取所有工具定义
+    pub fn get_tools(&self) -> Vec<Tool> {
+        vec![Tool {
+            name: "run_command".to_string(),
+            description: format!(
+                "Run a command and return its exit code and output. Timeout defaults to {}s and is capped at {}s; output beyond {} bytes is truncated to its tail.",
+                self.limits.default_timeout_secs, self.limits.max_timeout_secs, self.limits.max_output_bytes
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "program": {
+                        "type": "string",
+                        "description": "Program to run, or a full command line when shell is true"
+                    },
+                    "args": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Program arguments"
+                    },
+                    "shell": {
+                        "type": "boolean",
+                        "description": "Run through sh -c (cmd /C on Windows)"
+                    },
+                    "working_dir": {
+                        "type": "string",
+                        "description": "Working directory"
+                    },
+                    "env": {
+                        "type": "object",
+                        "additionalProperties": {"type": "string"},
+                        "description": "Extra environment variables"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": self.limits.max_timeout_secs,
+                        "description": "Timeout in seconds"
+                    }
+                },
+                "required": ["program"]
+            }),
+        }]
+    }
+
+    /// 调用工具
+    pub async fn call_tool(&self, request: CallToolRequest) -> CallToolResponse {
+        let result = match request.name.as_str() {
+            "run_command" => self.call_run_command(&request.arguments).await,
+            _ => Err(ToolError::UnknownTool(request.name)),
+        };
+        CallToolResponse::from_result(result)
+    }
+
+    async fn call_run_command(&self, arguments: &Value) -> ToolResult<String> {
+        let args = Arguments::new(arguments)?;
+        let timeout_secs = match args.u64("timeout_secs")? {
+            Some(0) => return Err(ToolError::InvalidArguments("timeout_secs must be at least 1".to_string())),
+            Some(secs) if secs > self.limits.max_timeout_secs => {
+                return Err(ToolError::InvalidArguments(format!(
+                    "timeout_secs must not exceed {}",
+                    self.limits.max_timeout_secs
+                )))
+            }
+            Some(secs) => secs,
+            None => self.limits.default_timeout_secs,
+        };
+
+        let command = Command {
+            id: Uuid::new_v4(),
+            program: args.required_str("program")?.to_string(),
+            args: args.strings("args")?,
+            environment: ExecutionEnvironment {
+                working_dir: args.str("working_dir")?.map(PathBuf::from),
+                env_vars: args.string_map("env")?,
+                timeout_secs: Some(timeout_secs),
+                use_shell: args.bool("shell")?.unwrap_or(false),
+            },
+            status: ExecutionStatus::Pending,
+        };
+
+        let result = self
+            .executor
+            .execute(command)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+        let (stdout, stdout_truncated) = tail(&result.stdout, self.limits.max_output_bytes);
+        let (stderr, stderr_truncated) = tail(&result.stderr, self.limits.max_output_bytes);
+
+        let output = json!({
+            "exit_code": result.exit_code,
+            "success": result.success,
+            "timed_out": result.timed_out,
+            "signal": result.signal,
+            "duration_ms": result.duration_ms,
+            "stdout": stdout,
+            "stderr": stderr,
+            "truncated": stdout_truncated || stderr_truncated,
+        });
+        Ok(serde_json::to_string_pretty(&output).unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl<E: CommandExecutor + 'static> ToolProvider for CommandToolAdapter<E> {
+    fn tools(&self) -> Vec<Tool> {
+        self.get_tools()
+    }
+
+    async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+        self.call_tool(request).await
+    }
+}
+
+/// 保留末尾不超过 `max_bytes` 字节的内容，不截断 UTF-8 字符
+fn tail(text: &str, max_bytes: usize) -> (&str, bool) {
+    if text.len() <= max_bytes {
+        return (text, false);
+    }
+    let mut start = text.len() - max_bytes;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    (&text[start..], true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocalCommandExecutor;
+
+    fn adapter() -> CommandToolAdapter<LocalCommandExecutor> {
+        CommandToolAdapter::new(Arc::new(LocalCommandExecutor::new())).with_limits(CommandToolLimits {
+            default_timeout_secs: 5,
+            max_timeout_secs: 10,
+            max_output_bytes: 8,
+        })
+    }
+
+    async fn call(adapter: &CommandToolAdapter<LocalCommandExecutor>, arguments: Value) -> CallToolResponse {
+        adapter
+            .call_tool(CallToolRequest {
+                name: "run_command".to_string(),
+                arguments,
+            })
+            .await
+    }
+
+    fn output(response: &CallToolResponse) -> Value {
+        serde_json::from_str(response.content[0].text.as_deref().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_run_command_truncates_output() {
+        let adapter = adapter();
+        let response = call(&adapter, json!({"program": "echo", "args": ["0123456789abcdef"]})).await;
+        assert!(response.success);
+        let result = output(&response);
+        assert_eq!(result["exit_code"], 0);
+        assert_eq!(result["stdout"], "9abcdef\n");
+        assert_eq!(result["truncated"], true);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_command_shell_env_and_timeout() {
+        let adapter = adapter();
+        let response = call(&adapter, json!({"program": "echo $X; exit 3", "shell": true, "env": {"X": "hi"}})).await;
+        let result = output(&response);
+        assert_eq!(result["stdout"], "hi\n");
+        assert_eq!(result["exit_code"], 3);
+        assert_eq!(result["success"], false);
+
+        let response = call(&adapter, json!({"program": "sleep 5", "shell": true, "timeout_secs": 1})).await;
+        assert_eq!(output(&response)["timed_out"], true);
+    }
+
+    #[tokio::test]
+    async fn test_run_command_rejects_invalid_arguments() {
+        let adapter = adapter();
+        let response = call(&adapter, json!({"program": "echo", "timeout_secs": 60})).await;
+        assert_eq!(response.error.as_deref(), Some("Invalid arguments: timeout_secs must not exceed 10"));
+
+        let response = call(&adapter, json!({"args": ["x"]})).await;
+        assert_eq!(response.error.as_deref(), Some("Invalid arguments: missing required argument: program"));
+
+        let response = call(&adapter, json!({"program": "nonexistent_command_xyz"})).await;
+        assert!(response.error.unwrap().starts_with("Tool execution failed"));
+    }
+
+    #[test]
+    fn test_tail_respects_char_boundary() {
+        assert_eq!(tail("短文本", 64), ("短文本", false));
+        assert_eq!(tail("ab任务", 4), ("务", true));
+    }
+}
@@ -1,13 +1,35 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+pub mod loader;
+
+pub use loader::{
+    find_project_file, user_config_file, ConfigError, ConfigFile, ConfigLoader, ConfigResult, ENV_PREFIX,
+    PROJECT_FILE_NAME,
+};
+
+/// 应用配置
+///
+/// 所有字段都有默认值，配置文件只需写出要修改的项；未知的键会被拒绝
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct AppConfig {
     pub cli: CliConfig,
     pub executor: ExecutorConfig,
     pub scheduler: SchedulerConfig,
     pub storage: StorageConfig,
     pub voice: VoiceConfig,
+    pub daemon: DaemonConfig,
+    /// 任务结果通知
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    /// LLM 工具调用策略
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    /// MCP 服务
+    #[serde(default)]
+    pub mcp: McpConfig,
 }
 
 impl Default for AppConfig {
@@ -18,11 +40,16 @@ impl Default for AppConfig {
             scheduler: SchedulerConfig::default(),
             storage: StorageConfig::default(),
             voice: VoiceConfig::default(),
+            daemon: DaemonConfig::default(),
+            notifications: NotificationConfig::default(),
+            policy: PolicyConfig::default(),
+            mcp: McpConfig::default(),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct CliConfig {
     pub prompt: String,
     pub colors: bool,
@@ -40,10 +67,17 @@ impl Default for CliConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ExecutorConfig {
+    /// `sker run` 和命令工具的默认超时，0 表示不限制
     pub default_timeout_secs: u64,
+    /// shell 模式使用的 shell
     pub shell: String,
+    /// 同时运行的定时任务上限，0 表示不限制
     pub max_concurrent: usize,
+    /// 按任务标签的并发上限
+    #[serde(default)]
+    pub tag_limits: HashMap<String, usize>,
 }
 
 impl Default for ExecutorConfig {
@@ -52,36 +86,72 @@ impl Default for ExecutorConfig {
             default_timeout_secs: 30,
             shell: if cfg!(windows) { "cmd".to_string() } else { "sh".to_string() },
             max_concurrent: 10,
+            tag_limits: HashMap::new(),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct SchedulerConfig {
+    /// 任务数量上限，0 表示不限制
     pub max_tasks: usize,
-    pub persistence_path: PathBuf,
+    /// 新建任务和工作流默认使用的 IANA 时区，如 `Asia/Shanghai`
+    pub timezone: String,
+    /// 运行记录保留策略
+    pub retention: RetentionConfig,
 }
 
 impl Default for SchedulerConfig {
     fn default() -> Self {
         Self {
             max_tasks: 100,
-            persistence_path: PathBuf::from("~/.config/rust-agent/tasks.json"),
+            timezone: "UTC".to_string(),
+            retention: RetentionConfig::default(),
+        }
+    }
+}
+
+/// 运行记录保留策略，超出任一限制的已结束运行连同日志被清理，各项为 0 表示不限制
+///
+/// 清理的运行汇总为按任务的统计，任务简报仍包含这些运行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// 保留天数
+    pub max_age_days: u64,
+    /// 每个任务保留的运行数
+    pub max_runs_per_task: usize,
+    /// 运行记录和日志的总大小上限 (MB)
+    pub max_size_mb: u64,
+    /// 守护进程后台清理的间隔 (分钟)，0 表示不自动清理
+    pub interval_minutes: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: 30,
+            max_runs_per_task: 200,
+            max_size_mb: 256,
+            interval_minutes: 60,
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct StorageConfig {
     pub backend: StorageBackend,
+    /// 调度器数据目录
     pub path: PathBuf,
 }
 
 impl Default for StorageConfig {
     fn default() -> Self {
         Self {
-            backend: StorageBackend::Json,
-            path: PathBuf::from("~/.config/rust-agent/storage.json"),
+            backend: StorageBackend::Sled,
+            path: PathBuf::from("~/.sker/scheduler"),
         }
     }
 }
@@ -89,12 +159,14 @@ impl Default for StorageConfig {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum StorageBackend {
+    Sled,
     Json,
     Sqlite,
     Memory,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
 pub struct VoiceConfig {
     pub enabled: bool,
     pub language: String,
@@ -111,6 +183,221 @@ impl Default for VoiceConfig {
     }
 }
 
+/// 守护进程配置，修改后由运行中的守护进程自动重新加载
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    /// 日志级别，支持 `RUST_LOG` 语法，如 `info,task_scheduler=debug`
+    pub log_level: String,
+    /// 日志文件超过该大小 (MB) 后轮转，0 表示不轮转
+    pub log_max_size_mb: u64,
+    /// 保留的已轮转日志数，旧日志以 gzip 压缩
+    pub log_keep: usize,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            log_level: "info".to_string(),
+            log_max_size_mb: 10,
+            log_keep: 5,
+        }
+    }
+}
+
+/// 任务结果通知配置
+///
+/// `sinks` 按名称定义发送目标，`routes` 决定哪些任务的哪些结果发往哪些目标
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub sinks: HashMap<String, NotificationSinkConfig>,
+    #[serde(default)]
+    pub routes: Vec<NotificationRouteConfig>,
+}
+
+/// 通知发送目标，模板中可使用 `{{task_name}}`、`{{error}}` 等变量
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationSinkConfig {
+    /// HTTP webhook，`body` 为 JSON 请求体模板，为空时发送完整的通知 JSON
+    Webhook {
+        url: String,
+        #[serde(default = "default_webhook_method")]
+        method: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        body: Option<String>,
+        #[serde(default = "default_webhook_timeout_secs")]
+        timeout_secs: u64,
+    },
+    /// SMTP 邮件 (不支持 TLS)
+    Smtp {
+        host: String,
+        #[serde(default = "default_smtp_port")]
+        port: u16,
+        from: String,
+        to: Vec<String>,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<String>,
+        #[serde(default)]
+        subject: Option<String>,
+        #[serde(default)]
+        body: Option<String>,
+    },
+    /// 桌面通知
+    Desktop {
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        body: Option<String>,
+    },
+    /// 追加到文件，`template` 为空时每行写入通知 JSON
+    File {
+        path: PathBuf,
+        #[serde(default)]
+        template: Option<String>,
+    },
+}
+
+fn default_webhook_method() -> String {
+    "POST".to_string()
+}
+
+fn default_webhook_timeout_secs() -> u64 {
+    10
+}
+
+fn default_smtp_port() -> u16 {
+    25
+}
+
+fn default_notify_on() -> Vec<String> {
+    vec!["failure".to_string(), "error".to_string()]
+}
+
+/// 通知路由规则
+///
+/// `tasks` (任务 ID 或名称) 和 `tags` 都为空时匹配所有任务；
+/// `on` 为 success、failure、error 的组合，默认 failure 和 error
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationRouteConfig {
+    #[serde(default)]
+    pub tasks: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default = "default_notify_on")]
+    pub on: Vec<String>,
+    pub sinks: Vec<String>,
+}
+
+/// LLM 工具调用策略
+///
+/// `rules` 按顺序匹配，第一条匹配的规则生效；没有规则匹配时使用 `default`。
+/// 效果为 allow、deny 或 confirm，confirm 的调用需要用户执行 `sker policy approve` 后才能执行
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyConfig {
+    /// 是否启用策略检查
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 没有规则匹配时的效果
+    #[serde(default = "default_policy_effect")]
+    pub default: String,
+    /// 演练模式：通过检查的调用只记录不执行
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 审计日志路径
+    #[serde(default = "default_audit_log")]
+    pub audit_log: PathBuf,
+    /// 确认请求的有效期(小时)
+    #[serde(default = "default_approval_ttl_hours")]
+    pub approval_ttl_hours: u64,
+    #[serde(default = "default_policy_rules")]
+    pub rules: Vec<PolicyRuleConfig>,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default: default_policy_effect(),
+            dry_run: false,
+            audit_log: default_audit_log(),
+            approval_ttl_hours: default_approval_ttl_hours(),
+            rules: default_policy_rules(),
+        }
+    }
+}
+
+/// MCP 服务配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct McpConfig {
+    /// HTTP 传输的访问令牌，客户端在每个请求中以 `Authorization: Bearer <令牌>` 携带；
+    /// 未设置时只允许监听本机地址
+    pub token: String,
+}
+
+/// 策略规则，`tool` 和 `arguments` 中的模式都是 glob
+///
+/// `arguments` 按参数名给出模式列表，如 `{ content = ["echo *", "backup.sh*"] }`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRuleConfig {
+    pub tool: String,
+    pub effect: String,
+    #[serde(default)]
+    pub arguments: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+impl PolicyRuleConfig {
+    pub fn new(tool: &str, effect: &str, reason: &str) -> Self {
+        Self {
+            tool: tool.to_string(),
+            effect: effect.to_string(),
+            arguments: HashMap::new(),
+            reason: Some(reason.to_string()),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_policy_effect() -> String {
+    "allow".to_string()
+}
+
+fn default_audit_log() -> PathBuf {
+    PathBuf::from("~/.sker/policy/audit.log")
+}
+
+fn default_approval_ttl_hours() -> u64 {
+    24
+}
+
+/// 默认对破坏性的工具，以及新建或修改定时运行的命令的工具要求人工确认
+fn default_policy_rules() -> Vec<PolicyRuleConfig> {
+    vec![
+        PolicyRuleConfig::new("add_task", "confirm", "a scheduled task runs its command unattended"),
+        PolicyRuleConfig::new("update_task", "confirm", "updating a task can change the command it runs and when"),
+        PolicyRuleConfig::new("add_workflow", "confirm", "a workflow runs its tasks unattended on its own schedule"),
+        PolicyRuleConfig::new("remove_task", "confirm", "removing a task deletes its history"),
+        PolicyRuleConfig::new("stop_task", "confirm", "stopping a task kills its running process"),
+        PolicyRuleConfig::new("run_command", "confirm", "running arbitrary commands needs human approval"),
+        PolicyRuleConfig::new("power_suspend", "confirm", "suspending the machine interrupts running work"),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,6 +423,58 @@ mod tests {
         let config = ExecutorConfig::default();
         assert_eq!(config.default_timeout_secs, 30);
         assert_eq!(config.max_concurrent, 10);
+        assert!(config.tag_limits.is_empty());
+    }
+
+    #[test]
+    fn test_notification_config_deserialize() {
+        let json = r#"{
+            "sinks": {
+                "ops": {"type": "webhook", "url": "http://localhost:9000/hook"},
+                "log": {"type": "file", "path": "/tmp/tasks.log"}
+            },
+            "routes": [{"tags": ["nightly"], "sinks": ["ops", "log"]}]
+        }"#;
+        let config: NotificationConfig = serde_json::from_str(json).unwrap();
+        match &config.sinks["ops"] {
+            NotificationSinkConfig::Webhook { method, timeout_secs, .. } => {
+                assert_eq!(method, "POST");
+                assert_eq!(*timeout_secs, 10);
+            }
+            other => panic!("unexpected sink: {:?}", other),
+        }
+        assert_eq!(config.routes[0].on, vec!["failure", "error"]);
+        assert!(AppConfig::default().notifications.routes.is_empty());
+    }
+
+    #[test]
+    fn test_policy_config() {
+        let policy = AppConfig::default().policy;
+        assert!(policy.enabled);
+        assert_eq!(policy.default, "allow");
+        for tool in ["add_task", "update_task", "add_workflow", "remove_task", "run_command"] {
+            assert!(policy.rules.iter().any(|r| r.tool == tool && r.effect == "confirm"), "{}", tool);
+        }
+
+        let json = r#"{
+            "default": "deny",
+            "rules": [{"tool": "add_task", "effect": "allow", "arguments": {"content": ["echo *"]}}]
+        }"#;
+        let policy: PolicyConfig = serde_json::from_str(json).unwrap();
+        assert!(policy.enabled);
+        assert!(!policy.dry_run);
+        assert_eq!(policy.rules.len(), 1);
+        assert_eq!(policy.rules[0].arguments["content"], vec!["echo *"]);
+        assert_eq!(policy.audit_log, PathBuf::from("~/.sker/policy/audit.log"));
+    }
+
+    #[test]
+    fn test_mcp_config() {
+        assert!(AppConfig::default().mcp.token.is_empty());
+        let config: AppConfig = serde_json::from_str(r#"{"mcp": {"token": "secret"}}"#).unwrap();
+        assert_eq!(config.mcp.token, "secret");
+        let config = ConfigLoader::default().with_env([("SKER_MCP_TOKEN", "secret")]).load().unwrap();
+        assert_eq!(config.mcp.token, "secret");
     }
 
     #[test]
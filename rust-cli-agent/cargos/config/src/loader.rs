@@ -0,0 +1,501 @@
+//! 分层加载配置
+//!
+//! 依次合并默认值、用户配置文件 `~/.config/sker/config.toml`、项目配置文件 `.sker.toml`、
+//! 命令行指定的配置文件、`SKER_*` 环境变量和命令行覆盖项，后面的层覆盖前面的层。
+//!
+//! 配置项用点号分隔的键表示，如 `executor.max_concurrent`，键按 [`AppConfig`] 的结构校验
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use toml::{Table, Value};
+
+use crate::AppConfig;
+
+/// 环境变量前缀，如 `SKER_EXECUTOR_MAX_CONCURRENT=4`
+pub const ENV_PREFIX: &str = "SKER_";
+
+/// 项目配置文件名，从当前目录向上查找
+pub const PROJECT_FILE_NAME: &str = ".sker.toml";
+
+/// 可以包含任意子键的表，子键由用户命名
+const OPEN_TABLES: &[&str] = &["executor.tag_limits", "notifications.sinks"];
+
+/// 配置错误
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid config file {path}: {message}")]
+    InvalidFile { path: PathBuf, message: String },
+
+    #[error("Unknown config key: {0}")]
+    UnknownKey(String),
+
+    #[error("Invalid value for {key}: {message}")]
+    InvalidValue { key: String, message: String },
+
+    #[error("Invalid config: {0}")]
+    Invalid(String),
+}
+
+pub type ConfigResult<T> = Result<T, ConfigError>;
+
+/// 用户配置文件路径 `~/.config/sker/config.toml`
+pub fn user_config_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config").join("sker").join("config.toml"))
+}
+
+/// 从 `start` 开始向上查找项目配置文件
+pub fn find_project_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// 分层配置加载器
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    user_file: Option<PathBuf>,
+    project_file: Option<PathBuf>,
+    files: Vec<PathBuf>,
+    env: Vec<(String, String)>,
+    overrides: Vec<(String, String)>,
+}
+
+impl ConfigLoader {
+    /// 使用默认位置：用户配置文件、从当前目录向上找到的项目配置文件和进程环境变量
+    pub fn new() -> Self {
+        let project_file = std::env::current_dir().ok().and_then(|dir| find_project_file(&dir));
+        Self::default()
+            .with_user_file(user_config_file())
+            .with_project_file(project_file)
+            .with_env(std::env::vars())
+    }
+
+    /// 设置用户配置文件，不存在时跳过
+    pub fn with_user_file(mut self, path: Option<PathBuf>) -> Self {
+        self.user_file = path;
+        self
+    }
+
+    /// 设置项目配置文件，不存在时跳过
+    pub fn with_project_file(mut self, path: Option<PathBuf>) -> Self {
+        self.project_file = path;
+        self
+    }
+
+    /// 追加一个必须存在的配置文件，位于项目配置之后
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files.push(path.into());
+        self
+    }
+
+    /// 设置环境变量，只有 `SKER_` 开头且能对应到配置键的变量生效
+    pub fn with_env<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env = vars.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+        self
+    }
+
+    /// 追加一个覆盖项，优先级最高
+    pub fn with_override(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn user_file(&self) -> Option<&Path> {
+        self.user_file.as_deref()
+    }
+
+    pub fn project_file(&self) -> Option<&Path> {
+        self.project_file.as_deref()
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    pub fn overrides(&self) -> &[(String, String)] {
+        &self.overrides
+    }
+
+    /// 生效的环境变量及其对应的配置键
+    pub fn env_overrides(&self) -> Vec<(&str, String)> {
+        self.env
+            .iter()
+            .filter_map(|(name, _)| env_key(name).map(|key| (name.as_str(), key)))
+            .collect()
+    }
+
+    /// 合并所有层得到最终配置
+    pub fn load(&self) -> ConfigResult<AppConfig> {
+        let mut merged = default_table();
+        let optional = self.user_file.iter().chain(self.project_file.iter());
+        for path in optional.filter(|path| path.exists()).chain(self.files.iter()) {
+            let file = ConfigFile::open(path)?;
+            merge(&mut merged, file.table);
+        }
+        for (name, raw) in &self.env {
+            if let Some(key) = env_key(name) {
+                set_value(&mut merged, &key, parse_value(&key, raw))?;
+                validate(&merged).map_err(|message| ConfigError::InvalidValue {
+                    key: format!("{} ({})", name, key),
+                    message,
+                })?;
+            }
+        }
+        for (key, raw) in &self.overrides {
+            check_key(key)?;
+            set_value(&mut merged, key, parse_value(key, raw))?;
+            validate(&merged).map_err(|message| ConfigError::InvalidValue {
+                key: key.clone(),
+                message,
+            })?;
+        }
+        AppConfig::deserialize(merged).map_err(|e| ConfigError::Invalid(e.to_string()))
+    }
+}
+
+/// 单个配置文件，只包含显式设置过的项
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    path: PathBuf,
+    table: Table,
+}
+
+impl ConfigFile {
+    /// 读取配置文件，文件不存在时为空
+    pub fn load(path: impl Into<PathBuf>) -> ConfigResult<Self> {
+        let path = path.into();
+        if !path.exists() {
+            return Ok(Self { path, table: Table::new() });
+        }
+        Self::open(&path)
+    }
+
+    /// 读取并校验必须存在的配置文件
+    fn open(path: &Path) -> ConfigResult<Self> {
+        let text = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let file = Self::parse(path, &text)?;
+        file.validate()?;
+        Ok(file)
+    }
+
+    fn parse(path: &Path, text: &str) -> ConfigResult<Self> {
+        let table = text.parse::<Table>().map_err(|e| ConfigError::InvalidFile {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            table,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 文件中设置的值
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        lookup(&self.table, key)
+    }
+
+    /// 设置配置项，值按 TOML 解析，字符串类型的项直接使用原文
+    pub fn set(&mut self, key: &str, raw: &str) -> ConfigResult<()> {
+        check_key(key)?;
+        let mut table = self.table.clone();
+        set_value(&mut table, key, parse_value(key, raw))?;
+        validate(&table).map_err(|message| ConfigError::InvalidValue {
+            key: key.to_string(),
+            message,
+        })?;
+        self.table = table;
+        Ok(())
+    }
+
+    /// 删除配置项，返回文件中是否设置过该项
+    pub fn unset(&mut self, key: &str) -> ConfigResult<bool> {
+        check_key(key)?;
+        Ok(remove_value(&mut self.table, key))
+    }
+
+    /// 按 schema 校验文件内容
+    pub fn validate(&self) -> ConfigResult<AppConfig> {
+        AppConfig::deserialize(self.table.clone()).map_err(|e| ConfigError::InvalidFile {
+            path: self.path.clone(),
+            message: e.to_string(),
+        })
+    }
+
+    /// 写回文件，必要时创建目录
+    pub fn save(&self) -> ConfigResult<()> {
+        let io_error = |source| ConfigError::Io {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        let text = toml::to_string_pretty(&self.table).map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        fs::write(&self.path, text).map_err(io_error)
+    }
+}
+
+impl AppConfig {
+    /// 按点号分隔的键读取生效值，键合法但未设置时 (如未配置的标签并发数) 返回 None
+    pub fn get(&self, key: &str) -> ConfigResult<Option<Value>> {
+        check_key(key)?;
+        let table = Table::try_from(self).map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        Ok(lookup(&table, key).cloned())
+    }
+
+    /// 所有可设置的叶子键，不含用户命名的子键
+    pub fn keys() -> Vec<String> {
+        let mut keys = Vec::new();
+        collect_keys(&default_table(), "", &mut keys);
+        keys
+    }
+}
+
+fn default_table() -> Table {
+    Table::try_from(AppConfig::default()).expect("default config serializes to TOML")
+}
+
+fn validate(table: &Table) -> Result<(), String> {
+    AppConfig::deserialize(table.clone()).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// 检查键是否存在于 schema 中
+fn check_key(key: &str) -> ConfigResult<()> {
+    let unknown = || ConfigError::UnknownKey(key.to_string());
+    let segments: Vec<&str> = key.split('.').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(unknown());
+    }
+
+    let defaults = default_table();
+    let mut current = &defaults;
+    for (i, segment) in segments.iter().enumerate() {
+        if OPEN_TABLES.contains(&segments[..i].join(".").as_str()) {
+            return Ok(());
+        }
+        match current.get(*segment) {
+            Some(Value::Table(table)) => current = table,
+            Some(_) if i + 1 == segments.len() => return Ok(()),
+            _ => return Err(unknown()),
+        }
+    }
+    Ok(())
+}
+
+/// 把原文解析为配置值
+///
+/// 默认值是字符串的项原样保留，其他项按 TOML 值解析，解析失败时作为字符串交给校验
+fn parse_value(key: &str, raw: &str) -> Value {
+    if let Some(Value::String(_)) = lookup(&default_table(), key) {
+        return Value::String(raw.to_string());
+    }
+    format!("value = {}", raw)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// 环境变量对应的配置键
+///
+/// `SKER_EXECUTOR_MAX_CONCURRENT` 按已知的叶子键匹配；
+/// 用 `__` 分隔的形式可以设置用户命名的子键，如 `SKER_EXECUTOR__TAG_LIMITS__GPU`
+fn env_key(name: &str) -> Option<String> {
+    let rest = name.strip_prefix(ENV_PREFIX)?.to_lowercase();
+    if rest.contains("__") {
+        let key = rest.replace("__", ".");
+        return check_key(&key).ok().map(|_| key);
+    }
+    AppConfig::keys().into_iter().find(|key| key.replace('.', "_") == rest)
+}
+
+fn collect_keys(table: &Table, prefix: &str, keys: &mut Vec<String>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        match value {
+            Value::Table(child) if !OPEN_TABLES.contains(&key.as_str()) => collect_keys(child, &key, keys),
+            _ => keys.push(key),
+        }
+    }
+}
+
+fn lookup<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let (parent, last) = match key.rsplit_once('.') {
+        Some((parent, last)) => (Some(parent), last),
+        None => (None, key),
+    };
+    let mut current = table;
+    for segment in parent.into_iter().flat_map(|p| p.split('.')) {
+        current = current.get(segment)?.as_table()?;
+    }
+    current.get(last)
+}
+
+fn set_value(table: &mut Table, key: &str, value: Value) -> ConfigResult<()> {
+    let mut segments: Vec<&str> = key.split('.').collect();
+    let last = segments.pop().unwrap_or(key);
+    let mut current = table;
+    for segment in segments {
+        let entry = current
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Table(Table::new()));
+        current = entry.as_table_mut().ok_or_else(|| ConfigError::InvalidValue {
+            key: key.to_string(),
+            message: format!("{} is not a table", segment),
+        })?;
+    }
+    current.insert(last.to_string(), value);
+    Ok(())
+}
+
+/// 删除键，并清理因此变空的父表
+fn remove_value(table: &mut Table, key: &str) -> bool {
+    match key.split_once('.') {
+        None => table.remove(key).is_some(),
+        Some((first, rest)) => {
+            let Some(Value::Table(child)) = table.get_mut(first) else {
+                return false;
+            };
+            let removed = remove_value(child, rest);
+            if child.is_empty() {
+                table.remove(first);
+            }
+            removed
+        }
+    }
+}
+
+/// 深度合并，表逐键合并，其他值整体替换
+fn merge(base: &mut Table, layer: Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(layer_table)) => merge(base_table, layer_table),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StorageBackend;
+
+    fn write(path: &Path, text: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.toml");
+        let project = dir.path().join("project").join(PROJECT_FILE_NAME);
+        write(&user, "[executor]\nmax_concurrent = 2\ndefault_timeout_secs = 60\n\n[voice]\nlanguage = \"zh-CN\"\n");
+        write(&project, "[executor]\nmax_concurrent = 3\n\n[executor.tag_limits]\ngpu = 1\n");
+
+        let loader = ConfigLoader::default()
+            .with_user_file(Some(user))
+            .with_project_file(find_project_file(&dir.path().join("project").join("src")))
+            .with_env([
+                ("SKER_EXECUTOR_MAX_CONCURRENT", "4"),
+                ("SKER_STORAGE__BACKEND", "json"),
+                ("SKER_PARAM_USER", "bob"),
+            ])
+            .with_override("voice.model", "whisper-small");
+        let config = loader.load().unwrap();
+
+        assert_eq!(config.executor.default_timeout_secs, 60);
+        assert_eq!(config.executor.max_concurrent, 4);
+        assert_eq!(config.executor.tag_limits["gpu"], 1);
+        assert_eq!(config.storage.backend, StorageBackend::Json);
+        assert_eq!(config.voice.language, "zh-CN");
+        assert_eq!(config.voice.model, "whisper-small");
+        assert_eq!(config.scheduler.max_tasks, 100);
+        assert_eq!(loader.env_overrides().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_layers_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.toml");
+        write(&user, "[executor]\nmax_concurent = 2\n");
+        let error = ConfigLoader::default().with_user_file(Some(user)).load().unwrap_err();
+        assert!(matches!(error, ConfigError::InvalidFile { .. }), "{}", error);
+
+        let error = ConfigLoader::default()
+            .with_env([("SKER_EXECUTOR_MAX_CONCURRENT", "many")])
+            .load()
+            .unwrap_err();
+        assert!(error.to_string().contains("SKER_EXECUTOR_MAX_CONCURRENT"), "{}", error);
+
+        let error = ConfigLoader::default().with_override("executor.nope", "1").load().unwrap_err();
+        assert!(matches!(error, ConfigError::UnknownKey(_)));
+
+        let missing = ConfigLoader::default().with_file(dir.path().join("missing.toml")).load();
+        assert!(matches!(missing, Err(ConfigError::Io { .. })));
+    }
+
+    #[test]
+    fn test_config_file_set_get_unset() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sker").join("config.toml");
+        let mut file = ConfigFile::load(&path).unwrap();
+
+        file.set("executor.max_concurrent", "8").unwrap();
+        file.set("executor.tag_limits.gpu", "1").unwrap();
+        file.set("cli.prompt", "42").unwrap();
+        file.set("policy.rules", r#"[{ tool = "run_command", effect = "deny" }]"#).unwrap();
+        assert!(matches!(file.set("executor.max_concurrent", "-1"), Err(ConfigError::InvalidValue { .. })));
+        assert!(matches!(file.set("executor.shel", "bash"), Err(ConfigError::UnknownKey(_))));
+        assert!(matches!(file.set("cli.prompt.x", "1"), Err(ConfigError::UnknownKey(_))));
+        file.save().unwrap();
+
+        let mut file = ConfigFile::load(&path).unwrap();
+        assert_eq!(file.get("executor.max_concurrent"), Some(&Value::Integer(8)));
+        assert_eq!(file.get("cli.prompt"), Some(&Value::String("42".to_string())));
+        let config = file.validate().unwrap();
+        assert_eq!(config.policy.rules.len(), 1);
+        assert_eq!(config.get("executor.tag_limits.gpu").unwrap(), Some(Value::Integer(1)));
+        assert_eq!(config.get("executor.tag_limits.cpu").unwrap(), None);
+
+        assert!(file.unset("executor.tag_limits.gpu").unwrap());
+        assert!(!file.unset("voice.model").unwrap());
+        assert!(file.get("executor.tag_limits").is_none());
+        assert!(file.get("executor.max_concurrent").is_some());
+    }
+
+    #[test]
+    fn test_keys_cover_schema() {
+        let keys = AppConfig::keys();
+        assert!(keys.contains(&"executor.max_concurrent".to_string()));
+        assert!(keys.contains(&"executor.tag_limits".to_string()));
+        assert!(keys.contains(&"policy.rules".to_string()));
+        for key in &keys {
+            check_key(key).unwrap();
+        }
+    }
+}
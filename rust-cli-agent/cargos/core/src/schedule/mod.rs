@@ -35,4 +35,16 @@ mod tests {
         };
         assert!(execution.success);
     }
+
+    #[test]
+    fn test_cron_expression_validation() {
+        assert!(CronExpression::new("0 9 * * 1-5").is_ok());
+        assert!(CronExpression::new("@daily").is_ok());
+        assert!(CronExpression::new("0 0 12 L * ? 2030").is_ok());
+
+        let error = CronExpression::new("0 25 * * *").unwrap_err();
+        assert!(error.contains("hour"));
+        assert!(CronExpression::new("").is_err());
+        assert!(CronExpression::new("a=b").is_err());
+    }
 }
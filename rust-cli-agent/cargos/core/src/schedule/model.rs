@@ -1,4 +1,5 @@
 use chrono::{DateTime, Utc};
+use cron_expression::CronSchedule;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -21,15 +22,12 @@ pub struct CronExpression {
 impl CronExpression {
     pub fn new(expression: impl Into<String>) -> Result<Self, String> {
         let expr = expression.into();
-        if Self::validate(&expr) {
-            Ok(Self { expression: expr })
-        } else {
-            Err("Invalid cron expression".to_string())
-        }
+        Self::validate(&expr)?;
+        Ok(Self { expression: expr })
     }
 
-    fn validate(expr: &str) -> bool {
-        !expr.contains('=') && !expr.is_empty()
+    fn validate(expr: &str) -> Result<(), String> {
+        CronSchedule::parse(expr).map(|_| ()).map_err(|e| e.to_string())
     }
 }
 
@@ -0,0 +1,762 @@
+//! cron 表达式
+//!
+//! 任务调度、系统调度器和领域模型共用的 cron 语法与计算：
+//! - 5 字段：`分 时 日 月 周`，秒固定为 0
+//! - 6 字段：`秒 分 时 日 月 周`
+//! - 7 字段：`秒 分 时 日 月 周 年`
+//! - 宏：`@yearly` (`@annually`)、`@monthly`、`@weekly`、`@daily` (`@midnight`)、`@hourly`
+//!
+//! 各字段支持 `*`、数值、`a-b` 范围、`/n` 步长和逗号列表，月份和星期可用英文缩写 (`JAN`、`MON`)。
+//! 星期取 0-7，0 和 7 都表示周日。日和周字段还支持：
+//! - `?`：同 `*`
+//! - 日：`L` 月末、`L-n` 月末前 n 天、`nW` 离 n 日最近的工作日 (不跨月)、`LW` 当月最后一个工作日
+//! - 周：`nL` 当月最后一个星期 n、`n#k` 当月第 k 个星期 n
+//!
+//! 日和周字段都受限 (不是 `*` 或 `?`) 时，满足其一即触发，与 crontab 一致。
+//! 计算按墙上时间进行，不涉及时区。
+//!
+//! 早先保存的表达式按 cron 0.12 的语法编写：星期取 1-7 且 1 为周日，日和周字段须同时满足，
+//! 用 [`upgrade_legacy`] 改写为当前语法。
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+
+/// 年字段的取值范围
+const MIN_YEAR: i32 = 2000;
+const MAX_YEAR: i32 = 2099;
+
+const MONTH_NAMES: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const DAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// cron 表达式的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CronField {
+    Second,
+    Minute,
+    Hour,
+    DayOfMonth,
+    Month,
+    DayOfWeek,
+    Year,
+}
+
+impl CronField {
+    /// 字段允许的取值范围
+    pub fn bounds(self) -> (u32, u32) {
+        match self {
+            CronField::Second | CronField::Minute => (0, 59),
+            CronField::Hour => (0, 23),
+            CronField::DayOfMonth => (1, 31),
+            CronField::Month => (1, 12),
+            CronField::DayOfWeek => (0, 7),
+            CronField::Year => (MIN_YEAR as u32, MAX_YEAR as u32),
+        }
+    }
+
+    /// 取值在位集中的位置，星期 7 与 0 同为周日
+    fn bit(self, value: u32) -> u128 {
+        let (min, _) = self.bounds();
+        match self {
+            CronField::DayOfWeek => 1u128 << (value % 7),
+            _ => 1u128 << (value - min),
+        }
+    }
+
+    /// 解析单个取值 (数值或英文缩写)
+    fn value(self, text: &str) -> Result<u32, String> {
+        if text.is_empty() {
+            return Err("missing value".to_string());
+        }
+        let upper = text.to_ascii_uppercase();
+        let value = match upper.parse::<u32>() {
+            Ok(value) => value,
+            Err(_) => {
+                let named = match self {
+                    CronField::Month => MONTH_NAMES.iter().position(|n| *n == upper).map(|i| i as u32 + 1),
+                    CronField::DayOfWeek => DAY_NAMES.iter().position(|n| *n == upper).map(|i| i as u32),
+                    _ => None,
+                };
+                return named.ok_or_else(|| self.unknown_value(text));
+            }
+        };
+        let (min, max) = self.bounds();
+        if !(min..=max).contains(&value) {
+            return Err(format!("{} is out of range {}-{}", value, min, max));
+        }
+        Ok(value)
+    }
+
+    fn unknown_value(self, text: &str) -> String {
+        match self {
+            CronField::Month => format!("'{}' is not a number or month name (JAN-DEC)", text),
+            CronField::DayOfWeek => format!("'{}' is not a number or day name (SUN-SAT)", text),
+            CronField::DayOfMonth => format!("'{}' is not a number, L, LW, L-n or nW", text),
+            _ if text.contains(['L', 'W', '#', 'l', 'w']) => {
+                "L, W and # are only supported in the day-of-month and day-of-week fields".to_string()
+            }
+            _ => format!("'{}' is not a number", text),
+        }
+    }
+
+    /// 解析不含 L/W/# 的取值列表项：`*`、`?`、`a`、`a-b`，可带 `/n` 步长
+    fn parse_item(self, item: &str) -> Result<u128, String> {
+        let (base, step) = match item.split_once('/') {
+            Some((base, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (base, Some(step)),
+                _ => return Err(format!("step '{}' must be a positive number", step)),
+            },
+            None => (item, None),
+        };
+        let (min, max) = self.bounds();
+        let wildcard = base == "*" || (base == "?" && matches!(self, CronField::DayOfMonth | CronField::DayOfWeek));
+        let (start, end) = if wildcard {
+            (min, max)
+        } else if let Some((start, end)) = base.split_once('-') {
+            let (start, end) = (self.value(start)?, self.value(end)?);
+            if start > end {
+                return Err(format!("range {} starts after it ends", base));
+            }
+            (start, end)
+        } else {
+            let value = self.value(base)?;
+            // `a/n` 表示从 a 开始到字段上限
+            (value, if step.is_some() { max } else { value })
+        };
+
+        Ok((start..=end)
+            .step_by(step.unwrap_or(1) as usize)
+            .fold(0, |bits, value| bits | self.bit(value)))
+    }
+
+    /// 解析逗号分隔的取值列表
+    fn parse_list(self, text: &str) -> Result<u128, String> {
+        text.split(',').try_fold(0, |bits, item| Ok(bits | self.parse_item(item)?))
+    }
+}
+
+impl fmt::Display for CronField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CronField::Second => "second",
+            CronField::Minute => "minute",
+            CronField::Hour => "hour",
+            CronField::DayOfMonth => "day-of-month",
+            CronField::Month => "month",
+            CronField::DayOfWeek => "day-of-week",
+            CronField::Year => "year",
+        };
+        f.write_str(name)
+    }
+}
+
+/// cron 表达式解析错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CronError {
+    #[error("Cron expression is empty")]
+    Empty,
+
+    #[error("Expected 5, 6 or 7 fields (or a macro such as @daily), found {0}")]
+    FieldCount(usize),
+
+    #[error("Unknown macro '{0}', expected @yearly, @annually, @monthly, @weekly, @daily, @midnight or @hourly")]
+    UnknownMacro(String),
+
+    #[error("Invalid {field} field '{value}': {reason}")]
+    InvalidField {
+        field: CronField,
+        value: String,
+        reason: String,
+    },
+
+    #[error(
+        "Day-of-month '{day_of_month}' and day-of-week '{day_of_week}' are both restricted: \
+         the old syntax required both to match, now either one matches"
+    )]
+    LegacyDayMatch { day_of_month: String, day_of_week: String },
+}
+
+/// 日字段
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct DaysOfMonth {
+    days: u128,
+    /// `L` 与 `L-n`：距月末的天数
+    from_last: Vec<u32>,
+    /// `nW`
+    nearest_weekday: Vec<u32>,
+    /// `LW`
+    last_weekday: bool,
+}
+
+impl DaysOfMonth {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut days = Self::default();
+        for item in text.split(',') {
+            let upper = item.to_ascii_uppercase();
+            if upper == "L" {
+                days.from_last.push(0);
+            } else if upper == "LW" {
+                days.last_weekday = true;
+            } else if let Some(offset) = upper.strip_prefix("L-") {
+                match offset.parse::<u32>() {
+                    Ok(offset) if (1..=30).contains(&offset) => days.from_last.push(offset),
+                    _ => return Err(format!("offset in '{}' must be between 1 and 30", item)),
+                }
+            } else if let Some(day) = upper.strip_suffix('W') {
+                days.nearest_weekday.push(CronField::DayOfMonth.value(day)?);
+            } else {
+                days.days |= CronField::DayOfMonth.parse_item(item)?;
+            }
+        }
+        Ok(days)
+    }
+
+    fn matches(&self, date: NaiveDate) -> bool {
+        let day = date.day();
+        let last = days_in_month(date);
+        self.days & CronField::DayOfMonth.bit(day) != 0
+            || self.from_last.iter().any(|offset| last > *offset && last - offset == day)
+            || self.nearest_weekday.iter().any(|target| nearest_weekday(date, *target) == Some(day))
+            || (self.last_weekday && nearest_weekday(date, last) == Some(day))
+    }
+
+    fn has_special(&self) -> bool {
+        !self.from_last.is_empty() || !self.nearest_weekday.is_empty() || self.last_weekday
+    }
+}
+
+/// 周字段
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct DaysOfWeek {
+    days: u128,
+    /// `nL`
+    last: Vec<u32>,
+    /// `n#k`
+    nth: Vec<(u32, u32)>,
+}
+
+impl DaysOfWeek {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut days = Self::default();
+        for item in text.split(',') {
+            let upper = item.to_ascii_uppercase();
+            if let Some((day, nth)) = upper.split_once('#') {
+                let day = CronField::DayOfWeek.value(day)? % 7;
+                match nth.parse::<u32>() {
+                    Ok(nth) if (1..=5).contains(&nth) => days.nth.push((day, nth)),
+                    _ => return Err(format!("occurrence in '{}' must be between 1 and 5", item)),
+                }
+            } else if let Some(day) = upper.strip_suffix('L').filter(|day| !day.is_empty()) {
+                days.last.push(CronField::DayOfWeek.value(day)? % 7);
+            } else {
+                days.days |= CronField::DayOfWeek.parse_item(item)?;
+            }
+        }
+        Ok(days)
+    }
+
+    fn matches(&self, date: NaiveDate) -> bool {
+        let weekday = date.weekday().num_days_from_sunday();
+        let day = date.day();
+        self.days & CronField::DayOfWeek.bit(weekday) != 0
+            || self.last.iter().any(|d| *d == weekday && day + 7 > days_in_month(date))
+            || self.nth.iter().any(|(d, nth)| *d == weekday && (day - 1) / 7 + 1 == *nth)
+    }
+
+    fn has_special(&self) -> bool {
+        !self.last.is_empty() || !self.nth.is_empty()
+    }
+}
+
+/// 解析后的 cron 表达式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    source: String,
+    seconds: u128,
+    minutes: u128,
+    hours: u128,
+    days_of_month: DaysOfMonth,
+    months: u128,
+    days_of_week: DaysOfWeek,
+    years: u128,
+    /// 日、周字段是否受限
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    /// 解析 cron 表达式
+    pub fn parse(expression: &str) -> Result<Self, CronError> {
+        let source = expression.trim();
+        if source.is_empty() {
+            return Err(CronError::Empty);
+        }
+        let expanded = if source.starts_with('@') {
+            expand_macro(source)?
+        } else {
+            source
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let (second, rest) = match fields.len() {
+            5 => ("0", &fields[..]),
+            6 | 7 => (fields[0], &fields[1..]),
+            count => return Err(CronError::FieldCount(count)),
+        };
+        let (minute, hour, day_of_month, month, day_of_week) = (rest[0], rest[1], rest[2], rest[3], rest[4]);
+        let year = rest.get(5).copied().unwrap_or("*");
+
+        let list = |field: CronField, text: &str| with_field(field, text, |text| field.parse_list(text));
+        Ok(Self {
+            source: source.to_string(),
+            seconds: list(CronField::Second, second)?,
+            minutes: list(CronField::Minute, minute)?,
+            hours: list(CronField::Hour, hour)?,
+            days_of_month: with_field(CronField::DayOfMonth, day_of_month, DaysOfMonth::parse)?,
+            months: list(CronField::Month, month)?,
+            days_of_week: with_field(CronField::DayOfWeek, day_of_week, DaysOfWeek::parse)?,
+            years: list(CronField::Year, year)?,
+            day_of_month_restricted: !matches!(day_of_month, "*" | "?"),
+            day_of_week_restricted: !matches!(day_of_week, "*" | "?"),
+        })
+    }
+
+    /// 原始表达式
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 字段的普通取值 (升序)，不含 L、W、# 指定的日期；星期以 0 表示周日
+    pub fn values(&self, field: CronField) -> Vec<u32> {
+        let (min, max) = field.bounds();
+        let max = if field == CronField::DayOfWeek { 6 } else { max };
+        (min..=max).filter(|value| self.set(field) & field.bit(*value) != 0).collect()
+    }
+
+    /// 字段是否受限，即不是每个取值都匹配
+    pub fn is_restricted(&self, field: CronField) -> bool {
+        match field {
+            CronField::DayOfMonth => self.day_of_month_restricted,
+            CronField::DayOfWeek => self.day_of_week_restricted,
+            _ => {
+                let (min, max) = field.bounds();
+                self.values(field).len() != (max - min + 1) as usize
+            }
+        }
+    }
+
+    /// 是否使用了 L、W 或 #
+    pub fn has_special_days(&self) -> bool {
+        self.days_of_month.has_special() || self.days_of_week.has_special()
+    }
+
+    /// after 之后 (不含) 的下一次触发时间
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_nanosecond(0)? + Duration::seconds(1);
+        let mut date = start.date();
+        let mut earliest = start.time();
+        while date.year() <= MAX_YEAR {
+            if date.year() < MIN_YEAR || self.years & CronField::Year.bit(date.year() as u32) == 0 {
+                date = NaiveDate::from_ymd_opt((date.year() + 1).max(MIN_YEAR), 1, 1)?;
+            } else if self.months & CronField::Month.bit(date.month()) == 0 {
+                date = first_of_next_month(date)?;
+            } else if let Some(time) = self.day_matches(date).then(|| self.first_time(earliest)).flatten() {
+                return Some(date.and_time(time));
+            } else {
+                date = date.succ_opt()?;
+            }
+            earliest = NaiveTime::MIN;
+        }
+        None
+    }
+
+    /// after 之后 (不含) 的触发时间，按时间升序
+    pub fn after(&self, after: NaiveDateTime) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        std::iter::successors(self.next_after(after), move |last| self.next_after(*last))
+    }
+
+    fn set(&self, field: CronField) -> u128 {
+        match field {
+            CronField::Second => self.seconds,
+            CronField::Minute => self.minutes,
+            CronField::Hour => self.hours,
+            CronField::DayOfMonth => self.days_of_month.days,
+            CronField::Month => self.months,
+            CronField::DayOfWeek => self.days_of_week.days,
+            CronField::Year => self.years,
+        }
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => self.days_of_month.matches(date) || self.days_of_week.matches(date),
+            (true, false) => self.days_of_month.matches(date),
+            (false, true) => self.days_of_week.matches(date),
+            (false, false) => true,
+        }
+    }
+
+    /// 当天 earliest 及之后的第一个匹配时刻
+    fn first_time(&self, earliest: NaiveTime) -> Option<NaiveTime> {
+        let matches = |field: CronField, value: u32| self.set(field) & field.bit(value) != 0;
+        let (hour0, minute0, second0) = (earliest.hour(), earliest.minute(), earliest.second());
+        for hour in (hour0..24).filter(|h| matches(CronField::Hour, *h)) {
+            let from_minute = if hour == hour0 { minute0 } else { 0 };
+            for minute in (from_minute..60).filter(|m| matches(CronField::Minute, *m)) {
+                let from_second = if hour == hour0 && minute == minute0 { second0 } else { 0 };
+                if let Some(second) = (from_second..60).find(|s| matches(CronField::Second, *s)) {
+                    return NaiveTime::from_hms_opt(hour, minute, second);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = CronError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// 宏展开为 6 字段表达式
+fn expand_macro(name: &str) -> Result<&'static str, CronError> {
+    match name.to_ascii_lowercase().as_str() {
+        "@yearly" | "@annually" => Ok("0 0 0 1 1 *"),
+        "@monthly" => Ok("0 0 0 1 * *"),
+        "@weekly" => Ok("0 0 0 * * 0"),
+        "@daily" | "@midnight" => Ok("0 0 0 * * *"),
+        "@hourly" => Ok("0 0 * * * *"),
+        _ => Err(CronError::UnknownMacro(name.to_string())),
+    }
+}
+
+/// 将 cron 0.12 语法的表达式改写为当前语法
+///
+/// 旧语法只有 6、7 字段，星期取 1-7 且 1 为周日，日和周字段须同时满足。
+/// 受限的星期字段改写为英文缩写，其他字段不变；日和周字段都受限时无法用当前语法表达，返回错误
+pub fn upgrade_legacy(expression: &str) -> Result<String, CronError> {
+    let source = expression.trim();
+    if source.is_empty() {
+        return Err(CronError::Empty);
+    }
+    // 宏在两种语法中含义相同
+    if source.starts_with('@') {
+        expand_macro(source)?;
+        return Ok(source.to_string());
+    }
+
+    let mut fields: Vec<String> = source.split_whitespace().map(str::to_string).collect();
+    if !(6..=7).contains(&fields.len()) {
+        return Err(CronError::FieldCount(fields.len()));
+    }
+    let (day_of_month, day_of_week) = (&fields[3], &fields[5]);
+    if !matches!(day_of_week.as_str(), "*" | "?") {
+        let days = with_field(CronField::DayOfWeek, day_of_week, legacy_days_of_week)?;
+        // 整周不限制星期，只看日字段，与旧语法相同
+        let upgraded = if days == 0x7f {
+            "*".to_string()
+        } else if !matches!(day_of_month.as_str(), "*" | "?") {
+            return Err(CronError::LegacyDayMatch {
+                day_of_month: day_of_month.clone(),
+                day_of_week: day_of_week.clone(),
+            });
+        } else {
+            format_days(days)
+        };
+        fields[5] = upgraded;
+    }
+
+    let upgraded = fields.join(" ");
+    CronSchedule::parse(&upgraded)?;
+    Ok(upgraded)
+}
+
+/// 按旧语法解析星期字段，返回以周日为第 0 位的位集
+fn legacy_days_of_week(text: &str) -> Result<u8, String> {
+    text.split(',').try_fold(0, |days, item| {
+        let (base, step) = match item.split_once('/') {
+            Some((base, step)) => match step.parse::<usize>() {
+                Ok(step) if step > 0 => (base, step),
+                _ => return Err(format!("step '{}' must be a positive number", step)),
+            },
+            None => (item, 1),
+        };
+        let (start, end) = if base == "*" || base == "?" {
+            (1, 7)
+        } else if let Some((start, end)) = base.split_once('-') {
+            let (start, end) = (legacy_day(start)?, legacy_day(end)?);
+            if start > end {
+                return Err(format!("range {} starts after it ends", base));
+            }
+            (start, end)
+        } else {
+            let day = legacy_day(base)?;
+            (day, if item.contains('/') { 7 } else { day })
+        };
+        Ok((start..=end).step_by(step).fold(days, |days, day| days | 1 << (day - 1)))
+    })
+}
+
+/// 旧语法的星期取值：1-7 或英文名称，1 为周日
+fn legacy_day(text: &str) -> Result<u32, String> {
+    if let Ok(day) = text.parse::<u32>() {
+        return match day {
+            1..=7 => Ok(day),
+            _ => Err(format!("{} is out of range 1-7", day)),
+        };
+    }
+    let day = match text.to_ascii_lowercase().as_str() {
+        "sun" | "sunday" => 1,
+        "mon" | "monday" => 2,
+        "tue" | "tues" | "tuesday" => 3,
+        "wed" | "wednesday" => 4,
+        "thu" | "thurs" | "thursday" => 5,
+        "fri" | "friday" => 6,
+        "sat" | "saturday" => 7,
+        _ => return Err(format!("'{}' is not a number or day name (SUN-SAT)", text)),
+    };
+    Ok(day)
+}
+
+/// 将星期位集写为英文缩写列表，连续三天以上写成范围
+fn format_days(days: u8) -> String {
+    let mut items = Vec::new();
+    let mut day = 0;
+    while day < 7 {
+        if days & 1 << day == 0 {
+            day += 1;
+            continue;
+        }
+        let start = day;
+        while day < 7 && days & 1 << day != 0 {
+            day += 1;
+        }
+        match day - start {
+            1 => items.push(DAY_NAMES[start].to_string()),
+            2 => items.push(format!("{},{}", DAY_NAMES[start], DAY_NAMES[start + 1])),
+            _ => items.push(format!("{}-{}", DAY_NAMES[start], DAY_NAMES[day - 1])),
+        }
+    }
+    items.join(",")
+}
+
+/// 为字段解析错误附上字段名和原文
+fn with_field<T>(field: CronField, text: &str, parse: impl FnOnce(&str) -> Result<T, String>) -> Result<T, CronError> {
+    parse(text).map_err(|reason| CronError::InvalidField {
+        field,
+        value: text.to_string(),
+        reason,
+    })
+}
+
+fn first_of_next_month(date: NaiveDate) -> Option<NaiveDate> {
+    if date.month() == 12 {
+        NaiveDate::from_ymd_opt(date.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1)
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    first_of_next_month(date)
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(31)
+}
+
+/// date 所在月份中离 target 日最近的工作日，不跨月；该月没有 target 日时返回 None
+fn nearest_weekday(date: NaiveDate, target: u32) -> Option<u32> {
+    let last = days_in_month(date);
+    let day = date.with_day(target)?;
+    Some(match day.weekday() {
+        Weekday::Sat if target == 1 => 3,
+        Weekday::Sat => target - 1,
+        Weekday::Sun if target == last => target - 2,
+        Weekday::Sun => target + 1,
+        _ => target,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32, s: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d).unwrap().and_hms_opt(h, mi, s).unwrap()
+    }
+
+    fn upcoming(expression: &str, since: NaiveDateTime, count: usize) -> Vec<NaiveDateTime> {
+        CronSchedule::parse(expression).unwrap().after(since).take(count).collect()
+    }
+
+    fn days(expression: &str, since: NaiveDateTime, count: usize) -> Vec<(u32, u32)> {
+        upcoming(expression, since, count)
+            .into_iter()
+            .map(|at| (at.month(), at.day()))
+            .collect()
+    }
+
+    #[test]
+    fn test_field_counts_and_macros() {
+        let since = at(2024, 1, 1, 8, 0, 0);
+        // 5 字段秒为 0，与 6 字段等价
+        assert_eq!(upcoming("30 9 * * *", since, 1), vec![at(2024, 1, 1, 9, 30, 0)]);
+        assert_eq!(upcoming("0 30 9 * * *", since, 1), vec![at(2024, 1, 1, 9, 30, 0)]);
+        assert_eq!(upcoming("15 30 9 * * * 2025", since, 1), vec![at(2025, 1, 1, 9, 30, 15)]);
+
+        assert_eq!(upcoming("@hourly", since, 2), vec![at(2024, 1, 1, 9, 0, 0), at(2024, 1, 1, 10, 0, 0)]);
+        assert_eq!(upcoming("@daily", since, 1), vec![at(2024, 1, 2, 0, 0, 0)]);
+        assert_eq!(upcoming("@midnight", since, 1), vec![at(2024, 1, 2, 0, 0, 0)]);
+        // 2024-01-07 为周日
+        assert_eq!(upcoming("@weekly", since, 1), vec![at(2024, 1, 7, 0, 0, 0)]);
+        assert_eq!(upcoming("@monthly", since, 1), vec![at(2024, 2, 1, 0, 0, 0)]);
+        assert_eq!(upcoming("@ANNUALLY", since, 1), vec![at(2025, 1, 1, 0, 0, 0)]);
+        assert_eq!(CronSchedule::parse("@daily").unwrap().source(), "@daily");
+    }
+
+    #[test]
+    fn test_next_is_strictly_after() {
+        let schedule = CronSchedule::parse("*/20 * * * * *").unwrap();
+        assert_eq!(schedule.next_after(at(2024, 1, 1, 0, 0, 20)), Some(at(2024, 1, 1, 0, 0, 40)));
+        let within = at(2024, 1, 1, 0, 0, 20) + Duration::milliseconds(500);
+        assert_eq!(schedule.next_after(within), Some(at(2024, 1, 1, 0, 0, 40)));
+        assert_eq!(schedule.next_after(at(2024, 12, 31, 23, 59, 50)), Some(at(2025, 1, 1, 0, 0, 0)));
+        // 年份之外不再触发
+        assert_eq!(CronSchedule::parse("0 0 0 1 1 * 2024").unwrap().next_after(at(2024, 6, 1, 0, 0, 0)), None);
+        assert_eq!(CronSchedule::parse("0 0 0 30 2 *").unwrap().next_after(at(2024, 1, 1, 0, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_lists_ranges_steps_and_names() {
+        let since = at(2024, 1, 1, 0, 0, 0);
+        assert_eq!(
+            upcoming("0 0,30 9-10 * * *", since, 4),
+            vec![at(2024, 1, 1, 9, 0, 0), at(2024, 1, 1, 9, 30, 0), at(2024, 1, 1, 10, 0, 0), at(2024, 1, 1, 10, 30, 0)]
+        );
+        assert_eq!(
+            upcoming("0 10/20 0 * * *", since, 3),
+            vec![at(2024, 1, 1, 0, 10, 0), at(2024, 1, 1, 0, 30, 0), at(2024, 1, 1, 0, 50, 0)]
+        );
+        // 2024-01-01 为周一，0 和 7 都是周日
+        assert_eq!(days("0 9 * * mon-fri", since, 6), vec![(1, 1), (1, 2), (1, 3), (1, 4), (1, 5), (1, 8)]);
+        assert_eq!(days("0 9 * * 0", since, 1), vec![(1, 7)]);
+        assert_eq!(days("0 9 * * 7", since, 1), vec![(1, 7)]);
+        assert_eq!(days("0 9 1 jan,JUL *", since, 2), vec![(1, 1), (7, 1)]);
+    }
+
+    #[test]
+    fn test_day_of_month_or_day_of_week() {
+        // 日和周都受限时满足其一即可：1 日或周五
+        let since = at(2024, 1, 1, 12, 0, 0);
+        assert_eq!(days("0 9 1 * 5", since, 3), vec![(1, 5), (1, 12), (1, 19)]);
+        assert_eq!(days("0 9 1 * 5", at(2024, 1, 27, 0, 0, 0), 2), vec![(2, 1), (2, 2)]);
+        // ? 不限制
+        assert_eq!(days("0 0 9 ? * FRI", since, 1), vec![(1, 5)]);
+    }
+
+    #[test]
+    fn test_last_weekday_and_nth() {
+        let since = at(2023, 12, 31, 12, 0, 0);
+        // 月末：2024 年 2 月有 29 天
+        assert_eq!(days("0 0 L * *", since, 2), vec![(1, 31), (2, 29)]);
+        assert_eq!(days("0 0 L-2 * *", since, 2), vec![(1, 29), (2, 27)]);
+        // 2024-03-31 为周日，最后一个工作日为 29 日周五
+        assert_eq!(days("0 0 LW 3 *", since, 1), vec![(3, 29)]);
+        // 2024-06-01 为周六，不跨月，取 3 日周一；2024-06-15 为周六，取 14 日周五
+        assert_eq!(days("0 0 1W 6 *", since, 1), vec![(6, 3)]);
+        assert_eq!(days("0 0 15W 6 *", since, 1), vec![(6, 14)]);
+        // 2024 年 1 月最后一个周五为 26 日，第三个周五为 19 日
+        assert_eq!(days("0 0 * 1 5L", since, 1), vec![(1, 26)]);
+        assert_eq!(days("0 0 * 1 FRIL", since, 1), vec![(1, 26)]);
+        assert_eq!(days("0 0 * 1 5#3", since, 1), vec![(1, 19)]);
+        assert_eq!(days("0 0 * 1,2 MON#1", since, 2), vec![(1, 1), (2, 5)]);
+
+        let schedule = CronSchedule::parse("0 0 L * *").unwrap();
+        assert!(schedule.has_special_days());
+        assert!(!CronSchedule::parse("0 0 1 * *").unwrap().has_special_days());
+    }
+
+    #[test]
+    fn test_field_values() {
+        let schedule = CronSchedule::parse("30 9 * * 1-5").unwrap();
+        assert_eq!(schedule.values(CronField::Second), vec![0]);
+        assert_eq!(schedule.values(CronField::Minute), vec![30]);
+        assert_eq!(schedule.values(CronField::DayOfWeek), vec![1, 2, 3, 4, 5]);
+        assert!(schedule.is_restricted(CronField::Hour));
+        assert!(!schedule.is_restricted(CronField::DayOfMonth));
+        assert!(schedule.is_restricted(CronField::DayOfWeek));
+        assert!(!schedule.is_restricted(CronField::Year));
+        assert_eq!(CronSchedule::parse("0 0 * * 7").unwrap().values(CronField::DayOfWeek), vec![0]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(CronSchedule::parse("  "), Err(CronError::Empty));
+        assert_eq!(CronSchedule::parse("* * *"), Err(CronError::FieldCount(3)));
+        assert_eq!(CronSchedule::parse("* * * * * * * *"), Err(CronError::FieldCount(8)));
+        assert!(matches!(CronSchedule::parse("@reboot"), Err(CronError::UnknownMacro(_))));
+
+        let message = |expression: &str| CronSchedule::parse(expression).unwrap_err().to_string();
+        assert_eq!(message("61 * * * *"), "Invalid minute field '61': 61 is out of range 0-59");
+        assert_eq!(message("0 9-x * * *"), "Invalid hour field '9-x': 'x' is not a number");
+        assert_eq!(message("0 17-9 * * *"), "Invalid hour field '17-9': range 17-9 starts after it ends");
+        assert_eq!(message("*/0 * * * *"), "Invalid minute field '*/0': step '0' must be a positive number");
+        assert_eq!(message("0 0 * FOO *"), "Invalid month field 'FOO': 'FOO' is not a number or month name (JAN-DEC)");
+        assert_eq!(message("0 0 * * 5#6"), "Invalid day-of-week field '5#6': occurrence in '5#6' must be between 1 and 5");
+        assert_eq!(message("0 0 32W * *"), "Invalid day-of-month field '32W': 32 is out of range 1-31");
+        assert_eq!(
+            message("0 L * * *"),
+            "Invalid hour field 'L': L, W and # are only supported in the day-of-month and day-of-week fields"
+        );
+        assert_eq!(message("0 0 0 * * * 1900"), "Invalid year field '1900': 1900 is out of range 2000-2099");
+        assert_eq!(message("0,,1 * * * *"), "Invalid minute field '0,,1': missing value");
+    }
+
+    #[test]
+    fn test_upgrade_legacy() {
+        // 旧语法 1 为周日、7 为周六
+        assert_eq!(upgrade_legacy("0 0 9 * * 2-6").unwrap(), "0 0 9 * * MON-FRI");
+        assert_eq!(upgrade_legacy("0 0 9 * * 1").unwrap(), "0 0 9 * * SUN");
+        assert_eq!(upgrade_legacy("0 0 9 * * 7 2030").unwrap(), "0 0 9 * * SAT 2030");
+        assert_eq!(upgrade_legacy("0 0 9 ? * Saturday,sun").unwrap(), "0 0 9 ? * SUN,SAT");
+        assert_eq!(upgrade_legacy("0 0 9 * * */2").unwrap(), "0 0 9 * * SUN,TUE,THU,SAT");
+        assert_eq!(upgrade_legacy("0 0 9 * * 4/2").unwrap(), "0 0 9 * * WED,FRI");
+        // 改写后的表达式在原来的日期触发：2024-01-01 为周一
+        let upgraded = upgrade_legacy("0 30 9 * * 2-6").unwrap();
+        assert_eq!(
+            days(&upgraded, at(2024, 1, 5, 12, 0, 0), 3),
+            vec![(1, 8), (1, 9), (1, 10)]
+        );
+
+        // 不限制星期的表达式和宏不变
+        assert_eq!(upgrade_legacy(" 0 0 9 1 * * ").unwrap(), "0 0 9 1 * *");
+        assert_eq!(upgrade_legacy("@weekly").unwrap(), "@weekly");
+        assert_eq!(upgrade_legacy("0 0 9 1 * 1-7").unwrap(), "0 0 9 1 * *");
+
+        // 日和周都受限时两种语法含义不同
+        assert_eq!(
+            upgrade_legacy("0 0 9 1 * 2"),
+            Err(CronError::LegacyDayMatch {
+                day_of_month: "1".to_string(),
+                day_of_week: "2".to_string(),
+            })
+        );
+        assert_eq!(upgrade_legacy("0 9 * * 1"), Err(CronError::FieldCount(5)));
+        assert_eq!(
+            upgrade_legacy("0 0 9 * * 0").unwrap_err().to_string(),
+            "Invalid day-of-week field '0': 0 is out of range 1-7"
+        );
+    }
+}
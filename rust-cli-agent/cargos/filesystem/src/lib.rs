@@ -6,6 +6,10 @@ use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::broadcast;
 
+pub mod llm;
+
+pub use llm::FileSystemToolAdapter;
+
 pub type FsResult<T> = std::result::Result<T, FileSystemError>;
 
 #[derive(Debug, Error)]
@@ -104,13 +108,32 @@ impl FileSystemService {
         }
     }
 
-    /// 开始监控路径
+    /// 开始监控路径，目录会递归监控
     pub async fn watch(&mut self, path: PathBuf) -> FsResult<()> {
+        self.watch_with_mode(path, RecursiveMode::Recursive)
+    }
+
+    /// 开始监控路径，目录只监控其直接子项
+    ///
+    /// 适合监控大目录中的单个文件：编辑器保存时常以重命名替换文件，
+    /// 直接监控文件会在替换后失效，应改为监控所在目录
+    pub async fn watch_shallow(&mut self, path: PathBuf) -> FsResult<()> {
+        self.watch_with_mode(path, RecursiveMode::NonRecursive)
+    }
+
+    /// 多次监控共用同一个 watcher，已监控的路径保持有效
+    fn watch_with_mode(&mut self, path: PathBuf, mode: RecursiveMode) -> FsResult<()> {
         // 检查路径是否存在
         if !path.exists() {
             return Err(FileSystemError::PathNotFound(path));
         }
 
+        if let Some(watcher) = self.watcher.as_mut() {
+            watcher.watch(&path, mode)?;
+            self._watched_paths.push(path);
+            return Ok(());
+        }
+
         // 创建事件通道
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
 
@@ -135,9 +158,10 @@ impl FileSystemService {
         })?;
 
         // 开始监控
-        watcher.watch(&path, RecursiveMode::Recursive)?;
+        watcher.watch(&path, mode)?;
 
         self.watcher = Some(watcher);
+        self._watched_paths.push(path);
 
         // 启动事件转发任务
         let event_tx = self.event_tx.clone();
@@ -444,6 +468,37 @@ mod tests {
         assert_eq!(received2.unwrap(), test_event);
     }
 
+    #[tokio::test]
+    async fn test_watch_multiple_paths() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let first_dir = first.path().canonicalize().unwrap();
+        let mut service = FileSystemService::new();
+
+        service.watch_shallow(first_dir.clone()).await.unwrap();
+        service.watch_shallow(second.path().to_path_buf()).await.unwrap();
+        assert_eq!(service._watched_paths.len(), 2);
+
+        // 监控第二个路径后，第一个路径仍然有效
+        let mut rx = service.subscribe();
+        let file_path = first_dir.join("config.toml");
+        fs::write(&file_path, "a = 1").unwrap();
+
+        let received = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                match rx.recv().await {
+                    Ok(FileSystemEvent::Created { path } | FileSystemEvent::Modified { path }) if path == file_path => {
+                        return true
+                    }
+                    Ok(_) => {}
+                    Err(_) => return false,
+                }
+            }
+        })
+        .await;
+        assert_eq!(received.ok(), Some(true));
+    }
+
     #[tokio::test]
     async fn test_real_file_operations() {
         let temp_dir = TempDir::new().unwrap();
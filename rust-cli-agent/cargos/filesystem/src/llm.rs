@@ -0,0 +1,348 @@
+//! LLM 工具适配器
+//!
+//! `fs_watch` 创建监控并返回 ID，之后的变化在 `fs_wait_for_change` 取走之前一直缓冲；
+//! `fs_wait_for_change` 也可以直接指定路径，只等待调用期间发生的变化
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, Mutex};
+use tools::{Arguments, CallToolRequest, CallToolResponse, Tool, ToolError, ToolProvider, ToolResult};
+
+use crate::{FileSystemError, FileSystemEvent, FileSystemService};
+
+/// 默认等待时间(秒)
+const DEFAULT_WAIT_SECS: u64 = 30;
+
+/// 最长等待时间(秒)
+const MAX_WAIT_SECS: u64 = 300;
+
+/// 收到第一个事件后继续收集的时间，合并一次保存产生的多个事件
+const SETTLE_DELAY: Duration = Duration::from_millis(200);
+
+/// 单次返回的最大事件数
+const MAX_EVENTS: usize = 100;
+
+/// 一个活动的监控
+struct Watch {
+    path: PathBuf,
+    /// 持有服务以保持底层 watcher 存活
+    _service: std::sync::Mutex<FileSystemService>,
+    events: Mutex<broadcast::Receiver<FileSystemEvent>>,
+}
+
+impl Watch {
+    async fn start(path: PathBuf) -> ToolResult<Self> {
+        let mut service = FileSystemService::new();
+        service.watch(path.clone()).await.map_err(tool_error)?;
+        let events = Mutex::new(service.subscribe());
+        Ok(Self {
+            path,
+            _service: std::sync::Mutex::new(service),
+            events,
+        })
+    }
+
+    /// 等待变化，超时返回空列表
+    async fn wait(&self, timeout: Duration) -> Vec<FileSystemEvent> {
+        let mut receiver = self.events.lock().await;
+        let mut events = Vec::new();
+        let first = tokio::time::timeout(timeout, next_event(&mut receiver)).await;
+        if let Ok(Some(event)) = first {
+            events.push(event);
+            let settle = tokio::time::sleep(SETTLE_DELAY);
+            tokio::pin!(settle);
+            while events.len() < MAX_EVENTS {
+                tokio::select! {
+                    _ = &mut settle => break,
+                    event = next_event(&mut receiver) => match event {
+                        Some(event) if !events.contains(&event) => events.push(event),
+                        Some(_) => {}
+                        None => break,
+                    },
+                }
+            }
+        }
+        events
+    }
+}
+
+/// 读取下一个事件，跳过因缓冲区满而丢失的部分
+async fn next_event(receiver: &mut broadcast::Receiver<FileSystemEvent>) -> Option<FileSystemEvent> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => return Some(event),
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// 文件系统工具适配器
+pub struct FileSystemToolAdapter {
+    watches: Mutex<HashMap<String, Arc<Watch>>>,
+    next_id: AtomicU64,
+    max_watches: usize,
+}
+
+impl FileSystemToolAdapter {
+    pub fn new() -> Self {
+        Self {
+            watches: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            max_watches: 16,
+        }
+    }
+
+    /// 设置同时存在的监控数量上限
+    pub fn with_max_watches(mut self, max_watches: usize) -> Self {
+        self.max_watches = max_watches;
+        self
+    }
+
+    /// 获取所有工具定义
+    pub fn get_tools(&self) -> Vec<Tool> {
+        vec![
+            Tool {
+                name: "fs_watch".to_string(),
+                description: "Start watching a file or directory (recursively) and return a watch_id; changes are buffered until fs_wait_for_change collects them".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "File or directory to watch"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            Tool {
+                name: "fs_wait_for_change".to_string(),
+                description: "Wait until a watched path changes and return the created, modified and deleted paths. Pass watch_id from fs_watch, or path to watch only for the duration of this call.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "watch_id": {
+                            "type": "string",
+                            "description": "Watch ID returned by fs_watch"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Path to watch for this call only"
+                        },
+                        "timeout_secs": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": MAX_WAIT_SECS,
+                            "description": format!("Seconds to wait, defaults to {}", DEFAULT_WAIT_SECS)
+                        }
+                    }
+                }),
+            },
+            Tool {
+                name: "fs_unwatch".to_string(),
+                description: "Stop a watch created by fs_watch".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "watch_id": {
+                            "type": "string",
+                            "description": "Watch ID returned by fs_watch"
+                        }
+                    },
+                    "required": ["watch_id"]
+                }),
+            },
+        ]
+    }
+
+    /// 调用工具
+    pub async fn call_tool(&self, request: CallToolRequest) -> CallToolResponse {
+        let result = match request.name.as_str() {
+            "fs_watch" => self.call_watch(&request.arguments).await,
+            "fs_wait_for_change" => self.call_wait_for_change(&request.arguments).await,
+            "fs_unwatch" => self.call_unwatch(&request.arguments).await,
+            _ => Err(ToolError::UnknownTool(request.name)),
+        };
+        CallToolResponse::from_result(result)
+    }
+
+    async fn call_watch(&self, arguments: &Value) -> ToolResult<String> {
+        let args = Arguments::new(arguments)?;
+        let path = PathBuf::from(args.required_str("path")?);
+        if self.watches.lock().await.len() >= self.max_watches {
+            return Err(ToolError::NotAvailable(format!(
+                "at most {} watches can be active; remove one with fs_unwatch",
+                self.max_watches
+            )));
+        }
+
+        let watch = Watch::start(path.clone()).await?;
+        let id = format!("watch-{}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.watches.lock().await.insert(id.clone(), Arc::new(watch));
+        let output = json!({"watch_id": id, "path": path});
+        Ok(serde_json::to_string_pretty(&output).unwrap_or_default())
+    }
+
+    async fn call_wait_for_change(&self, arguments: &Value) -> ToolResult<String> {
+        let args = Arguments::new(arguments)?;
+        let timeout_secs = match args.u64("timeout_secs")? {
+            Some(secs) if (1..=MAX_WAIT_SECS).contains(&secs) => secs,
+            Some(_) => {
+                return Err(ToolError::InvalidArguments(format!(
+                    "timeout_secs must be between 1 and {}",
+                    MAX_WAIT_SECS
+                )))
+            }
+            None => DEFAULT_WAIT_SECS,
+        };
+
+        let (watch_id, watch) = match (args.str("watch_id")?, args.str("path")?) {
+            (Some(id), _) => (Some(id), self.watch(id).await?),
+            (None, Some(path)) => (None, Arc::new(Watch::start(PathBuf::from(path)).await?)),
+            (None, None) => {
+                return Err(ToolError::InvalidArguments("either watch_id or path is required".to_string()))
+            }
+        };
+
+        let events = watch.wait(Duration::from_secs(timeout_secs)).await;
+        let output = json!({
+            "watch_id": watch_id,
+            "path": watch.path,
+            "changed": !events.is_empty(),
+            "events": events.iter().map(event_json).collect::<Vec<_>>(),
+        });
+        Ok(serde_json::to_string_pretty(&output).unwrap_or_default())
+    }
+
+    async fn call_unwatch(&self, arguments: &Value) -> ToolResult<String> {
+        let args = Arguments::new(arguments)?;
+        let id = args.required_str("watch_id")?;
+        match self.watches.lock().await.remove(id) {
+            Some(watch) => Ok(format!("Stopped watching {}", watch.path.display())),
+            None => Err(unknown_watch(id)),
+        }
+    }
+
+    async fn watch(&self, id: &str) -> ToolResult<Arc<Watch>> {
+        self.watches.lock().await.get(id).cloned().ok_or_else(|| unknown_watch(id))
+    }
+}
+
+impl Default for FileSystemToolAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ToolProvider for FileSystemToolAdapter {
+    fn tools(&self) -> Vec<Tool> {
+        self.get_tools()
+    }
+
+    async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+        self.call_tool(request).await
+    }
+}
+
+fn event_json(event: &FileSystemEvent) -> Value {
+    let (kind, path) = match event {
+        FileSystemEvent::Created { path } => ("created", path),
+        FileSystemEvent::Modified { path } => ("modified", path),
+        FileSystemEvent::Deleted { path } => ("deleted", path),
+    };
+    json!({"kind": kind, "path": path})
+}
+
+fn unknown_watch(id: &str) -> ToolError {
+    ToolError::InvalidArguments(format!("unknown watch_id: {}", id))
+}
+
+/// 文件系统错误映射为统一的工具错误
+fn tool_error(error: FileSystemError) -> ToolError {
+    match error {
+        FileSystemError::PathNotFound(path) => {
+            ToolError::InvalidArguments(format!("path does not exist: {}", path.display()))
+        }
+        other => ToolError::ExecutionFailed(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn call(adapter: &FileSystemToolAdapter, name: &str, arguments: Value) -> CallToolResponse {
+        adapter
+            .call_tool(CallToolRequest {
+                name: name.to_string(),
+                arguments,
+            })
+            .await
+    }
+
+    fn output(response: &CallToolResponse) -> Value {
+        assert!(response.success, "{:?}", response.error);
+        serde_json::from_str(response.content[0].text.as_deref().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_watch_buffers_changes_until_waited() {
+        let temp_dir = TempDir::new().unwrap();
+        let adapter = FileSystemToolAdapter::new();
+
+        let watch = output(&call(&adapter, "fs_watch", json!({"path": temp_dir.path()})).await);
+        let watch_id = watch["watch_id"].as_str().unwrap().to_string();
+        assert_eq!(watch_id, "watch-1");
+
+        // 变化发生在等待之前，应被缓冲
+        std::fs::write(temp_dir.path().join("a.txt"), b"hello").unwrap();
+        let waited = output(&call(&adapter, "fs_wait_for_change", json!({"watch_id": watch_id, "timeout_secs": 5})).await);
+        assert_eq!(waited["changed"], true);
+        let events = waited["events"].as_array().unwrap();
+        assert!(events.iter().any(|e| e["path"].as_str().unwrap().ends_with("a.txt")));
+
+        let response = call(&adapter, "fs_unwatch", json!({"watch_id": watch_id})).await;
+        assert!(response.success);
+        let response = call(&adapter, "fs_wait_for_change", json!({"watch_id": watch_id})).await;
+        assert_eq!(response.error.as_deref(), Some("Invalid arguments: unknown watch_id: watch-1"));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_change_by_path_times_out() {
+        let temp_dir = TempDir::new().unwrap();
+        let adapter = FileSystemToolAdapter::new();
+
+        let waited = output(&call(&adapter, "fs_wait_for_change", json!({"path": temp_dir.path(), "timeout_secs": 1})).await);
+        assert_eq!(waited["changed"], false);
+        assert!(waited["watch_id"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_arguments_and_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let adapter = FileSystemToolAdapter::new().with_max_watches(1);
+
+        let response = call(&adapter, "fs_watch", json!({"path": temp_dir.path().join("missing")})).await;
+        assert!(response.error.unwrap().starts_with("Invalid arguments: path does not exist"));
+        let response = call(&adapter, "fs_wait_for_change", json!({})).await;
+        assert_eq!(
+            response.error.as_deref(),
+            Some("Invalid arguments: either watch_id or path is required")
+        );
+        let response = call(&adapter, "fs_wait_for_change", json!({"path": temp_dir.path(), "timeout_secs": 0})).await;
+        assert!(!response.success);
+
+        assert!(call(&adapter, "fs_watch", json!({"path": temp_dir.path()})).await.success);
+        let response = call(&adapter, "fs_watch", json!({"path": temp_dir.path()})).await;
+        assert!(response.error.unwrap().starts_with("Not available"));
+    }
+}
@@ -101,9 +101,12 @@ fn get_linux_version() -> Option<String> {
     use std::fs::read_to_string;
     read_to_string("/etc/os-release")
         .ok()
-        .and_then(|s| s.lines().find(|l| l.starts_with("PRETTY_NAME=")))
-        .and_then(|l| l.split('=').nth(1)
-            .map(|s| s.trim().trim_matches('"').to_string()))
+        .and_then(|s| {
+            s.lines()
+                .find(|l| l.starts_with("PRETTY_NAME="))
+                .and_then(|l| l.split('=').nth(1))
+                .map(|v| v.trim().trim_matches('"').to_string())
+        })
 }
 
 #[cfg(windows)]
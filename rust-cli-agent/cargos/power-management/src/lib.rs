@@ -796,8 +796,11 @@ mod platform_impl {
     }
 }
 
+pub mod llm;
+
 // Re-export main types
 pub use crate::LocalPowerManager as DefaultPowerManager;
+pub use llm::PowerToolAdapter;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,278 @@
+//! LLM tool adapter
+//!
+//! Exposes capability queries, wake timers and suspend as tools. Suspending
+//! the machine ends the caller's session, so `power_suspend` refuses to act
+//! unless the call carries `confirm: true`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tools::{Arguments, CallToolRequest, CallToolResponse, Tool, ToolError, ToolProvider, ToolResult};
+
+use crate::{PowerError, PowerManager, PowerState, WakeConfig};
+
+/// Tool adapter over a [`PowerManager`]
+pub struct PowerToolAdapter<P: PowerManager> {
+    manager: Arc<P>,
+}
+
+impl<P: PowerManager> PowerToolAdapter<P> {
+    /// Create an adapter for the given power manager
+    pub fn new(manager: Arc<P>) -> Self {
+        Self { manager }
+    }
+
+    /// All tool definitions
+    pub fn get_tools(&self) -> Vec<Tool> {
+        vec![
+            Tool {
+                name: "power_capabilities".to_string(),
+                description: "Report the current power state and whether hibernation, scheduled wake and RTC wake are supported".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            Tool {
+                name: "power_schedule_wake".to_string(),
+                description: "Schedule the machine to wake up after the given number of seconds".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "seconds": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Seconds from now until the wake time"
+                        },
+                        "label": {
+                            "type": "string",
+                            "description": "Label for the wake timer"
+                        }
+                    },
+                    "required": ["seconds"]
+                }),
+            },
+            Tool {
+                name: "power_cancel_wake".to_string(),
+                description: "Cancel scheduled wake timers".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            Tool {
+                name: "power_suspend".to_string(),
+                description: "Put the machine to sleep or hibernate. Requires confirm=true; ask the user before calling.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "mode": {
+                            "type": "string",
+                            "enum": ["sleep", "hibernate"],
+                            "description": "Suspend mode, defaults to sleep"
+                        },
+                        "confirm": {
+                            "type": "boolean",
+                            "description": "Must be true to actually suspend"
+                        }
+                    },
+                    "required": ["confirm"]
+                }),
+            },
+        ]
+    }
+
+    /// Dispatch a tool call
+    pub async fn call_tool(&self, request: CallToolRequest) -> CallToolResponse {
+        let result = match request.name.as_str() {
+            "power_capabilities" => self.call_capabilities().await,
+            "power_schedule_wake" => self.call_schedule_wake(&request.arguments).await,
+            "power_cancel_wake" => self.call_cancel_wake().await,
+            "power_suspend" => self.call_suspend(&request.arguments).await,
+            _ => Err(ToolError::UnknownTool(request.name)),
+        };
+        CallToolResponse::from_result(result)
+    }
+
+    async fn call_capabilities(&self) -> ToolResult<String> {
+        let state = self.manager.get_power_state().await.map_err(tool_error)?;
+        let output = json!({
+            "state": format!("{:?}", state).to_lowercase(),
+            "hibernation": self.manager.supports_hibernation().await,
+            "scheduled_wake": self.manager.supports_scheduled_wake().await,
+            "rtc_wake": self.manager.supports_rtc_wake().await,
+        });
+        Ok(serde_json::to_string_pretty(&output).unwrap_or_default())
+    }
+
+    async fn call_schedule_wake(&self, arguments: &Value) -> ToolResult<String> {
+        let args = Arguments::new(arguments)?;
+        let seconds = args
+            .u64("seconds")?
+            .ok_or_else(|| ToolError::InvalidArguments("missing required argument: seconds".to_string()))?;
+        let mut config = WakeConfig::new(Duration::from_secs(seconds)).map_err(tool_error)?;
+        if let Some(label) = args.str("label")? {
+            config = config.with_label(label);
+        }
+        let wake_time = config.wake_time();
+
+        self.manager.schedule_wake(config).await.map_err(tool_error)?;
+        Ok(format!("Wake scheduled at {}", wake_time.to_rfc3339()))
+    }
+
+    async fn call_cancel_wake(&self) -> ToolResult<String> {
+        self.manager.cancel_wake().await.map_err(tool_error)?;
+        Ok("Wake timers cancelled".to_string())
+    }
+
+    async fn call_suspend(&self, arguments: &Value) -> ToolResult<String> {
+        let args = Arguments::new(arguments)?;
+        let state = match args.str("mode")?.unwrap_or("sleep") {
+            "sleep" => PowerState::Sleep,
+            "hibernate" => PowerState::Hibernate,
+            other => {
+                return Err(ToolError::InvalidArguments(format!(
+                    "mode must be sleep or hibernate, got {}",
+                    other
+                )))
+            }
+        };
+        if args.bool("confirm")? != Some(true) {
+            return Err(ToolError::ConfirmationRequired(
+                "suspending the machine interrupts running work; call again with confirm=true".to_string(),
+            ));
+        }
+        if state == PowerState::Hibernate && !self.manager.supports_hibernation().await {
+            return Err(ToolError::NotAvailable("hibernation is not supported on this machine".to_string()));
+        }
+
+        self.manager.set_power_state(state).await.map_err(tool_error)?;
+        Ok(format!("Entered {:?}", state).to_lowercase())
+    }
+}
+
+#[async_trait]
+impl<P: PowerManager + 'static> ToolProvider for PowerToolAdapter<P> {
+    fn tools(&self) -> Vec<Tool> {
+        self.get_tools()
+    }
+
+    async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+        self.call_tool(request).await
+    }
+}
+
+/// Map power errors onto the shared tool error categories
+fn tool_error(error: PowerError) -> ToolError {
+    match error {
+        PowerError::InvalidWakeTime(message) | PowerError::InvalidConfig(message) => {
+            ToolError::InvalidArguments(message)
+        }
+        PowerError::Unsupported | PowerError::RtcWakeNotAvailable(_) => ToolError::NotAvailable(error.to_string()),
+        other => ToolError::ExecutionFailed(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records requested transitions instead of touching the machine
+    #[derive(Default)]
+    struct RecordingPowerManager {
+        states: Mutex<Vec<PowerState>>,
+        wakes: Mutex<Vec<WakeConfig>>,
+    }
+
+    #[async_trait]
+    impl PowerManager for RecordingPowerManager {
+        async fn get_power_state(&self) -> crate::Result<PowerState> {
+            Ok(PowerState::Awake)
+        }
+
+        async fn set_power_state(&self, state: PowerState) -> crate::Result<()> {
+            self.states.lock().unwrap().push(state);
+            Ok(())
+        }
+
+        async fn schedule_wake(&self, config: WakeConfig) -> crate::Result<()> {
+            self.wakes.lock().unwrap().push(config);
+            Ok(())
+        }
+
+        async fn cancel_wake(&self) -> crate::Result<()> {
+            Err(PowerError::Unsupported)
+        }
+
+        async fn supports_hibernation(&self) -> bool {
+            false
+        }
+
+        async fn supports_scheduled_wake(&self) -> bool {
+            true
+        }
+
+        async fn supports_rtc_wake(&self) -> bool {
+            true
+        }
+    }
+
+    async fn call(adapter: &PowerToolAdapter<RecordingPowerManager>, name: &str, arguments: Value) -> CallToolResponse {
+        adapter
+            .call_tool(CallToolRequest {
+                name: name.to_string(),
+                arguments,
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_and_wake() {
+        let manager = Arc::new(RecordingPowerManager::default());
+        let adapter = PowerToolAdapter::new(manager.clone());
+
+        let response = call(&adapter, "power_capabilities", json!({})).await;
+        let output: Value = serde_json::from_str(response.content[0].text.as_deref().unwrap()).unwrap();
+        assert_eq!(output["state"], "awake");
+        assert_eq!(output["hibernation"], false);
+
+        let response = call(&adapter, "power_schedule_wake", json!({"seconds": 90, "label": "backup"})).await;
+        assert!(response.success);
+        let wake = manager.wakes.lock().unwrap()[0].clone();
+        assert_eq!(wake.duration, Duration::from_secs(90));
+        assert_eq!(wake.label.as_deref(), Some("backup"));
+
+        let response = call(&adapter, "power_schedule_wake", json!({"seconds": 0})).await;
+        assert_eq!(
+            response.error.as_deref(),
+            Some("Invalid arguments: Wake duration must be greater than 0")
+        );
+
+        let response = call(&adapter, "power_cancel_wake", json!({})).await;
+        assert!(response.error.unwrap().starts_with("Not available"));
+    }
+
+    #[tokio::test]
+    async fn test_suspend_requires_confirmation() {
+        let manager = Arc::new(RecordingPowerManager::default());
+        let adapter = PowerToolAdapter::new(manager.clone());
+
+        let response = call(&adapter, "power_suspend", json!({})).await;
+        assert!(response.error.unwrap().starts_with("Confirmation required"));
+        let response = call(&adapter, "power_suspend", json!({"confirm": false})).await;
+        assert!(!response.success);
+        assert!(manager.states.lock().unwrap().is_empty());
+
+        let response = call(&adapter, "power_suspend", json!({"mode": "hibernate", "confirm": true})).await;
+        assert!(response.error.unwrap().starts_with("Not available"));
+        let response = call(&adapter, "power_suspend", json!({"mode": "off", "confirm": true})).await;
+        assert!(response.error.unwrap().starts_with("Invalid arguments"));
+
+        let response = call(&adapter, "power_suspend", json!({"confirm": true})).await;
+        assert!(response.success);
+        assert_eq!(*manager.states.lock().unwrap(), vec![PowerState::Sleep]);
+    }
+}
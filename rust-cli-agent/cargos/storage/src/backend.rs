@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use config::{StorageBackend, StorageConfig};
+
+use crate::error::Result;
+use crate::json_storage::JsonStorage;
+use crate::memory_storage::MemoryStorage;
+use crate::sled_storage::SledStorage;
+use crate::sqlite_storage::SqliteStorage;
+use crate::r#trait::Storage;
+
+/// 按存储配置打开存储
+///
+/// `config.path` 为数据目录，调用方负责展开 `~`。各后端在目录中使用各自的文件，
+/// 切换后端不会读到其他后端的数据
+pub fn open_storage(config: &StorageConfig) -> Result<Arc<dyn Storage>> {
+    let storage: Arc<dyn Storage> = match config.backend {
+        StorageBackend::Sled => Arc::new(SledStorage::new(config.path.join("storage.sled"))?),
+        StorageBackend::Json => Arc::new(JsonStorage::new(config.path.join("storage.json"))?),
+        StorageBackend::Sqlite => Arc::new(SqliteStorage::new(config.path.join("storage.db"))?),
+        StorageBackend::Memory => Arc::new(MemoryStorage::new()),
+    };
+    Ok(storage)
+}
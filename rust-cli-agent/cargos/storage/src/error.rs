@@ -12,6 +12,8 @@ pub enum StorageError {
     Json(String),
     #[error("Sled error: {0}")]
     Sled(String),
+    #[error("SQLite error: {0}")]
+    Sqlite(String),
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -22,6 +24,12 @@ impl From<sled::Error> for StorageError {
     }
 }
 
+impl From<rusqlite::Error> for StorageError {
+    fn from(err: rusqlite::Error) -> Self {
+        StorageError::Sqlite(err.to_string())
+    }
+}
+
 impl From<tokio::task::JoinError> for StorageError {
     fn from(err: tokio::task::JoinError) -> Self {
         StorageError::Other(err.to_string())
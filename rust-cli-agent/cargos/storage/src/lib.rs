@@ -1,13 +1,17 @@
+mod backend;
 mod error;
 mod json_storage;
 mod memory_storage;
 mod sled_storage;
+mod sqlite_storage;
 mod r#trait;
 
+pub use backend::open_storage;
 pub use error::{Result, StorageError};
 pub use json_storage::JsonStorage;
 pub use memory_storage::MemoryStorage;
 pub use sled_storage::SledStorage;
+pub use sqlite_storage::{migrate_sqlite, open_sqlite, SqliteStorage};
 pub use r#trait::Storage;
 
 #[cfg(test)]
@@ -110,4 +114,61 @@ mod tests {
             assert_eq!(value, Some("value1".to_string()));
         }
     }
+
+    #[tokio::test]
+    async fn test_sqlite_storage() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("storage.db");
+
+        {
+            let storage = SqliteStorage::new(path.clone()).unwrap();
+            storage.set("key1", "value1").await.unwrap();
+            storage.set("key1", "value2").await.unwrap();
+            storage.set("key2", "value3").await.unwrap();
+            storage.delete("key2").await.unwrap();
+            assert!(!storage.exists("key2").await.unwrap());
+        }
+
+        let storage = SqliteStorage::new(path).unwrap();
+        assert_eq!(storage.get("key1").await.unwrap(), Some("value2".to_string()));
+        assert_eq!(storage.list_keys().await.unwrap(), vec!["key1".to_string()]);
+    }
+
+    #[test]
+    fn test_sqlite_migrations() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("test.db");
+        let v1 = ["CREATE TABLE a (id INTEGER PRIMARY KEY)"];
+        let v2 = ["CREATE TABLE a (id INTEGER PRIMARY KEY)", "ALTER TABLE a ADD COLUMN name TEXT"];
+
+        let mut conn = open_sqlite(&path).unwrap();
+        let mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(mode, "wal");
+        assert_eq!(migrate_sqlite(&mut conn, &v1).unwrap(), 1);
+        assert_eq!(migrate_sqlite(&mut conn, &v2).unwrap(), 1);
+        assert_eq!(migrate_sqlite(&mut conn, &v2).unwrap(), 0);
+        conn.execute("INSERT INTO a (name) VALUES ('x')", []).unwrap();
+        // 数据库版本比程序新时拒绝打开
+        assert!(matches!(migrate_sqlite(&mut conn, &v1), Err(StorageError::Sqlite(_))));
+    }
+
+    #[tokio::test]
+    async fn test_open_storage_by_backend() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for backend in [
+            config::StorageBackend::Sled,
+            config::StorageBackend::Json,
+            config::StorageBackend::Sqlite,
+            config::StorageBackend::Memory,
+        ] {
+            let config = config::StorageConfig {
+                backend,
+                path: temp_dir.path().to_path_buf(),
+            };
+            let storage = open_storage(&config).unwrap();
+            storage.set("key1", "value1").await.unwrap();
+            assert_eq!(storage.get("key1").await.unwrap(), Some("value1".to_string()));
+        }
+        assert!(temp_dir.path().join("storage.db").exists());
+    }
 }
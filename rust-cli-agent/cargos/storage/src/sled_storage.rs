@@ -13,6 +13,11 @@ impl SledStorage {
         let db = sled::open(path)?;
         Ok(Self { db })
     }
+
+    /// 底层数据库，用于在键值接口之外使用独立的树和事务
+    pub fn db(&self) -> &sled::Db {
+        &self.db
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,143 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+
+use crate::error::{Result, StorageError};
+use crate::r#trait::Storage;
+
+/// 其他进程写入时等待锁释放的最长时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 键值表结构，按顺序执行
+const MIGRATIONS: &[&str] = &["CREATE TABLE kv (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL
+);"];
+
+/// 打开 SQLite 数据库并启用 WAL
+///
+/// WAL 模式下写入不阻塞读取，守护进程写入时其他 CLI 进程仍可读取
+pub fn open_sqlite(path: &Path) -> Result<Connection> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    let mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+    if !mode.eq_ignore_ascii_case("wal") {
+        return Err(StorageError::Sqlite(format!("Failed to enable WAL mode, got {}", mode)));
+    }
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    Ok(conn)
+}
+
+/// 执行尚未执行的结构迁移，返回本次执行的数量
+///
+/// `PRAGMA user_version` 记录已执行的迁移数量。迁移在写事务中执行，
+/// 多个进程同时打开数据库时只有一个进程执行迁移
+pub fn migrate_sqlite(conn: &mut Connection, migrations: &[&str]) -> Result<usize> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let version: i64 = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let version = version as usize;
+    if version > migrations.len() {
+        return Err(StorageError::Sqlite(format!(
+            "Database schema version {} is newer than supported version {}",
+            version,
+            migrations.len()
+        )));
+    }
+    for migration in &migrations[version..] {
+        tx.execute_batch(migration)?;
+    }
+    tx.pragma_update(None, "user_version", migrations.len() as i64)?;
+    tx.commit()?;
+    Ok(migrations.len() - version)
+}
+
+pub struct SqliteStorage {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    pub fn new(path: PathBuf) -> Result<Self> {
+        let mut conn = open_sqlite(&path)?;
+        migrate_sqlite(&mut conn, MIGRATIONS)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// 在阻塞线程中使用连接
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|e| StorageError::Other(e.to_string()))?;
+            f(&conn)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        let key = key.to_string();
+        self.with_conn(move |conn| {
+            Ok(conn
+                .query_row("SELECT value FROM kv WHERE key = ?1", params![key], |row| row.get(0))
+                .optional()?)
+        })
+        .await
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<()> {
+        let key = key.to_string();
+        let value = value.to_string();
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO kv (key, value) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                params![key, value],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let key = key.to_string();
+        self.with_conn(move |conn| {
+            conn.execute("DELETE FROM kv WHERE key = ?1", params![key])?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_keys(&self) -> Result<Vec<String>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT key FROM kv ORDER BY key")?;
+            let keys = stmt
+                .query_map([], |row| row.get(0))?
+                .collect::<std::result::Result<Vec<String>, _>>()?;
+            Ok(keys)
+        })
+        .await
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        let key = key.to_string();
+        self.with_conn(move |conn| {
+            Ok(conn
+                .query_row("SELECT 1 FROM kv WHERE key = ?1", params![key], |_| Ok(()))
+                .optional()?
+                .is_some())
+        })
+        .await
+    }
+}
@@ -2,8 +2,10 @@
 
 use std::fmt;
 
+use cron_expression::{CronField, CronSchedule};
+
 /// 任务调度频率
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TaskSchedule {
     /// 每小时
     Hourly,
@@ -18,42 +20,86 @@ pub enum TaskSchedule {
 }
 
 impl TaskSchedule {
-    /// 从 cron 表达式转换 (5字段: 分 时 日 月 周)
+    /// 从 cron 表达式转换
+    ///
+    /// 秒字段忽略；只能转换每小时、每天、每周某天、每月某日的固定时间，
+    /// 其他计划 (多个取值、L/W/#、限定月份或年份等) 无法等价表达，返回 None
     pub fn from_cron(cron: &str) -> Option<Self> {
-        let parts: Vec<&str> = cron.split_whitespace().collect();
-        if parts.len() < 5 {
+        let schedule = CronSchedule::parse(cron).ok()?;
+        if schedule.has_special_days()
+            || schedule.is_restricted(CronField::Month)
+            || schedule.is_restricted(CronField::Year)
+        {
             return None;
         }
+        let single = |field: CronField| match schedule.values(field)[..] {
+            [value] => Some(value),
+            _ => None,
+        };
+
+        let minute = single(CronField::Minute)?;
+        if !schedule.is_restricted(CronField::Hour) {
+            let daily = !schedule.is_restricted(CronField::DayOfMonth) && !schedule.is_restricted(CronField::DayOfWeek);
+            return daily.then_some(TaskSchedule::Hourly);
+        }
+        let time = format!("{:02}:{:02}", single(CronField::Hour)?, minute);
+
+        match (
+            schedule.is_restricted(CronField::DayOfMonth),
+            schedule.is_restricted(CronField::DayOfWeek),
+        ) {
+            (false, false) => Some(TaskSchedule::Daily(time)),
+            (false, true) => Some(TaskSchedule::Weekly(time, single(CronField::DayOfWeek)? as u8)),
+            (true, false) => Some(TaskSchedule::Monthly(time, single(CronField::DayOfMonth)? as u8)),
+            (true, true) => None,
+        }
+    }
+
+    /// 从 cron 表达式转换，并把触发时间平移 `offset_minutes` 分钟
+    ///
+    /// 系统调度器按本机时区触发，偏移为本机时区相对任务时区的差值 (本机 - 任务)
+    pub fn from_cron_with_offset(cron: &str, offset_minutes: i32) -> Option<Self> {
+        Self::from_cron(cron)?.shifted(offset_minutes)
+    }
 
-        let minute = parts[0];
-        let hour = parts[1];
-        let day = parts[2];
-        let month = parts[3];
-        let dow = parts[4];
-
-        let time = if hour == "*" && minute == "*" {
-            "00:00".to_string()
-        } else if hour == "*" {
-            format!("00:{}", minute)
-        } else if minute == "*" {
-            format!("{}:00", hour)
-        } else {
-            // 确保两位数
-            let h = if hour.len() == 1 { format!("0{}", hour) } else { hour.to_string() };
-            let m = if minute.len() == 1 { format!("0{}", minute) } else { minute.to_string() };
-            format!("{}:{}", h, m)
+    /// 把触发时间平移 `offset_minutes` 分钟
+    ///
+    /// 跨过午夜时相应调整星期和日期；平移后无法等价表达时
+    /// (如每小时任务平移半小时、每月 1 日前移到上月末) 返回 None
+    pub fn shifted(self, offset_minutes: i32) -> Option<Self> {
+        if offset_minutes == 0 {
+            return Some(self);
+        }
+        // 返回平移后的 "HH:MM" 和跨过的天数
+        let shift = |time: &str| -> Option<(String, i32)> {
+            let (hour, minute) = time.split_once(':')?;
+            let (hour, minute): (i32, i32) = (hour.parse().ok()?, minute.parse().ok()?);
+            if !(0..24).contains(&hour) || !(0..60).contains(&minute) {
+                return None;
+            }
+            let total = hour * 60 + minute + offset_minutes;
+            let minutes = total.rem_euclid(24 * 60);
+            Some((format!("{:02}:{:02}", minutes / 60, minutes % 60), total.div_euclid(24 * 60)))
         };
 
-        if day != "*" && month != "*" {
-            Some(TaskSchedule::Monthly(time, day.parse().ok()?))
-        } else if dow != "*" {
-            Some(TaskSchedule::Weekly(time, dow.parse().ok()?))
-        } else if day != "*" {
-            Some(TaskSchedule::Monthly(time, day.parse().ok()?))
-        } else if hour == "*" {
-            Some(TaskSchedule::Hourly)
-        } else {
-            Some(TaskSchedule::Daily(time))
+        match self {
+            TaskSchedule::Hourly => (offset_minutes % 60 == 0).then_some(TaskSchedule::Hourly),
+            TaskSchedule::Daily(time) => shift(&time).map(|(time, _)| TaskSchedule::Daily(time)),
+            TaskSchedule::Weekly(time, dow) => {
+                let (time, days) = shift(&time)?;
+                let dow = (dow as i32 + days).rem_euclid(7) as u8;
+                Some(TaskSchedule::Weekly(time, dow))
+            }
+            TaskSchedule::Monthly(time, day) => {
+                let (time, days) = shift(&time)?;
+                // 只有各月都存在的日期才能整体前后移动
+                let shifted = day as i32 + days;
+                if days != 0 && (day > 28 || !(1..=28).contains(&shifted)) {
+                    return None;
+                }
+                Some(TaskSchedule::Monthly(time, shifted as u8))
+            }
+            TaskSchedule::Once(time) => shift(&time).map(|(time, _)| TaskSchedule::Once(time)),
         }
     }
 }
@@ -147,3 +193,53 @@ pub trait SystemScheduler: Send + Sync {
     /// 立即运行任务
     async fn run_task(&self, name: &str) -> Result<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_cron_with_seconds() {
+        assert_eq!(TaskSchedule::from_cron("0 30 9 * * *"), Some(TaskSchedule::Daily("09:30".into())));
+        assert_eq!(TaskSchedule::from_cron("30 9 * * *"), Some(TaskSchedule::Daily("09:30".into())));
+        assert_eq!(TaskSchedule::from_cron("@daily"), Some(TaskSchedule::Daily("00:00".into())));
+        assert_eq!(TaskSchedule::from_cron("@hourly"), Some(TaskSchedule::Hourly));
+        assert_eq!(TaskSchedule::from_cron("0 9 * * SUN"), Some(TaskSchedule::Weekly("09:00".into(), 0)));
+        assert_eq!(TaskSchedule::from_cron("0 9 * * 7"), Some(TaskSchedule::Weekly("09:00".into(), 0)));
+    }
+
+    #[test]
+    fn test_from_cron_unsupported() {
+        assert_eq!(TaskSchedule::from_cron("0 9 L * *"), None);
+        assert_eq!(TaskSchedule::from_cron("0 9 * * 1-5"), None);
+        assert_eq!(TaskSchedule::from_cron("0 9 1 6 *"), None);
+        assert_eq!(TaskSchedule::from_cron("*/5 * * * *"), None);
+        assert_eq!(TaskSchedule::from_cron("0 61 * * *"), None);
+    }
+
+    #[test]
+    fn test_shift_to_local_time() {
+        // 上海 09:00 的任务在 UTC 机器上为 01:00
+        assert_eq!(
+            TaskSchedule::from_cron_with_offset("0 0 9 * * *", -480),
+            Some(TaskSchedule::Daily("01:00".into()))
+        );
+        // 跨过午夜时调整星期和日期
+        assert_eq!(
+            TaskSchedule::from_cron_with_offset("0 0 2 * * 1", -480),
+            Some(TaskSchedule::Weekly("18:00".into(), 0))
+        );
+        assert_eq!(
+            TaskSchedule::from_cron_with_offset("0 0 22 * * 6", 180),
+            Some(TaskSchedule::Weekly("01:00".into(), 0))
+        );
+        assert_eq!(
+            TaskSchedule::from_cron_with_offset("0 0 2 15 * *", -480),
+            Some(TaskSchedule::Monthly("18:00".into(), 14))
+        );
+        // 无法等价表达的平移
+        assert_eq!(TaskSchedule::from_cron_with_offset("0 0 2 1 * *", -480), None);
+        assert_eq!(TaskSchedule::from_cron_with_offset("0 0 * * * *", 330), None);
+        assert_eq!(TaskSchedule::from_cron_with_offset("0 0 * * * *", 60), Some(TaskSchedule::Hourly));
+    }
+}
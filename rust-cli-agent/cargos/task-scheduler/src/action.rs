@@ -0,0 +1,529 @@
+//! 任务动作
+//!
+//! `TaskAction` 描述任务要做的事情，随任务一起持久化；
+//! `ActionRegistry` 在加载任务时把动作还原为执行器，重启后任务无需重新注册即可运行
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use command_executor::{
+    Command, CommandEvent, CommandExecutor, ExecutionEnvironment, ExecutionStatus,
+    LocalCommandExecutor,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+use crate::execution::{TaskExecutor, TaskRunContext};
+use crate::scheduler::AsyncTaskExecutor;
+use crate::types::TaskExecutionResult;
+
+/// 用户参数以环境变量形式传给命令时使用的前缀，如 `user=bob` -> `SKER_PARAM_USER=bob`
+pub const PARAM_ENV_PREFIX: &str = "SKER_PARAM_";
+
+/// 可序列化的任务动作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskAction {
+    /// 通过 shell 执行一行命令
+    Shell {
+        command: String,
+        #[serde(default)]
+        env: HashMap<String, String>,
+        #[serde(default)]
+        timeout_secs: Option<u64>,
+    },
+    /// 直接执行程序
+    Program {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: HashMap<String, String>,
+        #[serde(default)]
+        working_dir: Option<PathBuf>,
+        #[serde(default)]
+        timeout_secs: Option<u64>,
+    },
+    /// 发送 HTTP 请求
+    Http {
+        #[serde(default = "default_http_method")]
+        method: String,
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        body: Option<String>,
+        #[serde(default)]
+        timeout_secs: Option<u64>,
+    },
+}
+
+fn default_http_method() -> String {
+    "GET".to_string()
+}
+
+impl TaskAction {
+    /// 创建 shell 命令动作
+    pub fn shell(command: impl Into<String>) -> Self {
+        TaskAction::Shell {
+            command: command.into(),
+            env: HashMap::new(),
+            timeout_secs: None,
+        }
+    }
+
+    /// 动作类型名，与序列化时的 `type` 字段一致
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TaskAction::Shell { .. } => "shell",
+            TaskAction::Program { .. } => "program",
+            TaskAction::Http { .. } => "http",
+        }
+    }
+
+    /// 简短描述，用于列表展示
+    pub fn summary(&self) -> String {
+        match self {
+            TaskAction::Shell { command, .. } => command.clone(),
+            TaskAction::Program { program, args, .. } => {
+                std::iter::once(program.as_str())
+                    .chain(args.iter().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            TaskAction::Http { method, url, .. } => format!("{} {}", method, url),
+        }
+    }
+}
+
+/// 由动作构造执行器的工厂
+pub type ActionFactory = Arc<dyn Fn(&TaskAction) -> Result<AsyncTaskExecutor> + Send + Sync>;
+
+/// 动作注册表
+///
+/// 按动作类型查找工厂，默认内置 shell、program 和 http 三种动作
+#[derive(Clone)]
+pub struct ActionRegistry {
+    factories: HashMap<String, ActionFactory>,
+}
+
+impl ActionRegistry {
+    /// 创建包含内置动作的注册表
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        let command: ActionFactory =
+            Arc::new(|action| Ok(Arc::new(CommandActionExecutor::new(action.clone())?) as AsyncTaskExecutor));
+        registry.register("shell", command.clone());
+        registry.register("program", command);
+        registry.register(
+            "http",
+            Arc::new(|action| Ok(Arc::new(HttpActionExecutor::new(action.clone())?) as AsyncTaskExecutor)),
+        );
+        registry
+    }
+
+    /// 创建包含内置动作的注册表，shell 动作使用指定的 shell
+    pub fn with_shell(shell: impl Into<String>) -> Self {
+        let shell = shell.into();
+        let mut registry = Self::new();
+        let command: ActionFactory = Arc::new(move |action| {
+            let executor = CommandActionExecutor::new(action.clone())?.with_shell(shell.clone());
+            Ok(Arc::new(executor) as AsyncTaskExecutor)
+        });
+        registry.register("shell", command.clone());
+        registry.register("program", command);
+        registry
+    }
+
+    /// 创建空注册表
+    pub fn empty() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// 注册或替换某种动作的工厂
+    pub fn register(&mut self, kind: impl Into<String>, factory: ActionFactory) {
+        self.factories.insert(kind.into(), factory);
+    }
+
+    /// 把动作还原为执行器
+    pub fn resolve(&self, action: &TaskAction) -> Result<AsyncTaskExecutor> {
+        let factory = self.factories.get(action.kind()).ok_or_else(|| {
+            SchedulerError::InvalidParameter(format!("Unsupported task action: {}", action.kind()))
+        })?;
+        factory(action)
+    }
+}
+
+impl Default for ActionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 执行 shell / program 动作
+pub struct CommandActionExecutor {
+    action: TaskAction,
+    executor: LocalCommandExecutor,
+}
+
+impl CommandActionExecutor {
+    pub fn new(action: TaskAction) -> Result<Self> {
+        match action {
+            TaskAction::Shell { .. } | TaskAction::Program { .. } => Ok(Self {
+                action,
+                executor: LocalCommandExecutor::new(),
+            }),
+            other => Err(SchedulerError::InvalidParameter(format!(
+                "Not a command action: {}",
+                other.kind()
+            ))),
+        }
+    }
+
+    /// 设置 shell 动作使用的 shell
+    pub fn with_shell(mut self, shell: impl Into<String>) -> Self {
+        self.executor = self.executor.with_shell(shell);
+        self
+    }
+
+    /// 构建命令，用户参数作为 `SKER_PARAM_*` 环境变量传入
+    fn build_command(&self, user_params: &HashMap<String, String>) -> Command {
+        let mut environment = ExecutionEnvironment::default();
+        for (key, value) in user_params {
+            environment
+                .env_vars
+                .insert(format!("{}{}", PARAM_ENV_PREFIX, key.to_uppercase()), value.clone());
+        }
+
+        let (program, args) = match &self.action {
+            TaskAction::Shell { command, env, timeout_secs } => {
+                environment.env_vars.extend(env.clone());
+                environment.use_shell = true;
+                environment.timeout_secs = *timeout_secs;
+                (command.clone(), Vec::new())
+            }
+            TaskAction::Program {
+                program,
+                args,
+                env,
+                working_dir,
+                timeout_secs,
+            } => {
+                environment.env_vars.extend(env.clone());
+                environment.working_dir = working_dir.clone();
+                environment.timeout_secs = *timeout_secs;
+                (program.clone(), args.clone())
+            }
+            TaskAction::Http { .. } => unreachable!("checked in CommandActionExecutor::new"),
+        };
+
+        Command {
+            id: Uuid::new_v4(),
+            program,
+            args,
+            environment,
+            status: ExecutionStatus::Pending,
+        }
+    }
+}
+
+#[async_trait]
+impl TaskExecutor for CommandActionExecutor {
+    async fn execute(
+        &self,
+        task_id: Uuid,
+        user_params: HashMap<String, String>,
+        ctx: TaskRunContext,
+    ) -> Result<TaskExecutionResult> {
+        let started_at = chrono::Utc::now();
+        let mut handle = self
+            .executor
+            .execute_streaming(self.build_command(&user_params))
+            .await
+            .map_err(|e| SchedulerError::ExecutionError(e.to_string()))?;
+        if let Some(pid) = handle.pid() {
+            ctx.process_started(pid);
+        }
+
+        let mut output = None;
+        let mut stopping = false;
+        loop {
+            tokio::select! {
+                event = handle.next_event() => match event {
+                    Some(CommandEvent::Stdout(line)) => ctx.stdout(line),
+                    Some(CommandEvent::Stderr(line)) => ctx.stderr(line),
+                    Some(CommandEvent::Exited(r)) => output = Some(r),
+                    None => break,
+                },
+                // 任务被停止时终止整个进程组
+                _ = ctx.cancelled(), if !stopping => {
+                    stopping = true;
+                    let _ = self.executor.cancel(handle.id()).await;
+                }
+            }
+        }
+        let output = output
+            .ok_or_else(|| SchedulerError::ExecutionError("Command ended without exit status".to_string()))?;
+
+        Ok(TaskExecutionResult {
+            task_id,
+            run_instance_id: Some(ctx.run_instance_id()),
+            started_at,
+            completed_at: Some(chrono::Utc::now()),
+            success: output.success,
+            error: match output.signal {
+                _ if output.success => None,
+                Some(signal) => Some(format!("Terminated by signal {}", signal)),
+                None => Some(output.stderr.clone()),
+            },
+            stdout: Some(output.stdout),
+            stderr: Some(output.stderr),
+            exit_code: Some(output.exit_code),
+            signal: output.signal,
+            timed_out: output.timed_out,
+        })
+    }
+}
+
+/// 执行 HTTP 动作
+///
+/// 2xx 响应视为成功，响应体逐行写入输出
+pub struct HttpActionExecutor {
+    method: reqwest::Method,
+    url: String,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    client: reqwest::Client,
+}
+
+impl HttpActionExecutor {
+    pub fn new(action: TaskAction) -> Result<Self> {
+        let TaskAction::Http {
+            method,
+            url,
+            headers,
+            body,
+            timeout_secs,
+        } = action
+        else {
+            return Err(SchedulerError::InvalidParameter(format!(
+                "Not an http action: {}",
+                action.kind()
+            )));
+        };
+
+        let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+            .map_err(|_| SchedulerError::InvalidParameter(format!("Invalid HTTP method: {}", method)))?;
+        let mut client = reqwest::Client::builder();
+        if let Some(secs) = timeout_secs {
+            client = client.timeout(Duration::from_secs(secs));
+        }
+        let client = client
+            .build()
+            .map_err(|e| SchedulerError::ExecutionError(e.to_string()))?;
+
+        Ok(Self {
+            method,
+            url,
+            headers,
+            body,
+            client,
+        })
+    }
+
+    async fn send(&self, user_params: &HashMap<String, String>) -> reqwest::Result<(reqwest::StatusCode, String)> {
+        let mut request = self.client.request(self.method.clone(), &self.url);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        if !user_params.is_empty() {
+            request = request.query(user_params);
+        }
+        if let Some(body) = &self.body {
+            request = request.body(body.clone());
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        Ok((status, response.text().await?))
+    }
+}
+
+#[async_trait]
+impl TaskExecutor for HttpActionExecutor {
+    async fn execute(
+        &self,
+        task_id: Uuid,
+        user_params: HashMap<String, String>,
+        ctx: TaskRunContext,
+    ) -> Result<TaskExecutionResult> {
+        let started_at = chrono::Utc::now();
+        let response = tokio::select! {
+            response = self.send(&user_params) => response,
+            _ = ctx.cancelled() => {
+                return Ok(TaskExecutionResult::failure(task_id, "Request cancelled".to_string()));
+            }
+        };
+
+        let mut result = match response {
+            Ok((status, body)) => {
+                ctx.stdout(format!("HTTP {}", status));
+                for line in body.lines() {
+                    ctx.stdout(line);
+                }
+                TaskExecutionResult {
+                    task_id,
+                    run_instance_id: None,
+                    started_at,
+                    completed_at: Some(chrono::Utc::now()),
+                    success: status.is_success(),
+                    error: (!status.is_success()).then(|| format!("HTTP {}", status)),
+                    stdout: Some(body),
+                    stderr: None,
+                    exit_code: None,
+                    signal: None,
+                    timed_out: false,
+                }
+            }
+            Err(e) => {
+                ctx.stderr(e.to_string());
+                let mut result = TaskExecutionResult::failure(task_id, e.to_string());
+                result.timed_out = e.is_timeout();
+                result
+            }
+        };
+        result.run_instance_id = Some(ctx.run_instance_id());
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_action_serde_roundtrip() {
+        let action = TaskAction::Program {
+            program: "echo".to_string(),
+            args: vec!["hi".to_string()],
+            env: HashMap::from([("A".to_string(), "1".to_string())]),
+            working_dir: Some(PathBuf::from("/tmp")),
+            timeout_secs: Some(5),
+        };
+        let json = serde_json::to_string(&action).unwrap();
+        assert!(json.contains("\"type\":\"program\""));
+        let parsed: TaskAction = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, action);
+
+        let http: TaskAction = serde_json::from_str(r#"{"type":"http","url":"http://x"}"#).unwrap();
+        assert_eq!(http.kind(), "http");
+        assert_eq!(http.summary(), "GET http://x");
+    }
+
+    #[tokio::test]
+    async fn test_shell_action_streams_output_and_params() {
+        let executor = ActionRegistry::new()
+            .resolve(&TaskAction::shell("echo hello $SKER_PARAM_USER"))
+            .unwrap();
+        let (ctx, mut control) = TaskRunContext::new(Uuid::new_v4());
+        let params = HashMap::from([("user".to_string(), "bob".to_string())]);
+
+        let result = executor.execute(Uuid::new_v4(), params, ctx).await.unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.exit_code, Some(0));
+        let mut lines = Vec::new();
+        while let Ok(event) = control.events.try_recv() {
+            if let crate::execution::TaskRunEvent::Stdout(line) = event {
+                lines.push(line);
+            }
+        }
+        assert_eq!(lines, vec!["hello bob"]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_registry_with_shell() {
+        let executor = ActionRegistry::with_shell("bash")
+            .resolve(&TaskAction::shell("echo ${BASH_VERSION:+bash}"))
+            .unwrap();
+        let (ctx, _control) = TaskRunContext::new(Uuid::new_v4());
+        let result = executor.execute(Uuid::new_v4(), HashMap::new(), ctx).await.unwrap();
+        assert_eq!(result.stdout.as_deref().map(str::trim), Some("bash"));
+    }
+
+    #[tokio::test]
+    async fn test_program_action_env_and_working_dir() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let action = TaskAction::Program {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), "echo $GREETING; pwd".to_string()],
+            env: HashMap::from([("GREETING".to_string(), "hi".to_string())]),
+            working_dir: Some(dir.path().to_path_buf()),
+            timeout_secs: None,
+        };
+        let executor = ActionRegistry::new().resolve(&action).unwrap();
+        let result = executor
+            .execute(Uuid::new_v4(), HashMap::new(), TaskRunContext::detached(Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        let stdout = result.stdout.unwrap();
+        let mut lines = stdout.lines();
+        assert_eq!(lines.next(), Some("hi"));
+        assert!(lines.next().unwrap().ends_with(dir.path().file_name().unwrap().to_str().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_http_action_against_local_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .await
+                .unwrap();
+            request
+        });
+
+        let action = TaskAction::Http {
+            method: "post".to_string(),
+            url: format!("http://{}/hook", addr),
+            headers: HashMap::from([("X-Token".to_string(), "abc".to_string())]),
+            body: Some("payload".to_string()),
+            timeout_secs: Some(5),
+        };
+        let executor = ActionRegistry::new().resolve(&action).unwrap();
+        let params = HashMap::from([("user".to_string(), "bob".to_string())]);
+        let result = executor
+            .execute(Uuid::new_v4(), params, TaskRunContext::detached(Uuid::new_v4()))
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.stdout.as_deref(), Some("ok"));
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook?user=bob HTTP/1.1"));
+        assert!(request.to_lowercase().contains("x-token: abc"));
+        assert!(request.ends_with("payload"));
+    }
+
+    #[test]
+    fn test_registry_unknown_kind() {
+        let registry = ActionRegistry::empty();
+        assert!(matches!(
+            registry.resolve(&TaskAction::shell("true")),
+            Err(SchedulerError::InvalidParameter(_))
+        ));
+    }
+}
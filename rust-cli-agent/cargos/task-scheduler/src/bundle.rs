@@ -0,0 +1,663 @@
+//! 任务迁移包
+//!
+//! 在机器之间迁移任务及其运行历史。迁移包为 JSON Lines 格式，第一行是包头，
+//! 之后每行一条任务、运行实例、日志或已清理运行汇总记录。导出和导入都通过
+//! [`SchedulerStorage`] 完成，与具体存储后端无关
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+use crate::retention::RunHistorySummary;
+use crate::storage::SchedulerStorage;
+use crate::types::{ScheduledTask, TaskLog, TaskRunInstance, TaskStatus};
+
+/// 迁移包格式标识
+pub const BUNDLE_FORMAT: &str = "sker-schedule-bundle";
+
+/// 当前迁移包版本，读取时拒绝更新的版本
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// 迁移包头
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleHeader {
+    /// 格式标识，固定为 [`BUNDLE_FORMAT`]
+    pub format: String,
+    /// 格式版本
+    pub version: u32,
+    /// 导出时间
+    pub exported_at: DateTime<Utc>,
+}
+
+impl Default for BundleHeader {
+    fn default() -> Self {
+        Self {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            exported_at: Utc::now(),
+        }
+    }
+}
+
+/// 迁移包中的一行记录
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+enum BundleRecord {
+    Header(BundleHeader),
+    Task(ScheduledTask),
+    RunInstance(TaskRunInstance),
+    Log(TaskLog),
+    RunSummary(RunHistorySummary),
+}
+
+/// 任务迁移包
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleBundle {
+    pub header: BundleHeader,
+    pub tasks: Vec<ScheduledTask>,
+    #[serde(default)]
+    pub run_instances: Vec<TaskRunInstance>,
+    #[serde(default)]
+    pub logs: Vec<TaskLog>,
+    /// 已清理运行的汇总，保证导入后任务简报的统计完整
+    #[serde(default)]
+    pub run_summaries: Vec<RunHistorySummary>,
+}
+
+/// 导出选项
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExportOptions {
+    /// 只导出这些任务，为空时导出全部任务
+    #[serde(default)]
+    pub task_ids: Vec<Uuid>,
+    /// 同时导出运行实例、日志和已清理运行汇总
+    #[serde(default)]
+    pub include_history: bool,
+}
+
+impl ExportOptions {
+    /// 只导出指定任务
+    pub fn with_tasks(mut self, task_ids: Vec<Uuid>) -> Self {
+        self.task_ids = task_ids;
+        self
+    }
+
+    /// 导出运行历史
+    pub fn with_history(mut self) -> Self {
+        self.include_history = true;
+        self
+    }
+}
+
+/// 导入任务与现有任务冲突 (ID 或名称相同) 时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// 保留现有任务，不导入
+    #[default]
+    Skip,
+    /// 用导入的定义覆盖现有任务，保留现有任务的 ID
+    Overwrite,
+    /// 作为新任务导入，名称加数字后缀，ID 冲突时生成新 ID
+    Rename,
+}
+
+impl fmt::Display for ConflictStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictStrategy::Skip => write!(f, "skip"),
+            ConflictStrategy::Overwrite => write!(f, "overwrite"),
+            ConflictStrategy::Rename => write!(f, "rename"),
+        }
+    }
+}
+
+impl FromStr for ConflictStrategy {
+    type Err = SchedulerError;
+
+    /// 解析 `skip`、`overwrite`、`rename`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "skip" => Ok(ConflictStrategy::Skip),
+            "overwrite" => Ok(ConflictStrategy::Overwrite),
+            "rename" => Ok(ConflictStrategy::Rename),
+            _ => Err(SchedulerError::InvalidParameter(format!(
+                "Invalid conflict strategy: {} (expected skip, overwrite or rename)",
+                s
+            ))),
+        }
+    }
+}
+
+/// 导入选项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportOptions {
+    /// 冲突处理方式
+    #[serde(default)]
+    pub conflict: ConflictStrategy,
+    /// 为导入的任务、运行实例和日志生成新 ID，任务之间的依赖随之改写
+    #[serde(default)]
+    pub remap_ids: bool,
+    /// 导入迁移包中的运行历史
+    #[serde(default = "default_true")]
+    pub include_history: bool,
+    /// 保留系统级任务标记，由调用方重新创建系统任务；否则作为内置任务导入
+    #[serde(default)]
+    pub recreate_system: bool,
+    /// 只生成导入计划，不写入存储
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            conflict: ConflictStrategy::default(),
+            remap_ids: false,
+            include_history: true,
+            recreate_system: false,
+            dry_run: false,
+        }
+    }
+}
+
+/// 单个任务的导入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    /// 新建任务
+    Create,
+    /// 覆盖现有任务
+    Overwrite,
+    /// 改名后新建任务
+    Rename,
+    /// 与现有任务冲突，未导入
+    Skip,
+}
+
+impl fmt::Display for ImportAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportAction::Create => write!(f, "create"),
+            ImportAction::Overwrite => write!(f, "overwrite"),
+            ImportAction::Rename => write!(f, "rename"),
+            ImportAction::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+/// 单个任务的导入结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportedTask {
+    /// 迁移包中的任务 ID
+    pub source_id: Uuid,
+    /// 导入后的任务 ID，跳过时为冲突的现有任务 ID
+    pub target_id: Uuid,
+    /// 导入后的任务名称
+    pub name: String,
+    pub action: ImportAction,
+    /// 是否作为系统级任务导入，需要调用方重新创建系统任务
+    pub is_system: bool,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub tasks: Vec<ImportedTask>,
+    /// 导入的运行实例数
+    pub run_instances: usize,
+    /// 导入的日志数
+    pub logs: usize,
+    /// 是否为演练，演练不写入存储
+    pub dry_run: bool,
+}
+
+impl ImportReport {
+    /// 实际导入 (未跳过) 的任务
+    pub fn imported(&self) -> impl Iterator<Item = &ImportedTask> {
+        self.tasks.iter().filter(|t| t.action != ImportAction::Skip)
+    }
+}
+
+fn storage_error(e: impl fmt::Display) -> SchedulerError {
+    SchedulerError::StorageError(e.to_string())
+}
+
+impl ScheduleBundle {
+    /// 从存储导出任务，按任务创建时间排列
+    pub async fn export(storage: &dyn SchedulerStorage, options: &ExportOptions) -> Result<Self> {
+        let mut tasks = storage.list_tasks().await.map_err(storage_error)?;
+        if !options.task_ids.is_empty() {
+            let wanted: HashSet<Uuid> = options.task_ids.iter().copied().collect();
+            tasks.retain(|t| wanted.contains(&t.id));
+            if let Some(missing) = options.task_ids.iter().find(|id| !tasks.iter().any(|t| t.id == **id)) {
+                return Err(SchedulerError::JobNotFound(*missing));
+            }
+        }
+        tasks.sort_by_key(|t| t.created_at);
+
+        let mut bundle = Self {
+            tasks,
+            ..Default::default()
+        };
+        if options.include_history {
+            for task in &bundle.tasks {
+                let instances = storage.list_run_instances(task.id).await.map_err(storage_error)?;
+                for instance in &instances {
+                    bundle
+                        .logs
+                        .extend(storage.list_logs(instance.id).await.map_err(storage_error)?);
+                }
+                bundle.run_instances.extend(instances);
+                if let Some(summary) = storage.load_run_summary(task.id).await.map_err(storage_error)? {
+                    bundle.run_summaries.push(summary);
+                }
+            }
+        }
+        Ok(bundle)
+    }
+
+    /// 以 JSON Lines 格式写出
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        let records = std::iter::once(BundleRecord::Header(self.header.clone()))
+            .chain(self.tasks.iter().cloned().map(BundleRecord::Task))
+            .chain(self.run_instances.iter().cloned().map(BundleRecord::RunInstance))
+            .chain(self.logs.iter().cloned().map(BundleRecord::Log))
+            .chain(self.run_summaries.iter().cloned().map(BundleRecord::RunSummary));
+        for record in records {
+            serde_json::to_writer(&mut writer, &record).map_err(storage_error)?;
+            writer.write_all(b"\n").map_err(storage_error)?;
+        }
+        writer.flush().map_err(storage_error)
+    }
+
+    /// 读取 JSON Lines 格式的迁移包，校验格式标识和版本
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Self> {
+        let mut header = None;
+        let mut bundle = Self::default();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(storage_error)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: BundleRecord = serde_json::from_str(&line).map_err(|e| {
+                SchedulerError::InvalidParameter(format!("Invalid bundle record at line {}: {}", index + 1, e))
+            })?;
+            match (record, &header) {
+                (BundleRecord::Header(h), None) => {
+                    if h.format != BUNDLE_FORMAT {
+                        return Err(SchedulerError::InvalidParameter(format!(
+                            "Not a schedule bundle: unexpected format {}",
+                            h.format
+                        )));
+                    }
+                    if h.version > BUNDLE_VERSION {
+                        return Err(SchedulerError::InvalidParameter(format!(
+                            "Unsupported bundle version {} (supported up to {})",
+                            h.version, BUNDLE_VERSION
+                        )));
+                    }
+                    header = Some(h);
+                }
+                (BundleRecord::Header(_), Some(_)) => {
+                    return Err(SchedulerError::InvalidParameter(format!(
+                        "Duplicate bundle header at line {}",
+                        index + 1
+                    )));
+                }
+                (_, None) => {
+                    return Err(SchedulerError::InvalidParameter(
+                        "Bundle must start with a header record".to_string(),
+                    ));
+                }
+                (BundleRecord::Task(task), Some(_)) => bundle.tasks.push(task),
+                (BundleRecord::RunInstance(instance), Some(_)) => bundle.run_instances.push(instance),
+                (BundleRecord::Log(log), Some(_)) => bundle.logs.push(log),
+                (BundleRecord::RunSummary(summary), Some(_)) => bundle.run_summaries.push(summary),
+            }
+        }
+        bundle.header = header.ok_or_else(|| SchedulerError::InvalidParameter("Empty bundle".to_string()))?;
+        Ok(bundle)
+    }
+
+    /// 按选项生成导入计划，源任务 ID 到目标任务的映射
+    fn plan(&self, existing: &[ScheduledTask], options: &ImportOptions) -> Vec<(ScheduledTask, ImportedTask)> {
+        let by_id: HashMap<Uuid, &ScheduledTask> = existing.iter().map(|t| (t.id, t)).collect();
+        let by_name: HashMap<&str, &ScheduledTask> = existing.iter().map(|t| (t.name.as_str(), t)).collect();
+        let mut names: HashSet<String> = existing.iter().map(|t| t.name.clone()).collect();
+        let mut planned = Vec::new();
+
+        for task in &self.tasks {
+            let conflict = by_id.get(&task.id).or_else(|| by_name.get(task.name.as_str())).copied();
+            let fresh_id = if options.remap_ids { Uuid::new_v4() } else { task.id };
+            let (action, target_id, name) = match (conflict, options.conflict) {
+                (None, _) => (ImportAction::Create, fresh_id, task.name.clone()),
+                (Some(existing), ConflictStrategy::Skip) => (ImportAction::Skip, existing.id, existing.name.clone()),
+                (Some(existing), ConflictStrategy::Overwrite) => {
+                    (ImportAction::Overwrite, existing.id, task.name.clone())
+                }
+                (Some(_), ConflictStrategy::Rename) => {
+                    let id = if by_id.contains_key(&fresh_id) { Uuid::new_v4() } else { fresh_id };
+                    (ImportAction::Rename, id, unique_name(&task.name, &names))
+                }
+            };
+            names.insert(name.clone());
+            let is_system = task.is_system && options.recreate_system && action != ImportAction::Skip;
+            planned.push((
+                task.clone(),
+                ImportedTask {
+                    source_id: task.id,
+                    target_id,
+                    name,
+                    action,
+                    is_system,
+                },
+            ));
+        }
+        planned
+    }
+
+    /// 通过存储导入任务和运行历史
+    ///
+    /// 跳过的任务不导入运行历史，但指向它们的依赖改写为冲突的现有任务。
+    /// 导入的任务不会处于运行中状态
+    pub async fn import(&self, storage: &dyn SchedulerStorage, options: &ImportOptions) -> Result<ImportReport> {
+        let existing = storage.list_tasks().await.map_err(storage_error)?;
+        let planned = self.plan(&existing, options);
+        let task_ids: HashMap<Uuid, Uuid> = planned.iter().map(|(_, p)| (p.source_id, p.target_id)).collect();
+        let imported: HashSet<Uuid> = planned
+            .iter()
+            .filter(|(_, p)| p.action != ImportAction::Skip)
+            .map(|(_, p)| p.source_id)
+            .collect();
+
+        let mut report = ImportReport {
+            dry_run: options.dry_run,
+            ..Default::default()
+        };
+
+        // 运行实例随任务导入，ID 冲突时 (覆盖或改名后的任务) 生成新 ID
+        let mut instance_ids: HashMap<Uuid, Uuid> = HashMap::new();
+        let mut instances = Vec::new();
+        if options.include_history {
+            for instance in self.run_instances.iter().filter(|i| imported.contains(&i.task_id)) {
+                let renamed = task_ids[&instance.task_id] != instance.task_id;
+                let id = if options.remap_ids || renamed { Uuid::new_v4() } else { instance.id };
+                instance_ids.insert(instance.id, id);
+                instances.push(instance);
+            }
+        }
+
+        for (task, plan) in &planned {
+            if plan.action == ImportAction::Skip {
+                report.tasks.push(plan.clone());
+                continue;
+            }
+            let mut task = task.clone();
+            task.id = plan.target_id;
+            task.name = plan.name.clone();
+            task.is_system = plan.is_system;
+            if task.status == TaskStatus::Running {
+                task.status = TaskStatus::Pending;
+            }
+            for dep in &mut task.depends_on {
+                if let Some(target) = task_ids.get(&dep.task_id) {
+                    dep.task_id = *target;
+                }
+            }
+            if !options.dry_run {
+                storage.save_task(&task).await.map_err(storage_error)?;
+            }
+            report.tasks.push(plan.clone());
+        }
+
+        for instance in instances {
+            let mut instance = instance.clone();
+            instance.id = instance_ids[&instance.id];
+            instance.task_id = task_ids[&instance.task_id];
+            instance.logical_run_id = instance
+                .logical_run_id
+                .map(|id| instance_ids.get(&id).copied().unwrap_or(id));
+            // 原机器上的进程不存在，未结束的运行记为错误
+            instance.pid = None;
+            if matches!(instance.status, TaskStatus::Running | TaskStatus::Pending) {
+                instance.status = TaskStatus::Error;
+            }
+            if !options.dry_run {
+                storage.save_run_instance(&instance).await.map_err(storage_error)?;
+            }
+            report.run_instances += 1;
+        }
+
+        for log in self.logs.iter().filter(|l| instance_ids.contains_key(&l.run_instance_id)) {
+            if !options.dry_run {
+                let mut log = log.clone();
+                let instance_id = instance_ids[&log.run_instance_id];
+                if instance_id != log.run_instance_id {
+                    log.id = Uuid::new_v4();
+                    log.run_instance_id = instance_id;
+                }
+                storage.save_log(&log).await.map_err(storage_error)?;
+            }
+            report.logs += 1;
+        }
+
+        if options.include_history && !options.dry_run {
+            for summary in self.run_summaries.iter().filter(|s| imported.contains(&s.task_id)) {
+                let task_id = task_ids[&summary.task_id];
+                // 覆盖的任务已有汇总时保留现有汇总
+                if storage.load_run_summary(task_id).await.map_err(storage_error)?.is_none() {
+                    let summary = RunHistorySummary {
+                        task_id,
+                        ..summary.clone()
+                    };
+                    storage.save_run_summary(&summary).await.map_err(storage_error)?;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// 在已有名称中找不冲突的名称，依次尝试 `name_2`、`name_3`…
+fn unique_name(name: &str, names: &HashSet<String>) -> String {
+    (2..)
+        .map(|n| format!("{}_{}", name, n))
+        .find(|candidate| !names.contains(candidate))
+        .expect("unbounded suffixes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemorySchedulerStorage;
+    use crate::workflow::TaskDependency;
+    use std::collections::HashMap;
+
+    fn task(name: &str) -> ScheduledTask {
+        ScheduledTask::new(
+            Uuid::new_v4(),
+            name.to_string(),
+            name.to_string(),
+            "0 0 * * * *".to_string(),
+            None,
+            None,
+        )
+    }
+
+    async fn storage_with_history() -> (MemorySchedulerStorage, ScheduledTask, ScheduledTask) {
+        let storage = MemorySchedulerStorage::new();
+        let backup = task("backup");
+        let mut report = task("report");
+        report.depends_on = vec![TaskDependency {
+            task_id: backup.id,
+            within_secs: 3600,
+        }];
+        storage.save_task(&backup).await.unwrap();
+        storage.save_task(&report).await.unwrap();
+
+        let mut instance = TaskRunInstance::new(backup.id, HashMap::new());
+        instance.status = TaskStatus::Completed;
+        storage.save_run_instance(&instance).await.unwrap();
+        storage
+            .save_log(&TaskLog::info(instance.id, "done".to_string()))
+            .await
+            .unwrap();
+        (storage, backup, report)
+    }
+
+    #[tokio::test]
+    async fn test_bundle_round_trip() {
+        let (storage, backup, _) = storage_with_history().await;
+        let bundle = ScheduleBundle::export(&storage, &ExportOptions::default().with_history())
+            .await
+            .unwrap();
+        assert_eq!((bundle.tasks.len(), bundle.run_instances.len(), bundle.logs.len()), (2, 1, 1));
+
+        let mut buf = Vec::new();
+        bundle.write_to(&mut buf).unwrap();
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert_eq!(text.lines().count(), 5);
+        assert!(text.lines().next().unwrap().contains(BUNDLE_FORMAT));
+
+        let read = ScheduleBundle::read_from(buf.as_slice()).unwrap();
+        assert_eq!(read.header.version, BUNDLE_VERSION);
+        assert_eq!(read.tasks.iter().map(|t| t.id).collect::<Vec<_>>(), bundle.tasks.iter().map(|t| t.id).collect::<Vec<_>>());
+        assert_eq!(read.run_instances[0].task_id, backup.id);
+
+        let only = ScheduleBundle::export(&storage, &ExportOptions::default().with_tasks(vec![backup.id]))
+            .await
+            .unwrap();
+        assert_eq!(only.tasks.len(), 1);
+        assert!(only.run_instances.is_empty());
+    }
+
+    #[test]
+    fn test_read_rejects_invalid_bundles() {
+        assert!(ScheduleBundle::read_from("".as_bytes()).is_err());
+        let task_first = format!(
+            "{}\n",
+            serde_json::to_string(&BundleRecord::Task(task("a"))).unwrap()
+        );
+        assert!(ScheduleBundle::read_from(task_first.as_bytes()).is_err());
+
+        let newer = BundleHeader {
+            version: BUNDLE_VERSION + 1,
+            ..Default::default()
+        };
+        let newer = serde_json::to_string(&BundleRecord::Header(newer)).unwrap();
+        let err = ScheduleBundle::read_from(newer.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("Unsupported bundle version"));
+
+        let garbage = format!("{}\nnot json\n", serde_json::to_string(&BundleRecord::Header(BundleHeader::default())).unwrap());
+        let err = ScheduleBundle::read_from(garbage.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[tokio::test]
+    async fn test_import_remaps_ids_and_dependencies() {
+        let (source, backup, report) = storage_with_history().await;
+        let bundle = ScheduleBundle::export(&source, &ExportOptions::default().with_history())
+            .await
+            .unwrap();
+
+        let target = MemorySchedulerStorage::new();
+        let options = ImportOptions {
+            remap_ids: true,
+            ..Default::default()
+        };
+        let result = bundle.import(&target, &options).await.unwrap();
+        assert_eq!((result.run_instances, result.logs), (1, 1));
+
+        let ids: HashMap<Uuid, Uuid> = result.tasks.iter().map(|t| (t.source_id, t.target_id)).collect();
+        assert_ne!(ids[&backup.id], backup.id);
+        let imported_report = target.load_task(ids[&report.id]).await.unwrap().unwrap();
+        assert_eq!(imported_report.depends_on[0].task_id, ids[&backup.id]);
+
+        let instances = target.list_run_instances(ids[&backup.id]).await.unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(target.list_logs(instances[0].id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_conflicts() {
+        let (storage, backup, _) = storage_with_history().await;
+        let mut bundle = ScheduleBundle::export(&storage, &ExportOptions::default().with_history())
+            .await
+            .unwrap();
+        bundle.tasks[0].title = "Backup v2".to_string();
+
+        // 跳过：不写入任何内容
+        let skipped = bundle.import(&storage, &ImportOptions::default()).await.unwrap();
+        assert!(skipped.tasks.iter().all(|t| t.action == ImportAction::Skip));
+        assert_eq!(skipped.run_instances, 0);
+        assert_eq!(storage.load_task(backup.id).await.unwrap().unwrap().title, "backup");
+
+        // 演练：只生成计划
+        let options = ImportOptions {
+            conflict: ConflictStrategy::Overwrite,
+            dry_run: true,
+            ..Default::default()
+        };
+        let planned = bundle.import(&storage, &options).await.unwrap();
+        assert_eq!(planned.tasks[0].action, ImportAction::Overwrite);
+        assert_eq!(storage.load_task(backup.id).await.unwrap().unwrap().title, "backup");
+
+        // 覆盖：保留现有 ID
+        let options = ImportOptions {
+            conflict: ConflictStrategy::Overwrite,
+            ..Default::default()
+        };
+        bundle.import(&storage, &options).await.unwrap();
+        assert_eq!(storage.load_task(backup.id).await.unwrap().unwrap().title, "Backup v2");
+        assert_eq!(storage.list_tasks().await.unwrap().len(), 2);
+
+        // 改名：生成新 ID 和名称
+        let options = ImportOptions {
+            conflict: ConflictStrategy::Rename,
+            ..Default::default()
+        };
+        let renamed = bundle.import(&storage, &options).await.unwrap();
+        let plan = &renamed.tasks[0];
+        assert_eq!(plan.action, ImportAction::Rename);
+        assert_ne!(plan.target_id, backup.id);
+        assert_eq!(plan.name, "backup_2");
+        assert_eq!(storage.list_tasks().await.unwrap().len(), 4);
+        assert_eq!(storage.list_run_instances(plan.target_id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_system_tasks() {
+        let storage = MemorySchedulerStorage::new();
+        let mut system = task("nightly");
+        system.is_system = true;
+        let bundle = ScheduleBundle {
+            tasks: vec![system.clone()],
+            ..Default::default()
+        };
+
+        let target = MemorySchedulerStorage::new();
+        let report = bundle.import(&target, &ImportOptions::default()).await.unwrap();
+        assert!(!report.tasks[0].is_system);
+        assert!(!target.load_task(system.id).await.unwrap().unwrap().is_system);
+
+        let options = ImportOptions {
+            recreate_system: true,
+            ..Default::default()
+        };
+        let report = bundle.import(&storage, &options).await.unwrap();
+        assert!(report.tasks[0].is_system);
+        assert!(storage.load_task(system.id).await.unwrap().unwrap().is_system);
+    }
+}
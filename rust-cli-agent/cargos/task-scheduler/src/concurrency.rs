@@ -0,0 +1,409 @@
+//! 并发控制
+//!
+//! 每个任务有自己的重叠策略，决定上一次运行尚未结束时新的运行如何处理；
+//! 全局和按标签的并发上限限制同时运行的任务数，cron 触发、手动运行和工作流步骤都受其约束
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+
+/// 上一次运行尚未结束时新运行的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// 允许多个运行同时进行
+    #[default]
+    Allow,
+    /// 跳过新的运行，记为 Skipped 运行实例
+    Skip,
+    /// 新的运行排队，等上一次运行结束后开始
+    Queue,
+    /// 停止正在进行的运行，再开始新的运行
+    Replace,
+}
+
+impl std::fmt::Display for OverlapPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OverlapPolicy::Allow => write!(f, "allow"),
+            OverlapPolicy::Skip => write!(f, "skip"),
+            OverlapPolicy::Queue => write!(f, "queue"),
+            OverlapPolicy::Replace => write!(f, "replace"),
+        }
+    }
+}
+
+impl FromStr for OverlapPolicy {
+    type Err = SchedulerError;
+
+    /// 解析 `allow`、`skip`、`queue`、`replace`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "allow" => Ok(OverlapPolicy::Allow),
+            "skip" => Ok(OverlapPolicy::Skip),
+            "queue" => Ok(OverlapPolicy::Queue),
+            "replace" => Ok(OverlapPolicy::Replace),
+            _ => Err(SchedulerError::InvalidParameter(format!(
+                "Invalid overlap policy: {} (expected allow, skip, queue or replace)",
+                s
+            ))),
+        }
+    }
+}
+
+/// 并发上限，0 表示不限制
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConcurrencyLimits {
+    /// 同时运行的任务总数上限
+    #[serde(default)]
+    pub max_concurrent: usize,
+    /// 带某个标签的任务同时运行的上限
+    #[serde(default)]
+    pub per_tag: HashMap<String, usize>,
+}
+
+impl ConcurrencyLimits {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent,
+            per_tag: HashMap::new(),
+        }
+    }
+
+    /// 设置某个标签的上限
+    pub fn with_tag_limit(mut self, tag: impl Into<String>, limit: usize) -> Self {
+        self.per_tag.insert(tag.into(), limit);
+        self
+    }
+}
+
+/// 一次运行占用的并发名额，释放时归还
+pub(crate) struct RunSlots {
+    _permits: Vec<LimitPermit>,
+}
+
+/// 可调整的并发上限
+///
+/// 调整时沿用原信号量，正在运行的任务持有的名额继续计数；
+/// 缩小时被占用、无法立即收回的名额记为欠额，在运行结束归还时销毁
+struct Limit {
+    semaphore: Arc<Semaphore>,
+    state: Mutex<LimitState>,
+}
+
+struct LimitState {
+    size: usize,
+    /// 尚待收回的名额
+    debt: usize,
+}
+
+impl Limit {
+    fn new(size: usize) -> Arc<Self> {
+        Arc::new(Self {
+            semaphore: Arc::new(Semaphore::new(size)),
+            state: Mutex::new(LimitState { size, debt: 0 }),
+        })
+    }
+
+    fn resize(&self, size: usize) {
+        let mut state = self.state.lock().expect("limiter lock poisoned");
+        if size > state.size {
+            // 先抵消欠额，余下的才是新增的名额
+            let grow = size - state.size;
+            let repaid = grow.min(state.debt);
+            state.debt -= repaid;
+            self.semaphore.add_permits(grow - repaid);
+        } else {
+            let shrink = state.size - size;
+            let forgotten = self.semaphore.forget_permits(shrink);
+            state.debt += shrink - forgotten;
+        }
+        state.size = size;
+    }
+
+    /// 不再限制时关闭信号量，排队中的运行不再等待
+    fn close(&self) {
+        self.semaphore.close();
+    }
+
+    async fn acquire(self: Arc<Self>) -> Option<LimitPermit> {
+        let permit = self.semaphore.clone().acquire_owned().await.ok()?;
+        Some(LimitPermit {
+            permit: Some(permit),
+            limit: self,
+        })
+    }
+}
+
+/// 占用的一个名额，有欠额时归还即销毁
+struct LimitPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    limit: Arc<Limit>,
+}
+
+impl Drop for LimitPermit {
+    fn drop(&mut self) {
+        let Some(permit) = self.permit.take() else {
+            return;
+        };
+        let mut state = self.limit.state.lock().expect("limiter lock poisoned");
+        if state.debt > 0 {
+            state.debt -= 1;
+            permit.forget();
+        } else {
+            drop(permit);
+        }
+    }
+}
+
+/// 任务的重叠控制
+struct TaskGate {
+    /// 非 Allow 策略下同一任务同时只有一个运行持有
+    lock: Arc<Semaphore>,
+    /// 每次替换运行时递增，被替换的运行据此取消后续重试
+    generation: AtomicU64,
+}
+
+/// 任务独占运行的凭证
+pub(crate) struct TaskTurn {
+    _permit: OwnedSemaphorePermit,
+    gate: Arc<TaskGate>,
+    generation: u64,
+}
+
+impl TaskTurn {
+    /// 运行是否已被更新的运行替换
+    pub(crate) fn is_replaced(&self) -> bool {
+        self.gate.generation.load(Ordering::SeqCst) != self.generation
+    }
+}
+
+/// 全局、标签和任务级的并发控制
+pub(crate) struct ConcurrencyLimiter {
+    global: RwLock<Option<Arc<Limit>>>,
+    tags: RwLock<HashMap<String, Arc<Limit>>>,
+    gates: Mutex<HashMap<Uuid, Arc<TaskGate>>>,
+}
+
+impl ConcurrencyLimiter {
+    pub(crate) fn new(limits: &ConcurrencyLimits) -> Self {
+        let limiter = Self {
+            global: RwLock::new(None),
+            tags: RwLock::new(HashMap::new()),
+            gates: Mutex::new(HashMap::new()),
+        };
+        limiter.set_limits(limits);
+        limiter
+    }
+
+    /// 调整并发上限
+    ///
+    /// 已有的上限原地调整，正在运行的任务占用的名额继续计入新上限；
+    /// 取消的上限不再限制，排队中的运行直接放行
+    pub(crate) fn set_limits(&self, limits: &ConcurrencyLimits) {
+        {
+            let mut global = self.global.write().expect("limiter lock poisoned");
+            *global = resize(global.take(), limits.max_concurrent);
+        }
+        let mut tags = self.tags.write().expect("limiter lock poisoned");
+        let mut current = std::mem::take(&mut *tags);
+        for (tag, limit) in &limits.per_tag {
+            if let Some(limit) = resize(current.remove(tag), *limit) {
+                tags.insert(tag.clone(), limit);
+            }
+        }
+        for limit in current.into_values() {
+            limit.close();
+        }
+    }
+
+    /// 等待全局和各标签的名额
+    ///
+    /// 先取标签名额再取全局名额，等待标签名额的运行不占用全局名额
+    pub(crate) async fn acquire(&self, tags: &[String]) -> RunSlots {
+        let mut limits: Vec<(String, Arc<Limit>)> = {
+            let limits = self.tags.read().expect("limiter lock poisoned");
+            tags.iter()
+                .filter_map(|tag| limits.get(tag).map(|l| (tag.clone(), l.clone())))
+                .collect()
+        };
+        // 固定顺序获取，避免两个运行互相等待
+        limits.sort_by(|a, b| a.0.cmp(&b.0));
+        limits.dedup_by(|a, b| a.0 == b.0);
+        let global = self.global.read().expect("limiter lock poisoned").clone();
+
+        let mut permits = Vec::with_capacity(limits.len() + 1);
+        for limit in limits.into_iter().map(|(_, l)| l).chain(global) {
+            if let Some(permit) = limit.acquire().await {
+                permits.push(permit);
+            }
+        }
+        RunSlots { _permits: permits }
+    }
+
+    fn gate(&self, task_id: Uuid) -> Arc<TaskGate> {
+        let mut gates = self.gates.lock().expect("limiter lock poisoned");
+        gates
+            .entry(task_id)
+            .or_insert_with(|| {
+                Arc::new(TaskGate {
+                    lock: Arc::new(Semaphore::new(1)),
+                    generation: AtomicU64::new(0),
+                })
+            })
+            .clone()
+    }
+
+    /// 上一次运行未结束时立即返回 None
+    pub(crate) fn try_turn(&self, task_id: Uuid) -> Option<TaskTurn> {
+        let gate = self.gate(task_id);
+        let permit = gate.lock.clone().try_acquire_owned().ok()?;
+        let generation = gate.generation.load(Ordering::SeqCst);
+        Some(TaskTurn {
+            _permit: permit,
+            gate,
+            generation,
+        })
+    }
+
+    /// 将正在进行和排队的运行标记为已替换
+    pub(crate) fn supersede(&self, task_id: Uuid) {
+        self.gate(task_id).generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// 等待上一次运行结束
+    pub(crate) async fn turn(&self, task_id: Uuid) -> TaskTurn {
+        let gate = self.gate(task_id);
+        let permit = gate
+            .lock
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("task gate is never closed");
+        let generation = gate.generation.load(Ordering::SeqCst);
+        TaskTurn {
+            _permit: permit,
+            gate,
+            generation,
+        }
+    }
+
+    /// 删除任务时清理其重叠控制
+    pub(crate) fn forget(&self, task_id: Uuid) {
+        self.gates.lock().expect("limiter lock poisoned").remove(&task_id);
+    }
+}
+
+/// 把上限调整为 size，0 表示不再限制
+fn resize(limit: Option<Arc<Limit>>, size: usize) -> Option<Arc<Limit>> {
+    match (limit, size) {
+        (Some(limit), 0) => {
+            limit.close();
+            None
+        }
+        (Some(limit), size) => {
+            limit.resize(size);
+            Some(limit)
+        }
+        (None, 0) => None,
+        (None, size) => Some(Limit::new(size)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_overlap_policy_parse() {
+        assert_eq!("skip".parse::<OverlapPolicy>().unwrap(), OverlapPolicy::Skip);
+        assert_eq!(" Queue ".parse::<OverlapPolicy>().unwrap(), OverlapPolicy::Queue);
+        assert_eq!("replace".parse::<OverlapPolicy>().unwrap(), OverlapPolicy::Replace);
+        assert!("sometimes".parse::<OverlapPolicy>().is_err());
+        for policy in [OverlapPolicy::Allow, OverlapPolicy::Skip, OverlapPolicy::Queue, OverlapPolicy::Replace] {
+            assert_eq!(policy.to_string().parse::<OverlapPolicy>().unwrap(), policy);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tag_limit_blocks_until_released() {
+        let limiter = ConcurrencyLimiter::new(&ConcurrencyLimits::new(0).with_tag_limit("db", 1));
+        let tags = vec!["db".to_string()];
+
+        let first = limiter.acquire(&tags).await;
+        assert!(tokio::time::timeout(Duration::from_millis(50), limiter.acquire(&tags)).await.is_err());
+        // 其他标签不受影响
+        limiter.acquire(&["net".to_string()]).await;
+
+        drop(first);
+        assert!(tokio::time::timeout(Duration::from_millis(50), limiter.acquire(&tags)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_limits_hold_across_reload() {
+        let limiter = ConcurrencyLimiter::new(&ConcurrencyLimits::new(2).with_tag_limit("db", 1));
+        let tags = vec!["db".to_string()];
+        let wait = Duration::from_millis(50);
+
+        let running = limiter.acquire(&tags).await;
+        let other = limiter.acquire(&[]).await;
+        // 重新加载相同的上限，正在运行的任务仍占用名额
+        limiter.set_limits(&ConcurrencyLimits::new(2).with_tag_limit("db", 1));
+        assert!(tokio::time::timeout(wait, limiter.acquire(&[])).await.is_err());
+        assert!(tokio::time::timeout(wait, limiter.acquire(&tags)).await.is_err());
+
+        // 名额都被占用时缩小上限，先结束的运行归还的名额被收回
+        limiter.set_limits(&ConcurrencyLimits::new(1).with_tag_limit("db", 1));
+        drop(other);
+        assert!(tokio::time::timeout(wait, limiter.acquire(&[])).await.is_err());
+
+        // 之后按新上限只放行一个
+        drop(running);
+        let next = limiter.acquire(&[]).await;
+        assert!(tokio::time::timeout(wait, limiter.acquire(&[])).await.is_err());
+
+        // 扩大后立即获得新增的名额
+        limiter.set_limits(&ConcurrencyLimits::new(2).with_tag_limit("db", 1));
+        let second = tokio::time::timeout(wait, limiter.acquire(&[])).await.unwrap();
+        assert!(tokio::time::timeout(wait, limiter.acquire(&[])).await.is_err());
+
+        // 取消上限后不再等待
+        limiter.set_limits(&ConcurrencyLimits::default());
+        let mut unlimited = vec![next, second];
+        for _ in 0..4 {
+            unlimited.push(tokio::time::timeout(wait, limiter.acquire(&tags)).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_turn_and_replace() {
+        let limiter = ConcurrencyLimiter::new(&ConcurrencyLimits::default());
+        let task_id = Uuid::new_v4();
+
+        let first = limiter.try_turn(task_id).expect("first run gets the turn");
+        assert!(limiter.try_turn(task_id).is_none());
+        assert!(!first.is_replaced());
+
+        let limiter = Arc::new(limiter);
+        let waiting = {
+            let limiter = limiter.clone();
+            limiter.supersede(task_id);
+            tokio::spawn(async move { limiter.turn(task_id).await })
+        };
+        assert!(first.is_replaced());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        drop(first);
+        let second = waiting.await.unwrap();
+        assert!(!second.is_replaced());
+    }
+}
@@ -4,25 +4,66 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, TimeZone, Utc};
-use cron::Schedule;
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::str::FromStr;
-use tokio::sync::{Mutex, RwLock};
+use chrono_tz::Tz;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::JoinSet;
 use tokio_cron_scheduler::{Job, JobScheduler};
 use uuid::Uuid;
 
+use crate::action::{ActionRegistry, TaskAction};
+use crate::bundle::{ExportOptions, ImportOptions, ImportReport, ScheduleBundle};
+use crate::concurrency::{ConcurrencyLimiter, ConcurrencyLimits, OverlapPolicy, TaskTurn};
 use crate::error::{Result, SchedulerError};
+use crate::execution::{TaskRunContext, TaskRunControl, TaskRunEvent};
+use crate::hooks::{
+    BeforeRunDecision, ErrorRecoverySuggestion, HookManager, TaskExecutionContext, TaskResultContext,
+};
+use crate::misfire::MisfirePlan;
+use crate::retention::{PruneReport, RetentionPolicy, RunHistorySummary};
+use crate::retry::FailureKind;
 use crate::scheduler::TaskScheduler;
+use crate::storage::SchedulerStorage;
+use crate::timezone::{normalize_timezone, ZonedSchedule};
 use crate::types::*;
+use crate::workflow::{TaskDependency, Workflow, WorkflowBriefing, WorkflowRun};
 
 /// 计算下次运行时间
-fn calculate_next_run(cron_expression: &str) -> Option<DateTime<Utc>> {
-    // 尝试解析 cron 表达式
-    let schedule = Schedule::from_str(cron_expression).ok()?;
+fn calculate_next_run(cron_expression: &str, timezone: Option<&str>) -> Option<DateTime<Utc>> {
+    // 尝试解析 cron 表达式和时区
+    let schedule = ZonedSchedule::parse(cron_expression, timezone).ok()?;
 
     // 获取当前时间之后的下次运行时间
-    schedule.after(&Utc::now()).next()
+    schedule.next_after(Utc::now())
+}
+
+/// 序列化后的字节数，用于估算存储占用
+fn serialized_len<T: serde::Serialize>(value: &T) -> u64 {
+    serde_json::to_string(value).map(|v| v.len() as u64).unwrap_or(0)
+}
+
+/// 装箱的 Send future
+type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
+
+/// cron 作业触发时认领计划运行的容差，吸收作业提前几毫秒触发的情况
+const FIRE_TOLERANCE_MS: i64 = 500;
+
+/// 补跑检查只处理此时间之前的计划运行，更近的留给 cron 作业触发
+const CATCH_UP_DELAY_SECS: i64 = 1;
+
+/// 停止任务时等待执行器终止进程的最长时间
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 正在运行的实例
+struct LiveRun {
+    /// 停止信号
+    stop: watch::Sender<bool>,
+    /// 运行结束信号
+    finished: watch::Receiver<bool>,
 }
 
 /// Cron 调度器实现
@@ -34,6 +75,28 @@ pub struct CronTaskScheduler {
     run_instances: Arc<RwLock<HashMap<Uuid, TaskRunInstance>>>,
     /// 任务日志
     logs: Arc<RwLock<HashMap<Uuid, Vec<TaskLog>>>>,
+    /// 正在运行的实例，用于停止任务
+    live_runs: Arc<RwLock<HashMap<Uuid, LiveRun>>>,
+    /// 工作流定义
+    workflows: Arc<RwLock<HashMap<Uuid, Workflow>>>,
+    /// 工作流运行记录
+    workflow_runs: Arc<RwLock<HashMap<Uuid, WorkflowRun>>>,
+    /// 任务和工作流对应的 cron 作业
+    jobs: Arc<RwLock<HashMap<Uuid, Uuid>>>,
+    /// 已清理运行的汇总，有存储时保存在存储中
+    run_summaries: Arc<RwLock<HashMap<Uuid, RunHistorySummary>>>,
+    /// 运行实例和日志的写穿存储 (可选)
+    storage: Option<Arc<dyn SchedulerStorage>>,
+    /// 动作注册表
+    registry: Arc<ActionRegistry>,
+    /// 并发控制
+    limiter: Arc<ConcurrencyLimiter>,
+    /// 任务执行回调
+    hooks: Arc<RwLock<Arc<HookManager>>>,
+    /// 任务数量上限，0 表示不限制
+    max_tasks: Arc<AtomicUsize>,
+    /// 新建任务和工作流的默认时区
+    default_timezone: Arc<std::sync::RwLock<Tz>>,
     running: Arc<RwLock<bool>>,
 }
 
@@ -50,250 +113,208 @@ impl CronTaskScheduler {
             executors: Arc::new(RwLock::new(HashMap::new())),
             run_instances: Arc::new(RwLock::new(HashMap::new())),
             logs: Arc::new(RwLock::new(HashMap::new())),
+            live_runs: Arc::new(RwLock::new(HashMap::new())),
+            workflows: Arc::new(RwLock::new(HashMap::new())),
+            workflow_runs: Arc::new(RwLock::new(HashMap::new())),
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            run_summaries: Arc::new(RwLock::new(HashMap::new())),
+            storage: None,
+            registry: Arc::new(ActionRegistry::new()),
+            limiter: Arc::new(ConcurrencyLimiter::new(&ConcurrencyLimits::default())),
+            hooks: Arc::new(RwLock::new(Arc::new(HookManager::new()))),
+            max_tasks: Arc::new(AtomicUsize::new(0)),
+            default_timezone: Arc::new(std::sync::RwLock::new(Tz::UTC)),
             running: Arc::new(RwLock::new(false)),
         })
     }
 
-    /// 验证 cron 表达式
-    pub fn validate_cron(&self, cron_expression: &str) -> Result<()> {
-        let parts: Vec<&str> = cron_expression.split_whitespace().collect();
+    /// 创建带写穿存储的调度器
+    ///
+    /// 运行实例和日志在产生时即写入存储，其他进程可以实时看到
+    pub async fn with_storage(storage: Arc<dyn SchedulerStorage>) -> Result<Self> {
+        let mut scheduler = Self::new().await?;
+        scheduler.storage = Some(storage);
+        Ok(scheduler)
+    }
 
-        // tokio-cron-scheduler 需要 5 或 6 字段
-        if parts.len() < 5 || parts.len() > 6 {
-            return Err(SchedulerError::InvalidCronExpression(
-                cron_expression.to_string(),
-            ));
+    /// 任务已清理运行的汇总
+    async fn run_summary(&self, task_id: Uuid) -> Result<Option<RunHistorySummary>> {
+        match &self.storage {
+            Some(storage) => storage
+                .load_run_summary(task_id)
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string())),
+            None => Ok(self.run_summaries.read().await.get(&task_id).cloned()),
         }
-
-        // 尝试创建 Job 来验证
-        Job::new_async(cron_expression, move |_uuid, _l| {
-            Box::pin(async move {})
-        })
-        .map_err(|_| SchedulerError::InvalidCronExpression(cron_expression.to_string()))?;
-
-        Ok(())
     }
-}
 
-#[async_trait]
-impl TaskScheduler for CronTaskScheduler {
-    async fn add_task(
-        &self,
-        title: String,
-        name: String,
-        cron_expression: String,
-        executor: crate::scheduler::AsyncTaskExecutor,
-    ) -> Result<ScheduledTask> {
-        self.add_task_full(title, name, None, None, cron_expression, executor)
-            .await
+    /// 保存任务已清理运行的汇总
+    async fn save_run_summary(&self, summary: RunHistorySummary) -> Result<()> {
+        match &self.storage {
+            Some(storage) => storage
+                .save_run_summary(&summary)
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string())),
+            None => {
+                self.run_summaries.write().await.insert(summary.task_id, summary);
+                Ok(())
+            }
+        }
     }
 
-    async fn add_task_full(
-        &self,
-        title: String,
-        name: String,
-        description: Option<String>,
-        content: Option<String>,
-        cron_expression: String,
-        executor: crate::scheduler::AsyncTaskExecutor,
-    ) -> Result<ScheduledTask> {
-        self.add_task_with_system(title, name, description, content, cron_expression, executor, false).await
+    /// 各运行实例的日志条数和字节数
+    async fn log_usage(&self) -> Result<HashMap<Uuid, (usize, u64)>> {
+        if let Some(storage) = &self.storage {
+            return storage
+                .log_usage()
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string()));
+        }
+        let logs = self.logs.read().await;
+        Ok(logs
+            .iter()
+            .map(|(id, logs)| {
+                let bytes = logs.iter().map(serialized_len).sum();
+                (*id, (logs.len(), bytes))
+            })
+            .collect())
     }
 
-    async fn add_task_with_system(
-        &self,
-        title: String,
-        name: String,
-        description: Option<String>,
-        content: Option<String>,
-        cron_expression: String,
-        executor: crate::scheduler::AsyncTaskExecutor,
-        is_system: bool,
-    ) -> Result<ScheduledTask> {
-        // 验证 cron 表达式
-        self.validate_cron(&cron_expression)?;
-
-        let task_id = Uuid::new_v4();
-        let task_id_copy = task_id;
-        let tasks = self.tasks.clone();
-        let executors = self.executors.clone();
-        let run_instances = self.run_instances.clone();
-        let logs = self.logs.clone();
-        let cron_expression_str = cron_expression.clone();
-        let cron_for_next_run = cron_expression.clone();
-
-        // 创建 Job
-        let job = Job::new_async(cron_expression_str.as_str(), move |_uuid, _l| {
-            let task_id = task_id_copy;
-            let tasks = tasks.clone();
-            let executors = executors.clone();
-            let run_instances = run_instances.clone();
-            let logs = logs.clone();
-            let cron_expr = cron_for_next_run.clone();
-
-            Box::pin(async move {
-                // 创建运行实例
-                let mut instance = TaskRunInstance::new(task_id, HashMap::new());
-                instance.mark_running();
-
-                // 保存运行实例
-                {
-                    let mut instances = run_instances.write().await;
-                    instances.insert(instance.id, instance.clone());
-                }
-
-                // 创建日志
-                let log_msg = format!("Task {} started", task_id);
-                let log = TaskLog::info(instance.id, log_msg);
-                {
-                    let mut log_map = logs.write().await;
-                    log_map.entry(instance.id).or_insert_with(Vec::new).push(log);
-                }
-
-                // 更新任务状态为运行中
-                {
-                    let mut tasks_guard = tasks.write().await;
-                    if let Some(task) = tasks_guard.get_mut(&task_id) {
-                        task.status = TaskStatus::Running;
-                        task.last_run = Some(Utc::now());
-                    }
-                }
-
-                // 执行任务
-                let executors_guard = executors.read().await;
-                let result = if let Some(executor) = executors_guard.get(&task_id) {
-                    executor(task_id, instance.user_params.clone())
-                } else {
-                    Err(SchedulerError::ExecutionError("Executor not found".to_string()))
-                };
-
-                // 更新运行实例状态
-                {
-                    let mut instances = run_instances.write().await;
-                    if let Some(inst) = instances.get_mut(&instance.id) {
-                        match &result {
-                            Ok(r) => inst.mark_completed(r.clone()),
-                            Err(e) => inst.mark_error(e.to_string()),
-                        }
-                    }
-                }
+    /// 构建执行任务所需的共享状态
+    fn run_state(&self) -> RunState {
+        RunState {
+            tasks: self.tasks.clone(),
+            executors: self.executors.clone(),
+            run_instances: self.run_instances.clone(),
+            logs: self.logs.clone(),
+            live_runs: self.live_runs.clone(),
+            workflows: self.workflows.clone(),
+            workflow_runs: self.workflow_runs.clone(),
+            storage: self.storage.clone(),
+            limiter: self.limiter.clone(),
+            scheduler: self.scheduler.clone(),
+            jobs: self.jobs.clone(),
+            registry: self.registry.clone(),
+            hooks: self.hooks.clone(),
+        }
+    }
 
-                // 添加完成日志
-                {
-                    let log_msg = match &result {
-                        Ok(r) if r.success => format!("Task {} completed successfully", task_id),
-                        Ok(r) => format!("Task {} failed: {}", task_id, r.error.as_deref().unwrap_or("unknown")),
-                        Err(e) => format!("Task {} error: {}", task_id, e),
-                    };
-                    let log = if result.as_ref().map(|r| r.success).unwrap_or(false) {
-                        TaskLog::info(instance.id, log_msg)
-                    } else {
-                        TaskLog::error(instance.id, log_msg)
-                    };
-                    let mut log_map = logs.write().await;
-                    log_map.entry(instance.id).or_insert_with(Vec::new).push(log);
-                }
+    /// 设置动作注册表
+    pub fn with_action_registry(mut self, registry: ActionRegistry) -> Self {
+        self.registry = Arc::new(registry);
+        self
+    }
 
-                // 更新任务状态
-                {
-                    let mut tasks_guard = tasks.write().await;
-                    if let Some(task) = tasks_guard.get_mut(&task_id) {
-                        task.status = match &result {
-                            Ok(r) if r.success => TaskStatus::Completed,
-                            Ok(_) => TaskStatus::Failed,
-                            Err(_) => TaskStatus::Error,
-                        };
-                        task.run_count += 1;
-                        // 更新下次运行时间
-                        task.next_run = calculate_next_run(&cron_expr);
-                    }
-                }
+    /// 动作注册表
+    pub fn action_registry(&self) -> &ActionRegistry {
+        &self.registry
+    }
 
-                // 返回 () 而不是 Result
-                let _ = result;
-            })
-        })
-        .map_err(|_| SchedulerError::InvalidCronExpression(cron_expression.clone()))?;
+    /// 设置并发上限，默认不限制
+    pub fn with_concurrency_limits(self, limits: &ConcurrencyLimits) -> Self {
+        self.set_concurrency_limits(limits);
+        self
+    }
 
-        // 添加到调度器
-        {
-            let mut scheduler = self.scheduler.lock().await;
-            scheduler
-                .add(job)
-                .await
-                .map_err(|e| SchedulerError::SchedulerError(e.to_string()))?;
-        }
+    /// 调整并发上限，正在运行的任务占用的名额计入新上限
+    pub fn set_concurrency_limits(&self, limits: &ConcurrencyLimits) {
+        self.limiter.set_limits(limits);
+    }
 
-        // 保存执行器
-        {
-            let mut executors_guard = self.executors.write().await;
-            executors_guard.insert(task_id, executor);
-        }
+    /// 设置任务执行回调
+    pub fn with_hooks(mut self, hooks: HookManager) -> Self {
+        self.hooks = Arc::new(RwLock::new(Arc::new(hooks)));
+        self
+    }
 
-        // 创建任务元数据
-        let mut task = if is_system {
-            ScheduledTask::new_system(task_id, title, name, cron_expression.clone(), description, content)
-        } else {
-            ScheduledTask::new(task_id, title, name, cron_expression.clone(), description, content)
-        };
+    /// 替换任务执行回调，正在进行的运行仍使用开始时的回调
+    pub async fn set_hooks(&self, hooks: HookManager) {
+        *self.hooks.write().await = Arc::new(hooks);
+    }
 
-        // 计算下次运行时间
-        task.next_run = calculate_next_run(&cron_expression);
+    /// 设置任务数量上限，0 表示不限制
+    ///
+    /// 只限制新建任务，从存储恢复的任务和补救任务不受影响
+    pub fn set_max_tasks(&self, max_tasks: usize) {
+        self.max_tasks.store(max_tasks, AtomicOrdering::Relaxed);
+    }
 
-        // 保存任务
-        {
-            let mut tasks_guard = self.tasks.write().await;
-            tasks_guard.insert(task_id, task.clone());
-        }
+    /// 设置新建任务和工作流的默认时区，已有任务和工作流保持各自的时区
+    pub fn set_default_timezone(&self, timezone: Tz) {
+        *self.default_timezone.write().unwrap_or_else(|e| e.into_inner()) = timezone;
+    }
 
-        Ok(task)
+    /// 新建任务和工作流使用的时区，UTC 记为 None
+    fn new_task_timezone(&self) -> Option<String> {
+        let timezone = *self.default_timezone.read().unwrap_or_else(|e| e.into_inner());
+        (timezone != Tz::UTC).then(|| timezone.name().to_string())
     }
 
-    async fn remove_task(&self, task_id: Uuid) -> Result<()> {
-        // 检查任务是否存在
-        {
-            let tasks = self.tasks.read().await;
-            if !tasks.contains_key(&task_id) {
-                return Err(SchedulerError::JobNotFound(task_id));
-            }
+    /// 新建任务前检查数量上限
+    async fn ensure_task_capacity(&self) -> Result<()> {
+        let max_tasks = self.max_tasks.load(AtomicOrdering::Relaxed);
+        if max_tasks > 0 && self.tasks.read().await.len() >= max_tasks {
+            return Err(SchedulerError::InvalidParameter(format!(
+                "Task limit reached: at most {} tasks allowed",
+                max_tasks
+            )));
         }
-
-        // 移除任务
-        let mut tasks = self.tasks.write().await;
-        let mut executors = self.executors.write().await;
-
-        tasks.remove(&task_id);
-        executors.remove(&task_id);
-
         Ok(())
     }
 
-    async fn pause_task(&self, task_id: Uuid) -> Result<()> {
-        let mut tasks = self.tasks.write().await;
-        let task = tasks
-            .get_mut(&task_id)
-            .ok_or(SchedulerError::JobNotFound(task_id))?;
+    /// 按任务自身的 ID 注册任务和执行器
+    ///
+    /// 新建任务和从存储恢复任务共用此入口
+    pub async fn schedule_task(
+        &self,
+        task: ScheduledTask,
+        executor: crate::scheduler::AsyncTaskExecutor,
+    ) -> Result<ScheduledTask> {
+        self.run_state().schedule_task(task, executor).await
+    }
 
-        task.status = TaskStatus::Paused;
-        task.enabled = false;
+    /// 为任务注册 cron 作业，替换已有作业
+    async fn register_job(&self, task: &ScheduledTask) -> Result<()> {
+        self.run_state().register_job(task).await
+    }
 
-        Ok(())
+    /// 为带 cron 表达式的工作流注册作业，替换已有作业
+    async fn register_workflow_job(&self, workflow: &Workflow) -> Result<()> {
+        self.run_state().register_workflow_job(workflow).await
     }
 
-    async fn resume_task(&self, task_id: Uuid) -> Result<()> {
-        let mut tasks = self.tasks.write().await;
-        let task = tasks
-            .get_mut(&task_id)
-            .ok_or(SchedulerError::JobNotFound(task_id))?;
+    /// 移除任务或工作流的 cron 作业
+    async fn unregister_job(&self, owner: Uuid) -> Result<()> {
+        self.run_state().unregister_job(owner).await
+    }
 
-        task.status = TaskStatus::Pending;
-        task.enabled = true;
+    /// 按工作流自身的 ID 登记工作流并注册作业
+    ///
+    /// 只检查工作流结构，不检查步骤引用的任务是否存在，新建和从存储恢复共用此入口
+    pub async fn schedule_workflow(&self, workflow: Workflow) -> Result<Workflow> {
+        workflow.validate()?;
+        if let Some(cron_expression) = &workflow.cron_expression {
+            ensure_cron_version(cron_expression, workflow.cron_version)?;
+            ZonedSchedule::parse(cron_expression, workflow.timezone.as_deref())?;
+        }
+        self.register_workflow_job(&workflow).await?;
+        self.insert_workflow(workflow.clone()).await;
+        Ok(workflow)
+    }
 
-        Ok(())
+    /// 仅登记工作流定义 (无法注册作业的工作流)
+    pub(crate) async fn insert_workflow(&self, workflow: Workflow) {
+        let mut workflows = self.workflows.write().await;
+        workflows.insert(workflow.id, workflow);
     }
 
-    async fn update_task(&self, request: TaskUpdateRequest) -> Result<ScheduledTask> {
+    /// 更新任务字段
+    async fn apply_update(&self, request: TaskUpdateRequest) -> Result<ScheduledTask> {
+        let timezone = request.timezone.as_deref().map(normalize_timezone).transpose()?;
         let mut tasks = self.tasks.write().await;
+        if let Some(depends_on) = &request.depends_on {
+            validate_dependencies(&tasks, request.id, depends_on)?;
+        }
         let task = tasks
             .get_mut(&request.id)
             .ok_or(SchedulerError::JobNotFound(request.id))?;
@@ -307,127 +328,1345 @@ impl TaskScheduler for CronTaskScheduler {
         if let Some(content) = request.content {
             task.content = Some(content);
         }
+        let schedule_changed = request.cron_expression.is_some() || timezone.is_some();
         if let Some(cron) = request.cron_expression {
             // 验证新 cron 表达式
             self.validate_cron(&cron)?;
-            task.cron_expression = cron.clone();
+            task.cron_expression = cron;
+            task.cron_version = CRON_VERSION;
+        }
+        if let Some(timezone) = timezone {
+            task.timezone = timezone;
+        }
+        if schedule_changed {
             // 重新计算下次运行时间
-            task.next_run = calculate_next_run(&cron);
+            task.next_run = calculate_next_run(&task.cron_expression, task.timezone.as_deref());
+            // 旧计划下错过的运行不再补跑
+            task.last_scheduled_run = Some(Utc::now());
         }
         if let Some(enabled) = request.enabled {
             task.enabled = enabled;
             if enabled && task.status == TaskStatus::Paused {
                 task.status = TaskStatus::Pending;
+                task.last_scheduled_run = Some(Utc::now());
             } else if !enabled {
                 task.status = TaskStatus::Paused;
             }
         }
 
+        if let Some(misfire) = request.misfire {
+            task.misfire = misfire;
+        }
+        if let Some(retry) = request.retry {
+            if retry.max_attempts == 0 {
+                return Err(SchedulerError::InvalidParameter(
+                    "Max attempts must be at least 1".to_string(),
+                ));
+            }
+            task.retry = retry;
+        }
+        if let Some(depends_on) = request.depends_on {
+            task.depends_on = depends_on;
+        }
+        if let Some(overlap) = request.overlap {
+            task.overlap = overlap;
+        }
+        if let Some(tags) = request.tags {
+            task.tags = normalize_tags(tags);
+        }
+        if let Some(action) = request.action {
+            task.action = Some(action);
+        }
+
         Ok(task.clone())
     }
 
-    async fn get_task(&self, task_id: Uuid) -> Result<ScheduledTask> {
-        let tasks = self.tasks.read().await;
-        tasks
-            .get(&task_id)
-            .cloned()
-            .ok_or(SchedulerError::JobNotFound(task_id))
+    /// 处理所有任务错过的计划运行
+    ///
+    /// 守护进程停止或机器休眠期间到期的运行按各任务的策略补跑或记为过期，
+    /// 返回启动的补跑次数。补跑在后台执行，不等待结束
+    pub async fn catch_up_missed_runs(&self) -> Result<usize> {
+        let state = self.run_state();
+        let task_ids: Vec<Uuid> = {
+            let executors = self.executors.read().await;
+            executors.keys().copied().collect()
+        };
+        let horizon = Utc::now() - chrono::Duration::seconds(CATCH_UP_DELAY_SECS);
+
+        let mut started = 0;
+        for task_id in task_ids {
+            if !state.is_active(task_id).await {
+                continue;
+            }
+            let runs = state.account_missed(task_id, horizon, false).await?.unwrap_or(0);
+            if runs == 0 {
+                continue;
+            }
+            started += runs;
+            let state = state.clone();
+            tokio::spawn(async move {
+                for _ in 0..runs {
+                    if let Err(e) = state.execute_scheduled(task_id).await {
+                        tracing::error!("Task {} catch-up error: {}", task_id, e);
+                    }
+                }
+            });
+        }
+        Ok(started)
     }
 
-    async fn list_tasks(&self) -> Result<Vec<ScheduledTask>> {
-        let tasks = self.tasks.read().await;
-        Ok(tasks.values().cloned().collect())
+    /// 仅登记任务元数据 (没有可用执行器的任务)
+    pub(crate) async fn insert_task(&self, task: ScheduledTask) {
+        let mut tasks = self.tasks.write().await;
+        tasks.insert(task.id, task);
     }
 
-    async fn list_running_tasks(&self) -> Result<Vec<ScheduledTask>> {
-        let tasks = self.tasks.read().await;
-        let running: Vec<ScheduledTask> = tasks
-            .values()
-            .filter(|t| t.status == TaskStatus::Running)
-            .cloned()
-            .collect();
-        Ok(running)
+    /// 运行实例是否在本调度器中运行
+    pub async fn is_live(&self, run_instance_id: Uuid) -> bool {
+        self.live_runs.read().await.contains_key(&run_instance_id)
     }
 
-    async fn run_task(
-        &self,
-        task_id: Uuid,
-        user_params: std::collections::HashMap<String, String>,
-    ) -> Result<TaskRunInstance> {
-        // 检查任务是否存在
-        {
-            let tasks = self.tasks.read().await;
-            if !tasks.contains_key(&task_id) {
-                return Err(SchedulerError::JobNotFound(task_id));
-            }
-        }
+    /// 验证 cron 表达式
+    pub fn validate_cron(&self, cron_expression: &str) -> Result<()> {
+        validate_cron_expression(cron_expression)
+    }
+}
 
-        // 获取执行器
-        let executor = {
-            let executors = self.executors.read().await;
-            executors
-                .get(&task_id)
-                .cloned()
-                .ok_or(SchedulerError::ExecutionError("Executor not found".to_string()))?
+/// 单次运行所需的共享状态
+///
+/// cron 触发和手动运行共用同一条执行路径
+#[derive(Clone)]
+struct RunState {
+    tasks: Arc<RwLock<HashMap<Uuid, ScheduledTask>>>,
+    executors: Arc<RwLock<HashMap<Uuid, crate::scheduler::AsyncTaskExecutor>>>,
+    run_instances: Arc<RwLock<HashMap<Uuid, TaskRunInstance>>>,
+    logs: Arc<RwLock<HashMap<Uuid, Vec<TaskLog>>>>,
+    live_runs: Arc<RwLock<HashMap<Uuid, LiveRun>>>,
+    workflows: Arc<RwLock<HashMap<Uuid, Workflow>>>,
+    workflow_runs: Arc<RwLock<HashMap<Uuid, WorkflowRun>>>,
+    storage: Option<Arc<dyn SchedulerStorage>>,
+    limiter: Arc<ConcurrencyLimiter>,
+    scheduler: Arc<Mutex<JobScheduler>>,
+    jobs: Arc<RwLock<HashMap<Uuid, Uuid>>>,
+    registry: Arc<ActionRegistry>,
+    hooks: Arc<RwLock<Arc<HookManager>>>,
+}
+
+/// 供 cron 作业持有的弱引用状态
+///
+/// 调度器释放后作业不再触发，存储也随之关闭
+struct WeakRunState {
+    tasks: Weak<RwLock<HashMap<Uuid, ScheduledTask>>>,
+    executors: Weak<RwLock<HashMap<Uuid, crate::scheduler::AsyncTaskExecutor>>>,
+    run_instances: Weak<RwLock<HashMap<Uuid, TaskRunInstance>>>,
+    logs: Weak<RwLock<HashMap<Uuid, Vec<TaskLog>>>>,
+    live_runs: Weak<RwLock<HashMap<Uuid, LiveRun>>>,
+    workflows: Weak<RwLock<HashMap<Uuid, Workflow>>>,
+    workflow_runs: Weak<RwLock<HashMap<Uuid, WorkflowRun>>>,
+    storage: Option<Weak<dyn SchedulerStorage>>,
+    limiter: Weak<ConcurrencyLimiter>,
+    scheduler: Weak<Mutex<JobScheduler>>,
+    jobs: Weak<RwLock<HashMap<Uuid, Uuid>>>,
+    registry: Weak<ActionRegistry>,
+    hooks: Weak<RwLock<Arc<HookManager>>>,
+}
+
+impl WeakRunState {
+    fn upgrade(&self) -> Option<RunState> {
+        let storage = match &self.storage {
+            Some(storage) => Some(storage.upgrade()?),
+            None => None,
         };
+        Some(RunState {
+            tasks: self.tasks.upgrade()?,
+            executors: self.executors.upgrade()?,
+            run_instances: self.run_instances.upgrade()?,
+            logs: self.logs.upgrade()?,
+            live_runs: self.live_runs.upgrade()?,
+            workflows: self.workflows.upgrade()?,
+            workflow_runs: self.workflow_runs.upgrade()?,
+            storage,
+            limiter: self.limiter.upgrade()?,
+            scheduler: self.scheduler.upgrade()?,
+            jobs: self.jobs.upgrade()?,
+            registry: self.registry.upgrade()?,
+            hooks: self.hooks.upgrade()?,
+        })
+    }
+}
 
-        // 创建运行实例
-        let mut instance = TaskRunInstance::new(task_id, user_params);
-        instance.mark_running();
+impl RunState {
+    fn downgrade(&self) -> WeakRunState {
+        WeakRunState {
+            tasks: Arc::downgrade(&self.tasks),
+            executors: Arc::downgrade(&self.executors),
+            run_instances: Arc::downgrade(&self.run_instances),
+            logs: Arc::downgrade(&self.logs),
+            live_runs: Arc::downgrade(&self.live_runs),
+            workflows: Arc::downgrade(&self.workflows),
+            workflow_runs: Arc::downgrade(&self.workflow_runs),
+            storage: self.storage.as_ref().map(Arc::downgrade),
+            limiter: Arc::downgrade(&self.limiter),
+            scheduler: Arc::downgrade(&self.scheduler),
+            jobs: Arc::downgrade(&self.jobs),
+            registry: Arc::downgrade(&self.registry),
+            hooks: Arc::downgrade(&self.hooks),
+        }
+    }
+
+    /// 按任务自身的 ID 注册任务和执行器
+    ///
+    /// 新建任务和从存储恢复任务共用此入口
+    pub async fn schedule_task(
+        &self,
+        mut task: ScheduledTask,
+        executor: crate::scheduler::AsyncTaskExecutor,
+    ) -> Result<ScheduledTask> {
+        // 验证 cron 表达式和时区
+        task_schedule(&task)?;
 
-        // 保存运行实例
+        let task_id = task.id;
+        self.register_job(&task).await?;
+
+        // 保存执行器
         {
-            let mut instances = self.run_instances.write().await;
-            instances.insert(instance.id, instance.clone());
+            let mut executors_guard = self.executors.write().await;
+            executors_guard.insert(task_id, executor);
         }
 
-        // 更新任务状态
+        // 计算下次运行时间
+        task.next_run = calculate_next_run(&task.cron_expression, task.timezone.as_deref());
+
+        // 保存任务
         {
-            let mut tasks = self.tasks.write().await;
-            if let Some(task) = tasks.get_mut(&task_id) {
-                task.status = TaskStatus::Running;
-                task.last_run = Some(Utc::now());
-            }
+            let mut tasks_guard = self.tasks.write().await;
+            tasks_guard.insert(task_id, task.clone());
         }
 
-        // 执行任务
-        let result = executor(task_id, instance.user_params.clone());
+        Ok(task)
+    }
 
-        // 更新运行实例
-        {
-            let mut instances = self.run_instances.write().await;
-            if let Some(inst) = instances.get_mut(&instance.id) {
-                match &result {
-                    Ok(r) => inst.mark_completed(r.clone()),
-                    Err(e) => inst.mark_error(e.to_string()),
-                }
+    /// 为任务注册 cron 作业，替换已有作业
+    ///
+    /// 系统级任务由操作系统调度器触发，不注册作业。
+    /// 执行路径中可能创建补救任务并再次注册作业，返回装箱的 future 以打断 future 类型的递归
+    fn register_job<'a>(&'a self, task: &'a ScheduledTask) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.unregister_job(task.id).await?;
+            if task.is_system {
+                return Ok(());
             }
-        }
 
-        // 更新任务状态
-        {
-            let mut tasks = self.tasks.write().await;
+            let task_id = task.id;
+            let schedule = task_schedule(task)?;
+            let job = self.next_run_job(&schedule, move |state| {
+                Box::pin(async move {
+                    // 先安排下一次运行，本次运行的耗时不影响下次触发
+                    state.rearm_job(task_id).await;
+                    state.fire_task(task_id).await;
+                })
+            })?;
+            match job {
+                Some(job) => self.add_job(task_id, job).await,
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// 创建在计划的下一次运行时刻触发的单次作业，计划不再有运行时返回 None
+    ///
+    /// tokio-cron-scheduler 的 cron 作业按 cron crate 的语法解析，不支持 L、W、#，
+    /// 且在创建作业时固定时区偏移，跨过夏令时切换后会偏差。
+    /// 任务和工作流统一改用单次作业，由 fire 在触发时按计划注册下一次运行
+    fn next_run_job<F>(&self, schedule: &ZonedSchedule, fire: F) -> Result<Option<Job>>
+    where
+        F: Fn(RunState) -> BoxFuture<'static, ()> + Send + Sync + 'static,
+    {
+        let Some(next) = schedule.next_after(Utc::now()) else {
+            return Ok(None);
+        };
+        let delay = (next - Utc::now()).to_std().unwrap_or_default();
+        let state = self.downgrade();
+        Job::new_one_shot_async(delay, move |_uuid, _l| {
+            let run = state.upgrade().map(&fire);
+
+            Box::pin(async move {
+                let Some(run) = run else {
+                    return;
+                };
+                // 单次作业按整秒触发，可能略早于计划时间
+                if let Ok(early) = (next - Utc::now()).to_std() {
+                    tokio::time::sleep(early).await;
+                }
+                run.await;
+            })
+        })
+        .map(Some)
+        .map_err(|e| SchedulerError::SchedulerError(e.to_string()))
+    }
+
+    /// 按任务当前的计划重新注册作业
+    async fn rearm_job(&self, task_id: Uuid) {
+        let task = self.tasks.read().await.get(&task_id).cloned();
+        if let Some(task) = task {
+            if let Err(e) = self.register_job(&task).await {
+                tracing::error!("Task {} job registration failed: {}", task_id, e);
+            }
+        }
+    }
+
+    /// cron 作业触发任务
+    async fn fire_task(&self, task_id: Uuid) {
+        // 暂停或禁用的任务不触发
+        if !self.is_active(task_id).await {
+            return;
+        }
+        // 先处理两次触发之间错过的运行；本次运行已被补跑检查认领时不再执行
+        let fired_at = Utc::now() + chrono::Duration::milliseconds(FIRE_TOLERANCE_MS);
+        let runs = match self.account_missed(task_id, fired_at, true).await {
+            Ok(Some(extra)) => extra + 1,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!("Task {} misfire check failed: {}", task_id, e);
+                1
+            }
+        };
+        for _ in 0..runs {
+            if let Err(e) = self.execute_scheduled(task_id).await {
+                tracing::error!("Task {} error: {}", task_id, e);
+            }
+        }
+    }
+
+    /// 为带 cron 表达式的工作流注册作业，替换已有作业
+    ///
+    /// 触发时重新注册作业，返回装箱的 future 以打断 future 类型的递归
+    fn register_workflow_job<'a>(&'a self, workflow: &'a Workflow) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.unregister_job(workflow.id).await?;
+            let Some(cron_expression) = &workflow.cron_expression else {
+                return Ok(());
+            };
+
+            let workflow_id = workflow.id;
+            ensure_cron_version(cron_expression, workflow.cron_version)?;
+            let schedule = ZonedSchedule::parse(cron_expression, workflow.timezone.as_deref())?;
+            let job = self.next_run_job(&schedule, move |state| {
+                Box::pin(async move {
+                    let workflow = state.workflows.read().await.get(&workflow_id).cloned();
+                    let Some(workflow) = workflow else {
+                        return;
+                    };
+                    if let Err(e) = state.register_workflow_job(&workflow).await {
+                        tracing::error!("Workflow {} job registration failed: {}", workflow_id, e);
+                    }
+                    if !workflow.enabled {
+                        return;
+                    }
+                    if let Err(e) = state.execute_workflow(&workflow, HashMap::new()).await {
+                        tracing::error!("Workflow {} error: {}", workflow_id, e);
+                    }
+                })
+            })?;
+            match job {
+                Some(job) => self.add_job(workflow_id, job).await,
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// 将作业添加到调度器，并记录为 owner 的作业
+    async fn add_job(&self, owner: Uuid, job: Job) -> Result<()> {
+        let job_id = {
+            let scheduler = self.scheduler.lock().await;
+            scheduler
+                .add(job)
+                .await
+                .map_err(|e| SchedulerError::SchedulerError(e.to_string()))?
+        };
+        self.jobs.write().await.insert(owner, job_id);
+        Ok(())
+    }
+
+    /// 移除任务的 cron 作业
+    async fn unregister_job(&self, task_id: Uuid) -> Result<()> {
+        let job_id = self.jobs.write().await.remove(&task_id);
+        if let Some(job_id) = job_id {
+            let scheduler = self.scheduler.lock().await;
+            scheduler
+                .remove(&job_id)
+                .await
+                .map_err(|e| SchedulerError::SchedulerError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// 保存运行实例 (内存 + 存储)
+    async fn save_instance(&self, instance: &TaskRunInstance) -> Result<()> {
+        {
+            let mut instances = self.run_instances.write().await;
+            instances.insert(instance.id, instance.clone());
+        }
+        if let Some(storage) = &self.storage {
+            storage
+                .save_run_instance(instance)
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// 任务是否处于可被 cron 触发的状态
+    async fn is_active(&self, task_id: Uuid) -> bool {
+        let tasks = self.tasks.read().await;
+        tasks
+            .get(&task_id)
+            .is_some_and(|t| t.enabled && t.status != TaskStatus::Paused)
+    }
+
+    /// 处理任务在 until 之前错过的计划运行
+    ///
+    /// 超出宽限期且不补跑的运行记为 Expired 运行实例，返回需要补跑的次数。
+    /// `firing` 为 true 时 until 之前最近的一次运行由调用方执行，
+    /// 该运行已被之前的检查认领时返回 None
+    async fn account_missed(
+        &self,
+        task_id: Uuid,
+        until: DateTime<Utc>,
+        firing: bool,
+    ) -> Result<Option<usize>> {
+        // 在任务写锁内计算并推进处理位置，cron 触发和补跑检查不会重复认领
+        let plan = {
+            let mut tasks = self.tasks.write().await;
+            let Some(task) = tasks.get_mut(&task_id) else {
+                return Ok(None);
+            };
+            if task.is_system {
+                return Ok(Some(0));
+            }
+            let since = task
+                .last_scheduled_run
+                .or(task.last_run)
+                .unwrap_or(task.created_at);
+            if until <= since {
+                return Ok(if firing { None } else { Some(0) });
+            }
+            let Ok(schedule) = task_schedule(task) else {
+                return Ok(if firing { None } else { Some(0) });
+            };
+            let plan = MisfirePlan::compute_zoned(&schedule, &task.misfire, since, until, Utc::now(), firing);
+            if firing && plan.is_empty() && !has_occurrence(&schedule, since, until) {
+                return Ok(None);
+            }
+            task.last_scheduled_run = Some(until);
+            plan
+        };
+
+        for scheduled_at in &plan.expired {
+            let instance = TaskRunInstance::expired(task_id, *scheduled_at);
+            self.save_instance(&instance).await?;
+            self.push_log(TaskLog::warn(
+                instance.id,
+                format!("Task {} missed scheduled run at {}", task_id, scheduled_at),
+            ))
+            .await?;
+        }
+        if plan.uncounted > 0 {
+            tracing::warn!(
+                "Task {} missed {} more scheduled runs that were not recorded",
+                task_id,
+                plan.uncounted
+            );
+        }
+        self.save_task(task_id).await?;
+        Ok(Some(plan.runs))
+    }
+
+    /// 将任务运行状态写入存储
+    ///
+    /// 只更新运行相关字段，其他进程对任务定义的修改不会被覆盖；
+    /// 任务已被其他进程删除时不再写回
+    async fn save_task(&self, task_id: Uuid) -> Result<()> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+        let Some(task) = self.tasks.read().await.get(&task_id).cloned() else {
+            return Ok(());
+        };
+        let stored = storage
+            .load_task(task_id)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        let Some(mut stored) = stored else {
+            return Ok(());
+        };
+
+        if stored.status != TaskStatus::Paused {
+            stored.status = task.status;
+        }
+        stored.last_run = task.last_run;
+        stored.next_run = task.next_run;
+        stored.run_count = task.run_count;
+        stored.last_scheduled_run = stored.last_scheduled_run.max(task.last_scheduled_run);
+        storage
+            .save_task(&stored)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))
+    }
+
+    /// 记录执行器启动的子进程
+    async fn record_pid(&self, run_instance_id: Uuid, pid: u32) -> Result<()> {
+        let instance = {
+            let mut instances = self.run_instances.write().await;
+            instances.get_mut(&run_instance_id).map(|inst| {
+                inst.pid = Some(pid);
+                inst.clone()
+            })
+        };
+        match (instance, &self.storage) {
+            (Some(instance), Some(storage)) => storage
+                .save_run_instance(&instance)
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// 追加日志 (内存 + 存储)
+    async fn push_log(&self, log: TaskLog) -> Result<()> {
+        if let Some(storage) = &self.storage {
+            storage
+                .save_log(&log)
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        }
+        let mut logs = self.logs.write().await;
+        logs.entry(log.run_instance_id).or_default().push(log);
+        Ok(())
+    }
+
+    /// 任务的运行实例 (内存 + 存储)，包含其他进程记录的运行
+    async fn instances_of(&self, task_id: Uuid) -> Result<Vec<TaskRunInstance>> {
+        let mut instances: Vec<TaskRunInstance> = {
+            let instances = self.run_instances.read().await;
+            instances.values().filter(|i| i.task_id == task_id).cloned().collect()
+        };
+        if let Some(storage) = &self.storage {
+            let stored = storage
+                .list_run_instances(task_id)
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+            for instance in stored {
+                if !instances.iter().any(|i| i.id == instance.id) {
+                    instances.push(instance);
+                }
+            }
+        }
+        Ok(instances)
+    }
+
+    /// 第一个未满足的依赖，全部满足时返回 None
+    async fn unmet_dependency(&self, task_id: Uuid) -> Result<Option<TaskDependency>> {
+        let depends_on = {
+            let tasks = self.tasks.read().await;
+            tasks.get(&task_id).map(|t| t.depends_on.clone()).unwrap_or_default()
+        };
+        let now = Utc::now();
+        for dep in depends_on {
+            let instances = self.instances_of(dep.task_id).await?;
+            if !dep.is_satisfied_by(&instances, now) {
+                return Ok(Some(dep));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 执行一次定时触发的运行
+    ///
+    /// 依赖未满足时不运行，记为 Skipped 运行实例；手动运行不检查依赖
+    async fn execute_scheduled(&self, task_id: Uuid) -> Result<TaskRunInstance> {
+        if let Some(dep) = self.unmet_dependency(task_id).await? {
+            let reason = format!(
+                "Dependency {} has no successful run in the last {}s",
+                dep.task_id, dep.within_secs
+            );
+            return self.record_skipped(task_id, reason).await;
+        }
+        self.execute(task_id, HashMap::new()).await
+    }
+
+    /// 记录一次被跳过的运行
+    async fn record_skipped(&self, task_id: Uuid, reason: String) -> Result<TaskRunInstance> {
+        let instance = TaskRunInstance::skipped(task_id, reason.clone());
+        self.save_instance(&instance).await?;
+        self.push_log(TaskLog::warn(
+            instance.id,
+            format!("Task {} skipped: {}", task_id, reason),
+        ))
+        .await?;
+        Ok(instance)
+    }
+
+    /// 按任务的重叠策略等待运行机会
+    ///
+    /// Allow 不限制，返回 Ok(None)；Skip 策略下上一次运行未结束时返回 Err(())
+    async fn claim_turn(
+        &self,
+        task_id: Uuid,
+        overlap: OverlapPolicy,
+    ) -> std::result::Result<Option<TaskTurn>, ()> {
+        match overlap {
+            OverlapPolicy::Allow => Ok(None),
+            OverlapPolicy::Skip => self.limiter.try_turn(task_id).map(Some).ok_or(()),
+            OverlapPolicy::Queue => {
+                if let Some(turn) = self.limiter.try_turn(task_id) {
+                    return Ok(Some(turn));
+                }
+                tracing::info!("Task {} queued behind its previous run", task_id);
+                Ok(Some(self.limiter.turn(task_id).await))
+            }
+            OverlapPolicy::Replace => {
+                if let Some(turn) = self.limiter.try_turn(task_id) {
+                    return Ok(Some(turn));
+                }
+                self.limiter.supersede(task_id);
+                self.stop_runs_of(task_id).await;
+                Ok(Some(self.limiter.turn(task_id).await))
+            }
+        }
+    }
+
+    /// 停止任务正在进行的运行，供 Replace 策略使用
+    async fn stop_runs_of(&self, task_id: Uuid) {
+        let running: Vec<Uuid> = {
+            let instances = self.run_instances.read().await;
+            instances
+                .values()
+                .filter(|i| i.task_id == task_id && i.status == TaskStatus::Running)
+                .map(|i| i.id)
+                .collect()
+        };
+        for run_instance_id in running {
+            let stopped = {
+                let live_runs = self.live_runs.read().await;
+                live_runs
+                    .get(&run_instance_id)
+                    .is_some_and(|run| run.stop.send(true).is_ok())
+            };
+            if stopped {
+                let log = TaskLog::warn(
+                    run_instance_id,
+                    format!("Task {} run replaced by a newer run", task_id),
+                );
+                if let Err(e) = self.push_log(log).await {
+                    tracing::warn!("Failed to record replacement of {}: {}", run_instance_id, e);
+                }
+            }
+        }
+    }
+
+    /// 保存工作流运行记录 (内存 + 存储)
+    async fn save_workflow_run(&self, run: &WorkflowRun) -> Result<()> {
+        {
+            let mut runs = self.workflow_runs.write().await;
+            runs.insert(run.id, run.clone());
+        }
+        if let Some(storage) = &self.storage {
+            storage
+                .save_workflow_run(run)
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// 运行一次工作流，所有步骤结束后返回运行记录
+    ///
+    /// 前序步骤全部成功的步骤并行执行，任一前序步骤未成功的步骤被跳过。
+    /// 每个步骤按所执行任务的重试策略重试，运行记录在每次状态变化后保存
+    async fn execute_workflow(
+        &self,
+        workflow: &Workflow,
+        user_params: HashMap<String, String>,
+    ) -> Result<WorkflowRun> {
+        let mut run = WorkflowRun::new(workflow, user_params);
+        run.status = TaskStatus::Running;
+        tracing::info!("Workflow {} started (run {})", workflow.id, run.id);
+
+        let mut steps = JoinSet::new();
+        loop {
+            run.skip_blocked(workflow);
+            for name in run.ready_steps(workflow) {
+                let Some(step) = workflow.step(&name) else {
+                    continue;
+                };
+                let task_id = step.task_id;
+                let params = run.step_params(workflow, &name);
+                run.start_step(&name);
+                let state = self.clone();
+                steps.spawn(async move {
+                    let result = state.execute(task_id, params).await;
+                    (name, result)
+                });
+            }
+            self.save_workflow_run(&run).await?;
+
+            let Some(joined) = steps.join_next().await else {
+                break;
+            };
+            let (name, result) = joined.map_err(|e| SchedulerError::ExecutionError(e.to_string()))?;
+            run.finish_step(&name, result);
+        }
+
+        run.finish();
+        self.save_workflow_run(&run).await?;
+        tracing::info!("Workflow {} finished with status {}", workflow.id, run.status);
+        Ok(run)
+    }
+
+    /// 执行一次任务，返回最后一次尝试结束后的运行实例
+    ///
+    /// 先按重叠策略处理上一次未结束的运行，每次尝试前等待全局和标签的并发名额。
+    /// 失败时按任务的重试策略等待后再次尝试，各次尝试归入同一逻辑运行；
+    /// 被用户停止、被新的运行替换、任务被删除或暂停后不再重试
+    async fn execute(
+        &self,
+        task_id: Uuid,
+        user_params: HashMap<String, String>,
+    ) -> Result<TaskRunInstance> {
+        let (policy, overlap, tags) = {
+            let tasks = self.tasks.read().await;
+            tasks
+                .get(&task_id)
+                .map(|t| (t.retry, t.overlap, t.tags.clone()))
+                .unwrap_or_default()
+        };
+
+        let Ok(turn) = self.claim_turn(task_id, overlap).await else {
+            return self
+                .record_skipped(task_id, "Previous run is still running".to_string())
+                .await;
+        };
+        let hooks = self.hooks.read().await.clone();
+        let user_params = match self.before_run(&hooks, task_id, user_params).await {
+            Ok(user_params) => user_params,
+            Err(reason) => {
+                return self
+                    .record_skipped(task_id, format!("Vetoed by hook: {}", reason))
+                    .await;
+            }
+        };
+
+        let (mut instance, mut failure) = self
+            .execute_limited(TaskRunInstance::new(task_id, user_params), &tags)
+            .await?;
+        while let Some(kind) = failure {
+            if !policy.should_retry(instance.attempt, kind) {
+                break;
+            }
+            let delay = policy.delay(instance.attempt);
+            self.push_log(TaskLog::warn(
+                instance.id,
+                format!(
+                    "Attempt {}/{} failed ({}), retrying in {}s",
+                    instance.attempt,
+                    policy.max_attempts,
+                    kind,
+                    delay.as_secs_f32()
+                ),
+            ))
+            .await?;
+            tokio::time::sleep(delay).await;
+            if turn.as_ref().is_some_and(|t| t.is_replaced()) {
+                self.push_log(TaskLog::warn(
+                    instance.id,
+                    format!("Task {} was replaced by a newer run, retry cancelled", task_id),
+                ))
+                .await?;
+                break;
+            }
+            if !self.is_active(task_id).await {
+                self.push_log(TaskLog::warn(
+                    instance.id,
+                    format!("Task {} is no longer active, retry cancelled", task_id),
+                ))
+                .await?;
+                break;
+            }
+            (instance, failure) = self
+                .execute_limited(TaskRunInstance::retry_of(&instance), &tags)
+                .await?;
+        }
+        self.after_run(&hooks, &instance).await;
+        Ok(instance)
+    }
+
+    /// 最近一次结束的运行结果，不含跳过和过期的运行
+    async fn last_result(&self, task_id: Uuid) -> Result<Option<TaskExecutionResult>> {
+        let instances = self.instances_of(task_id).await?;
+        Ok(instances
+            .into_iter()
+            .filter(|i| matches!(i.status, TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Error))
+            .max_by_key(|i| i.started_at)
+            .and_then(|i| i.result))
+    }
+
+    /// 调用执行前回调，返回回调修改后的参数；被否决时返回否决原因
+    ///
+    /// 回调出错时记录警告并按已修改的参数继续运行
+    async fn before_run(
+        &self,
+        hooks: &HookManager,
+        task_id: Uuid,
+        user_params: HashMap<String, String>,
+    ) -> std::result::Result<HashMap<String, String>, String> {
+        if hooks.is_empty() {
+            return Ok(user_params);
+        }
+        let Some(task) = self.tasks.read().await.get(&task_id).cloned() else {
+            return Ok(user_params);
+        };
+        let last_result = self.last_result(task_id).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to load last result of task {}: {}", task_id, e);
+            None
+        });
+        let mut context = TaskExecutionContext::from_task(&task, user_params, last_result);
+        match hooks.trigger_before_run(&mut context).await {
+            Ok(BeforeRunDecision::Proceed) => {}
+            Ok(BeforeRunDecision::Veto(reason)) => return Err(reason),
+            Err(e) => tracing::warn!("Before-run hook failed for task {}: {}", task_id, e),
+        }
+        Ok(context.user_params)
+    }
+
+    /// 逻辑运行结束后调用执行后回调，并按错误回调的建议创建补救任务
+    ///
+    /// 回调出错只记录警告，不影响运行结果
+    async fn after_run(&self, hooks: &HookManager, instance: &TaskRunInstance) {
+        if hooks.is_empty() {
+            return;
+        }
+        let task_id = instance.task_id;
+        let mut context = TaskResultContext::from_instance(instance);
+        if let Some(task) = self.tasks.read().await.get(&task_id) {
+            context = context.with_task(task);
+        }
+        if let Err(e) = hooks.trigger_after_run(&context).await {
+            tracing::warn!("After-run hook failed for task {}: {}", task_id, e);
+        }
+        let outcome = match instance.status {
+            TaskStatus::Completed => hooks.trigger_on_success(&context).await,
+            TaskStatus::Failed => hooks.trigger_on_failure(&context).await,
+            TaskStatus::Error => {
+                let error = context.result.error.as_deref().unwrap_or("unknown");
+                match hooks.trigger_on_error(&context, error).await {
+                    Ok(suggestion) => {
+                        if let Err(e) = self.create_recovery_task(instance, suggestion).await {
+                            tracing::warn!("Failed to create recovery task for task {}: {}", task_id, e);
+                        }
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = outcome {
+            tracing::warn!("Result hook failed for task {}: {}", task_id, e);
+        }
+    }
+
+    /// 按错误恢复建议创建补救任务
+    ///
+    /// 补救任务按建议的 cron 表达式运行修复命令，名称为原任务名加 `_recovery`；
+    /// 同名任务已存在时不重复创建
+    async fn create_recovery_task(
+        &self,
+        instance: &TaskRunInstance,
+        suggestion: ErrorRecoverySuggestion,
+    ) -> Result<()> {
+        if !suggestion.create_recovery_task {
+            return Ok(());
+        }
+        let (Some(command), Some(cron)) = (suggestion.fix_command, suggestion.recovery_cron) else {
+            self.push_log(TaskLog::warn(
+                instance.id,
+                "Recovery suggestion needs both a fix command and a cron expression, no recovery task created"
+                    .to_string(),
+            ))
+            .await?;
+            return Ok(());
+        };
+        let (title, name) = {
+            let tasks = self.tasks.read().await;
+            let Some(source) = tasks.get(&instance.task_id) else {
+                return Ok(());
+            };
+            let name = format!("{}_recovery", source.name);
+            if tasks.values().any(|t| t.name == name) {
+                return self
+                    .push_log(TaskLog::info(
+                        instance.id,
+                        format!("Recovery task {} already exists", name),
+                    ))
+                    .await;
+            }
+            (format!("Recovery: {}", source.title), name)
+        };
+
+        let action = TaskAction::shell(command);
+        let executor = self.registry.resolve(&action)?;
+        let description = (!suggestion.description.is_empty()).then_some(suggestion.description);
+        let mut task = ScheduledTask::new(Uuid::new_v4(), title, name, cron, description, None);
+        task.action = Some(action);
+        let task = self.schedule_task(task, executor).await?;
+        if let Some(storage) = &self.storage {
+            storage
+                .save_task(&task)
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        }
+        self.push_log(TaskLog::info(
+            instance.id,
+            format!("Recovery task {} created for task {}", task.id, instance.task_id),
+        ))
+        .await
+    }
+
+    /// 取得并发名额后执行一次尝试，重试等待期间不占用名额
+    async fn execute_limited(
+        &self,
+        instance: TaskRunInstance,
+        tags: &[String],
+    ) -> Result<(TaskRunInstance, Option<FailureKind>)> {
+        let _slots = self.limiter.acquire(tags).await;
+        self.execute_attempt(instance).await
+    }
+
+    /// 执行一次尝试，返回结束后的运行实例和可重试的失败类型
+    ///
+    /// 执行器的输出在产生时逐行写入日志
+    async fn execute_attempt(
+        &self,
+        mut instance: TaskRunInstance,
+    ) -> Result<(TaskRunInstance, Option<FailureKind>)> {
+        let task_id = instance.task_id;
+        // 获取执行器
+        let executor = {
+            let executors = self.executors.read().await;
+            executors
+                .get(&task_id)
+                .cloned()
+                .ok_or(SchedulerError::ExecutionError("Executor not found".to_string()))?
+        };
+
+        instance.mark_running();
+        self.save_instance(&instance).await?;
+        let started = if instance.attempt > 1 {
+            format!("Task {} started (attempt {})", task_id, instance.attempt)
+        } else {
+            format!("Task {} started", task_id)
+        };
+        self.push_log(TaskLog::info(instance.id, started)).await?;
+
+        // 更新任务状态为运行中
+        {
+            let mut tasks = self.tasks.write().await;
+            if let Some(task) = tasks.get_mut(&task_id) {
+                task.status = TaskStatus::Running;
+                task.last_run = Some(Utc::now());
+            }
+        }
+        self.save_task(task_id).await?;
+
+        // 登记为运行中，供 stop_task 发送停止信号
+        let (ctx, control) = TaskRunContext::new(instance.id);
+        let TaskRunControl { mut events, stop } = control;
+        let stop_requested = stop.subscribe();
+        let (finished_tx, finished) = watch::channel(false);
+        {
+            let mut live_runs = self.live_runs.write().await;
+            live_runs.insert(instance.id, LiveRun { stop, finished });
+        }
+
+        // 输出逐行转为日志
+        let drain = {
+            let state = self.clone();
+            let instance_id = instance.id;
+            tokio::spawn(async move {
+                while let Some(event) = events.recv().await {
+                    let recorded = match event {
+                        TaskRunEvent::Stdout(line) => state.push_log(TaskLog::info(instance_id, line)).await,
+                        TaskRunEvent::Stderr(line) => state.push_log(TaskLog::warn(instance_id, line)).await,
+                        TaskRunEvent::ProcessStarted(pid) => state.record_pid(instance_id, pid).await,
+                    };
+                    if let Err(e) = recorded {
+                        tracing::warn!("Failed to record output of {}: {}", instance_id, e);
+                    }
+                }
+            })
+        };
+
+        // 执行任务 (独立任务中运行，执行器 panic 不影响调度器)
+        let params = instance.user_params.clone();
+        let result = tokio::spawn(async move { executor.execute(task_id, params, ctx).await })
+            .await
+            .unwrap_or_else(|e| Err(SchedulerError::ExecutionError(e.to_string())));
+        let _ = drain.await;
+
+        // 被停止的运行记为失败，保留执行器记录的退出信号
+        let stopped = *stop_requested.borrow();
+        let result = if stopped {
+            Ok(stopped_result(task_id, result))
+        } else {
+            result
+        };
+
+        // 更新运行实例状态
+        instance.pid = {
+            let instances = self.run_instances.read().await;
+            instances.get(&instance.id).and_then(|inst| inst.pid)
+        };
+        match &result {
+            Ok(r) => instance.mark_completed(r.clone()),
+            Err(e) => instance.mark_error(e.to_string()),
+        }
+        let saved = self.save_instance(&instance).await;
+        {
+            let mut live_runs = self.live_runs.write().await;
+            live_runs.remove(&instance.id);
+        }
+        let _ = finished_tx.send(true);
+        saved?;
+
+        // 添加完成日志
+        let log_msg = match &result {
+            Ok(r) if r.success => format!("Task {} completed successfully", task_id),
+            Ok(r) => format!("Task {} failed: {}", task_id, r.error.as_deref().unwrap_or("unknown")),
+            Err(e) => format!("Task {} error: {}", task_id, e),
+        };
+        let log = if result.as_ref().map(|r| r.success).unwrap_or(false) {
+            TaskLog::info(instance.id, log_msg)
+        } else {
+            TaskLog::error(instance.id, log_msg)
+        };
+        self.push_log(log).await?;
+
+        // 更新任务状态
+        {
+            let mut tasks = self.tasks.write().await;
             if let Some(task) = tasks.get_mut(&task_id) {
                 task.status = match &result {
                     Ok(r) if r.success => TaskStatus::Completed,
                     Ok(_) => TaskStatus::Failed,
                     Err(_) => TaskStatus::Error,
                 };
-                task.run_count += 1;
+                // 重试不计入运行次数
+                if instance.attempt == 1 {
+                    task.run_count += 1;
+                }
+                // 更新下次运行时间
+                task.next_run = calculate_next_run(&task.cron_expression, task.timezone.as_deref());
             }
         }
+        self.save_task(task_id).await?;
 
-        // 获取更新后的实例
-        let instances = self.run_instances.read().await;
-        let updated_instance = instances
-            .get(&instance.id)
+        let failure = if stopped {
+            None
+        } else {
+            FailureKind::classify(&result)
+        };
+        Ok((instance, failure))
+    }
+}
+
+/// 检查任务依赖：依赖的任务必须存在，且不能直接或间接依赖自身
+fn validate_dependencies(
+    tasks: &HashMap<Uuid, ScheduledTask>,
+    task_id: Uuid,
+    depends_on: &[TaskDependency],
+) -> Result<()> {
+    let mut pending = Vec::new();
+    for dep in depends_on {
+        if !tasks.contains_key(&dep.task_id) {
+            return Err(SchedulerError::JobNotFound(dep.task_id));
+        }
+        pending.push(dep.task_id);
+    }
+
+    let mut visited = HashSet::new();
+    while let Some(id) = pending.pop() {
+        if id == task_id {
+            return Err(SchedulerError::InvalidParameter(format!(
+                "Dependency cycle: task {} would depend on itself",
+                task_id
+            )));
+        }
+        if visited.insert(id) {
+            if let Some(task) = tasks.get(&id) {
+                pending.extend(task.depends_on.iter().map(|d| d.task_id));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 验证 cron 表达式
+pub(crate) fn validate_cron_expression(cron_expression: &str) -> Result<()> {
+    ZonedSchedule::parse(cron_expression, None).map(|_| ())
+}
+
+/// 拒绝按旧版语法保存且未能迁移的 cron 表达式
+pub(crate) fn ensure_cron_version(cron_expression: &str, cron_version: u32) -> Result<()> {
+    if cron_version < CRON_VERSION {
+        return Err(SchedulerError::InvalidCronExpression(format!(
+            "{} (saved with the old cron syntax and not migrated, set a new expression)",
+            cron_expression
+        )));
+    }
+    Ok(())
+}
+
+/// 将按旧版语法保存的 cron 表达式改写为当前语法
+pub(crate) fn upgrade_cron_expression(cron_expression: &str) -> Result<String> {
+    cron_expression::upgrade_legacy(cron_expression).map_err(|e| {
+        SchedulerError::InvalidCronExpression(format!("{} (old cron syntax: {})", cron_expression, e))
+    })
+}
+
+/// 按任务的时区解析 cron 表达式，未迁移的旧版语法返回错误
+fn task_schedule(task: &ScheduledTask) -> Result<ZonedSchedule> {
+    ensure_cron_version(&task.cron_expression, task.cron_version)?;
+    ZonedSchedule::parse(&task.cron_expression, task.timezone.as_deref())
+}
+
+/// 去掉空白和重复的标签
+pub(crate) fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| t == tag) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+/// (since, until] 之间是否有计划运行
+fn has_occurrence(schedule: &ZonedSchedule, since: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+    schedule.next_after(since).is_some_and(|at| at <= until)
+}
+
+/// 生成被用户停止的运行结果
+fn stopped_result(task_id: Uuid, result: Result<TaskExecutionResult>) -> TaskExecutionResult {
+    let mut result =
+        result.unwrap_or_else(|e| TaskExecutionResult::failure(task_id, e.to_string()));
+    result.success = false;
+    result.error = Some(match result.signal {
+        Some(signal) => format!("Task stopped by user (signal {})", signal),
+        None => "Task stopped by user".to_string(),
+    });
+    result
+}
+
+#[async_trait]
+impl TaskScheduler for CronTaskScheduler {
+    async fn add_task(
+        &self,
+        title: String,
+        name: String,
+        cron_expression: String,
+        executor: crate::scheduler::AsyncTaskExecutor,
+    ) -> Result<ScheduledTask> {
+        self.add_task_full(title, name, None, None, cron_expression, executor)
+            .await
+    }
+
+    async fn add_task_full(
+        &self,
+        title: String,
+        name: String,
+        description: Option<String>,
+        content: Option<String>,
+        cron_expression: String,
+        executor: crate::scheduler::AsyncTaskExecutor,
+    ) -> Result<ScheduledTask> {
+        self.add_task_with_system(title, name, description, content, cron_expression, executor, false).await
+    }
+
+    async fn add_task_with_system(
+        &self,
+        title: String,
+        name: String,
+        description: Option<String>,
+        content: Option<String>,
+        cron_expression: String,
+        executor: crate::scheduler::AsyncTaskExecutor,
+        is_system: bool,
+    ) -> Result<ScheduledTask> {
+        self.ensure_task_capacity().await?;
+        let task_id = Uuid::new_v4();
+        let mut task = if is_system {
+            ScheduledTask::new_system(task_id, title, name, cron_expression, description, content)
+        } else {
+            ScheduledTask::new(task_id, title, name, cron_expression, description, content)
+        };
+        task.timezone = self.new_task_timezone();
+        self.schedule_task(task, executor).await
+    }
+
+    async fn add_task_with_action(
+        &self,
+        title: String,
+        name: String,
+        description: Option<String>,
+        content: Option<String>,
+        cron_expression: String,
+        action: TaskAction,
+        is_system: bool,
+    ) -> Result<ScheduledTask> {
+        self.ensure_task_capacity().await?;
+        let executor = self.registry.resolve(&action)?;
+        let task_id = Uuid::new_v4();
+        let mut task = if is_system {
+            ScheduledTask::new_system(task_id, title, name, cron_expression, description, content)
+        } else {
+            ScheduledTask::new(task_id, title, name, cron_expression, description, content)
+        };
+        task.action = Some(action);
+        task.timezone = self.new_task_timezone();
+        self.schedule_task(task, executor).await
+    }
+
+    async fn remove_task(&self, task_id: Uuid) -> Result<()> {
+        // 检查任务是否存在
+        {
+            let tasks = self.tasks.read().await;
+            if !tasks.contains_key(&task_id) {
+                return Err(SchedulerError::JobNotFound(task_id));
+            }
+        }
+
+        self.unregister_job(task_id).await?;
+
+        // 移除任务
+        let mut tasks = self.tasks.write().await;
+        let mut executors = self.executors.write().await;
+
+        tasks.remove(&task_id);
+        executors.remove(&task_id);
+        self.limiter.forget(task_id);
+        self.run_summaries.write().await.remove(&task_id);
+
+        Ok(())
+    }
+
+    async fn pause_task(&self, task_id: Uuid) -> Result<()> {
+        let mut tasks = self.tasks.write().await;
+        let task = tasks
+            .get_mut(&task_id)
+            .ok_or(SchedulerError::JobNotFound(task_id))?;
+
+        task.status = TaskStatus::Paused;
+        task.enabled = false;
+
+        Ok(())
+    }
+
+    async fn resume_task(&self, task_id: Uuid) -> Result<()> {
+        let mut tasks = self.tasks.write().await;
+        let task = tasks
+            .get_mut(&task_id)
+            .ok_or(SchedulerError::JobNotFound(task_id))?;
+
+        task.status = TaskStatus::Pending;
+        task.enabled = true;
+        // 暂停期间的计划运行不算错过
+        task.last_scheduled_run = Some(Utc::now());
+
+        Ok(())
+    }
+
+    async fn update_task(&self, request: TaskUpdateRequest) -> Result<ScheduledTask> {
+        let cron_changed = request.cron_expression.is_some() || request.timezone.is_some();
+        // 先按新动作重建执行器，无法还原的动作不写入任务
+        let executor = request
+            .action
+            .as_ref()
+            .map(|action| self.registry.resolve(action))
+            .transpose()?;
+        let task = self.apply_update(request).await?;
+        let has_job = self.jobs.read().await.contains_key(&task.id);
+        let action_changed = executor.is_some();
+        if let Some(executor) = executor {
+            self.executors.write().await.insert(task.id, executor);
+        }
+        if (cron_changed && has_job) || (action_changed && !has_job) {
+            // 按新的 cron 表达式或时区重新注册作业，只登记了元数据的任务有了执行器后开始调度
+            self.register_job(&task).await?;
+        }
+        Ok(task)
+    }
+
+    async fn get_task(&self, task_id: Uuid) -> Result<ScheduledTask> {
+        let tasks = self.tasks.read().await;
+        tasks
+            .get(&task_id)
+            .cloned()
+            .ok_or(SchedulerError::JobNotFound(task_id))
+    }
+
+    async fn list_tasks(&self) -> Result<Vec<ScheduledTask>> {
+        let tasks = self.tasks.read().await;
+        Ok(tasks.values().cloned().collect())
+    }
+
+    async fn list_running_tasks(&self) -> Result<Vec<ScheduledTask>> {
+        let tasks = self.tasks.read().await;
+        let running: Vec<ScheduledTask> = tasks
+            .values()
+            .filter(|t| t.status == TaskStatus::Running)
             .cloned()
-            .ok_or(SchedulerError::RunInstanceNotFound(instance.id))?;
+            .collect();
+        Ok(running)
+    }
+
+    async fn run_task(
+        &self,
+        task_id: Uuid,
+        user_params: std::collections::HashMap<String, String>,
+    ) -> Result<TaskRunInstance> {
+        // 检查任务是否存在
+        {
+            let tasks = self.tasks.read().await;
+            if !tasks.contains_key(&task_id) {
+                return Err(SchedulerError::JobNotFound(task_id));
+            }
+        }
 
-        Ok(updated_instance)
+        self.run_state().execute(task_id, user_params).await
     }
 
     async fn stop_task(&self, run_instance_id: Uuid) -> Result<()> {
+        // 通知执行器停止，并等待其终止进程、记录结果
+        let finished = {
+            let live_runs = self.live_runs.read().await;
+            live_runs.get(&run_instance_id).map(|run| {
+                let _ = run.stop.send(true);
+                run.finished.clone()
+            })
+        };
+        if let Some(mut finished) = finished {
+            if tokio::time::timeout(STOP_TIMEOUT, finished.wait_for(|done| *done))
+                .await
+                .is_ok()
+            {
+                return Ok(());
+            }
+            tracing::warn!("Run {} did not stop within {:?}", run_instance_id, STOP_TIMEOUT);
+        }
+
+        // 执行器未响应停止：仅标记状态
         let mut instances = self.run_instances.write().await;
         let instance = instances
             .get_mut(&run_instance_id)
@@ -475,10 +1714,7 @@ impl TaskScheduler for CronTaskScheduler {
 
     async fn add_log(&self, run_instance_id: Uuid, level: LogLevel, message: String) -> Result<TaskLog> {
         let log = TaskLog::new(run_instance_id, level, message);
-        let mut logs = self.logs.write().await;
-        logs.entry(run_instance_id)
-            .or_insert_with(Vec::new)
-            .push(log.clone());
+        self.run_state().push_log(log.clone()).await?;
         Ok(log)
     }
 
@@ -500,7 +1736,8 @@ impl TaskScheduler for CronTaskScheduler {
     async fn get_task_briefing(&self, task_id: Uuid) -> Result<TaskBriefing> {
         let task = self.get_task(task_id).await?;
         let instances = self.get_task_instances(task_id).await?;
-        Ok(TaskBriefing::from_task(&task, instances))
+        let summary = self.run_summary(task_id).await?;
+        Ok(TaskBriefing::from_task(&task, instances).with_history(summary))
     }
 
     async fn clear_all_tasks(&self) -> Result<usize> {
@@ -511,6 +1748,10 @@ impl TaskScheduler for CronTaskScheduler {
         };
 
         // 清空所有数据
+        let task_ids: Vec<Uuid> = self.jobs.read().await.keys().copied().collect();
+        for task_id in task_ids {
+            self.unregister_job(task_id).await?;
+        }
         {
             let mut tasks = self.tasks.write().await;
             tasks.clear();
@@ -527,8 +1768,167 @@ impl TaskScheduler for CronTaskScheduler {
             let mut logs = self.logs.write().await;
             logs.clear();
         }
+        self.run_summaries.write().await.clear();
+        {
+            let mut workflows = self.workflows.write().await;
+            workflows.clear();
+        }
+        {
+            let mut runs = self.workflow_runs.write().await;
+            runs.clear();
+        }
+
+        Ok(count)
+    }
+
+    async fn prune_history(&self, policy: RetentionPolicy, dry_run: bool) -> Result<PruneReport> {
+        // 合并内存和存储中的实例，包含其他进程记录的运行
+        let mut instances = self.run_instances.read().await.clone();
+        if let Some(storage) = &self.storage {
+            let stored = storage
+                .list_all_run_instances()
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+            for instance in stored {
+                instances.entry(instance.id).or_insert(instance);
+            }
+        }
+        let log_usage = self.log_usage().await?;
+        let sizes: HashMap<Uuid, u64> = instances
+            .values()
+            .map(|i| (i.id, serialized_len(i) + log_usage.get(&i.id).map_or(0, |u| u.1)))
+            .collect();
+
+        // 本进程正在运行的实例不清理
+        let live: HashSet<Uuid> = self.live_runs.read().await.keys().copied().collect();
+        let selected = policy.select(
+            instances.values().filter(|i| !live.contains(&i.id)),
+            &sizes,
+            Utc::now(),
+        );
+
+        let mut report = PruneReport {
+            dry_run,
+            ..Default::default()
+        };
+        let mut summaries: HashMap<Uuid, RunHistorySummary> = HashMap::new();
+        for instance_id in selected {
+            let instance = &instances[&instance_id];
+            let summary = match summaries.entry(instance.task_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(
+                    self.run_summary(instance.task_id)
+                        .await?
+                        .unwrap_or_else(|| RunHistorySummary::new(instance.task_id)),
+                ),
+            };
+            summary.absorb(instance);
+            report.runs += 1;
+            report.logs += log_usage.get(&instance_id).map_or(0, |u| u.0);
+            report.bytes += sizes.get(&instance_id).copied().unwrap_or(0);
+
+            if !dry_run {
+                if let Some(storage) = &self.storage {
+                    storage
+                        .delete_run_instance(instance_id)
+                        .await
+                        .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+                }
+                self.run_instances.write().await.remove(&instance_id);
+                self.logs.write().await.remove(&instance_id);
+            }
+        }
+
+        report.tasks = summaries.len();
+        if !dry_run {
+            for summary in summaries.into_values() {
+                self.save_run_summary(summary).await?;
+            }
+        }
+        Ok(report)
+    }
+
+    async fn export_bundle(&self, _options: ExportOptions) -> Result<ScheduleBundle> {
+        // 任务定义只保存在内存中，迁移包由持久化调度器通过存储导出
+        Err(SchedulerError::StorageError(
+            "Schedule bundles require a persistent scheduler".to_string(),
+        ))
+    }
+
+    async fn import_bundle(&self, _bundle: ScheduleBundle, _options: ImportOptions) -> Result<ImportReport> {
+        Err(SchedulerError::StorageError(
+            "Schedule bundles require a persistent scheduler".to_string(),
+        ))
+    }
+
+    async fn add_workflow(&self, mut workflow: Workflow) -> Result<Workflow> {
+        workflow.timezone = match workflow.timezone.as_deref() {
+            Some(timezone) => normalize_timezone(timezone)?,
+            None => self.new_task_timezone(),
+        };
+        // 新建的工作流按当前语法解释，定义文件中不必填写语法版本
+        workflow.cron_version = CRON_VERSION;
+        {
+            let tasks = self.tasks.read().await;
+            if let Some(step) = workflow.steps.iter().find(|s| !tasks.contains_key(&s.task_id)) {
+                return Err(SchedulerError::JobNotFound(step.task_id));
+            }
+        }
+        self.schedule_workflow(workflow).await
+    }
+
+    async fn remove_workflow(&self, workflow_id: Uuid) -> Result<()> {
+        if !self.workflows.read().await.contains_key(&workflow_id) {
+            return Err(SchedulerError::WorkflowNotFound(workflow_id));
+        }
+        self.unregister_job(workflow_id).await?;
+        self.workflows.write().await.remove(&workflow_id);
+        self.workflow_runs
+            .write()
+            .await
+            .retain(|_, r| r.workflow_id != workflow_id);
+        Ok(())
+    }
 
-        Ok(count)
+    async fn get_workflow(&self, workflow_id: Uuid) -> Result<Workflow> {
+        let workflows = self.workflows.read().await;
+        workflows
+            .get(&workflow_id)
+            .cloned()
+            .ok_or(SchedulerError::WorkflowNotFound(workflow_id))
+    }
+
+    async fn list_workflows(&self) -> Result<Vec<Workflow>> {
+        let workflows = self.workflows.read().await;
+        Ok(workflows.values().cloned().collect())
+    }
+
+    async fn run_workflow(
+        &self,
+        workflow_id: Uuid,
+        user_params: HashMap<String, String>,
+    ) -> Result<WorkflowRun> {
+        let workflow = self.get_workflow(workflow_id).await?;
+        self.run_state().execute_workflow(&workflow, user_params).await
+    }
+
+    async fn get_workflow_run(&self, run_id: Uuid) -> Result<WorkflowRun> {
+        let runs = self.workflow_runs.read().await;
+        runs.get(&run_id)
+            .cloned()
+            .ok_or(SchedulerError::WorkflowRunNotFound(run_id))
+    }
+
+    async fn get_workflow_briefing(&self, workflow_id: Uuid) -> Result<WorkflowBriefing> {
+        let workflow = self.get_workflow(workflow_id).await?;
+        let runs: Vec<WorkflowRun> = {
+            let runs = self.workflow_runs.read().await;
+            runs.values()
+                .filter(|r| r.workflow_id == workflow_id)
+                .cloned()
+                .collect()
+        };
+        Ok(WorkflowBriefing::from_workflow(&workflow, runs))
     }
 
     async fn start(&self) -> Result<()> {
@@ -566,33 +1966,37 @@ impl TaskScheduler for CronTaskScheduler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::executor_fn;
     use std::collections::HashMap;
     use std::sync::atomic::{AtomicU32, Ordering};
     use tokio::time::{sleep, Duration};
 
     /// 创建测试用的任务执行器
     fn create_test_executor(counter: Arc<AtomicU32>) -> crate::scheduler::AsyncTaskExecutor {
-        Arc::new(move |_task_id, _params: HashMap<String, String>| {
-            let counter = counter.clone();
+        executor_fn(move |_task_id, _params, _ctx| {
             counter.fetch_add(1, Ordering::SeqCst);
-            Ok(TaskExecutionResult {
-                task_id: Uuid::new_v4(),
-                run_instance_id: None,
-                started_at: Utc::now(),
-                completed_at: Some(Utc::now()),
-                success: true,
-                error: None,
-                stdout: None,
-                stderr: None,
-                exit_code: Some(0),
-            })
+            async move {
+                Ok(TaskExecutionResult {
+                    task_id: Uuid::new_v4(),
+                    run_instance_id: None,
+                    started_at: Utc::now(),
+                    completed_at: Some(Utc::now()),
+                    success: true,
+                    error: None,
+                    stdout: None,
+                    stderr: None,
+                    exit_code: Some(0),
+                    signal: None,
+                    timed_out: false,
+                })
+            }
         })
     }
 
     /// 创建会失败的执行器
     #[allow(dead_code)]
     fn create_failing_executor() -> crate::scheduler::AsyncTaskExecutor {
-        Arc::new(|_task_id, _params: HashMap<String, String>| {
+        executor_fn(|_task_id, _params, _ctx| async {
             Err(SchedulerError::ExecutionError("Test failure".to_string()))
         })
     }
@@ -613,6 +2017,12 @@ mod tests {
         assert!(scheduler.validate_cron("0 */10 * * * *").is_ok());
         assert!(scheduler.validate_cron("0 0 * * * *").is_ok());  // 每天午夜
         assert!(scheduler.validate_cron("0 0 * * 1 *").is_ok());  // 每周一午夜
+        // 5 字段、7 字段、宏和 L/W/#
+        assert!(scheduler.validate_cron("30 9 * * MON-FRI").is_ok());
+        assert!(scheduler.validate_cron("0 0 12 1 1 * 2030").is_ok());
+        assert!(scheduler.validate_cron("@daily").is_ok());
+        assert!(scheduler.validate_cron("0 0 LW * *").is_ok());
+        assert!(scheduler.validate_cron("0 0 * * 5#3").is_ok());
     }
 
     #[tokio::test]
@@ -623,6 +2033,8 @@ mod tests {
         assert!(scheduler.validate_cron("").is_err());
         assert!(scheduler.validate_cron("* * *").is_err());
         assert!(scheduler.validate_cron("invalid").is_err());
+        assert!(scheduler.validate_cron("@reboot").is_err());
+        assert!(scheduler.validate_cron("0 0 L * * * 2030 1").is_err());
     }
 
     #[tokio::test]
@@ -754,6 +2166,43 @@ mod tests {
         assert_eq!(tasks.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_max_tasks() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        scheduler.set_max_tasks(1);
+
+        let counter = Arc::new(AtomicU32::new(0));
+        let task = scheduler
+            .add_task("Task 1".to_string(), "task1".to_string(), "0 * * * * *".to_string(), create_test_executor(counter.clone()))
+            .await
+            .unwrap();
+        let result = scheduler
+            .add_task_with_action(
+                "Task 2".to_string(),
+                "task2".to_string(),
+                None,
+                None,
+                "0 * * * * *".to_string(),
+                TaskAction::shell("echo hi"),
+                false,
+            )
+            .await;
+        assert!(matches!(result, Err(SchedulerError::InvalidParameter(_))));
+
+        // 删除后腾出名额，0 表示不限制
+        scheduler.remove_task(task.id).await.unwrap();
+        scheduler
+            .add_task("Task 3".to_string(), "task3".to_string(), "0 * * * * *".to_string(), create_test_executor(counter.clone()))
+            .await
+            .unwrap();
+        scheduler.set_max_tasks(0);
+        scheduler
+            .add_task("Task 4".to_string(), "task4".to_string(), "0 * * * * *".to_string(), create_test_executor(counter))
+            .await
+            .unwrap();
+        assert_eq!(scheduler.list_tasks().await.unwrap().len(), 2);
+    }
+
     #[tokio::test]
     async fn test_pause_task() {
         let scheduler = CronTaskScheduler::new().await.unwrap();
@@ -777,6 +2226,77 @@ mod tests {
         assert!(!paused_task.enabled);
     }
 
+    #[tokio::test]
+    async fn test_cron_fires_only_active_tasks() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let active = Arc::new(AtomicU32::new(0));
+        let paused = Arc::new(AtomicU32::new(0));
+        let removed = Arc::new(AtomicU32::new(0));
+
+        let every_second = || "* * * * * *".to_string();
+        scheduler
+            .add_task("Active".to_string(), "active".to_string(), every_second(), create_test_executor(active.clone()))
+            .await
+            .unwrap();
+        let paused_task = scheduler
+            .add_task("Paused".to_string(), "paused".to_string(), every_second(), create_test_executor(paused.clone()))
+            .await
+            .unwrap();
+        let removed_task = scheduler
+            .add_task("Removed".to_string(), "removed".to_string(), every_second(), create_test_executor(removed.clone()))
+            .await
+            .unwrap();
+        scheduler.pause_task(paused_task.id).await.unwrap();
+        scheduler.remove_task(removed_task.id).await.unwrap();
+
+        scheduler.start().await.unwrap();
+        sleep(Duration::from_millis(2500)).await;
+        scheduler.stop().await.unwrap();
+
+        assert!(active.load(Ordering::SeqCst) >= 1);
+        assert_eq!(paused.load(Ordering::SeqCst), 0);
+        assert_eq!(removed.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_zoned_task_fires_and_rearms() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        scheduler.set_default_timezone("Asia/Shanghai".parse().unwrap());
+        let counter = Arc::new(AtomicU32::new(0));
+
+        let task = scheduler
+            .add_task("Zoned".to_string(), "zoned".to_string(), "* * * * * *".to_string(), create_test_executor(counter.clone()))
+            .await
+            .unwrap();
+        assert_eq!(task.timezone.as_deref(), Some("Asia/Shanghai"));
+
+        scheduler.start().await.unwrap();
+        sleep(Duration::from_millis(3500)).await;
+        scheduler.stop().await.unwrap();
+
+        // 单次作业每次触发后重新注册，连续触发多次
+        assert!(counter.load(Ordering::SeqCst) >= 2);
+
+        // 设为 UTC 时清除时区
+        let task = scheduler
+            .update_task(TaskUpdateRequest {
+                id: task.id,
+                timezone: Some("UTC".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(task.timezone, None);
+        let result = scheduler
+            .update_task(TaskUpdateRequest {
+                id: task.id,
+                timezone: Some("Mars/Olympus".to_string()),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn test_resume_task() {
         let scheduler = CronTaskScheduler::new().await.unwrap();
@@ -921,6 +2441,8 @@ await.unwrap();
             stdout: Some("output".to_string()),
             stderr: None,
             exit_code: Some(0),
+            signal: None,
+            timed_out: false,
         };
         assert!(result.success);
         assert!(result.error.is_none());
@@ -944,21 +2466,270 @@ await.unwrap();
             .await
             .unwrap();
 
-        assert_eq!(task.title, "Complex Task");
-        assert_eq!(task.name, "complex_task");
-        assert_eq!(task.description, Some("A complex task".to_string()));
-        assert_eq!(task.content, Some("echo hello".to_string()));
-        assert_eq!(task.cron_expression, "0 */10 * * * *");
-        assert_eq!(task.status, TaskStatus::Pending);
-        assert!(task.created_at <= Utc::now());
-        assert!(task.last_run.is_none());
-        assert!(task.next_run.is_none());
-        assert_eq!(task.run_count, 0);
-        assert!(task.enabled);
+        assert_eq!(task.title, "Complex Task");
+        assert_eq!(task.name, "complex_task");
+        assert_eq!(task.description, Some("A complex task".to_string()));
+        assert_eq!(task.content, Some("echo hello".to_string()));
+        assert_eq!(task.cron_expression, "0 */10 * * * *");
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert!(task.created_at <= Utc::now());
+        assert!(task.last_run.is_none());
+        assert!(task.next_run.is_some());
+        assert_eq!(task.run_count, 0);
+        assert!(task.enabled);
+    }
+
+    #[tokio::test]
+    async fn test_run_task() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let counter = Arc::new(AtomicU32::new(0));
+        let executor = create_test_executor(counter);
+
+        let task = scheduler
+            .add_task(
+                "Test Task".to_string(),
+                "test_task".to_string(),
+                "0 * * * * *".to_string(),
+                executor,
+            )
+            .await
+            .unwrap();
+
+        // 手动运行任务
+        let mut params = HashMap::new();
+        params.insert("user".to_string(), "testuser".to_string());
+
+        let instance = scheduler.run_task(task.id, params).await.unwrap();
+
+        assert_eq!(instance.task_id, task.id);
+        assert_eq!(instance.status, TaskStatus::Completed);
+        assert!(instance.completed_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_run_nonexistent_task() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+
+        let result = scheduler
+            .run_task(Uuid::new_v4(), HashMap::new())
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_task_briefing() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let counter = Arc::new(AtomicU32::new(0));
+        let executor = create_test_executor(counter);
+
+        let task = scheduler
+            .add_task(
+                "Test Task".to_string(),
+                "test_task".to_string(),
+                "0 * * * * *".to_string(),
+                executor,
+            )
+            .await
+            .unwrap();
+
+        let briefing = scheduler.get_task_briefing(task.id).await.unwrap();
+
+        assert_eq!(briefing.task_id, task.id);
+        assert_eq!(briefing.title, "Test Task");
+    }
+
+    #[tokio::test]
+    async fn test_task_logs() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let counter = Arc::new(AtomicU32::new(0));
+        let executor = create_test_executor(counter);
+
+        let task = scheduler
+            .add_task(
+                "Test Task".to_string(),
+                "test_task".to_string(),
+                "0 * * * * *".to_string(),
+                executor,
+            )
+            .await
+            .unwrap();
+
+        // 运行任务生成日志
+        let instance = scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+
+        // 添加日志
+        scheduler
+            .add_log(instance.id, LogLevel::Info, "Test log message".to_string())
+            .await
+            .unwrap();
+
+        // 获取日志
+        let logs = scheduler.get_instance_logs(instance.id, None).await.unwrap();
+        assert!(!logs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_task_records_output_logs() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let executor = executor_fn(|task_id, _params, ctx: TaskRunContext| async move {
+            ctx.stdout("line 1");
+            ctx.stderr("warning 1");
+            ctx.stdout("line 2");
+            Ok(TaskExecutionResult::success(task_id, String::new(), String::new(), 0))
+        });
+
+        let task = scheduler
+            .add_task(
+                "Output Task".to_string(),
+                "output_task".to_string(),
+                "0 * * * * *".to_string(),
+                executor,
+            )
+            .await
+            .unwrap();
+
+        let instance = scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+        let logs = scheduler.get_instance_logs(instance.id, None).await.unwrap();
+        let messages: Vec<&str> = logs.iter().map(|l| l.message.as_str()).collect();
+
+        // 开始日志 + 3 行输出 + 完成日志，顺序与输出一致
+        assert_eq!(messages.len(), 5);
+        assert_eq!(&messages[1..4], &["line 1", "warning 1", "line 2"]);
+        assert_eq!(logs[2].level, LogLevel::Warn);
+    }
+
+    #[tokio::test]
+    async fn test_stop_running_task() {
+        let scheduler = Arc::new(CronTaskScheduler::new().await.unwrap());
+        let executor = executor_fn(|task_id, _params, ctx: TaskRunContext| async move {
+            ctx.process_started(4242);
+            ctx.cancelled().await;
+            let mut result = TaskExecutionResult::failure(task_id, "terminated".to_string());
+            result.signal = Some(15);
+            Ok(result)
+        });
+
+        let task = scheduler
+            .add_task(
+                "Long Task".to_string(),
+                "long_task".to_string(),
+                "0 * * * * *".to_string(),
+                executor,
+            )
+            .await
+            .unwrap();
+
+        let run = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move { scheduler.run_task(task.id, HashMap::new()).await })
+        };
+
+        // 等待运行实例出现
+        let instance_id = loop {
+            let instances = scheduler.get_task_instances(task.id).await.unwrap();
+            if let Some(inst) = instances.first() {
+                break inst.id;
+            }
+            sleep(Duration::from_millis(10)).await;
+        };
+
+        scheduler.stop_task(instance_id).await.unwrap();
+        let instance = run.await.unwrap().unwrap();
+
+        assert_eq!(instance.id, instance_id);
+        assert_eq!(instance.status, TaskStatus::Failed);
+        assert_eq!(instance.pid, Some(4242));
+        let result = instance.result.unwrap();
+        assert_eq!(result.signal, Some(15));
+        assert_eq!(result.error.as_deref(), Some("Task stopped by user (signal 15)"));
+        assert!(!scheduler.is_live(instance_id).await);
+        assert!(scheduler.stop_task(instance_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_long_task_does_not_block_others() {
+        // 单线程运行时下，长任务等待期间其他任务仍可执行
+        let scheduler = Arc::new(CronTaskScheduler::new().await.unwrap());
+        let slow = executor_fn(|task_id, _params, ctx: TaskRunContext| async move {
+            ctx.cancelled().await;
+            Ok(TaskExecutionResult::success(task_id, String::new(), String::new(), 0))
+        });
+        let counter = Arc::new(AtomicU32::new(0));
+
+        let slow_task = scheduler
+            .add_task("Slow".to_string(), "slow".to_string(), "0 * * * * *".to_string(), slow)
+            .await
+            .unwrap();
+        let fast_task = scheduler
+            .add_task(
+                "Fast".to_string(),
+                "fast".to_string(),
+                "0 * * * * *".to_string(),
+                create_test_executor(counter.clone()),
+            )
+            .await
+            .unwrap();
+
+        let slow_run = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move { scheduler.run_task(slow_task.id, HashMap::new()).await })
+        };
+        sleep(Duration::from_millis(50)).await;
+
+        let fast = scheduler.run_task(fast_task.id, HashMap::new()).await.unwrap();
+        assert_eq!(fast.status, TaskStatus::Completed);
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        let slow_instance = scheduler.get_task_instances(slow_task.id).await.unwrap().remove(0);
+        assert_eq!(slow_instance.status, TaskStatus::Running);
+        scheduler.stop_task(slow_instance.id).await.unwrap();
+        assert_eq!(slow_run.await.unwrap().unwrap().status, TaskStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_update_task() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let counter = Arc::new(AtomicU32::new(0));
+        let executor = create_test_executor(counter);
+
+        let task = scheduler
+            .add_task(
+                "Original Title".to_string(),
+                "test_task".to_string(),
+                "0 * * * * *".to_string(),
+                executor,
+            )
+            .await
+            .unwrap();
+
+        // 更新任务
+        let request = TaskUpdateRequest {
+            id: task.id,
+            title: Some("Updated Title".to_string()),
+            description: Some("New description".to_string()),
+            content: None,
+            cron_expression: None,
+            enabled: None,
+            ..Default::default()
+        };
+
+        let updated = scheduler.update_task(request).await.unwrap();
+        assert_eq!(updated.title, "Updated Title");
+        assert_eq!(updated.description, Some("New description".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_list_running_tasks() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+
+        // 初始没有运行中的任务
+        let running = scheduler.list_running_tasks().await.unwrap();
+        assert!(running.is_empty());
     }
 
     #[tokio::test]
-    async fn test_run_task() {
+    async fn test_get_task_instances() {
         let scheduler = CronTaskScheduler::new().await.unwrap();
         let counter = Arc::new(AtomicU32::new(0));
         let executor = create_test_executor(counter);
@@ -973,141 +2744,713 @@ await.unwrap();
             .await
             .unwrap();
 
-        // 手动运行任务
-        let mut params = HashMap::new();
-        params.insert("user".to_string(), "testuser".to_string());
+        // 运行任务
+        scheduler.run_task(task.id, HashMap::new()).await.unwrap();
 
-        let instance = scheduler.run_task(task.id, params).await.unwrap();
+        // 获取实例
+        let instances = scheduler.get_task_instances(task.id).await.unwrap();
+        assert_eq!(instances.len(), 1);
+    }
 
-        assert_eq!(instance.task_id, task.id);
-        assert_eq!(instance.status, TaskStatus::Completed);
-        assert!(instance.completed_at.is_some());
+    /// 将任务的上次计划运行时间调到过去，模拟守护进程停止
+    async fn rewind_task(scheduler: &CronTaskScheduler, task_id: Uuid, by: chrono::Duration) {
+        let mut tasks = scheduler.tasks.write().await;
+        let task = tasks.get_mut(&task_id).unwrap();
+        task.last_scheduled_run = Some(Utc::now() - by);
     }
 
     #[tokio::test]
-    async fn test_run_nonexistent_task() {
+    async fn test_catch_up_runs_missed_occurrences() {
         let scheduler = CronTaskScheduler::new().await.unwrap();
+        let counter = Arc::new(AtomicU32::new(0));
+        let task = scheduler
+            .add_task(
+                "Every minute".to_string(),
+                "every_minute".to_string(),
+                "0 * * * * *".to_string(),
+                create_test_executor(counter.clone()),
+            )
+            .await
+            .unwrap();
+        scheduler
+            .update_task(TaskUpdateRequest {
+                id: task.id,
+                title: None,
+                description: None,
+                content: None,
+                cron_expression: None,
+                enabled: None,
+                misfire: Some(crate::misfire::MisfireConfig::new(
+                    crate::misfire::MisfirePolicy::RunAll { limit: 2 },
+                    0,
+                )),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        rewind_task(&scheduler, task.id, chrono::Duration::minutes(10)).await;
 
-        let result = scheduler
-            .run_task(Uuid::new_v4(), HashMap::new())
-            .await;
+        assert_eq!(scheduler.catch_up_missed_runs().await.unwrap(), 2);
+        sleep(Duration::from_millis(200)).await;
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
 
-        assert!(result.is_err());
+        // 错过的其余运行记为过期
+        let briefing = scheduler.get_task_briefing(task.id).await.unwrap();
+        assert!((8..=10).contains(&briefing.expired_count), "{}", briefing.expired_count);
+        let expired = scheduler
+            .get_task_instances(task.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|i| i.status == TaskStatus::Expired)
+            .collect::<Vec<_>>();
+        assert!(expired.iter().all(|i| i.result.as_ref().is_some_and(|r| !r.success)));
+
+        // 已处理的运行不会重复补跑
+        assert_eq!(scheduler.catch_up_missed_runs().await.unwrap(), 0);
     }
 
     #[tokio::test]
-    async fn test_task_briefing() {
+    async fn test_catch_up_skips_paused_tasks() {
         let scheduler = CronTaskScheduler::new().await.unwrap();
         let counter = Arc::new(AtomicU32::new(0));
-        let executor = create_test_executor(counter);
-
         let task = scheduler
             .add_task(
-                "Test Task".to_string(),
-                "test_task".to_string(),
+                "Paused".to_string(),
+                "paused".to_string(),
                 "0 * * * * *".to_string(),
-                executor,
+                create_test_executor(counter.clone()),
             )
             .await
             .unwrap();
+        rewind_task(&scheduler, task.id, chrono::Duration::minutes(10)).await;
+        scheduler.pause_task(task.id).await.unwrap();
+        assert_eq!(scheduler.catch_up_missed_runs().await.unwrap(), 0);
 
+        // 恢复后暂停期间的运行不算错过
+        scheduler.resume_task(task.id).await.unwrap();
+        assert_eq!(scheduler.catch_up_missed_runs().await.unwrap(), 0);
         let briefing = scheduler.get_task_briefing(task.id).await.unwrap();
-
-        assert_eq!(briefing.task_id, task.id);
-        assert_eq!(briefing.title, "Test Task");
+        assert_eq!(briefing.expired_count, 0);
     }
 
     #[tokio::test]
-    async fn test_task_logs() {
+    async fn test_cron_fire_records_missed_runs() {
         let scheduler = CronTaskScheduler::new().await.unwrap();
         let counter = Arc::new(AtomicU32::new(0));
-        let executor = create_test_executor(counter);
+        let task = scheduler
+            .add_task(
+                "Every second".to_string(),
+                "every_second".to_string(),
+                "* * * * * *".to_string(),
+                create_test_executor(counter.clone()),
+            )
+            .await
+            .unwrap();
+        rewind_task(&scheduler, task.id, chrono::Duration::hours(1)).await;
 
+        scheduler.start().await.unwrap();
+        sleep(Duration::from_millis(1500)).await;
+        scheduler.stop().await.unwrap();
+
+        // 默认策略跳过错过的运行，只执行本次触发；过期记录有上限
+        let runs = counter.load(Ordering::SeqCst);
+        assert!((1..=2).contains(&runs), "{}", runs);
+        let briefing = scheduler.get_task_briefing(task.id).await.unwrap();
+        assert_eq!(briefing.expired_count, crate::misfire::MAX_EXPIRED_RECORDS);
+        assert!(briefing.recent_instances.len() <= 5);
+    }
+
+    /// 不带抖动、立即重试的策略
+    fn immediate_retry(max_attempts: u32, retry_on: &str) -> crate::retry::RetryPolicy {
+        crate::retry::RetryPolicy {
+            max_attempts,
+            backoff: crate::retry::Backoff::Fixed { delay_secs: 0 },
+            jitter: false,
+            retry_on: retry_on.parse().unwrap(),
+        }
+    }
+
+    async fn set_retry(scheduler: &CronTaskScheduler, task_id: Uuid, retry: crate::retry::RetryPolicy) {
+        scheduler
+            .update_task(TaskUpdateRequest {
+                id: task_id,
+                retry: Some(retry),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retry_links_attempts() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        // 前两次以非零状态退出，第三次成功
+        let executor = executor_fn(move |task_id, _params, _ctx| {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let exit_code = if call < 2 { 1 } else { 0 };
+                Ok(TaskExecutionResult::success(task_id, String::new(), String::new(), exit_code))
+            }
+        });
+        let task = scheduler
+            .add_task("Flaky".to_string(), "flaky".to_string(), "0 0 * * * *".to_string(), executor)
+            .await
+            .unwrap();
+        set_retry(&scheduler, task.id, immediate_retry(5, "exit")).await;
+
+        let instance = scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Completed);
+        assert_eq!(instance.attempt, 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let instances = scheduler.get_task_instances(task.id).await.unwrap();
+        assert_eq!(instances.len(), 3);
+        let logical_id = instance.logical_id();
+        assert!(instances.iter().all(|i| i.logical_id() == logical_id));
+        assert_eq!(
+            instances.iter().filter(|i| i.status == TaskStatus::Failed).count(),
+            2
+        );
+
+        // 重试不计入运行次数
+        let briefing = scheduler.get_task_briefing(task.id).await.unwrap();
+        assert_eq!(briefing.run_count, 1);
+        assert_eq!(briefing.latest_attempts, 3);
+        assert_eq!(briefing.recent_instances[0].attempt, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_respects_policy() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
         let task = scheduler
             .add_task(
-                "Test Task".to_string(),
-                "test_task".to_string(),
-                "0 * * * * *".to_string(),
-                executor,
+                "Broken".to_string(),
+                "broken".to_string(),
+                "0 0 * * * *".to_string(),
+                create_failing_executor(),
             )
             .await
             .unwrap();
 
-        // 运行任务生成日志
+        // 执行器错误不在重试条件内
+        set_retry(&scheduler, task.id, immediate_retry(3, "exit,timeout")).await;
         let instance = scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Error);
+        assert_eq!(instance.attempt, 1);
 
-        // 添加日志
+        // 用尽尝试次数后停止
+        set_retry(&scheduler, task.id, immediate_retry(2, "error")).await;
+        let instance = scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Error);
+        assert_eq!(instance.attempt, 2);
+        let logs = scheduler
+            .get_instance_logs(instance.logical_id(), Some(LogLevel::Warn))
+            .await
+            .unwrap();
+        assert!(logs.iter().any(|l| l.message.starts_with("Attempt 1/2 failed (error)")));
+    }
+
+    /// 输出 "<标签>:<排序后的参数>" 的执行器，exit_code 非零时失败
+    fn echo_executor(label: &'static str, exit_code: i32) -> crate::scheduler::AsyncTaskExecutor {
+        executor_fn(move |task_id, params, _ctx| async move {
+            let mut pairs: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            pairs.sort();
+            let stdout = format!("{}:{}\n", label, pairs.join(","));
+            Ok(TaskExecutionResult::success(task_id, stdout, String::new(), exit_code))
+        })
+    }
+
+    async fn add_echo_task(scheduler: &CronTaskScheduler, label: &'static str, exit_code: i32) -> Uuid {
         scheduler
-            .add_log(instance.id, LogLevel::Info, "Test log message".to_string())
+            .add_task(
+                label.to_string(),
+                label.to_string(),
+                "0 0 0 1 1 *".to_string(),
+                echo_executor(label, exit_code),
+            )
+            .await
+            .unwrap()
+            .id
+    }
+
+    #[tokio::test]
+    async fn test_workflow_fan_out_fan_in() {
+        use crate::workflow::WorkflowStep;
+
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let fetch = add_echo_task(&scheduler, "fetch", 0).await;
+        let left = add_echo_task(&scheduler, "left", 0).await;
+        let right = add_echo_task(&scheduler, "right", 0).await;
+        let publish = add_echo_task(&scheduler, "publish", 0).await;
+
+        let workflow = scheduler
+            .add_workflow(Workflow::new(
+                "etl",
+                vec![
+                    WorkflowStep::new("fetch", fetch),
+                    WorkflowStep::new("left", left).after(&["fetch"]),
+                    WorkflowStep::new("right", right).after(&["fetch"]),
+                    WorkflowStep::new("publish", publish).after(&["left", "right"]),
+                ],
+            ))
             .await
             .unwrap();
 
-        // 获取日志
+        let params = HashMap::from([("day".to_string(), "mon".to_string())]);
+        let run = scheduler.run_workflow(workflow.id, params).await.unwrap();
+        assert_eq!(run.status, TaskStatus::Completed);
+        assert!(run.steps.iter().all(|s| s.run_instance_id.is_some()));
+
+        let output = |name: &str| run.step(name).unwrap().output.clone().unwrap();
+        assert_eq!(output("fetch"), "fetch:day=mon");
+        assert_eq!(output("left"), "left:day=mon,fetch_output=fetch:day=mon");
+        assert_eq!(
+            output("publish"),
+            "publish:day=mon,left_output=left:day=mon,fetch_output=fetch:day=mon,right_output=right:day=mon,fetch_output=fetch:day=mon"
+        );
+
+        // 步骤的运行实例与普通运行一样记录在任务上
+        let instance_id = run.step("publish").unwrap().run_instance_id.unwrap();
+        let instance = scheduler.get_run_instance(instance_id).await.unwrap();
+        assert_eq!(instance.task_id, publish);
+
+        let briefing = scheduler.get_workflow_briefing(workflow.id).await.unwrap();
+        assert_eq!(briefing.run_count, 1);
+        assert_eq!(briefing.latest_run().unwrap().id, run.id);
+        assert_eq!(scheduler.get_workflow_run(run.id).await.unwrap().status, TaskStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_workflow_failure_skips_downstream() {
+        use crate::workflow::WorkflowStep;
+
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let fetch = add_echo_task(&scheduler, "fetch", 0).await;
+        let broken = add_echo_task(&scheduler, "broken", 2).await;
+        let other = add_echo_task(&scheduler, "other", 0).await;
+        let publish = add_echo_task(&scheduler, "publish", 0).await;
+
+        let workflow = scheduler
+            .add_workflow(Workflow::new(
+                "etl",
+                vec![
+                    WorkflowStep::new("fetch", fetch),
+                    WorkflowStep::new("broken", broken).after(&["fetch"]),
+                    WorkflowStep::new("other", other).after(&["fetch"]),
+                    WorkflowStep::new("publish", publish).after(&["broken", "other"]),
+                ],
+            ))
+            .await
+            .unwrap();
+
+        let run = scheduler.run_workflow(workflow.id, HashMap::new()).await.unwrap();
+        assert_eq!(run.status, TaskStatus::Failed);
+        assert_eq!(run.step("broken").unwrap().status, TaskStatus::Failed);
+        assert_eq!(run.step("other").unwrap().status, TaskStatus::Completed);
+        assert_eq!(run.step("publish").unwrap().status, TaskStatus::Skipped);
+        assert!(scheduler.get_task_instances(publish).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_workflow_requires_known_tasks() {
+        use crate::workflow::WorkflowStep;
+
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let missing = Uuid::new_v4();
+        let result = scheduler
+            .add_workflow(Workflow::new("etl", vec![WorkflowStep::new("fetch", missing)]))
+            .await;
+        assert!(matches!(result, Err(SchedulerError::JobNotFound(id)) if id == missing));
+    }
+
+    #[tokio::test]
+    async fn test_workflow_uses_timezone() {
+        use crate::workflow::WorkflowStep;
+        use chrono::Timelike;
+
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        scheduler.set_default_timezone("Asia/Shanghai".parse().unwrap());
+        let fetch = add_echo_task(&scheduler, "fetch", 0).await;
+        let mut workflow = Workflow::new("daily", vec![WorkflowStep::new("fetch", fetch)]);
+        workflow.cron_expression = Some("0 0 9 * * *".to_string());
+
+        // 未填写时区的新工作流使用默认时区
+        let workflow = scheduler.add_workflow(workflow).await.unwrap();
+        assert_eq!(workflow.timezone.as_deref(), Some("Asia/Shanghai"));
+
+        // 作业按上海时间 9 点 (UTC 1 点) 触发
+        let job_id = scheduler.jobs.read().await[&workflow.id];
+        let next = scheduler
+            .scheduler
+            .lock()
+            .await
+            .next_tick_for_job(job_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let expected = ZonedSchedule::parse("0 0 9 * * *", Some("Asia/Shanghai"))
+            .unwrap()
+            .next_after(Utc::now())
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(expected.hour(), 1);
+        assert!((next - expected).num_seconds().abs() <= 1);
+
+        // 填写的时区按任务的规则规范化，无效时区被拒绝
+        let mut utc = Workflow::new("utc", vec![WorkflowStep::new("fetch", fetch)]);
+        utc.timezone = Some("UTC".to_string());
+        assert_eq!(scheduler.add_workflow(utc).await.unwrap().timezone, None);
+        let mut invalid = Workflow::new("invalid", vec![WorkflowStep::new("fetch", fetch)]);
+        invalid.timezone = Some("Mars/Olympus".to_string());
+        assert!(scheduler.add_workflow(invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_run_waits_for_dependency() {
+        let scheduler = CronTaskScheduler::new().await.unwrap();
+        let upstream = add_echo_task(&scheduler, "upstream", 0).await;
+        let downstream = add_echo_task(&scheduler, "downstream", 0).await;
+        scheduler
+            .update_task(TaskUpdateRequest {
+                id: downstream,
+                depends_on: Some(vec![TaskDependency::new(upstream)]),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // 上游没有成功运行过：定时触发被跳过
+        let state = scheduler.run_state();
+        let instance = state.execute_scheduled(downstream).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Skipped);
         let logs = scheduler.get_instance_logs(instance.id, None).await.unwrap();
-        assert!(!logs.is_empty());
+        assert!(logs[0].message.contains("skipped"));
+
+        scheduler.run_task(upstream, HashMap::new()).await.unwrap();
+        let instance = state.execute_scheduled(downstream).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Completed);
+
+        let briefing = scheduler.get_task_briefing(downstream).await.unwrap();
+        assert_eq!(briefing.skipped_count, 1);
+        assert_eq!(briefing.depends_on, vec![TaskDependency::new(upstream)]);
     }
 
     #[tokio::test]
-    async fn test_update_task() {
+    async fn test_dependency_validation() {
         let scheduler = CronTaskScheduler::new().await.unwrap();
-        let counter = Arc::new(AtomicU32::new(0));
-        let executor = create_test_executor(counter);
+        let a = add_echo_task(&scheduler, "a", 0).await;
+        let b = add_echo_task(&scheduler, "b", 0).await;
+        let depend = |id: Uuid, on: Uuid| TaskUpdateRequest {
+            id,
+            depends_on: Some(vec![TaskDependency::new(on)]),
+            ..Default::default()
+        };
+
+        scheduler.update_task(depend(b, a)).await.unwrap();
+        // 形成环
+        assert!(scheduler.update_task(depend(a, b)).await.is_err());
+        assert!(scheduler.update_task(depend(a, a)).await.is_err());
+        // 依赖不存在的任务
+        let missing = Uuid::new_v4();
+        assert!(matches!(
+            scheduler.update_task(depend(a, missing)).await,
+            Err(SchedulerError::JobNotFound(id)) if id == missing
+        ));
+        assert!(scheduler.get_task(a).await.unwrap().depends_on.is_empty());
+    }
+
+    /// 记录同时运行数峰值的执行器；参数 block 存在时一直运行到被停止
+    fn tracking_executor(active: Arc<AtomicU32>, peak: Arc<AtomicU32>) -> crate::scheduler::AsyncTaskExecutor {
+        executor_fn(move |task_id, params, ctx: TaskRunContext| {
+            let active = active.clone();
+            let peak = peak.clone();
+            async move {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                if params.contains_key("block") {
+                    ctx.cancelled().await;
+                } else {
+                    sleep(Duration::from_millis(50)).await;
+                }
+                active.fetch_sub(1, Ordering::SeqCst);
+                if ctx.is_cancelled() {
+                    return Ok(TaskExecutionResult::failure(task_id, "terminated".to_string()));
+                }
+                Ok(TaskExecutionResult::success(task_id, String::new(), String::new(), 0))
+            }
+        })
+    }
 
+    async fn add_tracked_task(
+        scheduler: &CronTaskScheduler,
+        overlap: OverlapPolicy,
+        tags: &[&str],
+        active: Arc<AtomicU32>,
+        peak: Arc<AtomicU32>,
+    ) -> Uuid {
         let task = scheduler
             .add_task(
-                "Original Title".to_string(),
-                "test_task".to_string(),
-                "0 * * * * *".to_string(),
-                executor,
+                "Tracked".to_string(),
+                "tracked".to_string(),
+                "0 0 0 1 1 *".to_string(),
+                tracking_executor(active, peak),
             )
             .await
             .unwrap();
+        scheduler
+            .update_task(TaskUpdateRequest {
+                id: task.id,
+                overlap: Some(overlap),
+                tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        task.id
+    }
 
-        // 更新任务
-        let request = TaskUpdateRequest {
-            id: task.id,
-            title: Some("Updated Title".to_string()),
-            description: Some("New description".to_string()),
-            content: None,
-            cron_expression: None,
-            enabled: None,
+    async fn wait_until_running(scheduler: &CronTaskScheduler, task_id: Uuid) -> Uuid {
+        loop {
+            let instances = scheduler.get_task_instances(task_id).await.unwrap();
+            if let Some(inst) = instances.iter().find(|i| i.status == TaskStatus::Running) {
+                return inst.id;
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    fn blocking_params() -> HashMap<String, String> {
+        HashMap::from([("block".to_string(), "1".to_string())])
+    }
+
+    #[tokio::test]
+    async fn test_overlap_skip_records_skipped_run() {
+        let scheduler = Arc::new(CronTaskScheduler::new().await.unwrap());
+        let (active, peak) = (Arc::new(AtomicU32::new(0)), Arc::new(AtomicU32::new(0)));
+        let task_id = add_tracked_task(&scheduler, OverlapPolicy::Skip, &[], active, peak).await;
+
+        let first = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move { scheduler.run_task(task_id, blocking_params()).await })
         };
+        let running = wait_until_running(&scheduler, task_id).await;
+
+        let skipped = scheduler.run_task(task_id, HashMap::new()).await.unwrap();
+        assert_eq!(skipped.status, TaskStatus::Skipped);
+        let logs = scheduler.get_instance_logs(skipped.id, None).await.unwrap();
+        assert!(logs[0].message.contains("skipped: Previous run is still running"));
+
+        scheduler.stop_task(running).await.unwrap();
+        first.await.unwrap().unwrap();
+        // 上一次运行结束后可以再次运行
+        let next = scheduler.run_task(task_id, HashMap::new()).await.unwrap();
+        assert_eq!(next.status, TaskStatus::Completed);
+    }
 
-        let updated = scheduler.update_task(request).await.unwrap();
-        assert_eq!(updated.title, "Updated Title");
-        assert_eq!(updated.description, Some("New description".to_string()));
+    #[tokio::test]
+    async fn test_overlap_queue_runs_one_at_a_time() {
+        let scheduler = Arc::new(CronTaskScheduler::new().await.unwrap());
+        let (active, peak) = (Arc::new(AtomicU32::new(0)), Arc::new(AtomicU32::new(0)));
+        let task_id = add_tracked_task(&scheduler, OverlapPolicy::Queue, &[], active, peak.clone()).await;
+
+        let mut runs = JoinSet::new();
+        for _ in 0..3 {
+            let scheduler = scheduler.clone();
+            runs.spawn(async move { scheduler.run_task(task_id, HashMap::new()).await });
+        }
+        while let Some(run) = runs.join_next().await {
+            assert_eq!(run.unwrap().unwrap().status, TaskStatus::Completed);
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
     }
 
     #[tokio::test]
-    async fn test_list_running_tasks() {
-        let scheduler = CronTaskScheduler::new().await.unwrap();
+    async fn test_overlap_replace_stops_previous_run() {
+        let scheduler = Arc::new(CronTaskScheduler::new().await.unwrap());
+        let (active, peak) = (Arc::new(AtomicU32::new(0)), Arc::new(AtomicU32::new(0)));
+        let task_id = add_tracked_task(&scheduler, OverlapPolicy::Replace, &[], active, peak.clone()).await;
+
+        let first = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move { scheduler.run_task(task_id, blocking_params()).await })
+        };
+        let replaced = wait_until_running(&scheduler, task_id).await;
 
-        // 初始没有运行中的任务
-        let running = scheduler.list_running_tasks().await.unwrap();
-        assert!(running.is_empty());
+        let second = scheduler.run_task(task_id, HashMap::new()).await.unwrap();
+        assert_eq!(second.status, TaskStatus::Completed);
+        let first = first.await.unwrap().unwrap();
+        assert_eq!(first.id, replaced);
+        assert_eq!(first.status, TaskStatus::Failed);
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+
+        let logs = scheduler.get_instance_logs(replaced, None).await.unwrap();
+        assert!(logs.iter().any(|l| l.message.contains("replaced by a newer run")));
     }
 
     #[tokio::test]
-    async fn test_get_task_instances() {
-        let scheduler = CronTaskScheduler::new().await.unwrap();
-        let counter = Arc::new(AtomicU32::new(0));
-        let executor = create_test_executor(counter);
+    async fn test_global_and_tag_concurrency_limits() {
+        let scheduler = Arc::new(
+            CronTaskScheduler::new()
+                .await
+                .unwrap()
+                .with_concurrency_limits(&ConcurrencyLimits::new(2).with_tag_limit("db", 1)),
+        );
+        let (active, peak) = (Arc::new(AtomicU32::new(0)), Arc::new(AtomicU32::new(0)));
+        let (db_active, db_peak) = (Arc::new(AtomicU32::new(0)), Arc::new(AtomicU32::new(0)));
+
+        let mut task_ids = Vec::new();
+        for _ in 0..2 {
+            task_ids.push(add_tracked_task(&scheduler, OverlapPolicy::Allow, &["db"], db_active.clone(), db_peak.clone()).await);
+        }
+        for _ in 0..4 {
+            task_ids.push(add_tracked_task(&scheduler, OverlapPolicy::Allow, &[], active.clone(), peak.clone()).await);
+        }
+
+        let mut runs = JoinSet::new();
+        for task_id in task_ids {
+            let scheduler = scheduler.clone();
+            runs.spawn(async move { scheduler.run_task(task_id, HashMap::new()).await });
+        }
+        while let Some(run) = runs.join_next().await {
+            assert_eq!(run.unwrap().unwrap().status, TaskStatus::Completed);
+        }
+        assert_eq!(db_peak.load(Ordering::SeqCst), 1);
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    /// 记录回调调用情况的测试回调
+    #[derive(Default)]
+    struct RecordingHook {
+        seen_last_results: std::sync::Mutex<Vec<Option<bool>>>,
+        outcomes: std::sync::Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait]
+    impl crate::hooks::OnTaskBeforeRun for RecordingHook {
+        async fn on_before_run(
+            &self,
+            context: &mut TaskExecutionContext,
+        ) -> crate::hooks::HookResult<BeforeRunDecision> {
+            self.seen_last_results
+                .lock()
+                .unwrap()
+                .push(context.last_result.as_ref().map(|r| r.success));
+            if let Some(reason) = context.user_params.get("veto") {
+                return Ok(BeforeRunDecision::Veto(reason.clone()));
+            }
+            context.user_params.insert("injected".to_string(), "yes".to_string());
+            Ok(BeforeRunDecision::Proceed)
+        }
+    }
+
+    #[async_trait]
+    impl crate::hooks::OnTaskAfterRun for RecordingHook {
+        async fn on_after_run(&self, _context: &TaskResultContext) -> crate::hooks::HookResult<()> {
+            self.outcomes.lock().unwrap().push("after");
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl crate::hooks::OnTaskSuccess for RecordingHook {
+        async fn on_success(&self, _context: &TaskResultContext) -> crate::hooks::HookResult<()> {
+            self.outcomes.lock().unwrap().push("success");
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl crate::hooks::OnTaskError for RecordingHook {
+        async fn on_error(
+            &self,
+            context: &TaskResultContext,
+            error: &str,
+        ) -> crate::hooks::HookResult<ErrorRecoverySuggestion> {
+            self.outcomes.lock().unwrap().push("error");
+            Ok(ErrorRecoverySuggestion {
+                description: format!("Retry {} after: {}", context.task_id, error),
+                fix_command: Some("echo fix".to_string()),
+                create_recovery_task: true,
+                recovery_cron: Some("0 0 * * * *".to_string()),
+            })
+        }
+    }
+
+    fn recording_hooks(hook: &Arc<RecordingHook>) -> HookManager {
+        let mut hooks = HookManager::new();
+        hooks.register_before_run(hook.clone());
+        hooks.register_after_run(hook.clone());
+        hooks.register_on_success(hook.clone());
+        hooks.register_on_error(hook.clone());
+        hooks
+    }
+
+    #[tokio::test]
+    async fn test_before_run_hook_modifies_and_vetoes() {
+        let hook = Arc::new(RecordingHook::default());
+        let scheduler = CronTaskScheduler::new().await.unwrap().with_hooks(recording_hooks(&hook));
+        let task_id = add_echo_task(&scheduler, "hooked", 0).await;
+
+        let first = scheduler.run_task(task_id, HashMap::new()).await.unwrap();
+        assert_eq!(first.status, TaskStatus::Completed);
+        assert_eq!(first.user_params.get("injected").map(String::as_str), Some("yes"));
+        assert_eq!(
+            first.result.unwrap().stdout.as_deref(),
+            Some("hooked:injected=yes\n")
+        );
+
+        let params = HashMap::from([("veto".to_string(), "maintenance window".to_string())]);
+        let vetoed = scheduler.run_task(task_id, params).await.unwrap();
+        assert_eq!(vetoed.status, TaskStatus::Skipped);
+        let logs = scheduler.get_instance_logs(vetoed.id, None).await.unwrap();
+        assert!(logs[0].message.contains("Vetoed by hook: maintenance window"));
+
+        // 第二次运行前能看到上一次运行的结果
+        assert_eq!(*hook.seen_last_results.lock().unwrap(), vec![None, Some(true)]);
+        // 被否决的运行不触发执行后回调
+        assert_eq!(*hook.outcomes.lock().unwrap(), vec!["after", "success"]);
+    }
 
+    #[tokio::test]
+    async fn test_error_hook_creates_recovery_task() {
+        let hook = Arc::new(RecordingHook::default());
+        let scheduler = CronTaskScheduler::new().await.unwrap().with_hooks(recording_hooks(&hook));
+        let broken = executor_fn(|_task_id, _params, _ctx| async move {
+            Err(SchedulerError::ExecutionError("disk full".to_string()))
+        });
         let task = scheduler
-            .add_task(
-                "Test Task".to_string(),
-                "test_task".to_string(),
-                "0 * * * * *".to_string(),
-                executor,
-            )
+            .add_task("Backup".to_string(), "backup".to_string(), "0 0 3 * * *".to_string(), broken)
             .await
             .unwrap();
 
-        // 运行任务
-        scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+        let instance = scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Error);
+        assert_eq!(*hook.outcomes.lock().unwrap(), vec!["after", "error"]);
 
-        // 获取实例
-        let instances = scheduler.get_task_instances(task.id).await.unwrap();
-        assert_eq!(instances.len(), 1);
+        let recovery: Vec<ScheduledTask> = scheduler
+            .list_tasks()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|t| t.name == "backup_recovery")
+            .collect();
+        assert_eq!(recovery.len(), 1);
+        assert_eq!(recovery[0].cron_expression, "0 0 * * * *");
+        assert_eq!(recovery[0].action, Some(TaskAction::shell("echo fix")));
+        assert!(recovery[0].description.as_deref().unwrap().contains("disk full"));
+
+        // 再次出错不会重复创建
+        scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+        let count = scheduler
+            .list_tasks()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|t| t.name == "backup_recovery")
+            .count();
+        assert_eq!(count, 1);
     }
 }
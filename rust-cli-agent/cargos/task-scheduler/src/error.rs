@@ -30,6 +30,18 @@ pub enum SchedulerError {
 
     #[error("System scheduler error: {0}")]
     SystemError(String),
+
+    #[error("IPC error: {0}")]
+    IpcError(String),
+
+    #[error("Workflow not found: {0}")]
+    WorkflowNotFound(Uuid),
+
+    #[error("Workflow run not found: {0}")]
+    WorkflowRunNotFound(Uuid),
+
+    #[error("Notification error: {0}")]
+    NotificationError(String),
 }
 
 /// 调度器操作结果
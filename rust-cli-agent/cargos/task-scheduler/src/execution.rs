@@ -0,0 +1,250 @@
+//! 任务执行器与执行上下文
+//!
+//! 执行器通过上下文把运行中产生的输出实时交给调度器，调度器将其写入 TaskLog；
+//! 调度器也通过上下文通知执行器停止运行
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::{mpsc, watch};
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+use crate::types::TaskExecutionResult;
+
+/// 任务执行器
+///
+/// 执行过程是异步的，不占用调度器的运行时线程；
+/// 输出、子进程等进度事件通过 `TaskRunContext` 实时推送
+#[async_trait]
+pub trait TaskExecutor: Send + Sync {
+    /// 执行一次任务
+    async fn execute(
+        &self,
+        task_id: Uuid,
+        user_params: HashMap<String, String>,
+        ctx: TaskRunContext,
+    ) -> Result<TaskExecutionResult>;
+}
+
+/// 由异步闭包构造执行器
+pub fn executor_fn<F, Fut>(f: F) -> Arc<dyn TaskExecutor>
+where
+    F: Fn(Uuid, HashMap<String, String>, TaskRunContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<TaskExecutionResult>> + Send + 'static,
+{
+    Arc::new(FnExecutor(f))
+}
+
+/// 由同步闭包构造执行器，闭包在阻塞线程池中运行
+pub fn blocking_executor_fn<F>(f: F) -> Arc<dyn TaskExecutor>
+where
+    F: Fn(Uuid, HashMap<String, String>, TaskRunContext) -> Result<TaskExecutionResult>
+        + Send
+        + Sync
+        + 'static,
+{
+    Arc::new(BlockingFnExecutor(Arc::new(f)))
+}
+
+struct FnExecutor<F>(F);
+
+#[async_trait]
+impl<F, Fut> TaskExecutor for FnExecutor<F>
+where
+    F: Fn(Uuid, HashMap<String, String>, TaskRunContext) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<TaskExecutionResult>> + Send,
+{
+    async fn execute(
+        &self,
+        task_id: Uuid,
+        user_params: HashMap<String, String>,
+        ctx: TaskRunContext,
+    ) -> Result<TaskExecutionResult> {
+        (self.0)(task_id, user_params, ctx).await
+    }
+}
+
+struct BlockingFnExecutor<F>(Arc<F>);
+
+#[async_trait]
+impl<F> TaskExecutor for BlockingFnExecutor<F>
+where
+    F: Fn(Uuid, HashMap<String, String>, TaskRunContext) -> Result<TaskExecutionResult>
+        + Send
+        + Sync
+        + 'static,
+{
+    async fn execute(
+        &self,
+        task_id: Uuid,
+        user_params: HashMap<String, String>,
+        ctx: TaskRunContext,
+    ) -> Result<TaskExecutionResult> {
+        let f = self.0.clone();
+        tokio::task::spawn_blocking(move || f(task_id, user_params, ctx))
+            .await
+            .unwrap_or_else(|e| Err(SchedulerError::ExecutionError(e.to_string())))
+    }
+}
+
+/// 任务运行中产生的事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskRunEvent {
+    /// 一行标准输出
+    Stdout(String),
+    /// 一行标准错误
+    Stderr(String),
+    /// 执行器启动了子进程 (Unix 下同时是进程组 ID)
+    ProcessStarted(u32),
+}
+
+/// 调度器持有的运行控制端
+pub struct TaskRunControl {
+    /// 执行器推送的事件
+    pub events: mpsc::UnboundedReceiver<TaskRunEvent>,
+    /// 停止信号，写入 true 表示请求停止
+    pub stop: watch::Sender<bool>,
+}
+
+/// 单次运行的执行上下文
+///
+/// 由调度器为每个运行实例创建，执行器可随时调用 `stdout`/`stderr` 推送输出，
+/// 并通过 `cancelled`/`is_cancelled` 响应停止请求
+#[derive(Debug, Clone)]
+pub struct TaskRunContext {
+    run_instance_id: Uuid,
+    events: mpsc::UnboundedSender<TaskRunEvent>,
+    stop: watch::Receiver<bool>,
+}
+
+impl TaskRunContext {
+    /// 创建上下文及其控制端
+    pub fn new(run_instance_id: Uuid) -> (Self, TaskRunControl) {
+        let (events, events_rx) = mpsc::unbounded_channel();
+        let (stop_tx, stop) = watch::channel(false);
+        (
+            Self {
+                run_instance_id,
+                events,
+                stop,
+            },
+            TaskRunControl {
+                events: events_rx,
+                stop: stop_tx,
+            },
+        )
+    }
+
+    /// 创建不收集输出、不会被停止的上下文
+    pub fn detached(run_instance_id: Uuid) -> Self {
+        Self::new(run_instance_id).0
+    }
+
+    /// 当前运行实例 ID
+    pub fn run_instance_id(&self) -> Uuid {
+        self.run_instance_id
+    }
+
+    /// 推送一行标准输出
+    pub fn stdout(&self, line: impl Into<String>) {
+        let _ = self.events.send(TaskRunEvent::Stdout(line.into()));
+    }
+
+    /// 推送一行标准错误
+    pub fn stderr(&self, line: impl Into<String>) {
+        let _ = self.events.send(TaskRunEvent::Stderr(line.into()));
+    }
+
+    /// 登记执行器启动的子进程，停止任务时据此终止进程
+    pub fn process_started(&self, pid: u32) {
+        let _ = self.events.send(TaskRunEvent::ProcessStarted(pid));
+    }
+
+    /// 是否已请求停止
+    pub fn is_cancelled(&self) -> bool {
+        *self.stop.borrow()
+    }
+
+    /// 等待停止请求；控制端已释放时永不返回
+    pub async fn cancelled(&self) {
+        let mut stop = self.stop.clone();
+        if stop.wait_for(|stopped| *stopped).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_context_forwards_output() {
+        let instance_id = Uuid::new_v4();
+        let (ctx, mut control) = TaskRunContext::new(instance_id);
+        assert_eq!(ctx.run_instance_id(), instance_id);
+
+        ctx.stdout("hello");
+        ctx.stderr("oops");
+        ctx.process_started(42);
+        drop(ctx);
+
+        assert_eq!(control.events.recv().await, Some(TaskRunEvent::Stdout("hello".to_string())));
+        assert_eq!(control.events.recv().await, Some(TaskRunEvent::Stderr("oops".to_string())));
+        assert_eq!(control.events.recv().await, Some(TaskRunEvent::ProcessStarted(42)));
+        assert_eq!(control.events.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_context_cancellation() {
+        let (ctx, control) = TaskRunContext::new(Uuid::new_v4());
+        assert!(!ctx.is_cancelled());
+
+        let waiter = {
+            let ctx = ctx.clone();
+            tokio::spawn(async move { ctx.cancelled().await })
+        };
+        control.stop.send(true).unwrap();
+        waiter.await.unwrap();
+        assert!(ctx.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_executor_fn() {
+        let executor = executor_fn(|task_id, params: HashMap<String, String>, ctx: TaskRunContext| async move {
+            ctx.stdout(params.get("user").cloned().unwrap_or_default());
+            Ok(TaskExecutionResult::success(task_id, String::new(), String::new(), 0))
+        });
+
+        let (ctx, mut control) = TaskRunContext::new(Uuid::new_v4());
+        let mut params = HashMap::new();
+        params.insert("user".to_string(), "alice".to_string());
+        let result = executor.execute(Uuid::new_v4(), params, ctx).await.unwrap();
+
+        assert!(result.success);
+        assert_eq!(control.events.recv().await, Some(TaskRunEvent::Stdout("alice".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_blocking_executor_fn() {
+        let executor = blocking_executor_fn(|task_id, _params, _ctx| {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            Err(SchedulerError::ExecutionError(format!("{} failed", task_id)))
+        });
+
+        let result = executor
+            .execute(Uuid::new_v4(), HashMap::new(), TaskRunContext::detached(Uuid::new_v4()))
+            .await;
+        assert!(matches!(result, Err(SchedulerError::ExecutionError(_))));
+    }
+
+    #[test]
+    fn test_detached_context_discards_output() {
+        let ctx = TaskRunContext::detached(Uuid::new_v4());
+        ctx.stdout("ignored");
+        assert!(!ctx.is_cancelled());
+    }
+}
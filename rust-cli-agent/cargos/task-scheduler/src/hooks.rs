@@ -1,6 +1,7 @@
 //! AI 增强 - 回调 Hook 接口
 //!
-//! 在任务执行的关键位置预留回调，支持外部扩展
+//! 在任务执行的关键位置预留回调，支持外部扩展。
+//! 调度器在每次逻辑运行开始前调用执行前回调，在最后一次尝试结束后调用其余回调
 
 use std::sync::Arc;
 use async_trait::async_trait;
@@ -33,6 +34,27 @@ pub struct TaskExecutionContext {
     pub last_result: Option<TaskExecutionResult>,
 }
 
+impl TaskExecutionContext {
+    /// 从任务和本次运行的参数创建上下文
+    pub fn from_task(
+        task: &ScheduledTask,
+        user_params: std::collections::HashMap<String, String>,
+        last_result: Option<TaskExecutionResult>,
+    ) -> Self {
+        Self {
+            task_id: task.id,
+            title: task.title.clone(),
+            name: task.name.clone(),
+            description: task.description.clone(),
+            content: task.content.clone(),
+            cron_expression: task.cron_expression.clone(),
+            user_params,
+            run_count: task.run_count,
+            last_result,
+        }
+    }
+}
+
 /// 任务执行结果上下文
 ///
 /// 在任务执行后传递给回调函数
@@ -40,14 +62,59 @@ pub struct TaskExecutionContext {
 pub struct TaskResultContext {
     /// 任务 ID
     pub task_id: Uuid,
+    /// 任务标题
+    pub title: String,
+    /// 任务名称
+    pub name: String,
+    /// 任务标签
+    pub tags: Vec<String>,
     /// 运行实例 ID
     pub run_instance_id: Uuid,
+    /// 运行结束时的状态
+    pub status: TaskStatus,
     /// 执行结果
     pub result: TaskExecutionResult,
     /// 执行时长(毫秒)
     pub duration_ms: i64,
 }
 
+impl TaskResultContext {
+    /// 从结束的运行实例创建上下文
+    pub fn from_instance(instance: &TaskRunInstance) -> Self {
+        let result = instance.result.clone().unwrap_or_else(|| {
+            TaskExecutionResult::failure(instance.task_id, format!("Run ended with status {}", instance.status))
+        });
+        Self {
+            task_id: instance.task_id,
+            title: String::new(),
+            name: String::new(),
+            tags: Vec::new(),
+            run_instance_id: instance.id,
+            status: instance.status.clone(),
+            result,
+            duration_ms: instance.duration_ms().unwrap_or(0),
+        }
+    }
+
+    /// 补充任务的标题、名称和标签
+    pub fn with_task(mut self, task: &ScheduledTask) -> Self {
+        self.title = task.title.clone();
+        self.name = task.name.clone();
+        self.tags = task.tags.clone();
+        self
+    }
+}
+
+/// 任务执行前回调的决定
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum BeforeRunDecision {
+    /// 继续运行
+    #[default]
+    Proceed,
+    /// 取消本次运行，记为 Skipped 运行实例
+    Veto(String),
+}
+
 /// 错误恢复建议
 #[derive(Debug, Clone)]
 pub struct ErrorRecoverySuggestion {
@@ -76,16 +143,16 @@ pub type HookResult<T> = std::result::Result<T, HookError>;
 /// 任务执行前回调
 ///
 /// 在任务执行前调用，可用于:
-/// - 准备执行上下文
-/// - 验证参数
+/// - 准备执行上下文 (修改后的 user_params 用于本次运行)
+/// - 验证参数，返回 Veto 取消本次运行
 /// - 记录开始日志
 #[async_trait]
 pub trait OnTaskBeforeRun: Send + Sync {
     /// 任务执行前回调
     async fn on_before_run(
         &self,
-        context: &TaskExecutionContext,
-    ) -> HookResult<()>;
+        context: &mut TaskExecutionContext,
+    ) -> HookResult<BeforeRunDecision>;
 }
 
 /// 任务执行后回调
@@ -167,6 +234,15 @@ impl HookManager {
         }
     }
 
+    /// 是否没有注册任何回调
+    pub fn is_empty(&self) -> bool {
+        self.before_run.is_empty()
+            && self.after_run.is_empty()
+            && self.on_success.is_empty()
+            && self.on_failure.is_empty()
+            && self.on_error.is_empty()
+    }
+
     /// 注册任务执行前回调
     pub fn register_before_run(&mut self, hook: Arc<dyn OnTaskBeforeRun>) {
         self.before_run.push(hook);
@@ -193,11 +269,18 @@ impl HookManager {
     }
 
     /// 触发任务执行前回调
-    pub async fn trigger_before_run(&self, context: &TaskExecutionContext) -> HookResult<()> {
+    ///
+    /// 按注册顺序调用，后面的回调看到前面回调对上下文的修改；任一回调否决时不再调用其余回调
+    pub async fn trigger_before_run(
+        &self,
+        context: &mut TaskExecutionContext,
+    ) -> HookResult<BeforeRunDecision> {
         for hook in &self.before_run {
-            hook.on_before_run(context).await?;
+            if let BeforeRunDecision::Veto(reason) = hook.on_before_run(context).await? {
+                return Ok(BeforeRunDecision::Veto(reason));
+            }
         }
-        Ok(())
+        Ok(BeforeRunDecision::Proceed)
     }
 
     /// 触发任务执行后回调
@@ -264,9 +347,9 @@ impl LoggingHook {
 
 #[async_trait]
 impl OnTaskBeforeRun for LoggingHook {
-    async fn on_before_run(&self, context: &TaskExecutionContext) -> HookResult<()> {
+    async fn on_before_run(&self, context: &mut TaskExecutionContext) -> HookResult<BeforeRunDecision> {
         tracing::info!("Task {} ({}) starting", context.title, context.task_id);
-        Ok(())
+        Ok(BeforeRunDecision::Proceed)
     }
 }
 
@@ -328,7 +411,7 @@ mod tests {
     async fn test_hook_manager_empty() {
         let manager = HookManager::new();
 
-        let context = TaskExecutionContext {
+        let mut context = TaskExecutionContext {
             task_id: Uuid::new_v4(),
             title: "Test".to_string(),
             name: "test".to_string(),
@@ -340,7 +423,7 @@ mod tests {
             last_result: None,
         };
 
-        manager.trigger_before_run(&context).await.unwrap();
+        assert_eq!(manager.trigger_before_run(&mut context).await.unwrap(), BeforeRunDecision::Proceed);
     }
 
     #[tokio::test]
@@ -349,7 +432,7 @@ mod tests {
         let mut manager = HookManager::new();
         manager.register_before_run(hook);
 
-        let context = TaskExecutionContext {
+        let mut context = TaskExecutionContext {
             task_id: Uuid::new_v4(),
             title: "Test".to_string(),
             name: "test".to_string(),
@@ -361,6 +444,43 @@ mod tests {
             last_result: None,
         };
 
-        manager.trigger_before_run(&context).await.unwrap();
+        manager.trigger_before_run(&mut context).await.unwrap();
+    }
+
+    struct VetoWhen(&'static str);
+
+    #[async_trait]
+    impl OnTaskBeforeRun for VetoWhen {
+        async fn on_before_run(&self, context: &mut TaskExecutionContext) -> HookResult<BeforeRunDecision> {
+            if context.user_params.contains_key(self.0) {
+                return Ok(BeforeRunDecision::Veto(format!("{} is set", self.0)));
+            }
+            context.user_params.insert("checked".to_string(), "1".to_string());
+            Ok(BeforeRunDecision::Proceed)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_before_run_modify_and_veto() {
+        let mut manager = HookManager::new();
+        manager.register_before_run(Arc::new(VetoWhen("checked")));
+        manager.register_before_run(Arc::new(VetoWhen("never")));
+        assert!(!manager.is_empty());
+
+        let task = ScheduledTask::new(
+            Uuid::new_v4(),
+            "Test".to_string(),
+            "test".to_string(),
+            "0 * * * * *".to_string(),
+            None,
+            None,
+        );
+        let mut context = TaskExecutionContext::from_task(&task, std::collections::HashMap::new(), None);
+        assert_eq!(manager.trigger_before_run(&mut context).await.unwrap(), BeforeRunDecision::Proceed);
+        assert_eq!(context.user_params.get("checked").map(String::as_str), Some("1"));
+
+        // 第一个回调在参数已存在时否决
+        let decision = manager.trigger_before_run(&mut context).await.unwrap();
+        assert_eq!(decision, BeforeRunDecision::Veto("checked is set".to_string()));
     }
 }
@@ -0,0 +1,947 @@
+//! 守护进程控制协议
+//!
+//! 守护进程在 Unix 域套接字上提供按行分隔的 JSON-RPC 2.0 接口，方法名与
+//! `TaskScheduler` 的方法一一对应。每个请求携带协议版本，版本不一致时守护进程拒绝请求
+
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::action::TaskAction;
+use crate::bundle::{ExportOptions, ImportOptions, ImportReport, ScheduleBundle};
+use crate::error::SchedulerError;
+use crate::retention::{PruneReport, RetentionPolicy};
+use crate::scheduler::TaskScheduler;
+use crate::types::*;
+use crate::workflow::{Workflow, WorkflowBriefing, WorkflowRun};
+
+/// 当前协议版本
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// 单条消息的大小上限
+pub const MAX_MESSAGE_BYTES: usize = 4 * 1024 * 1024;
+
+/// JSON 解析失败
+pub const PARSE_ERROR: i64 = -32700;
+/// 请求格式不正确
+pub const INVALID_REQUEST: i64 = -32600;
+/// 方法不存在
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// 参数不正确
+pub const INVALID_PARAMS: i64 = -32602;
+/// 协议版本不一致
+pub const VERSION_MISMATCH: i64 = -32001;
+
+// 调度器错误按变体映射到固定错误码，客户端据此还原错误类型
+const JOB_NOT_FOUND: i64 = -32010;
+const RUN_INSTANCE_NOT_FOUND: i64 = -32011;
+const INVALID_CRON_EXPRESSION: i64 = -32012;
+const SCHEDULER_ERROR: i64 = -32013;
+const EXECUTION_ERROR: i64 = -32014;
+const STORAGE_ERROR: i64 = -32015;
+const INVALID_PARAMETER: i64 = -32016;
+const SYSTEM_ERROR: i64 = -32017;
+const WORKFLOW_NOT_FOUND: i64 = -32018;
+const WORKFLOW_RUN_NOT_FOUND: i64 = -32019;
+
+/// 请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub id: u64,
+    /// 客户端使用的协议版本
+    pub version: u32,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl RpcRequest {
+    /// 创建当前协议版本的请求
+    pub fn new(id: u64, method: impl Into<String>, params: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            version: PROTOCOL_VERSION,
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    /// 请求无法解析时为空
+    pub id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn success(id: u64, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id),
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    pub fn failure(id: Option<u64>, error: RpcError) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// 错误对象
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<SchedulerError> for RpcError {
+    fn from(error: SchedulerError) -> Self {
+        let (code, message) = match error {
+            SchedulerError::JobNotFound(id) => (JOB_NOT_FOUND, id.to_string()),
+            SchedulerError::RunInstanceNotFound(id) => (RUN_INSTANCE_NOT_FOUND, id.to_string()),
+            SchedulerError::InvalidCronExpression(m) => (INVALID_CRON_EXPRESSION, m),
+            SchedulerError::SchedulerError(m) => (SCHEDULER_ERROR, m),
+            SchedulerError::ExecutionError(m) => (EXECUTION_ERROR, m),
+            SchedulerError::StorageError(m) => (STORAGE_ERROR, m),
+            SchedulerError::InvalidParameter(m) => (INVALID_PARAMETER, m),
+            SchedulerError::SystemError(m) => (SYSTEM_ERROR, m),
+            SchedulerError::IpcError(m) => (SCHEDULER_ERROR, m),
+            SchedulerError::NotificationError(m) => (SCHEDULER_ERROR, m),
+            SchedulerError::WorkflowNotFound(id) => (WORKFLOW_NOT_FOUND, id.to_string()),
+            SchedulerError::WorkflowRunNotFound(id) => (WORKFLOW_RUN_NOT_FOUND, id.to_string()),
+        };
+        Self { code, message }
+    }
+}
+
+impl From<RpcError> for SchedulerError {
+    fn from(error: RpcError) -> Self {
+        let id = Uuid::parse_str(&error.message);
+        match (error.code, id) {
+            (JOB_NOT_FOUND, Ok(id)) => SchedulerError::JobNotFound(id),
+            (RUN_INSTANCE_NOT_FOUND, Ok(id)) => SchedulerError::RunInstanceNotFound(id),
+            (WORKFLOW_NOT_FOUND, Ok(id)) => SchedulerError::WorkflowNotFound(id),
+            (WORKFLOW_RUN_NOT_FOUND, Ok(id)) => SchedulerError::WorkflowRunNotFound(id),
+            (INVALID_CRON_EXPRESSION, _) => SchedulerError::InvalidCronExpression(error.message),
+            (SCHEDULER_ERROR, _) => SchedulerError::SchedulerError(error.message),
+            (EXECUTION_ERROR, _) => SchedulerError::ExecutionError(error.message),
+            (STORAGE_ERROR, _) => SchedulerError::StorageError(error.message),
+            (INVALID_PARAMETER, _) => SchedulerError::InvalidParameter(error.message),
+            (SYSTEM_ERROR, _) => SchedulerError::SystemError(error.message),
+            _ => SchedulerError::IpcError(format!("{} (code {})", error.message, error.code)),
+        }
+    }
+}
+
+/// `hello` 方法的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelloResult {
+    /// 守护进程使用的协议版本
+    pub version: u32,
+    /// 守护进程 PID
+    pub pid: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TaskIdParams {
+    task_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PruneParams {
+    policy: RetentionPolicy,
+    dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ImportParams {
+    bundle: ScheduleBundle,
+    options: ImportOptions,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RunInstanceParams {
+    run_instance_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AddTaskParams {
+    title: String,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    content: Option<String>,
+    cron_expression: String,
+    action: TaskAction,
+    #[serde(default)]
+    is_system: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RunTaskParams {
+    task_id: Uuid,
+    #[serde(default)]
+    user_params: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AddLogParams {
+    run_instance_id: Uuid,
+    level: LogLevel,
+    message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WorkflowIdParams {
+    workflow_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RunWorkflowParams {
+    workflow_id: Uuid,
+    #[serde(default)]
+    user_params: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct WorkflowRunParams {
+    run_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct InstanceLogsParams {
+    run_instance_id: Uuid,
+    #[serde(default)]
+    level: Option<LogLevel>,
+}
+
+/// 处理一个请求
+pub async fn handle_request(scheduler: &dyn TaskScheduler, request: RpcRequest) -> RpcResponse {
+    if request.jsonrpc != "2.0" {
+        return RpcResponse::failure(
+            Some(request.id),
+            RpcError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""),
+        );
+    }
+    if request.version != PROTOCOL_VERSION {
+        return RpcResponse::failure(
+            Some(request.id),
+            RpcError::new(
+                VERSION_MISMATCH,
+                format!(
+                    "Unsupported protocol version {}, daemon speaks version {}",
+                    request.version, PROTOCOL_VERSION
+                ),
+            ),
+        );
+    }
+
+    match dispatch(scheduler, &request.method, request.params).await {
+        Ok(result) => RpcResponse::success(request.id, result),
+        Err(error) => RpcResponse::failure(Some(request.id), error),
+    }
+}
+
+async fn dispatch(
+    scheduler: &dyn TaskScheduler,
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "hello" => to_value(HelloResult {
+            version: PROTOCOL_VERSION,
+            pid: std::process::id(),
+        }),
+        "add_task_with_action" => {
+            let p: AddTaskParams = parse(params)?;
+            to_value(
+                scheduler
+                    .add_task_with_action(
+                        p.title,
+                        p.name,
+                        p.description,
+                        p.content,
+                        p.cron_expression,
+                        p.action,
+                        p.is_system,
+                    )
+                    .await?,
+            )
+        }
+        "remove_task" => {
+            let p: TaskIdParams = parse(params)?;
+            to_value(scheduler.remove_task(p.task_id).await?)
+        }
+        "pause_task" => {
+            let p: TaskIdParams = parse(params)?;
+            to_value(scheduler.pause_task(p.task_id).await?)
+        }
+        "resume_task" => {
+            let p: TaskIdParams = parse(params)?;
+            to_value(scheduler.resume_task(p.task_id).await?)
+        }
+        "update_task" => {
+            let request: TaskUpdateRequest = parse(params)?;
+            to_value(scheduler.update_task(request).await?)
+        }
+        "get_task" => {
+            let p: TaskIdParams = parse(params)?;
+            to_value(scheduler.get_task(p.task_id).await?)
+        }
+        "list_tasks" => to_value(scheduler.list_tasks().await?),
+        "list_running_tasks" => to_value(scheduler.list_running_tasks().await?),
+        "run_task" => {
+            let p: RunTaskParams = parse(params)?;
+            to_value(scheduler.run_task(p.task_id, p.user_params).await?)
+        }
+        "stop_task" => {
+            let p: RunInstanceParams = parse(params)?;
+            to_value(scheduler.stop_task(p.run_instance_id).await?)
+        }
+        "get_run_instance" => {
+            let p: RunInstanceParams = parse(params)?;
+            to_value(scheduler.get_run_instance(p.run_instance_id).await?)
+        }
+        "get_task_instances" => {
+            let p: TaskIdParams = parse(params)?;
+            to_value(scheduler.get_task_instances(p.task_id).await?)
+        }
+        "add_log" => {
+            let p: AddLogParams = parse(params)?;
+            to_value(scheduler.add_log(p.run_instance_id, p.level, p.message).await?)
+        }
+        "get_instance_logs" => {
+            let p: InstanceLogsParams = parse(params)?;
+            to_value(scheduler.get_instance_logs(p.run_instance_id, p.level).await?)
+        }
+        "get_task_briefing" => {
+            let p: TaskIdParams = parse(params)?;
+            to_value(scheduler.get_task_briefing(p.task_id).await?)
+        }
+        "clear_all_tasks" => to_value(scheduler.clear_all_tasks().await?),
+        "prune_history" => {
+            let p: PruneParams = parse(params)?;
+            to_value(scheduler.prune_history(p.policy, p.dry_run).await?)
+        }
+        "export_bundle" => {
+            let options: ExportOptions = parse(params)?;
+            to_value(scheduler.export_bundle(options).await?)
+        }
+        "import_bundle" => {
+            let p: ImportParams = parse(params)?;
+            to_value(scheduler.import_bundle(p.bundle, p.options).await?)
+        }
+        "add_workflow" => {
+            let workflow: Workflow = parse(params)?;
+            to_value(scheduler.add_workflow(workflow).await?)
+        }
+        "remove_workflow" => {
+            let p: WorkflowIdParams = parse(params)?;
+            to_value(scheduler.remove_workflow(p.workflow_id).await?)
+        }
+        "get_workflow" => {
+            let p: WorkflowIdParams = parse(params)?;
+            to_value(scheduler.get_workflow(p.workflow_id).await?)
+        }
+        "list_workflows" => to_value(scheduler.list_workflows().await?),
+        "run_workflow" => {
+            let p: RunWorkflowParams = parse(params)?;
+            to_value(scheduler.run_workflow(p.workflow_id, p.user_params).await?)
+        }
+        "get_workflow_run" => {
+            let p: WorkflowRunParams = parse(params)?;
+            to_value(scheduler.get_workflow_run(p.run_id).await?)
+        }
+        "get_workflow_briefing" => {
+            let p: WorkflowIdParams = parse(params)?;
+            to_value(scheduler.get_workflow_briefing(p.workflow_id).await?)
+        }
+        "is_running" => to_value(scheduler.is_running().await),
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method: {}", method),
+        )),
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // 无参数方法的 params 可以省略
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| RpcError::new(SCHEDULER_ERROR, e.to_string()))
+}
+
+#[cfg(unix)]
+pub use unix::{serve, DaemonClient};
+
+#[cfg(unix)]
+mod unix {
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+
+    use super::*;
+    use crate::error::Result;
+    use crate::scheduler::AsyncTaskExecutor;
+
+    /// 在监听套接字上提供控制接口，直到监听出错
+    pub async fn serve(
+        listener: UnixListener,
+        scheduler: Arc<dyn TaskScheduler>,
+    ) -> std::io::Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, scheduler.as_ref()).await {
+                    tracing::warn!("IPC connection error: {}", e);
+                }
+            });
+        }
+    }
+
+    /// 逐行读取请求并按顺序应答
+    ///
+    /// 一行超过 [`MAX_MESSAGE_BYTES`] 时不再读取，应答错误后关闭连接
+    async fn handle_connection(
+        stream: UnixStream,
+        scheduler: &dyn TaskScheduler,
+    ) -> std::io::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = (&mut reader)
+                .take(MAX_MESSAGE_BYTES as u64 + 1)
+                .read_until(b'\n', &mut line)
+                .await?;
+            if read == 0 {
+                return Ok(());
+            }
+            let too_long = line.len() > MAX_MESSAGE_BYTES && line.last() != Some(&b'\n');
+            let response = if too_long {
+                RpcResponse::failure(
+                    None,
+                    RpcError::new(
+                        INVALID_REQUEST,
+                        format!("Request exceeds {} bytes", MAX_MESSAGE_BYTES),
+                    ),
+                )
+            } else if line.trim_ascii().is_empty() {
+                continue;
+            } else {
+                match serde_json::from_slice::<RpcRequest>(&line) {
+                    Ok(request) => handle_request(scheduler, request).await,
+                    Err(e) => RpcResponse::failure(None, RpcError::new(PARSE_ERROR, e.to_string())),
+                }
+            };
+            let mut payload = serde_json::to_vec(&response)?;
+            payload.push(b'\n');
+            writer.write_all(&payload).await?;
+            if too_long {
+                return Ok(());
+            }
+        }
+    }
+
+    /// 守护进程控制接口的客户端
+    ///
+    /// 实现 `TaskScheduler`，CLI 在守护进程运行时通过它操作守护进程中的调度器。
+    /// 每次调用使用独立连接，长时间运行的 `run_task` 不会阻塞其他调用
+    pub struct DaemonClient {
+        path: PathBuf,
+        next_id: AtomicU64,
+    }
+
+    impl DaemonClient {
+        /// 连接守护进程并校验协议版本
+        pub async fn connect(path: impl AsRef<Path>) -> Result<Self> {
+            let client = Self {
+                path: path.as_ref().to_path_buf(),
+                next_id: AtomicU64::new(1),
+            };
+            let hello = client.hello().await?;
+            if hello.version != PROTOCOL_VERSION {
+                return Err(SchedulerError::IpcError(format!(
+                    "Daemon speaks protocol version {}, expected {}",
+                    hello.version, PROTOCOL_VERSION
+                )));
+            }
+            Ok(client)
+        }
+
+        /// 查询守护进程信息
+        pub async fn hello(&self) -> Result<HelloResult> {
+            self.call("hello", Value::Null).await
+        }
+
+        /// 调用一个方法
+        pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let request = RpcRequest::new(id, method, params);
+
+            let stream = UnixStream::connect(&self.path)
+                .await
+                .map_err(|e| SchedulerError::IpcError(e.to_string()))?;
+            let (reader, mut writer) = stream.into_split();
+            let mut payload =
+                serde_json::to_vec(&request).map_err(|e| SchedulerError::IpcError(e.to_string()))?;
+            payload.push(b'\n');
+            writer
+                .write_all(&payload)
+                .await
+                .map_err(|e| SchedulerError::IpcError(e.to_string()))?;
+
+            let line = BufReader::new(reader)
+                .lines()
+                .next_line()
+                .await
+                .map_err(|e| SchedulerError::IpcError(e.to_string()))?
+                .ok_or_else(|| SchedulerError::IpcError("Daemon closed the connection".to_string()))?;
+            let response: RpcResponse =
+                serde_json::from_str(&line).map_err(|e| SchedulerError::IpcError(e.to_string()))?;
+
+            if let Some(error) = response.error {
+                return Err(error.into());
+            }
+            serde_json::from_value(response.result.unwrap_or(Value::Null))
+                .map_err(|e| SchedulerError::IpcError(e.to_string()))
+        }
+    }
+
+    fn executor_not_supported() -> SchedulerError {
+        SchedulerError::InvalidParameter(
+            "Executors cannot be sent to the daemon, use add_task_with_action".to_string(),
+        )
+    }
+
+    #[async_trait]
+    impl TaskScheduler for DaemonClient {
+        async fn add_task(
+            &self,
+            _title: String,
+            _name: String,
+            _cron_expression: String,
+            _executor: AsyncTaskExecutor,
+        ) -> Result<ScheduledTask> {
+            Err(executor_not_supported())
+        }
+
+        async fn add_task_full(
+            &self,
+            _title: String,
+            _name: String,
+            _description: Option<String>,
+            _content: Option<String>,
+            _cron_expression: String,
+            _executor: AsyncTaskExecutor,
+        ) -> Result<ScheduledTask> {
+            Err(executor_not_supported())
+        }
+
+        async fn add_task_with_action(
+            &self,
+            title: String,
+            name: String,
+            description: Option<String>,
+            content: Option<String>,
+            cron_expression: String,
+            action: TaskAction,
+            is_system: bool,
+        ) -> Result<ScheduledTask> {
+            let params = AddTaskParams {
+                title,
+                name,
+                description,
+                content,
+                cron_expression,
+                action,
+                is_system,
+            };
+            self.call("add_task_with_action", to_params(params)?).await
+        }
+
+        async fn remove_task(&self, task_id: Uuid) -> Result<()> {
+            self.call("remove_task", to_params(TaskIdParams { task_id })?).await
+        }
+
+        async fn pause_task(&self, task_id: Uuid) -> Result<()> {
+            self.call("pause_task", to_params(TaskIdParams { task_id })?).await
+        }
+
+        async fn resume_task(&self, task_id: Uuid) -> Result<()> {
+            self.call("resume_task", to_params(TaskIdParams { task_id })?).await
+        }
+
+        async fn update_task(&self, request: TaskUpdateRequest) -> Result<ScheduledTask> {
+            self.call("update_task", to_params(request)?).await
+        }
+
+        async fn get_task(&self, task_id: Uuid) -> Result<ScheduledTask> {
+            self.call("get_task", to_params(TaskIdParams { task_id })?).await
+        }
+
+        async fn list_tasks(&self) -> Result<Vec<ScheduledTask>> {
+            self.call("list_tasks", Value::Null).await
+        }
+
+        async fn list_running_tasks(&self) -> Result<Vec<ScheduledTask>> {
+            self.call("list_running_tasks", Value::Null).await
+        }
+
+        async fn run_task(
+            &self,
+            task_id: Uuid,
+            user_params: HashMap<String, String>,
+        ) -> Result<TaskRunInstance> {
+            self.call("run_task", to_params(RunTaskParams { task_id, user_params })?)
+                .await
+        }
+
+        async fn stop_task(&self, run_instance_id: Uuid) -> Result<()> {
+            self.call("stop_task", to_params(RunInstanceParams { run_instance_id })?)
+                .await
+        }
+
+        async fn get_run_instance(&self, run_instance_id: Uuid) -> Result<TaskRunInstance> {
+            self.call("get_run_instance", to_params(RunInstanceParams { run_instance_id })?)
+                .await
+        }
+
+        async fn get_task_instances(&self, task_id: Uuid) -> Result<Vec<TaskRunInstance>> {
+            self.call("get_task_instances", to_params(TaskIdParams { task_id })?)
+                .await
+        }
+
+        async fn add_log(
+            &self,
+            run_instance_id: Uuid,
+            level: LogLevel,
+            message: String,
+        ) -> Result<TaskLog> {
+            let params = AddLogParams {
+                run_instance_id,
+                level,
+                message,
+            };
+            self.call("add_log", to_params(params)?).await
+        }
+
+        async fn get_instance_logs(
+            &self,
+            run_instance_id: Uuid,
+            level: Option<LogLevel>,
+        ) -> Result<Vec<TaskLog>> {
+            let params = InstanceLogsParams {
+                run_instance_id,
+                level,
+            };
+            self.call("get_instance_logs", to_params(params)?).await
+        }
+
+        async fn get_task_briefing(&self, task_id: Uuid) -> Result<TaskBriefing> {
+            self.call("get_task_briefing", to_params(TaskIdParams { task_id })?)
+                .await
+        }
+
+        async fn clear_all_tasks(&self) -> Result<usize> {
+            self.call("clear_all_tasks", Value::Null).await
+        }
+
+        async fn prune_history(&self, policy: RetentionPolicy, dry_run: bool) -> Result<PruneReport> {
+            self.call("prune_history", to_params(PruneParams { policy, dry_run })?)
+                .await
+        }
+
+        async fn export_bundle(&self, options: ExportOptions) -> Result<ScheduleBundle> {
+            self.call("export_bundle", to_params(options)?).await
+        }
+
+        async fn import_bundle(&self, bundle: ScheduleBundle, options: ImportOptions) -> Result<ImportReport> {
+            self.call("import_bundle", to_params(ImportParams { bundle, options })?)
+                .await
+        }
+
+        async fn add_workflow(&self, workflow: Workflow) -> Result<Workflow> {
+            self.call("add_workflow", to_params(workflow)?).await
+        }
+
+        async fn remove_workflow(&self, workflow_id: Uuid) -> Result<()> {
+            self.call("remove_workflow", to_params(WorkflowIdParams { workflow_id })?)
+                .await
+        }
+
+        async fn get_workflow(&self, workflow_id: Uuid) -> Result<Workflow> {
+            self.call("get_workflow", to_params(WorkflowIdParams { workflow_id })?)
+                .await
+        }
+
+        async fn list_workflows(&self) -> Result<Vec<Workflow>> {
+            self.call("list_workflows", Value::Null).await
+        }
+
+        async fn run_workflow(
+            &self,
+            workflow_id: Uuid,
+            user_params: HashMap<String, String>,
+        ) -> Result<WorkflowRun> {
+            let params = RunWorkflowParams {
+                workflow_id,
+                user_params,
+            };
+            self.call("run_workflow", to_params(params)?).await
+        }
+
+        async fn get_workflow_run(&self, run_id: Uuid) -> Result<WorkflowRun> {
+            self.call("get_workflow_run", to_params(WorkflowRunParams { run_id })?)
+                .await
+        }
+
+        async fn get_workflow_briefing(&self, workflow_id: Uuid) -> Result<WorkflowBriefing> {
+            self.call("get_workflow_briefing", to_params(WorkflowIdParams { workflow_id })?)
+                .await
+        }
+
+        async fn start(&self) -> Result<()> {
+            Err(SchedulerError::SchedulerError(
+                "The daemon scheduler is started by the daemon process".to_string(),
+            ))
+        }
+
+        async fn stop(&self) -> Result<()> {
+            Err(SchedulerError::SchedulerError(
+                "The daemon scheduler is stopped by the daemon process".to_string(),
+            ))
+        }
+
+        async fn is_running(&self) -> bool {
+            self.call::<bool>("is_running", Value::Null)
+                .await
+                .unwrap_or(false)
+        }
+    }
+
+    fn to_params<T: Serialize>(params: T) -> Result<Value> {
+        serde_json::to_value(params).map_err(|e| SchedulerError::IpcError(e.to_string()))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tempfile::TempDir;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+
+    use crate::cron_scheduler::CronTaskScheduler;
+
+    async fn start_server(dir: &TempDir) -> (std::path::PathBuf, Arc<CronTaskScheduler>) {
+        let path = dir.path().join("daemon.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let scheduler = Arc::new(CronTaskScheduler::new().await.unwrap());
+        let served: Arc<dyn TaskScheduler> = scheduler.clone();
+        tokio::spawn(serve(listener, served));
+        (path, scheduler)
+    }
+
+    async fn raw_call(path: &std::path::Path, line: &str) -> RpcResponse {
+        let mut stream = UnixStream::connect(path).await.unwrap();
+        stream.write_all(format!("{}\n", line).as_bytes()).await.unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut response = String::new();
+        reader.read_line(&mut response).await.unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_client_mirrors_scheduler() {
+        let dir = TempDir::new().unwrap();
+        let (path, scheduler) = start_server(&dir).await;
+        let client = DaemonClient::connect(&path).await.unwrap();
+
+        let task = client
+            .add_task_with_action(
+                "Echo".to_string(),
+                "echo".to_string(),
+                None,
+                None,
+                "0 0 * * * *".to_string(),
+                TaskAction::shell("echo over socket"),
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(scheduler.get_task(task.id).await.unwrap().title, "Echo");
+        assert_eq!(client.list_tasks().await.unwrap().len(), 1);
+
+        let instance = client.run_task(task.id, HashMap::new()).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Completed);
+        let logs = client.get_instance_logs(instance.id, None).await.unwrap();
+        assert!(logs.iter().any(|l| l.message == "over socket"));
+
+        client.pause_task(task.id).await.unwrap();
+        assert_eq!(scheduler.get_task(task.id).await.unwrap().status, TaskStatus::Paused);
+
+        client.remove_task(task.id).await.unwrap();
+        assert!(matches!(
+            client.get_task(task.id).await,
+            Err(SchedulerError::JobNotFound(id)) if id == task.id
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stop_live_run_over_socket() {
+        let dir = TempDir::new().unwrap();
+        let (path, _scheduler) = start_server(&dir).await;
+        let client = Arc::new(DaemonClient::connect(&path).await.unwrap());
+
+        let task = client
+            .add_task_with_action(
+                "Sleep".to_string(),
+                "sleep".to_string(),
+                None,
+                None,
+                "0 0 * * * *".to_string(),
+                TaskAction::shell("sleep 30"),
+                false,
+            )
+            .await
+            .unwrap();
+
+        let run = {
+            let client = client.clone();
+            tokio::spawn(async move { client.run_task(task.id, HashMap::new()).await })
+        };
+
+        // 等待运行实例出现
+        let mut running = None;
+        for _ in 0..50 {
+            let instances = client.get_task_instances(task.id).await.unwrap();
+            if let Some(instance) = instances.into_iter().find(|i| i.status == TaskStatus::Running) {
+                running = Some(instance);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let running = running.expect("run instance did not start");
+
+        client.stop_task(running.id).await.unwrap();
+        let instance = tokio::time::timeout(Duration::from_secs(10), run)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(instance.status, TaskStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors() {
+        let dir = TempDir::new().unwrap();
+        let (path, _scheduler) = start_server(&dir).await;
+
+        let response = raw_call(&path, "not json").await;
+        assert_eq!(response.id, None);
+        assert_eq!(response.error.unwrap().code, PARSE_ERROR);
+
+        let mut request = RpcRequest::new(7, "list_tasks", Value::Null);
+        request.version = PROTOCOL_VERSION + 1;
+        let response = raw_call(&path, &serde_json::to_string(&request).unwrap()).await;
+        assert_eq!(response.id, Some(7));
+        assert_eq!(response.error.unwrap().code, VERSION_MISMATCH);
+
+        let request = RpcRequest::new(8, "launch_rockets", Value::Null);
+        let response = raw_call(&path, &serde_json::to_string(&request).unwrap()).await;
+        assert_eq!(response.error.unwrap().code, METHOD_NOT_FOUND);
+
+        let request = RpcRequest::new(9, "get_task", serde_json::json!({ "task_id": "nope" }));
+        let response = raw_call(&path, &serde_json::to_string(&request).unwrap()).await;
+        assert_eq!(response.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_request_closes_connection() {
+        let dir = TempDir::new().unwrap();
+        let (path, _scheduler) = start_server(&dir).await;
+
+        let stream = UnixStream::connect(&path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        // 发送方不结束这一行，守护进程读到上限即应答
+        let sending = tokio::spawn(async move {
+            let chunk = vec![b'x'; 64 * 1024];
+            while writer.write_all(&chunk).await.is_ok() {}
+        });
+        let mut reader = BufReader::new(reader);
+        let mut response = String::new();
+        tokio::time::timeout(Duration::from_secs(10), reader.read_line(&mut response))
+            .await
+            .unwrap()
+            .unwrap();
+        let response: RpcResponse = serde_json::from_str(&response).unwrap();
+        assert_eq!(response.error.unwrap().code, INVALID_REQUEST);
+        // 连接已关闭，未读的输入可能使其以重置结束
+        let mut rest = String::new();
+        assert!(!matches!(reader.read_line(&mut rest).await, Ok(n) if n > 0));
+        tokio::time::timeout(Duration::from_secs(10), sending).await.unwrap().unwrap();
+
+        // 其他连接不受影响
+        let request = RpcRequest::new(1, "list_tasks", Value::Null);
+        let response = raw_call(&path, &serde_json::to_string(&request).unwrap()).await;
+        assert!(response.error.is_none());
+    }
+
+    #[test]
+    fn test_scheduler_error_roundtrip() {
+        let id = Uuid::new_v4();
+        let error: SchedulerError = RpcError::from(SchedulerError::RunInstanceNotFound(id)).into();
+        assert!(matches!(error, SchedulerError::RunInstanceNotFound(got) if got == id));
+
+        let error: SchedulerError = RpcError::from(SchedulerError::WorkflowNotFound(id)).into();
+        assert!(matches!(error, SchedulerError::WorkflowNotFound(got) if got == id));
+
+        let error: SchedulerError =
+            RpcError::from(SchedulerError::InvalidParameter("Task is not running".to_string())).into();
+        assert_eq!(error.to_string(), "Invalid parameter: Task is not running");
+    }
+}
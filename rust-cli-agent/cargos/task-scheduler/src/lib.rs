@@ -3,11 +3,20 @@
 //! 基于 cron 表达式的定时任务调度系统
 //!
 //! # 特性
-//! - Cron 表达式定时执行
+//! - Cron 表达式定时执行 (5/6/7 字段、@daily 等宏、L/W/#)
+//!   按旧版语法 (星期 1 为周日，日和周须同时满足) 保存的任务和工作流在加载时迁移
+//! - 按任务时区调度，明确处理夏令时切换
 //! - 任务持久化存储
 //! - 任务运行实例管理
 //! - 完整的日志系统
+//! - 任务依赖和 DAG 工作流
+//! - 重叠策略和并发上限
+//! - 运行记录保留策略和清理
+//! - 任务和运行历史的导出、导入
+//! - 声明式任务清单 (TOML / YAML)
+//! - 任务结果通知 (webhook、邮件、桌面通知、文件)
 //! - LLM Function Call 支持
+//! - MCP 服务端 (stdio 和 streamable HTTP)
 //! - 系统任务调度器集成
 //!
 //! # 使用示例
@@ -24,10 +33,24 @@
 //! ```
 
 pub mod types;
+pub mod action;
+pub mod misfire;
+pub mod timezone;
+pub mod retry;
+pub mod retention;
+pub mod bundle;
+pub mod manifest;
+pub mod concurrency;
+pub mod workflow;
 pub mod storage;
+pub mod sqlite_storage;
 pub mod llm;
+pub mod mcp;
 pub mod hooks;
+pub mod notify;
 pub mod error;
+pub mod execution;
+pub mod ipc;
 pub mod scheduler;
 pub mod cron_scheduler;
 pub mod persistent_scheduler;
@@ -43,27 +66,87 @@ pub use error::{SchedulerError, Result};
 pub use scheduler::TaskScheduler;
 pub use scheduler::AsyncTaskExecutor;
 
+// Re-export task actions
+pub use action::{ActionFactory, ActionRegistry, TaskAction};
+
+// Re-export misfire handling
+pub use misfire::{MisfireConfig, MisfirePlan, MisfirePolicy};
+
+// Re-export timezone support
+pub use timezone::{normalize_timezone, parse_timezone, ZonedSchedule};
+pub use chrono_tz::Tz;
+pub use cron_expression::{CronError, CronField, CronSchedule};
+
+// Re-export retry policy
+pub use retry::{Backoff, FailureKind, RetryOn, RetryPolicy};
+
+// Re-export retention policy
+pub use retention::{PruneReport, RetentionPolicy, RunHistorySummary};
+
+// Re-export schedule bundles
+pub use bundle::{
+    BundleHeader, ConflictStrategy, ExportOptions, ImportAction, ImportOptions, ImportReport, ImportedTask,
+    ScheduleBundle, BUNDLE_FORMAT, BUNDLE_VERSION,
+};
+
+// Re-export declarative task manifests
+pub use manifest::{
+    ChangeKind, FieldChange, ManifestError, ManifestFormat, ManifestPlan, ManifestRetry, ManifestTask, PlanSummary,
+    PlannedChange, TaskManifest, TaskSpec,
+};
+
+// Re-export concurrency control
+pub use concurrency::{ConcurrencyLimits, OverlapPolicy};
+
+// Re-export task dependencies and workflows
+pub use workflow::{
+    StepRun, TaskDependency, Workflow, WorkflowBriefing, WorkflowRun, WorkflowStep,
+};
+
+// Re-export executor and execution context
+pub use execution::{
+    blocking_executor_fn, executor_fn, TaskExecutor, TaskRunContext, TaskRunControl, TaskRunEvent,
+};
+
+// Re-export daemon control protocol
+pub use ipc::{HelloResult, RpcError, RpcRequest, RpcResponse, PROTOCOL_VERSION};
+#[cfg(unix)]
+pub use ipc::DaemonClient;
+
 // Re-export storage types
 pub use storage::{
-    MemorySchedulerStorage, SchedulerStorage, SchedulerStorageError, SledSchedulerStorage,
-    StorageResult,
+    open_scheduler_storage, MemorySchedulerStorage, Page, RangeQuery, SchedulerStorage, SchedulerStorageError,
+    SledSchedulerStorage, StorageResult,
 };
+pub use sqlite_storage::SqliteSchedulerStorage;
 
 // Re-export LLM types
 pub use llm::{
     CallToolRequest, CallToolResponse, SchedulerToolAdapter, Tool, ToolContent,
 };
 
+// Re-export tool registry
+pub use tools::{ToolError, ToolProvider, ToolRegistry};
+
+// Re-export MCP server
+pub use mcp::McpServer;
+
 // Re-export hook types
 pub use hooks::{
-    ErrorRecoverySuggestion, HookError, HookManager, HookResult,
+    BeforeRunDecision, ErrorRecoverySuggestion, HookError, HookManager, HookResult,
     OnTaskAfterRun, OnTaskBeforeRun, OnTaskError, OnTaskFailure, OnTaskSuccess,
     TaskExecutionContext, TaskResultContext,
 };
 
+// Re-export notification types
+pub use notify::{
+    DesktopSink, FileSink, Notification, NotificationRoute, NotificationRouter, NotificationSink,
+    SmtpSettings, SmtpSink, TaskOutcome, WebhookSink,
+};
+
 // Re-export scheduler implementations
 pub use cron_scheduler::CronTaskScheduler;
-pub use persistent_scheduler::PersistentCronTaskScheduler;
+pub use persistent_scheduler::{PersistentCronTaskScheduler, ReloadSummary};
 
 // Re-export system integration
 pub use system_integration::SystemTaskManager;
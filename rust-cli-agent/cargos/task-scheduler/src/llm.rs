@@ -9,62 +9,7 @@ use async_trait::async_trait;
 use crate::types::*;
 use crate::{SchedulerError, TaskScheduler};
 
-/// 工具定义
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct Tool {
-    /// 工具名称
-    pub name: String,
-    /// 工具描述
-    pub description: String,
-    /// 参数 Schema
-    pub input_schema: serde_json::Value,
-}
-
-/// 工具调用请求
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct CallToolRequest {
-    /// 工具名称
-    pub name: String,
-    /// 参数
-    pub arguments: serde_json::Value,
-}
-
-/// 工具调用响应
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct CallToolResponse {
-    /// 是否成功
-    pub success: bool,
-    /// 结果内容
-    pub content: Vec<ToolContent>,
-    /// 错误信息
-    pub error: Option<String>,
-}
-
-/// 工具内容
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct ToolContent {
-    /// 内容类型
-    #[serde(rename = "type")]
-    pub content_type: String,
-    /// 内容文本
-    pub text: Option<String>,
-}
-
-impl ToolContent {
-    pub fn text(text: String) -> Self {
-        Self {
-            content_type: "text".to_string(),
-            text: Some(text),
-        }
-    }
-
-    pub fn error(text: String) -> Self {
-        Self {
-            content_type: "text".to_string(),
-            text: Some(text),
-        }
-    }
-}
+pub use tools::{CallToolRequest, CallToolResponse, Tool, ToolContent, ToolError, ToolProvider};
 
 /// LLM 工具适配器
 pub struct SchedulerToolAdapter<S: TaskScheduler> {
@@ -96,7 +41,7 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
                         },
                         "cron": {
                             "type": "string",
-                            "description": "Cron 表达式 (6字段: 秒 分 时 日 月 周)"
+                            "description": "Cron 表达式：5 字段 (分 时 日 月 周)、6 字段 (秒 分 时 日 月 周)、7 字段 (末尾加年) 或 @daily、@hourly 等宏；日和周字段支持 L、W、#，星期 0 和 7 为周日"
                         },
                         "description": {
                             "type": "string",
@@ -105,6 +50,49 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
                         "content": {
                             "type": "string",
                             "description": "任务内容/命令"
+                        },
+                        "command": {
+                            "type": "string",
+                            "description": "到期时执行的 shell 命令，随任务持久化"
+                        },
+                        "misfire_policy": {
+                            "type": "string",
+                            "description": "错过运行的处理策略: skip (跳过)、run_once (补跑一次)、run_all:<N> (逐次补跑，最多 N 次)"
+                        },
+                        "misfire_grace_secs": {
+                            "type": "integer",
+                            "description": "宽限期 (秒)，晚于计划时间不超过宽限期的运行照常补跑"
+                        },
+                        "max_attempts": {
+                            "type": "integer",
+                            "description": "失败时最多尝试次数 (含首次运行)，1 表示不重试"
+                        },
+                        "retry_backoff": {
+                            "type": "string",
+                            "description": "重试间隔: fixed:<秒> 或 exponential:<初始秒>[:<最大秒>[:<倍数>]]"
+                        },
+                        "retry_on": {
+                            "type": "string",
+                            "description": "需要重试的失败类型，逗号分隔: exit (非零退出)、timeout (超时)、error (执行器错误)、all"
+                        },
+                        "depends_on": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "依赖的任务，格式 <任务 ID>[:<窗口秒数>]；定时触发时依赖任务须在窗口内 (默认 24 小时) 成功运行过，否则本次运行被跳过"
+                        },
+                        "overlap": {
+                            "type": "string",
+                            "enum": ["allow", "skip", "queue", "replace"],
+                            "description": "上一次运行尚未结束时的处理: allow (同时运行)、skip (跳过本次)、queue (排队等待)、replace (停止上一次运行)"
+                        },
+                        "tags": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "任务标签，用于按标签限制并发"
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "description": "解释 cron 表达式所用的 IANA 时区，如 Asia/Shanghai；设为 UTC 时按 UTC 调度"
                         }
                     },
                     "required": ["title", "name", "cron"]
@@ -248,6 +236,45 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
                         "enabled": {
                             "type": "boolean",
                             "description": "是否启用"
+                        },
+                        "misfire_policy": {
+                            "type": "string",
+                            "description": "错过运行的处理策略: skip (跳过)、run_once (补跑一次)、run_all:<N> (逐次补跑，最多 N 次)"
+                        },
+                        "misfire_grace_secs": {
+                            "type": "integer",
+                            "description": "宽限期 (秒)，晚于计划时间不超过宽限期的运行照常补跑"
+                        },
+                        "max_attempts": {
+                            "type": "integer",
+                            "description": "失败时最多尝试次数 (含首次运行)，1 表示不重试"
+                        },
+                        "retry_backoff": {
+                            "type": "string",
+                            "description": "重试间隔: fixed:<秒> 或 exponential:<初始秒>[:<最大秒>[:<倍数>]]"
+                        },
+                        "retry_on": {
+                            "type": "string",
+                            "description": "需要重试的失败类型，逗号分隔: exit (非零退出)、timeout (超时)、error (执行器错误)、all"
+                        },
+                        "depends_on": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "依赖的任务，格式 <任务 ID>[:<窗口秒数>]；定时触发时依赖任务须在窗口内 (默认 24 小时) 成功运行过，否则本次运行被跳过"
+                        },
+                        "overlap": {
+                            "type": "string",
+                            "enum": ["allow", "skip", "queue", "replace"],
+                            "description": "上一次运行尚未结束时的处理: allow (同时运行)、skip (跳过本次)、queue (排队等待)、replace (停止上一次运行)"
+                        },
+                        "tags": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "任务标签，用于按标签限制并发"
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "description": "解释 cron 表达式所用的 IANA 时区，如 Asia/Shanghai；设为 UTC 时按 UTC 调度"
                         }
                     },
                     "required": ["id"]
@@ -303,6 +330,103 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
                     "required": ["run_id"]
                 }),
             },
+            // 添加工作流
+            Tool {
+                name: "add_workflow".to_string(),
+                description: "添加一个由多个任务组成的工作流 (有向无环图)，前序步骤全部成功后才运行后续步骤".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "description": "工作流名称"
+                        },
+                        "description": {
+                            "type": "string",
+                            "description": "工作流描述"
+                        },
+                        "cron": {
+                            "type": "string",
+                            "description": "定时运行的 Cron 表达式，省略时只能手动运行"
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "description": "解释 cron 表达式所用的 IANA 时区，如 Asia/Shanghai；省略时使用默认时区"
+                        },
+                        "steps": {
+                            "type": "array",
+                            "description": "步骤列表。步骤的标准输出以 <步骤名>_output 参数传给直接依赖它的步骤",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "name": {
+                                        "type": "string",
+                                        "description": "步骤名称 (字母、数字、下划线)"
+                                    },
+                                    "task_id": {
+                                        "type": "string",
+                                        "description": "执行的任务 ID"
+                                    },
+                                    "depends_on": {
+                                        "type": "array",
+                                        "items": { "type": "string" },
+                                        "description": "前序步骤名称"
+                                    },
+                                    "params": {
+                                        "type": "object",
+                                        "description": "本步骤额外的参数 (key-value)"
+                                    }
+                                },
+                                "required": ["name", "task_id"]
+                            }
+                        }
+                    },
+                    "required": ["name", "steps"]
+                }),
+            },
+            // 获取工作流列表
+            Tool {
+                name: "list_workflows".to_string(),
+                description: "列出所有工作流".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            // 手动运行工作流
+            Tool {
+                name: "run_workflow".to_string(),
+                description: "手动运行一个工作流，等待所有步骤结束后返回各步骤状态".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "string",
+                            "description": "工作流 ID"
+                        },
+                        "user_params": {
+                            "type": "object",
+                            "description": "传给每个步骤的参数 (key-value)"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            },
+            // 获取工作流简报
+            Tool {
+                name: "get_workflow_briefing".to_string(),
+                description: "获取工作流的步骤定义和最近运行中各步骤的状态".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "string",
+                            "description": "工作流 ID"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            },
         ]
     }
 
@@ -321,28 +445,32 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
             "remove_task" => self.call_remove_task(request.arguments).await,
             "get_task_logs" => self.call_get_task_logs(request.arguments).await,
             "get_run_instance" => self.call_get_run_instance(request.arguments).await,
-            _ => Err(crate::SchedulerError::InvalidParameter(format!(
-                "Unknown tool: {}",
-                request.name
-            ))),
+            "add_workflow" => self.call_add_workflow(request.arguments).await,
+            "list_workflows" => self.call_list_workflows().await,
+            "run_workflow" => self.call_run_workflow(request.arguments).await,
+            "get_workflow_briefing" => self.call_get_workflow_briefing(request.arguments).await,
+            _ => return CallToolResponse::failure(ToolError::UnknownTool(request.name)),
         };
 
-        match result {
-            Ok(output) => CallToolResponse {
-                success: true,
-                content: vec![ToolContent::text(output)],
-                error: None,
-            },
-            Err(e) => CallToolResponse {
-                success: false,
-                content: vec![],
-                error: Some(e.to_string()),
-            },
-        }
+        CallToolResponse::from_result(result)
     }
 
     // 工具调用实现
 
+    /// 为新建任务设置错过运行、失败重试、依赖和并发等策略
+    async fn apply_policies(
+        &self,
+        task: crate::types::ScheduledTask,
+        policies: TaskUpdateRequest,
+    ) -> Result<crate::types::ScheduledTask, crate::SchedulerError> {
+        if policies.is_empty() {
+            return Ok(task);
+        }
+        self.scheduler
+            .update_task(TaskUpdateRequest { id: task.id, ..policies })
+            .await
+    }
+
     async fn call_add_task(&self, args: serde_json::Value) -> Result<String, crate::SchedulerError> {
         #[derive(serde::Deserialize)]
         struct AddTaskInput {
@@ -351,14 +479,61 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
             cron: String,
             description: Option<String>,
             content: Option<String>,
+            command: Option<String>,
+            misfire_policy: Option<String>,
+            misfire_grace_secs: Option<u64>,
+            max_attempts: Option<u32>,
+            retry_backoff: Option<String>,
+            retry_on: Option<String>,
+            depends_on: Option<Vec<String>>,
+            overlap: Option<String>,
+            tags: Option<Vec<String>>,
+            timezone: Option<String>,
         }
 
         let input: AddTaskInput = serde_json::from_value(args)
             .map_err(|e| crate::SchedulerError::InvalidParameter(e.to_string()))?;
+        let misfire = crate::misfire::MisfireConfig::default()
+            .with_overrides(input.misfire_policy.as_deref(), input.misfire_grace_secs)?;
+        let retry = crate::retry::RetryPolicy::default().with_overrides(
+            input.max_attempts,
+            input.retry_backoff.as_deref(),
+            input.retry_on.as_deref(),
+        )?;
+        // 时区在创建任务前校验，避免留下未设置时区的任务
+        if let Some(timezone) = &input.timezone {
+            crate::timezone::parse_timezone(timezone)?;
+        }
+        let policies = TaskUpdateRequest {
+            misfire,
+            retry,
+            depends_on: parse_dependencies(input.depends_on)?,
+            overlap: input.overlap.as_deref().map(str::parse).transpose()?,
+            tags: input.tags,
+            timezone: input.timezone,
+            ..Default::default()
+        };
 
-        let executor = Arc::new(|_task_id: uuid::Uuid, _params: HashMap<String, String>| {
+        if let Some(command) = input.command {
+            let task = self
+                .scheduler
+                .add_task_with_action(
+                    input.title,
+                    input.name,
+                    input.description,
+                    input.content,
+                    input.cron,
+                    crate::action::TaskAction::shell(command),
+                    false,
+                )
+                .await?;
+            let task = self.apply_policies(task, policies).await?;
+            return Ok(format!("任务已添加: {} ({})", task.title, task.id));
+        }
+
+        let executor = crate::execution::executor_fn(|task_id, _params, _ctx| async move {
             Ok(TaskExecutionResult {
-                task_id: _task_id,
+                task_id,
                 run_instance_id: None,
                 started_at: chrono::Utc::now(),
                 completed_at: Some(chrono::Utc::now()),
@@ -367,6 +542,8 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
                 stdout: None,
                 stderr: None,
                 exit_code: Some(0),
+                signal: None,
+                timed_out: false,
             })
         });
 
@@ -381,6 +558,7 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
                 executor,
             )
             .await?;
+        let task = self.apply_policies(task, policies).await?;
 
         Ok(format!("任务已添加: {} ({})", task.title, task.id))
     }
@@ -449,10 +627,42 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
 
         let briefing = self.scheduler.get_task_briefing(task_id).await?;
 
-        Ok(format!(
-            "任务: {}\n状态: {}\nCron: {}\n运行次数: {}",
-            briefing.title, briefing.status, briefing.cron_expression, briefing.run_count
-        ))
+        let mut text = format!(
+            "任务: {}\n状态: {}\nCron: {}\n运行次数: {}\n错过运行策略: {} (宽限 {} 秒)\n过期运行: {}\n最近一次运行尝试: {}/{}",
+            briefing.title,
+            briefing.status,
+            briefing.cron_expression,
+            briefing.run_count,
+            briefing.misfire.policy,
+            briefing.misfire.grace_secs,
+            briefing.expired_count,
+            briefing.latest_attempts,
+            briefing.retry.max_attempts
+        );
+        if let Some(timezone) = &briefing.timezone {
+            text.push_str(&format!("\n时区: {}", timezone));
+        }
+        text.push_str(&format!("\n重叠策略: {}", briefing.overlap));
+        if !briefing.tags.is_empty() {
+            text.push_str(&format!("\n标签: {}", briefing.tags.join(", ")));
+        }
+        if !briefing.depends_on.is_empty() {
+            let deps: Vec<String> = briefing.depends_on.iter().map(|d| d.to_string()).collect();
+            text.push_str(&format!("\n依赖: {}", deps.join(", ")));
+        }
+        if briefing.skipped_count > 0 {
+            text.push_str(&format!("\n跳过的运行: {}", briefing.skipped_count));
+        }
+        for instance in &briefing.recent_instances {
+            text.push_str(&format!(
+                "\n- {} {} {} (第 {} 次尝试)",
+                instance.started_at.format("%Y-%m-%d %H:%M:%S"),
+                instance.status,
+                instance.id,
+                instance.attempt
+            ));
+        }
+        Ok(text)
     }
 
     async fn call_run_task(&self, args: serde_json::Value) -> Result<String, crate::SchedulerError> {
@@ -534,6 +744,15 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
             content: Option<String>,
             cron: Option<String>,
             enabled: Option<bool>,
+            misfire_policy: Option<String>,
+            misfire_grace_secs: Option<u64>,
+            max_attempts: Option<u32>,
+            retry_backoff: Option<String>,
+            retry_on: Option<String>,
+            depends_on: Option<Vec<String>>,
+            overlap: Option<String>,
+            tags: Option<Vec<String>>,
+            timezone: Option<String>,
         }
 
         let input: UpdateTaskInput = serde_json::from_value(args)
@@ -542,6 +761,16 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
         let task_id = uuid::Uuid::parse_str(&input.id)
             .map_err(|e| crate::SchedulerError::InvalidParameter(e.to_string()))?;
 
+        let current = self.scheduler.get_task(task_id).await?;
+        let misfire = current
+            .misfire
+            .with_overrides(input.misfire_policy.as_deref(), input.misfire_grace_secs)?;
+        let retry = current.retry.with_overrides(
+            input.max_attempts,
+            input.retry_backoff.as_deref(),
+            input.retry_on.as_deref(),
+        )?;
+
         let request = TaskUpdateRequest {
             id: task_id,
             title: input.title,
@@ -549,6 +778,13 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
             content: input.content,
             cron_expression: input.cron,
             enabled: input.enabled,
+            misfire,
+            retry,
+            depends_on: parse_dependencies(input.depends_on)?,
+            overlap: input.overlap.as_deref().map(str::parse).transpose()?,
+            tags: input.tags,
+            action: None,
+            timezone: input.timezone,
         };
 
         let task = self.scheduler.update_task(request).await?;
@@ -639,6 +875,155 @@ impl<S: TaskScheduler> SchedulerToolAdapter<S> {
             result_str
         ))
     }
+
+    async fn call_add_workflow(&self, args: serde_json::Value) -> Result<String, crate::SchedulerError> {
+        #[derive(serde::Deserialize)]
+        struct AddWorkflowInput {
+            name: String,
+            description: Option<String>,
+            cron: Option<String>,
+            timezone: Option<String>,
+            steps: Vec<crate::workflow::WorkflowStep>,
+        }
+
+        let input: AddWorkflowInput = serde_json::from_value(args)
+            .map_err(|e| crate::SchedulerError::InvalidParameter(e.to_string()))?;
+
+        let mut workflow = crate::workflow::Workflow::new(input.name, input.steps);
+        workflow.description = input.description;
+        workflow.cron_expression = input.cron;
+        workflow.timezone = input.timezone;
+        let workflow = self.scheduler.add_workflow(workflow).await?;
+
+        Ok(format!("工作流已添加: {} ({})", workflow.name, workflow.id))
+    }
+
+    async fn call_list_workflows(&self) -> Result<String, crate::SchedulerError> {
+        let workflows = self.scheduler.list_workflows().await?;
+        if workflows.is_empty() {
+            return Ok("没有工作流".to_string());
+        }
+
+        let mut output = String::new();
+        for workflow in workflows {
+            output.push_str(&format!(
+                "- {} ({}) - {} 个步骤 - {}\n",
+                workflow.name,
+                workflow.id,
+                workflow.steps.len(),
+                workflow.cron_expression.as_deref().unwrap_or("手动运行")
+            ));
+        }
+        Ok(output)
+    }
+
+    async fn call_run_workflow(&self, args: serde_json::Value) -> Result<String, crate::SchedulerError> {
+        #[derive(serde::Deserialize)]
+        struct RunWorkflowInput {
+            id: String,
+            user_params: Option<HashMap<String, String>>,
+        }
+
+        let input: RunWorkflowInput = serde_json::from_value(args)
+            .map_err(|e| crate::SchedulerError::InvalidParameter(e.to_string()))?;
+
+        let workflow_id = uuid::Uuid::parse_str(&input.id)
+            .map_err(|e| crate::SchedulerError::InvalidParameter(e.to_string()))?;
+
+        let run = self
+            .scheduler
+            .run_workflow(workflow_id, input.user_params.unwrap_or_default())
+            .await?;
+
+        Ok(format!(
+            "工作流运行结束: {} ({})\n{}",
+            run.id,
+            run.status,
+            format_step_runs(&run)
+        ))
+    }
+
+    async fn call_get_workflow_briefing(&self, args: serde_json::Value) -> Result<String, crate::SchedulerError> {
+        #[derive(serde::Deserialize)]
+        struct GetWorkflowBriefingInput {
+            id: String,
+        }
+
+        let input: GetWorkflowBriefingInput = serde_json::from_value(args)
+            .map_err(|e| crate::SchedulerError::InvalidParameter(e.to_string()))?;
+
+        let workflow_id = uuid::Uuid::parse_str(&input.id)
+            .map_err(|e| crate::SchedulerError::InvalidParameter(e.to_string()))?;
+
+        let briefing = self.scheduler.get_workflow_briefing(workflow_id).await?;
+        let workflow = &briefing.workflow;
+
+        let mut text = format!(
+            "工作流: {}\nCron: {}\n运行次数: {}",
+            workflow.name,
+            workflow.cron_expression.as_deref().unwrap_or("手动运行"),
+            briefing.run_count
+        );
+        if let Some(timezone) = &workflow.timezone {
+            text.push_str(&format!("\n时区: {}", timezone));
+        }
+        text.push_str("\n步骤:");
+        for step in &workflow.steps {
+            text.push_str(&format!("\n- {} (任务 {})", step.name, step.task_id));
+            if !step.depends_on.is_empty() {
+                text.push_str(&format!(" 依赖 {}", step.depends_on.join(", ")));
+            }
+        }
+        match briefing.latest_run() {
+            Some(run) => text.push_str(&format!(
+                "\n最近一次运行: {} {} ({})\n{}",
+                run.started_at.format("%Y-%m-%d %H:%M:%S"),
+                run.status,
+                run.id,
+                format_step_runs(run)
+            )),
+            None => text.push_str("\n尚未运行"),
+        }
+        Ok(text)
+    }
+}
+
+/// 解析 `<任务 ID>[:<窗口秒数>]` 形式的依赖列表
+fn parse_dependencies(
+    depends_on: Option<Vec<String>>,
+) -> Result<Option<Vec<crate::workflow::TaskDependency>>, crate::SchedulerError> {
+    depends_on
+        .map(|deps| deps.iter().map(|d| d.parse()).collect())
+        .transpose()
+}
+
+/// 每个步骤一行的运行状态
+fn format_step_runs(run: &crate::workflow::WorkflowRun) -> String {
+    run.steps
+        .iter()
+        .map(|step| {
+            let mut line = format!("- {}: {}", step.name, step.status);
+            if step.attempts > 1 {
+                line.push_str(&format!(" (尝试 {} 次)", step.attempts));
+            }
+            if let Some(error) = &step.error {
+                line.push_str(&format!(" - {}", error));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[async_trait]
+impl<S: TaskScheduler + 'static> ToolProvider for SchedulerToolAdapter<S> {
+    fn tools(&self) -> Vec<Tool> {
+        self.get_tools()
+    }
+
+    async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+        self.call_tool(request).await
+    }
 }
 
 #[cfg(test)]
@@ -689,4 +1074,64 @@ mod tests {
         assert!(response.success);
         assert!(response.content.len() > 0);
     }
+
+    #[tokio::test]
+    async fn test_workflow_tools() {
+        let scheduler = Arc::new(CronTaskScheduler::new().await.unwrap());
+        let adapter = SchedulerToolAdapter::new(scheduler.clone());
+        let call = |name: &str, arguments: serde_json::Value| {
+            adapter.call_tool(CallToolRequest {
+                name: name.to_string(),
+                arguments,
+            })
+        };
+
+        let mut task_ids = Vec::new();
+        for name in ["fetch", "publish"] {
+            let response = call(
+                "add_task",
+                serde_json::json!({ "title": name, "name": name, "cron": "0 0 0 1 1 *" }),
+            )
+            .await;
+            assert!(response.success);
+        }
+        for task in scheduler.list_tasks().await.unwrap() {
+            task_ids.push((task.name.clone(), task.id.to_string()));
+        }
+        let id_of = |name: &str| task_ids.iter().find(|(n, _)| n == name).unwrap().1.clone();
+
+        let response = call(
+            "add_workflow",
+            serde_json::json!({
+                "name": "pipeline",
+                "steps": [
+                    { "name": "fetch", "task_id": id_of("fetch") },
+                    { "name": "publish", "task_id": id_of("publish"), "depends_on": ["fetch"] }
+                ]
+            }),
+        )
+        .await;
+        assert!(response.success, "{:?}", response.error);
+        let workflow_id = scheduler.list_workflows().await.unwrap()[0].id.to_string();
+
+        let response = call("run_workflow", serde_json::json!({ "id": workflow_id })).await;
+        assert!(response.success);
+        let text = response.content[0].text.clone().unwrap();
+        assert!(text.contains("- fetch: Completed"));
+        assert!(text.contains("- publish: Completed"));
+
+        let response = call("get_workflow_briefing", serde_json::json!({ "id": workflow_id })).await;
+        let text = response.content[0].text.clone().unwrap();
+        assert!(text.contains("publish (任务"));
+        assert!(text.contains("依赖 fetch"));
+        assert!(text.contains("- publish: Completed"));
+
+        // 依赖格式错误
+        let response = call(
+            "update_task",
+            serde_json::json!({ "id": id_of("publish"), "depends_on": ["bogus"] }),
+        )
+        .await;
+        assert!(!response.success);
+    }
 }
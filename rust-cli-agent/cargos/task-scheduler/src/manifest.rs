@@ -0,0 +1,712 @@
+//! 声明式任务清单
+//!
+//! 用 TOML 或 YAML 描述一组任务，按任务名称与调度器中的任务对比，
+//! 生成新建、更新和删除计划后通过 [`TaskScheduler`] 应用
+//!
+//! ```toml
+//! [[tasks]]
+//! name = "backup"
+//! cron = "0 0 2 * * *"
+//! timezone = "Asia/Shanghai"
+//! command = "tar czf /tmp/home.tgz ~"
+//! env = { GZIP = "-9" }
+//! tags = ["io"]
+//! retry = { max_attempts = 3, backoff = "fixed:60" }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::action::TaskAction;
+use crate::cron_scheduler::{normalize_tags, validate_cron_expression};
+use crate::error::{Result, SchedulerError};
+use crate::retry::RetryPolicy;
+use crate::scheduler::TaskScheduler;
+use crate::timezone::parse_timezone;
+use crate::types::{ScheduledTask, TaskUpdateRequest};
+
+/// 清单文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Toml,
+    Yaml,
+}
+
+impl ManifestFormat {
+    /// 按扩展名判断格式，`.yaml` / `.yml` 为 YAML，其余为 TOML
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+            Some("yaml") | Some("yml") => ManifestFormat::Yaml,
+            _ => ManifestFormat::Toml,
+        }
+    }
+}
+
+/// 任务清单
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskManifest {
+    #[serde(default)]
+    pub tasks: Vec<ManifestTask>,
+}
+
+/// 清单中的一个任务
+///
+/// `command` 是 shell 动作的简写，与 `action` 二选一。未填写的标题、描述和时区不受清单管理
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestTask {
+    /// 任务名称，清单按名称与现有任务对应
+    pub name: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Cron 表达式
+    pub cron: String,
+    /// IANA 时区，新建任务未填写时使用配置的默认时区
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// shell 命令
+    #[serde(default)]
+    pub command: Option<String>,
+    /// 任务动作
+    #[serde(default)]
+    pub action: Option<TaskAction>,
+    /// 命令的环境变量，合并到 shell / program 动作
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub retry: Option<ManifestRetry>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// 清单中的重试策略，格式与 `--backoff`、`--retry-on` 参数相同
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestRetry {
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub backoff: Option<String>,
+    #[serde(default)]
+    pub retry_on: Option<String>,
+}
+
+/// 清单中某个任务的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestError {
+    /// 任务在清单中的位置 (从 0 开始)
+    pub index: usize,
+    pub name: String,
+    pub message: String,
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tasks[{}] ({}): {}", self.index, self.name, self.message)
+    }
+}
+
+/// 校验后的任务定义
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskSpec {
+    pub name: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub cron_expression: String,
+    /// 规范化的时区名称，UTC 写作 "UTC"
+    pub timezone: Option<String>,
+    pub action: TaskAction,
+    pub retry: RetryPolicy,
+    pub tags: Vec<String>,
+    pub enabled: bool,
+}
+
+impl ManifestTask {
+    /// 校验并合并简写字段
+    fn resolve(&self) -> std::result::Result<TaskSpec, String> {
+        if self.name.trim().is_empty() || self.name.chars().any(char::is_whitespace) {
+            return Err("name must be non-empty and contain no whitespace".to_string());
+        }
+        validate_cron_expression(&self.cron).map_err(|e| e.to_string())?;
+        let timezone = self
+            .timezone
+            .as_deref()
+            .map(parse_timezone)
+            .transpose()
+            .map_err(|e| e.to_string())?
+            .map(|tz| tz.name().to_string());
+
+        let mut action = match (&self.command, &self.action) {
+            (Some(command), None) => TaskAction::shell(command.clone()),
+            (None, Some(action)) => action.clone(),
+            (Some(_), Some(_)) => return Err("command and action are mutually exclusive".to_string()),
+            (None, None) => return Err("either command or action is required".to_string()),
+        };
+        if !self.env.is_empty() {
+            match &mut action {
+                TaskAction::Shell { env, .. } | TaskAction::Program { env, .. } => {
+                    if let Some(key) = self.env.keys().find(|key| env.contains_key(*key)) {
+                        return Err(format!("env {} is also set in action", key));
+                    }
+                    env.extend(self.env.clone());
+                }
+                TaskAction::Http { .. } => return Err("env is not supported for http actions".to_string()),
+            }
+        }
+
+        let retry = match &self.retry {
+            Some(retry) => RetryPolicy::default()
+                .with_overrides(Some(retry.max_attempts), retry.backoff.as_deref(), retry.retry_on.as_deref())
+                .map_err(|e| e.to_string())?
+                .unwrap_or_default(),
+            None => RetryPolicy::default(),
+        };
+
+        Ok(TaskSpec {
+            name: self.name.clone(),
+            title: self.title.clone(),
+            description: self.description.clone(),
+            cron_expression: self.cron.clone(),
+            timezone,
+            action,
+            retry,
+            tags: normalize_tags(self.tags.clone()),
+            enabled: self.enabled,
+        })
+    }
+}
+
+impl TaskManifest {
+    /// 解析清单文本
+    pub fn parse(text: &str, format: ManifestFormat) -> Result<Self> {
+        match format {
+            ManifestFormat::Toml => toml::from_str(text).map_err(|e| SchedulerError::InvalidParameter(e.to_string())),
+            ManifestFormat::Yaml => {
+                serde_yaml::from_str(text).map_err(|e| SchedulerError::InvalidParameter(e.to_string()))
+            }
+        }
+    }
+
+    /// 读取清单文件，按扩展名判断格式
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            SchedulerError::InvalidParameter(format!("Cannot read manifest {}: {}", path.display(), e))
+        })?;
+        Self::parse(&text, ManifestFormat::from_path(path))
+    }
+
+    /// 校验所有任务，返回全部错误
+    pub fn validate(&self) -> std::result::Result<Vec<TaskSpec>, Vec<ManifestError>> {
+        let mut specs = Vec::new();
+        let mut errors = Vec::new();
+        let mut names = HashSet::new();
+        for (index, task) in self.tasks.iter().enumerate() {
+            let error = |message: String| ManifestError {
+                index,
+                name: task.name.clone(),
+                message,
+            };
+            if !names.insert(task.name.as_str()) {
+                errors.push(error("duplicate task name".to_string()));
+                continue;
+            }
+            match task.resolve() {
+                Ok(spec) => specs.push(spec),
+                Err(message) => errors.push(error(message)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(specs)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// 与现有任务对比生成计划
+    ///
+    /// `prune` 为 true 时清单中没有的任务计划删除，系统级任务除外
+    pub fn plan(&self, existing: &[ScheduledTask], prune: bool) -> Result<ManifestPlan> {
+        let specs = self.validate().map_err(|errors| {
+            let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            SchedulerError::InvalidParameter(format!("Invalid manifest: {}", messages.join("; ")))
+        })?;
+
+        let mut by_name: HashMap<&str, Vec<&ScheduledTask>> = HashMap::new();
+        for task in existing {
+            by_name.entry(task.name.as_str()).or_default().push(task);
+        }
+
+        let mut changes = Vec::new();
+        for spec in specs {
+            let change = match by_name.get(spec.name.as_str()).map(Vec::as_slice) {
+                None | Some([]) => PlannedChange {
+                    kind: ChangeKind::Create,
+                    name: spec.name.clone(),
+                    task_id: None,
+                    fields: spec.describe(),
+                    spec: Some(spec),
+                },
+                Some([task]) => {
+                    let fields = spec.diff(task);
+                    PlannedChange {
+                        kind: if fields.is_empty() { ChangeKind::Unchanged } else { ChangeKind::Update },
+                        name: spec.name.clone(),
+                        task_id: Some(task.id),
+                        fields,
+                        spec: Some(spec),
+                    }
+                }
+                Some(_) => {
+                    return Err(SchedulerError::InvalidParameter(format!(
+                        "Multiple existing tasks are named {}",
+                        spec.name
+                    )))
+                }
+            };
+            changes.push(change);
+        }
+
+        if prune {
+            let names: HashSet<String> = changes.iter().map(|c| c.name.clone()).collect();
+            let mut removed: Vec<&ScheduledTask> = existing
+                .iter()
+                .filter(|t| !t.is_system && !names.contains(&t.name))
+                .collect();
+            removed.sort_by(|a, b| a.name.cmp(&b.name));
+            changes.extend(removed.into_iter().map(|task| PlannedChange {
+                kind: ChangeKind::Delete,
+                name: task.name.clone(),
+                task_id: Some(task.id),
+                fields: Vec::new(),
+                spec: None,
+            }));
+        }
+
+        Ok(ManifestPlan { changes })
+    }
+}
+
+/// 计划中的变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Create,
+    Update,
+    Delete,
+    Unchanged,
+}
+
+impl fmt::Display for ChangeKind {
+    /// 计划输出中使用的符号
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Create => write!(f, "+"),
+            ChangeKind::Update => write!(f, "~"),
+            ChangeKind::Delete => write!(f, "-"),
+            ChangeKind::Unchanged => write!(f, " "),
+        }
+    }
+}
+
+/// 字段变化，新建时没有旧值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub from: Option<String>,
+    pub to: String,
+}
+
+/// 单个任务的变更
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedChange {
+    pub kind: ChangeKind,
+    pub name: String,
+    /// 现有任务 ID，新建时为 None
+    pub task_id: Option<Uuid>,
+    pub fields: Vec<FieldChange>,
+    /// 清单中的定义，删除时为 None
+    pub spec: Option<TaskSpec>,
+}
+
+/// 清单与现有任务的对比计划
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ManifestPlan {
+    pub changes: Vec<PlannedChange>,
+}
+
+/// 计划中各类变更的数量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+}
+
+impl ManifestPlan {
+    /// 各类变更的数量
+    pub fn summary(&self) -> PlanSummary {
+        let mut summary = PlanSummary::default();
+        for change in &self.changes {
+            match change.kind {
+                ChangeKind::Create => summary.created += 1,
+                ChangeKind::Update => summary.updated += 1,
+                ChangeKind::Delete => summary.deleted += 1,
+                ChangeKind::Unchanged => summary.unchanged += 1,
+            }
+        }
+        summary
+    }
+
+    /// 是否没有任何需要应用的变更
+    pub fn is_empty(&self) -> bool {
+        self.changes.iter().all(|c| c.kind == ChangeKind::Unchanged)
+    }
+
+    /// 通过调度器应用计划，返回新建任务的 ID (按计划顺序)
+    pub async fn apply(&self, scheduler: &dyn TaskScheduler) -> Result<Vec<Uuid>> {
+        let mut created = Vec::new();
+        for change in &self.changes {
+            match (change.kind, &change.spec, change.task_id) {
+                (ChangeKind::Create, Some(spec), _) => {
+                    let task = scheduler
+                        .add_task_with_action(
+                            spec.title.clone().unwrap_or_else(|| spec.name.clone()),
+                            spec.name.clone(),
+                            spec.description.clone(),
+                            None,
+                            spec.cron_expression.clone(),
+                            spec.action.clone(),
+                            false,
+                        )
+                        .await?;
+                    let request = TaskUpdateRequest {
+                        id: task.id,
+                        timezone: spec.timezone.clone().filter(|tz| *tz != task_timezone(&task)),
+                        retry: (spec.retry != task.retry).then_some(spec.retry),
+                        tags: (!spec.tags.is_empty()).then(|| spec.tags.clone()),
+                        enabled: (!spec.enabled).then_some(false),
+                        ..Default::default()
+                    };
+                    if !request.is_empty() {
+                        scheduler.update_task(request).await?;
+                    }
+                    created.push(task.id);
+                }
+                (ChangeKind::Update, Some(spec), Some(task_id)) => {
+                    let current = scheduler.get_task(task_id).await?;
+                    scheduler.update_task(spec.update_request(&current)).await?;
+                }
+                (ChangeKind::Delete, _, Some(task_id)) => scheduler.remove_task(task_id).await?,
+                _ => {}
+            }
+        }
+        Ok(created)
+    }
+}
+
+impl TaskSpec {
+    /// 新建任务时展示的字段
+    fn describe(&self) -> Vec<FieldChange> {
+        let defaults = TaskSpec {
+            title: None,
+            description: None,
+            timezone: None,
+            action: TaskAction::shell(""),
+            retry: RetryPolicy::default(),
+            tags: Vec::new(),
+            enabled: true,
+            ..self.clone()
+        };
+        let mut fields = vec![FieldChange {
+            field: "cron",
+            from: None,
+            to: self.cron_expression.clone(),
+        }];
+        fields.extend(self.changed_fields(&defaults).into_iter().filter(|f| f.field != "cron").map(|f| FieldChange {
+            from: None,
+            ..f
+        }));
+        fields
+    }
+
+    /// 与现有任务的差异
+    fn diff(&self, task: &ScheduledTask) -> Vec<FieldChange> {
+        let current = TaskSpec {
+            name: task.name.clone(),
+            title: Some(task.title.clone()),
+            description: task.description.clone(),
+            cron_expression: task.cron_expression.clone(),
+            timezone: Some(task_timezone(task)),
+            action: task.action.clone().unwrap_or_else(|| TaskAction::shell("")),
+            retry: task.retry,
+            tags: task.tags.clone(),
+            enabled: task.enabled,
+        };
+        let mut fields = self.changed_fields(&current);
+        if task.action.is_none() {
+            // 只登记了元数据的任务总是设置动作
+            if let Some(field) = fields.iter_mut().find(|f| f.field == "action") {
+                field.from = None;
+            } else {
+                fields.push(FieldChange {
+                    field: "action",
+                    from: None,
+                    to: describe_action(&self.action),
+                });
+            }
+        }
+        fields
+    }
+
+    /// 相对 `current` 变化的字段，未填写的标题、描述和时区不比较
+    fn changed_fields(&self, current: &TaskSpec) -> Vec<FieldChange> {
+        let mut fields = Vec::new();
+        let mut push = |field: &'static str, from: String, to: String| {
+            if from != to {
+                fields.push(FieldChange {
+                    field,
+                    from: Some(from),
+                    to,
+                });
+            }
+        };
+        if let Some(title) = &self.title {
+            push("title", current.title.clone().unwrap_or_default(), title.clone());
+        }
+        if let Some(description) = &self.description {
+            push("description", current.description.clone().unwrap_or_default(), description.clone());
+        }
+        push("cron", current.cron_expression.clone(), self.cron_expression.clone());
+        if let Some(timezone) = &self.timezone {
+            push("timezone", current.timezone.clone().unwrap_or_default(), timezone.clone());
+        }
+        if self.action != current.action {
+            let (from, to) = (describe_action(&current.action), describe_action(&self.action));
+            let (from_env, to_env) = (action_env(&current.action), action_env(&self.action));
+            if from == to && from_env == to_env {
+                // 只有超时、请求头等细节不同
+                push("action", action_json(&current.action), action_json(&self.action));
+            } else {
+                push("action", from, to);
+                push("env", from_env, to_env);
+            }
+        }
+        push("retry", describe_retry(&current.retry), describe_retry(&self.retry));
+        push("tags", current.tags.join(","), self.tags.join(","));
+        push("enabled", current.enabled.to_string(), self.enabled.to_string());
+        fields
+    }
+
+    /// 把现有任务更新为清单中的定义
+    fn update_request(&self, current: &ScheduledTask) -> TaskUpdateRequest {
+        TaskUpdateRequest {
+            id: current.id,
+            title: self.title.clone().filter(|t| *t != current.title),
+            description: self.description.clone().filter(|d| Some(d) != current.description.as_ref()),
+            cron_expression: Some(self.cron_expression.clone()).filter(|c| *c != current.cron_expression),
+            timezone: self.timezone.clone().filter(|tz| *tz != task_timezone(current)),
+            enabled: Some(self.enabled).filter(|e| *e != current.enabled),
+            retry: Some(self.retry).filter(|r| *r != current.retry),
+            tags: Some(self.tags.clone()).filter(|t| *t != current.tags),
+            action: Some(self.action.clone()).filter(|a| Some(a) != current.action.as_ref()),
+            ..Default::default()
+        }
+    }
+}
+
+/// 任务的时区名称，未设置时为 UTC
+fn task_timezone(task: &ScheduledTask) -> String {
+    task.timezone.clone().unwrap_or_else(|| "UTC".to_string())
+}
+
+fn describe_action(action: &TaskAction) -> String {
+    format!("{}: {}", action.kind(), action.summary())
+}
+
+/// 动作的环境变量，按名称排序
+fn action_env(action: &TaskAction) -> String {
+    let env: BTreeMap<&String, &String> = match action {
+        TaskAction::Shell { env, .. } | TaskAction::Program { env, .. } => env.iter().collect(),
+        TaskAction::Http { .. } => BTreeMap::new(),
+    };
+    env.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join(" ")
+}
+
+fn action_json(action: &TaskAction) -> String {
+    // Value 的对象键有序，输出稳定
+    serde_json::to_value(action).map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn describe_retry(retry: &RetryPolicy) -> String {
+    if retry.is_enabled() {
+        format!("max_attempts={}, backoff={}, retry_on={}", retry.max_attempts, retry.backoff, retry.retry_on)
+    } else {
+        "none".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistent_scheduler::PersistentCronTaskScheduler;
+    use tempfile::TempDir;
+
+    const MANIFEST: &str = r#"
+[[tasks]]
+name = "backup"
+cron = "0 0 2 * * *"
+command = "tar czf /tmp/home.tgz ~"
+env = { GZIP = "-9" }
+tags = ["io"]
+retry = { max_attempts = 3, backoff = "fixed:60" }
+
+[[tasks]]
+name = "ping"
+title = "Ping"
+cron = "0 */5 * * * *"
+action = { type = "http", url = "http://localhost:8080/health" }
+enabled = false
+"#;
+
+    #[test]
+    fn test_parse_toml_and_yaml() {
+        let toml = TaskManifest::parse(MANIFEST, ManifestFormat::Toml).unwrap();
+        let yaml = TaskManifest::parse(
+            r#"
+tasks:
+  - name: backup
+    cron: "0 0 2 * * *"
+    command: tar czf /tmp/home.tgz ~
+    env:
+      GZIP: "-9"
+    tags: [io]
+    retry:
+      max_attempts: 3
+      backoff: fixed:60
+  - name: ping
+    title: Ping
+    cron: "0 */5 * * * *"
+    action:
+      type: http
+      url: http://localhost:8080/health
+    enabled: false
+"#,
+            ManifestFormat::Yaml,
+        )
+        .unwrap();
+        assert_eq!(toml, yaml);
+
+        let specs = toml.validate().unwrap();
+        assert_eq!(specs[0].retry.max_attempts, 3);
+        match &specs[0].action {
+            TaskAction::Shell { env, .. } => assert_eq!(env["GZIP"], "-9"),
+            other => panic!("unexpected action {:?}", other),
+        }
+        assert!(!specs[1].enabled);
+
+        assert_eq!(ManifestFormat::from_path(Path::new("tasks.yml")), ManifestFormat::Yaml);
+        assert_eq!(ManifestFormat::from_path(Path::new("tasks.toml")), ManifestFormat::Toml);
+        assert!(TaskManifest::parse("[[tasks]]\nname = \"x\"\ncron = \"* * * * *\"\nshell = \"ls\"\n", ManifestFormat::Toml).is_err());
+    }
+
+    #[test]
+    fn test_validate_collects_errors() {
+        let manifest = TaskManifest::parse(
+            r#"
+[[tasks]]
+name = "a"
+cron = "not a cron"
+command = "ls"
+
+[[tasks]]
+name = "a"
+cron = "0 * * * * *"
+command = "ls"
+
+[[tasks]]
+name = "b"
+cron = "0 * * * * *"
+
+[[tasks]]
+name = "c"
+cron = "0 * * * * *"
+action = { type = "http", url = "http://x" }
+env = { A = "1" }
+"#,
+            ManifestFormat::Toml,
+        )
+        .unwrap();
+        let errors = manifest.validate().unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert_eq!(errors[1].message, "duplicate task name");
+        assert!(errors[2].to_string().starts_with("tasks[2] (b):"));
+        assert!(errors[3].message.contains("http"));
+    }
+
+    #[tokio::test]
+    async fn test_plan_and_apply() {
+        let temp_dir = TempDir::new().unwrap();
+        let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let manual = scheduler
+            .add_task_with_action(
+                "Manual".to_string(),
+                "manual".to_string(),
+                None,
+                None,
+                "0 0 * * * *".to_string(),
+                TaskAction::shell("echo manual"),
+                false,
+            )
+            .await
+            .unwrap();
+
+        let manifest = TaskManifest::parse(MANIFEST, ManifestFormat::Toml).unwrap();
+        let plan = manifest.plan(&scheduler.list_tasks().await.unwrap(), false).unwrap();
+        assert_eq!(plan.summary(), PlanSummary { created: 2, ..Default::default() });
+        let created = plan.apply(&scheduler).await.unwrap();
+        assert_eq!(created.len(), 2);
+
+        let backup = scheduler.get_task(created[0]).await.unwrap();
+        assert_eq!((backup.retry.max_attempts, backup.tags.clone()), (3, vec!["io".to_string()]));
+        assert!(!scheduler.get_task(created[1]).await.unwrap().enabled);
+
+        // 再次对比没有变化
+        let plan = manifest.plan(&scheduler.list_tasks().await.unwrap(), false).unwrap();
+        assert!(plan.is_empty());
+
+        // 修改清单后只更新变化的字段，按名称对应同一个任务
+        let mut changed = manifest.clone();
+        changed.tasks[0].cron = "0 30 2 * * *".to_string();
+        changed.tasks[0].env.insert("LEVEL".to_string(), "1".to_string());
+        changed.tasks[0].timezone = Some("Asia/Shanghai".to_string());
+        changed.tasks.remove(1);
+        let plan = changed.plan(&scheduler.list_tasks().await.unwrap(), true).unwrap();
+        assert_eq!(
+            plan.summary(),
+            PlanSummary { updated: 1, deleted: 2, ..Default::default() }
+        );
+        let update = &plan.changes[0];
+        let fields: Vec<&str> = update.fields.iter().map(|f| f.field).collect();
+        assert_eq!(fields, vec!["cron", "timezone", "env"]);
+        plan.apply(&scheduler).await.unwrap();
+
+        let tasks = scheduler.list_tasks().await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, backup.id);
+        assert_eq!(tasks[0].cron_expression, "0 30 2 * * *");
+        assert_eq!(tasks[0].timezone.as_deref(), Some("Asia/Shanghai"));
+        assert!(scheduler.get_task(manual.id).await.is_err());
+    }
+}
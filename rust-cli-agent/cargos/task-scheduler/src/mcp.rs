@@ -0,0 +1,812 @@
+//! Model Context Protocol 服务端
+//!
+//! 把工具提供者 (如 `SchedulerToolAdapter`) 以 MCP 的 `initialize`、`tools/list`、`tools/call`
+//! 提供给其他 agent，支持两种传输：
+//! - stdio：按行分隔的 JSON-RPC 消息，标准输出只用于协议消息
+//! - streamable HTTP：单个端点接收 POST 的 JSON-RPC 消息并以 JSON 应答，`initialize` 时分配会话
+//!
+//! 每个请求在独立任务中处理，收到 `notifications/cancelled` 时中止对应请求且不再应答
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hyper::body::{Buf, HttpBody};
+use hyper::header::{HeaderValue, ALLOW, AUTHORIZATION, CONTENT_TYPE, ORIGIN, WWW_AUTHENTICATE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+use crate::ipc::{INVALID_PARAMS, INVALID_REQUEST, MAX_MESSAGE_BYTES, METHOD_NOT_FOUND, PARSE_ERROR};
+use crate::llm::{CallToolRequest, CallToolResponse, ToolProvider};
+
+/// 支持的协议版本，第一个为最新版本
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// HTTP 传输的会话头
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// 处理失败的内部错误
+const INTERNAL_ERROR: i64 = -32603;
+
+/// HTTP 请求体上限
+const MAX_BODY_BYTES: usize = MAX_MESSAGE_BYTES;
+
+/// HTTP 会话默认的空闲超时
+const DEFAULT_SESSION_IDLE: Duration = Duration::from_secs(30 * 60);
+
+/// 默认同时存在的 HTTP 会话上限
+const DEFAULT_MAX_SESSIONS: usize = 64;
+
+/// MCP 服务端
+pub struct McpServer {
+    provider: Arc<dyn ToolProvider>,
+    name: String,
+    version: String,
+    instructions: Option<String>,
+    /// 正在处理的请求，按 (会话, 请求 ID) 索引
+    in_flight: Mutex<HashMap<(Option<String>, String), AbortHandle>>,
+    /// HTTP 传输的会话及其最近一次请求的时间
+    sessions: Mutex<HashMap<String, Instant>>,
+    /// 空闲超过该时间的会话被清除
+    session_idle: Duration,
+    max_sessions: usize,
+    /// HTTP 传输的访问令牌
+    bearer_token: Option<String>,
+}
+
+impl McpServer {
+    pub fn new(provider: Arc<dyn ToolProvider>) -> Self {
+        Self {
+            provider,
+            name: "sker".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            instructions: None,
+            in_flight: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+            session_idle: DEFAULT_SESSION_IDLE,
+            max_sessions: DEFAULT_MAX_SESSIONS,
+            bearer_token: None,
+        }
+    }
+
+    /// 设置 `initialize` 中返回的服务端名称和版本
+    pub fn with_server_info(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.name = name.into();
+        self.version = version.into();
+        self
+    }
+
+    /// 设置 `initialize` 中返回的使用说明
+    pub fn with_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// 要求 HTTP 传输的每个请求携带 `Authorization: Bearer <token>`
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// 设置 HTTP 会话的空闲超时和同时存在的会话上限
+    pub fn with_session_limits(mut self, idle: Duration, max_sessions: usize) -> Self {
+        self.session_idle = idle;
+        self.max_sessions = max_sessions;
+        self
+    }
+
+    /// 处理一段 JSON 文本，解析失败时返回 JSON-RPC 解析错误
+    pub async fn handle_payload(self: &Arc<Self>, session: Option<&str>, payload: &str) -> Option<Value> {
+        match serde_json::from_str::<Value>(payload) {
+            Ok(message) => self.handle_message(session, message).await,
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+        }
+    }
+
+    /// 处理单条或批量消息
+    ///
+    /// 通知和客户端发来的响应没有应答，全部是通知的批量消息返回 None
+    pub async fn handle_message(self: &Arc<Self>, session: Option<&str>, message: Value) -> Option<Value> {
+        match message {
+            Value::Array(batch) if batch.is_empty() => {
+                Some(error_response(Value::Null, INVALID_REQUEST, "Empty batch"))
+            }
+            Value::Array(batch) => {
+                let mut responses = Vec::new();
+                for message in batch {
+                    if let Some(response) = self.handle_single(session, message).await {
+                        responses.push(response);
+                    }
+                }
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            message => self.handle_single(session, message).await,
+        }
+    }
+
+    async fn handle_single(self: &Arc<Self>, session: Option<&str>, message: Value) -> Option<Value> {
+        let Value::Object(fields) = message else {
+            return Some(error_response(Value::Null, INVALID_REQUEST, "Message must be an object"));
+        };
+        let id = fields.get("id").cloned();
+        let Some(method) = fields.get("method").and_then(Value::as_str).map(str::to_string) else {
+            // 客户端发来的响应 (我们不向客户端发请求)，忽略
+            return match id {
+                Some(_) if fields.contains_key("result") || fields.contains_key("error") => None,
+                Some(id) => Some(error_response(id, INVALID_REQUEST, "Missing method")),
+                None => Some(error_response(Value::Null, INVALID_REQUEST, "Missing method")),
+            };
+        };
+        let params = fields.get("params").cloned().unwrap_or(Value::Null);
+
+        let Some(id) = id else {
+            self.handle_notification(session, &method, &params);
+            return None;
+        };
+
+        let key = (session.map(str::to_string), id.to_string());
+        let server = self.clone();
+        let task = tokio::spawn(async move { server.dispatch(&method, params).await });
+        self.in_flight
+            .lock()
+            .expect("in-flight lock poisoned")
+            .insert(key.clone(), task.abort_handle());
+        let outcome = task.await;
+        self.in_flight.lock().expect("in-flight lock poisoned").remove(&key);
+
+        match outcome {
+            Ok(Ok(result)) => Some(json!({"jsonrpc": "2.0", "id": id, "result": result})),
+            Ok(Err((code, message))) => Some(error_response(id, code, message)),
+            // 被客户端取消的请求不应答
+            Err(e) if e.is_cancelled() => None,
+            Err(e) => Some(error_response(id, INTERNAL_ERROR, e.to_string())),
+        }
+    }
+
+    fn handle_notification(&self, session: Option<&str>, method: &str, params: &Value) {
+        match method {
+            "notifications/cancelled" => {
+                let Some(request_id) = params.get("requestId") else {
+                    return;
+                };
+                let key = (session.map(str::to_string), request_id.to_string());
+                if let Some(handle) = self.in_flight.lock().expect("in-flight lock poisoned").remove(&key) {
+                    tracing::debug!("MCP request {} cancelled by client", request_id);
+                    handle.abort();
+                }
+            }
+            "notifications/initialized" => tracing::debug!("MCP client initialized"),
+            other => tracing::debug!("Ignoring MCP notification {}", other),
+        }
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> std::result::Result<Value, (i64, String)> {
+        match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => {
+                let tools: Vec<Value> = self
+                    .provider
+                    .tools()
+                    .into_iter()
+                    .map(|tool| {
+                        json!({
+                            "name": tool.name,
+                            "description": tool.description,
+                            "inputSchema": tool.input_schema,
+                        })
+                    })
+                    .collect();
+                Ok(json!({ "tools": tools }))
+            }
+            "tools/call" => {
+                let Some(name) = params.get("name").and_then(Value::as_str) else {
+                    return Err((INVALID_PARAMS, "tools/call requires a tool name".to_string()));
+                };
+                let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+                let response = self
+                    .provider
+                    .call(CallToolRequest {
+                        name: name.to_string(),
+                        arguments,
+                    })
+                    .await;
+                Ok(call_result(response))
+            }
+            other => Err((METHOD_NOT_FOUND, format!("Method not found: {}", other))),
+        }
+    }
+
+    /// 协商协议版本：支持客户端的版本时沿用，否则返回最新版本
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params.get("protocolVersion").and_then(Value::as_str);
+        let version = requested
+            .filter(|v| SUPPORTED_PROTOCOL_VERSIONS.contains(v))
+            .unwrap_or(SUPPORTED_PROTOCOL_VERSIONS[0]);
+        let mut result = json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": { "name": self.name, "version": self.version },
+        });
+        if let Some(instructions) = &self.instructions {
+            result["instructions"] = json!(instructions);
+        }
+        result
+    }
+
+    /// 在 stdio 上提供服务，直到输入结束
+    pub async fn serve_stdio<R, W>(self: Arc<Self>, reader: R, writer: W) -> std::io::Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+        let writer_task = tokio::spawn(async move {
+            let mut writer = writer;
+            while let Some(message) = rx.recv().await {
+                let mut payload = serde_json::to_vec(&message)?;
+                payload.push(b'\n');
+                writer.write_all(&payload).await?;
+                writer.flush().await?;
+            }
+            Ok::<_, std::io::Error>(())
+        });
+
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let server = self.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                if let Some(response) = server.handle_payload(None, &line).await {
+                    let _ = tx.send(response);
+                }
+            });
+        }
+        // 输入结束后等待进行中的请求应答完毕
+        drop(tx);
+        writer_task.await.map_err(std::io::Error::other)?
+    }
+
+    /// 在 HTTP 端点上提供 streamable HTTP 传输，直到 `shutdown` 完成
+    ///
+    /// 只接受本机来源 (Origin) 的浏览器请求，防止 DNS 重绑定攻击；
+    /// 设置了访问令牌时拒绝未携带正确令牌的请求
+    pub async fn serve_http(
+        self: Arc<Self>,
+        listener: std::net::TcpListener,
+        path: impl Into<String>,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<()> {
+        let path = Arc::new(path.into());
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| SchedulerError::IpcError(e.to_string()))?;
+        let make_service = make_service_fn(move |_| {
+            let server = self.clone();
+            let path = path.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let server = server.clone();
+                    let path = path.clone();
+                    async move { Ok::<_, Infallible>(server.handle_http(&path, request).await) }
+                }))
+            }
+        });
+        Server::from_tcp(listener)
+            .map_err(|e| SchedulerError::IpcError(e.to_string()))?
+            .serve(make_service)
+            .with_graceful_shutdown(shutdown)
+            .await
+            .map_err(|e| SchedulerError::IpcError(e.to_string()))
+    }
+
+    async fn handle_http(self: &Arc<Self>, path: &str, request: Request<Body>) -> Response<Body> {
+        if request.uri().path() != path {
+            return plain_response(StatusCode::NOT_FOUND, "Not found");
+        }
+        if let Some(token) = &self.bearer_token {
+            if !is_authorized(request.headers().get(AUTHORIZATION), token) {
+                let mut response = plain_response(StatusCode::UNAUTHORIZED, "Unauthorized");
+                response
+                    .headers_mut()
+                    .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+                return response;
+            }
+        }
+        if let Some(origin) = request.headers().get(ORIGIN) {
+            if !is_local_origin(origin) {
+                return plain_response(StatusCode::FORBIDDEN, "Origin not allowed");
+            }
+        }
+        let session = request
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+
+        match *request.method() {
+            Method::POST => self.handle_http_post(session, request.into_body()).await,
+            Method::DELETE => match session {
+                Some(id) if self.sessions.lock().expect("session lock poisoned").remove(&id).is_some() => {
+                    plain_response(StatusCode::OK, "")
+                }
+                Some(_) => plain_response(StatusCode::NOT_FOUND, "Unknown session"),
+                None => plain_response(StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header"),
+            },
+            // 不提供服务端主动推送的 SSE 流
+            _ => {
+                let mut response = plain_response(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+                response
+                    .headers_mut()
+                    .insert(ALLOW, HeaderValue::from_static("POST, DELETE"));
+                response
+            }
+        }
+    }
+
+    async fn handle_http_post(self: &Arc<Self>, session: Option<String>, body: Body) -> Response<Body> {
+        let bytes = match read_body(body).await {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => return plain_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large"),
+            Err(e) => return plain_response(StatusCode::BAD_REQUEST, &e.to_string()),
+        };
+        let message = match serde_json::from_slice::<Value>(&bytes) {
+            Ok(message) => message,
+            Err(e) => {
+                return json_response(
+                    StatusCode::BAD_REQUEST,
+                    &error_response(Value::Null, PARSE_ERROR, e.to_string()),
+                    None,
+                )
+            }
+        };
+
+        // initialize 创建新会话，其余消息必须带已知的会话
+        let session = {
+            let mut sessions = self.sessions.lock().expect("session lock poisoned");
+            let now = Instant::now();
+            sessions.retain(|_, last_seen| now.duration_since(*last_seen) < self.session_idle);
+            if is_initialize(&message) {
+                if sessions.len() >= self.max_sessions {
+                    return plain_response(StatusCode::SERVICE_UNAVAILABLE, "Too many sessions");
+                }
+                let id = Uuid::new_v4().to_string();
+                sessions.insert(id.clone(), now);
+                id
+            } else {
+                match session {
+                    Some(id) => match sessions.get_mut(&id) {
+                        Some(last_seen) => {
+                            *last_seen = now;
+                            id
+                        }
+                        None => return plain_response(StatusCode::NOT_FOUND, "Unknown session"),
+                    },
+                    None => return plain_response(StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header"),
+                }
+            }
+        };
+
+        match self.handle_message(Some(&session), message).await {
+            Some(response) => json_response(StatusCode::OK, &response, Some(&session)),
+            None => plain_response(StatusCode::ACCEPTED, ""),
+        }
+    }
+}
+
+/// 读取请求体，超过 [`MAX_BODY_BYTES`] 时返回 None
+///
+/// Content-Length 超限时不读取；分块传输时边读边累计，越过上限立即停止
+async fn read_body<B>(mut body: B) -> std::result::Result<Option<Vec<u8>>, B::Error>
+where
+    B: HttpBody + Unpin,
+{
+    if body.size_hint().lower() > MAX_BODY_BYTES as u64 {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.remaining() > MAX_BODY_BYTES {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(chunk.chunk());
+    }
+    Ok(Some(bytes))
+}
+
+/// 把工具调用结果转换为 `tools/call` 的返回值
+fn call_result(response: CallToolResponse) -> Value {
+    let mut content: Vec<Value> = response
+        .content
+        .into_iter()
+        .map(|c| json!({"type": c.content_type, "text": c.text.unwrap_or_default()}))
+        .collect();
+    if let Some(error) = response.error {
+        content.push(json!({"type": "text", "text": error}));
+    }
+    json!({ "content": content, "isError": !response.success })
+}
+
+fn error_response(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() },
+    })
+}
+
+fn is_initialize(message: &Value) -> bool {
+    let single = |m: &Value| m.get("method").and_then(Value::as_str) == Some("initialize");
+    match message {
+        Value::Array(batch) => batch.iter().any(single),
+        message => single(message),
+    }
+}
+
+/// Authorization 头是否携带了正确的令牌，逐字节比较全部内容，耗时与令牌的匹配位置无关
+fn is_authorized(header: Option<&HeaderValue>, token: &str) -> bool {
+    let Some(provided) = header
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Origin 是否指向本机
+fn is_local_origin(origin: &HeaderValue) -> bool {
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    let host = origin
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(origin)
+        .trim_end_matches('/');
+    let host = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+fn plain_response(status: StatusCode, text: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(text.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+fn json_response(status: StatusCode, body: &Value, session: Option<&str>) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    if let Some(value) = session.and_then(|s| HeaderValue::from_str(s).ok()) {
+        response.headers_mut().insert(SESSION_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use crate::cron_scheduler::CronTaskScheduler;
+    use crate::llm::{SchedulerToolAdapter, Tool, ToolContent};
+
+    /// 带一个会阻塞的工具的测试提供者
+    struct SlowTools;
+
+    #[async_trait]
+    impl ToolProvider for SlowTools {
+        fn tools(&self) -> Vec<Tool> {
+            vec![Tool {
+                name: "wait".to_string(),
+                description: "Wait forever".to_string(),
+                input_schema: json!({"type": "object"}),
+            }]
+        }
+
+        async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+            if request.name == "wait" {
+                tokio::time::sleep(Duration::from_secs(3600)).await;
+            }
+            CallToolResponse {
+                success: true,
+                content: vec![ToolContent::text(request.arguments.to_string())],
+                error: None,
+            }
+        }
+    }
+
+    async fn scheduler_server() -> Arc<McpServer> {
+        let scheduler = Arc::new(CronTaskScheduler::new().await.unwrap());
+        Arc::new(McpServer::new(Arc::new(SchedulerToolAdapter::new(scheduler))))
+    }
+
+    #[tokio::test]
+    async fn test_initialize_list_and_call() {
+        let server = scheduler_server().await;
+
+        let init = server
+            .handle_payload(None, r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(init["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(init["result"]["serverInfo"]["name"], "sker");
+        let init = server
+            .handle_payload(None, r#"{"jsonrpc":"2.0","id":2,"method":"initialize","params":{"protocolVersion":"1999-01-01"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(init["result"]["protocolVersion"], SUPPORTED_PROTOCOL_VERSIONS[0]);
+        assert!(server
+            .handle_payload(None, r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)
+            .await
+            .is_none());
+
+        let list = server
+            .handle_payload(None, r#"{"jsonrpc":"2.0","id":"l","method":"tools/list"}"#)
+            .await
+            .unwrap();
+        assert_eq!(list["id"], "l");
+        let tools = list["result"]["tools"].as_array().unwrap();
+        let add = tools.iter().find(|t| t["name"] == "add_task").unwrap();
+        assert_eq!(add["inputSchema"]["type"], "object");
+
+        let call = server
+            .handle_payload(
+                None,
+                r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"list_tasks","arguments":{}}}"#,
+            )
+            .await
+            .unwrap();
+        assert_eq!(call["result"]["isError"], false);
+        assert_eq!(call["result"]["content"][0]["type"], "text");
+
+        let unknown = server
+            .handle_payload(None, r#"{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"nope"}}"#)
+            .await
+            .unwrap();
+        assert_eq!(unknown["result"]["isError"], true);
+        assert!(unknown["result"]["content"][0]["text"].as_str().unwrap().contains("Unknown tool"));
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_batches() {
+        let server = scheduler_server().await;
+        let parse = server.handle_payload(None, "{oops").await.unwrap();
+        assert_eq!(parse["error"]["code"], PARSE_ERROR);
+        let missing = server
+            .handle_payload(None, r#"{"jsonrpc":"2.0","id":1,"method":"resources/list"}"#)
+            .await
+            .unwrap();
+        assert_eq!(missing["error"]["code"], METHOD_NOT_FOUND);
+
+        let batch = server
+            .handle_payload(
+                None,
+                r#"[{"jsonrpc":"2.0","id":1,"method":"ping"},{"jsonrpc":"2.0","method":"notifications/initialized"},{"jsonrpc":"2.0","id":2,"method":"ping"}]"#,
+            )
+            .await
+            .unwrap();
+        assert_eq!(batch.as_array().unwrap().len(), 2);
+        assert_eq!(batch[1]["id"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_stdio_transport_with_cancellation() {
+        let server = Arc::new(McpServer::new(Arc::new(SlowTools)));
+        let (client, server_io) = tokio::io::duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server_io);
+        let serving = tokio::spawn(server.serve_stdio(server_read, server_write));
+
+        let (client_read, mut client_write) = tokio::io::split(client);
+        let mut responses = BufReader::new(client_read).lines();
+        let messages = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"wait"}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#,
+        ];
+        for message in messages {
+            client_write.write_all(format!("{}\n", message).as_bytes()).await.unwrap();
+        }
+        // 阻塞中的调用不影响其他请求
+        let ping: Value = serde_json::from_str(&responses.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(ping["id"], 2);
+
+        let cancel = r#"{"jsonrpc":"2.0","method":"notifications/cancelled","params":{"requestId":1}}"#;
+        client_write.write_all(format!("{}\n", cancel).as_bytes()).await.unwrap();
+        let echo = r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"echo","arguments":{"x":1}}}"#;
+        client_write.write_all(format!("{}\n", echo).as_bytes()).await.unwrap();
+        client_write.shutdown().await.unwrap();
+
+        // 被取消的请求没有应答，输入结束后服务退出
+        let echo: Value = serde_json::from_str(&responses.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(echo["id"], 3);
+        assert_eq!(echo["result"]["content"][0]["text"], r#"{"x":1}"#);
+        tokio::time::timeout(Duration::from_secs(5), serving).await.unwrap().unwrap().unwrap();
+        assert!(responses.next_line().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_transport_sessions() {
+        let server = scheduler_server().await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn(server.serve_http(listener, "/mcp", async {
+            let _ = stopped.await;
+        }));
+        let client = reqwest::Client::new();
+        let post = |body: &'static str, session: Option<String>| {
+            let mut request = client
+                .post(&url)
+                .header(CONTENT_TYPE, "application/json")
+                .header("accept", "application/json, text/event-stream")
+                .body(body);
+            if let Some(session) = session {
+                request = request.header(SESSION_HEADER, session);
+            }
+            request.send()
+        };
+
+        let init = post(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#, None)
+            .await
+            .unwrap();
+        assert_eq!(init.status(), 200);
+        let session = init.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+
+        let without = post(r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#, None).await.unwrap();
+        assert_eq!(without.status(), 400);
+        let notified = post(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#, Some(session.clone()))
+            .await
+            .unwrap();
+        assert_eq!(notified.status(), 202);
+        let list = post(r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#, Some(session.clone()))
+            .await
+            .unwrap();
+        assert_eq!(list.status(), 200);
+        let list: Value = serde_json::from_str(&list.text().await.unwrap()).unwrap();
+        assert!(!list["result"]["tools"].as_array().unwrap().is_empty());
+
+        let foreign = client
+            .post(&url)
+            .header(ORIGIN, "http://evil.example.com")
+            .body(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(foreign.status(), 403);
+        assert_eq!(client.get(&url).send().await.unwrap().status(), 405);
+
+        let deleted = client.delete(&url).header(SESSION_HEADER, &session).send().await.unwrap();
+        assert_eq!(deleted.status(), 200);
+        let gone = post(r#"{"jsonrpc":"2.0","id":3,"method":"ping"}"#, Some(session)).await.unwrap();
+        assert_eq!(gone.status(), 404);
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_http_session_expiry_and_limit() {
+        let server = Arc::new(
+            McpServer::new(Arc::new(SlowTools)).with_session_limits(Duration::from_millis(300), 2),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn(server.clone().serve_http(listener, "/mcp", async {
+            let _ = stopped.await;
+        }));
+        let client = reqwest::Client::new();
+        let post = |body: &'static str, session: Option<String>| {
+            let mut request = client.post(&url).header(CONTENT_TYPE, "application/json").body(body);
+            if let Some(session) = session {
+                request = request.header(SESSION_HEADER, session);
+            }
+            request.send()
+        };
+        let init = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#;
+        let ping = r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#;
+
+        let first = post(init, None).await.unwrap();
+        let first = first.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+        let second = post(init, None).await.unwrap();
+        let second = second.headers()[SESSION_HEADER].to_str().unwrap().to_string();
+        // 达到上限后拒绝新会话
+        assert_eq!(post(init, None).await.unwrap().status(), 503);
+
+        // 有请求的会话保持活跃，空闲的会话过期后释放名额
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            assert_eq!(post(ping, Some(first.clone())).await.unwrap().status(), 200);
+        }
+        assert_eq!(post(ping, Some(second)).await.unwrap().status(), 404);
+        assert_eq!(post(init, None).await.unwrap().status(), 200);
+        assert_eq!(server.sessions.lock().unwrap().len(), 2);
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_http_bearer_token() {
+        let server = Arc::new(McpServer::new(Arc::new(SlowTools)).with_bearer_token("s3cret"));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn(server.serve_http(listener, "/mcp", async {
+            let _ = stopped.await;
+        }));
+        let client = reqwest::Client::new();
+        let init = |token: Option<&'static str>| {
+            let mut request = client
+                .post(&url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#);
+            if let Some(token) = token {
+                request = request.header(AUTHORIZATION, token);
+            }
+            request.send()
+        };
+
+        let missing = init(None).await.unwrap();
+        assert_eq!(missing.status(), 401);
+        assert_eq!(missing.headers()[WWW_AUTHENTICATE], "Bearer");
+        assert_eq!(init(Some("Bearer wrong!")).await.unwrap().status(), 401);
+        assert_eq!(init(Some("Basic s3cret")).await.unwrap().status(), 401);
+        assert_eq!(init(Some("Bearer s3cret")).await.unwrap().status(), 200);
+        // 会话 ID 不能代替令牌
+        assert_eq!(client.delete(&url).header(SESSION_HEADER, "x").send().await.unwrap().status(), 401);
+
+        stop.send(()).unwrap();
+        serving.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_body_size_limit() {
+        let small = read_body(Body::from("{}")).await.unwrap();
+        assert_eq!(small.as_deref(), Some(&b"{}"[..]));
+
+        // 声明的长度超限时不读取
+        assert!(read_body(Body::from(vec![b' '; MAX_BODY_BYTES + 1])).await.unwrap().is_none());
+
+        // 未声明长度时越过上限即停止，不等发送方结束
+        let (mut sender, body) = Body::channel();
+        let sending = tokio::spawn(async move {
+            let chunk = hyper::body::Bytes::from(vec![b' '; 1024 * 1024]);
+            while sender.send_data(chunk.clone()).await.is_ok() {}
+        });
+        let read = tokio::time::timeout(Duration::from_secs(5), read_body(body)).await.unwrap();
+        assert!(read.unwrap().is_none());
+        sending.await.unwrap();
+    }
+
+    #[test]
+    fn test_local_origin() {
+        assert!(is_local_origin(&HeaderValue::from_static("http://localhost:3000")));
+        assert!(is_local_origin(&HeaderValue::from_static("http://127.0.0.1")));
+        assert!(is_local_origin(&HeaderValue::from_static("http://[::1]:8080")));
+        assert!(!is_local_origin(&HeaderValue::from_static("http://localhost.evil.com")));
+        assert!(!is_local_origin(&HeaderValue::from_static("null")));
+    }
+}
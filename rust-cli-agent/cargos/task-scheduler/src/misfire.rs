@@ -0,0 +1,345 @@
+//! 错过运行的补偿策略
+//!
+//! 守护进程停止或机器休眠期间到期的运行称为错过的运行。
+//! 晚于计划时间但仍在宽限期内的运行照常补跑一次；
+//! 超出宽限期的运行按任务的策略跳过或补跑，未补跑的记录为 Expired 运行实例
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SchedulerError};
+use crate::timezone::ZonedSchedule;
+
+/// 默认宽限期 (秒)
+pub const DEFAULT_GRACE_SECS: u64 = 60;
+
+/// 单次检查最多逐条记录的错过运行数，更早的只计数
+pub const MAX_EXPIRED_RECORDS: usize = 100;
+
+/// 错过运行的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum MisfirePolicy {
+    /// 跳过错过的运行
+    #[default]
+    Skip,
+    /// 不论错过多少次，只补跑一次
+    RunOnce,
+    /// 逐次补跑最近错过的运行，最多 limit 次
+    RunAll { limit: u32 },
+}
+
+impl std::fmt::Display for MisfirePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MisfirePolicy::Skip => write!(f, "skip"),
+            MisfirePolicy::RunOnce => write!(f, "run_once"),
+            MisfirePolicy::RunAll { limit } => write!(f, "run_all:{}", limit),
+        }
+    }
+}
+
+impl FromStr for MisfirePolicy {
+    type Err = SchedulerError;
+
+    /// 解析 `skip`、`run_once`、`run_all:<limit>`
+    fn from_str(s: &str) -> Result<Self> {
+        let normalized = s.trim().to_lowercase().replace('-', "_");
+        match normalized.split_once(':') {
+            None if normalized == "skip" => Ok(MisfirePolicy::Skip),
+            None if normalized == "run_once" => Ok(MisfirePolicy::RunOnce),
+            Some(("run_all", limit)) => limit
+                .parse()
+                .map(|limit| MisfirePolicy::RunAll { limit })
+                .map_err(|_| SchedulerError::InvalidParameter(format!("Invalid misfire limit: {}", limit))),
+            _ => Err(SchedulerError::InvalidParameter(format!(
+                "Invalid misfire policy: {} (expected skip, run_once or run_all:<limit>)",
+                s
+            ))),
+        }
+    }
+}
+
+/// 任务的错过运行配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MisfireConfig {
+    /// 超出宽限期的错过运行如何处理
+    #[serde(flatten)]
+    pub policy: MisfirePolicy,
+    /// 宽限期 (秒)，晚于计划时间不超过宽限期的运行照常补跑
+    #[serde(default = "default_grace_secs")]
+    pub grace_secs: u64,
+}
+
+fn default_grace_secs() -> u64 {
+    DEFAULT_GRACE_SECS
+}
+
+impl Default for MisfireConfig {
+    fn default() -> Self {
+        Self {
+            policy: MisfirePolicy::Skip,
+            grace_secs: DEFAULT_GRACE_SECS,
+        }
+    }
+}
+
+impl MisfireConfig {
+    pub fn new(policy: MisfirePolicy, grace_secs: u64) -> Self {
+        Self { policy, grace_secs }
+    }
+
+    /// 用命令行或工具调用给出的字段覆盖当前配置，两者都未给出时返回 None
+    pub fn with_overrides(self, policy: Option<&str>, grace_secs: Option<u64>) -> Result<Option<Self>> {
+        if policy.is_none() && grace_secs.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            policy: match policy {
+                Some(policy) => policy.parse()?,
+                None => self.policy,
+            },
+            grace_secs: grace_secs.unwrap_or(self.grace_secs),
+        }))
+    }
+}
+
+/// 一次检查的处理结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MisfirePlan {
+    /// 需要补跑的次数
+    pub runs: usize,
+    /// 记录为 Expired 的计划时间 (最多 `MAX_EXPIRED_RECORDS` 条，按时间升序)
+    pub expired: Vec<DateTime<Utc>>,
+    /// 超出记录上限、只计数的错过运行数
+    pub uncounted: usize,
+}
+
+impl MisfirePlan {
+    /// 计算 (since, until] 之间未触发的计划运行如何处理
+    ///
+    /// `firing` 为 true 时区间内最后一次运行正由 cron 作业触发，不计入错过的运行，
+    /// 宽限期内的运行与其合并；否则宽限期内的运行合并为一次补跑
+    pub fn compute(
+        cron_expression: &str,
+        config: &MisfireConfig,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        now: DateTime<Utc>,
+        firing: bool,
+    ) -> Self {
+        match ZonedSchedule::parse(cron_expression, None) {
+            Ok(schedule) => Self::compute_zoned(&schedule, config, since, until, now, firing),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 按带时区的计划计算 (since, until] 之间未触发的计划运行如何处理
+    pub fn compute_zoned(
+        schedule: &ZonedSchedule,
+        config: &MisfireConfig,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        now: DateTime<Utc>,
+        firing: bool,
+    ) -> Self {
+        let grace = Duration::seconds(config.grace_secs.min(i64::MAX as u64) as i64);
+        let run_limit = match config.policy {
+            MisfirePolicy::Skip => 0,
+            MisfirePolicy::RunOnce => 1,
+            MisfirePolicy::RunAll { limit } => limit as usize,
+        };
+
+        // 逐条分类，只保留最近的超期运行，避免长时间停机后占用大量内存；
+        // 区间内最后一次运行延后分类，触发时由调用方执行
+        let mut missed = VecDeque::new();
+        let mut missed_total = 0usize;
+        let mut late = 0usize;
+        let mut pending: Option<DateTime<Utc>> = None;
+        for at in schedule.after(since).take_while(|at| *at <= until) {
+            if let Some(previous) = pending.replace(at) {
+                if now - previous <= grace {
+                    late += 1;
+                } else {
+                    missed_total += 1;
+                    missed.push_back(previous);
+                    if missed.len() > MAX_EXPIRED_RECORDS + run_limit {
+                        missed.pop_front();
+                    }
+                }
+            }
+        }
+        match pending {
+            None => return Self::default(),
+            Some(_) if firing => {}
+            Some(at) if now - at <= grace => late += 1,
+            Some(at) => {
+                missed_total += 1;
+                missed.push_back(at);
+            }
+        }
+
+        let mut runs = match config.policy {
+            // 正在触发的运行已经满足 "补跑一次"
+            MisfirePolicy::RunOnce if firing => 0,
+            _ => missed_total.min(run_limit),
+        };
+        // 补跑最近的运行，其余记为过期
+        missed.truncate(missed.len().saturating_sub(runs));
+        while missed.len() > MAX_EXPIRED_RECORDS {
+            missed.pop_front();
+        }
+        let uncounted = missed_total - runs - missed.len();
+        if !firing && late > 0 {
+            runs = runs.max(1);
+        }
+
+        Self {
+            runs,
+            expired: missed.into(),
+            uncounted,
+        }
+    }
+
+    /// 是否没有任何需要处理的运行
+    pub fn is_empty(&self) -> bool {
+        self.runs == 0 && self.expired.is_empty() && self.uncounted == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const HOURLY: &str = "0 0 * * * *";
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!("skip".parse::<MisfirePolicy>().unwrap(), MisfirePolicy::Skip);
+        assert_eq!("run-once".parse::<MisfirePolicy>().unwrap(), MisfirePolicy::RunOnce);
+        assert_eq!(
+            "run_all:3".parse::<MisfirePolicy>().unwrap(),
+            MisfirePolicy::RunAll { limit: 3 }
+        );
+        assert!("run_all".parse::<MisfirePolicy>().is_err());
+        assert!("sometimes".parse::<MisfirePolicy>().is_err());
+        assert_eq!(MisfirePolicy::RunAll { limit: 3 }.to_string(), "run_all:3");
+    }
+
+    #[test]
+    fn test_config_serde() {
+        let config = MisfireConfig::new(MisfirePolicy::RunAll { limit: 2 }, 300);
+        let json = serde_json::to_value(config).unwrap();
+        assert_eq!(json["policy"], "run_all");
+        assert_eq!(json["limit"], 2);
+        assert_eq!(serde_json::from_value::<MisfireConfig>(json).unwrap(), config);
+
+        let config: MisfireConfig = serde_json::from_str(r#"{"policy":"run_once"}"#).unwrap();
+        assert_eq!(config.grace_secs, DEFAULT_GRACE_SECS);
+    }
+
+    #[test]
+    fn test_with_overrides() {
+        let base = MisfireConfig::default();
+        assert_eq!(base.with_overrides(None, None).unwrap(), None);
+        assert_eq!(
+            base.with_overrides(Some("run_once"), None).unwrap(),
+            Some(MisfireConfig::new(MisfirePolicy::RunOnce, DEFAULT_GRACE_SECS))
+        );
+        assert_eq!(
+            base.with_overrides(None, Some(5)).unwrap(),
+            Some(MisfireConfig::new(MisfirePolicy::Skip, 5))
+        );
+        assert!(base.with_overrides(Some("never"), None).is_err());
+    }
+
+    #[test]
+    fn test_skip_expires_missed_runs() {
+        // 01:00 - 05:00 错过 5 次，06:30 检查
+        let config = MisfireConfig::new(MisfirePolicy::Skip, 60);
+        let plan = MisfirePlan::compute(HOURLY, &config, at(0, 30), at(6, 30), at(6, 30), false);
+        assert_eq!(plan.runs, 0);
+        assert_eq!(plan.expired, vec![at(1, 0), at(2, 0), at(3, 0), at(4, 0), at(5, 0), at(6, 0)]);
+    }
+
+    #[test]
+    fn test_late_run_within_grace_runs_once() {
+        let config = MisfireConfig::new(MisfirePolicy::Skip, 600);
+        let plan = MisfirePlan::compute(HOURLY, &config, at(0, 30), at(1, 5), at(1, 5), false);
+        assert_eq!(plan.runs, 1);
+        assert!(plan.expired.is_empty());
+    }
+
+    #[test]
+    fn test_run_once_coalesces() {
+        let config = MisfireConfig::new(MisfirePolicy::RunOnce, 60);
+        let plan = MisfirePlan::compute(HOURLY, &config, at(0, 30), at(6, 30), at(6, 30), false);
+        assert_eq!(plan.runs, 1);
+        // 最近一次被补跑，其余过期
+        assert_eq!(plan.expired, vec![at(1, 0), at(2, 0), at(3, 0), at(4, 0), at(5, 0)]);
+    }
+
+    #[test]
+    fn test_run_all_respects_limit() {
+        let config = MisfireConfig::new(MisfirePolicy::RunAll { limit: 2 }, 60);
+        let plan = MisfirePlan::compute(HOURLY, &config, at(0, 30), at(6, 30), at(6, 30), false);
+        assert_eq!(plan.runs, 2);
+        assert_eq!(plan.expired, vec![at(1, 0), at(2, 0), at(3, 0), at(4, 0)]);
+    }
+
+    #[test]
+    fn test_firing_excludes_current_run() {
+        // 06:00 正在触发，之前错过 01:00 - 05:00
+        let config = MisfireConfig::new(MisfirePolicy::RunOnce, 60);
+        let plan = MisfirePlan::compute(HOURLY, &config, at(0, 30), at(6, 0), at(6, 0), true);
+        assert_eq!(plan.runs, 0);
+        assert_eq!(plan.expired.len(), 5);
+
+        let config = MisfireConfig::new(MisfirePolicy::RunAll { limit: 10 }, 60);
+        let plan = MisfirePlan::compute(HOURLY, &config, at(0, 30), at(6, 0), at(6, 0), true);
+        assert_eq!(plan.runs, 5);
+        assert!(plan.expired.is_empty());
+
+        // 没有错过的运行
+        let plan = MisfirePlan::compute(HOURLY, &config, at(5, 30), at(6, 0), at(6, 0), true);
+        assert!(plan.is_empty());
+    }
+
+    #[test]
+    fn test_long_outage_is_capped() {
+        let config = MisfireConfig::new(MisfirePolicy::Skip, 0);
+        let since = at(0, 0);
+        let until = since + Duration::hours(2);
+        let plan = MisfirePlan::compute("0 * * * * *", &config, since, until, until, false);
+        // 最后一次运行恰好在检查时刻，视为迟到的运行
+        assert_eq!(plan.runs, 1);
+        assert_eq!(plan.expired.len(), MAX_EXPIRED_RECORDS);
+        assert_eq!(plan.expired.last(), Some(&(until - Duration::minutes(1))));
+        assert_eq!(plan.expired.len() + plan.uncounted, 119);
+    }
+
+    #[test]
+    fn test_zoned_missed_runs_follow_timezone() {
+        // 停机跨过柏林的夏令时切换，错过的运行按当地 02:30 计算，跳过的 02:30 顺延为 03:30
+        let schedule = ZonedSchedule::parse("0 30 2 * * *", Some("Europe/Berlin")).unwrap();
+        let config = MisfireConfig::new(MisfirePolicy::Skip, 60);
+        let since = Utc.with_ymd_and_hms(2024, 3, 30, 12, 0, 0).unwrap();
+        let until = Utc.with_ymd_and_hms(2024, 4, 1, 12, 0, 0).unwrap();
+        let plan = MisfirePlan::compute_zoned(&schedule, &config, since, until, until, false);
+        assert_eq!(
+            plan.expired,
+            vec![
+                Utc.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap(),
+                Utc.with_ymd_and_hms(2024, 4, 1, 0, 30, 0).unwrap(),
+            ]
+        );
+    }
+}
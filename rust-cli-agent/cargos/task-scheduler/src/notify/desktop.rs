@@ -0,0 +1,148 @@
+//! 桌面通知
+//!
+//! 通过系统命令显示通知：Linux 使用 notify-send，macOS 使用 osascript，Windows 使用 PowerShell。
+//! 标题和正文通过环境变量 `SKER_NOTIFY_TITLE`、`SKER_NOTIFY_BODY` 传给命令，
+//! 参数中的 `{{title}}`、`{{body}}` 也会被替换，不经过 shell 解析
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use super::{Notification, NotificationSink};
+use crate::error::{Result, SchedulerError};
+
+/// 通知命令的超时
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 调用系统通知命令
+pub struct DesktopSink {
+    program: String,
+    args: Vec<String>,
+    title: Option<String>,
+    body: Option<String>,
+}
+
+impl DesktopSink {
+    /// 使用当前平台的通知命令
+    pub fn new() -> Self {
+        let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+            (
+                "osascript",
+                &[
+                    "-e",
+                    "display notification (system attribute \"SKER_NOTIFY_BODY\") with title (system attribute \"SKER_NOTIFY_TITLE\")",
+                ],
+            )
+        } else if cfg!(windows) {
+            (
+                "powershell",
+                &[
+                    "-NoProfile",
+                    "-Command",
+                    "Add-Type -AssemblyName System.Windows.Forms; $n = New-Object System.Windows.Forms.NotifyIcon; $n.Icon = [System.Drawing.SystemIcons]::Information; $n.Visible = $true; $n.ShowBalloonTip(10000, $env:SKER_NOTIFY_TITLE, $env:SKER_NOTIFY_BODY, 'None'); Start-Sleep -Seconds 5; $n.Dispose()",
+                ],
+            )
+        } else {
+            ("notify-send", &["--app-name=sker", "{{title}}", "{{body}}"])
+        };
+        Self::with_command(program, args.iter().map(|a| a.to_string()).collect())
+    }
+
+    /// 使用自定义通知命令
+    pub fn with_command(program: impl Into<String>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+            title: None,
+            body: None,
+        }
+    }
+
+    /// 设置标题模板，默认使用通知的默认标题
+    pub fn with_title_template(mut self, template: impl Into<String>) -> Self {
+        self.title = Some(template.into());
+        self
+    }
+
+    /// 设置正文模板
+    pub fn with_body_template(mut self, template: impl Into<String>) -> Self {
+        self.body = Some(template.into());
+        self
+    }
+}
+
+impl Default for DesktopSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl NotificationSink for DesktopSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let title = match &self.title {
+            Some(template) => notification.render(template),
+            None => notification.subject(),
+        };
+        let body = match &self.body {
+            Some(template) => notification.render(template),
+            None => notification.render("{{error}}"),
+        };
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| arg.replace("{{title}}", &title).replace("{{body}}", &body))
+            .collect();
+
+        let output = Command::new(&self.program)
+            .args(&args)
+            .env("SKER_NOTIFY_TITLE", &title)
+            .env("SKER_NOTIFY_BODY", &body)
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(COMMAND_TIMEOUT, output)
+            .await
+            .map_err(|_| SchedulerError::NotificationError(format!("{} timed out", self.program)))?
+            .map_err(|e| {
+                SchedulerError::NotificationError(format!("Failed to run {}: {}", self.program, e))
+            })?;
+        if !output.status.success() {
+            return Err(SchedulerError::NotificationError(format!(
+                "{} exited with {}: {}",
+                self.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::notify::tests::sample_notification;
+    use crate::notify::TaskOutcome;
+
+    #[tokio::test]
+    async fn test_desktop_sink_passes_title_and_body() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shown.txt");
+        let script = "printf '%s|%s|%s' \"$SKER_NOTIFY_TITLE\" \"$SKER_NOTIFY_BODY\" \"$1\" > \"$0\"";
+        let sink = DesktopSink::with_command(
+            "sh",
+            vec!["-c".to_string(), script.to_string(), path.display().to_string(), "{{title}}".to_string()],
+        )
+        .with_body_template("{{task_name}} exit {{exit_code}}");
+
+        sink.send(&sample_notification(TaskOutcome::Failure)).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[sker] 任务 Nightly backup 运行失败|nightly_backup exit 1|[sker] 任务 Nightly backup 运行失败"
+        );
+
+        let failing = DesktopSink::with_command("sh", vec!["-c".to_string(), "exit 2".to_string()]);
+        assert!(failing.send(&sample_notification(TaskOutcome::Error)).await.is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! 追加到文件的通知
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use super::{Notification, NotificationSink};
+use crate::error::{Result, SchedulerError};
+
+/// 每条通知追加一行到文件
+///
+/// 没有设置模板时写入通知的 JSON
+pub struct FileSink {
+    path: PathBuf,
+    template: Option<String>,
+    /// 串行写入，避免并发运行的通知交错
+    lock: Mutex<()>,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            template: None,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// 设置行模板
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = Some(template.into());
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationSink for FileSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let mut line = match &self.template {
+            Some(template) => notification.render(template),
+            None => serde_json::to_string(notification)
+                .map_err(|e| SchedulerError::NotificationError(e.to_string()))?,
+        };
+        line.push('\n');
+
+        let _guard = self.lock.lock().await;
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| SchedulerError::NotificationError(e.to_string()))?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| {
+                SchedulerError::NotificationError(format!("Failed to open {}: {}", self.path.display(), e))
+            })?;
+        file.write_all(line.as_bytes())
+            .await
+            .map_err(|e| SchedulerError::NotificationError(e.to_string()))?;
+        file.flush()
+            .await
+            .map_err(|e| SchedulerError::NotificationError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::tests::sample_notification;
+    use crate::notify::TaskOutcome;
+
+    #[tokio::test]
+    async fn test_file_sink_appends_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts").join("tasks.log");
+
+        let json = FileSink::new(&path);
+        let first = sample_notification(TaskOutcome::Failure);
+        json.send(&first).await.unwrap();
+        let text = FileSink::new(&path).with_template("{{outcome}} {{task_name}} exit={{exit_code}}");
+        text.send(&sample_notification(TaskOutcome::Error)).await.unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["run_id"], first.run_id.to_string());
+        assert_eq!(lines[1], "error nightly_backup exit=1");
+    }
+}
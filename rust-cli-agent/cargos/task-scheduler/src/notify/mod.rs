@@ -0,0 +1,569 @@
+//! 任务结果通知
+//!
+//! 基于执行后回调，把运行结果按路由规则发送到 webhook、SMTP 邮件、桌面通知和文件。
+//! 路由规则按任务 (ID 或名称) 或标签匹配，并指定关心的结果类型，默认只通知失败和出错
+//!
+//! 模板中的 `{{变量}}` 会被替换为通知内容，可用变量:
+//! `task_id`、`task_name`、`task_title`、`tags`、`run_id`、`status`、`outcome`、
+//! `error`、`exit_code`、`duration_ms`、`stdout`、`stderr`、`finished_at`、`subject`、`body`
+
+mod desktop;
+mod file;
+mod smtp;
+mod webhook;
+
+pub use desktop::DesktopSink;
+pub use file::FileSink;
+pub use smtp::{SmtpSettings, SmtpSink};
+pub use webhook::WebhookSink;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+use crate::hooks::{HookError, HookManager, HookResult, OnTaskAfterRun, TaskResultContext};
+use crate::types::TaskStatus;
+
+/// 通知中保留的输出长度 (字符)
+const OUTPUT_TAIL_CHARS: usize = 2000;
+
+/// 运行结果类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskOutcome {
+    /// 运行成功
+    Success,
+    /// 运行失败 (非零退出、超时、被停止)
+    Failure,
+    /// 执行出错
+    Error,
+}
+
+impl TaskOutcome {
+    /// 按运行结束时的状态归类，未结束的运行返回 None
+    pub fn from_status(status: &TaskStatus) -> Option<Self> {
+        match status {
+            TaskStatus::Completed => Some(TaskOutcome::Success),
+            TaskStatus::Failed => Some(TaskOutcome::Failure),
+            TaskStatus::Error => Some(TaskOutcome::Error),
+            _ => None,
+        }
+    }
+
+    /// 中文描述
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaskOutcome::Success => "成功",
+            TaskOutcome::Failure => "失败",
+            TaskOutcome::Error => "出错",
+        }
+    }
+}
+
+impl std::fmt::Display for TaskOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskOutcome::Success => write!(f, "success"),
+            TaskOutcome::Failure => write!(f, "failure"),
+            TaskOutcome::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl FromStr for TaskOutcome {
+    type Err = SchedulerError;
+
+    /// 解析 `success`、`failure`、`error`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "success" => Ok(TaskOutcome::Success),
+            "failure" => Ok(TaskOutcome::Failure),
+            "error" => Ok(TaskOutcome::Error),
+            _ => Err(SchedulerError::InvalidParameter(format!(
+                "Invalid task outcome: {} (expected success, failure or error)",
+                s
+            ))),
+        }
+    }
+}
+
+/// 一次运行结果的通知
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// 结果类型
+    pub outcome: TaskOutcome,
+    /// 任务 ID
+    pub task_id: Uuid,
+    /// 任务名称
+    pub task_name: String,
+    /// 任务标题
+    pub task_title: String,
+    /// 任务标签
+    pub tags: Vec<String>,
+    /// 运行实例 ID
+    pub run_id: Uuid,
+    /// 运行状态
+    pub status: TaskStatus,
+    /// 错误信息
+    pub error: Option<String>,
+    /// 退出码
+    pub exit_code: Option<i32>,
+    /// 执行时长(毫秒)
+    pub duration_ms: i64,
+    /// 标准输出末尾
+    pub stdout: Option<String>,
+    /// 标准错误末尾
+    pub stderr: Option<String>,
+    /// 结束时间
+    pub finished_at: DateTime<Utc>,
+}
+
+impl Notification {
+    /// 从执行后回调的上下文创建，未结束的运行返回 None
+    pub fn from_context(context: &TaskResultContext) -> Option<Self> {
+        let outcome = TaskOutcome::from_status(&context.status)?;
+        let result = &context.result;
+        Some(Self {
+            outcome,
+            task_id: context.task_id,
+            task_name: context.name.clone(),
+            task_title: context.title.clone(),
+            tags: context.tags.clone(),
+            run_id: context.run_instance_id,
+            status: context.status.clone(),
+            error: result.error.clone(),
+            exit_code: result.exit_code,
+            duration_ms: context.duration_ms,
+            stdout: result.stdout.as_deref().map(tail),
+            stderr: result.stderr.as_deref().map(tail),
+            finished_at: result.completed_at.unwrap_or_else(Utc::now),
+        })
+    }
+
+    /// 任务的显示名称，没有标题时使用名称或 ID
+    fn display_name(&self) -> String {
+        if !self.task_title.is_empty() {
+            self.task_title.clone()
+        } else if !self.task_name.is_empty() {
+            self.task_name.clone()
+        } else {
+            self.task_id.to_string()
+        }
+    }
+
+    /// 默认标题
+    pub fn subject(&self) -> String {
+        format!("[sker] 任务 {} 运行{}", self.display_name(), self.outcome.label())
+    }
+
+    /// 默认正文
+    pub fn body(&self) -> String {
+        let mut lines = vec![
+            format!("任务: {} ({})", self.display_name(), self.task_id),
+            format!("运行: {}", self.run_id),
+            format!("状态: {}", self.status),
+            format!("耗时: {}ms", self.duration_ms),
+            format!("结束时间: {}", self.finished_at.to_rfc3339()),
+        ];
+        if !self.tags.is_empty() {
+            lines.push(format!("标签: {}", self.tags.join(", ")));
+        }
+        if let Some(code) = self.exit_code {
+            lines.push(format!("退出码: {}", code));
+        }
+        if let Some(error) = &self.error {
+            lines.push(format!("错误: {}", error));
+        }
+        if let Some(stderr) = self.stderr.as_deref().filter(|s| !s.trim().is_empty()) {
+            lines.push(format!("标准错误:\n{}", stderr.trim_end()));
+        }
+        lines.join("\n")
+    }
+
+    /// 模板变量的值
+    fn variable(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "task_id" => self.task_id.to_string(),
+            "task_name" => self.task_name.clone(),
+            "task_title" => self.display_name(),
+            "tags" => self.tags.join(","),
+            "run_id" => self.run_id.to_string(),
+            "status" => self.status.to_string(),
+            "outcome" => self.outcome.to_string(),
+            "error" => self.error.clone().unwrap_or_default(),
+            "exit_code" => self.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+            "duration_ms" => self.duration_ms.to_string(),
+            "stdout" => self.stdout.clone().unwrap_or_default(),
+            "stderr" => self.stderr.clone().unwrap_or_default(),
+            "finished_at" => self.finished_at.to_rfc3339(),
+            "subject" => self.subject(),
+            "body" => self.body(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// 替换模板中的 `{{变量}}`，未知变量原样保留
+    pub fn render(&self, template: &str) -> String {
+        let mut output = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            output.push_str(&rest[..start]);
+            let placeholder = &rest[start..start + 2 + len + 2];
+            match self.variable(rest[start + 2..start + 2 + len].trim()) {
+                Some(value) => output.push_str(&value),
+                None => output.push_str(placeholder),
+            }
+            rest = &rest[start + placeholder.len()..];
+        }
+        output.push_str(rest);
+        output
+    }
+
+    /// 渲染 JSON 模板：只替换字符串值中的变量，替换结果按 JSON 转义
+    pub fn render_json(&self, template: &serde_json::Value) -> serde_json::Value {
+        match template {
+            serde_json::Value::String(s) => serde_json::Value::String(self.render(s)),
+            serde_json::Value::Array(items) => {
+                serde_json::Value::Array(items.iter().map(|v| self.render_json(v)).collect())
+            }
+            serde_json::Value::Object(fields) => serde_json::Value::Object(
+                fields
+                    .iter()
+                    .map(|(k, v)| (self.render(k), self.render_json(v)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+}
+
+/// 保留输出末尾
+fn tail(output: &str) -> String {
+    let count = output.chars().count();
+    if count <= OUTPUT_TAIL_CHARS {
+        return output.to_string();
+    }
+    output.chars().skip(count - OUTPUT_TAIL_CHARS).collect()
+}
+
+/// 通知发送目标
+#[async_trait]
+pub trait NotificationSink: Send + Sync {
+    /// 发送一条通知
+    async fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+fn default_outcomes() -> Vec<TaskOutcome> {
+    vec![TaskOutcome::Failure, TaskOutcome::Error]
+}
+
+/// 通知路由规则
+///
+/// `tasks` 和 `tags` 都为空时匹配所有任务，否则任务 ID、名称或任一标签命中即匹配
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationRoute {
+    /// 任务 ID 或名称
+    #[serde(default)]
+    pub tasks: Vec<String>,
+    /// 任务标签
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 需要通知的结果类型
+    #[serde(default = "default_outcomes")]
+    pub on: Vec<TaskOutcome>,
+    /// 发送目标名称
+    pub sinks: Vec<String>,
+}
+
+impl NotificationRoute {
+    /// 创建匹配所有任务、通知失败和出错的规则
+    pub fn new<I, S>(sinks: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            tasks: Vec::new(),
+            tags: Vec::new(),
+            on: default_outcomes(),
+            sinks: sinks.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// 匹配指定任务 (ID 或名称)
+    pub fn for_task(mut self, task: impl Into<String>) -> Self {
+        self.tasks.push(task.into());
+        self
+    }
+
+    /// 匹配带指定标签的任务
+    pub fn for_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    /// 设置需要通知的结果类型
+    pub fn on(mut self, outcomes: impl IntoIterator<Item = TaskOutcome>) -> Self {
+        self.on = outcomes.into_iter().collect();
+        self
+    }
+
+    /// 规则是否匹配这条通知
+    pub fn matches(&self, notification: &Notification) -> bool {
+        if !self.on.contains(&notification.outcome) {
+            return false;
+        }
+        if self.tasks.is_empty() && self.tags.is_empty() {
+            return true;
+        }
+        let task_id = notification.task_id.to_string();
+        self.tasks
+            .iter()
+            .any(|t| *t == task_id || (!notification.task_name.is_empty() && *t == notification.task_name))
+            || self.tags.iter().any(|t| notification.tags.contains(t))
+    }
+}
+
+/// 按路由规则把运行结果发送到各个目标
+///
+/// 作为执行后回调注册到 [`HookManager`]，同一目标被多条规则命中时只发送一次
+#[derive(Default)]
+pub struct NotificationRouter {
+    sinks: HashMap<String, Arc<dyn NotificationSink>>,
+    routes: Vec<NotificationRoute>,
+}
+
+impl NotificationRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加发送目标
+    pub fn with_sink(mut self, name: impl Into<String>, sink: Arc<dyn NotificationSink>) -> Self {
+        self.sinks.insert(name.into(), sink);
+        self
+    }
+
+    /// 添加路由规则
+    pub fn with_route(mut self, route: NotificationRoute) -> Self {
+        self.routes.push(route);
+        self
+    }
+
+    /// 是否没有路由规则
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// 检查路由规则引用的目标都已添加
+    pub fn validate(&self) -> Result<()> {
+        for route in &self.routes {
+            if let Some(name) = route.sinks.iter().find(|s| !self.sinks.contains_key(*s)) {
+                return Err(SchedulerError::NotificationError(format!(
+                    "Unknown notification sink: {}",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 命中的目标名称，按规则顺序去重
+    pub fn sinks_for(&self, notification: &Notification) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for route in self.routes.iter().filter(|r| r.matches(notification)) {
+            for name in &route.sinks {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// 发送通知到所有命中的目标
+    ///
+    /// 某个目标失败不影响其他目标，所有失败汇总到返回的错误中
+    pub async fn notify(&self, notification: &Notification) -> Result<()> {
+        let mut failures = Vec::new();
+        for name in self.sinks_for(notification) {
+            let outcome = match self.sinks.get(name) {
+                Some(sink) => sink.send(notification).await,
+                None => Err(SchedulerError::NotificationError(format!(
+                    "Unknown notification sink: {}",
+                    name
+                ))),
+            };
+            if let Err(e) = outcome {
+                failures.push(format!("{}: {}", name, e));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(SchedulerError::NotificationError(failures.join("; ")))
+        }
+    }
+
+    /// 注册为执行后回调
+    pub fn register(self: Arc<Self>, hooks: &mut HookManager) {
+        hooks.register_after_run(self);
+    }
+}
+
+#[async_trait]
+impl OnTaskAfterRun for NotificationRouter {
+    async fn on_after_run(&self, context: &TaskResultContext) -> HookResult<()> {
+        let Some(notification) = Notification::from_context(context) else {
+            return Ok(());
+        };
+        self.notify(&notification)
+            .await
+            .map_err(|e| HookError::CallbackError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cron_scheduler::CronTaskScheduler;
+    use crate::scheduler::TaskScheduler;
+    use crate::types::TaskExecutionResult;
+    use std::sync::Mutex;
+
+    pub(super) fn sample_notification(outcome: TaskOutcome) -> Notification {
+        Notification {
+            outcome,
+            task_id: Uuid::new_v4(),
+            task_name: "nightly_backup".to_string(),
+            task_title: "Nightly backup".to_string(),
+            tags: vec!["db".to_string()],
+            run_id: Uuid::new_v4(),
+            status: TaskStatus::Failed,
+            error: Some("exit \"1\"".to_string()),
+            exit_code: Some(1),
+            duration_ms: 42,
+            stdout: None,
+            stderr: Some("disk full\n".to_string()),
+            finished_at: Utc::now(),
+        }
+    }
+
+    /// 记录收到的通知的测试目标
+    #[derive(Default)]
+    struct RecordingSink {
+        received: Mutex<Vec<Uuid>>,
+    }
+
+    #[async_trait]
+    impl NotificationSink for RecordingSink {
+        async fn send(&self, notification: &Notification) -> Result<()> {
+            self.received.lock().unwrap().push(notification.run_id);
+            Ok(())
+        }
+    }
+
+    struct FailingSink;
+
+    #[async_trait]
+    impl NotificationSink for FailingSink {
+        async fn send(&self, _notification: &Notification) -> Result<()> {
+            Err(SchedulerError::NotificationError("connection refused".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let notification = sample_notification(TaskOutcome::Failure);
+        assert_eq!(
+            notification.render("{{task_name}} {{ outcome }} ({{exit_code}}) {{unknown}} {{"),
+            "nightly_backup failure (1) {{unknown}} {{"
+        );
+
+        let template = serde_json::json!({"text": "{{subject}}: {{error}}", "code": 1, "tags": ["{{tags}}"]});
+        let body = notification.render_json(&template);
+        assert_eq!(body["text"], "[sker] 任务 Nightly backup 运行失败: exit \"1\"");
+        assert_eq!(body["code"], 1);
+        assert_eq!(body["tags"][0], "db");
+    }
+
+    #[test]
+    fn test_route_matching() {
+        let failure = sample_notification(TaskOutcome::Failure);
+        assert!(NotificationRoute::new(["ops"]).matches(&failure));
+        assert!(NotificationRoute::new(["ops"]).for_task("nightly_backup").matches(&failure));
+        assert!(NotificationRoute::new(["ops"]).for_task(failure.task_id.to_string()).matches(&failure));
+        assert!(NotificationRoute::new(["ops"]).for_tag("db").matches(&failure));
+        assert!(!NotificationRoute::new(["ops"]).for_tag("web").matches(&failure));
+        assert!(!NotificationRoute::new(["ops"]).on([TaskOutcome::Success]).matches(&failure));
+
+        let success = sample_notification(TaskOutcome::Success);
+        assert!(!NotificationRoute::new(["ops"]).matches(&success));
+        assert_eq!("Error".parse::<TaskOutcome>().unwrap(), TaskOutcome::Error);
+        assert!("crashed".parse::<TaskOutcome>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_router_dedupes_and_collects_failures() {
+        let sink = Arc::new(RecordingSink::default());
+        let router = NotificationRouter::new()
+            .with_sink("ops", sink.clone())
+            .with_sink("broken", Arc::new(FailingSink))
+            .with_route(NotificationRoute::new(["ops"]).for_tag("db"))
+            .with_route(NotificationRoute::new(["ops", "broken"]).for_task("nightly_backup"));
+        router.validate().unwrap();
+
+        let notification = sample_notification(TaskOutcome::Error);
+        let err = router.notify(&notification).await.unwrap_err();
+        assert!(err.to_string().contains("broken: Notification error: connection refused"));
+        assert_eq!(*sink.received.lock().unwrap(), vec![notification.run_id]);
+
+        let invalid = NotificationRouter::new().with_route(NotificationRoute::new(["missing"]));
+        assert!(invalid.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_router_receives_scheduler_failures() {
+        let sink = Arc::new(RecordingSink::default());
+        let router = Arc::new(
+            NotificationRouter::new()
+                .with_sink("ops", sink.clone())
+                .with_route(NotificationRoute::new(["ops"]).for_tag("nightly")),
+        );
+        let mut hooks = HookManager::new();
+        router.register(&mut hooks);
+        let scheduler = CronTaskScheduler::new().await.unwrap().with_hooks(hooks);
+
+        let failing = crate::execution::executor_fn(|task_id, _params, _ctx| async move {
+            let mut result = TaskExecutionResult::failure(task_id, "exit code 3".to_string());
+            result.exit_code = Some(3);
+            Ok(result)
+        });
+        let task = scheduler
+            .add_task("Nightly".to_string(), "nightly".to_string(), "0 0 3 * * *".to_string(), failing)
+            .await
+            .unwrap();
+        scheduler
+            .update_task(crate::types::TaskUpdateRequest {
+                id: task.id,
+                tags: Some(vec!["nightly".to_string()]),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let instance = scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Failed);
+        assert_eq!(*sink.received.lock().unwrap(), vec![instance.id]);
+    }
+}
@@ -0,0 +1,323 @@
+//! SMTP 邮件通知
+//!
+//! 内置精简的 SMTP 客户端，支持 AUTH PLAIN，不支持 TLS，
+//! 适用于本机或内网的邮件中继
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use super::{Notification, NotificationSink};
+use crate::error::{Result, SchedulerError};
+
+fn default_port() -> u16 {
+    25
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+/// SMTP 服务器和收发件人
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmtpSettings {
+    /// 服务器地址
+    pub host: String,
+    /// 服务器端口
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// 发件人
+    pub from: String,
+    /// 收件人
+    pub to: Vec<String>,
+    /// 登录用户名，为空时不登录
+    #[serde(default)]
+    pub username: Option<String>,
+    /// 登录密码
+    #[serde(default)]
+    pub password: Option<String>,
+    /// 整个会话的超时(秒)
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl SmtpSettings {
+    pub fn new(host: impl Into<String>, port: u16, from: impl Into<String>, to: Vec<String>) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            from: from.into(),
+            to,
+            username: None,
+            password: None,
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+
+    /// 设置登录凭据
+    pub fn with_credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+}
+
+/// 发送纯文本邮件
+pub struct SmtpSink {
+    settings: SmtpSettings,
+    subject: Option<String>,
+    body: Option<String>,
+}
+
+impl SmtpSink {
+    pub fn new(settings: SmtpSettings) -> Result<Self> {
+        if settings.to.is_empty() {
+            return Err(SchedulerError::InvalidParameter(
+                "SMTP sink needs at least one recipient".to_string(),
+            ));
+        }
+        Ok(Self {
+            settings,
+            subject: None,
+            body: None,
+        })
+    }
+
+    /// 设置主题模板，默认使用通知的默认标题
+    pub fn with_subject_template(mut self, template: impl Into<String>) -> Self {
+        self.subject = Some(template.into());
+        self
+    }
+
+    /// 设置正文模板，默认使用通知的默认正文
+    pub fn with_body_template(mut self, template: impl Into<String>) -> Self {
+        self.body = Some(template.into());
+        self
+    }
+
+    /// 组装邮件内容 (头部和正文，已做行首点号转义)
+    fn message(&self, notification: &Notification) -> String {
+        let subject = match &self.subject {
+            Some(template) => notification.render(template),
+            None => notification.subject(),
+        };
+        let body = match &self.body {
+            Some(template) => notification.render(template),
+            None => notification.body(),
+        };
+        let mut message = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+            self.settings.from,
+            self.settings.to.join(", "),
+            encode_header(&subject),
+            chrono::Utc::now().to_rfc2822(),
+        );
+        for line in body.lines() {
+            if line.starts_with('.') {
+                message.push('.');
+            }
+            message.push_str(line);
+            message.push_str("\r\n");
+        }
+        message
+    }
+
+    async fn deliver(&self, message: &str) -> Result<()> {
+        let address = format!("{}:{}", self.settings.host, self.settings.port);
+        let stream = TcpStream::connect(&address)
+            .await
+            .map_err(|e| smtp_error(format!("Failed to connect to {}: {}", address, e)))?;
+        let mut session = SmtpSession::new(stream);
+
+        session.expect(220).await?;
+        session.command("EHLO sker", 250).await?;
+        if let Some(username) = &self.settings.username {
+            let password = self.settings.password.as_deref().unwrap_or_default();
+            let token = STANDARD.encode(format!("\0{}\0{}", username, password));
+            session.command(&format!("AUTH PLAIN {}", token), 235).await?;
+        }
+        session.command(&format!("MAIL FROM:<{}>", self.settings.from), 250).await?;
+        for recipient in &self.settings.to {
+            session.command(&format!("RCPT TO:<{}>", recipient), 250).await?;
+        }
+        session.command("DATA", 354).await?;
+        session.write(message).await?;
+        session.command(".", 250).await?;
+        // 邮件已被接受，QUIT 失败不影响结果
+        let _ = session.command("QUIT", 221).await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NotificationSink for SmtpSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let message = self.message(notification);
+        let timeout = Duration::from_secs(self.settings.timeout_secs);
+        tokio::time::timeout(timeout, self.deliver(&message))
+            .await
+            .map_err(|_| smtp_error("SMTP session timed out".to_string()))?
+    }
+}
+
+fn smtp_error(message: String) -> SchedulerError {
+    SchedulerError::NotificationError(message)
+}
+
+/// 非 ASCII 的头部按 RFC 2047 编码
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(value))
+    }
+}
+
+/// 一次 SMTP 会话
+struct SmtpSession<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> SmtpSession<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    async fn write(&mut self, data: &str) -> Result<()> {
+        self.stream
+            .get_mut()
+            .write_all(data.as_bytes())
+            .await
+            .map_err(|e| smtp_error(e.to_string()))
+    }
+
+    /// 发送一条命令并检查响应码
+    async fn command(&mut self, command: &str, code: u16) -> Result<()> {
+        self.write(&format!("{}\r\n", command)).await?;
+        self.expect(code).await.map_err(|e| match command.split(' ').next() {
+            // 不在错误中暴露登录凭据
+            Some("AUTH") => smtp_error(format!("AUTH failed: {}", e)),
+            _ => e,
+        })
+    }
+
+    /// 读取 (可能多行的) 响应并检查响应码
+    async fn expect(&mut self, code: u16) -> Result<()> {
+        loop {
+            let mut line = String::new();
+            let read = self
+                .stream
+                .read_line(&mut line)
+                .await
+                .map_err(|e| smtp_error(e.to_string()))?;
+            if read == 0 {
+                return Err(smtp_error("SMTP server closed the connection".to_string()));
+            }
+            let line = line.trim_end();
+            let status = line.get(..3).and_then(|s| s.parse::<u16>().ok());
+            if status != Some(code) && !(code == 250 && status == Some(251)) {
+                return Err(smtp_error(format!("Unexpected SMTP response: {}", line)));
+            }
+            // "250-" 表示后面还有响应行
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::tests::sample_notification;
+    use crate::notify::TaskOutcome;
+    use tokio::net::TcpListener;
+
+    /// 本地 SMTP 服务：接收一封邮件，返回收到的命令和邮件内容
+    async fn capture_mail(reject_rcpt: bool) -> (u16, tokio::task::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut commands = Vec::new();
+            let mut data = String::new();
+            stream.get_mut().write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                commands.push(line.clone());
+                let reply: &[u8] = match line.split(' ').next().unwrap() {
+                    "EHLO" => b"250-localhost\r\n250 AUTH PLAIN\r\n",
+                    "AUTH" => b"235 ok\r\n",
+                    "RCPT" if reject_rcpt => b"550 no such user\r\n",
+                    "DATA" => {
+                        stream.get_mut().write_all(b"354 go ahead\r\n").await.unwrap();
+                        loop {
+                            let mut line = String::new();
+                            stream.read_line(&mut line).await.unwrap();
+                            if line == ".\r\n" {
+                                break;
+                            }
+                            data.push_str(&line);
+                        }
+                        b"250 queued\r\n"
+                    }
+                    "QUIT" => {
+                        stream.get_mut().write_all(b"221 bye\r\n").await.unwrap();
+                        break;
+                    }
+                    _ => b"250 ok\r\n",
+                };
+                stream.get_mut().write_all(reply).await.unwrap();
+            }
+            (commands, data)
+        });
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn test_smtp_sink_delivers_mail() {
+        let (port, server) = capture_mail(false).await;
+        let settings = SmtpSettings::new("127.0.0.1", port, "sker@localhost", vec!["oncall@example.com".to_string()])
+            .with_credentials("bot", "secret");
+        let sink = SmtpSink::new(settings)
+            .unwrap()
+            .with_body_template("{{task_name}} failed\n.hidden line");
+        sink.send(&sample_notification(TaskOutcome::Failure)).await.unwrap();
+
+        let (commands, data) = server.await.unwrap();
+        assert_eq!(commands[0], "EHLO sker");
+        assert_eq!(commands[1], format!("AUTH PLAIN {}", STANDARD.encode("\0bot\0secret")));
+        assert_eq!(commands[2], "MAIL FROM:<sker@localhost>");
+        assert_eq!(commands[3], "RCPT TO:<oncall@example.com>");
+        assert_eq!(commands.last().unwrap(), "QUIT");
+        assert!(data.contains("To: oncall@example.com\r\n"));
+        assert!(data.contains(&format!("Subject: {}\r\n", encode_header("[sker] 任务 Nightly backup 运行失败"))));
+        assert!(data.ends_with("\r\nnightly_backup failed\r\n..hidden line\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_smtp_sink_reports_rejection() {
+        let (port, _server) = capture_mail(true).await;
+        let settings = SmtpSettings::new("127.0.0.1", port, "sker@localhost", vec!["nobody@example.com".to_string()]);
+        let err = SmtpSink::new(settings)
+            .unwrap()
+            .send(&sample_notification(TaskOutcome::Error))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("550 no such user"));
+
+        assert!(SmtpSink::new(SmtpSettings::new("127.0.0.1", port, "sker@localhost", Vec::new())).is_err());
+    }
+}
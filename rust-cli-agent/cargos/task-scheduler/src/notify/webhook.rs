@@ -0,0 +1,175 @@
+//! HTTP webhook 通知
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+
+use super::{Notification, NotificationSink};
+use crate::error::{Result, SchedulerError};
+
+/// 默认请求超时
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 以 JSON 请求体调用 HTTP 接口
+///
+/// 没有设置请求体模板时发送完整的通知 JSON
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    method: Method,
+    headers: Vec<(String, String)>,
+    body: Option<serde_json::Value>,
+}
+
+impl WebhookSink {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: Self::client(DEFAULT_TIMEOUT),
+            url: url.into(),
+            method: Method::POST,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    fn client(timeout: Duration) -> reqwest::Client {
+        reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new())
+    }
+
+    /// 设置请求方法，默认 POST
+    pub fn with_method(mut self, method: &str) -> Result<Self> {
+        self.method = Method::from_bytes(method.trim().to_uppercase().as_bytes())
+            .map_err(|_| SchedulerError::InvalidParameter(format!("Invalid HTTP method: {}", method)))?;
+        Ok(self)
+    }
+
+    /// 添加请求头，值可以使用模板变量
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// 设置 JSON 请求体模板，字符串值中可以使用模板变量
+    pub fn with_body_template(mut self, template: &str) -> Result<Self> {
+        let body = serde_json::from_str(template).map_err(|e| {
+            SchedulerError::InvalidParameter(format!("Invalid webhook body template: {}", e))
+        })?;
+        self.body = Some(body);
+        Ok(self)
+    }
+
+    /// 设置请求超时
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = Self::client(timeout);
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationSink for WebhookSink {
+    async fn send(&self, notification: &Notification) -> Result<()> {
+        let body = match &self.body {
+            Some(template) => notification.render_json(template),
+            None => serde_json::to_value(notification)
+                .map_err(|e| SchedulerError::NotificationError(e.to_string()))?,
+        };
+        let mut request = self
+            .client
+            .request(self.method.clone(), &self.url)
+            .header(CONTENT_TYPE, "application/json");
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), notification.render(value));
+        }
+        let response = request
+            .body(body.to_string())
+            .send()
+            .await
+            .map_err(|e| SchedulerError::NotificationError(format!("Webhook request failed: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(SchedulerError::NotificationError(format!(
+                "Webhook returned HTTP {}",
+                status
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::tests::sample_notification;
+    use crate::notify::TaskOutcome;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// 本地 HTTP 服务：接收一个请求，返回请求头和请求体
+    async fn capture_one(status: u16) -> (String, tokio::task::JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await.unwrap();
+                let line = line.trim_end().to_string();
+                if line.is_empty() {
+                    break;
+                }
+                headers.push(line);
+            }
+            let length = headers
+                .iter()
+                .find_map(|h| h.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).await.unwrap();
+            let response = format!("HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            reader.get_mut().write_all(response.as_bytes()).await.unwrap();
+            (headers, String::from_utf8(body).unwrap())
+        });
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_templated_body() {
+        let (url, server) = capture_one(200).await;
+        let sink = WebhookSink::new(url)
+            .with_header("X-Task", "{{task_name}}")
+            .with_body_template(r#"{"text": "{{task_title}} {{outcome}}: {{error}}", "run": "{{run_id}}"}"#)
+            .unwrap();
+        let notification = sample_notification(TaskOutcome::Failure);
+        sink.send(&notification).await.unwrap();
+
+        let (headers, body) = server.await.unwrap();
+        assert!(headers[0].starts_with("POST /hook"));
+        assert!(headers.iter().any(|h| h.eq_ignore_ascii_case("x-task: nightly_backup")));
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["text"], "Nightly backup failure: exit \"1\"");
+        assert_eq!(body["run"], notification.run_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_default_body_and_http_error() {
+        let (url, server) = capture_one(500).await;
+        let sink = WebhookSink::new(url).with_method("put").unwrap();
+        let err = sink.send(&sample_notification(TaskOutcome::Error)).await.unwrap_err();
+        assert!(err.to_string().contains("HTTP 500"));
+
+        let (headers, body) = server.await.unwrap();
+        assert!(headers[0].starts_with("PUT "));
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["outcome"], "error");
+        assert_eq!(body["task_name"], "nightly_backup");
+
+        assert!(WebhookSink::new("http://localhost").with_body_template("{not json").is_err());
+    }
+}
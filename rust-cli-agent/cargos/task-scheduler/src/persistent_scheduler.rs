@@ -2,51 +2,321 @@
 //!
 //! 支持任务持久化存储的 Cron 调度器
 
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use cron::Schedule;
-use std::str::FromStr;
 use chrono::{DateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 
+use crate::action::{ActionRegistry, TaskAction};
+use crate::bundle::{ExportOptions, ImportOptions, ImportReport, ScheduleBundle};
+use crate::concurrency::ConcurrencyLimits;
+use crate::cron_scheduler::upgrade_cron_expression;
+use crate::hooks::HookManager;
 use crate::error::{Result, SchedulerError};
+use crate::retention::{PruneReport, RetentionPolicy};
 use crate::scheduler::TaskScheduler;
 use crate::storage::{SchedulerStorage, SledSchedulerStorage};
+use crate::timezone::ZonedSchedule;
 use crate::types::*;
+use crate::workflow::{Workflow, WorkflowBriefing, WorkflowRun};
+use uuid::Uuid;
 
 /// 计算下次运行时间
-fn calculate_next_run(cron_expression: &str) -> Option<DateTime<Utc>> {
-    let schedule = Schedule::from_str(cron_expression).ok()?;
-    schedule.after(&Utc::now()).next()
+fn calculate_next_run(cron_expression: &str, timezone: Option<&str>) -> Option<DateTime<Utc>> {
+    let schedule = ZonedSchedule::parse(cron_expression, timezone).ok()?;
+    schedule.next_after(Utc::now())
 }
 
+/// 任务定义是否发生变化 (忽略运行状态字段)
+fn definition_changed(current: &ScheduledTask, stored: &ScheduledTask) -> bool {
+    current.title != stored.title
+        || current.name != stored.name
+        || current.description != stored.description
+        || current.content != stored.content
+        || current.cron_expression != stored.cron_expression
+        || current.enabled != stored.enabled
+        || current.is_system != stored.is_system
+        || current.action != stored.action
+        || current.misfire != stored.misfire
+        || current.retry != stored.retry
+        || current.depends_on != stored.depends_on
+        || current.overlap != stored.overlap
+        || current.tags != stored.tags
+        || current.timezone != stored.timezone
+        || current.cron_version != stored.cron_version
+        || (current.status == TaskStatus::Paused) != (stored.status == TaskStatus::Paused)
+}
+
+/// 一次重新同步的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadSummary {
+    /// 新增的任务数
+    pub added: usize,
+    /// 定义发生变化的任务数
+    pub updated: usize,
+    /// 已删除的任务数
+    pub removed: usize,
+}
+
+impl ReloadSummary {
+    /// 是否有任何变化
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.updated == 0 && self.removed == 0
+    }
+}
+
+/// 跨进程停止任务后等待运行方记录结果的最长时间
+const REMOTE_STOP_WAIT: std::time::Duration = std::time::Duration::from_secs(3);
+
 /// 支持持久化存储的 Cron 调度器
 pub struct PersistentCronTaskScheduler {
     /// 内部基础调度器
     scheduler: Arc<crate::cron_scheduler::CronTaskScheduler>,
     /// 持久化存储
-    storage: Arc<SledSchedulerStorage>,
+    storage: Arc<dyn SchedulerStorage>,
 }
 
 impl PersistentCronTaskScheduler {
     /// 创建新的持久化调度器
     pub async fn new(data_dir: PathBuf) -> Result<Self> {
+        Self::with_action_registry(data_dir, ActionRegistry::new()).await
+    }
+
+    /// 使用指定的动作注册表创建持久化调度器
+    ///
+    /// 存储中带有动作的任务会通过注册表重建执行器并重新注册到调度器
+    pub async fn with_action_registry(data_dir: PathBuf, registry: ActionRegistry) -> Result<Self> {
         // 确保目录存在
         std::fs::create_dir_all(&data_dir).map_err(|e| {
             SchedulerError::StorageError(format!("Failed to create data directory: {}", e))
         })?;
 
-        let storage = Arc::new(
-            SledSchedulerStorage::new(data_dir)
-                .map_err(|e| SchedulerError::StorageError(e.to_string()))?,
+        // 共享模式打开存储，守护进程和其他 CLI 调用可同时使用同一数据目录
+        let storage = Arc::new(SledSchedulerStorage::shared(data_dir));
+        Self::with_storage(storage, registry).await
+    }
+
+    /// 使用指定的存储和动作注册表创建持久化调度器
+    pub async fn with_storage(storage: Arc<dyn SchedulerStorage>, registry: ActionRegistry) -> Result<Self> {
+        // 运行实例和日志由内部调度器写穿到存储
+        let scheduler = Arc::new(
+            crate::cron_scheduler::CronTaskScheduler::with_storage(storage.clone())
+                .await?
+                .with_action_registry(registry),
         );
-        let scheduler = Arc::new(crate::cron_scheduler::CronTaskScheduler::new().await?);
 
-        Ok(Self {
+        let persistent = Self {
             scheduler,
             storage,
-        })
+        };
+        persistent.restore_tasks().await?;
+        persistent.restore_workflows().await?;
+        Ok(persistent)
+    }
+
+    /// 调整并发上限，正在运行的任务占用的名额计入新上限
+    pub fn set_concurrency_limits(&self, limits: &ConcurrencyLimits) {
+        self.scheduler.set_concurrency_limits(limits);
+    }
+
+    /// 设置任务数量上限，0 表示不限制
+    pub fn set_max_tasks(&self, max_tasks: usize) {
+        self.scheduler.set_max_tasks(max_tasks);
+    }
+
+    /// 设置新建任务和工作流的默认时区，已有任务和工作流保持各自的时区
+    pub fn set_default_timezone(&self, timezone: Tz) {
+        self.scheduler.set_default_timezone(timezone);
+    }
+
+    /// 替换任务执行回调
+    ///
+    /// 错误回调建议的补救任务同样写入存储
+    pub async fn set_hooks(&self, hooks: HookManager) {
+        self.scheduler.set_hooks(hooks).await;
+    }
+
+    /// 从存储恢复任务
+    async fn restore_tasks(&self) -> Result<()> {
+        for task in self.load_tasks().await? {
+            self.restore_task(task).await;
+        }
+        Ok(())
+    }
+
+    /// 恢复单个任务
+    ///
+    /// 没有动作或动作无法解析、或 cron 表达式无法迁移的任务只登记元数据，可查询但不会被执行
+    async fn restore_task(&self, mut task: ScheduledTask) {
+        if let Err(e) = self.migrate_task(&mut task).await {
+            tracing::warn!("Task {} cannot be scheduled: {}", task.id, e);
+            self.scheduler.insert_task(task).await;
+            return;
+        }
+        let executor = match &task.action {
+            Some(action) => match self.scheduler.action_registry().resolve(action) {
+                Ok(executor) => Some(executor),
+                Err(e) => {
+                    tracing::warn!("Task {} action cannot be restored: {}", task.id, e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        match executor {
+            Some(executor) => {
+                if let Err(e) = self.scheduler.schedule_task(task.clone(), executor).await {
+                    tracing::warn!("Task {} cannot be scheduled: {}", task.id, e);
+                    self.scheduler.insert_task(task).await;
+                }
+            }
+            None => self.scheduler.insert_task(task).await,
+        }
+    }
+
+    /// 从存储恢复工作流
+    async fn restore_workflows(&self) -> Result<()> {
+        for workflow in self.load_workflows().await? {
+            self.restore_workflow(workflow).await;
+        }
+        Ok(())
+    }
+
+    /// 恢复单个工作流，无法注册作业的工作流只登记定义
+    async fn restore_workflow(&self, mut workflow: Workflow) {
+        let scheduled = match self.migrate_workflow(&mut workflow).await {
+            Ok(()) => self.scheduler.schedule_workflow(workflow.clone()).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = scheduled {
+            tracing::warn!("Workflow {} cannot be scheduled: {}", workflow.id, e);
+            self.scheduler.insert_workflow(workflow).await;
+        }
+    }
+
+    /// 将按旧版 cron 语法保存的任务改写为当前语法并写回存储
+    ///
+    /// 日和周字段都受限的表达式无法改写，任务保持原样，设置新表达式后才会调度
+    async fn migrate_task(&self, task: &mut ScheduledTask) -> Result<()> {
+        if task.cron_version >= CRON_VERSION {
+            return Ok(());
+        }
+        let cron_expression = upgrade_cron_expression(&task.cron_expression)?;
+        if cron_expression != task.cron_expression {
+            tracing::info!(
+                "Task {} cron expression migrated: {} -> {}",
+                task.id,
+                task.cron_expression,
+                cron_expression
+            );
+        }
+        task.cron_expression = cron_expression;
+        task.cron_version = CRON_VERSION;
+        self.sync_task(task).await
+    }
+
+    /// 将按旧版 cron 语法保存的工作流改写为当前语法并写回存储
+    async fn migrate_workflow(&self, workflow: &mut Workflow) -> Result<()> {
+        if workflow.cron_version >= CRON_VERSION {
+            return Ok(());
+        }
+        if let Some(cron_expression) = &workflow.cron_expression {
+            let upgraded = upgrade_cron_expression(cron_expression)?;
+            if upgraded != *cron_expression {
+                tracing::info!(
+                    "Workflow {} cron expression migrated: {} -> {}",
+                    workflow.id,
+                    cron_expression,
+                    upgraded
+                );
+            }
+            workflow.cron_expression = Some(upgraded);
+        }
+        workflow.cron_version = CRON_VERSION;
+        self.storage
+            .save_workflow(workflow)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))
+    }
+
+    /// 与存储重新同步工作流，计入 summary
+    async fn reload_workflows(&self, summary: &mut ReloadSummary) -> Result<()> {
+        let stored = self.load_workflows().await?;
+        let current: HashMap<Uuid, Workflow> = self
+            .scheduler
+            .list_workflows()
+            .await?
+            .into_iter()
+            .map(|w| (w.id, w))
+            .collect();
+
+        let stored_ids: HashSet<Uuid> = stored.iter().map(|w| w.id).collect();
+        for workflow in stored {
+            match current.get(&workflow.id) {
+                None => {
+                    self.restore_workflow(workflow).await;
+                    summary.added += 1;
+                }
+                Some(existing) if *existing != workflow => {
+                    self.restore_workflow(workflow).await;
+                    summary.updated += 1;
+                }
+                Some(_) => {}
+            }
+        }
+        for workflow_id in current.keys().filter(|id| !stored_ids.contains(id)) {
+            self.scheduler.remove_workflow(*workflow_id).await?;
+            summary.removed += 1;
+        }
+        Ok(())
+    }
+
+    /// 与存储重新同步任务和工作流
+    ///
+    /// 其他进程新增、修改或删除的任务在此生效，守护进程定期调用
+    pub async fn reload_tasks(&self) -> Result<ReloadSummary> {
+        let stored = self.load_tasks().await?;
+        let current: HashMap<Uuid, ScheduledTask> = self
+            .scheduler
+            .list_tasks()
+            .await?
+            .into_iter()
+            .map(|t| (t.id, t))
+            .collect();
+
+        let mut summary = ReloadSummary::default();
+        let stored_ids: HashSet<Uuid> = stored.iter().map(|t| t.id).collect();
+        for task in stored {
+            match current.get(&task.id) {
+                None => {
+                    self.restore_task(task).await;
+                    summary.added += 1;
+                }
+                Some(existing) if definition_changed(existing, &task) => {
+                    self.scheduler.remove_task(task.id).await?;
+                    self.restore_task(task).await;
+                    summary.updated += 1;
+                }
+                Some(_) => {}
+            }
+        }
+        for task_id in current.keys().filter(|id| !stored_ids.contains(id)) {
+            self.scheduler.remove_task(*task_id).await?;
+            summary.removed += 1;
+        }
+        self.reload_workflows(&mut summary).await?;
+        Ok(summary)
+    }
+
+    /// 处理错过的计划运行，返回启动的补跑次数
+    ///
+    /// 守护进程启动时和每次同步后调用
+    pub async fn catch_up_missed_runs(&self) -> Result<usize> {
+        self.scheduler.catch_up_missed_runs().await
     }
 
     /// 加载所有任务
@@ -57,6 +327,14 @@ impl PersistentCronTaskScheduler {
             .map_err(|e| SchedulerError::StorageError(e.to_string()))
     }
 
+    /// 加载所有工作流
+    pub async fn load_workflows(&self) -> Result<Vec<Workflow>> {
+        self.storage
+            .list_workflows()
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))
+    }
+
     /// 同步任务到存储
     async fn sync_task(&self, task: &ScheduledTask) -> Result<()> {
         self.storage
@@ -75,6 +353,10 @@ impl PersistentCronTaskScheduler {
             .delete_run_instances(task_id)
             .await
             .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        self.storage
+            .delete_run_summary(task_id)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
         Ok(())
     }
 }
@@ -131,6 +413,24 @@ impl TaskScheduler for PersistentCronTaskScheduler {
         Ok(task)
     }
 
+    async fn add_task_with_action(
+        &self,
+        title: String,
+        name: String,
+        description: Option<String>,
+        content: Option<String>,
+        cron_expression: String,
+        action: TaskAction,
+        is_system: bool,
+    ) -> Result<ScheduledTask> {
+        let task = self
+            .scheduler
+            .add_task_with_action(title, name, description, content, cron_expression, action, is_system)
+            .await?;
+        self.sync_task(&task).await?;
+        Ok(task)
+    }
+
     async fn remove_task(&self, task_id: uuid::Uuid) -> Result<()> {
         self.scheduler.remove_task(task_id).await?;
         self.delete_task_from_storage(task_id).await?;
@@ -152,8 +452,14 @@ impl TaskScheduler for PersistentCronTaskScheduler {
     }
 
     async fn update_task(&self, request: TaskUpdateRequest) -> Result<ScheduledTask> {
+        let migrated = self.scheduler.get_task(request.id).await?.cron_version >= CRON_VERSION;
         let task = self.scheduler.update_task(request.clone()).await?;
         self.sync_task(&task).await?;
+        if !migrated && task.cron_version >= CRON_VERSION {
+            // 未能迁移的任务设置新表达式后按正常任务重新调度
+            self.scheduler.remove_task(task.id).await?;
+            self.restore_task(task.clone()).await;
+        }
         Ok(task)
     }
 
@@ -171,7 +477,7 @@ impl TaskScheduler for PersistentCronTaskScheduler {
         // 重新计算下次运行时间（对于未暂停的任务）
         for task in &mut tasks {
             if task.enabled && task.status != TaskStatus::Paused {
-                task.next_run = calculate_next_run(&task.cron_expression);
+                task.next_run = calculate_next_run(&task.cron_expression, task.timezone.as_deref());
             }
         }
 
@@ -192,17 +498,60 @@ impl TaskScheduler for PersistentCronTaskScheduler {
         task_id: uuid::Uuid,
         user_params: std::collections::HashMap<String, String>,
     ) -> Result<TaskRunInstance> {
-        let instance = self.scheduler.run_task(task_id, user_params).await?;
-        // 保存运行实例到存储
-        self.storage
-            .save_run_instance(&instance)
-            .await
-            .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
-        Ok(instance)
+        // 运行实例由内部调度器写入存储
+        self.scheduler.run_task(task_id, user_params).await
     }
 
     async fn stop_task(&self, run_instance_id: uuid::Uuid) -> Result<()> {
-        self.scheduler.stop_task(run_instance_id).await
+        if self.scheduler.is_live(run_instance_id).await {
+            return self.scheduler.stop_task(run_instance_id).await;
+        }
+
+        // 由其他进程运行：按记录的进程组终止
+        let mut instance = self
+            .storage
+            .load_run_instance(run_instance_id)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))?
+            .ok_or(SchedulerError::RunInstanceNotFound(run_instance_id))?;
+        if instance.status != TaskStatus::Running {
+            return Err(SchedulerError::InvalidParameter(
+                "Task is not running".to_string(),
+            ));
+        }
+
+        if let Some(pid) = instance.pid {
+            command_executor::terminate_process_group(pid, command_executor::DEFAULT_KILL_GRACE).await;
+
+            // 等待运行方记录真实的退出结果
+            let deadline = tokio::time::Instant::now() + REMOTE_STOP_WAIT;
+            while tokio::time::Instant::now() < deadline {
+                if let Some(current) = self
+                    .storage
+                    .load_run_instance(run_instance_id)
+                    .await
+                    .map_err(|e| SchedulerError::StorageError(e.to_string()))?
+                {
+                    if current.status != TaskStatus::Running {
+                        return Ok(());
+                    }
+                    instance = current;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+        }
+
+        // 运行方已不存在：直接标记为停止
+        instance.status = TaskStatus::Failed;
+        instance.completed_at = Some(Utc::now());
+        instance.result = Some(TaskExecutionResult::failure(
+            instance.task_id,
+            "Task stopped by user".to_string(),
+        ));
+        self.storage
+            .save_run_instance(&instance)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))
     }
 
     async fn get_run_instance(&self, run_instance_id: uuid::Uuid) -> Result<TaskRunInstance> {
@@ -240,13 +589,8 @@ impl TaskScheduler for PersistentCronTaskScheduler {
     }
 
     async fn add_log(&self, run_instance_id: uuid::Uuid, level: LogLevel, message: String) -> Result<TaskLog> {
-        let log = self.scheduler.add_log(run_instance_id, level, message).await?;
-        // 保存到存储
-        self.storage
-            .save_log(&log)
-            .await
-            .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
-        Ok(log)
+        // 日志由内部调度器写入存储
+        self.scheduler.add_log(run_instance_id, level, message).await
     }
 
     async fn get_instance_logs(
@@ -278,7 +622,15 @@ impl TaskScheduler for PersistentCronTaskScheduler {
     }
 
     async fn get_task_briefing(&self, task_id: uuid::Uuid) -> Result<TaskBriefing> {
-        self.scheduler.get_task_briefing(task_id).await
+        // 简报包含其他进程记录的运行实例 (含过期的运行)
+        let task = self.scheduler.get_task(task_id).await?;
+        let instances = self.get_task_instances(task_id).await?;
+        let summary = self
+            .storage
+            .load_run_summary(task_id)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        Ok(TaskBriefing::from_task(&task, instances).with_history(summary))
     }
 
     async fn clear_all_tasks(&self) -> Result<usize> {
@@ -298,10 +650,89 @@ impl TaskScheduler for PersistentCronTaskScheduler {
             .clear_all_tasks()
             .await
             .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        self.storage
+            .clear_all_workflows()
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
 
         Ok(count)
     }
 
+    async fn prune_history(&self, policy: RetentionPolicy, dry_run: bool) -> Result<PruneReport> {
+        // 内部调度器同时清理内存和存储中的运行记录
+        self.scheduler.prune_history(policy, dry_run).await
+    }
+
+    async fn export_bundle(&self, options: ExportOptions) -> Result<ScheduleBundle> {
+        ScheduleBundle::export(self.storage.as_ref(), &options).await
+    }
+
+    async fn import_bundle(&self, bundle: ScheduleBundle, options: ImportOptions) -> Result<ImportReport> {
+        let report = bundle.import(self.storage.as_ref(), &options).await?;
+        if !options.dry_run {
+            // 与存储同步，导入和覆盖的任务按新定义重新调度
+            self.reload_tasks().await?;
+        }
+        Ok(report)
+    }
+
+    async fn add_workflow(&self, workflow: Workflow) -> Result<Workflow> {
+        let workflow = self.scheduler.add_workflow(workflow).await?;
+        self.storage
+            .save_workflow(&workflow)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        Ok(workflow)
+    }
+
+    async fn remove_workflow(&self, workflow_id: Uuid) -> Result<()> {
+        self.scheduler.remove_workflow(workflow_id).await?;
+        self.storage
+            .delete_workflow(workflow_id)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))
+    }
+
+    async fn get_workflow(&self, workflow_id: Uuid) -> Result<Workflow> {
+        self.scheduler.get_workflow(workflow_id).await
+    }
+
+    async fn list_workflows(&self) -> Result<Vec<Workflow>> {
+        self.load_workflows().await
+    }
+
+    async fn run_workflow(
+        &self,
+        workflow_id: Uuid,
+        user_params: HashMap<String, String>,
+    ) -> Result<WorkflowRun> {
+        // 运行记录由内部调度器写入存储
+        self.scheduler.run_workflow(workflow_id, user_params).await
+    }
+
+    async fn get_workflow_run(&self, run_id: Uuid) -> Result<WorkflowRun> {
+        match self.scheduler.get_workflow_run(run_id).await {
+            Ok(run) => Ok(run),
+            Err(_) => self
+                .storage
+                .load_workflow_run(run_id)
+                .await
+                .map_err(|e| SchedulerError::StorageError(e.to_string()))?
+                .ok_or(SchedulerError::WorkflowRunNotFound(run_id)),
+        }
+    }
+
+    async fn get_workflow_briefing(&self, workflow_id: Uuid) -> Result<WorkflowBriefing> {
+        // 存储中的运行记录是最新的，包含其他进程的运行
+        let workflow = self.scheduler.get_workflow(workflow_id).await?;
+        let runs = self
+            .storage
+            .list_workflow_runs(workflow_id)
+            .await
+            .map_err(|e| SchedulerError::StorageError(e.to_string()))?;
+        Ok(WorkflowBriefing::from_workflow(&workflow, runs))
+    }
+
     async fn start(&self) -> Result<()> {
         self.scheduler.start().await
     }
@@ -323,7 +754,7 @@ mod tests {
     use tempfile::TempDir;
 
     fn create_test_executor() -> crate::scheduler::AsyncTaskExecutor {
-        Arc::new(|_task_id, _params: HashMap<String, String>| {
+        crate::execution::executor_fn(|_task_id, _params, _ctx| async {
             Ok(TaskExecutionResult {
                 task_id: uuid::Uuid::new_v4(),
                 run_instance_id: None,
@@ -334,6 +765,8 @@ mod tests {
                 stdout: None,
                 stderr: None,
                 exit_code: Some(0),
+                signal: None,
+                timed_out: false,
             })
         })
     }
@@ -385,6 +818,211 @@ mod tests {
         assert_eq!(instance.task_id, task.id);
     }
 
+    #[tokio::test]
+    async fn test_persistent_output_logs_stored() {
+        let temp_dir = TempDir::new().unwrap();
+        let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let executor = crate::execution::executor_fn(
+            |task_id, _params, ctx: crate::execution::TaskRunContext| async move {
+                ctx.stdout("persisted line");
+                Ok(TaskExecutionResult::success(task_id, String::new(), String::new(), 0))
+            },
+        );
+
+        let task = scheduler
+            .add_task(
+                "Test Task".to_string(),
+                "test_task".to_string(),
+                "0 * * * * *".to_string(),
+                executor,
+            )
+            .await
+            .unwrap();
+
+        let instance = scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+
+        let stored = scheduler.storage.list_logs(instance.id).await.unwrap();
+        assert!(stored.iter().any(|l| l.message == "persisted line"));
+        let stored_instance = scheduler.storage.load_run_instance(instance.id).await.unwrap();
+        assert_eq!(stored_instance.unwrap().status, TaskStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_prune_history_keeps_briefing_totals() {
+        let temp_dir = TempDir::new().unwrap();
+        let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let task = scheduler
+            .add_task(
+                "Test Task".to_string(),
+                "test_task".to_string(),
+                "0 * * * * *".to_string(),
+                create_test_executor(),
+            )
+            .await
+            .unwrap();
+        let mut runs = Vec::new();
+        for _ in 0..3 {
+            runs.push(scheduler.run_task(task.id, HashMap::new()).await.unwrap());
+        }
+
+        let policy = RetentionPolicy::default().with_max_runs_per_task(1);
+        let report = scheduler.prune_history(policy.clone(), true).await.unwrap();
+        assert_eq!((report.runs, report.tasks), (2, 1));
+        assert_eq!(scheduler.get_task_instances(task.id).await.unwrap().len(), 3);
+
+        let report = scheduler.prune_history(policy, false).await.unwrap();
+        assert_eq!(report.runs, 2);
+        assert!(report.logs > 0);
+        let instances = scheduler.get_task_instances(task.id).await.unwrap();
+        assert_eq!(instances.len(), 1);
+        assert!(scheduler.storage.list_logs(runs[0].id).await.unwrap().is_empty());
+
+        let briefing = scheduler.get_task_briefing(task.id).await.unwrap();
+        assert_eq!(briefing.run_count, 3);
+        let pruned = briefing.pruned.unwrap();
+        assert_eq!((pruned.runs, pruned.succeeded), (2, 2));
+
+        scheduler.remove_task(task.id).await.unwrap();
+        assert!(scheduler.storage.load_run_summary(task.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_bundle_schedules_tasks() {
+        let source_dir = TempDir::new().unwrap();
+        let source = PersistentCronTaskScheduler::new(source_dir.path().to_path_buf()).await.unwrap();
+        let task = source
+            .add_task_with_action(
+                "Echo".to_string(),
+                "echo".to_string(),
+                None,
+                None,
+                "0 0 * * * *".to_string(),
+                TaskAction::shell("echo hi"),
+                false,
+            )
+            .await
+            .unwrap();
+        source.run_task(task.id, HashMap::new()).await.unwrap();
+        let bundle = source.export_bundle(ExportOptions::default().with_history()).await.unwrap();
+        assert_eq!(bundle.run_instances.len(), 1);
+
+        let target_dir = TempDir::new().unwrap();
+        let target = PersistentCronTaskScheduler::new(target_dir.path().to_path_buf()).await.unwrap();
+        let options = ImportOptions {
+            remap_ids: true,
+            ..Default::default()
+        };
+        let report = target.import_bundle(bundle, options).await.unwrap();
+        let imported = report.tasks[0].target_id;
+        assert_ne!(imported, task.id);
+
+        // 导入的任务已在内部调度器中注册，可直接运行
+        let restored = target.get_task(imported).await.unwrap();
+        assert_eq!(restored.action, Some(TaskAction::shell("echo hi")));
+        assert_eq!(target.get_task_instances(imported).await.unwrap().len(), 1);
+        target.run_task(imported, HashMap::new()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persistent_stop_orphaned_instance() {
+        let temp_dir = TempDir::new().unwrap();
+        let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+
+        // 模拟由已退出的进程留下的运行中实例
+        let mut instance = TaskRunInstance::new(uuid::Uuid::new_v4(), HashMap::new());
+        instance.mark_running();
+        scheduler.storage.save_run_instance(&instance).await.unwrap();
+
+        scheduler.stop_task(instance.id).await.unwrap();
+
+        let stopped = scheduler.get_run_instance(instance.id).await.unwrap();
+        assert_eq!(stopped.status, TaskStatus::Failed);
+        assert_eq!(
+            stopped.result.unwrap().error.as_deref(),
+            Some("Task stopped by user")
+        );
+        assert!(scheduler.stop_task(instance.id).await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_action_task_runs_after_restart() {
+        let temp_dir = TempDir::new().unwrap();
+        let task_id = {
+            let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+            let task = scheduler
+                .add_task_with_action(
+                    "Echo".to_string(),
+                    "echo".to_string(),
+                    None,
+                    None,
+                    "0 0 * * * *".to_string(),
+                    TaskAction::shell("echo restored"),
+                    false,
+                )
+                .await
+                .unwrap();
+            task.id
+        };
+
+        // 重新打开同一数据目录，不再注册任何执行器
+        let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let task = scheduler.get_task(task_id).await.unwrap();
+        assert_eq!(task.action, Some(TaskAction::shell("echo restored")));
+
+        let instance = scheduler.run_task(task_id, HashMap::new()).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Completed);
+        let logs = scheduler.storage.list_logs(instance.id).await.unwrap();
+        assert!(logs.iter().any(|l| l.message == "restored"));
+
+        // 运行次数同步写入存储
+        let stored = scheduler.load_tasks().await.unwrap();
+        assert_eq!(stored[0].run_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reload_picks_up_other_instance_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let daemon = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let cli = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+
+        let task = cli
+            .add_task_with_action(
+                "Echo".to_string(),
+                "echo".to_string(),
+                None,
+                None,
+                "0 0 * * * *".to_string(),
+                TaskAction::shell("echo hi"),
+                false,
+            )
+            .await
+            .unwrap();
+        let summary = daemon.reload_tasks().await.unwrap();
+        assert_eq!(summary, ReloadSummary { added: 1, updated: 0, removed: 0 });
+        assert!(daemon.reload_tasks().await.unwrap().is_empty());
+
+        cli.update_task(TaskUpdateRequest {
+            id: task.id,
+            title: None,
+            description: None,
+            content: None,
+            cron_expression: Some("0 30 * * * *".to_string()),
+            enabled: None,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let summary = daemon.reload_tasks().await.unwrap();
+        assert_eq!(summary.updated, 1);
+        assert_eq!(daemon.get_task(task.id).await.unwrap().cron_expression, "0 30 * * * *");
+
+        cli.remove_task(task.id).await.unwrap();
+        let summary = daemon.reload_tasks().await.unwrap();
+        assert_eq!(summary.removed, 1);
+        assert!(daemon.get_task(task.id).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_persistent_pause_resume() {
         let temp_dir = TempDir::new().unwrap();
@@ -411,4 +1049,218 @@ mod tests {
         let resumed = scheduler.get_task(task.id).await.unwrap();
         assert_eq!(resumed.status, TaskStatus::Pending);
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_retry_policy_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let retry = crate::retry::RetryPolicy {
+            max_attempts: 3,
+            backoff: crate::retry::Backoff::Fixed { delay_secs: 0 },
+            jitter: false,
+            retry_on: crate::retry::RetryOn::default(),
+        };
+        let task_id = {
+            let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+            let task = scheduler
+                .add_task_with_action(
+                    "Fail".to_string(),
+                    "fail".to_string(),
+                    None,
+                    None,
+                    "0 0 * * * *".to_string(),
+                    TaskAction::shell("exit 3"),
+                    false,
+                )
+                .await
+                .unwrap();
+            scheduler
+                .update_task(TaskUpdateRequest {
+                    id: task.id,
+                    retry: Some(retry),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            task.id
+        };
+
+        let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+        assert_eq!(scheduler.get_task(task_id).await.unwrap().retry, retry);
+
+        let instance = scheduler.run_task(task_id, HashMap::new()).await.unwrap();
+        assert_eq!(instance.status, TaskStatus::Failed);
+        assert_eq!(instance.attempt, 3);
+
+        // 各次尝试写入存储并关联到首次运行
+        let stored = scheduler.storage.list_run_instances(task_id).await.unwrap();
+        assert_eq!(stored.len(), 3);
+        assert!(stored.iter().all(|i| i.logical_id() == instance.logical_id()));
+        let briefing = scheduler.get_task_briefing(task_id).await.unwrap();
+        assert_eq!(briefing.latest_attempts, 3);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_workflow_persists() {
+        use crate::workflow::WorkflowStep;
+
+        let temp_dir = TempDir::new().unwrap();
+        let workflow_id = {
+            let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+            let mut steps = Vec::new();
+            for (name, command) in [("fetch", "echo 42"), ("publish", "echo got $SKER_PARAM_FETCH_OUTPUT")] {
+                let task = scheduler
+                    .add_task_with_action(
+                        name.to_string(),
+                        name.to_string(),
+                        None,
+                        None,
+                        "0 0 0 1 1 *".to_string(),
+                        TaskAction::shell(command),
+                        false,
+                    )
+                    .await
+                    .unwrap();
+                steps.push(WorkflowStep::new(name, task.id));
+            }
+            steps[1].depends_on = vec!["fetch".to_string()];
+            scheduler.add_workflow(Workflow::new("pipeline", steps)).await.unwrap().id
+        };
+
+        let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+        assert_eq!(scheduler.list_workflows().await.unwrap().len(), 1);
+        let run = scheduler.run_workflow(workflow_id, HashMap::new()).await.unwrap();
+        assert_eq!(run.status, TaskStatus::Completed);
+        assert_eq!(run.step("publish").unwrap().output.as_deref(), Some("got 42"));
+
+        // 运行记录写入存储，其他进程可见
+        let other = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let briefing = other.get_workflow_briefing(workflow_id).await.unwrap();
+        assert_eq!(briefing.latest_run().unwrap().id, run.id);
+        assert_eq!(other.get_workflow_run(run.id).await.unwrap().status, TaskStatus::Completed);
+
+        other.remove_workflow(workflow_id).await.unwrap();
+        assert!(scheduler.storage.load_workflow_run(run.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_cron_expressions_migrated() {
+        use crate::storage::MemorySchedulerStorage;
+        use crate::workflow::WorkflowStep;
+        use chrono::{Datelike, Weekday};
+
+        // 早先保存的记录没有 cron_version 字段
+        fn legacy<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+            let mut json = serde_json::to_value(value).unwrap();
+            json.as_object_mut().unwrap().remove("cron_version");
+            serde_json::from_value(json).unwrap()
+        }
+        let task = |cron: &str| {
+            let mut task = ScheduledTask::new(Uuid::new_v4(), cron.to_string(), "legacy".to_string(), cron.to_string(), None, None);
+            task.action = Some(TaskAction::shell("true"));
+            legacy(&task)
+        };
+
+        let storage = Arc::new(MemorySchedulerStorage::new());
+        // 旧语法 2-6 为周一到周五，1 为周日
+        let weekdays = task("0 0 9 * * 2-6");
+        let ambiguous = task("0 0 9 1 * 2");
+        let mut workflow = Workflow::new("weekly", vec![WorkflowStep::new("run", weekdays.id)]);
+        workflow.cron_expression = Some("0 0 9 * * 1".to_string());
+        let workflow = legacy(&workflow);
+        assert_eq!(weekdays.cron_version, 0);
+        assert_eq!(workflow.cron_version, 0);
+        storage.save_task(&weekdays).await.unwrap();
+        storage.save_task(&ambiguous).await.unwrap();
+        storage.save_workflow(&workflow).await.unwrap();
+
+        let scheduler = PersistentCronTaskScheduler::with_storage(storage.clone(), ActionRegistry::new())
+            .await
+            .unwrap();
+
+        // 星期字段改写为当前语法并写回存储
+        let migrated = scheduler.get_task(weekdays.id).await.unwrap();
+        assert_eq!(migrated.cron_expression, "0 0 9 * * MON-FRI");
+        assert_eq!(migrated.cron_version, CRON_VERSION);
+        let next = migrated.next_run.unwrap().weekday();
+        assert!(next != Weekday::Sat && next != Weekday::Sun);
+        let stored = storage.load_task(weekdays.id).await.unwrap().unwrap();
+        assert_eq!(stored.cron_expression, "0 0 9 * * MON-FRI");
+        assert_eq!(stored.cron_version, CRON_VERSION);
+        let stored = storage.load_workflow(workflow.id).await.unwrap().unwrap();
+        assert_eq!(stored.cron_expression.as_deref(), Some("0 0 9 * * SUN"));
+        assert_eq!(scheduler.get_workflow(workflow.id).await.unwrap(), stored);
+
+        // 日和周都受限的表达式含义已改变，不调度并保持原样
+        let rejected = scheduler.get_task(ambiguous.id).await.unwrap();
+        assert_eq!(rejected.cron_expression, "0 0 9 1 * 2");
+        assert_eq!(rejected.cron_version, 0);
+        assert!(rejected.next_run.is_none());
+        let error = upgrade_cron_expression(&rejected.cron_expression).unwrap_err().to_string();
+        assert!(error.contains("the old syntax required both to match"));
+
+        // 设置新表达式后按当前语法调度
+        let updated = scheduler
+            .update_task(TaskUpdateRequest {
+                id: ambiguous.id,
+                cron_expression: Some("0 0 9 1 * *".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(updated.cron_version, CRON_VERSION);
+        assert_eq!(scheduler.get_task(ambiguous.id).await.unwrap().next_run.unwrap().day(), 1);
+        assert_eq!(storage.load_task(ambiguous.id).await.unwrap().unwrap().cron_version, CRON_VERSION);
+    }
+
+    struct RecoveryHook;
+
+    #[async_trait]
+    impl crate::hooks::OnTaskError for RecoveryHook {
+        async fn on_error(
+            &self,
+            _context: &crate::hooks::TaskResultContext,
+            error: &str,
+        ) -> crate::hooks::HookResult<crate::hooks::ErrorRecoverySuggestion> {
+            Ok(crate::hooks::ErrorRecoverySuggestion {
+                description: error.to_string(),
+                fix_command: Some("echo fix".to_string()),
+                create_recovery_task: true,
+                recovery_cron: Some("0 0 * * * *".to_string()),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recovery_task_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+            let mut hooks = HookManager::new();
+            hooks.register_on_error(Arc::new(RecoveryHook));
+            scheduler.set_hooks(hooks).await;
+
+            let broken = crate::execution::executor_fn(|_task_id, _params, _ctx| async move {
+                Err(SchedulerError::ExecutionError("disk full".to_string()))
+            });
+            let task = scheduler
+                .add_task("Backup".to_string(), "backup".to_string(), "0 0 3 * * *".to_string(), broken)
+                .await
+                .unwrap();
+            let instance = scheduler.run_task(task.id, HashMap::new()).await.unwrap();
+            assert_eq!(instance.status, TaskStatus::Error);
+        }
+
+        let scheduler = PersistentCronTaskScheduler::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let recovery = scheduler
+            .list_tasks()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|t| t.name == "backup_recovery")
+            .expect("recovery task stored");
+        assert_eq!(recovery.action, Some(TaskAction::shell("echo fix")));
+        assert_eq!(recovery.description.as_deref(), Some("Job execution error: disk full"));
+    }
 }
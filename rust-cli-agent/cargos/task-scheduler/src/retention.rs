@@ -0,0 +1,262 @@
+//! 运行记录保留策略
+//!
+//! 按保留时间、每个任务的运行数和总大小选出可以清理的运行实例。
+//! 被清理的运行汇总到按任务的 [`RunHistorySummary`]，任务简报据此保留完整统计
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::types::{TaskRunInstance, TaskStatus};
+
+/// 保留策略，各项为 None 表示不限制
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// 保留时间 (秒)，结束时间早于此的运行被清理
+    #[serde(default)]
+    pub max_age_secs: Option<u64>,
+    /// 每个任务保留的最近运行数
+    #[serde(default)]
+    pub max_runs_per_task: Option<usize>,
+    /// 运行记录和日志的总字节数上限，超出时从最旧的运行开始清理
+    #[serde(default)]
+    pub max_total_bytes: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// 按保留时间限制
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age_secs = Some(max_age.num_seconds().max(0) as u64);
+        self
+    }
+
+    /// 按每个任务的运行数限制
+    pub fn with_max_runs_per_task(mut self, max_runs: usize) -> Self {
+        self.max_runs_per_task = Some(max_runs);
+        self
+    }
+
+    /// 按总大小限制
+    pub fn with_max_total_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_total_bytes = Some(max_bytes);
+        self
+    }
+
+    /// 是否不做任何限制
+    pub fn is_unlimited(&self) -> bool {
+        self.max_age_secs.is_none() && self.max_runs_per_task.is_none() && self.max_total_bytes.is_none()
+    }
+
+    /// 选出需要清理的运行实例，按开始时间从旧到新排列
+    ///
+    /// `sizes` 为各实例连同日志占用的字节数。等待中和运行中的实例不会被清理，
+    /// 但计入总大小
+    pub fn select<'a>(
+        &self,
+        instances: impl IntoIterator<Item = &'a TaskRunInstance>,
+        sizes: &HashMap<Uuid, u64>,
+        now: DateTime<Utc>,
+    ) -> Vec<Uuid> {
+        let instances: Vec<&TaskRunInstance> = instances.into_iter().collect();
+        let mut finished: Vec<&TaskRunInstance> = instances.iter().copied().filter(|i| is_finished(i)).collect();
+        // 从新到旧
+        finished.sort_by_key(|i| Reverse(i.started_at));
+
+        let mut removed = HashSet::new();
+        if let Some(max_age) = self.max_age_secs {
+            let cutoff = now - Duration::seconds(max_age.min(i64::MAX as u64) as i64);
+            for instance in &finished {
+                if instance.completed_at.unwrap_or(instance.started_at) < cutoff {
+                    removed.insert(instance.id);
+                }
+            }
+        }
+
+        if let Some(max_runs) = self.max_runs_per_task {
+            let mut kept: HashMap<Uuid, usize> = HashMap::new();
+            for instance in &finished {
+                if removed.contains(&instance.id) {
+                    continue;
+                }
+                let count = kept.entry(instance.task_id).or_default();
+                if *count >= max_runs {
+                    removed.insert(instance.id);
+                } else {
+                    *count += 1;
+                }
+            }
+        }
+
+        if let Some(max_bytes) = self.max_total_bytes {
+            let size = |instance: &TaskRunInstance| sizes.get(&instance.id).copied().unwrap_or(0);
+            let mut total: u64 = instances
+                .iter()
+                .filter(|i| !removed.contains(&i.id))
+                .map(|i| size(i))
+                .sum();
+            for instance in finished.iter().rev() {
+                if total <= max_bytes {
+                    break;
+                }
+                if removed.insert(instance.id) {
+                    total = total.saturating_sub(size(instance));
+                }
+            }
+        }
+
+        finished
+            .iter()
+            .rev()
+            .filter(|i| removed.contains(&i.id))
+            .map(|i| i.id)
+            .collect()
+    }
+}
+
+/// 运行是否已结束
+fn is_finished(instance: &TaskRunInstance) -> bool {
+    !matches!(instance.status, TaskStatus::Pending | TaskStatus::Running)
+}
+
+/// 已清理运行的按任务汇总
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunHistorySummary {
+    /// 任务 ID
+    pub task_id: Uuid,
+    /// 已清理的运行数
+    pub runs: u64,
+    /// 其中成功的运行数
+    pub succeeded: u64,
+    /// 其中失败或出错的运行数
+    pub failed: u64,
+    /// 其中过期的运行数
+    pub expired: u64,
+    /// 其中跳过的运行数
+    pub skipped: u64,
+    /// 已清理运行的总时长 (毫秒)
+    pub total_duration_ms: i64,
+    /// 最早一次已清理运行的开始时间
+    pub first_started_at: Option<DateTime<Utc>>,
+    /// 最近一次已清理运行的开始时间
+    pub last_started_at: Option<DateTime<Utc>>,
+}
+
+impl RunHistorySummary {
+    pub fn new(task_id: Uuid) -> Self {
+        Self {
+            task_id,
+            runs: 0,
+            succeeded: 0,
+            failed: 0,
+            expired: 0,
+            skipped: 0,
+            total_duration_ms: 0,
+            first_started_at: None,
+            last_started_at: None,
+        }
+    }
+
+    /// 计入一次被清理的运行
+    pub fn absorb(&mut self, instance: &TaskRunInstance) {
+        self.runs += 1;
+        match instance.status {
+            TaskStatus::Completed => self.succeeded += 1,
+            TaskStatus::Failed | TaskStatus::Error => self.failed += 1,
+            TaskStatus::Expired => self.expired += 1,
+            TaskStatus::Skipped => self.skipped += 1,
+            _ => {}
+        }
+        self.total_duration_ms += instance.duration_ms().unwrap_or(0).max(0);
+        self.first_started_at = Some(match self.first_started_at {
+            Some(first) => first.min(instance.started_at),
+            None => instance.started_at,
+        });
+        self.last_started_at = Some(match self.last_started_at {
+            Some(last) => last.max(instance.started_at),
+            None => instance.started_at,
+        });
+    }
+}
+
+/// 一次清理的结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PruneReport {
+    /// 清理的运行实例数
+    pub runs: usize,
+    /// 清理的日志条数，包含所属运行已不存在的日志
+    pub logs: usize,
+    /// 释放的字节数 (按序列化后的大小估算)
+    pub bytes: u64,
+    /// 涉及的任务数
+    pub tasks: usize,
+    /// 是否只是演练，未实际删除
+    pub dry_run: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished(task_id: Uuid, minutes_ago: i64, status: TaskStatus) -> TaskRunInstance {
+        let mut instance = TaskRunInstance::new(task_id, HashMap::new());
+        instance.started_at = Utc::now() - Duration::minutes(minutes_ago);
+        instance.completed_at = Some(instance.started_at + Duration::seconds(1));
+        instance.status = status;
+        instance
+    }
+
+    #[test]
+    fn test_select_by_age_and_count() {
+        let task = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let old = finished(task, 120, TaskStatus::Completed);
+        let mid = finished(task, 30, TaskStatus::Failed);
+        let new = finished(task, 10, TaskStatus::Completed);
+        let other_run = finished(other, 20, TaskStatus::Completed);
+        let mut running = TaskRunInstance::new(task, HashMap::new());
+        running.started_at = Utc::now() - Duration::minutes(500);
+        running.status = TaskStatus::Running;
+        let instances = [old.clone(), mid.clone(), new.clone(), other_run, running];
+
+        let by_age = RetentionPolicy::default().with_max_age(Duration::minutes(60));
+        assert_eq!(by_age.select(&instances, &HashMap::new(), Utc::now()), vec![old.id]);
+
+        let by_count = RetentionPolicy::default().with_max_runs_per_task(1);
+        assert_eq!(by_count.select(&instances, &HashMap::new(), Utc::now()), vec![old.id, mid.id]);
+
+        assert!(RetentionPolicy::default().select(&instances, &HashMap::new(), Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn test_select_by_size() {
+        let task = Uuid::new_v4();
+        let old = finished(task, 30, TaskStatus::Completed);
+        let new = finished(task, 10, TaskStatus::Completed);
+        let sizes = HashMap::from([(old.id, 600), (new.id, 500)]);
+        let instances = [new.clone(), old.clone()];
+
+        let policy = RetentionPolicy::default().with_max_total_bytes(1000);
+        assert_eq!(policy.select(&instances, &sizes, Utc::now()), vec![old.id]);
+        let policy = RetentionPolicy::default().with_max_total_bytes(100);
+        assert_eq!(policy.select(&instances, &sizes, Utc::now()), vec![old.id, new.id]);
+    }
+
+    #[test]
+    fn test_summary_absorb() {
+        let task = Uuid::new_v4();
+        let mut summary = RunHistorySummary::new(task);
+        let first = finished(task, 30, TaskStatus::Completed);
+        let second = finished(task, 10, TaskStatus::Error);
+        summary.absorb(&second);
+        summary.absorb(&first);
+        summary.absorb(&finished(task, 5, TaskStatus::Expired));
+
+        assert_eq!(summary.runs, 3);
+        assert_eq!((summary.succeeded, summary.failed, summary.expired), (1, 1, 1));
+        assert_eq!(summary.first_started_at, Some(first.started_at));
+        assert_eq!(summary.total_duration_ms, 3000);
+    }
+}
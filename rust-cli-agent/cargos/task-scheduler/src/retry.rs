@@ -0,0 +1,424 @@
+//! 失败重试策略
+//!
+//! 任务运行失败后按策略等待一段时间再次运行。
+//! 同一次逻辑运行的各次尝试记录为独立的运行实例，通过首次尝试的实例 ID 关联
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SchedulerError};
+use crate::types::TaskExecutionResult;
+
+/// 指数退避的默认初始间隔 (秒)
+const DEFAULT_INITIAL_SECS: u64 = 10;
+
+/// 指数退避的默认最大间隔 (秒)
+const DEFAULT_MAX_SECS: u64 = 600;
+
+/// 指数退避的默认倍数
+const DEFAULT_MULTIPLIER: u32 = 2;
+
+/// 重试间隔的计算方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Backoff {
+    /// 固定间隔
+    Fixed { delay_secs: u64 },
+    /// 指数增长的间隔，不超过 max_secs
+    Exponential {
+        initial_secs: u64,
+        max_secs: u64,
+        multiplier: u32,
+    },
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff::Exponential {
+            initial_secs: DEFAULT_INITIAL_SECS,
+            max_secs: DEFAULT_MAX_SECS,
+            multiplier: DEFAULT_MULTIPLIER,
+        }
+    }
+}
+
+impl Backoff {
+    /// 第 attempt 次尝试失败后的基础等待时间 (不含抖动)
+    pub fn base_delay(&self, attempt: u32) -> Duration {
+        match *self {
+            Backoff::Fixed { delay_secs } => Duration::from_secs(delay_secs),
+            Backoff::Exponential {
+                initial_secs,
+                max_secs,
+                multiplier,
+            } => {
+                let factor = (multiplier.max(1) as u64).saturating_pow(attempt.saturating_sub(1));
+                Duration::from_secs(initial_secs.saturating_mul(factor).min(max_secs))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Backoff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backoff::Fixed { delay_secs } => write!(f, "fixed:{}", delay_secs),
+            Backoff::Exponential {
+                initial_secs,
+                max_secs,
+                multiplier,
+            } => write!(f, "exponential:{}:{}:{}", initial_secs, max_secs, multiplier),
+        }
+    }
+}
+
+impl FromStr for Backoff {
+    type Err = SchedulerError;
+
+    /// 解析 `fixed:<秒>` 或 `exponential:<初始秒>[:<最大秒>[:<倍数>]]`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            SchedulerError::InvalidParameter(format!(
+                "Invalid backoff: {} (expected fixed:<secs> or exponential:<initial>[:<max>[:<multiplier>]])",
+                s
+            ))
+        };
+        let mut parts = s.trim().split(':');
+        let kind = parts.next().unwrap_or_default().to_lowercase();
+        let numbers = parts
+            .map(|p| p.trim().parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>>>()?;
+
+        match (kind.as_str(), numbers.as_slice()) {
+            ("fixed", [delay_secs]) => Ok(Backoff::Fixed { delay_secs: *delay_secs }),
+            ("exponential" | "exp", [initial, rest @ ..]) if rest.len() <= 2 => Ok(Backoff::Exponential {
+                initial_secs: *initial,
+                max_secs: rest.first().copied().unwrap_or(DEFAULT_MAX_SECS).max(*initial),
+                multiplier: match rest.get(1) {
+                    Some(m) => u32::try_from(*m).map_err(|_| invalid())?,
+                    None => DEFAULT_MULTIPLIER,
+                },
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// 失败的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// 以非零状态退出 (或 HTTP 非 2xx、被信号终止)
+    NonZeroExit,
+    /// 超时
+    Timeout,
+    /// 执行器本身出错
+    ExecutorError,
+}
+
+impl FailureKind {
+    /// 对执行结果分类，成功时返回 None
+    pub fn classify(result: &Result<TaskExecutionResult>) -> Option<Self> {
+        match result {
+            Ok(r) if r.success => None,
+            Ok(r) if r.timed_out => Some(FailureKind::Timeout),
+            Ok(_) => Some(FailureKind::NonZeroExit),
+            Err(_) => Some(FailureKind::ExecutorError),
+        }
+    }
+}
+
+impl std::fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailureKind::NonZeroExit => write!(f, "exit"),
+            FailureKind::Timeout => write!(f, "timeout"),
+            FailureKind::ExecutorError => write!(f, "error"),
+        }
+    }
+}
+
+/// 哪些失败需要重试
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryOn {
+    #[serde(default = "enabled")]
+    pub non_zero_exit: bool,
+    #[serde(default = "enabled")]
+    pub timeout: bool,
+    #[serde(default = "enabled")]
+    pub executor_error: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Default for RetryOn {
+    fn default() -> Self {
+        Self {
+            non_zero_exit: true,
+            timeout: true,
+            executor_error: true,
+        }
+    }
+}
+
+impl RetryOn {
+    pub fn matches(&self, kind: FailureKind) -> bool {
+        match kind {
+            FailureKind::NonZeroExit => self.non_zero_exit,
+            FailureKind::Timeout => self.timeout,
+            FailureKind::ExecutorError => self.executor_error,
+        }
+    }
+}
+
+impl std::fmt::Display for RetryOn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kinds: Vec<String> = [FailureKind::NonZeroExit, FailureKind::Timeout, FailureKind::ExecutorError]
+            .into_iter()
+            .filter(|kind| self.matches(*kind))
+            .map(|kind| kind.to_string())
+            .collect();
+        if kinds.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", kinds.join(","))
+        }
+    }
+}
+
+impl FromStr for RetryOn {
+    type Err = SchedulerError;
+
+    /// 解析逗号分隔的 `exit`、`timeout`、`error`，或 `all` / `none`
+    fn from_str(s: &str) -> Result<Self> {
+        let mut retry_on = RetryOn {
+            non_zero_exit: false,
+            timeout: false,
+            executor_error: false,
+        };
+        for kind in s.split(',').map(|k| k.trim().to_lowercase()) {
+            match kind.as_str() {
+                "all" => retry_on = RetryOn::default(),
+                "none" | "" => {}
+                "exit" | "non_zero_exit" => retry_on.non_zero_exit = true,
+                "timeout" => retry_on.timeout = true,
+                "error" | "executor_error" => retry_on.executor_error = true,
+                _ => {
+                    return Err(SchedulerError::InvalidParameter(format!(
+                        "Invalid retry condition: {} (expected exit, timeout, error, all or none)",
+                        kind
+                    )))
+                }
+            }
+        }
+        Ok(retry_on)
+    }
+}
+
+/// 任务的重试策略
+///
+/// 默认只尝试一次，即不重试
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// 最多尝试次数 (含首次运行)
+    pub max_attempts: u32,
+    /// 重试间隔
+    #[serde(default)]
+    pub backoff: Backoff,
+    /// 是否在间隔上加入随机抖动，避免多个任务同时重试
+    #[serde(default = "enabled")]
+    pub jitter: bool,
+    /// 需要重试的失败类型
+    #[serde(default)]
+    pub retry_on: RetryOn,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Backoff::default(),
+            jitter: true,
+            retry_on: RetryOn::default(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 是否会重试
+    pub fn is_enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    /// 第 attempt 次尝试以 kind 失败后是否还要重试
+    pub fn should_retry(&self, attempt: u32, kind: FailureKind) -> bool {
+        attempt < self.max_attempts && self.retry_on.matches(kind)
+    }
+
+    /// 第 attempt 次尝试失败后的等待时间
+    ///
+    /// 启用抖动时在基础间隔的一半到全部之间随机取值
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let base = self.backoff.base_delay(attempt);
+        if !self.jitter || base.is_zero() {
+            return base;
+        }
+        rand::thread_rng().gen_range(base / 2..=base)
+    }
+
+    /// 用命令行或工具调用给出的字段覆盖当前策略，都未给出时返回 None
+    pub fn with_overrides(
+        self,
+        max_attempts: Option<u32>,
+        backoff: Option<&str>,
+        retry_on: Option<&str>,
+    ) -> Result<Option<Self>> {
+        if max_attempts.is_none() && backoff.is_none() && retry_on.is_none() {
+            return Ok(None);
+        }
+        if max_attempts == Some(0) {
+            return Err(SchedulerError::InvalidParameter(
+                "Max attempts must be at least 1".to_string(),
+            ));
+        }
+        Ok(Some(Self {
+            max_attempts: max_attempts.unwrap_or(self.max_attempts),
+            backoff: match backoff {
+                Some(backoff) => backoff.parse()?,
+                None => self.backoff,
+            },
+            jitter: self.jitter,
+            retry_on: match retry_on {
+                Some(retry_on) => retry_on.parse()?,
+                None => self.retry_on,
+            },
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_backoff_delays() {
+        let fixed = Backoff::Fixed { delay_secs: 30 };
+        assert_eq!(fixed.base_delay(1), Duration::from_secs(30));
+        assert_eq!(fixed.base_delay(5), Duration::from_secs(30));
+
+        let exp = Backoff::Exponential {
+            initial_secs: 10,
+            max_secs: 60,
+            multiplier: 2,
+        };
+        assert_eq!(exp.base_delay(1), Duration::from_secs(10));
+        assert_eq!(exp.base_delay(2), Duration::from_secs(20));
+        assert_eq!(exp.base_delay(3), Duration::from_secs(40));
+        assert_eq!(exp.base_delay(4), Duration::from_secs(60));
+        assert_eq!(exp.base_delay(100), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_jitter_stays_in_range() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            backoff: Backoff::Fixed { delay_secs: 10 },
+            ..RetryPolicy::default()
+        };
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_secs(5) && delay <= Duration::from_secs(10));
+        }
+        let exact = RetryPolicy { jitter: false, ..policy };
+        assert_eq!(exact.delay(1), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_parse_backoff() {
+        assert_eq!("fixed:5".parse::<Backoff>().unwrap(), Backoff::Fixed { delay_secs: 5 });
+        assert_eq!(
+            "exp:2:30".parse::<Backoff>().unwrap(),
+            Backoff::Exponential {
+                initial_secs: 2,
+                max_secs: 30,
+                multiplier: 2
+            }
+        );
+        let backoff: Backoff = "exponential:1:8:3".parse().unwrap();
+        assert_eq!(backoff.to_string(), "exponential:1:8:3");
+        assert!("fixed".parse::<Backoff>().is_err());
+        assert!("linear:5".parse::<Backoff>().is_err());
+    }
+
+    #[test]
+    fn test_parse_retry_on() {
+        let retry_on: RetryOn = "exit,timeout".parse().unwrap();
+        assert!(retry_on.matches(FailureKind::NonZeroExit));
+        assert!(retry_on.matches(FailureKind::Timeout));
+        assert!(!retry_on.matches(FailureKind::ExecutorError));
+        assert_eq!(retry_on.to_string(), "exit,timeout");
+        assert_eq!("all".parse::<RetryOn>().unwrap(), RetryOn::default());
+        assert!("crash".parse::<RetryOn>().is_err());
+    }
+
+    #[test]
+    fn test_classify_failures() {
+        let task_id = Uuid::new_v4();
+        assert_eq!(
+            FailureKind::classify(&Ok(TaskExecutionResult::success(task_id, String::new(), String::new(), 0))),
+            None
+        );
+        assert_eq!(
+            FailureKind::classify(&Ok(TaskExecutionResult::success(task_id, String::new(), String::new(), 1))),
+            Some(FailureKind::NonZeroExit)
+        );
+        let mut timed_out = TaskExecutionResult::failure(task_id, "Command timeout".to_string());
+        timed_out.timed_out = true;
+        assert_eq!(FailureKind::classify(&Ok(timed_out)), Some(FailureKind::Timeout));
+        assert_eq!(
+            FailureKind::classify(&Err(SchedulerError::ExecutionError("boom".to_string()))),
+            Some(FailureKind::ExecutorError)
+        );
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            retry_on: "timeout".parse().unwrap(),
+            ..RetryPolicy::default()
+        };
+        assert!(policy.should_retry(1, FailureKind::Timeout));
+        assert!(policy.should_retry(2, FailureKind::Timeout));
+        assert!(!policy.should_retry(3, FailureKind::Timeout));
+        assert!(!policy.should_retry(1, FailureKind::NonZeroExit));
+        assert!(!RetryPolicy::default().should_retry(1, FailureKind::Timeout));
+    }
+
+    #[test]
+    fn test_policy_serde_defaults() {
+        let policy: RetryPolicy = serde_json::from_str(r#"{"max_attempts":4}"#).unwrap();
+        assert_eq!(policy.max_attempts, 4);
+        assert_eq!(policy.backoff, Backoff::default());
+        assert!(policy.jitter);
+        assert_eq!(policy.retry_on, RetryOn::default());
+
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(serde_json::from_str::<RetryPolicy>(&json).unwrap(), policy);
+    }
+
+    #[test]
+    fn test_with_overrides() {
+        let base = RetryPolicy::default();
+        assert_eq!(base.with_overrides(None, None, None).unwrap(), None);
+        let policy = base.with_overrides(Some(3), Some("fixed:1"), None).unwrap().unwrap();
+        assert_eq!(policy.max_attempts, 3);
+        assert_eq!(policy.backoff, Backoff::Fixed { delay_secs: 1 });
+        assert!(base.with_overrides(Some(0), None, None).is_err());
+    }
+}
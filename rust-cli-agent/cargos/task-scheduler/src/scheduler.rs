@@ -7,14 +7,15 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::action::TaskAction;
+use crate::execution::TaskExecutor;
 use crate::types::*;
+use crate::workflow::{Workflow, WorkflowBriefing, WorkflowRun};
 
 /// 异步任务执行器
-pub type AsyncTaskExecutor =
-    Arc<dyn Fn(Uuid, std::collections::HashMap<String, String>) -> crate::error::Result<TaskExecutionResult>
-        + Send
-        + Sync>;
-
+///
+/// 调度器、持久化调度器和 LLM 工具适配器共用的执行器句柄
+pub type AsyncTaskExecutor = Arc<dyn TaskExecutor>;
 /// 调度器服务 Trait
 #[async_trait]
 pub trait TaskScheduler: Send + Sync {
@@ -54,6 +55,21 @@ pub trait TaskScheduler: Send + Sync {
         self.add_task_full(title, name, description, content, cron_expression, executor).await
     }
 
+    /// 添加以可序列化动作执行的定时任务
+    ///
+    /// 动作随任务一起保存，调度器通过动作注册表构造执行器，重启后可直接恢复
+    #[allow(clippy::too_many_arguments)]
+    async fn add_task_with_action(
+        &self,
+        title: String,
+        name: String,
+        description: Option<String>,
+        content: Option<String>,
+        cron_expression: String,
+        action: TaskAction,
+        is_system: bool,
+    ) -> crate::error::Result<ScheduledTask>;
+
     /// 删除任务
     async fn remove_task(&self, task_id: Uuid) -> crate::error::Result<()>;
 
@@ -107,6 +123,53 @@ pub trait TaskScheduler: Send + Sync {
     /// 清空所有任务，返回被清空的任务数量
     async fn clear_all_tasks(&self) -> crate::error::Result<usize>;
 
+    /// 按保留策略清理已结束的运行实例及其日志，被清理的运行汇总到任务的运行汇总
+    ///
+    /// `dry_run` 为 true 时只统计不删除
+    async fn prune_history(
+        &self,
+        policy: crate::retention::RetentionPolicy,
+        dry_run: bool,
+    ) -> crate::error::Result<crate::retention::PruneReport>;
+
+    /// 导出任务，可选包含运行历史
+    async fn export_bundle(
+        &self,
+        options: crate::bundle::ExportOptions,
+    ) -> crate::error::Result<crate::bundle::ScheduleBundle>;
+
+    /// 导入迁移包中的任务和运行历史，导入的任务立即参与调度
+    async fn import_bundle(
+        &self,
+        bundle: crate::bundle::ScheduleBundle,
+        options: crate::bundle::ImportOptions,
+    ) -> crate::error::Result<crate::bundle::ImportReport>;
+
+    /// 添加工作流，步骤引用的任务必须已存在
+    async fn add_workflow(&self, workflow: Workflow) -> crate::error::Result<Workflow>;
+
+    /// 删除工作流
+    async fn remove_workflow(&self, workflow_id: Uuid) -> crate::error::Result<()>;
+
+    /// 获取工作流
+    async fn get_workflow(&self, workflow_id: Uuid) -> crate::error::Result<Workflow>;
+
+    /// 获取所有工作流
+    async fn list_workflows(&self) -> crate::error::Result<Vec<Workflow>>;
+
+    /// 手动运行工作流，等待所有步骤结束后返回运行记录
+    async fn run_workflow(
+        &self,
+        workflow_id: Uuid,
+        user_params: HashMap<String, String>,
+    ) -> crate::error::Result<WorkflowRun>;
+
+    /// 获取工作流运行记录
+    async fn get_workflow_run(&self, run_id: Uuid) -> crate::error::Result<WorkflowRun>;
+
+    /// 获取工作流简报
+    async fn get_workflow_briefing(&self, workflow_id: Uuid) -> crate::error::Result<WorkflowBriefing>;
+
     /// 启动调度器
     async fn start(&self) -> crate::error::Result<()>;
 
@@ -0,0 +1,553 @@
+//! 基于 SQLite 的调度器存储
+//!
+//! 任务、运行实例、日志和工作流各用一张表，查询条件对应的列建有索引，
+//! 完整记录以 JSON 存在 `data` 列。数据库启用 WAL，守护进程写入时其他 CLI 进程仍可读取
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Params};
+use uuid::Uuid;
+
+use storage::{migrate_sqlite, open_sqlite};
+
+use crate::retention::RunHistorySummary;
+use crate::storage::{Page, RangeQuery, SchedulerStorage, SchedulerStorageError, StorageResult};
+use crate::types::*;
+use crate::workflow::{Workflow, WorkflowRun};
+
+/// 表结构迁移，按顺序执行，已执行的迁移不可修改
+const MIGRATIONS: &[&str] = &["
+CREATE TABLE tasks (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX idx_tasks_name ON tasks (name);
+
+CREATE TABLE run_instances (
+    id TEXT PRIMARY KEY NOT NULL,
+    task_id TEXT NOT NULL,
+    status TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX idx_run_instances_task_id ON run_instances (task_id, started_at);
+CREATE INDEX idx_run_instances_started_at ON run_instances (started_at);
+
+CREATE TABLE logs (
+    id TEXT PRIMARY KEY NOT NULL,
+    run_instance_id TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX idx_logs_run_instance_id ON logs (run_instance_id, timestamp);
+
+CREATE TABLE workflows (
+    id TEXT PRIMARY KEY NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE workflow_runs (
+    id TEXT PRIMARY KEY NOT NULL,
+    workflow_id TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX idx_workflow_runs_workflow_id ON workflow_runs (workflow_id, started_at);
+", "
+CREATE TABLE run_summaries (
+    task_id TEXT PRIMARY KEY NOT NULL,
+    data TEXT NOT NULL
+);
+"];
+
+/// 基于 SQLite 的存储实现
+pub struct SqliteSchedulerStorage {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteSchedulerStorage {
+    /// 打开数据库文件，不存在时创建，并执行尚未执行的迁移
+    pub fn new(path: PathBuf) -> StorageResult<Self> {
+        let mut conn = open_sqlite(&path).map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        migrate_sqlite(&mut conn, MIGRATIONS).map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// 在阻塞线程中使用连接
+    async fn with_conn<T, F>(&self, f: F) -> StorageResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+            f(&mut conn).map_err(|e| SchedulerStorageError::StorageError(e.to_string()))
+        })
+        .await
+        .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?
+    }
+
+    /// 序列化值
+    fn serialize<T: serde::Serialize>(value: &T) -> StorageResult<String> {
+        serde_json::to_string(value).map_err(|e| SchedulerStorageError::SerializationError(e.to_string()))
+    }
+
+    /// 反序列化值
+    fn deserialize<T: serde::de::DeserializeOwned>(value: &str) -> StorageResult<T> {
+        serde_json::from_str(value).map_err(|e| SchedulerStorageError::SerializationError(e.to_string()))
+    }
+
+    /// 时间列的格式，固定精度的 UTC 时间按字符串排序即按时间排序
+    fn timestamp(time: &DateTime<Utc>) -> String {
+        time.to_rfc3339_opts(SecondsFormat::Micros, true)
+    }
+
+    /// 读取单条记录
+    async fn load<T, P>(&self, sql: &'static str, params: P) -> StorageResult<Option<T>>
+    where
+        T: serde::de::DeserializeOwned,
+        P: Params + Send + 'static,
+    {
+        let data: Option<String> = self
+            .with_conn(move |conn| conn.query_row(sql, params, |row| row.get(0)).optional())
+            .await?;
+        data.map(|data| Self::deserialize(&data)).transpose()
+    }
+
+    /// 读取多条记录，无法解析的记录被忽略
+    async fn list<T, P>(&self, sql: &'static str, params: P) -> StorageResult<Vec<T>>
+    where
+        T: serde::de::DeserializeOwned,
+        P: Params + Send + 'static,
+    {
+        let rows: Vec<String> = self
+            .with_conn(move |conn| {
+                let mut stmt = conn.prepare(sql)?;
+                let rows = stmt.query_map(params, |row| row.get(0))?.collect();
+                rows
+            })
+            .await?;
+        Ok(rows.iter().filter_map(|data| Self::deserialize(data).ok()).collect())
+    }
+
+    /// 时间范围查询的参数: 起始时间、结束时间、多取一条的条数和 offset
+    fn range_params(query: &RangeQuery) -> (String, String, i64, i64) {
+        (
+            query.since.as_ref().map(Self::timestamp).unwrap_or_default(),
+            // 时间列以数字开头，"~" 大于任何时间
+            query.until.as_ref().map_or_else(|| "~".to_string(), Self::timestamp),
+            query.limit.map_or(-1, |limit| limit.saturating_add(1).min(i64::MAX as usize) as i64),
+            query.offset.min(i64::MAX as usize) as i64,
+        )
+    }
+
+    /// 执行写入语句，返回影响的行数
+    async fn execute<P>(&self, sql: &'static str, params: P) -> StorageResult<usize>
+    where
+        P: Params + Send + 'static,
+    {
+        self.with_conn(move |conn| conn.execute(sql, params)).await
+    }
+}
+
+#[async_trait]
+impl SchedulerStorage for SqliteSchedulerStorage {
+    // 任务操作
+    async fn save_task(&self, task: &ScheduledTask) -> StorageResult<()> {
+        let data = Self::serialize(task)?;
+        self.execute(
+            "INSERT INTO tasks (id, name, created_at, data) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (id) DO UPDATE SET name = excluded.name, created_at = excluded.created_at, data = excluded.data",
+            (task.id.to_string(), task.name.clone(), Self::timestamp(&task.created_at), data),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn load_task(&self, task_id: Uuid) -> StorageResult<Option<ScheduledTask>> {
+        self.load("SELECT data FROM tasks WHERE id = ?1", (task_id.to_string(),)).await
+    }
+
+    async fn delete_task(&self, task_id: Uuid) -> StorageResult<()> {
+        self.execute("DELETE FROM tasks WHERE id = ?1", (task_id.to_string(),)).await?;
+        Ok(())
+    }
+
+    async fn list_tasks(&self) -> StorageResult<Vec<ScheduledTask>> {
+        self.list("SELECT data FROM tasks ORDER BY created_at", ()).await
+    }
+
+    async fn clear_all_tasks(&self) -> StorageResult<usize> {
+        self.execute("DELETE FROM tasks", ()).await
+    }
+
+    // 运行实例操作
+    async fn save_run_instance(&self, instance: &TaskRunInstance) -> StorageResult<()> {
+        let data = Self::serialize(instance)?;
+        let status = Self::serialize(&instance.status)?;
+        self.execute(
+            "INSERT INTO run_instances (id, task_id, status, started_at, data) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (id) DO UPDATE SET task_id = excluded.task_id, status = excluded.status,
+                 started_at = excluded.started_at, data = excluded.data",
+            (
+                instance.id.to_string(),
+                instance.task_id.to_string(),
+                status.trim_matches('"').to_string(),
+                Self::timestamp(&instance.started_at),
+                data
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn load_run_instance(&self, instance_id: Uuid) -> StorageResult<Option<TaskRunInstance>> {
+        self.load("SELECT data FROM run_instances WHERE id = ?1", (instance_id.to_string(),))
+            .await
+    }
+
+    async fn list_run_instances(&self, task_id: Uuid) -> StorageResult<Vec<TaskRunInstance>> {
+        self.list(
+            "SELECT data FROM run_instances WHERE task_id = ?1 ORDER BY started_at",
+            (task_id.to_string(),),
+        )
+        .await
+    }
+
+    async fn query_run_instances(&self, task_id: Uuid, query: &RangeQuery) -> StorageResult<Page<TaskRunInstance>> {
+        let sql = if query.newest_first {
+            "SELECT data FROM run_instances WHERE task_id = ?1 AND started_at >= ?2 AND started_at < ?3
+             ORDER BY started_at DESC LIMIT ?4 OFFSET ?5"
+        } else {
+            "SELECT data FROM run_instances WHERE task_id = ?1 AND started_at >= ?2 AND started_at < ?3
+             ORDER BY started_at LIMIT ?4 OFFSET ?5"
+        };
+        let (since, until, limit, offset) = Self::range_params(query);
+        let items = self
+            .list(sql, (task_id.to_string(), since, until, limit, offset))
+            .await?;
+        Ok(Page::new(items, query))
+    }
+
+    async fn delete_run_instances(&self, task_id: Uuid) -> StorageResult<()> {
+        self.execute("DELETE FROM run_instances WHERE task_id = ?1", (task_id.to_string(),))
+            .await?;
+        Ok(())
+    }
+
+    async fn clear_all_instances(&self) -> StorageResult<usize> {
+        self.with_conn(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM run_summaries", [])?;
+            let count = tx.execute("DELETE FROM run_instances", [])?;
+            tx.commit()?;
+            Ok(count)
+        })
+        .await
+    }
+
+    async fn list_all_run_instances(&self) -> StorageResult<Vec<TaskRunInstance>> {
+        self.list("SELECT data FROM run_instances ORDER BY started_at", ()).await
+    }
+
+    async fn delete_run_instance(&self, instance_id: Uuid) -> StorageResult<usize> {
+        let instance_id = instance_id.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let logs = tx.execute("DELETE FROM logs WHERE run_instance_id = ?1", params![instance_id])?;
+            tx.execute("DELETE FROM run_instances WHERE id = ?1", params![instance_id])?;
+            tx.commit()?;
+            Ok(logs)
+        })
+        .await
+    }
+
+    // 日志操作
+    async fn save_log(&self, log: &TaskLog) -> StorageResult<()> {
+        let data = Self::serialize(log)?;
+        self.execute(
+            "INSERT INTO logs (id, run_instance_id, timestamp, data) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (id) DO UPDATE SET run_instance_id = excluded.run_instance_id,
+                 timestamp = excluded.timestamp, data = excluded.data",
+            (
+                log.id.to_string(),
+                log.run_instance_id.to_string(),
+                Self::timestamp(&log.timestamp),
+                data
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn list_logs(&self, instance_id: Uuid) -> StorageResult<Vec<TaskLog>> {
+        self.list(
+            "SELECT data FROM logs WHERE run_instance_id = ?1 ORDER BY timestamp",
+            (instance_id.to_string(),),
+        )
+        .await
+    }
+
+    async fn query_logs(&self, instance_id: Uuid, query: &RangeQuery) -> StorageResult<Page<TaskLog>> {
+        let sql = if query.newest_first {
+            "SELECT data FROM logs WHERE run_instance_id = ?1 AND timestamp >= ?2 AND timestamp < ?3
+             ORDER BY timestamp DESC LIMIT ?4 OFFSET ?5"
+        } else {
+            "SELECT data FROM logs WHERE run_instance_id = ?1 AND timestamp >= ?2 AND timestamp < ?3
+             ORDER BY timestamp LIMIT ?4 OFFSET ?5"
+        };
+        let (since, until, limit, offset) = Self::range_params(query);
+        let items = self
+            .list(sql, (instance_id.to_string(), since, until, limit, offset))
+            .await?;
+        Ok(Page::new(items, query))
+    }
+
+    async fn delete_logs(&self, instance_id: Uuid) -> StorageResult<()> {
+        self.execute("DELETE FROM logs WHERE run_instance_id = ?1", (instance_id.to_string(),))
+            .await?;
+        Ok(())
+    }
+
+    async fn clear_all_logs(&self) -> StorageResult<usize> {
+        self.execute("DELETE FROM logs", ()).await
+    }
+
+    async fn log_usage(&self) -> StorageResult<HashMap<Uuid, (usize, u64)>> {
+        let rows: Vec<(String, i64, i64)> = self
+            .with_conn(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT run_instance_id, COUNT(*), SUM(LENGTH(data)) FROM logs GROUP BY run_instance_id",
+                )?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?.collect();
+                rows
+            })
+            .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, count, bytes)| Some((id.parse().ok()?, (count as usize, bytes as u64))))
+            .collect())
+    }
+
+    // 已清理运行的汇总
+    async fn save_run_summary(&self, summary: &RunHistorySummary) -> StorageResult<()> {
+        let data = Self::serialize(summary)?;
+        self.execute(
+            "INSERT INTO run_summaries (task_id, data) VALUES (?1, ?2)
+             ON CONFLICT (task_id) DO UPDATE SET data = excluded.data",
+            (summary.task_id.to_string(), data),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn load_run_summary(&self, task_id: Uuid) -> StorageResult<Option<RunHistorySummary>> {
+        self.load("SELECT data FROM run_summaries WHERE task_id = ?1", (task_id.to_string(),))
+            .await
+    }
+
+    async fn delete_run_summary(&self, task_id: Uuid) -> StorageResult<()> {
+        self.execute("DELETE FROM run_summaries WHERE task_id = ?1", (task_id.to_string(),))
+            .await?;
+        Ok(())
+    }
+
+    // 工作流操作
+    async fn save_workflow(&self, workflow: &Workflow) -> StorageResult<()> {
+        let data = Self::serialize(workflow)?;
+        self.execute(
+            "INSERT INTO workflows (id, data) VALUES (?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET data = excluded.data",
+            (workflow.id.to_string(), data),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn load_workflow(&self, workflow_id: Uuid) -> StorageResult<Option<Workflow>> {
+        self.load("SELECT data FROM workflows WHERE id = ?1", (workflow_id.to_string(),))
+            .await
+    }
+
+    async fn delete_workflow(&self, workflow_id: Uuid) -> StorageResult<()> {
+        let workflow_id = workflow_id.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM workflow_runs WHERE workflow_id = ?1", params![workflow_id])?;
+            tx.execute("DELETE FROM workflows WHERE id = ?1", params![workflow_id])?;
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn list_workflows(&self) -> StorageResult<Vec<Workflow>> {
+        self.list("SELECT data FROM workflows", ()).await
+    }
+
+    async fn save_workflow_run(&self, run: &WorkflowRun) -> StorageResult<()> {
+        let data = Self::serialize(run)?;
+        self.execute(
+            "INSERT INTO workflow_runs (id, workflow_id, started_at, data) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (id) DO UPDATE SET workflow_id = excluded.workflow_id,
+                 started_at = excluded.started_at, data = excluded.data",
+            (
+                run.id.to_string(),
+                run.workflow_id.to_string(),
+                Self::timestamp(&run.started_at),
+                data
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn load_workflow_run(&self, run_id: Uuid) -> StorageResult<Option<WorkflowRun>> {
+        self.load("SELECT data FROM workflow_runs WHERE id = ?1", (run_id.to_string(),))
+            .await
+    }
+
+    async fn list_workflow_runs(&self, workflow_id: Uuid) -> StorageResult<Vec<WorkflowRun>> {
+        self.list(
+            "SELECT data FROM workflow_runs WHERE workflow_id = ?1 ORDER BY started_at",
+            (workflow_id.to_string(),),
+        )
+        .await
+    }
+
+    async fn clear_all_workflows(&self) -> StorageResult<usize> {
+        self.with_conn(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM workflow_runs", [])?;
+            let count = tx.execute("DELETE FROM workflows", [])?;
+            tx.commit()?;
+            Ok(count)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_temp_storage() -> (TempDir, SqliteSchedulerStorage) {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = SqliteSchedulerStorage::new(temp_dir.path().join("scheduler.db")).unwrap();
+        (temp_dir, storage)
+    }
+
+    fn create_task() -> ScheduledTask {
+        ScheduledTask::new(
+            Uuid::new_v4(),
+            "Task".to_string(),
+            "task".to_string(),
+            "0 * * * * *".to_string(),
+            None,
+            Some("echo hello".to_string()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_tasks() {
+        let (temp, storage) = create_temp_storage();
+        let mut task = create_task();
+        storage.save_task(&task).await.unwrap();
+        task.title = "Renamed".to_string();
+        storage.save_task(&task).await.unwrap();
+
+        let loaded = storage.load_task(task.id).await.unwrap().unwrap();
+        assert_eq!(loaded.title, "Renamed");
+        assert_eq!(storage.list_tasks().await.unwrap().len(), 1);
+
+        // 重新打开后数据仍在，迁移不会重复执行
+        drop(storage);
+        let storage = SqliteSchedulerStorage::new(temp.path().join("scheduler.db")).unwrap();
+        assert!(storage.load_task(task.id).await.unwrap().is_some());
+        storage.delete_task(task.id).await.unwrap();
+        assert!(storage.load_task(task.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_run_instances_and_logs() {
+        let (_temp, storage) = create_temp_storage();
+        let task_id = Uuid::new_v4();
+        let mut first = TaskRunInstance::new(task_id, Default::default());
+        first.started_at = Utc::now() - chrono::Duration::minutes(5);
+        let second = TaskRunInstance::new(task_id, Default::default());
+        storage.save_run_instance(&second).await.unwrap();
+        storage.save_run_instance(&first).await.unwrap();
+        storage.save_run_instance(&TaskRunInstance::new(Uuid::new_v4(), Default::default())).await.unwrap();
+
+        let instances = storage.list_run_instances(task_id).await.unwrap();
+        assert_eq!(instances.iter().map(|i| i.id).collect::<Vec<_>>(), vec![first.id, second.id]);
+
+        storage
+            .save_log(&TaskLog::new(first.id, LogLevel::Info, "started".to_string()))
+            .await
+            .unwrap();
+        storage
+            .save_log(&TaskLog::new(first.id, LogLevel::Info, "finished".to_string()))
+            .await
+            .unwrap();
+        let logs = storage.list_logs(first.id).await.unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].message, "started");
+
+        storage.delete_run_instances(task_id).await.unwrap();
+        assert!(storage.list_run_instances(task_id).await.unwrap().is_empty());
+        assert_eq!(storage.clear_all_instances().await.unwrap(), 1);
+        assert_eq!(storage.clear_all_logs().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_query_by_time_range() {
+        let (_temp, storage) = create_temp_storage();
+        let task_id = Uuid::new_v4();
+        let mut instances = Vec::new();
+        for minutes in [30, 20, 10] {
+            let mut instance = TaskRunInstance::new(task_id, Default::default());
+            instance.started_at = Utc::now() - chrono::Duration::minutes(minutes);
+            storage.save_run_instance(&instance).await.unwrap();
+            instances.push(instance);
+        }
+
+        let query = RangeQuery::default().newest_first().with_page(0, 2);
+        let page = storage.query_run_instances(task_id, &query).await.unwrap();
+        assert_eq!(page.items.iter().map(|i| i.id).collect::<Vec<_>>(), vec![instances[2].id, instances[1].id]);
+        assert_eq!(page.next_offset, Some(2));
+
+        let query = RangeQuery::default().with_until(instances[1].started_at);
+        let page = storage.query_run_instances(task_id, &query).await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, instances[0].id);
+        assert_eq!(page.next_offset, None);
+
+        storage
+            .save_log(&TaskLog::new(instances[0].id, LogLevel::Info, "only".to_string()))
+            .await
+            .unwrap();
+        let logs = storage.query_logs(instances[0].id, &RangeQuery::default()).await.unwrap();
+        assert_eq!(logs.items.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reader() {
+        let (temp, writer) = create_temp_storage();
+        let reader = SqliteSchedulerStorage::new(temp.path().join("scheduler.db")).unwrap();
+        let task = create_task();
+        writer.save_task(&task).await.unwrap();
+        assert!(reader.load_task(task.id).await.unwrap().is_some());
+    }
+}
@@ -1,17 +1,28 @@
 //! 任务调度器持久化存储
 //!
-//! 使用 sled 实现任务、运行实例和日志的持久化存储
+//! 定义任务、运行实例和日志的存储接口，提供 sled 和内存实现，
+//! SQLite 实现见 [`crate::sqlite_storage`]
 
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use async_trait::async_trait;
-use tokio::sync::RwLock;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sled::transaction::{ConflictableTransactionResult, TransactionError};
+use sled::Transactional;
+use tokio::sync::{Mutex, RwLock};
 use uuid::Uuid;
 
+use config::{StorageBackend, StorageConfig};
 use storage::{SledStorage, Storage};
 
+use crate::retention::RunHistorySummary;
+use crate::sqlite_storage::SqliteSchedulerStorage;
 use crate::types::*;
+use crate::workflow::{Workflow, WorkflowRun};
 
 /// 调度器存储错误
 #[derive(Debug, thiserror::Error)]
@@ -26,6 +37,105 @@ pub enum SchedulerStorageError {
 
 pub type StorageResult<T> = Result<T, SchedulerStorageError>;
 
+impl From<sled::Error> for SchedulerStorageError {
+    fn from(e: sled::Error) -> Self {
+        SchedulerStorageError::StorageError(e.to_string())
+    }
+}
+
+impl From<TransactionError<()>> for SchedulerStorageError {
+    fn from(e: TransactionError<()>) -> Self {
+        match e {
+            TransactionError::Storage(e) => e.into(),
+            TransactionError::Abort(()) => SchedulerStorageError::StorageError("Transaction aborted".to_string()),
+        }
+    }
+}
+
+/// 按时间范围分页查询的条件
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RangeQuery {
+    /// 起始时间 (含)
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    /// 结束时间 (不含)
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    /// 跳过的条数
+    #[serde(default)]
+    pub offset: usize,
+    /// 返回的最大条数，None 表示不限制
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// 是否从新到旧排列
+    #[serde(default)]
+    pub newest_first: bool,
+}
+
+impl RangeQuery {
+    /// 只返回不早于该时间的记录
+    pub fn with_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// 只返回早于该时间的记录
+    pub fn with_until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// 设置分页位置和每页条数
+    pub fn with_page(mut self, offset: usize, limit: usize) -> Self {
+        self.offset = offset;
+        self.limit = Some(limit);
+        self
+    }
+
+    /// 从新到旧排列
+    pub fn newest_first(mut self) -> Self {
+        self.newest_first = true;
+        self
+    }
+
+    /// 时间是否在查询范围内
+    pub fn contains(&self, time: &DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| *time >= since) && self.until.is_none_or(|until| *time < until)
+    }
+}
+
+/// 分页查询结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    /// 本页记录
+    pub items: Vec<T>,
+    /// 下一页的 offset，没有更多记录时为 None
+    pub next_offset: Option<usize>,
+}
+
+impl<T> Page<T> {
+    /// 由多取一条的查询结果构建分页，多出的一条说明还有下一页
+    pub(crate) fn new(mut items: Vec<T>, query: &RangeQuery) -> Self {
+        let next_offset = match query.limit {
+            Some(limit) if items.len() > limit => {
+                items.truncate(limit);
+                Some(query.offset + limit)
+            }
+            _ => None,
+        };
+        Self { items, next_offset }
+    }
+
+    /// 从已按查询排序和过滤的记录中取出一页
+    pub(crate) fn from_sorted(items: impl Iterator<Item = T>, query: &RangeQuery) -> Self {
+        let items = items
+            .skip(query.offset)
+            .take(query.limit.map_or(usize::MAX, |limit| limit.saturating_add(1)))
+            .collect();
+        Self::new(items, query)
+    }
+}
+
 /// 任务调度器存储 trait
 #[async_trait]
 pub trait SchedulerStorage: Send + Sync {
@@ -40,34 +150,340 @@ pub trait SchedulerStorage: Send + Sync {
     // 运行实例操作
     async fn save_run_instance(&self, instance: &TaskRunInstance) -> StorageResult<()>;
     async fn load_run_instance(&self, instance_id: Uuid) -> StorageResult<Option<TaskRunInstance>>;
+    /// 列出任务的运行实例，按开始时间排列
     async fn list_run_instances(&self, task_id: Uuid) -> StorageResult<Vec<TaskRunInstance>>;
+    /// 按开始时间范围分页查询任务的运行实例
+    async fn query_run_instances(&self, task_id: Uuid, query: &RangeQuery) -> StorageResult<Page<TaskRunInstance>>;
     async fn delete_run_instances(&self, task_id: Uuid) -> StorageResult<()>;
-    /// 清空所有运行实例，返回被清空的实例数量
+    /// 清空所有运行实例和已清理运行的汇总，返回被清空的实例数量
     async fn clear_all_instances(&self) -> StorageResult<usize>;
+    /// 列出所有任务的运行实例
+    async fn list_all_run_instances(&self) -> StorageResult<Vec<TaskRunInstance>>;
+    /// 删除单个运行实例及其日志，返回删除的日志数量
+    async fn delete_run_instance(&self, instance_id: Uuid) -> StorageResult<usize>;
 
     // 日志操作
     async fn save_log(&self, log: &TaskLog) -> StorageResult<()>;
+    /// 列出运行实例的日志，按时间排列
     async fn list_logs(&self, instance_id: Uuid) -> StorageResult<Vec<TaskLog>>;
+    /// 按时间范围分页查询运行实例的日志
+    async fn query_logs(&self, instance_id: Uuid, query: &RangeQuery) -> StorageResult<Page<TaskLog>>;
     async fn delete_logs(&self, instance_id: Uuid) -> StorageResult<()>;
     /// 清空所有日志，返回被清空的日志数量
     async fn clear_all_logs(&self) -> StorageResult<usize>;
+    /// 按运行实例统计日志条数和序列化后的字节数
+    async fn log_usage(&self) -> StorageResult<HashMap<Uuid, (usize, u64)>>;
+
+    // 已清理运行的汇总
+    async fn save_run_summary(&self, summary: &RunHistorySummary) -> StorageResult<()>;
+    async fn load_run_summary(&self, task_id: Uuid) -> StorageResult<Option<RunHistorySummary>>;
+    async fn delete_run_summary(&self, task_id: Uuid) -> StorageResult<()>;
+
+    // 工作流操作
+    async fn save_workflow(&self, workflow: &Workflow) -> StorageResult<()>;
+    async fn load_workflow(&self, workflow_id: Uuid) -> StorageResult<Option<Workflow>>;
+    /// 删除工作流及其运行记录
+    async fn delete_workflow(&self, workflow_id: Uuid) -> StorageResult<()>;
+    async fn list_workflows(&self) -> StorageResult<Vec<Workflow>>;
+    async fn save_workflow_run(&self, run: &WorkflowRun) -> StorageResult<()>;
+    async fn load_workflow_run(&self, run_id: Uuid) -> StorageResult<Option<WorkflowRun>>;
+    async fn list_workflow_runs(&self, workflow_id: Uuid) -> StorageResult<Vec<WorkflowRun>>;
+    /// 清空所有工作流及其运行记录，返回被清空的工作流数量
+    async fn clear_all_workflows(&self) -> StorageResult<usize>;
+}
+
+/// 按存储配置打开调度器存储
+///
+/// `config.path` 为调度器数据目录，调用方负责展开 `~`。sled 直接使用该目录，
+/// SQLite 使用目录中的 `scheduler.db`
+pub fn open_scheduler_storage(config: &StorageConfig) -> StorageResult<Arc<dyn SchedulerStorage>> {
+    let create_dir = || {
+        std::fs::create_dir_all(&config.path).map_err(|e| {
+            SchedulerStorageError::StorageError(format!("Failed to create data directory: {}", e))
+        })
+    };
+    let storage: Arc<dyn SchedulerStorage> = match config.backend {
+        StorageBackend::Sled => {
+            create_dir()?;
+            // 共享模式打开，守护进程和其他 CLI 调用可同时使用同一数据目录
+            Arc::new(SledSchedulerStorage::shared(config.path.clone()))
+        }
+        StorageBackend::Sqlite => {
+            create_dir()?;
+            Arc::new(SqliteSchedulerStorage::new(config.path.join("scheduler.db"))?)
+        }
+        StorageBackend::Memory => Arc::new(MemorySchedulerStorage::new()),
+        StorageBackend::Json => {
+            return Err(SchedulerStorageError::StorageError(
+                "JSON backend is not supported for scheduler storage".to_string(),
+            ))
+        }
+    };
+    Ok(storage)
+}
+
+/// 共享模式下打开数据库时等待其他进程释放锁的最长时间
+const SHARED_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 共享模式下数据库空闲多久后释放
+const SHARED_IDLE_RELEASE: Duration = Duration::from_millis(250);
+
+/// 按任务索引运行实例的树
+const INSTANCES_BY_TASK: &str = "index:instances_by_task";
+
+/// 按运行实例索引日志的树
+const LOGS_BY_INSTANCE: &str = "index:logs_by_instance";
+
+/// 记录索引版本的树
+const INDEX_META: &str = "index:meta";
+
+/// 索引版本，索引结构变化时递增，打开数据库时按新结构重建
+const INDEX_VERSION: u64 = 1;
+
+/// 索引键中的时间，有符号微秒数翻转符号位后按大端序比较即按时间先后
+fn encode_time(time: &DateTime<Utc>) -> [u8; 8] {
+    ((time.timestamp_micros() as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+/// 索引键: 所属 ID (16 字节) + 时间 (8 字节) + 记录 ID (16 字节)
+fn index_key(owner: Uuid, time: &DateTime<Utc>, id: Uuid) -> Vec<u8> {
+    let mut key = Vec::with_capacity(40);
+    key.extend_from_slice(owner.as_bytes());
+    key.extend_from_slice(&encode_time(time));
+    key.extend_from_slice(id.as_bytes());
+    key
+}
+
+fn instance_index_key(instance: &TaskRunInstance) -> Vec<u8> {
+    index_key(instance.task_id, &instance.started_at, instance.id)
+}
+
+fn log_index_key(log: &TaskLog) -> Vec<u8> {
+    index_key(log.run_instance_id, &log.timestamp, log.id)
+}
+
+/// 索引键中的记录 ID
+fn index_record_id(key: &[u8]) -> Uuid {
+    key.get(24..).and_then(|id| Uuid::from_slice(id).ok()).unwrap_or_default()
+}
+
+/// 按索引查询所属记录中的一页，索引指向的记录已不存在或无法解析时跳过
+fn query_index<T: serde::de::DeserializeOwned>(
+    db: &sled::Db,
+    index: &sled::Tree,
+    owner: Uuid,
+    query: &RangeQuery,
+    record_key: impl Fn(Uuid) -> String,
+) -> StorageResult<Page<T>> {
+    let bound = |time: Option<DateTime<Utc>>, default: [u8; 8]| {
+        let mut key = owner.as_bytes().to_vec();
+        key.extend_from_slice(&time.map_or(default, |time| encode_time(&time)));
+        key
+    };
+    let range = index.range(bound(query.since, [0; 8])..bound(query.until, [0xff; 8]));
+    let entries: Box<dyn Iterator<Item = sled::Result<(sled::IVec, sled::IVec)>>> = if query.newest_first {
+        Box::new(range.rev())
+    } else {
+        Box::new(range)
+    };
+
+    let wanted = query.limit.map(|limit| limit.saturating_add(1));
+    let mut skipped = 0;
+    let mut items = Vec::new();
+    for entry in entries {
+        let (key, _) = entry?;
+        let Some(value) = db.get(record_key(index_record_id(&key)))? else {
+            continue;
+        };
+        let Ok(item) = serde_json::from_slice::<T>(&value) else {
+            continue;
+        };
+        if skipped < query.offset {
+            skipped += 1;
+            continue;
+        }
+        items.push(item);
+        if Some(items.len()) == wanted {
+            break;
+        }
+    }
+    Ok(Page::new(items, query))
+}
+
+/// 为还没有索引或索引版本较旧的数据库重建索引
+fn migrate_indexes(db: &sled::Db) -> StorageResult<()> {
+    let meta = db.open_tree(INDEX_META)?;
+    let version = meta
+        .get("version")?
+        .and_then(|v| <[u8; 8]>::try_from(v.as_ref()).ok())
+        .map_or(0, u64::from_be_bytes);
+    if version >= INDEX_VERSION {
+        return Ok(());
+    }
+
+    let instances = db.open_tree(INSTANCES_BY_TASK)?;
+    let logs = db.open_tree(LOGS_BY_INSTANCE)?;
+    instances.clear()?;
+    logs.clear()?;
+    let mut indexed = 0;
+    for entry in db.scan_prefix("instance:") {
+        let (_, value) = entry?;
+        if let Ok(instance) = serde_json::from_slice::<TaskRunInstance>(&value) {
+            instances.insert(instance_index_key(&instance), &[])?;
+            indexed += 1;
+        }
+    }
+    for entry in db.scan_prefix("log:") {
+        let (_, value) = entry?;
+        if let Ok(log) = serde_json::from_slice::<TaskLog>(&value) {
+            logs.insert(log_index_key(&log), &[])?;
+            indexed += 1;
+        }
+    }
+    meta.insert("version", &INDEX_VERSION.to_be_bytes())?;
+    db.flush()?;
+    if indexed > 0 {
+        tracing::info!("Built scheduler storage indexes (version {}) for {} records", INDEX_VERSION, indexed);
+    }
+    Ok(())
 }
 
 /// 基于 Sled 的存储实现
+///
+/// sled 同一时间只允许一个进程打开数据库。共享模式下按需打开数据库，
+/// 空闲后立即释放，使守护进程和其他 CLI 调用可以交替访问同一数据目录。
+/// 运行实例和日志另有按所属任务、运行实例和时间排序的索引树，
+/// 查询时无需扫描全部记录
 pub struct SledSchedulerStorage {
-    storage: Arc<SledStorage>,
+    path: PathBuf,
+    shared: bool,
+    db: Arc<Mutex<Option<Arc<SledStorage>>>>,
+    /// 每次访问递增，用于判断数据库是否空闲
+    accesses: Arc<AtomicU64>,
 }
 
 impl SledSchedulerStorage {
-    /// 创建新的存储实例
+    /// 创建新的存储实例，独占数据库直到实例释放
     pub fn new(path: PathBuf) -> StorageResult<Self> {
-        let storage = SledStorage::new(path)
+        let storage = SledStorage::new(path.clone())
             .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        migrate_indexes(storage.db())?;
         Ok(Self {
-            storage: Arc::new(storage),
+            path,
+            shared: false,
+            db: Arc::new(Mutex::new(Some(Arc::new(storage)))),
+            accesses: Arc::new(AtomicU64::new(0)),
         })
     }
 
+    /// 创建共享模式的存储实例
+    pub fn shared(path: PathBuf) -> Self {
+        Self {
+            path,
+            shared: true,
+            db: Arc::new(Mutex::new(None)),
+            accesses: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// 获取数据库句柄，共享模式下按需打开
+    async fn db(&self) -> StorageResult<Arc<SledStorage>> {
+        let mut db = self.db.lock().await;
+        self.accesses.fetch_add(1, Ordering::Relaxed);
+        if let Some(storage) = db.as_ref() {
+            return Ok(storage.clone());
+        }
+
+        let storage = Arc::new(Self::open_shared(self.path.clone()).await?);
+        *db = Some(storage.clone());
+        if self.shared {
+            self.release_when_idle();
+        }
+        Ok(storage)
+    }
+
+    /// 打开数据库，被其他进程占用时重试
+    async fn open_shared(path: PathBuf) -> StorageResult<SledStorage> {
+        let deadline = tokio::time::Instant::now() + SHARED_LOCK_TIMEOUT;
+        loop {
+            let attempt = path.clone();
+            let result = tokio::task::spawn_blocking(move || SledStorage::new(attempt))
+                .await
+                .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+            match result {
+                Ok(storage) => {
+                    let db = storage.db().clone();
+                    tokio::task::spawn_blocking(move || migrate_indexes(&db))
+                        .await
+                        .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))??;
+                    return Ok(storage);
+                }
+                // sled 在文件锁被占用时报告 "could not acquire lock"
+                Err(e) if e.to_string().contains("could not acquire lock")
+                    && tokio::time::Instant::now() < deadline =>
+                {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                Err(e) => return Err(SchedulerStorageError::StorageError(e.to_string())),
+            }
+        }
+    }
+
+    /// 数据库空闲后释放文件锁
+    fn release_when_idle(&self) {
+        let db = self.db.clone();
+        let accesses = self.accesses.clone();
+        tokio::spawn(async move {
+            loop {
+                let seen = accesses.load(Ordering::Relaxed);
+                tokio::time::sleep(SHARED_IDLE_RELEASE).await;
+                let mut guard = db.lock().await;
+                let in_use = guard
+                    .as_ref()
+                    .is_some_and(|storage| Arc::strong_count(storage) > 1);
+                if accesses.load(Ordering::Relaxed) == seen && !in_use {
+                    *guard = None;
+                    return;
+                }
+            }
+        });
+    }
+
+    /// 在阻塞线程中使用底层数据库，完成后刷盘
+    async fn with_db<T, F>(&self, f: F) -> StorageResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&sled::Db) -> StorageResult<T> + Send + 'static,
+    {
+        // 持有句柄直到操作完成，期间共享模式不会释放数据库
+        let storage = self.db().await?;
+        tokio::task::spawn_blocking(move || {
+            let db = storage.db();
+            let result = f(db)?;
+            db.flush()?;
+            Ok(result)
+        })
+        .await
+        .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?
+    }
+
+    /// 删除运行实例的日志，返回删除的条数
+    async fn delete_logs_of(&self, instance_id: Uuid) -> StorageResult<usize> {
+        self.with_db(move |db| {
+            let index = db.open_tree(LOGS_BY_INSTANCE)?;
+            let mut count = 0;
+            for entry in index.scan_prefix(instance_id.as_bytes()) {
+                let (index_key, _) = entry?;
+                if db.remove(Self::log_key(index_record_id(&index_key)))?.is_some() {
+                    count += 1;
+                }
+                index.remove(index_key)?;
+            }
+            Ok(count)
+        })
+        .await
+    }
+
     /// 序列化值
     fn serialize<T: serde::Serialize>(value: &T) -> StorageResult<String> {
         serde_json::to_string(value)
@@ -95,6 +511,44 @@ impl SledSchedulerStorage {
         format!("log:{}", log_id)
     }
 
+    /// 生成运行汇总键
+    fn summary_key(task_id: Uuid) -> String {
+        format!("summary:{}", task_id)
+    }
+
+    /// 生成工作流键
+    fn workflow_key(workflow_id: Uuid) -> String {
+        format!("workflow:{}", workflow_id)
+    }
+
+    /// 生成工作流运行键
+    fn workflow_run_key(run_id: Uuid) -> String {
+        format!("workflow_run:{}", run_id)
+    }
+
+    /// 读取并反序列化指定前缀下的所有值，无法解析的条目被忽略
+    async fn list_prefixed<T: serde::de::DeserializeOwned>(
+        storage: &SledStorage,
+        prefix: &str,
+    ) -> StorageResult<Vec<T>> {
+        let keys = storage
+            .list_keys()
+            .await
+            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+
+        let mut values = Vec::new();
+        for key in keys {
+            if key.starts_with(prefix) {
+                if let Ok(Some(value)) = storage.get(&key).await {
+                    if let Ok(value) = Self::deserialize::<T>(&value) {
+                        values.push(value);
+                    }
+                }
+            }
+        }
+        Ok(values)
+    }
+
     /// 生成任务索引键
     fn task_index_key() -> &'static str {
         "index:tasks"
@@ -105,9 +559,10 @@ impl SledSchedulerStorage {
 impl SchedulerStorage for SledSchedulerStorage {
     // 任务操作
     async fn save_task(&self, task: &ScheduledTask) -> StorageResult<()> {
+        let storage = self.db().await?;
         let key = Self::task_key(task.id);
         let value = Self::serialize(task)?;
-        self.storage
+        storage
             .set(&key, &value)
             .await
             .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
@@ -115,9 +570,9 @@ impl SchedulerStorage for SledSchedulerStorage {
     }
 
     async fn load_task(&self, task_id: Uuid) -> StorageResult<Option<ScheduledTask>> {
+        let storage = self.db().await?;
         let key = Self::task_key(task_id);
-        let result = self
-            .storage
+        let result = storage
             .get(&key)
             .await
             .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
@@ -132,8 +587,9 @@ impl SchedulerStorage for SledSchedulerStorage {
     }
 
     async fn delete_task(&self, task_id: Uuid) -> StorageResult<()> {
+        let storage = self.db().await?;
         let key = Self::task_key(task_id);
-        self.storage
+        storage
             .delete(&key)
             .await
             .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
@@ -141,8 +597,8 @@ impl SchedulerStorage for SledSchedulerStorage {
     }
 
     async fn list_tasks(&self) -> StorageResult<Vec<ScheduledTask>> {
-        let keys = self
-            .storage
+        let storage = self.db().await?;
+        let keys = storage
             .list_keys()
             .await
             .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
@@ -150,7 +606,7 @@ impl SchedulerStorage for SledSchedulerStorage {
         let mut tasks = Vec::new();
         for key in keys {
             if key.starts_with("task:") {
-                if let Ok(Some(value)) = self.storage.get(&key).await {
+                if let Ok(Some(value)) = storage.get(&key).await {
                     if let Ok(task) = Self::deserialize::<ScheduledTask>(&value) {
                         tasks.push(task);
                     }
@@ -161,11 +617,12 @@ impl SchedulerStorage for SledSchedulerStorage {
     }
 
     async fn clear_all_tasks(&self) -> StorageResult<usize> {
+        let storage = self.db().await?;
         let tasks = self.list_tasks().await?;
         let count = tasks.len();
         for task in tasks {
             let key = Self::task_key(task.id);
-            let _ = self.storage.delete(&key).await;
+            let _ = storage.delete(&key).await;
         }
         Ok(count)
     }
@@ -174,17 +631,28 @@ impl SchedulerStorage for SledSchedulerStorage {
     async fn save_run_instance(&self, instance: &TaskRunInstance) -> StorageResult<()> {
         let key = Self::instance_key(instance.id);
         let value = Self::serialize(instance)?;
-        self.storage
-            .set(&key, &value)
-            .await
-            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
-        Ok(())
+        let index_key = instance_index_key(instance);
+        self.with_db(move |db| {
+            let index = db.open_tree(INSTANCES_BY_TASK)?;
+            (&**db, &index).transaction(|(main, index)| -> ConflictableTransactionResult<()> {
+                // 开始时间变化时移除旧的索引项
+                if let Some(old) = main.insert(key.as_bytes(), value.as_bytes())? {
+                    if let Ok(old) = serde_json::from_slice::<TaskRunInstance>(&old) {
+                        index.remove(instance_index_key(&old))?;
+                    }
+                }
+                index.insert(index_key.clone(), &[])?;
+                Ok(())
+            })?;
+            Ok(())
+        })
+        .await
     }
 
     async fn load_run_instance(&self, instance_id: Uuid) -> StorageResult<Option<TaskRunInstance>> {
+        let storage = self.db().await?;
         let key = Self::instance_key(instance_id);
-        let result = self
-            .storage
+        let result = storage
             .get(&key)
             .await
             .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
@@ -199,110 +667,248 @@ impl SchedulerStorage for SledSchedulerStorage {
     }
 
     async fn list_run_instances(&self, task_id: Uuid) -> StorageResult<Vec<TaskRunInstance>> {
-        let keys = self
-            .storage
-            .list_keys()
-            .await
-            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        Ok(self.query_run_instances(task_id, &RangeQuery::default()).await?.items)
+    }
 
-        let mut instances = Vec::new();
-        for key in keys {
-            if key.starts_with("instance:") {
-                if let Ok(Some(value)) = self.storage.get(&key).await {
-                    if let Ok(instance) = Self::deserialize::<TaskRunInstance>(&value) {
-                        if instance.task_id == task_id {
-                            instances.push(instance);
-                        }
-                    }
-                }
-            }
-        }
-        Ok(instances)
+    async fn query_run_instances(&self, task_id: Uuid, query: &RangeQuery) -> StorageResult<Page<TaskRunInstance>> {
+        let query = query.clone();
+        self.with_db(move |db| {
+            let index = db.open_tree(INSTANCES_BY_TASK)?;
+            query_index(db, &index, task_id, &query, Self::instance_key)
+        })
+        .await
     }
 
     async fn delete_run_instances(&self, task_id: Uuid) -> StorageResult<()> {
-        let instances = self.list_run_instances(task_id).await?;
-        for instance in instances {
-            let key = Self::instance_key(instance.id);
-            let _ = self.storage.delete(&key).await;
-        }
-        Ok(())
+        self.with_db(move |db| {
+            let index = db.open_tree(INSTANCES_BY_TASK)?;
+            for entry in index.scan_prefix(task_id.as_bytes()) {
+                let (index_key, _) = entry?;
+                db.remove(Self::instance_key(index_record_id(&index_key)))?;
+                index.remove(index_key)?;
+            }
+            Ok(())
+        })
+        .await
     }
 
     async fn clear_all_instances(&self) -> StorageResult<usize> {
-        let keys = self
-            .storage
-            .list_keys()
-            .await
-            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
-
-        let mut count = 0;
-        for key in keys {
-            if key.starts_with("instance:") {
-                let _ = self.storage.delete(&key).await;
-                count += 1;
+        self.with_db(|db| {
+            let mut count = 0;
+            for prefix in ["instance:", "summary:"] {
+                for key in db.scan_prefix(prefix).keys() {
+                    db.remove(key?)?;
+                    if prefix == "instance:" {
+                        count += 1;
+                    }
+                }
             }
-        }
-        Ok(count)
+            db.open_tree(INSTANCES_BY_TASK)?.clear()?;
+            Ok(count)
+        })
+        .await
+    }
+
+    async fn list_all_run_instances(&self) -> StorageResult<Vec<TaskRunInstance>> {
+        let storage = self.db().await?;
+        Self::list_prefixed(&storage, "instance:").await
+    }
+
+    async fn delete_run_instance(&self, instance_id: Uuid) -> StorageResult<usize> {
+        let deleted_logs = self.delete_logs_of(instance_id).await?;
+        let key = Self::instance_key(instance_id);
+        self.with_db(move |db| {
+            let index = db.open_tree(INSTANCES_BY_TASK)?;
+            (&**db, &index).transaction(|(main, index)| -> ConflictableTransactionResult<()> {
+                if let Some(old) = main.remove(key.as_bytes())? {
+                    if let Ok(old) = serde_json::from_slice::<TaskRunInstance>(&old) {
+                        index.remove(instance_index_key(&old))?;
+                    }
+                }
+                Ok(())
+            })?;
+            Ok(())
+        })
+        .await?;
+        Ok(deleted_logs)
     }
 
     // 日志操作
     async fn save_log(&self, log: &TaskLog) -> StorageResult<()> {
         let key = Self::log_key(log.id);
         let value = Self::serialize(log)?;
-        self.storage
+        let index_key = log_index_key(log);
+        self.with_db(move |db| {
+            let index = db.open_tree(LOGS_BY_INSTANCE)?;
+            (&**db, &index).transaction(|(main, index)| -> ConflictableTransactionResult<()> {
+                if let Some(old) = main.insert(key.as_bytes(), value.as_bytes())? {
+                    if let Ok(old) = serde_json::from_slice::<TaskLog>(&old) {
+                        index.remove(log_index_key(&old))?;
+                    }
+                }
+                index.insert(index_key.clone(), &[])?;
+                Ok(())
+            })?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn list_logs(&self, instance_id: Uuid) -> StorageResult<Vec<TaskLog>> {
+        Ok(self.query_logs(instance_id, &RangeQuery::default()).await?.items)
+    }
+
+    async fn query_logs(&self, instance_id: Uuid, query: &RangeQuery) -> StorageResult<Page<TaskLog>> {
+        let query = query.clone();
+        self.with_db(move |db| {
+            let index = db.open_tree(LOGS_BY_INSTANCE)?;
+            query_index(db, &index, instance_id, &query, Self::log_key)
+        })
+        .await
+    }
+
+    async fn delete_logs(&self, instance_id: Uuid) -> StorageResult<()> {
+        self.delete_logs_of(instance_id).await?;
+        Ok(())
+    }
+
+    async fn clear_all_logs(&self) -> StorageResult<usize> {
+        self.with_db(|db| {
+            let mut count = 0;
+            for key in db.scan_prefix("log:").keys() {
+                db.remove(key?)?;
+                count += 1;
+            }
+            db.open_tree(LOGS_BY_INSTANCE)?.clear()?;
+            Ok(count)
+        })
+        .await
+    }
+
+    async fn log_usage(&self) -> StorageResult<HashMap<Uuid, (usize, u64)>> {
+        self.with_db(|db| {
+            let mut usage: HashMap<Uuid, (usize, u64)> = HashMap::new();
+            for entry in db.scan_prefix("log:") {
+                let (_, value) = entry?;
+                if let Ok(log) = serde_json::from_slice::<TaskLog>(&value) {
+                    let entry = usage.entry(log.run_instance_id).or_default();
+                    entry.0 += 1;
+                    entry.1 += value.len() as u64;
+                }
+            }
+            Ok(usage)
+        })
+        .await
+    }
+
+    // 已清理运行的汇总
+    async fn save_run_summary(&self, summary: &RunHistorySummary) -> StorageResult<()> {
+        let storage = self.db().await?;
+        let value = Self::serialize(summary)?;
+        storage
+            .set(&Self::summary_key(summary.task_id), &value)
+            .await
+            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn load_run_summary(&self, task_id: Uuid) -> StorageResult<Option<RunHistorySummary>> {
+        let storage = self.db().await?;
+        let result = storage
+            .get(&Self::summary_key(task_id))
+            .await
+            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        result.map(|value| Self::deserialize(&value)).transpose()
+    }
+
+    async fn delete_run_summary(&self, task_id: Uuid) -> StorageResult<()> {
+        let storage = self.db().await?;
+        storage
+            .delete(&Self::summary_key(task_id))
+            .await
+            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        Ok(())
+    }
+
+    // 工作流操作
+    async fn save_workflow(&self, workflow: &Workflow) -> StorageResult<()> {
+        let storage = self.db().await?;
+        let key = Self::workflow_key(workflow.id);
+        let value = Self::serialize(workflow)?;
+        storage
             .set(&key, &value)
             .await
             .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
         Ok(())
     }
 
-    async fn list_logs(&self, instance_id: Uuid) -> StorageResult<Vec<TaskLog>> {
-        let keys = self
-            .storage
-            .list_keys()
+    async fn load_workflow(&self, workflow_id: Uuid) -> StorageResult<Option<Workflow>> {
+        let storage = self.db().await?;
+        let result = storage
+            .get(&Self::workflow_key(workflow_id))
             .await
             .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        result.map(|value| Self::deserialize(&value)).transpose()
+    }
 
-        let mut logs = Vec::new();
-        for key in keys {
-            if key.starts_with("log:") {
-                if let Ok(Some(value)) = self.storage.get(&key).await {
-                    if let Ok(log) = Self::deserialize::<TaskLog>(&value) {
-                        if log.run_instance_id == instance_id {
-                            logs.push(log);
-                        }
-                    }
-                }
-            }
+    async fn delete_workflow(&self, workflow_id: Uuid) -> StorageResult<()> {
+        let storage = self.db().await?;
+        for run in self.list_workflow_runs(workflow_id).await? {
+            let _ = storage.delete(&Self::workflow_run_key(run.id)).await;
         }
-        // 按时间排序
-        logs.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-        Ok(logs)
+        storage
+            .delete(&Self::workflow_key(workflow_id))
+            .await
+            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        Ok(())
     }
 
-    async fn delete_logs(&self, instance_id: Uuid) -> StorageResult<()> {
-        let logs = self.list_logs(instance_id).await?;
-        for log in logs {
-            let key = Self::log_key(log.id);
-            let _ = self.storage.delete(&key).await;
-        }
+    async fn list_workflows(&self) -> StorageResult<Vec<Workflow>> {
+        let storage = self.db().await?;
+        Self::list_prefixed(&storage, "workflow:").await
+    }
+
+    async fn save_workflow_run(&self, run: &WorkflowRun) -> StorageResult<()> {
+        let storage = self.db().await?;
+        let key = Self::workflow_run_key(run.id);
+        let value = Self::serialize(run)?;
+        storage
+            .set(&key, &value)
+            .await
+            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
         Ok(())
     }
 
-    async fn clear_all_logs(&self) -> StorageResult<usize> {
-        let keys = self
-            .storage
+    async fn load_workflow_run(&self, run_id: Uuid) -> StorageResult<Option<WorkflowRun>> {
+        let storage = self.db().await?;
+        let result = storage
+            .get(&Self::workflow_run_key(run_id))
+            .await
+            .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
+        result.map(|value| Self::deserialize(&value)).transpose()
+    }
+
+    async fn list_workflow_runs(&self, workflow_id: Uuid) -> StorageResult<Vec<WorkflowRun>> {
+        let storage = self.db().await?;
+        let runs: Vec<WorkflowRun> = Self::list_prefixed(&storage, "workflow_run:").await?;
+        Ok(runs.into_iter().filter(|r| r.workflow_id == workflow_id).collect())
+    }
+
+    async fn clear_all_workflows(&self) -> StorageResult<usize> {
+        let storage = self.db().await?;
+        let keys = storage
             .list_keys()
             .await
             .map_err(|e| SchedulerStorageError::StorageError(e.to_string()))?;
 
         let mut count = 0;
         for key in keys {
-            if key.starts_with("log:") {
-                let _ = self.storage.delete(&key).await;
+            if key.starts_with("workflow:") {
                 count += 1;
             }
+            if key.starts_with("workflow:") || key.starts_with("workflow_run:") {
+                let _ = storage.delete(&key).await;
+            }
         }
         Ok(count)
     }
@@ -313,6 +919,9 @@ pub struct MemorySchedulerStorage {
     tasks: Arc<RwLock<Vec<ScheduledTask>>>,
     instances: Arc<RwLock<Vec<TaskRunInstance>>>,
     logs: Arc<RwLock<Vec<TaskLog>>>,
+    workflows: Arc<RwLock<HashMap<Uuid, Workflow>>>,
+    workflow_runs: Arc<RwLock<HashMap<Uuid, WorkflowRun>>>,
+    summaries: Arc<RwLock<HashMap<Uuid, RunHistorySummary>>>,
 }
 
 impl MemorySchedulerStorage {
@@ -321,6 +930,9 @@ impl MemorySchedulerStorage {
             tasks: Arc::new(RwLock::new(Vec::new())),
             instances: Arc::new(RwLock::new(Vec::new())),
             logs: Arc::new(RwLock::new(Vec::new())),
+            workflows: Arc::new(RwLock::new(HashMap::new())),
+            workflow_runs: Arc::new(RwLock::new(HashMap::new())),
+            summaries: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
@@ -382,8 +994,20 @@ impl SchedulerStorage for MemorySchedulerStorage {
     }
 
     async fn list_run_instances(&self, task_id: Uuid) -> StorageResult<Vec<TaskRunInstance>> {
+        Ok(self.query_run_instances(task_id, &RangeQuery::default()).await?.items)
+    }
+
+    async fn query_run_instances(&self, task_id: Uuid, query: &RangeQuery) -> StorageResult<Page<TaskRunInstance>> {
         let instances = self.instances.read().await;
-        Ok(instances.iter().filter(|i| i.task_id == task_id).cloned().collect())
+        let mut matched: Vec<&TaskRunInstance> = instances
+            .iter()
+            .filter(|i| i.task_id == task_id && query.contains(&i.started_at))
+            .collect();
+        matched.sort_by_key(|i| i.started_at);
+        if query.newest_first {
+            matched.reverse();
+        }
+        Ok(Page::from_sorted(matched.into_iter().cloned(), query))
     }
 
     async fn delete_run_instances(&self, task_id: Uuid) -> StorageResult<()> {
@@ -393,12 +1017,25 @@ impl SchedulerStorage for MemorySchedulerStorage {
     }
 
     async fn clear_all_instances(&self) -> StorageResult<usize> {
+        self.summaries.write().await.clear();
         let mut instances = self.instances.write().await;
         let count = instances.len();
         instances.clear();
         Ok(count)
     }
 
+    async fn list_all_run_instances(&self) -> StorageResult<Vec<TaskRunInstance>> {
+        Ok(self.instances.read().await.clone())
+    }
+
+    async fn delete_run_instance(&self, instance_id: Uuid) -> StorageResult<usize> {
+        self.instances.write().await.retain(|i| i.id != instance_id);
+        let mut logs = self.logs.write().await;
+        let before = logs.len();
+        logs.retain(|l| l.run_instance_id != instance_id);
+        Ok(before - logs.len())
+    }
+
     async fn save_log(&self, log: &TaskLog) -> StorageResult<()> {
         let mut logs = self.logs.write().await;
         logs.push(log.clone());
@@ -406,13 +1043,20 @@ impl SchedulerStorage for MemorySchedulerStorage {
     }
 
     async fn list_logs(&self, instance_id: Uuid) -> StorageResult<Vec<TaskLog>> {
+        Ok(self.query_logs(instance_id, &RangeQuery::default()).await?.items)
+    }
+
+    async fn query_logs(&self, instance_id: Uuid, query: &RangeQuery) -> StorageResult<Page<TaskLog>> {
         let logs = self.logs.read().await;
-        let mut result: Vec<TaskLog> = logs.iter()
-            .filter(|l| l.run_instance_id == instance_id)
-            .cloned()
+        let mut matched: Vec<&TaskLog> = logs
+            .iter()
+            .filter(|l| l.run_instance_id == instance_id && query.contains(&l.timestamp))
             .collect();
-        result.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-        Ok(result)
+        matched.sort_by_key(|l| l.timestamp);
+        if query.newest_first {
+            matched.reverse();
+        }
+        Ok(Page::from_sorted(matched.into_iter().cloned(), query))
     }
 
     async fn delete_logs(&self, instance_id: Uuid) -> StorageResult<()> {
@@ -427,6 +1071,80 @@ impl SchedulerStorage for MemorySchedulerStorage {
         logs.clear();
         Ok(count)
     }
+
+    async fn log_usage(&self) -> StorageResult<HashMap<Uuid, (usize, u64)>> {
+        let logs = self.logs.read().await;
+        let mut usage: HashMap<Uuid, (usize, u64)> = HashMap::new();
+        for log in logs.iter() {
+            let entry = usage.entry(log.run_instance_id).or_default();
+            entry.0 += 1;
+            entry.1 += serde_json::to_string(log).map(|v| v.len() as u64).unwrap_or(0);
+        }
+        Ok(usage)
+    }
+
+    async fn save_run_summary(&self, summary: &RunHistorySummary) -> StorageResult<()> {
+        self.summaries.write().await.insert(summary.task_id, summary.clone());
+        Ok(())
+    }
+
+    async fn load_run_summary(&self, task_id: Uuid) -> StorageResult<Option<RunHistorySummary>> {
+        Ok(self.summaries.read().await.get(&task_id).cloned())
+    }
+
+    async fn delete_run_summary(&self, task_id: Uuid) -> StorageResult<()> {
+        self.summaries.write().await.remove(&task_id);
+        Ok(())
+    }
+
+    async fn save_workflow(&self, workflow: &Workflow) -> StorageResult<()> {
+        let mut workflows = self.workflows.write().await;
+        workflows.insert(workflow.id, workflow.clone());
+        Ok(())
+    }
+
+    async fn load_workflow(&self, workflow_id: Uuid) -> StorageResult<Option<Workflow>> {
+        let workflows = self.workflows.read().await;
+        Ok(workflows.get(&workflow_id).cloned())
+    }
+
+    async fn delete_workflow(&self, workflow_id: Uuid) -> StorageResult<()> {
+        self.workflows.write().await.remove(&workflow_id);
+        self.workflow_runs
+            .write()
+            .await
+            .retain(|_, r| r.workflow_id != workflow_id);
+        Ok(())
+    }
+
+    async fn list_workflows(&self) -> StorageResult<Vec<Workflow>> {
+        let workflows = self.workflows.read().await;
+        Ok(workflows.values().cloned().collect())
+    }
+
+    async fn save_workflow_run(&self, run: &WorkflowRun) -> StorageResult<()> {
+        let mut runs = self.workflow_runs.write().await;
+        runs.insert(run.id, run.clone());
+        Ok(())
+    }
+
+    async fn load_workflow_run(&self, run_id: Uuid) -> StorageResult<Option<WorkflowRun>> {
+        let runs = self.workflow_runs.read().await;
+        Ok(runs.get(&run_id).cloned())
+    }
+
+    async fn list_workflow_runs(&self, workflow_id: Uuid) -> StorageResult<Vec<WorkflowRun>> {
+        let runs = self.workflow_runs.read().await;
+        Ok(runs.values().filter(|r| r.workflow_id == workflow_id).cloned().collect())
+    }
+
+    async fn clear_all_workflows(&self) -> StorageResult<usize> {
+        let mut workflows = self.workflows.write().await;
+        let count = workflows.len();
+        workflows.clear();
+        self.workflow_runs.write().await.clear();
+        Ok(count)
+    }
 }
 
 #[cfg(test)]
@@ -440,6 +1158,18 @@ mod tests {
         (temp_dir, storage)
     }
 
+    #[tokio::test]
+    async fn test_open_scheduler_storage() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = |backend| StorageConfig { backend, path: temp_dir.path().join("data") };
+
+        let storage = open_scheduler_storage(&config(StorageBackend::Sqlite)).unwrap();
+        assert!(storage.list_tasks().await.unwrap().is_empty());
+        assert!(temp_dir.path().join("data").join("scheduler.db").exists());
+        assert!(open_scheduler_storage(&config(StorageBackend::Memory)).is_ok());
+        assert!(open_scheduler_storage(&config(StorageBackend::Json)).is_err());
+    }
+
     #[tokio::test]
     async fn test_save_and_load_task() {
         let (_temp, storage) = create_temp_storage();
@@ -538,6 +1268,126 @@ mod tests {
         assert_eq!(logs.len(), 2);
     }
 
+    /// 在同一任务下按分钟间隔创建运行实例
+    fn instances_at(task_id: Uuid, minutes_ago: &[i64]) -> Vec<TaskRunInstance> {
+        minutes_ago
+            .iter()
+            .map(|m| {
+                let mut instance = TaskRunInstance::new(task_id, HashMap::new());
+                instance.started_at = Utc::now() - chrono::Duration::minutes(*m);
+                instance
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_query_run_instances_by_index() {
+        let (_temp, storage) = create_temp_storage();
+        let task_id = Uuid::new_v4();
+        let instances = instances_at(task_id, &[50, 40, 30, 20, 10]);
+        for instance in instances.iter().rev() {
+            storage.save_run_instance(instance).await.unwrap();
+        }
+        storage.save_run_instance(&TaskRunInstance::new(Uuid::new_v4(), HashMap::new())).await.unwrap();
+
+        let ids = |items: &[TaskRunInstance]| items.iter().map(|i| i.id).collect::<Vec<_>>();
+        let all = storage.list_run_instances(task_id).await.unwrap();
+        assert_eq!(ids(&all), ids(&instances));
+
+        let query = RangeQuery::default().newest_first().with_page(0, 2);
+        let page = storage.query_run_instances(task_id, &query).await.unwrap();
+        assert_eq!(ids(&page.items), vec![instances[4].id, instances[3].id]);
+        assert_eq!(page.next_offset, Some(2));
+        let page = storage.query_run_instances(task_id, &query.with_page(4, 2)).await.unwrap();
+        assert_eq!(ids(&page.items), vec![instances[0].id]);
+        assert_eq!(page.next_offset, None);
+
+        let query = RangeQuery::default()
+            .with_since(instances[1].started_at)
+            .with_until(instances[3].started_at);
+        let page = storage.query_run_instances(task_id, &query).await.unwrap();
+        assert_eq!(ids(&page.items), vec![instances[1].id, instances[2].id]);
+
+        // 修改开始时间后旧的索引项被替换
+        let mut moved = instances[0].clone();
+        moved.started_at = Utc::now();
+        storage.save_run_instance(&moved).await.unwrap();
+        let all = storage.list_run_instances(task_id).await.unwrap();
+        assert_eq!(all.len(), 5);
+        assert_eq!(all.last().unwrap().id, moved.id);
+
+        storage.delete_run_instance(moved.id).await.unwrap();
+        storage.delete_run_instances(task_id).await.unwrap();
+        assert!(storage.list_run_instances(task_id).await.unwrap().is_empty());
+        assert_eq!(storage.clear_all_instances().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_query_logs_by_index() {
+        let (_temp, storage) = create_temp_storage();
+        let instance_id = Uuid::new_v4();
+        for message in ["first", "second", "third"] {
+            storage
+                .save_log(&TaskLog::new(instance_id, LogLevel::Info, message.to_string()))
+                .await
+                .unwrap();
+        }
+        storage
+            .save_log(&TaskLog::new(Uuid::new_v4(), LogLevel::Info, "other".to_string()))
+            .await
+            .unwrap();
+
+        let page = storage
+            .query_logs(instance_id, &RangeQuery::default().newest_first().with_page(0, 1))
+            .await
+            .unwrap();
+        assert_eq!(page.items[0].message, "third");
+        assert_eq!(page.next_offset, Some(1));
+        assert_eq!(storage.delete_run_instance(instance_id).await.unwrap(), 3);
+        assert!(storage.list_logs(instance_id).await.unwrap().is_empty());
+        assert_eq!(storage.clear_all_logs().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_indexes_built_for_existing_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let task_id = Uuid::new_v4();
+        let instances = instances_at(task_id, &[20, 10]);
+        let log = TaskLog::new(instances[0].id, LogLevel::Info, "legacy".to_string());
+        {
+            // 模拟建立索引之前写入的数据库
+            let legacy = SledStorage::new(temp_dir.path().to_path_buf()).unwrap();
+            for instance in &instances {
+                let value = serde_json::to_string(instance).unwrap();
+                legacy.set(&format!("instance:{}", instance.id), &value).await.unwrap();
+            }
+            legacy
+                .set(&format!("log:{}", log.id), &serde_json::to_string(&log).unwrap())
+                .await
+                .unwrap();
+        }
+
+        let storage = SledSchedulerStorage::shared(temp_dir.path().to_path_buf());
+        let listed = storage.list_run_instances(task_id).await.unwrap();
+        assert_eq!(listed.iter().map(|i| i.id).collect::<Vec<_>>(), vec![instances[0].id, instances[1].id]);
+        assert_eq!(storage.list_logs(instances[0].id).await.unwrap()[0].message, "legacy");
+    }
+
+    #[tokio::test]
+    async fn test_memory_query_run_instances() {
+        let storage = MemorySchedulerStorage::new();
+        let task_id = Uuid::new_v4();
+        let instances = instances_at(task_id, &[30, 20, 10]);
+        for instance in instances.iter().rev() {
+            storage.save_run_instance(instance).await.unwrap();
+        }
+
+        let query = RangeQuery::default().with_since(instances[1].started_at).with_page(0, 1);
+        let page = storage.query_run_instances(task_id, &query).await.unwrap();
+        assert_eq!(page.items[0].id, instances[1].id);
+        assert_eq!(page.next_offset, Some(1));
+    }
+
     #[tokio::test]
     async fn test_memory_storage() {
         let storage = MemorySchedulerStorage::new();
@@ -658,4 +1508,27 @@ mod tests {
         let tasks = storage.list_tasks().await.unwrap();
         assert!(tasks.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_workflows() {
+        use crate::workflow::WorkflowStep;
+
+        let (_temp_dir, storage) = create_temp_storage();
+        let workflow = Workflow::new("etl", vec![WorkflowStep::new("fetch", Uuid::new_v4())]);
+        storage.save_workflow(&workflow).await.unwrap();
+        let run = WorkflowRun::new(&workflow, HashMap::new());
+        storage.save_workflow_run(&run).await.unwrap();
+
+        assert_eq!(storage.load_workflow(workflow.id).await.unwrap(), Some(workflow.clone()));
+        assert_eq!(storage.list_workflows().await.unwrap().len(), 1);
+        let runs = storage.list_workflow_runs(workflow.id).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].steps[0].name, "fetch");
+        assert!(storage.load_workflow_run(run.id).await.unwrap().is_some());
+
+        // 删除工作流同时删除运行记录
+        storage.delete_workflow(workflow.id).await.unwrap();
+        assert!(storage.load_workflow(workflow.id).await.unwrap().is_none());
+        assert!(storage.load_workflow_run(run.id).await.unwrap().is_none());
+    }
 }
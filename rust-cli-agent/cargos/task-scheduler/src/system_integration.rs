@@ -4,6 +4,7 @@
 //! 实现任务持久化与系统级调度的协同工作
 
 use std::path::PathBuf;
+use chrono::{Local, Offset, TimeZone, Utc};
 use uuid::Uuid;
 
 #[cfg(windows)]
@@ -11,8 +12,30 @@ use system_scheduler::WindowsSystemScheduler;
 use system_scheduler::{SystemScheduler, TaskSchedule};
 
 use crate::error::{Result, SchedulerError};
+use crate::timezone::ZonedSchedule;
 use crate::types::ScheduledTask;
 
+/// 任务在系统调度器中的触发计划
+///
+/// 系统调度器按本机时区触发，带时区的任务按下一次运行时两地的偏移换算触发时间；
+/// 任务时区与本机时区的夏令时规则不同时，切换后需要重新创建系统任务
+#[cfg_attr(not(windows), allow(dead_code))]
+fn system_schedule(task: &ScheduledTask) -> Result<TaskSchedule> {
+    let invalid = || SchedulerError::InvalidCronExpression(task.cron_expression.clone());
+    let zoned = ZonedSchedule::parse(&task.cron_expression, task.timezone.as_deref())?;
+    let at = zoned.next_after(Utc::now()).ok_or_else(invalid)?;
+    let task_offset = zoned.timezone().offset_from_utc_datetime(&at.naive_utc()).fix().local_minus_utc();
+    let local_offset = Local.offset_from_utc_datetime(&at.naive_utc()).local_minus_utc();
+    TaskSchedule::from_cron(&task.cron_expression).ok_or_else(invalid)?;
+    TaskSchedule::from_cron_with_offset(&task.cron_expression, (local_offset - task_offset) / 60).ok_or_else(|| {
+        SchedulerError::InvalidParameter(format!(
+            "Schedule {} in {} cannot be expressed in the system scheduler's local time",
+            task.cron_expression,
+            zoned.timezone().name()
+        ))
+    })
+}
+
 /// 系统任务管理器
 ///
 /// 负责管理 Windows 任务计划程序中的任务
@@ -89,8 +112,7 @@ impl SystemTaskManager {
             );
 
             // 转换 cron 表达式到 TaskSchedule
-            let schedule = TaskSchedule::from_cron(&task.cron_expression)
-                .ok_or_else(|| SchedulerError::InvalidCronExpression(task.cron_expression.clone()))?;
+            let schedule = system_schedule(task)?;
 
             tracing::info!(
                 "Creating system task: {} with command: {}",
@@ -0,0 +1,170 @@
+//! 任务时区
+//!
+//! cron 表达式按任务时区的墙上时间解释，未设置时区的任务按 UTC 调度。
+//! 夏令时切换当天的规则：
+//! - 跳过的时间 (如 02:00 → 03:00 切换日的 02:30) 按切换前的偏移换算，即顺延跳过的时长 (02:30 → 03:30)；
+//!   跳过区间内有多次计划运行时，与切换后时间重合的只运行一次
+//! - 重复的时间 (如 03:00 → 02:00 切换日的 02:30) 只在第一次出现时运行，回拨后重复的一小时内不再运行
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use cron_expression::CronSchedule;
+
+use crate::error::{Result, SchedulerError};
+
+/// 解析 IANA 时区名称，如 `Asia/Shanghai`
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    Tz::from_str(name.trim())
+        .map_err(|_| SchedulerError::InvalidParameter(format!("Unknown timezone: {}", name)))
+}
+
+/// 规范化任务时区：UTC 记为 None，其他时区返回规范名称
+pub fn normalize_timezone(name: &str) -> Result<Option<String>> {
+    let timezone = parse_timezone(name)?;
+    Ok((timezone != Tz::UTC).then(|| timezone.name().to_string()))
+}
+
+/// 带时区的 cron 计划
+#[derive(Debug, Clone)]
+pub struct ZonedSchedule {
+    schedule: CronSchedule,
+    timezone: Tz,
+}
+
+impl ZonedSchedule {
+    /// 解析 cron 表达式和可选的时区名称，时区为 None 时按 UTC
+    pub fn parse(cron_expression: &str, timezone: Option<&str>) -> Result<Self> {
+        let schedule = CronSchedule::parse(cron_expression).map_err(|e| {
+            SchedulerError::InvalidCronExpression(format!("{} ({})", cron_expression, e))
+        })?;
+        let timezone = timezone.map(parse_timezone).transpose()?.unwrap_or(Tz::UTC);
+        Ok(Self { schedule, timezone })
+    }
+
+    /// 解析后的 cron 表达式
+    pub fn schedule(&self) -> &CronSchedule {
+        &self.schedule
+    }
+
+    /// 计划所用的时区
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// 是否按 UTC 调度
+    pub fn is_utc(&self) -> bool {
+        self.timezone == Tz::UTC
+    }
+
+    /// since 之后 (不含) 的计划运行时间，按时间升序
+    pub fn after(&self, since: DateTime<Utc>) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        // 按墙上时间遍历，再逐个换算到 UTC，不会丢失夏令时切换当天的候选时间
+        let local = since.with_timezone(&self.timezone).naive_local();
+        let mut last = since;
+        self.schedule
+            .after(local)
+            .filter_map(move |candidate| {
+                let at = self.resolve(candidate);
+                // 跳过区间顺延后可能与切换后的计划时间重合，同一时刻只运行一次
+                (at > last).then(|| {
+                    last = at;
+                    at
+                })
+            })
+    }
+
+    /// since 之后的下一次计划运行时间
+    pub fn next_after(&self, since: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.after(since).next()
+    }
+
+    /// 将时区内的墙上时间换算为 UTC 时刻
+    fn resolve(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let resolved = self.timezone.from_local_datetime(&local);
+        if let Some(at) = resolved.earliest() {
+            return at.with_timezone(&Utc);
+        }
+        // 被夏令时跳过的时间按切换前的偏移换算，切换前后一天内不会再有第二次切换
+        let before = self
+            .timezone
+            .offset_from_utc_datetime(&(local - Duration::days(1)))
+            .fix();
+        Utc.from_utc_datetime(&(local - Duration::seconds(before.local_minus_utc() as i64)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn upcoming(cron: &str, timezone: &str, since: DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        let schedule = ZonedSchedule::parse(cron, Some(timezone)).unwrap();
+        schedule.after(since).take(count).collect()
+    }
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!(parse_timezone("Asia/Shanghai").unwrap(), Tz::Asia__Shanghai);
+        assert!(parse_timezone("Mars/Olympus").is_err());
+        assert_eq!(normalize_timezone("UTC").unwrap(), None);
+        assert_eq!(normalize_timezone(" Europe/Berlin ").unwrap().as_deref(), Some("Europe/Berlin"));
+        assert!(ZonedSchedule::parse("0 0 9 * * *", Some("Nowhere")).is_err());
+    }
+
+    #[test]
+    fn test_wall_clock_in_timezone() {
+        // 上海 09:00 为 UTC 01:00
+        let runs = upcoming("0 0 9 * * *", "Asia/Shanghai", utc(2024, 1, 1, 12, 0), 2);
+        assert_eq!(runs, vec![utc(2024, 1, 2, 1, 0), utc(2024, 1, 3, 1, 0)]);
+
+        // 未设置时区按 UTC
+        let schedule = ZonedSchedule::parse("0 0 9 * * *", None).unwrap();
+        assert!(schedule.is_utc());
+        assert_eq!(schedule.next_after(utc(2024, 1, 1, 12, 0)), Some(utc(2024, 1, 2, 9, 0)));
+    }
+
+    #[test]
+    fn test_skipped_hour_runs_shifted() {
+        // 柏林 2024-03-31 02:00 CET 跳到 03:00 CEST (UTC 01:00)
+        let runs = upcoming("0 30 2 * * *", "Europe/Berlin", utc(2024, 3, 30, 12, 0), 2);
+        // 02:30 不存在，顺延为 03:30 CEST；次日恢复 02:30 CEST
+        assert_eq!(runs, vec![utc(2024, 3, 31, 1, 30), utc(2024, 4, 1, 0, 30)]);
+
+        // 每 30 分钟的任务在切换前后不重复也不中断
+        let runs = upcoming("0 */30 * * * *", "Europe/Berlin", utc(2024, 3, 31, 0, 0), 4);
+        assert_eq!(
+            runs,
+            vec![
+                utc(2024, 3, 31, 0, 30),
+                utc(2024, 3, 31, 1, 0),
+                utc(2024, 3, 31, 1, 30),
+                utc(2024, 3, 31, 2, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeated_hour_runs_once() {
+        // 柏林 2024-10-27 03:00 CEST 回拨到 02:00 CET (UTC 01:00)
+        let runs = upcoming("0 30 2 * * *", "Europe/Berlin", utc(2024, 10, 26, 12, 0), 2);
+        // 02:30 出现两次，只在第一次 (CEST) 运行
+        assert_eq!(runs, vec![utc(2024, 10, 27, 0, 30), utc(2024, 10, 28, 1, 30)]);
+
+        // 每小时的任务不在回拨后重复的一小时内再次运行
+        let runs = upcoming("0 0 * * * *", "Europe/Berlin", utc(2024, 10, 26, 23, 30), 3);
+        assert_eq!(
+            runs,
+            vec![utc(2024, 10, 27, 0, 0), utc(2024, 10, 27, 2, 0), utc(2024, 10, 27, 3, 0)]
+        );
+
+        // 从重复时段的第二次出现开始计算时同样不回头
+        let schedule = ZonedSchedule::parse("0 30 2 * * *", Some("Europe/Berlin")).unwrap();
+        assert_eq!(schedule.next_after(utc(2024, 10, 27, 1, 0)), Some(utc(2024, 10, 28, 1, 30)));
+    }
+}
@@ -7,6 +7,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::action::TaskAction;
+use crate::concurrency::OverlapPolicy;
+use crate::misfire::MisfireConfig;
+use crate::retention::RunHistorySummary;
+use crate::retry::RetryPolicy;
+use crate::workflow::TaskDependency;
+
 /// 任务执行状态
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TaskStatus {
@@ -24,6 +31,8 @@ pub enum TaskStatus {
     Expired,
     /// 暂停
     Paused,
+    /// 跳过(依赖未满足)
+    Skipped,
 }
 
 impl Default for TaskStatus {
@@ -42,6 +51,7 @@ impl std::fmt::Display for TaskStatus {
             TaskStatus::Error => write!(f, "Error"),
             TaskStatus::Expired => write!(f, "Expired"),
             TaskStatus::Paused => write!(f, "Paused"),
+            TaskStatus::Skipped => write!(f, "Skipped"),
         }
     }
 }
@@ -72,6 +82,11 @@ impl std::fmt::Display for LogLevel {
     }
 }
 
+/// cron 表达式的语法版本
+///
+/// 0 为 cron 0.12 的语法 (星期 1-7 且 1 为周日，日和周字段须同时满足)，加载时改写为当前语法
+pub const CRON_VERSION: u32 = 1;
+
 /// 定时任务元数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledTask {
@@ -102,6 +117,33 @@ pub struct ScheduledTask {
     /// 是否为系统级任务 (Windows schtasks / macOS launchd / Linux cron)
     #[serde(default)]
     pub is_system: bool,
+    /// 任务动作，加载任务时据此重建执行器
+    #[serde(default)]
+    pub action: Option<TaskAction>,
+    /// 错过运行的处理策略和宽限期
+    #[serde(default)]
+    pub misfire: MisfireConfig,
+    /// 最近一次已处理 (运行或记为过期) 的计划运行时间，用于发现错过的运行
+    #[serde(default)]
+    pub last_scheduled_run: Option<DateTime<Utc>>,
+    /// 失败重试策略
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// 依赖的任务，定时触发时这些任务须在各自的时间窗口内成功运行过
+    #[serde(default)]
+    pub depends_on: Vec<TaskDependency>,
+    /// 上一次运行尚未结束时新运行的处理策略
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    /// 标签，用于按标签限制并发
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 解释 cron 表达式所用的 IANA 时区，未设置时按 UTC
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// cron 表达式的语法版本，早先保存的任务没有此字段，按旧版语法读取
+    #[serde(default)]
+    pub cron_version: u32,
 }
 
 impl ScheduledTask {
@@ -128,6 +170,15 @@ impl ScheduledTask {
             run_count: 0,
             enabled: true,
             is_system: false,
+            action: None,
+            misfire: MisfireConfig::default(),
+            last_scheduled_run: None,
+            retry: RetryPolicy::default(),
+            depends_on: Vec::new(),
+            overlap: OverlapPolicy::default(),
+            tags: Vec::new(),
+            timezone: None,
+            cron_version: CRON_VERSION,
         }
     }
 
@@ -154,6 +205,15 @@ impl ScheduledTask {
             run_count: 0,
             enabled: true,
             is_system: true,
+            action: None,
+            misfire: MisfireConfig::default(),
+            last_scheduled_run: None,
+            retry: RetryPolicy::default(),
+            depends_on: Vec::new(),
+            overlap: OverlapPolicy::default(),
+            tags: Vec::new(),
+            timezone: None,
+            cron_version: CRON_VERSION,
         }
     }
 }
@@ -179,6 +239,12 @@ pub struct TaskExecutionResult {
     pub stderr: Option<String>,
     /// 退出码
     pub exit_code: Option<i32>,
+    /// 进程被信号终止时的信号编号
+    #[serde(default)]
+    pub signal: Option<i32>,
+    /// 是否因超时被终止
+    #[serde(default)]
+    pub timed_out: bool,
 }
 
 impl TaskExecutionResult {
@@ -194,6 +260,8 @@ impl TaskExecutionResult {
             stdout: Some(stdout),
             stderr: Some(stderr),
             exit_code: Some(exit_code),
+            signal: None,
+            timed_out: false,
         }
     }
 
@@ -209,6 +277,8 @@ impl TaskExecutionResult {
             stdout: None,
             stderr: None,
             exit_code: None,
+            signal: None,
+            timed_out: false,
         }
     }
 
@@ -240,6 +310,19 @@ pub struct TaskRunInstance {
     pub completed_at: Option<DateTime<Utc>>,
     /// 执行结果
     pub result: Option<TaskExecutionResult>,
+    /// 执行器启动的子进程 ID (进程组 ID)，用于停止任务
+    #[serde(default)]
+    pub pid: Option<u32>,
+    /// 第几次尝试 (从 1 开始)
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// 重试实例所属逻辑运行的首次尝试实例 ID，首次尝试为 None
+    #[serde(default)]
+    pub logical_run_id: Option<Uuid>,
+}
+
+fn first_attempt() -> u32 {
+    1
 }
 
 impl TaskRunInstance {
@@ -253,9 +336,25 @@ impl TaskRunInstance {
             started_at: Utc::now(),
             completed_at: None,
             result: None,
+            pid: None,
+            attempt: 1,
+            logical_run_id: None,
         }
     }
 
+    /// 创建重试实例，归入 previous 所属的逻辑运行
+    pub fn retry_of(previous: &TaskRunInstance) -> Self {
+        let mut instance = Self::new(previous.task_id, previous.user_params.clone());
+        instance.attempt = previous.attempt + 1;
+        instance.logical_run_id = Some(previous.logical_id());
+        instance
+    }
+
+    /// 所属逻辑运行的 ID (首次尝试的实例 ID)
+    pub fn logical_id(&self) -> Uuid {
+        self.logical_run_id.unwrap_or(self.id)
+    }
+
     /// 标记为运行中
     pub fn mark_running(&mut self) {
         self.status = TaskStatus::Running;
@@ -279,6 +378,42 @@ impl TaskRunInstance {
         self.result = Some(TaskExecutionResult::failure(self.task_id, error));
     }
 
+    /// 创建错过计划时间而未运行的实例
+    pub fn expired(task_id: Uuid, scheduled_at: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            task_id,
+            user_params: HashMap::new(),
+            status: TaskStatus::Expired,
+            started_at: scheduled_at,
+            completed_at: Some(Utc::now()),
+            result: Some(TaskExecutionResult::failure(
+                task_id,
+                format!("Missed scheduled run at {}", scheduled_at),
+            )),
+            pid: None,
+            attempt: 1,
+            logical_run_id: None,
+        }
+    }
+
+    /// 创建被跳过的实例 (依赖未满足或上一次运行尚未结束)
+    pub fn skipped(task_id: Uuid, reason: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            task_id,
+            user_params: HashMap::new(),
+            status: TaskStatus::Skipped,
+            started_at: now,
+            completed_at: Some(now),
+            result: Some(TaskExecutionResult::failure(task_id, reason)),
+            pid: None,
+            attempt: 1,
+            logical_run_id: None,
+        }
+    }
+
     /// 计算执行时长(毫秒)
     pub fn duration_ms(&self) -> Option<i64> {
         self.completed_at.map(|completed| {
@@ -352,6 +487,9 @@ pub struct TaskBriefing {
     pub status: TaskStatus,
     /// Cron 表达式
     pub cron_expression: String,
+    /// 任务时区，未设置时按 UTC
+    #[serde(default)]
+    pub timezone: Option<String>,
     /// 创建时间
     pub created_at: DateTime<Utc>,
     /// 上次运行
@@ -364,13 +502,54 @@ pub struct TaskBriefing {
     pub enabled: bool,
     /// 是否为系统级任务
     pub is_system: bool,
+    /// 错过运行的处理策略
+    #[serde(default)]
+    pub misfire: MisfireConfig,
+    /// 因错过计划时间而过期的运行次数
+    #[serde(default)]
+    pub expired_count: usize,
+    /// 失败重试策略
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// 最近一次逻辑运行已进行的尝试次数
+    #[serde(default)]
+    pub latest_attempts: u32,
+    /// 依赖的任务
+    #[serde(default)]
+    pub depends_on: Vec<TaskDependency>,
+    /// 因依赖未满足或上一次运行尚未结束而跳过的运行次数
+    #[serde(default)]
+    pub skipped_count: usize,
+    /// 重叠策略
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    /// 标签
+    #[serde(default)]
+    pub tags: Vec<String>,
     /// 最近运行实例
     pub recent_instances: Vec<RunInstanceSummary>,
+    /// 已按保留策略清理的运行汇总
+    #[serde(default)]
+    pub pruned: Option<RunHistorySummary>,
 }
 
 impl TaskBriefing {
     /// 从任务创建简报
-    pub fn from_task(task: &ScheduledTask, instances: Vec<TaskRunInstance>) -> Self {
+    pub fn from_task(task: &ScheduledTask, mut instances: Vec<TaskRunInstance>) -> Self {
+        let expired_count = instances
+            .iter()
+            .filter(|i| i.status == TaskStatus::Expired)
+            .count();
+        let skipped_count = instances
+            .iter()
+            .filter(|i| i.status == TaskStatus::Skipped)
+            .count();
+        instances.sort_by_key(|i| std::cmp::Reverse(i.started_at));
+        let latest_attempts = instances
+            .iter()
+            .find(|i| !matches!(i.status, TaskStatus::Expired | TaskStatus::Skipped))
+            .map(|i| i.attempt)
+            .unwrap_or(0);
         let recent_instances: Vec<RunInstanceSummary> = instances
             .into_iter()
             .take(5)
@@ -384,15 +563,35 @@ impl TaskBriefing {
             description: task.description.clone(),
             status: task.status.clone(),
             cron_expression: task.cron_expression.clone(),
+            timezone: task.timezone.clone(),
             created_at: task.created_at,
             last_run: task.last_run,
             next_run: task.next_run,
             run_count: task.run_count,
             enabled: task.enabled,
             is_system: task.is_system,
+            misfire: task.misfire,
+            expired_count,
+            retry: task.retry,
+            latest_attempts,
+            depends_on: task.depends_on.clone(),
+            skipped_count,
+            overlap: task.overlap,
+            tags: task.tags.clone(),
             recent_instances,
+            pruned: None,
         }
     }
+
+    /// 计入已清理运行的汇总，过期和跳过次数包含已清理的运行
+    pub fn with_history(mut self, summary: Option<RunHistorySummary>) -> Self {
+        if let Some(summary) = &summary {
+            self.expired_count += summary.expired as usize;
+            self.skipped_count += summary.skipped as usize;
+        }
+        self.pruned = summary;
+        self
+    }
 }
 
 /// 运行实例摘要
@@ -410,12 +609,19 @@ pub struct RunInstanceSummary {
     pub success: bool,
     /// 执行时长(毫秒)
     pub duration_ms: Option<i64>,
+    /// 第几次尝试
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    /// 所属逻辑运行的 ID
+    #[serde(default)]
+    pub logical_run_id: Option<Uuid>,
 }
 
 impl From<TaskRunInstance> for RunInstanceSummary {
     fn from(instance: TaskRunInstance) -> Self {
         let duration = instance.duration_ms();
         let success = instance.result.as_ref().map(|r| r.success).unwrap_or(false);
+        let logical_run_id = Some(instance.logical_id());
         Self {
             id: instance.id,
             status: instance.status,
@@ -423,6 +629,8 @@ impl From<TaskRunInstance> for RunInstanceSummary {
             completed_at: instance.completed_at,
             success,
             duration_ms: duration,
+            attempt: instance.attempt,
+            logical_run_id,
         }
     }
 }
@@ -444,7 +652,7 @@ impl FunctionCallArgs {
 }
 
 /// 任务更新请求
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TaskUpdateRequest {
     /// 任务 ID
     pub id: Uuid,
@@ -458,9 +666,46 @@ pub struct TaskUpdateRequest {
     pub cron_expression: Option<String>,
     /// 是否启用
     pub enabled: Option<bool>,
+    /// 错过运行的处理策略
+    #[serde(default)]
+    pub misfire: Option<MisfireConfig>,
+    /// 失败重试策略
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+    /// 依赖的任务 (整体替换)
+    #[serde(default)]
+    pub depends_on: Option<Vec<TaskDependency>>,
+    /// 重叠策略
+    #[serde(default)]
+    pub overlap: Option<OverlapPolicy>,
+    /// 标签 (整体替换)
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// 任务动作，执行器随之重建
+    #[serde(default)]
+    pub action: Option<TaskAction>,
+    /// IANA 时区，设为 UTC 时清除
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
 impl TaskUpdateRequest {
+    /// 除 ID 外没有任何需要更新的字段
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.content.is_none()
+            && self.cron_expression.is_none()
+            && self.enabled.is_none()
+            && self.misfire.is_none()
+            && self.retry.is_none()
+            && self.depends_on.is_none()
+            && self.overlap.is_none()
+            && self.tags.is_none()
+            && self.action.is_none()
+            && self.timezone.is_none()
+    }
+
     /// 验证请求
     pub fn validate(&self) -> Result<(), &'static str> {
         if self.title.as_ref().map(|s| s.is_empty()).unwrap_or(false) {
@@ -485,6 +730,7 @@ mod tests {
         assert_eq!(TaskStatus::Failed.to_string(), "Failed");
         assert_eq!(TaskStatus::Error.to_string(), "Error");
         assert_eq!(TaskStatus::Expired.to_string(), "Expired");
+        assert_eq!(TaskStatus::Skipped.to_string(), "Skipped");
         assert_eq!(TaskStatus::Paused.to_string(), "Paused");
     }
 
@@ -596,6 +842,7 @@ mod tests {
             content: None,
             cron_expression: None,
             enabled: None,
+            ..Default::default()
         };
         assert!(req.validate().is_ok());
 
@@ -606,6 +853,7 @@ mod tests {
             content: None,
             cron_expression: None,
             enabled: None,
+            ..Default::default()
         };
         assert!(req_empty_title.validate().is_err());
     }
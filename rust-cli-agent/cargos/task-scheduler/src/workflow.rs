@@ -0,0 +1,646 @@
+//! 任务依赖和工作流
+//!
+//! 任务可以声明依赖其他任务在一段时间窗口内的成功运行，依赖未满足时定时触发被跳过。
+//! 工作流把多个任务组织成有向无环图，没有依赖关系的步骤并行执行，
+//! 前序步骤的输出通过 user_params 传给直接依赖它的步骤
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{Result, SchedulerError};
+use crate::types::{TaskRunInstance, TaskStatus, CRON_VERSION};
+
+/// 依赖的默认时间窗口 (秒)
+pub const DEFAULT_DEPENDENCY_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// 传给后续步骤的输出的最大字节数
+pub const MAX_OUTPUT_BYTES: usize = 32 * 1024;
+
+/// 步骤输出参数名的后缀，步骤 `fetch` 的输出以 `fetch_output` 传给后续步骤
+pub const OUTPUT_PARAM_SUFFIX: &str = "_output";
+
+fn default_window_secs() -> u64 {
+    DEFAULT_DEPENDENCY_WINDOW_SECS
+}
+
+/// 任务依赖
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskDependency {
+    /// 依赖的任务 ID
+    pub task_id: Uuid,
+    /// 依赖任务须在此时间窗口 (秒) 内成功运行过
+    #[serde(default = "default_window_secs")]
+    pub within_secs: u64,
+}
+
+impl TaskDependency {
+    /// 使用默认时间窗口创建依赖
+    pub fn new(task_id: Uuid) -> Self {
+        Self {
+            task_id,
+            within_secs: DEFAULT_DEPENDENCY_WINDOW_SECS,
+        }
+    }
+
+    /// 依赖任务的运行实例中是否有 now 之前时间窗口内的成功运行
+    pub fn is_satisfied_by(&self, instances: &[TaskRunInstance], now: DateTime<Utc>) -> bool {
+        let window = chrono::Duration::seconds(i64::try_from(self.within_secs).unwrap_or(i64::MAX));
+        let since = now.checked_sub_signed(window).unwrap_or(DateTime::<Utc>::MIN_UTC);
+        instances.iter().any(|i| {
+            i.task_id == self.task_id
+                && i.status == TaskStatus::Completed
+                && i.completed_at.is_some_and(|at| at >= since && at <= now)
+        })
+    }
+}
+
+impl std::fmt::Display for TaskDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.task_id, self.within_secs)
+    }
+}
+
+impl FromStr for TaskDependency {
+    type Err = SchedulerError;
+
+    /// 解析 `<任务 ID>[:<窗口秒数>]`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            SchedulerError::InvalidParameter(format!(
+                "Invalid dependency: {} (expected <task-id>[:<window-secs>])",
+                s
+            ))
+        };
+        let (id, window) = match s.trim().split_once(':') {
+            Some((id, window)) => (id, Some(window)),
+            None => (s.trim(), None),
+        };
+        let task_id = Uuid::parse_str(id.trim()).map_err(|_| invalid())?;
+        let within_secs = match window {
+            Some(w) => w.trim().parse::<u64>().map_err(|_| invalid())?,
+            None => DEFAULT_DEPENDENCY_WINDOW_SECS,
+        };
+        Ok(Self { task_id, within_secs })
+    }
+}
+
+/// 工作流步骤
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    /// 步骤名称，在工作流内唯一，只能包含字母、数字和下划线
+    pub name: String,
+    /// 执行的任务 ID
+    pub task_id: Uuid,
+    /// 前序步骤名称，全部成功后才执行本步骤
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// 本步骤额外的用户参数
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+}
+
+impl WorkflowStep {
+    /// 创建步骤
+    pub fn new(name: impl Into<String>, task_id: Uuid) -> Self {
+        Self {
+            name: name.into(),
+            task_id,
+            depends_on: Vec::new(),
+            params: HashMap::new(),
+        }
+    }
+
+    /// 设置前序步骤
+    pub fn after(mut self, steps: &[&str]) -> Self {
+        self.depends_on = steps.iter().map(|s| s.to_string()).collect();
+        self
+    }
+}
+
+/// 工作流定义
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workflow {
+    /// 工作流唯一标识，从定义文件读取时可省略
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
+    /// 工作流名称
+    pub name: String,
+    /// 描述
+    #[serde(default)]
+    pub description: Option<String>,
+    /// 定时触发的 Cron 表达式，为空时只能手动运行
+    #[serde(default)]
+    pub cron_expression: Option<String>,
+    /// 解释 cron 表达式所用的 IANA 时区，新建时未填写则使用默认时区，UTC 记为 None
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// cron 表达式的语法版本，早先保存的工作流没有此字段，按旧版语法读取
+    #[serde(default)]
+    pub cron_version: u32,
+    /// 步骤
+    pub steps: Vec<WorkflowStep>,
+    /// 是否启用定时触发
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// 创建时间
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl Workflow {
+    /// 创建工作流
+    pub fn new(name: impl Into<String>, steps: Vec<WorkflowStep>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            description: None,
+            cron_expression: None,
+            timezone: None,
+            cron_version: CRON_VERSION,
+            steps,
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// 按名称查找步骤
+    pub fn step(&self, name: &str) -> Option<&WorkflowStep> {
+        self.steps.iter().find(|s| s.name == name)
+    }
+
+    /// 检查步骤名称、依赖引用，并确认步骤之间没有环
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(SchedulerError::InvalidParameter(
+                "Workflow name cannot be empty".to_string(),
+            ));
+        }
+        if self.steps.is_empty() {
+            return Err(SchedulerError::InvalidParameter(format!(
+                "Workflow {} has no steps",
+                self.name
+            )));
+        }
+
+        let mut names = HashSet::new();
+        for step in &self.steps {
+            let valid = !step.name.is_empty()
+                && step.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(SchedulerError::InvalidParameter(format!(
+                    "Invalid step name: {:?} (use letters, digits and underscores)",
+                    step.name
+                )));
+            }
+            if !names.insert(step.name.as_str()) {
+                return Err(SchedulerError::InvalidParameter(format!(
+                    "Duplicate step name: {}",
+                    step.name
+                )));
+            }
+        }
+        for step in &self.steps {
+            for dep in &step.depends_on {
+                if !names.contains(dep.as_str()) {
+                    return Err(SchedulerError::InvalidParameter(format!(
+                        "Step {} depends on unknown step {}",
+                        step.name, dep
+                    )));
+                }
+            }
+        }
+
+        // Kahn 算法：无法排序的步骤在环上
+        let mut indegree: HashMap<&str, usize> = self
+            .steps
+            .iter()
+            .map(|s| (s.name.as_str(), s.depends_on.len()))
+            .collect();
+        let mut ready: VecDeque<&str> = indegree
+            .iter()
+            .filter(|(_, n)| **n == 0)
+            .map(|(name, _)| *name)
+            .collect();
+        let mut sorted = 0;
+        while let Some(name) = ready.pop_front() {
+            sorted += 1;
+            for step in &self.steps {
+                let edges = step.depends_on.iter().filter(|d| *d == name).count();
+                if edges == 0 {
+                    continue;
+                }
+                let n = indegree.get_mut(step.name.as_str()).expect("step indexed above");
+                *n -= edges;
+                if *n == 0 {
+                    ready.push_back(step.name.as_str());
+                }
+            }
+        }
+        if sorted != self.steps.len() {
+            let mut cyclic: Vec<&str> = indegree
+                .into_iter()
+                .filter(|(_, n)| *n > 0)
+                .map(|(name, _)| name)
+                .collect();
+            cyclic.sort_unstable();
+            return Err(SchedulerError::InvalidParameter(format!(
+                "Workflow {} has a dependency cycle between steps: {}",
+                self.name,
+                cyclic.join(", ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 工作流运行中单个步骤的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRun {
+    /// 步骤名称
+    pub name: String,
+    /// 执行的任务 ID
+    pub task_id: Uuid,
+    /// 步骤状态 (Pending/Running/Completed/Failed/Error/Skipped)
+    pub status: TaskStatus,
+    /// 最后一次尝试的运行实例 ID
+    #[serde(default)]
+    pub run_instance_id: Option<Uuid>,
+    /// 尝试次数
+    #[serde(default)]
+    pub attempts: u32,
+    /// 开始时间
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    /// 结束时间
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// 传给后续步骤的输出 (标准输出，去除首尾空白并截断)
+    #[serde(default)]
+    pub output: Option<String>,
+    /// 错误信息
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl StepRun {
+    /// 步骤是否已结束
+    pub fn is_finished(&self) -> bool {
+        !matches!(self.status, TaskStatus::Pending | TaskStatus::Running)
+    }
+}
+
+/// 工作流运行实例
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowRun {
+    /// 运行实例唯一标识
+    pub id: Uuid,
+    /// 工作流 ID
+    pub workflow_id: Uuid,
+    /// 工作流名称
+    pub workflow_name: String,
+    /// 运行参数，传给每个步骤
+    #[serde(default)]
+    pub user_params: HashMap<String, String>,
+    /// 运行状态
+    pub status: TaskStatus,
+    /// 开始时间
+    pub started_at: DateTime<Utc>,
+    /// 结束时间
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// 各步骤状态，顺序与工作流定义一致
+    pub steps: Vec<StepRun>,
+}
+
+impl WorkflowRun {
+    /// 创建运行实例，所有步骤处于等待状态
+    pub fn new(workflow: &Workflow, user_params: HashMap<String, String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            workflow_id: workflow.id,
+            workflow_name: workflow.name.clone(),
+            user_params,
+            status: TaskStatus::Pending,
+            started_at: Utc::now(),
+            completed_at: None,
+            steps: workflow
+                .steps
+                .iter()
+                .map(|s| StepRun {
+                    name: s.name.clone(),
+                    task_id: s.task_id,
+                    status: TaskStatus::Pending,
+                    run_instance_id: None,
+                    attempts: 0,
+                    started_at: None,
+                    completed_at: None,
+                    output: None,
+                    error: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// 按名称查找步骤状态
+    pub fn step(&self, name: &str) -> Option<&StepRun> {
+        self.steps.iter().find(|s| s.name == name)
+    }
+
+    fn step_mut(&mut self, name: &str) -> Option<&mut StepRun> {
+        self.steps.iter_mut().find(|s| s.name == name)
+    }
+
+    /// 前序步骤未全部成功的等待步骤标记为跳过，直到没有新的跳过
+    pub fn skip_blocked(&mut self, workflow: &Workflow) {
+        loop {
+            let blocked: Vec<(String, String)> = workflow
+                .steps
+                .iter()
+                .filter(|s| self.step(&s.name).is_some_and(|r| r.status == TaskStatus::Pending))
+                .filter_map(|s| {
+                    s.depends_on
+                        .iter()
+                        .find(|d| {
+                            self.step(d)
+                                .is_some_and(|r| r.is_finished() && r.status != TaskStatus::Completed)
+                        })
+                        .map(|d| (s.name.clone(), d.clone()))
+                })
+                .collect();
+            if blocked.is_empty() {
+                return;
+            }
+            let now = Utc::now();
+            for (name, dep) in blocked {
+                if let Some(step) = self.step_mut(&name) {
+                    step.status = TaskStatus::Skipped;
+                    step.completed_at = Some(now);
+                    step.error = Some(format!("Upstream step {} did not succeed", dep));
+                }
+            }
+        }
+    }
+
+    /// 前序步骤全部成功、可以开始的等待步骤
+    pub fn ready_steps(&self, workflow: &Workflow) -> Vec<String> {
+        workflow
+            .steps
+            .iter()
+            .filter(|s| self.step(&s.name).is_some_and(|r| r.status == TaskStatus::Pending))
+            .filter(|s| {
+                s.depends_on.iter().all(|d| {
+                    self.step(d).is_some_and(|r| r.status == TaskStatus::Completed)
+                })
+            })
+            .map(|s| s.name.clone())
+            .collect()
+    }
+
+    /// 步骤的用户参数：运行参数、步骤参数，再加上直接前序步骤的输出
+    pub fn step_params(&self, workflow: &Workflow, name: &str) -> HashMap<String, String> {
+        let mut params = self.user_params.clone();
+        if let Some(step) = workflow.step(name) {
+            params.extend(step.params.clone());
+            for dep in &step.depends_on {
+                if let Some(output) = self.step(dep).and_then(|r| r.output.clone()) {
+                    params.insert(format!("{}{}", dep, OUTPUT_PARAM_SUFFIX), output);
+                }
+            }
+        }
+        params
+    }
+
+    /// 标记步骤开始
+    pub fn start_step(&mut self, name: &str) {
+        if let Some(step) = self.step_mut(name) {
+            step.status = TaskStatus::Running;
+            step.started_at = Some(Utc::now());
+        }
+    }
+
+    /// 记录步骤的执行结果
+    pub fn finish_step(&mut self, name: &str, result: Result<TaskRunInstance>) {
+        let Some(step) = self.step_mut(name) else {
+            return;
+        };
+        step.completed_at = Some(Utc::now());
+        match result {
+            Ok(instance) => {
+                step.status = instance.status.clone();
+                step.run_instance_id = Some(instance.id);
+                step.attempts = instance.attempt;
+                if let Some(result) = instance.result {
+                    if instance.status == TaskStatus::Completed {
+                        step.output = result.stdout.as_deref().map(truncate_output);
+                    }
+                    step.error = result.error;
+                }
+            }
+            Err(e) => {
+                step.status = TaskStatus::Error;
+                step.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// 所有步骤结束后汇总运行状态：全部成功为 Completed，否则为 Failed
+    pub fn finish(&mut self) {
+        self.status = if self.steps.iter().all(|s| s.status == TaskStatus::Completed) {
+            TaskStatus::Completed
+        } else {
+            TaskStatus::Failed
+        };
+        self.completed_at = Some(Utc::now());
+    }
+}
+
+/// 去除首尾空白并按字符边界截断到 MAX_OUTPUT_BYTES
+fn truncate_output(output: &str) -> String {
+    let output = output.trim();
+    if output.len() <= MAX_OUTPUT_BYTES {
+        return output.to_string();
+    }
+    let mut end = MAX_OUTPUT_BYTES;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    output[..end].to_string()
+}
+
+/// 工作流简报
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowBriefing {
+    /// 工作流定义
+    pub workflow: Workflow,
+    /// 运行次数
+    pub run_count: usize,
+    /// 最近的运行 (含各步骤状态)，按开始时间倒序
+    pub recent_runs: Vec<WorkflowRun>,
+}
+
+impl WorkflowBriefing {
+    /// 从工作流和运行记录创建简报
+    pub fn from_workflow(workflow: &Workflow, mut runs: Vec<WorkflowRun>) -> Self {
+        let run_count = runs.len();
+        runs.sort_by_key(|r| std::cmp::Reverse(r.started_at));
+        runs.truncate(5);
+        Self {
+            workflow: workflow.clone(),
+            run_count,
+            recent_runs: runs,
+        }
+    }
+
+    /// 最近一次运行
+    pub fn latest_run(&self) -> Option<&WorkflowRun> {
+        self.recent_runs.first()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TaskExecutionResult;
+
+    fn diamond() -> Workflow {
+        Workflow::new(
+            "etl",
+            vec![
+                WorkflowStep::new("fetch", Uuid::new_v4()),
+                WorkflowStep::new("transform_a", Uuid::new_v4()).after(&["fetch"]),
+                WorkflowStep::new("transform_b", Uuid::new_v4()).after(&["fetch"]),
+                WorkflowStep::new("publish", Uuid::new_v4()).after(&["transform_a", "transform_b"]),
+            ],
+        )
+    }
+
+    fn finished(task_id: Uuid, success: bool, stdout: &str) -> TaskRunInstance {
+        let mut instance = TaskRunInstance::new(task_id, HashMap::new());
+        let mut result =
+            TaskExecutionResult::success(task_id, stdout.to_string(), String::new(), if success { 0 } else { 1 });
+        result.run_instance_id = Some(instance.id);
+        instance.mark_completed(result);
+        instance
+    }
+
+    #[test]
+    fn test_dependency_parse() {
+        let id = Uuid::new_v4();
+        let dep: TaskDependency = id.to_string().parse().unwrap();
+        assert_eq!(dep, TaskDependency::new(id));
+
+        let dep: TaskDependency = format!("{}:3600", id).parse().unwrap();
+        assert_eq!(dep.within_secs, 3600);
+        assert_eq!(dep.to_string().parse::<TaskDependency>().unwrap(), dep);
+
+        assert!("not-a-uuid".parse::<TaskDependency>().is_err());
+        assert!(format!("{}:soon", id).parse::<TaskDependency>().is_err());
+    }
+
+    #[test]
+    fn test_dependency_window() {
+        let task_id = Uuid::new_v4();
+        let dep = TaskDependency { task_id, within_secs: 60 };
+        let now = Utc::now();
+
+        let mut recent = finished(task_id, true, "");
+        recent.completed_at = Some(now - chrono::Duration::seconds(30));
+        let mut old = recent.clone();
+        old.completed_at = Some(now - chrono::Duration::seconds(120));
+        let failed = finished(task_id, false, "");
+
+        assert!(dep.is_satisfied_by(&[recent], now));
+        assert!(!dep.is_satisfied_by(&[old, failed], now));
+        assert!(!dep.is_satisfied_by(&[], now));
+    }
+
+    #[test]
+    fn test_validate_workflow() {
+        assert!(diamond().validate().is_ok());
+
+        let mut empty = diamond();
+        empty.steps.clear();
+        assert!(empty.validate().is_err());
+
+        let mut duplicate = diamond();
+        duplicate.steps[1].name = "fetch".to_string();
+        assert!(duplicate.validate().unwrap_err().to_string().contains("Duplicate"));
+
+        let mut bad_name = diamond();
+        bad_name.steps[0].name = "fetch-data".to_string();
+        assert!(bad_name.validate().is_err());
+
+        let mut unknown = diamond();
+        unknown.steps[3].depends_on.push("missing".to_string());
+        assert!(unknown.validate().unwrap_err().to_string().contains("unknown step"));
+
+        let mut cyclic = diamond();
+        cyclic.steps[0].depends_on.push("publish".to_string());
+        let error = cyclic.validate().unwrap_err().to_string();
+        assert!(error.contains("cycle"));
+        assert!(error.contains("fetch"));
+    }
+
+    #[test]
+    fn test_run_progression_passes_outputs() {
+        let workflow = diamond();
+        let mut run = WorkflowRun::new(&workflow, HashMap::from([("env".to_string(), "prod".to_string())]));
+        assert_eq!(run.ready_steps(&workflow), vec!["fetch"]);
+
+        run.start_step("fetch");
+        assert!(run.ready_steps(&workflow).is_empty());
+        run.finish_step("fetch", Ok(finished(workflow.steps[0].task_id, true, "  rows=3\n")));
+
+        let ready = run.ready_steps(&workflow);
+        assert_eq!(ready, vec!["transform_a", "transform_b"]);
+        let params = run.step_params(&workflow, "transform_a");
+        assert_eq!(params.get("fetch_output").map(String::as_str), Some("rows=3"));
+        assert_eq!(params.get("env").map(String::as_str), Some("prod"));
+
+        run.finish_step("transform_a", Ok(finished(workflow.steps[1].task_id, true, "a")));
+        run.finish_step("transform_b", Ok(finished(workflow.steps[2].task_id, true, "b")));
+        let params = run.step_params(&workflow, "publish");
+        assert_eq!(params.get("transform_a_output").map(String::as_str), Some("a"));
+        assert_eq!(params.get("transform_b_output").map(String::as_str), Some("b"));
+        // 只传直接前序步骤的输出
+        assert!(!params.contains_key("fetch_output"));
+
+        run.finish_step("publish", Ok(finished(workflow.steps[3].task_id, true, "")));
+        run.finish();
+        assert_eq!(run.status, TaskStatus::Completed);
+    }
+
+    #[test]
+    fn test_failed_step_skips_downstream() {
+        let workflow = diamond();
+        let mut run = WorkflowRun::new(&workflow, HashMap::new());
+        run.finish_step("fetch", Ok(finished(workflow.steps[0].task_id, true, "")));
+        run.finish_step("transform_a", Ok(finished(workflow.steps[1].task_id, false, "")));
+        run.skip_blocked(&workflow);
+
+        assert_eq!(run.step("publish").unwrap().status, TaskStatus::Skipped);
+        assert_eq!(run.step("transform_b").unwrap().status, TaskStatus::Pending);
+        assert_eq!(run.ready_steps(&workflow), vec!["transform_b"]);
+
+        run.finish_step("transform_b", Err(SchedulerError::ExecutionError("boom".to_string())));
+        run.finish();
+        assert_eq!(run.step("transform_b").unwrap().status, TaskStatus::Error);
+        assert_eq!(run.status, TaskStatus::Failed);
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        let long = "é".repeat(MAX_OUTPUT_BYTES);
+        let truncated = truncate_output(&long);
+        assert!(truncated.len() <= MAX_OUTPUT_BYTES);
+        assert!(truncated.chars().all(|c| c == 'é'));
+    }
+}
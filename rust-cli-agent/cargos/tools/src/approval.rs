@@ -0,0 +1,268 @@
+//! 人工确认
+//!
+//! 需要确认的调用在目录中登记为待确认请求，用户在另一个终端批准或拒绝后，
+//! 调用方用相同的工具和参数重试即可执行。每次批准只对一次调用有效
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{CallToolRequest, ToolError, ToolResult};
+
+/// 待确认请求默认的有效期
+const DEFAULT_TTL_HOURS: i64 = 24;
+
+/// 确认状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// 一条确认请求
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    /// 由工具名称和参数计算，相同的调用得到相同的 ID
+    pub id: String,
+    pub tool: String,
+    pub arguments: Value,
+    pub reason: String,
+    pub status: ApprovalStatus,
+    pub requested_at: DateTime<Utc>,
+}
+
+/// 基于目录的确认存储，每个请求一个 JSON 文件
+#[derive(Debug, Clone)]
+pub struct ApprovalStore {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl ApprovalStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: Duration::hours(DEFAULT_TTL_HOURS),
+        }
+    }
+
+    /// 设置请求的有效期，过期的请求 (包括已批准的) 视为不存在
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 调用对应的确认 ID
+    pub fn request_id(request: &CallToolRequest) -> String {
+        // FNV-1a，serde_json 的对象按键排序，相同参数得到相同文本
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let text = format!("{}\0{}", request.name, request.arguments);
+        for byte in text.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("{:016x}", hash)
+    }
+
+    fn path(&self, id: &str) -> ToolResult<PathBuf> {
+        // ID 来自命令行，只接受 request_id 的格式，避免拼出目录之外的路径
+        if id.len() != 16 || !id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+            return Err(ToolError::InvalidArguments(format!("invalid approval id: {}", id)));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    fn load(&self, id: &str) -> ToolResult<Option<ApprovalRequest>> {
+        let path = self.path(id)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read_to_string(&path).map_err(store_error)?;
+        let approval: ApprovalRequest = serde_json::from_str(&data).map_err(store_error)?;
+        if Utc::now() - approval.requested_at > self.ttl {
+            fs::remove_file(&path).map_err(store_error)?;
+            return Ok(None);
+        }
+        Ok(Some(approval))
+    }
+
+    fn save(&self, approval: &ApprovalRequest) -> ToolResult<()> {
+        let path = self.path(&approval.id)?;
+        fs::create_dir_all(&self.dir).map_err(store_error)?;
+        let data = serde_json::to_string_pretty(approval).map_err(store_error)?;
+        fs::write(path, data).map_err(store_error)
+    }
+
+    /// 检查调用的确认状态
+    ///
+    /// 没有请求时登记一条待确认请求；已批准或已拒绝的请求在返回后删除。
+    /// ID 只是散列值，登记的工具和参数与本次调用不一致时拒绝，不使用别人的批准
+    pub fn check(&self, request: &CallToolRequest, reason: &str) -> ToolResult<(String, ApprovalStatus)> {
+        let id = Self::request_id(request);
+        let existing = self.load(&id)?;
+        if let Some(approval) = &existing {
+            if approval.tool != request.name || approval.arguments != request.arguments {
+                return Err(ToolError::InvalidArguments(format!(
+                    "approval {} was requested for a different call",
+                    id
+                )));
+            }
+        }
+        match existing {
+            Some(approval) if approval.status == ApprovalStatus::Pending => Ok((id, ApprovalStatus::Pending)),
+            Some(approval) => {
+                fs::remove_file(self.path(&id)?).map_err(store_error)?;
+                Ok((id, approval.status))
+            }
+            None => {
+                self.save(&ApprovalRequest {
+                    id: id.clone(),
+                    tool: request.name.clone(),
+                    arguments: request.arguments.clone(),
+                    reason: reason.to_string(),
+                    status: ApprovalStatus::Pending,
+                    requested_at: Utc::now(),
+                })?;
+                Ok((id, ApprovalStatus::Pending))
+            }
+        }
+    }
+
+    /// 所有未过期的请求，按登记时间排序
+    pub fn list(&self) -> ToolResult<Vec<ApprovalRequest>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut approvals = Vec::new();
+        for entry in fs::read_dir(&self.dir).map_err(store_error)? {
+            let path = entry.map_err(store_error)?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+                if self.path(id).is_err() {
+                    continue;
+                }
+                if let Some(approval) = self.load(id)? {
+                    approvals.push(approval);
+                }
+            }
+        }
+        approvals.sort_by_key(|a| a.requested_at);
+        Ok(approvals)
+    }
+
+    /// 批准请求
+    pub fn approve(&self, id: &str) -> ToolResult<ApprovalRequest> {
+        self.resolve(id, ApprovalStatus::Approved)
+    }
+
+    /// 拒绝请求
+    pub fn reject(&self, id: &str) -> ToolResult<ApprovalRequest> {
+        self.resolve(id, ApprovalStatus::Rejected)
+    }
+
+    fn resolve(&self, id: &str, status: ApprovalStatus) -> ToolResult<ApprovalRequest> {
+        let mut approval = self
+            .load(id)?
+            .ok_or_else(|| ToolError::InvalidArguments(format!("no pending approval: {}", id)))?;
+        approval.status = status;
+        self.save(&approval)?;
+        Ok(approval)
+    }
+}
+
+fn store_error(error: impl std::fmt::Display) -> ToolError {
+    ToolError::ExecutionFailed(format!("approval store: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(id: &str) -> CallToolRequest {
+        CallToolRequest {
+            name: "remove_task".to_string(),
+            arguments: json!({"id": id}),
+        }
+    }
+
+    #[test]
+    fn test_approval_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ApprovalStore::new(dir.path());
+
+        let (id, status) = store.check(&request("a"), "destructive").unwrap();
+        assert_eq!(status, ApprovalStatus::Pending);
+        assert_eq!(id, ApprovalStore::request_id(&request("a")));
+        assert_ne!(id, ApprovalStore::request_id(&request("b")));
+        assert_eq!(store.check(&request("a"), "destructive").unwrap().1, ApprovalStatus::Pending);
+        assert_eq!(store.list().unwrap().len(), 1);
+
+        store.approve(&id).unwrap();
+        assert_eq!(store.check(&request("a"), "destructive").unwrap().1, ApprovalStatus::Approved);
+        // 批准已被使用，再次调用重新登记
+        assert_eq!(store.check(&request("a"), "destructive").unwrap().1, ApprovalStatus::Pending);
+
+        store.reject(&id).unwrap();
+        assert_eq!(store.check(&request("a"), "destructive").unwrap().1, ApprovalStatus::Rejected);
+        assert!(store.approve("0000000000000000").is_err());
+    }
+
+    #[test]
+    fn test_approval_bound_to_call() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ApprovalStore::new(dir.path());
+
+        // 模拟散列碰撞：另一次调用的批准登记在同一 ID 下
+        let id = ApprovalStore::request_id(&request("a"));
+        store
+            .save(&ApprovalRequest {
+                id: id.clone(),
+                tool: "run_command".to_string(),
+                arguments: json!({"command": "rm -rf /"}),
+                reason: "destructive".to_string(),
+                status: ApprovalStatus::Approved,
+                requested_at: Utc::now(),
+            })
+            .unwrap();
+        assert!(store.check(&request("a"), "destructive").is_err());
+        // 原请求的批准不被消耗
+        assert_eq!(store.list().unwrap()[0].status, ApprovalStatus::Approved);
+    }
+
+    #[test]
+    fn test_malformed_ids_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ApprovalStore::new(dir.path().join("approvals"));
+        let outside = dir.path().join("outside.json");
+        std::fs::write(&outside, "{}").unwrap();
+
+        for id in ["../outside", "../../etc/passwd", "0000000000000000.json", "ABCDEF0123456789", "0123", ""] {
+            let error = store.approve(id).unwrap_err();
+            assert!(matches!(error, ToolError::InvalidArguments(_)), "{}: {}", id, error);
+            assert!(store.reject(id).is_err());
+        }
+        assert_eq!(std::fs::read_to_string(&outside).unwrap(), "{}");
+        assert!(!store.dir().exists());
+    }
+
+    #[test]
+    fn test_expired_requests_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ApprovalStore::new(dir.path()).with_ttl(Duration::seconds(-1));
+        let (id, _) = store.check(&request("a"), "destructive").unwrap();
+        assert!(store.list().unwrap().is_empty());
+        assert!(store.approve(&id).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! 工具调用审计日志
+//!
+//! 每次策略决定追加一行 JSON
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::policy::PolicyEffect;
+use crate::{CallToolRequest, ToolError, ToolResult};
+
+/// 调用的最终结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// 已执行且成功
+    Executed,
+    /// 已执行但失败，或策略检查本身出错
+    Failed,
+    /// 被策略或人工拒绝
+    Denied,
+    /// 等待人工确认
+    Pending,
+    /// 演练模式，未执行
+    DryRun,
+}
+
+/// 一条审计记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub time: DateTime<Utc>,
+    pub tool: String,
+    pub arguments: Value,
+    pub effect: PolicyEffect,
+    /// 生效的规则，None 表示使用默认效果
+    pub rule: Option<String>,
+    pub outcome: AuditOutcome,
+    pub approval_id: Option<String>,
+    pub dry_run: bool,
+    pub error: Option<String>,
+}
+
+impl AuditRecord {
+    pub fn new(request: &CallToolRequest, dry_run: bool) -> Self {
+        Self {
+            time: Utc::now(),
+            tool: request.name.clone(),
+            arguments: request.arguments.clone(),
+            effect: PolicyEffect::Allow,
+            rule: None,
+            outcome: AuditOutcome::Failed,
+            approval_id: None,
+            dry_run,
+            error: None,
+        }
+    }
+}
+
+/// JSON Lines 审计日志文件
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条记录
+    pub fn append(&self, record: &AuditRecord) -> ToolResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(audit_error)?;
+        }
+        let mut line = serde_json::to_string(record).map_err(audit_error)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(audit_error)
+    }
+
+    /// 最近的记录，按时间先后排列；无法解析的行被跳过
+    pub fn read_recent(&self, limit: usize) -> ToolResult<Vec<AuditRecord>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let data = fs::read_to_string(&self.path).map_err(audit_error)?;
+        let records: Vec<AuditRecord> = data
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        let skip = records.len().saturating_sub(limit);
+        Ok(records.into_iter().skip(skip).collect())
+    }
+}
+
+fn audit_error(error: impl std::fmt::Display) -> ToolError {
+    ToolError::ExecutionFailed(format!("audit log: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_audit_log_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(dir.path().join("logs").join("audit.log"));
+        assert!(log.read_recent(5).unwrap().is_empty());
+
+        for i in 0..3 {
+            let mut record = AuditRecord::new(
+                &CallToolRequest {
+                    name: format!("tool_{}", i),
+                    arguments: json!({"n": i}),
+                },
+                false,
+            );
+            record.outcome = AuditOutcome::Executed;
+            log.append(&record).unwrap();
+        }
+        fs::OpenOptions::new()
+            .append(true)
+            .open(log.path())
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+
+        let records = log.read_recent(2).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].tool, "tool_1");
+        assert_eq!(records[1].arguments, json!({"n": 2}));
+    }
+}
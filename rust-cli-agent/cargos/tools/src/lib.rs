@@ -0,0 +1,392 @@
+//! LLM 工具接口
+//!
+//! 各模块的工具适配器共用的工具定义、调用请求和响应、参数解析和错误类型。
+//! 适配器实现 [`ToolProvider`]，注册到 [`ToolRegistry`] 后由同一个 MCP 或工具端点统一提供，
+//! 需要限制调用时再用 [`PolicyGuard`] 包装
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub mod approval;
+pub mod audit;
+pub mod policy;
+
+pub use approval::{ApprovalRequest, ApprovalStatus, ApprovalStore};
+pub use audit::{AuditLog, AuditOutcome, AuditRecord};
+pub use policy::{PolicyDecision, PolicyEffect, PolicyGuard, PolicyRule, ToolPolicy};
+
+/// 工具定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tool {
+    /// 工具名称
+    pub name: String,
+    /// 工具描述
+    pub description: String,
+    /// 参数 Schema
+    pub input_schema: Value,
+}
+
+/// 工具调用请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallToolRequest {
+    /// 工具名称
+    pub name: String,
+    /// 参数
+    pub arguments: Value,
+}
+
+/// 工具调用响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallToolResponse {
+    /// 是否成功
+    pub success: bool,
+    /// 结果内容
+    pub content: Vec<ToolContent>,
+    /// 错误信息
+    pub error: Option<String>,
+}
+
+impl CallToolResponse {
+    /// 成功的文本结果
+    pub fn text(output: String) -> Self {
+        Self {
+            success: true,
+            content: vec![ToolContent::text(output)],
+            error: None,
+        }
+    }
+
+    /// 失败的调用
+    pub fn failure(error: impl std::fmt::Display) -> Self {
+        Self {
+            success: false,
+            content: vec![],
+            error: Some(error.to_string()),
+        }
+    }
+
+    /// 按工具结果创建响应
+    pub fn from_result<E: std::fmt::Display>(result: Result<String, E>) -> Self {
+        match result {
+            Ok(output) => Self::text(output),
+            Err(e) => Self::failure(e),
+        }
+    }
+}
+
+/// 工具内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolContent {
+    /// 内容类型
+    #[serde(rename = "type")]
+    pub content_type: String,
+    /// 内容文本
+    pub text: Option<String>,
+}
+
+impl ToolContent {
+    pub fn text(text: String) -> Self {
+        Self {
+            content_type: "text".to_string(),
+            text: Some(text),
+        }
+    }
+
+    pub fn error(text: String) -> Self {
+        Self {
+            content_type: "text".to_string(),
+            text: Some(text),
+        }
+    }
+}
+
+/// 工具调用错误
+#[derive(Debug, thiserror::Error)]
+pub enum ToolError {
+    #[error("Unknown tool: {0}")]
+    UnknownTool(String),
+
+    #[error("Duplicate tool: {0}")]
+    DuplicateTool(String),
+
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Confirmation required: {0}")]
+    ConfirmationRequired(String),
+
+    #[error("Not available: {0}")]
+    NotAvailable(String),
+
+    #[error("Tool execution failed: {0}")]
+    ExecutionFailed(String),
+}
+
+/// 工具调用结果
+pub type ToolResult<T> = Result<T, ToolError>;
+
+/// 工具提供者
+///
+/// 每个模块的工具适配器实现该 trait
+#[async_trait]
+pub trait ToolProvider: Send + Sync {
+    /// 所有工具定义
+    fn tools(&self) -> Vec<Tool>;
+
+    /// 调用工具
+    async fn call(&self, request: CallToolRequest) -> CallToolResponse;
+}
+
+/// 工具注册表
+///
+/// 汇总多个工具提供者，按工具名称分派调用；工具名称在注册表内必须唯一
+#[derive(Default)]
+pub struct ToolRegistry {
+    providers: Vec<Arc<dyn ToolProvider>>,
+    /// 工具名称到提供者下标
+    index: HashMap<String, usize>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册工具提供者，工具名称与已注册的工具重复时拒绝注册
+    pub fn register(&mut self, provider: Arc<dyn ToolProvider>) -> ToolResult<()> {
+        let names: Vec<String> = provider.tools().into_iter().map(|t| t.name).collect();
+        for (i, name) in names.iter().enumerate() {
+            if self.index.contains_key(name) || names[..i].contains(name) {
+                return Err(ToolError::DuplicateTool(name.clone()));
+            }
+        }
+        let position = self.providers.len();
+        self.providers.push(provider);
+        self.index.extend(names.into_iter().map(|name| (name, position)));
+        Ok(())
+    }
+
+    /// 注册工具提供者并返回注册表
+    pub fn with_provider(mut self, provider: Arc<dyn ToolProvider>) -> ToolResult<Self> {
+        self.register(provider)?;
+        Ok(self)
+    }
+
+    /// 是否包含指定工具
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    /// 工具数量
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// 是否没有任何工具
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+}
+
+#[async_trait]
+impl ToolProvider for ToolRegistry {
+    fn tools(&self) -> Vec<Tool> {
+        self.providers.iter().flat_map(|p| p.tools()).collect()
+    }
+
+    async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+        match self.index.get(&request.name) {
+            Some(&position) => self.providers[position].call(request).await,
+            None => CallToolResponse::failure(ToolError::UnknownTool(request.name)),
+        }
+    }
+}
+
+/// 工具参数
+///
+/// 统一参数校验和错误信息，值为 null 的参数视为未提供
+pub struct Arguments<'a> {
+    fields: Option<&'a serde_json::Map<String, Value>>,
+}
+
+impl<'a> Arguments<'a> {
+    /// 参数必须是对象或 null
+    pub fn new(value: &'a Value) -> ToolResult<Self> {
+        match value {
+            Value::Object(fields) => Ok(Self { fields: Some(fields) }),
+            Value::Null => Ok(Self { fields: None }),
+            _ => Err(ToolError::InvalidArguments("arguments must be an object".to_string())),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Value> {
+        self.fields.and_then(|f| f.get(key)).filter(|v| !v.is_null())
+    }
+
+    fn invalid(key: &str, expected: &str) -> ToolError {
+        ToolError::InvalidArguments(format!("{} must be {}", key, expected))
+    }
+
+    /// 可选字符串
+    pub fn str(&self, key: &str) -> ToolResult<Option<&'a str>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(value) => value.as_str().map(Some).ok_or_else(|| Self::invalid(key, "a string")),
+        }
+    }
+
+    /// 必填且非空的字符串
+    pub fn required_str(&self, key: &str) -> ToolResult<&'a str> {
+        match self.str(key)? {
+            Some(value) if !value.trim().is_empty() => Ok(value),
+            _ => Err(ToolError::InvalidArguments(format!("missing required argument: {}", key))),
+        }
+    }
+
+    /// 可选非负整数
+    pub fn u64(&self, key: &str) -> ToolResult<Option<u64>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(value) => value
+                .as_u64()
+                .map(Some)
+                .ok_or_else(|| Self::invalid(key, "a non-negative integer")),
+        }
+    }
+
+    /// 可选数字
+    pub fn f32(&self, key: &str) -> ToolResult<Option<f32>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .map(|v| Some(v as f32))
+                .ok_or_else(|| Self::invalid(key, "a number")),
+        }
+    }
+
+    /// 可选布尔值
+    pub fn bool(&self, key: &str) -> ToolResult<Option<bool>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(value) => value.as_bool().map(Some).ok_or_else(|| Self::invalid(key, "a boolean")),
+        }
+    }
+
+    /// 字符串数组，未提供时为空
+    pub fn strings(&self, key: &str) -> ToolResult<Vec<String>> {
+        match self.get(key) {
+            None => Ok(Vec::new()),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| Self::invalid(key, "an array of strings")),
+            Some(_) => Err(Self::invalid(key, "an array of strings")),
+        }
+    }
+
+    /// 字符串到字符串的对象，未提供时为空
+    pub fn string_map(&self, key: &str) -> ToolResult<HashMap<String, String>> {
+        match self.get(key) {
+            None => Ok(HashMap::new()),
+            Some(Value::Object(fields)) => fields
+                .iter()
+                .map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                .collect::<Option<HashMap<_, _>>>()
+                .ok_or_else(|| Self::invalid(key, "an object of string values")),
+            Some(_) => Err(Self::invalid(key, "an object of string values")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct EchoTools {
+        names: Vec<&'static str>,
+    }
+
+    #[async_trait]
+    impl ToolProvider for EchoTools {
+        fn tools(&self) -> Vec<Tool> {
+            self.names
+                .iter()
+                .map(|name| Tool {
+                    name: name.to_string(),
+                    description: "Echo".to_string(),
+                    input_schema: json!({"type": "object"}),
+                })
+                .collect()
+        }
+
+        async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+            CallToolResponse::text(format!("{}:{}", request.name, request.arguments))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_dispatch_and_duplicates() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTools { names: vec!["a", "b"] })).unwrap();
+        registry.register(Arc::new(EchoTools { names: vec!["c"] })).unwrap();
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.tools().len(), 3);
+
+        let duplicate = registry.register(Arc::new(EchoTools { names: vec!["d", "b"] }));
+        assert!(matches!(duplicate, Err(ToolError::DuplicateTool(name)) if name == "b"));
+        assert!(!registry.contains("d"));
+
+        let response = registry
+            .call(CallToolRequest { name: "c".to_string(), arguments: json!({"x": 1}) })
+            .await;
+        assert!(response.success);
+        assert_eq!(response.content[0].text.as_deref(), Some(r#"c:{"x":1}"#));
+
+        let unknown = registry
+            .call(CallToolRequest { name: "zzz".to_string(), arguments: json!({}) })
+            .await;
+        assert!(!unknown.success);
+        assert_eq!(unknown.error.as_deref(), Some("Unknown tool: zzz"));
+    }
+
+    #[test]
+    fn test_arguments() {
+        let value = json!({
+            "name": "x",
+            "blank": " ",
+            "count": 3,
+            "rate": 1.5,
+            "flag": true,
+            "list": ["a", "b"],
+            "env": {"K": "V"},
+            "nothing": null
+        });
+        let args = Arguments::new(&value).unwrap();
+        assert_eq!(args.required_str("name").unwrap(), "x");
+        assert!(args.required_str("blank").is_err());
+        assert!(args.required_str("nothing").is_err());
+        assert_eq!(args.u64("count").unwrap(), Some(3));
+        assert!(args.u64("rate").is_err());
+        assert_eq!(args.f32("rate").unwrap(), Some(1.5));
+        assert_eq!(args.bool("flag").unwrap(), Some(true));
+        assert_eq!(args.strings("list").unwrap(), vec!["a", "b"]);
+        assert!(args.strings("env").is_err());
+        assert_eq!(args.string_map("env").unwrap()["K"], "V");
+        assert_eq!(args.str("missing").unwrap(), None);
+
+        let err = args.bool("name").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid arguments: name must be a boolean");
+        assert!(Arguments::new(&json!([1])).is_err());
+        assert!(Arguments::new(&Value::Null).unwrap().strings("x").unwrap().is_empty());
+    }
+}
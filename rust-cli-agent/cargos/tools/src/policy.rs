@@ -0,0 +1,595 @@
+//! 工具调用策略
+//!
+//! [`PolicyGuard`] 包装任意 [`ToolProvider`]，在调用前按 [`ToolPolicy`] 决定放行、拒绝或等待人工确认，
+//! 并把每次决定写入审计日志。规则按顺序匹配，第一条匹配的规则生效，没有规则匹配时使用默认效果
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::approval::{ApprovalStatus, ApprovalStore};
+use crate::audit::{AuditLog, AuditOutcome, AuditRecord};
+use crate::{CallToolRequest, CallToolResponse, Tool, ToolError, ToolProvider};
+
+/// 策略效果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyEffect {
+    /// 直接执行
+    Allow,
+    /// 拒绝执行
+    Deny,
+    /// 人工确认后执行
+    Confirm,
+}
+
+impl fmt::Display for PolicyEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PolicyEffect::Allow => "allow",
+            PolicyEffect::Deny => "deny",
+            PolicyEffect::Confirm => "confirm",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for PolicyEffect {
+    type Err = ToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "allow" => Ok(PolicyEffect::Allow),
+            "deny" => Ok(PolicyEffect::Deny),
+            "confirm" => Ok(PolicyEffect::Confirm),
+            other => Err(ToolError::InvalidArguments(format!(
+                "unknown policy effect: {} (expected allow, deny or confirm)",
+                other
+            ))),
+        }
+    }
+}
+
+/// 策略规则
+///
+/// `tool` 和参数模式都是 glob (`*` 匹配任意字符串，`?` 匹配单个字符)。
+/// 参数按名称匹配，同一参数的多个模式任一匹配即可，所有列出的参数都匹配时规则才匹配；
+/// 数组参数 (如 `args`) 以空格拼接后匹配，缺少的参数视为不匹配。
+/// 列出参数的放行规则 (允许清单) 还要求调用的所有字符串参数都不含 shell 元字符
+/// (`;`、`&`、`|`、反引号、`$`、重定向、换行)，且 `shell`、`env` 参数须在规则中列出才能使用，
+/// 避免 `program = ["ls"]` 之类的允许清单放行 `ls; rm -rf /` 或改写 `LD_PRELOAD`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyRule {
+    pub tool: String,
+    pub arguments: BTreeMap<String, Vec<String>>,
+    pub effect: PolicyEffect,
+    pub reason: Option<String>,
+}
+
+impl PolicyRule {
+    pub fn new(tool: impl Into<String>, effect: PolicyEffect) -> Self {
+        Self {
+            tool: tool.into(),
+            arguments: BTreeMap::new(),
+            effect,
+            reason: None,
+        }
+    }
+
+    /// 要求参数匹配任一模式
+    pub fn with_argument(mut self, name: impl Into<String>, patterns: Vec<String>) -> Self {
+        self.arguments.insert(name.into(), patterns);
+        self
+    }
+
+    /// 设置说明，拒绝或要求确认时返回给调用方
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// 是否匹配调用请求
+    pub fn matches(&self, request: &CallToolRequest) -> bool {
+        if !glob_match(&self.tool, &request.name) {
+            return false;
+        }
+        if self.effect == PolicyEffect::Allow && !self.arguments.is_empty() && !self.admits(&request.arguments) {
+            return false;
+        }
+        self.arguments.iter().all(|(name, patterns)| {
+            match request.arguments.get(name).and_then(argument_text) {
+                Some(text) => patterns.iter().any(|pattern| glob_match(pattern, &text)),
+                None => false,
+            }
+        })
+    }
+
+    /// 允许清单是否接受调用的全部参数，而不仅是规则列出的参数
+    fn admits(&self, arguments: &Value) -> bool {
+        let unlisted = EXPLICIT_ARGUMENTS
+            .iter()
+            .any(|name| !self.arguments.contains_key(*name) && arguments.get(*name).is_some_and(is_set));
+        !unlisted && !contains_shell_metacharacters(arguments)
+    }
+}
+
+impl fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.effect, self.tool)?;
+        for (name, patterns) in &self.arguments {
+            write!(f, " {}=[{}]", name, patterns.join("|"))?;
+        }
+        Ok(())
+    }
+}
+
+/// 参数值用于匹配的文本形式
+fn argument_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(text) => Some(text.clone()),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string).unwrap_or_else(|| item.to_string()))
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+/// 允许清单须显式列出才能使用的参数：`shell` 把参数拼成一行交给 shell，`env` 可改写 `PATH`、`LD_PRELOAD`
+const EXPLICIT_ARGUMENTS: &[&str] = &["shell", "env"];
+
+/// 参数是否设置了非空值
+fn is_set(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(flag) => *flag,
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+        Value::Number(_) => true,
+    }
+}
+
+/// 参数中的任一字符串 (包括对象的键) 是否含有 shell 元字符
+fn contains_shell_metacharacters(value: &Value) -> bool {
+    match value {
+        Value::String(text) => has_shell_metacharacters(text),
+        Value::Array(items) => items.iter().any(contains_shell_metacharacters),
+        Value::Object(map) => map
+            .iter()
+            .any(|(key, value)| has_shell_metacharacters(key) || contains_shell_metacharacters(value)),
+        _ => false,
+    }
+}
+
+/// 是否含有可以串联命令或展开变量的 shell 元字符
+fn has_shell_metacharacters(text: &str) -> bool {
+    text.contains(['\n', '\r', ';', '&', '|', '`', '<', '>', '$'])
+}
+
+/// glob 匹配，`*` 匹配任意字符串 (包括空串)，`?` 匹配单个字符
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 `*` 的位置和它当时对应的文本位置，失配时回溯
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// 策略对一次调用的决定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyDecision {
+    pub effect: PolicyEffect,
+    /// 生效的规则，None 表示使用默认效果
+    pub rule: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// 工具调用策略
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolPolicy {
+    rules: Vec<PolicyRule>,
+    default_effect: PolicyEffect,
+    dry_run: bool,
+}
+
+impl Default for ToolPolicy {
+    fn default() -> Self {
+        Self::new(PolicyEffect::Allow)
+    }
+}
+
+impl ToolPolicy {
+    pub fn new(default_effect: PolicyEffect) -> Self {
+        Self {
+            rules: Vec::new(),
+            default_effect,
+            dry_run: false,
+        }
+    }
+
+    /// 追加规则，先添加的规则优先
+    pub fn with_rule(mut self, rule: PolicyRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// 演练模式：通过策略检查的调用只记录不执行
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn rules(&self) -> &[PolicyRule] {
+        &self.rules
+    }
+
+    pub fn default_effect(&self) -> PolicyEffect {
+        self.default_effect
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// 决定如何处理调用
+    pub fn evaluate(&self, request: &CallToolRequest) -> PolicyDecision {
+        match self.rules.iter().find(|rule| rule.matches(request)) {
+            Some(rule) => PolicyDecision {
+                effect: rule.effect,
+                rule: Some(rule.to_string()),
+                reason: rule.reason.clone(),
+            },
+            None => PolicyDecision {
+                effect: self.default_effect,
+                rule: None,
+                reason: None,
+            },
+        }
+    }
+}
+
+/// 按策略保护工具调用
+pub struct PolicyGuard {
+    inner: Arc<dyn ToolProvider>,
+    policy: ToolPolicy,
+    approvals: Option<ApprovalStore>,
+    audit: Option<AuditLog>,
+}
+
+impl PolicyGuard {
+    pub fn new(inner: Arc<dyn ToolProvider>, policy: ToolPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            approvals: None,
+            audit: None,
+        }
+    }
+
+    /// 设置人工确认的存储，未设置时需要确认的调用一律拒绝
+    pub fn with_approvals(mut self, approvals: ApprovalStore) -> Self {
+        self.approvals = Some(approvals);
+        self
+    }
+
+    /// 设置审计日志
+    pub fn with_audit(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    pub fn policy(&self) -> &ToolPolicy {
+        &self.policy
+    }
+
+    /// 检查确认状态，返回 None 表示已确认可以执行
+    fn check_confirmation(
+        &self,
+        request: &CallToolRequest,
+        decision: &PolicyDecision,
+        record: &mut AuditRecord,
+    ) -> Option<ToolError> {
+        let reason = decision
+            .reason
+            .clone()
+            .unwrap_or_else(|| format!("{} needs human confirmation", request.name));
+        let Some(approvals) = &self.approvals else {
+            record.outcome = AuditOutcome::Denied;
+            return Some(ToolError::ConfirmationRequired(format!(
+                "{}; no confirmation channel is configured",
+                reason
+            )));
+        };
+
+        match approvals.check(request, &reason) {
+            Ok((id, status)) => {
+                record.approval_id = Some(id.clone());
+                match status {
+                    ApprovalStatus::Approved => None,
+                    ApprovalStatus::Rejected => {
+                        record.outcome = AuditOutcome::Denied;
+                        Some(ToolError::PermissionDenied(format!("{} was rejected by a human ({})", request.name, id)))
+                    }
+                    ApprovalStatus::Pending => {
+                        record.outcome = AuditOutcome::Pending;
+                        Some(ToolError::ConfirmationRequired(format!(
+                            "{}; ask the user to run `sker policy approve {}` and then retry the same call",
+                            reason, id
+                        )))
+                    }
+                }
+            }
+            Err(e) => {
+                record.outcome = AuditOutcome::Failed;
+                Some(e)
+            }
+        }
+    }
+
+    async fn guarded_call(&self, request: CallToolRequest, record: &mut AuditRecord) -> CallToolResponse {
+        let decision = self.policy.evaluate(&request);
+        record.effect = decision.effect;
+        record.rule = decision.rule.clone();
+
+        match decision.effect {
+            PolicyEffect::Deny => {
+                record.outcome = AuditOutcome::Denied;
+                let reason = decision
+                    .reason
+                    .unwrap_or_else(|| format!("{} is not allowed by policy", request.name));
+                return CallToolResponse::failure(ToolError::PermissionDenied(reason));
+            }
+            PolicyEffect::Confirm => {
+                if let Some(error) = self.check_confirmation(&request, &decision, record) {
+                    return CallToolResponse::failure(error);
+                }
+            }
+            PolicyEffect::Allow => {}
+        }
+
+        if self.policy.dry_run {
+            record.outcome = AuditOutcome::DryRun;
+            return CallToolResponse::text(format!(
+                "Dry run: {} was allowed by policy but not executed. Arguments: {}",
+                request.name, request.arguments
+            ));
+        }
+
+        let response = self.inner.call(request).await;
+        if response.success {
+            record.outcome = AuditOutcome::Executed;
+        } else {
+            record.outcome = AuditOutcome::Failed;
+            record.error = response.error.clone();
+        }
+        response
+    }
+}
+
+#[async_trait]
+impl ToolProvider for PolicyGuard {
+    fn tools(&self) -> Vec<Tool> {
+        self.inner.tools()
+    }
+
+    async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+        let mut record = AuditRecord::new(&request, self.policy.dry_run);
+        let response = self.guarded_call(request, &mut record).await;
+        if let Some(audit) = &self.audit {
+            // 审计失败不影响调用结果
+            if let Err(e) = audit.append(&record) {
+                tracing::warn!("Failed to write audit log {:?}: {}", audit.path(), e);
+            }
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ToolRegistry;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 记录调用次数的工具
+    struct CountingTools {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ToolProvider for CountingTools {
+        fn tools(&self) -> Vec<Tool> {
+            ["add_task", "remove_task", "list_tasks"]
+                .iter()
+                .map(|name| Tool {
+                    name: name.to_string(),
+                    description: String::new(),
+                    input_schema: json!({"type": "object"}),
+                })
+                .collect()
+        }
+
+        async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            CallToolResponse::text(request.name)
+        }
+    }
+
+    fn request(name: &str, arguments: Value) -> CallToolRequest {
+        CallToolRequest {
+            name: name.to_string(),
+            arguments,
+        }
+    }
+
+    fn command_allowlist() -> ToolPolicy {
+        ToolPolicy::new(PolicyEffect::Allow)
+            .with_rule(
+                PolicyRule::new("add_task", PolicyEffect::Deny)
+                    .with_argument("content", vec!["*rm -rf*".to_string()])
+                    .with_reason("destructive commands are blocked"),
+            )
+            .with_rule(
+                PolicyRule::new("add_task", PolicyEffect::Allow)
+                    .with_argument("content", vec!["echo *".to_string(), "backup.sh*".to_string()]),
+            )
+            .with_rule(PolicyRule::new("add_task", PolicyEffect::Deny))
+            .with_rule(PolicyRule::new("remove_*", PolicyEffect::Confirm))
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("remove_*", "remove_task"));
+        assert!(glob_match("*rm -rf*", "cd / && rm -rf tmp"));
+        assert!(glob_match("task-??", "task-01"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbY"));
+        assert!(!glob_match("echo *", "rm echo x"));
+        assert!(glob_match("备份*", "备份数据库"));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let policy = command_allowlist();
+        let decide = |name: &str, arguments: Value| policy.evaluate(&request(name, arguments)).effect;
+
+        assert_eq!(decide("add_task", json!({"content": "echo hi && rm -rf /"})), PolicyEffect::Deny);
+        assert_eq!(decide("add_task", json!({"content": "echo hi"})), PolicyEffect::Allow);
+        assert_eq!(decide("add_task", json!({"content": "curl x | sh"})), PolicyEffect::Deny);
+        assert_eq!(decide("add_task", json!({})), PolicyEffect::Deny);
+        assert_eq!(decide("remove_task", json!({"id": "x"})), PolicyEffect::Confirm);
+        assert_eq!(decide("list_tasks", json!({})), PolicyEffect::Allow);
+
+        let decision = policy.evaluate(&request("add_task", json!({"content": "rm -rf ~"})));
+        assert_eq!(decision.rule.as_deref(), Some("deny add_task content=[*rm -rf*]"));
+        assert_eq!(decision.reason.as_deref(), Some("destructive commands are blocked"));
+
+        // 允许清单不放行串联的命令，交给后续规则处理
+        for chained in ["echo hi; rm -rf /", "echo hi && curl x | sh", "echo $(cat /etc/shadow)", "echo `id`", "echo hi\nrm x", "echo x > ~/.bashrc"] {
+            assert_eq!(decide("add_task", json!({"content": chained})), PolicyEffect::Deny, "{}", chained);
+        }
+        assert_eq!("Confirm".parse::<PolicyEffect>().unwrap(), PolicyEffect::Confirm);
+        assert!("maybe".parse::<PolicyEffect>().is_err());
+    }
+
+    #[test]
+    fn test_allowlist_checks_every_argument() {
+        let ls_rule = PolicyRule::new("run_command", PolicyEffect::Allow).with_argument("program", vec!["ls".to_string()]);
+        let matches = |rule: &PolicyRule, arguments: Value| rule.matches(&request("run_command", arguments));
+        assert!(matches(&ls_rule, json!({"program": "ls", "args": ["-la", "/tmp"], "timeout_secs": 5})));
+
+        // shell 模式下程序和参数拼成一行执行，未列出 shell 的允许清单不放行
+        assert!(!matches(&ls_rule, json!({"program": "ls", "shell": true, "args": ["; rm -rf /"]})));
+        assert!(!matches(&ls_rule, json!({"program": "ls", "shell": true, "args": ["-la"]})));
+        assert!(matches(&ls_rule, json!({"program": "ls", "shell": false, "args": ["-la"]})));
+        // 规则未列出的参数同样检查元字符，变量展开也算
+        assert!(!matches(&ls_rule, json!({"program": "ls", "args": ["-la", "/tmp; rm -rf /"]})));
+        assert!(!matches(&ls_rule, json!({"program": "ls", "args": ["$HOME"]})));
+        assert!(!matches(&ls_rule, json!({"program": "ls", "working_dir": "${IFS}x"})));
+        // env 可改写 LD_PRELOAD、PATH，须在规则中列出
+        assert!(!matches(&ls_rule, json!({"program": "ls", "env": {"LD_PRELOAD": "/tmp/evil.so"}})));
+        assert!(matches(&ls_rule, json!({"program": "ls", "env": {}})));
+
+        let shell_rule = ls_rule.clone().with_argument("shell", vec!["true".to_string()]);
+        assert!(matches(&shell_rule, json!({"program": "ls", "shell": true, "args": ["-la"]})));
+        assert!(!matches(&shell_rule, json!({"program": "ls", "shell": true, "args": ["; rm -rf /"]})));
+        assert!(!matches(&shell_rule, json!({"program": "ls", "shell": true, "args": ["$(id)"]})));
+
+        // 拒绝规则仍按原文匹配
+        let deny_rule = PolicyRule::new("run_command", PolicyEffect::Deny).with_argument("args", vec!["*rm *".to_string()]);
+        assert!(matches(&deny_rule, json!({"program": "ls", "shell": true, "args": ["; rm -rf /"]})));
+
+        let args_rule = PolicyRule::new("run_command", PolicyEffect::Allow)
+            .with_argument("args", vec!["-la *".to_string()]);
+        assert!(matches(&args_rule, json!({"program": "ls", "args": ["-la", "/tmp"]})));
+    }
+
+    #[tokio::test]
+    async fn test_guard_denies_confirms_and_audits() {
+        let dir = tempfile::tempdir().unwrap();
+        let tools = Arc::new(CountingTools { calls: AtomicUsize::new(0) });
+        let registry = ToolRegistry::new().with_provider(tools.clone()).unwrap();
+        let approvals = ApprovalStore::new(dir.path().join("approvals"));
+        let guard = PolicyGuard::new(Arc::new(registry), command_allowlist())
+            .with_approvals(approvals.clone())
+            .with_audit(AuditLog::new(dir.path().join("audit.log")));
+        assert_eq!(guard.tools().len(), 3);
+
+        let denied = guard.call(request("add_task", json!({"content": "rm -rf /"}))).await;
+        assert_eq!(
+            denied.error.as_deref(),
+            Some("Permission denied: destructive commands are blocked")
+        );
+        assert!(guard.call(request("add_task", json!({"content": "echo ok"}))).await.success);
+
+        let pending = guard.call(request("remove_task", json!({"id": "t1"}))).await;
+        let error = pending.error.unwrap();
+        assert!(error.starts_with("Confirmation required"));
+        assert_eq!(tools.calls.load(Ordering::SeqCst), 1);
+
+        let id = approvals.list().unwrap()[0].id.clone();
+        assert!(error.contains(&format!("sker policy approve {}", id)));
+        approvals.approve(&id).unwrap();
+        assert!(guard.call(request("remove_task", json!({"id": "t1"}))).await.success);
+        assert_eq!(tools.calls.load(Ordering::SeqCst), 2);
+        // 确认只生效一次
+        assert!(!guard.call(request("remove_task", json!({"id": "t1"}))).await.success);
+
+        let records = AuditLog::new(dir.path().join("audit.log")).read_recent(10).unwrap();
+        let outcomes: Vec<AuditOutcome> = records.iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                AuditOutcome::Denied,
+                AuditOutcome::Executed,
+                AuditOutcome::Pending,
+                AuditOutcome::Executed,
+                AuditOutcome::Pending,
+            ]
+        );
+        assert_eq!(records[3].approval_id.as_deref(), Some(id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_guard_dry_run_and_missing_confirmation_channel() {
+        let tools = Arc::new(CountingTools { calls: AtomicUsize::new(0) });
+        let guard = PolicyGuard::new(tools.clone(), command_allowlist().with_dry_run(true));
+
+        let response = guard.call(request("add_task", json!({"content": "echo hi"}))).await;
+        assert!(response.success);
+        assert!(response.content[0].text.as_deref().unwrap().starts_with("Dry run: add_task"));
+        assert!(!guard.call(request("add_task", json!({"content": "rm -rf /"}))).await.success);
+
+        let response = guard.call(request("remove_task", json!({"id": "t1"}))).await;
+        assert!(response.error.unwrap().contains("no confirmation channel is configured"));
+        assert_eq!(tools.calls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -19,11 +19,13 @@ pub mod error;
 pub mod tts;
 pub mod stt;
 pub mod config;
+pub mod llm;
 
 pub use error::{VoiceError, VoiceResult};
 pub use tts::{TTSBackend, TTSEngine, TTSOptions};
 pub use stt::{STTBackend, STTEngine, STTResult, STTOptions, WhisperSTT, transcribe_file, transcribe_url};
 pub use config::VoiceConfig;
+pub use llm::VoiceToolAdapter;
 
 /// 语音助手服务
 #[derive(Clone, Debug)]
@@ -0,0 +1,269 @@
+//! LLM 工具适配器
+//!
+//! 提供 `voice_speak` 和 `voice_transcribe` 两个工具，转录的音频从本地文件读取
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tools::{Arguments, CallToolRequest, CallToolResponse, Tool, ToolError, ToolProvider, ToolResult};
+
+use crate::{STTBackend, STTOptions, TTSBackend, TTSOptions, VoiceAssistantService, VoiceError};
+
+/// 转录文件的默认大小上限
+pub const DEFAULT_MAX_AUDIO_BYTES: u64 = 50 * 1024 * 1024;
+
+/// 语音工具适配器
+pub struct VoiceToolAdapter<T: TTSBackend, S: STTBackend> {
+    service: VoiceAssistantService<T, S>,
+    max_audio_bytes: u64,
+    /// 调用方未指定时使用的识别选项
+    stt_defaults: STTOptions,
+}
+
+impl<T: TTSBackend, S: STTBackend> VoiceToolAdapter<T, S> {
+    pub fn new(service: VoiceAssistantService<T, S>) -> Self {
+        Self {
+            service,
+            max_audio_bytes: DEFAULT_MAX_AUDIO_BYTES,
+            stt_defaults: STTOptions::default(),
+        }
+    }
+
+    /// 设置默认的识别语言和模型
+    pub fn with_stt_defaults(mut self, defaults: STTOptions) -> Self {
+        self.stt_defaults = defaults;
+        self
+    }
+
+    /// 设置转录文件的大小上限
+    pub fn with_max_audio_bytes(mut self, max_audio_bytes: u64) -> Self {
+        self.max_audio_bytes = max_audio_bytes;
+        self
+    }
+
+    /// 获取所有工具定义
+    pub fn get_tools(&self) -> Vec<Tool> {
+        vec![
+            Tool {
+                name: "voice_speak".to_string(),
+                description: "Speak text aloud through the local text-to-speech engine".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "text": {
+                            "type": "string",
+                            "description": "Text to speak"
+                        },
+                        "voice": {
+                            "type": "string",
+                            "description": "Voice name"
+                        },
+                        "rate": {
+                            "type": "number",
+                            "minimum": 0.1,
+                            "maximum": 10.0,
+                            "description": "Speaking rate, 1.0 is normal"
+                        },
+                        "volume": {
+                            "type": "number",
+                            "minimum": 0.0,
+                            "maximum": 1.0,
+                            "description": "Volume"
+                        }
+                    },
+                    "required": ["text"]
+                }),
+            },
+            Tool {
+                name: "voice_transcribe".to_string(),
+                description: format!(
+                    "Transcribe a local audio file to text (files up to {} bytes)",
+                    self.max_audio_bytes
+                ),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Path of the audio file"
+                        },
+                        "language": {
+                            "type": "string",
+                            "description": "Language code, e.g. en-US or zh-CN"
+                        },
+                        "model": {
+                            "type": "string",
+                            "description": "Recognition model name"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+        ]
+    }
+
+    /// 调用工具
+    pub async fn call_tool(&self, request: CallToolRequest) -> CallToolResponse {
+        let result = match request.name.as_str() {
+            "voice_speak" => self.call_speak(&request.arguments).await,
+            "voice_transcribe" => self.call_transcribe(&request.arguments).await,
+            _ => Err(ToolError::UnknownTool(request.name)),
+        };
+        CallToolResponse::from_result(result)
+    }
+
+    async fn call_speak(&self, arguments: &Value) -> ToolResult<String> {
+        let args = Arguments::new(arguments)?;
+        let text = args.required_str("text")?;
+        let rate = args.f32("rate")?;
+        if rate.is_some_and(|r| !(0.1..=10.0).contains(&r)) {
+            return Err(ToolError::InvalidArguments("rate must be between 0.1 and 10.0".to_string()));
+        }
+        let volume = args.f32("volume")?;
+        if volume.is_some_and(|v| !(0.0..=1.0).contains(&v)) {
+            return Err(ToolError::InvalidArguments("volume must be between 0.0 and 1.0".to_string()));
+        }
+        let defaults = TTSOptions::default();
+        let options = TTSOptions {
+            voice: args.str("voice")?.map(str::to_string),
+            rate: rate.or(defaults.rate),
+            volume: volume.or(defaults.volume),
+            pitch: defaults.pitch,
+        };
+
+        self.service.speak(text, Some(options)).await.map_err(tool_error)?;
+        Ok(format!("Spoke {} characters", text.chars().count()))
+    }
+
+    async fn call_transcribe(&self, arguments: &Value) -> ToolResult<String> {
+        let args = Arguments::new(arguments)?;
+        let path = Path::new(args.required_str("path")?);
+        let metadata = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| ToolError::InvalidArguments(format!("cannot read {}: {}", path.display(), e)))?;
+        if !metadata.is_file() {
+            return Err(ToolError::InvalidArguments(format!("{} is not a file", path.display())));
+        }
+        if metadata.len() > self.max_audio_bytes {
+            return Err(ToolError::InvalidArguments(format!(
+                "{} is {} bytes, larger than the {} byte limit",
+                path.display(),
+                metadata.len(),
+                self.max_audio_bytes
+            )));
+        }
+        if !self.service.is_stt_available().await {
+            return Err(ToolError::NotAvailable("speech recognition backend is not available".to_string()));
+        }
+
+        let audio = tokio::fs::read(path)
+            .await
+            .map_err(|e| ToolError::ExecutionFailed(format!("failed to read {}: {}", path.display(), e)))?;
+        let options = STTOptions {
+            language: args.str("language")?.map(str::to_string).or_else(|| self.stt_defaults.language.clone()),
+            model: args.str("model")?.map(str::to_string).or_else(|| self.stt_defaults.model.clone()),
+        };
+
+        let result = self.service.listen(&audio, Some(options)).await.map_err(tool_error)?;
+        Ok(serde_json::to_string_pretty(&result).unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl<T: TTSBackend + 'static, S: STTBackend + 'static> ToolProvider for VoiceToolAdapter<T, S> {
+    fn tools(&self) -> Vec<Tool> {
+        self.get_tools()
+    }
+
+    async fn call(&self, request: CallToolRequest) -> CallToolResponse {
+        self.call_tool(request).await
+    }
+}
+
+/// 语音错误映射为统一的工具错误
+fn tool_error(error: VoiceError) -> ToolError {
+    match error {
+        VoiceError::NotAvailable | VoiceError::NotSupported(_) => ToolError::NotAvailable(error.to_string()),
+        VoiceError::InvalidInput(message) => ToolError::InvalidArguments(message),
+        other => ToolError::ExecutionFailed(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stt::MockSTT;
+    use crate::tts::MockTTS;
+
+    fn voice_tools(tts: MockTTS, stt: MockSTT) -> VoiceToolAdapter<MockTTS, MockSTT> {
+        VoiceToolAdapter::new(VoiceAssistantService::new(tts, stt)).with_max_audio_bytes(16)
+    }
+
+    async fn call(adapter: &VoiceToolAdapter<MockTTS, MockSTT>, name: &str, arguments: Value) -> CallToolResponse {
+        adapter
+            .call_tool(CallToolRequest {
+                name: name.to_string(),
+                arguments,
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_voice_speak() {
+        let adapter = voice_tools(MockTTS::new(), MockSTT::new());
+        let response = call(&adapter, "voice_speak", json!({"text": "你好", "rate": 1.5})).await;
+        assert_eq!(response.content[0].text.as_deref(), Some("Spoke 2 characters"));
+
+        let response = call(&adapter, "voice_speak", json!({"text": "hi", "volume": 2})).await;
+        assert_eq!(
+            response.error.as_deref(),
+            Some("Invalid arguments: volume must be between 0.0 and 1.0")
+        );
+
+        let mut tts = MockTTS::new();
+        tts.set_available(false);
+        let response = call(&voice_tools(tts, MockSTT::new()), "voice_speak", json!({"text": "hi"})).await;
+        assert!(response.error.unwrap().starts_with("Not available"));
+    }
+
+    #[tokio::test]
+    async fn test_voice_transcribe_file() {
+        let dir = std::env::temp_dir().join(format!("sker-voice-tools-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let audio = dir.join("clip.wav");
+        std::fs::write(&audio, b"RIFF0000WAVE").unwrap();
+        let large = dir.join("large.wav");
+        std::fs::write(&large, [0u8; 32]).unwrap();
+
+        let mut stt = MockSTT::new();
+        stt.set_default_result(Some("打开灯".to_string()));
+        let adapter = voice_tools(MockTTS::new(), stt);
+
+        let path = audio.to_string_lossy();
+        let response = call(&adapter, "voice_transcribe", json!({"path": path, "language": "zh-CN"})).await;
+        let result: Value = serde_json::from_str(response.content[0].text.as_deref().unwrap()).unwrap();
+        assert_eq!(result["text"], "打开灯");
+        assert_eq!(result["language"], "zh-CN");
+
+        let defaults = STTOptions {
+            language: Some("ja-JP".to_string()),
+            model: None,
+        };
+        let response = call(&voice_tools(MockTTS::new(), MockSTT::new()).with_stt_defaults(defaults), "voice_transcribe", json!({"path": path})).await;
+        let result: Value = serde_json::from_str(response.content[0].text.as_deref().unwrap()).unwrap();
+        assert_eq!(result["language"], "ja-JP");
+
+        let response = call(&adapter, "voice_transcribe", json!({"path": large.to_string_lossy()})).await;
+        assert!(response.error.unwrap().contains("larger than the 16 byte limit"));
+        let response = call(&adapter, "voice_transcribe", json!({"path": dir.join("missing.wav").to_string_lossy()})).await;
+        assert!(response.error.unwrap().starts_with("Invalid arguments: cannot read"));
+
+        let mut stt = MockSTT::new();
+        stt.set_available(false);
+        let response = call(&voice_tools(MockTTS::new(), stt), "voice_transcribe", json!({"path": path})).await;
+        assert!(response.error.unwrap().starts_with("Not available"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
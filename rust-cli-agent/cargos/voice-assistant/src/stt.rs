@@ -228,9 +228,9 @@ impl WhisperSTT {
         self
     }
 
-    /// 设置模型大小 (tiny, base, small, medium, large)
+    /// 设置模型大小 (tiny, base, small, medium, large)，也接受 `whisper-small` 形式的模型名
     pub fn with_model_size(mut self, size: &str) -> Self {
-        self.model_size = size.to_string();
+        self.model_size = size.trim_start_matches("whisper-").to_string();
         self
     }
 
@@ -332,7 +332,7 @@ impl WhisperSTT {
     }
 
     /// 使用 whisper.cpp 进行转录
-    fn run_whisper(&self, audio_path: &str) -> VoiceResult<STTResult> {
+    fn run_whisper(&self, audio_path: &str, language: &str) -> VoiceResult<STTResult> {
         let model_path = self.model_path.as_ref()
             .cloned()
             .unwrap_or_else(|| self.get_default_model_path().to_string_lossy().to_string());
@@ -341,7 +341,7 @@ impl WhisperSTT {
             .args([
                 "-m", &model_path,
                 "-f", audio_path,
-                "--language", "auto",
+                "--language", &whisper_language(language),
             ])
             .output()
             .map_err(|e| VoiceError::AudioError(format!("Failed to run whisper: {}", e)))?;
@@ -356,11 +356,16 @@ impl WhisperSTT {
         Ok(STTResult {
             text,
             confidence: 0.9,
-            language: "auto".to_string(),
+            language: language.to_string(),
         })
     }
 }
 
+/// whisper.cpp 的语言参数只接受语言部分，如 `zh-CN` -> `zh`
+fn whisper_language(language: &str) -> String {
+    language.split(['-', '_']).next().unwrap_or("auto").to_lowercase()
+}
+
 impl Default for WhisperSTT {
     fn default() -> Self {
         Self::new()
@@ -369,21 +374,25 @@ impl Default for WhisperSTT {
 
 #[async_trait]
 impl STTBackend for WhisperSTT {
-    async fn transcribe(&self, _audio: &[u8], options: Option<STTOptions>) -> VoiceResult<STTResult> {
-        // Whisper 需要文件路径，不支持原始音频数据
-        // 这里返回提示信息
-        tracing::warn!("Whisper STT requires file path, use transcribe_file() instead");
-
-        let language = options
-            .as_ref()
-            .and_then(|o| o.language.clone())
-            .unwrap_or_else(|| "en-US".to_string());
+    async fn transcribe(&self, audio: &[u8], options: Option<STTOptions>) -> VoiceResult<STTResult> {
+        if !self.is_available().await {
+            return Err(VoiceError::NotAvailable);
+        }
 
-        Ok(STTResult {
-            text: "[Whisper STT] Please use transcribe_file() or transcribe_url() for transcription".to_string(),
-            confidence: 0.0,
-            language,
-        })
+        // whisper.cpp 只接受文件路径，音频数据先写入临时文件
+        let audio_path = std::env::temp_dir().join(format!(
+            "sker-stt-{}-{}.wav",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default()
+        ));
+        std::fs::write(&audio_path, audio)?;
+        let language = options.and_then(|o| o.language).unwrap_or_else(|| "auto".to_string());
+        let result = self.run_whisper(&audio_path.to_string_lossy(), &language);
+        let _ = std::fs::remove_file(&audio_path);
+        result
     }
 
     async fn is_available(&self) -> bool {
@@ -439,7 +448,7 @@ pub async fn transcribe_file(path: &str, options: Option<STTOptions>) -> VoiceRe
         .args([
             "-m", &model_path,
             "-f", path,
-            "--language", &language,
+            "--language", &whisper_language(&language),
         ])
         .output()
         .map_err(|e| VoiceError::AudioError(format!("Failed to run whisper: {}", e)))?;
@@ -540,6 +549,14 @@ mod tests {
         assert_eq!(options.model, Some("whisper-base".to_string()));
     }
 
+    #[test]
+    fn test_whisper_model_and_language() {
+        assert_eq!(WhisperSTT::new().with_model_size("whisper-tiny").model_size, "tiny");
+        assert_eq!(WhisperSTT::new().with_model_size("small").model_size, "small");
+        assert_eq!(whisper_language("zh-CN"), "zh");
+        assert_eq!(whisper_language("auto"), "auto");
+    }
+
     #[tokio::test]
     async fn test_mock_stt() {
         let mock = MockSTT::new();